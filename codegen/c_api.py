@@ -0,0 +1,284 @@
+#!/usr/bin/env python3
+"""
+Single-header C API with versioned symbol names
+
+Amalgamates cpp/include/interop/*.h and the generated interop_errors.h into
+one self-contained header in which every Rust function is declared with a
+version prefix, e.g. ai1_rust_actor_send(). It goes with the Rust library
+built with `cargo build --features versioned_symbols`, which exports those
+names and no others, so two versions of the library can be loaded into
+one C++ process during a migration.
+
+Usage:
+    python3 codegen/c_api.py cpp/include/interop generated [--prefix ai1_]
+
+Writes generated/c/actors_interop_<prefix>.h. Types, enums and constants
+keep their names and the include guards of the headers they come from:
+the same definition is shared by every version and by the plain headers.
+Define ACTORS_INTEROP_<PREFIX>UNPREFIXED before including it to call the
+versioned functions by their plain names. Rust functions with no
+declaration in any header are listed on stderr.
+"""
+
+import argparse
+import os
+import re
+import sys
+from typing import Dict, List, Set, Tuple
+
+# Must match c_symbol! in rust/src/lib.rs
+DEFAULT_PREFIX = 'ai1_'
+
+DECL_NAME = re.compile(r'INTEROP_API\b[^(]*?\b([A-Za-z_][A-Za-z0-9_]*)\s*\(')
+LOCAL_INCLUDE = re.compile(r'#include\s+"([^"]+)"')
+SYSTEM_INCLUDE = re.compile(r'#include\s+<([^>]+)>')
+RUST_EXPORT = re.compile(r'c_symbol!\("([A-Za-z0-9_]+)"\)')
+
+
+class Header:
+    """One header split into its guard, includes, definitions and declarations"""
+
+    def __init__(self, path: str):
+        self.path = path
+        self.name = os.path.basename(path)
+        self.guard = ''
+        self.includes: List[str] = []
+        self.system: List[str] = []
+        self.definitions: List[str] = []
+        # (function name, comment and declaration lines)
+        self.declarations: List[Tuple[str, List[str]]] = []
+        self._parse()
+
+    def _parse(self):
+        with open(self.path, newline='') as f:
+            lines = f.read().replace('\r\n', '\n').split('\n')
+
+        # The file's own doc comment is left in the original header
+        i = 0
+        if lines and lines[0].startswith('/*'):
+            while i < len(lines) and '*/' not in lines[i]:
+                i += 1
+            i += 1
+        body = lines[i:]
+
+        guard_at = next((j for j, l in enumerate(body) if l.startswith('#ifndef ')), None)
+        if guard_at is not None and guard_at + 1 < len(body) and body[guard_at + 1].startswith('#define '):
+            self.guard = body[guard_at].split()[1]
+            end = max(j for j, l in enumerate(body) if l.startswith('#endif'))
+            body = body[guard_at + 2:end]
+
+        comment: List[str] = []
+        j = 0
+        while j < len(body):
+            line = body[j]
+            stripped = line.strip()
+            if stripped == '#ifdef __cplusplus' and j + 2 < len(body) and body[j + 2].strip() == '#endif':
+                # extern "C" { or its closing brace
+                j += 3
+                continue
+            m = LOCAL_INCLUDE.match(stripped)
+            if m:
+                self.includes.append(m.group(1))
+                j += 1
+                continue
+            m = SYSTEM_INCLUDE.match(stripped)
+            if m:
+                self.system.append(m.group(1))
+                j += 1
+                continue
+            if stripped.startswith('INTEROP_API'):
+                decl = [line]
+                while not decl[-1].rstrip().endswith(';'):
+                    j += 1
+                    decl.append(body[j])
+                name = DECL_NAME.search(' '.join(decl)).group(1)
+                self.declarations.append((name, comment + decl))
+                comment = []
+                j += 1
+                continue
+            if stripped.startswith('/*') or stripped.startswith('*') or stripped.startswith('//'):
+                comment.append(line)
+                j += 1
+                continue
+            self.definitions.extend(comment)
+            comment = []
+            self.definitions.append(line)
+            j += 1
+        self.definitions.extend(comment)
+
+
+def squeeze(lines: List[str]) -> List[str]:
+    """Drop leading, trailing and repeated blank lines"""
+    out: List[str] = []
+    for line in lines:
+        if not line.strip() and (not out or not out[-1].strip()):
+            continue
+        out.append(line.rstrip())
+    while out and not out[-1].strip():
+        out.pop()
+    return out
+
+
+def prefixed(lines: List[str], name: str, prefix: str) -> List[str]:
+    """The declaration with its function renamed"""
+    done = False
+    out = []
+    for line in lines:
+        if not done and 'INTEROP_API' in line:
+            line = re.sub(r'\b%s(\s*\()' % re.escape(name), prefix + name + r'\1', line, count=1)
+            done = True
+        out.append(line)
+    return out
+
+
+def rust_exports(rust_src: str) -> Set[str]:
+    names: Set[str] = set()
+    for root, _, files in os.walk(rust_src):
+        for f in files:
+            if f.endswith('.rs'):
+                with open(os.path.join(root, f)) as src:
+                    names.update(RUST_EXPORT.findall(src.read()))
+    return names
+
+
+def amalgamate(include_dir: str, generated_dir: str, prefix: str) -> Tuple[str, List[str]]:
+    headers: Dict[str, Header] = {}
+    errors_h = os.path.join(generated_dir, 'cpp', 'interop_errors.h')
+    if not os.path.exists(errors_h):
+        sys.exit(f'{errors_h} not found; run codegen/generate.py first')
+    headers['interop_errors.h'] = Header(errors_h)
+    for f in sorted(os.listdir(include_dir)):
+        if f.endswith('.h'):
+            headers[f] = Header(os.path.join(include_dir, f))
+
+    # Headers in include order: those a header includes come first
+    ordered: List[Header] = []
+    seen: Set[str] = set()
+
+    def visit(name: str):
+        if name in seen:
+            return
+        if name not in headers:
+            sys.exit(f'included header {name} not found')
+        seen.add(name)
+        for inc in headers[name].includes:
+            visit(inc)
+        ordered.append(headers[name])
+
+    visit('export.h')
+    visit('interop_errors.h')
+    for name in sorted(headers):
+        visit(name)
+
+    system = sorted({s for h in ordered for s in h.system})
+    version = prefix.rstrip('_').upper()
+    guard = f'ACTORS_INTEROP_{version}_H'
+    unprefixed = f'ACTORS_INTEROP_{version}_UNPREFIXED'
+
+    out: List[str] = []
+    out.append('/*')
+    out.append(' * AUTO-GENERATED FILE - DO NOT EDIT')
+    out.append(' * Generated by codegen/c_api.py from cpp/include/interop/*.h')
+    out.append(' *')
+    out.append(f' * The whole C API of actors-interop, every function prefixed {prefix}.')
+    out.append(' * Link the library built with `--features versioned_symbols`, which')
+    out.append(f' * exports these names only, so it can share a process with another')
+    out.append(' * version:')
+    out.append(' *')
+    out.append(f' *   #include "actors_interop_{prefix.rstrip("_")}.h"')
+    out.append(f' *   {prefix}create_rust_manager();')
+    out.append(f' *   {prefix}rust_actor_send("rust_pong", "cpp_ping", Ping::ID, &c_msg);')
+    out.append(' *')
+    out.append(f' * Define {unprefixed} first to call them by their')
+    out.append(' * plain names, e.g. from the generated RustActorIF. Types and constants')
+    out.append(' * keep their names and guards, shared with the plain interop headers.')
+    out.append(' * Each function is documented in the header named above it.')
+    out.append(' */')
+    out.append('')
+    out.append(f'#ifndef {guard}')
+    out.append(f'#define {guard}')
+    out.append('')
+    for s in system:
+        out.append(f'#include <{s}>')
+    out.append('')
+    out.append('#ifdef __cplusplus')
+    out.append('extern "C" {')
+    out.append('#endif')
+
+    for h in ordered:
+        body = squeeze(h.definitions)
+        if not body:
+            continue
+        out.append('')
+        out.append(f'/* ---- {h.name} ---- */')
+        if h.guard:
+            out.append('')
+            out.append(f'#ifndef {h.guard}')
+            out.append(f'#define {h.guard}')
+        out.append('')
+        out.extend(body)
+        if h.guard:
+            out.append('')
+            out.append(f'#endif /* {h.guard} */')
+
+    declared: List[str] = []
+    for h in ordered:
+        decls = [(n, d) for n, d in h.declarations if n not in declared]
+        if not decls:
+            continue
+        out.append('')
+        out.append(f'/* ---- {h.name} ---- */')
+        for name, decl in decls:
+            declared.append(name)
+            out.append('')
+            out.extend(l.rstrip() for l in prefixed(decl, name, prefix))
+
+    out.append('')
+    out.append('#ifdef __cplusplus')
+    out.append('}')
+    out.append('#endif')
+    out.append('')
+    out.append(f'#ifdef {unprefixed}')
+    for name in declared:
+        out.append(f'#define {name} {prefix}{name}')
+    out.append('#endif')
+    out.append('')
+    out.append(f'#endif /* {guard} */')
+    out.append('')
+    return '\n'.join(out), declared
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Write the versioned single-header C API')
+    parser.add_argument('include_dir', help='cpp/include/interop')
+    parser.add_argument('generated_dir', help='output of codegen/generate.py')
+    parser.add_argument('--prefix', default=DEFAULT_PREFIX, help=f'symbol prefix (default {DEFAULT_PREFIX})')
+    parser.add_argument('--rust-src', default=os.path.join(os.path.dirname(__file__), '..', 'rust', 'src'),
+                        help='Rust sources, to list exports with no declaration')
+    args = parser.parse_args()
+
+    if not re.fullmatch(r'[a-z][a-z0-9]*_', args.prefix):
+        sys.exit(f'bad prefix {args.prefix!r}: lowercase letters and digits, then _')
+
+    text, declared = amalgamate(args.include_dir, args.generated_dir, args.prefix)
+    out_dir = os.path.join(args.generated_dir, 'c')
+    os.makedirs(out_dir, exist_ok=True)
+    out = os.path.join(out_dir, f'actors_interop_{args.prefix.rstrip("_")}.h')
+    with open(out, 'w') as f:
+        f.write(text)
+    print(f'Generated {out}: {len(declared)} functions')
+
+    if os.path.isdir(args.rust_src):
+        exports = rust_exports(args.rust_src)
+        generated_rust = os.path.join(args.generated_dir, 'rust')
+        if os.path.isdir(generated_rust):
+            exports |= rust_exports(generated_rust)
+        missing = sorted(exports - set(declared))
+        if missing:
+            print(f'Exported but not declared in any header ({len(missing)}), left out:', file=sys.stderr)
+            for name in missing:
+                print(f'  {name}', file=sys.stderr)
+
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,71 @@
+/*
+ * C++ side of rust/benches/interop.rs with --features cpp_bench
+ *
+ * - cpp_pong replies to every Ping with a Pong, like the example
+ * - cpp_sub_0 .. cpp_sub_<n-1> count the MarketUpdates they receive
+ *
+ * Built into lib/libinterop_bench.a, together with the C++ bridge, by
+ * `make bench-cpp`.
+ */
+
+#include <atomic>
+#include <cstdio>
+#include <cstring>
+#include "actors/Actor.hpp"
+#include "actors/act/Manager.hpp"
+#include "InteropMessages.hpp"
+#include "CppActorBridge.hpp"
+
+static std::atomic<uint64_t> delivered{0};
+
+class BenchPong : public actors::Actor {
+public:
+    BenchPong() {
+        strncpy(name, "cpp_pong", sizeof(name));
+        MESSAGE_HANDLER(msg::Ping, on_ping);
+    }
+
+    void on_ping(const msg::Ping* m) noexcept {
+        auto* pong = new msg::Pong();
+        pong->count = m->count;
+        reply(pong);
+    }
+};
+
+class BenchSubscriber : public actors::Actor {
+public:
+    explicit BenchSubscriber(int32_t i) {
+        snprintf(name, sizeof(name), "cpp_sub_%d", i);
+        MESSAGE_HANDLER(msg::MarketUpdate, on_update);
+    }
+
+    void on_update(const msg::MarketUpdate*) noexcept {
+        delivered.fetch_add(1, std::memory_order_release);
+    }
+};
+
+class BenchManager : public actors::Manager {
+public:
+    explicit BenchManager(int32_t subscribers) {
+        manage(new BenchPong());
+        for (int32_t i = 0; i < subscribers; i++) {
+            manage(new BenchSubscriber(i));
+        }
+    }
+};
+
+extern "C" {
+
+// Start the C++ actors; the Manager lives until the process exits
+void interop_bench_cpp_start(int32_t subscribers) {
+    auto* mgr = new BenchManager(subscribers);
+    cpp_actor_init(mgr);
+    mgr->init();
+}
+
+// MarketUpdates received by all subscribers so far
+uint64_t interop_bench_cpp_delivered() {
+    return delivered.load(std::memory_order_acquire);
+}
+
+}
@@ -0,0 +1,69 @@
+/*
+ * Numeric actor ids instead of names on the send path
+ *
+ * At lookup the two registries agree on a uint32_t id per actor name, and
+ * sends carry the id instead of the name:
+ *
+ *   uint32_t id = rust_actor_lookup_id("rust_strategy");   // 0: not a Rust actor
+ *   int rc = id ? rust_actor_send_id(id, "cpp_feed", 1012, &update)
+ *               : INTEROP_STALE_ACTOR_ID;
+ *   if (rc == INTEROP_STALE_ACTOR_ID) {                    // look up again
+ *       rc = rust_actor_send("rust_strategy", "cpp_feed", 1012, &update);
+ *   }
+ *
+ * RustActorRef does this by itself. Ids are never reused; one goes stale
+ * when the name's lookups are invalidated (interop_invalidate_lookup(),
+ * the actor stopping or restarting, an alias change).
+ *
+ * The other way, Rust asks a C++ runtime for ids through the functions
+ * registered with interop_set_cpp_runtime_ids(). The generated
+ * cpp_actor_init() registers the linked C++ code's; an attached runtime
+ * (see cpp_runtime.h) may register its own under its name, else Rust
+ * addresses its actors by name.
+ */
+
+#ifndef INTEROP_ACTOR_IDS_H
+#define INTEROP_ACTOR_IDS_H
+
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+/* The id is unknown or forgotten; send by name instead */
+#define INTEROP_STALE_ACTOR_ID INTEROP_E_STALE_ACTOR_ID
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef uint32_t (INTEROP_CALL *InteropCppLookupIdFn)(const char* name);
+typedef int32_t (INTEROP_CALL *InteropCppSendIdFn)(uint32_t actor_id, const char* sender_name,
+                                                   int32_t msg_type, const void* msg_data);
+typedef void (INTEROP_CALL *InteropCppForgetIdFn)(uint32_t actor_id);
+
+/* Id functions of one C++ runtime (cpp_actor_lookup_id and friends) */
+typedef struct {
+    InteropCppLookupIdFn lookup_id;
+    InteropCppSendIdFn send_id;
+    InteropCppSendIdFn fast_send_id;
+    InteropCppForgetIdFn forget_id;
+} InteropCppRuntimeIds;
+
+/* Id of Rust actor name (aliases resolved), 0 if it is not one */
+INTEROP_API uint32_t rust_actor_lookup_id(const char* name);
+
+/* rust_actor_send() to the actor with this id; INTEROP_STALE_ACTOR_ID if
+ * the id is unknown or forgotten */
+INTEROP_API int32_t rust_actor_send_id(uint32_t actor_id, const char* sender_name,
+                                       int32_t msg_type, const void* msg_data);
+
+/* Send to runtime's actors by id; runtime NULL for the linked C++ code.
+ * Returns 0, or INTEROP_E_NOT_FOUND if no such runtime is attached. */
+INTEROP_API int interop_set_cpp_runtime_ids(const char* runtime, InteropCppRuntimeIds ids);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_ACTOR_IDS_H */
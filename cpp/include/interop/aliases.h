@@ -0,0 +1,41 @@
+/*
+ * Actor name aliases, resolved by both runtimes' lookups
+ *
+ * Actors that embed a name like "cpp_price_feed" can be pointed at another
+ * actor, Rust or C++, from deployment config:
+ *
+ *   interop_alias("price_feed", "cpp_price_feed");   // logical -> concrete
+ *   interop_alias_load("deploy/aliases.conf");       // "name = target" lines
+ *
+ * Rust lookups and C++ sends to Rust (rust_actor_send, rust_actor_exists,
+ * ...) resolve aliases first, so an alias wins over an actor of the same
+ * name. Aliases of aliases are followed; a cycle is refused.
+ */
+
+#ifndef INTEROP_ALIASES_H
+#define INTEROP_ALIASES_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 0, INTEROP_E_INVALID_NAME on a bad name, or
+ * INTEROP_E_INVALID_ARGUMENT if it would close a cycle */
+INTEROP_API int32_t interop_alias(const char* name, const char* target);
+
+/* Returns 0, or INTEROP_E_NOT_FOUND if name was not an alias */
+INTEROP_API int32_t interop_unalias(const char* name);
+
+/* Returns how many aliases were set, or INTEROP_E_INVALID_ARGUMENT on an
+ * unreadable file or bad line */
+INTEROP_API int32_t interop_alias_load(const char* path);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_ALIASES_H */
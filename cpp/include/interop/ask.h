@@ -0,0 +1,68 @@
+/*
+ * Blocking request into a Rust actor from C++
+ *
+ * For a query whose answer the caller needs before going on (e.g. an
+ * analytics actor's current state), without a reply actor of its own:
+ *
+ *   DataRequest req = ...;           // from interop_messages.h
+ *   DataResponse resp;
+ *   int32_t reply_id;
+ *   int rc = rust_actor_ask("rust_analytics", 1002, &req,
+ *                           &reply_id, &resp, sizeof(resp), 100);
+ *   if (rc == INTEROP_ASK_OK && reply_id == 1003) { ... use resp ... }
+ *
+ * The Rust actor answers with ctx.reply() as usual; the first message it
+ * sends back is the reply and later ones are dropped. The calling thread
+ * blocks until then, so do not ask from a C++ actor the Rust actor may
+ * send to synchronously. Replies with interop_bytes fields cannot be
+ * returned (INTEROP_ASK_UNKNOWN_MESSAGE).
+ *
+ * An ask that ends without a reply (timeout, or cancelled on the Rust
+ * side) sends AskCancel { correlation_id } to the target; replies after
+ * that are dead letters. A C++ actor answering an ask from Rust sees the
+ * sender name "__ask-<id>" and can check during long work:
+ *
+ *   if (rust_ask_cancelled(sender_name)) return;   // nobody waits any more
+ */
+
+#ifndef INTEROP_ASK_H
+#define INTEROP_ASK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_ASK_OK               INTEROP_OK
+#define INTEROP_ASK_NOT_FOUND        INTEROP_E_NOT_FOUND         /* no such Rust actor (yet) */
+#define INTEROP_ASK_UNKNOWN_MESSAGE  INTEROP_E_UNKNOWN_MESSAGE   /* unknown request, or reply not returnable */
+#define INTEROP_ASK_TIMEOUT          INTEROP_E_TIMEOUT
+#define INTEROP_ASK_INVALID          INTEROP_E_INVALID_ARGUMENT  /* null name, request or out-parameter */
+#define INTEROP_ASK_BUFFER_TOO_SMALL INTEROP_E_BUFFER_TOO_SMALL  /* *reply_msg_id_out is set, reply dropped */
+#define INTEROP_ASK_DENIED           INTEROP_E_POLICY_DENIED     /* refused by the policy or sender auth */
+#define INTEROP_ASK_TOO_LARGE        INTEROP_E_TOO_LARGE         /* over the size limit, see size_limit.h */
+#define INTEROP_ASK_SHUTTING_DOWN    INTEROP_E_SHUTTING_DOWN     /* shutdown requested, see shutdown.h */
+#define INTEROP_ASK_INVALID_MESSAGE  INTEROP_E_INVALID_MESSAGE   /* request breaks a field rule, see validation.h */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+INTEROP_API int32_t rust_actor_ask(const char* actor_name, int32_t msg_type, const void* msg_data,
+                                   int32_t* reply_msg_id_out, void* reply_buf, size_t reply_len,
+                                   uint32_t timeout_ms);
+
+/* Cancel the ask with this correlation id; 0, or INTEROP_E_NOT_FOUND if it
+ * is not waiting */
+INTEROP_API int32_t rust_ask_cancel(uint64_t correlation_id);
+
+/* 1 once nobody waits for a reply to reply_to (an "__ask-<id>" sender
+ * name): answered, cancelled or timed out; else 0 */
+INTEROP_API int32_t rust_ask_cancelled(const char* reply_to);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_ASK_H */
@@ -0,0 +1,48 @@
+/*
+ * Copy-free sends to Rust for INTEROP_BORROWED messages
+ *
+ * rust_actor_send() copies the message into Rust before it returns.
+ * rust_actor_borrow_send() lends the buffer instead: a Rust handler
+ * registered with borrow::register_outside_mailbox() reads it in place.
+ *
+ *   MarketDepth depth;                     // on the stack, reused per tick
+ *   fill_depth(&depth, book);
+ *   rust_actor_borrow_send("rust_book", "cpp_feed", MSG_MARKETDEPTH, &depth);
+ *   // depth is the caller's again
+ *
+ * Contract:
+ * - The handler runs on the calling thread and has finished when the call
+ *   returns. Rust keeps no pointer into the buffer after that.
+ * - The caller must not change or free the buffer during the call.
+ * Debug builds of the Rust library assert both.
+ *
+ * The handler is not an actor delivery: it runs beside the target actor's
+ * own handlers, even while it is paused, and taps, dedup and the journal
+ * do not see the message. The admission checks of rust_actor_fast_send()
+ * (shutdown, policy, sender auth, size limit) still apply.
+ *
+ * If the target has no borrowed handler for the message, the call falls
+ * back to rust_actor_fast_send(), which copies.
+ */
+
+#ifndef INTEROP_BORROW_H
+#define INTEROP_BORROW_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns an InteropErrorCode (interop_errors.h): INTEROP_OK,
+ * INTEROP_E_INVALID_ARGUMENT, or the codes of rust_actor_fast_send() */
+INTEROP_API int32_t rust_actor_borrow_send(const char* actor_name, const char* sender_name,
+                                           int32_t msg_type, const void* msg_data);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_BORROW_H */
@@ -0,0 +1,38 @@
+/*
+ * The Rust broker's subscription table
+ *
+ * rust_dump_subscriptions() writes every subscription the Rust broker
+ * holds as a JSON array, one entry per topic and publisher, each
+ * subscriber marked "rust", "cpp" or null (no longer found). Use it to
+ * check that subscriptions sent from C++ reached the Rust publisher:
+ *
+ *   char buf[8192];
+ *   int n = rust_dump_subscriptions(buf, sizeof(buf));
+ *   if (n >= (int)sizeof(buf)) { ... retry with n + 1 bytes ... }
+ *
+ *   [{"topic":"AAPL","publisher":"rust_publisher",
+ *     "subscribers":[{"name":"cpp_gui","side":"cpp","qos":0}]}]
+ *
+ * The same table is printed by `interop-ctl subscriptions`.
+ */
+
+#ifndef INTEROP_BROKER_H
+#define INTEROP_BROKER_H
+
+#include <stddef.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Write the table into out_json (NUL-terminated, truncated to fit len).
+ * Returns the full length, like snprintf. */
+INTEROP_API int rust_dump_subscriptions(char* out_json, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_BROKER_H */
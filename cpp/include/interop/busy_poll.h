@@ -0,0 +1,34 @@
+/*
+ * Busy-poll receive for latency-critical Rust actors
+ *
+ * After each message, a Rust actor's thread normally parks until the next
+ * one, and waking it is most of the C++ -> Rust latency. With a spin
+ * budget it spins until the next bridge message is queued, parking only
+ * once the budget runs out:
+ *
+ *   rust_actor_set_busy_poll("rust_strategy", 50);   // spin up to 50 us
+ *   rust_actor_set_busy_poll("rust_strategy", 0);    // off (the default)
+ *
+ * May be called before or after the actor is registered. A spinning thread
+ * keeps its core busy; give busy-poll actors cores of their own.
+ */
+
+#ifndef INTEROP_BUSY_POLL_H
+#define INTEROP_BUSY_POLL_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad name */
+INTEROP_API int32_t rust_actor_set_busy_poll(const char* actor_name, uint32_t spin_budget_us);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_BUSY_POLL_H */
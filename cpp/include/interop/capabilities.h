@@ -0,0 +1,63 @@
+/*
+ * Capabilities exchange with Rust
+ *
+ * The generated cpp_actor_init() tells Rust which message IDs and bridge
+ * functions this side was built with. init_cpp_actor_lookup() and
+ * interop_cpp_ready() then refuse to connect (return INTEROP_E_INCOMPATIBLE)
+ * if anything Rust needs is missing, e.g. C++ generated from an older
+ * interop_messages.h:
+ *
+ *   cpp_actor_init(&cpp_mgr);
+ *   if (init_cpp_actor_lookup() != 0) {
+ *       char report[512];
+ *       interop_capability_report(report, sizeof report);
+ *       // "C++ peer is missing messages: StreamOpen (1040), ...;"
+ *   }
+ *
+ * A hand-written bridge calls interop_cpp_capabilities() itself.
+ *
+ * Every check prints a banner of what was negotiated; the same as JSON,
+ * for support tickets:
+ *
+ *   char summary[1024];
+ *   interop_capability_summary(summary, sizeof summary);
+ *   // {"abi_version":1,"peer_abi_version":1,"schema_hash":"5d3c0a9e21f4b7c6",...}
+ */
+
+#ifndef INTEROP_CAPABILITIES_H
+#define INTEROP_CAPABILITIES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#ifndef INTEROP_CAPABILITIES_VERSION
+#define INTEROP_CAPABILITIES_VERSION 1
+#endif
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 0, or INTEROP_E_INVALID_ARGUMENT on null arrays with nonzero counts */
+INTEROP_API int interop_cpp_capabilities(uint32_t version,
+                                         const int32_t* msg_ids, uint32_t msg_count,
+                                         const char* const* functions, uint32_t function_count);
+
+/* 0 once the peer checks out, INTEROP_E_INCOMPATIBLE otherwise (see
+ * interop_capability_report) */
+INTEROP_API int init_cpp_actor_lookup(void);
+
+/* Why the last check failed, NUL-terminated; returns its full length like snprintf */
+INTEROP_API int interop_capability_report(char* out, size_t len);
+
+/* ABI versions, schema hashes, message counts, features and transports
+ * of both sides as JSON, NUL-terminated; returns its full length like snprintf */
+INTEROP_API int interop_capability_summary(char* out, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CAPABILITIES_H */
@@ -0,0 +1,53 @@
+/*
+ * Sampled bridge traffic for production debugging
+ *
+ * Keeps 1 in N messages of each message type crossing the bridge (what
+ * wire taps see, see tap.h) in a ring of the last M, and returns them as
+ * JSON on demand:
+ *
+ *   interop_capture_start(100, 512);            // 1 in 100, last 512
+ *   interop_capture_set_every(MSG_NEWORDER, 1); // every order
+ *   ...
+ *   char* json = interop_capture_json(50);      // 0 for all kept
+ *   // [{"time_ms":..,"sender":"cpp_feed","target":"rust_strategy",
+ *   //   "msg_id":1002,"message":"MarketUpdate","payload":{...}},...]
+ *   if (json) { log(json); interop_free_json(json); }
+ *
+ * The first message of a type is kept, then every N-th. payload is null
+ * for messages without a JSON form. interop_free_json() is in tap.h.
+ */
+
+#ifndef INTEROP_CAPTURE_H
+#define INTEROP_CAPTURE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Keep 1 in every messages of each type (0 is taken as 1), the last
+ * capacity of them (0: 256). Restarts the per-type counts. */
+INTEROP_API void interop_capture_start(uint32_t every, uint32_t capacity);
+
+/* Stop sampling; kept samples stay readable */
+INTEROP_API void interop_capture_stop(void);
+
+/* Keep 1 in every messages of type msg_type; 0 goes back to the rate
+ * given to interop_capture_start() */
+INTEROP_API void interop_capture_set_every(int32_t msg_type, uint32_t every);
+
+/* The last max samples (all kept if 0) as a JSON array, oldest first.
+ * Free with interop_free_json(). */
+INTEROP_API char* interop_capture_json(uint32_t max);
+
+/* Drop the kept samples */
+INTEROP_API void interop_capture_clear(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CAPTURE_H */
@@ -0,0 +1,50 @@
+/*
+ * Circuit breaker on Rust -> C++ sends
+ *
+ * A dead C++ actor makes every Rust send to it pay for the FFI call and
+ * its failure. With a breaker set, a sender -> target pair whose sends
+ * fail `failures` times in a row is opened: its sends return
+ * INTEROP_CIRCUIT_OPEN without calling C++ for `open_ms`, then one send is
+ * let through as a probe, which closes the circuit if it succeeds. Codes
+ * that are the sender's doing (INTEROP_E_UNKNOWN_MESSAGE, WRONG_TYPE,
+ * INVALID_MESSAGE, TOO_LARGE, INVALID_ARGUMENT, INVALID_NAME) are not
+ * failures.
+ *
+ * Usage:
+ *   interop_set_circuit_breaker(5, 2000);   // 5 failures, 2 s open
+ *
+ *   if (interop_circuit_state("rust_strategy", "cpp_gateway") == INTEROP_CIRCUIT_OPEN) { ... }
+ *
+ * Openings and closings are also published on $sys/circuit (sys_topics.h).
+ */
+
+#ifndef INTEROP_CIRCUIT_H
+#define INTEROP_CIRCUIT_H
+
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_CIRCUIT_CLOSED    0
+#define INTEROP_CIRCUIT_OPEN      1
+#define INTEROP_CIRCUIT_HALF_OPEN 2  /* a probe send is in flight */
+
+/* Send return code while the pair's circuit is open */
+#define INTEROP_SEND_CIRCUIT_OPEN INTEROP_E_CIRCUIT_OPEN
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* failures = 0 turns circuit breaking off; changing it resets every pair */
+INTEROP_API void interop_set_circuit_breaker(uint32_t failures, uint32_t open_ms);
+
+/* INTEROP_CIRCUIT_* for the pair, INTEROP_E_INVALID_ARGUMENT on a bad name */
+INTEROP_API int interop_circuit_state(const char* sender, const char* target);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CIRCUIT_H */
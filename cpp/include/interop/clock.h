@@ -0,0 +1,52 @@
+/*
+ * Interop clock: system time, or virtual time for backtests
+ *
+ * Timers and time stamps on the Rust side (leases, circuit breakers, rate
+ * limits, heartbeat ages, dispatch timings, Timestamp::now()) and
+ * interop::Timestamp::now() in C++ all read one clock. A backtest swaps
+ * in a simulated clock and drives it:
+ *
+ *   interop_clock_simulate(1704187800000000000LL, 0.0);  // stands still
+ *   ...
+ *   interop_clock_advance_ns(250000000);                 // +250 ms
+ *   interop_clock_set_ns(next_tick_ns);                  // jump to a tick
+ *   ...
+ *   interop_clock_use_system();
+ *
+ * With speed > 0 virtual time also runs on its own, `speed` times faster
+ * than real time. Virtual time never runs backwards. Code pacing itself
+ * on the clock sleeps with interop_clock_sleep_ns().
+ */
+
+#ifndef INTEROP_CLOCK_H
+#define INTEROP_CLOCK_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+INTEROP_API void interop_clock_simulate(int64_t start_unix_ns, double speed);
+INTEROP_API void interop_clock_use_system(void);
+
+/* Returns 0, or INTEROP_E_NOT_FOUND if no simulated clock is installed */
+INTEROP_API int32_t interop_clock_advance_ns(uint64_t ns);
+
+/* Returns 0, INTEROP_E_NOT_FOUND if no simulated clock is installed, or
+ * INTEROP_E_INVALID_ARGUMENT if unix_ns is in the virtual past */
+INTEROP_API int32_t interop_clock_set_ns(int64_t unix_ns);
+
+/* Nanoseconds since the Unix epoch on the interop clock */
+INTEROP_API int64_t interop_clock_now_ns(void);
+
+/* Sleep ns of interop clock time */
+INTEROP_API void interop_clock_sleep_ns(uint64_t ns);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CLOCK_H */
@@ -0,0 +1,62 @@
+/*
+ * Configuration values shared and watched by both runtimes
+ *
+ * One table of string values by key, set from either side. Watchers
+ * subscribe to the key (or "*" for every key) with a normal Subscribe sent
+ * to the reserved publisher "$config", which resolves like a Rust actor,
+ * and receive ConfigChanged messages.
+ *
+ * Usage:
+ *   interop_config_set("throttle_ms", "250");
+ *
+ *   auto* sub = new msg::Subscribe();
+ *   interop::set_str(sub->topic, "throttle_ms");
+ *   manager->get_ref(INTEROP_CONFIG_PUBLISHER).send(sub, this);
+ *
+ *   void on_config(const msg::ConfigChanged* m) noexcept {
+ *       throttle_ms_ = std::stoi(m->value);   // version 0: removed
+ *   }
+ *
+ * A new watcher of a key that is set is sent its current value at once.
+ * A ConfigSet message sent to "$config" sets a key too. Keys are at most
+ * 31 bytes, values at most 63.
+ */
+
+#ifndef INTEROP_CONFIG_H
+#define INTEROP_CONFIG_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#define INTEROP_CONFIG_PUBLISHER "$config"
+#define INTEROP_CONFIG_ALL       "*"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 1 if the value changed, 0 if it was already set to it, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad key or a value over 63 bytes */
+INTEROP_API int interop_config_set(const char* key, const char* value);
+
+/* The value, NUL-terminated; returns its full length like snprintf, or
+ * INTEROP_E_NOT_FOUND if key is not set */
+INTEROP_API int interop_config_get(const char* key, char* out, size_t len);
+
+/* Times key has been set since it was last removed; 0 if not set */
+INTEROP_API int64_t interop_config_version(const char* key);
+
+/* Returns 0, or INTEROP_E_NOT_FOUND if key was not set */
+INTEROP_API int interop_config_remove(const char* key);
+
+/* `key = value` lines, # comments. Returns how many keys changed, or
+ * INTEROP_E_INVALID_ARGUMENT (see stderr). */
+INTEROP_API int interop_config_load(const char* path);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CONFIG_H */
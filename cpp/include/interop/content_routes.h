@@ -0,0 +1,51 @@
+/*
+ * Content routes: route names whose rules pick each message's target
+ *
+ * Senders address the route; the bridge sends each message on to the
+ * target of the first rule it matches, with the original sender:
+ *
+ *   # deploy/routes.conf: route: conditions -> target
+ *   orders: symbol < N -> cpp_legacy_engine
+ *   orders: symbol >= N -> rust_order_engine
+ *
+ *   interop_routes_load("deploy/routes.conf");
+ *   rust_actor_send("orders", "cpp_gateway", NewOrder::ID, &c_msg);
+ *
+ * Conditions are those of subscription filters (sub_filter.h), joined by
+ * &&; "*" matches every message. A message no rule matches is a dead
+ * letter and its send returns INTEROP_E_NOT_FOUND. Loading replaces the rules of each route the file names.
+ */
+
+#ifndef INTEROP_CONTENT_ROUTES_H
+#define INTEROP_CONTENT_ROUTES_H
+
+#include <stddef.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns how many rules were set, or INTEROP_E_INVALID_ARGUMENT on a
+ * NULL path, an unreadable file or a bad line; nothing is set then. */
+INTEROP_API int interop_routes_load(const char* path);
+
+/* Add a rule after the rules of route. Returns 0, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad name or conditions. */
+INTEROP_API int interop_route_add(const char* route, const char* when, const char* target);
+
+/* Returns 0, INTEROP_E_NOT_FOUND if route was not a route, or
+ * INTEROP_E_INVALID_ARGUMENT on a NULL or non-UTF-8 name */
+INTEROP_API int interop_route_remove(const char* route);
+
+/* Write every rule with the messages it routed as a JSON array into
+ * out_json (NUL-terminated, truncated to fit len). Returns the full
+ * length, like snprintf. */
+INTEROP_API int interop_routes(char* out_json, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CONTENT_ROUTES_H */
@@ -0,0 +1,53 @@
+/*
+ * Message contracts of Rust actors, checked at the bridge
+ *
+ * The INTEROP_ACTOR declaration of a Rust actor (interop_messages.h) says
+ * what it takes; INTEROP_EMITS, what it sends besides its replies. In
+ * contract checking mode the bridge flags messages outside them: to a
+ * Rust actor that does not take them, or from one that does not send
+ * them. LOG logs each kind once and counts; REJECT also refuses the
+ * message like a policy denial (INTEROP_POLICY_DENIED, audit callback).
+ *
+ * Usage:
+ *   interop_set_contract_mode(INTEROP_CONTRACT_LOG);
+ *
+ *   int takes[] = {1012, 1013};                       // no header entry
+ *   interop_declare_contract("rust_risk", takes, 2, NULL, 0);
+ *
+ * Actors without a contract, C++ actors and Rust -> Rust sends are not
+ * checked.
+ */
+
+#ifndef INTEROP_CONTRACTS_H
+#define INTEROP_CONTRACTS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#define INTEROP_CONTRACT_OFF    0  /* default */
+#define INTEROP_CONTRACT_LOG    1
+#define INTEROP_CONTRACT_REJECT 2
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Return 0, or INTEROP_E_INVALID_ARGUMENT for an unknown mode */
+INTEROP_API int interop_set_contract_mode(int mode);
+
+/* Replaces the header's contract for actor. NULL emits leaves what it
+ * sends unchecked. Return 0, or INTEROP_E_INVALID_ARGUMENT on a bad name
+ * or NULL handles with a length. */
+INTEROP_API int interop_declare_contract(const char* actor, const int* handles, size_t handles_len,
+                                         const int* emits, size_t emits_len);
+
+/* Violations seen since start */
+INTEROP_API uint64_t interop_contract_violations(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CONTRACTS_H */
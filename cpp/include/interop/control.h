@@ -0,0 +1,41 @@
+/*
+ * Control socket for interop-ctl
+ *
+ * Opens a unix socket in this process that the interop-ctl tool connects
+ * to, to list Rust actors, dump the health summary, tap a topic or send a
+ * test message:
+ *
+ *   interop_control_listen("/tmp/pricing.sock");   // NULL: /tmp/actors-interop.sock
+ *   ...
+ *   interop_control_close();
+ *
+ *   $ interop-ctl --socket /tmp/pricing.sock tap cpp_feed AAPL
+ *
+ * The socket is mode 0600 before it appears at path, and command lines
+ * over 64 KiB are refused. Not available on Windows.
+ */
+
+#ifndef INTEROP_CONTROL_H
+#define INTEROP_CONTROL_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Open the control socket at path (NULL for the default), closing one
+ * already open. Returns 0, or INTEROP_E_INVALID_ARGUMENT if it cannot be
+ * opened. */
+INTEROP_API int interop_control_listen(const char* path);
+
+/* Close the control socket and remove its file */
+INTEROP_API void interop_control_close(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CONTROL_H */
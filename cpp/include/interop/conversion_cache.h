@@ -0,0 +1,40 @@
+/*
+ * One C struct conversion per fan-out for equal messages
+ *
+ * A Rust publisher sending the same message to many C++ subscribers
+ * converts it to its C struct once per send. With the cache on, sends of
+ * equal messages within one fan-out share one conversion; it is dropped
+ * when the fan-out returns. Each send still gets a pointer valid for the
+ * duration of its call.
+ *
+ * Usage:
+ *   interop_set_conversion_cache(1);
+ *   ...
+ *   uint64_t hits, misses;
+ *   interop_conversion_cache_stats(&hits, &misses);
+ *
+ * Messages with interop_bytes or sequenced fields are converted per send.
+ */
+
+#ifndef INTEROP_CONVERSION_CACHE_H
+#define INTEROP_CONVERSION_CACHE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Non-zero turns it on, 0 off (the default). Returns 0. */
+INTEROP_API int interop_set_conversion_cache(int enabled);
+
+/* Either pointer may be NULL */
+INTEROP_API void interop_conversion_cache_stats(uint64_t* hits, uint64_t* misses);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CONVERSION_CACHE_H */
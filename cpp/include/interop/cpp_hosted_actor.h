@@ -0,0 +1,49 @@
+/*
+ * C++ actors hosted by the Rust Manager
+ *
+ * C++ supplies a table of callbacks and the Rust Manager runs the actor
+ * on its own thread, exactly like a Rust actor. Messages arrive as the
+ * C structs from interop_messages.h; msg_data is only valid during the call.
+ * on_stop and on_release may be called from another thread than the
+ * actor's (whichever drops it), but never while another callback runs.
+ *
+ * Ownership of user_data: the Manager builds a new instance from the same
+ * table after a panic, on passivation, on replacement and on
+ * rust_manager_restart(), so on_stop (an instance stopped) may be followed
+ * by on_start again. Do not free user_data in on_stop. on_release is
+ * called once, after the last on_stop, when the registration is forgotten
+ * by interop_shutdown_all(); free user_data there. A process that exits
+ * without it never gets on_release.
+ *
+ * Usage:
+ *   CppActorVTable vt = { my_on_start, my_on_message, my_on_stop, this, my_on_release };
+ *   register_cpp_hosted_actor("cpp_migrated", vt);
+ */
+
+#ifndef INTEROP_CPP_HOSTED_ACTOR_H
+#define INTEROP_CPP_HOSTED_ACTOR_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct {
+    void (INTEROP_CALL *on_start)(void* user_data);
+    void (INTEROP_CALL *on_message)(void* user_data, int32_t msg_type, const void* msg_data);
+    void (INTEROP_CALL *on_stop)(void* user_data);
+    void* user_data;
+    void (INTEROP_CALL *on_release)(void* user_data);  /* may be NULL; last, so older tables leave it NULL */
+} CppActorVTable;
+
+/* Returns the Rust Manager pointer, or NULL if no Manager / bad name */
+INTEROP_API const void* register_cpp_hosted_actor(const char* name, CppActorVTable vtable);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CPP_HOSTED_ACTOR_H */
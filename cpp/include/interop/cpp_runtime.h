@@ -0,0 +1,77 @@
+/*
+ * Several C++ runtimes attached to one Rust Manager
+ *
+ * The C++ code linked into the process is always reachable. Independently
+ * built C++ actor libraries are attached with their bridge functions:
+ *
+ *   void* lib = dlopen("libpricing.so", RTLD_NOW | RTLD_LOCAL);
+ *   InteropCppRuntime rt = {
+ *       (InteropCppSendFn)dlsym(lib, "cpp_actor_send"),
+ *       (InteropCppSendFn)dlsym(lib, "cpp_actor_fast_send"),
+ *       (InteropCppExistsFn)dlsym(lib, "cpp_actor_exists"),
+ *   };
+ *   interop_attach_cpp_runtime("pricing", "pricing.", rt);
+ *
+ * Rust lookups and sends for a name starting with a runtime's prefix go to
+ * that runtime. Other names go to the linked C++ code or the runtimes
+ * attached without a prefix, whichever knows the name first (in attach
+ * order). Each library still runs its own cpp_actor_init(). A runtime
+ * that also registers its cpp_actor_lookup_id() and friends with
+ * interop_set_cpp_runtime_ids(name, ...) is sent to by id (actor_ids.h).
+ *
+ * Where Rust is a Windows DLL it cannot resolve the linked C++ code's
+ * cpp_actor_* functions at link time; the generated cpp_actor_init()
+ * hands them over with interop_set_linked_cpp_runtime() instead.
+ *
+ * A runtime about to go away (dlclose, process teardown) says so first:
+ *
+ *   interop_cpp_runtime_detached("pricing", 1000);   // NULL: the linked code
+ *
+ * Rust then sends it nothing more: sends to its actors return
+ * INTEROP_E_RUNTIME_DETACHED and are recorded as dead letters, and
+ * $sys/cpp_runtime publishes INTEROP_SYS_CPP_RUNTIME_DETACHED.
+ */
+
+#ifndef INTEROP_CPP_RUNTIME_H
+#define INTEROP_CPP_RUNTIME_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef int32_t (INTEROP_CALL *InteropCppSendFn)(const char* actor_name, const char* sender_name,
+                                                 int32_t msg_type, const void* msg_data);
+typedef int32_t (INTEROP_CALL *InteropCppExistsFn)(const char* name);
+
+typedef struct {
+    InteropCppSendFn send;
+    InteropCppSendFn fast_send;
+    InteropCppExistsFn exists;
+} InteropCppRuntime;
+
+/* prefix may be NULL. Returns 0, INTEROP_E_INVALID_ARGUMENT on a bad name or
+ * prefix, or INTEROP_E_NAME_TAKEN if either is in use. */
+INTEROP_API int interop_attach_cpp_runtime(const char* name, const char* prefix, InteropCppRuntime runtime);
+
+/* Returns 0, or INTEROP_E_NOT_FOUND if name is not attached */
+INTEROP_API int interop_detach_cpp_runtime(const char* name);
+
+/* The runtime name (NULL: the linked C++ code) is going away. Waits up to
+ * timeout_ms for Rust calls already in it. Returns 0, 1 if calls are still
+ * running, or INTEROP_E_NOT_FOUND if no runtime has the name. */
+INTEROP_API int interop_cpp_runtime_detached(const char* name, uint32_t timeout_ms);
+
+/* Bridge functions of the C++ code linked into the process. Only needed
+ * on Windows; elsewhere it only undoes interop_cpp_runtime_detached(NULL).
+ * Returns 0. */
+INTEROP_API int interop_set_linked_cpp_runtime(InteropCppRuntime runtime);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_CPP_RUNTIME_H */
@@ -0,0 +1,45 @@
+/*
+ * Recent failed deliveries
+ *
+ * The health summary counts dead letters; the bridge also keeps the last
+ * ones (256 by default) with where each was lost:
+ *
+ *   char* json = interop_dead_letters_json(20);   // 0 for all kept
+ *   // [{"time_ms":..,"origin":"cpp","route":"direct","stage":"lookup",
+ *   //   "sender":"cpp_feed","target":"rust_monitor","msg_id":1000,
+ *   //   "message":"Ping","code":-1},...]
+ *   if (json) { log(json); interop_free_json(json); }
+ *
+ * origin is the side the send started on ("cpp" or "rust"), route how
+ * the target was picked ("direct", "broker" or "router"), stage where it
+ * failed ("lookup", "conversion", "transport", "mailbox_full", "name",
+ * "panic" for a message quarantined after panicking its handler, or
+ * "detached" for a target whose C++ runtime went away), code
+ * the return code the sender got. message is null for an unknown ID.
+ * "panic" records of actors with a state dump also have "context".
+ * interop_free_json() is in tap.h.
+ */
+
+#ifndef INTEROP_DEAD_LETTERS_H
+#define INTEROP_DEAD_LETTERS_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The last max records (all kept if 0) as a JSON array, oldest first.
+ * Free with interop_free_json(). */
+INTEROP_API char* interop_dead_letters_json(uint32_t max);
+
+/* Keep the last capacity records; 0 keeps none */
+INTEROP_API void interop_set_dead_letter_capacity(uint32_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_DEAD_LETTERS_H */
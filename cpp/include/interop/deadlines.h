@@ -0,0 +1,44 @@
+/*
+ * Processing deadlines for Rust actor handlers
+ *
+ * With a deadline set for an actor and message type, every handler run
+ * that takes longer is counted and published as INTEROP_SYS_DEADLINE_MISSED
+ * on $sys/deadlines (sys_topics.h), so the host sees when Rust stops
+ * meeting its latency budget:
+ *
+ *   rust_actor_set_deadline("rust_strategy", 1012, 500);   // MarketUpdate: 500 us
+ *   rust_actor_set_deadline("rust_strategy", 0, 5000);     // every other type
+ *
+ *   uint64_t worst_ns;
+ *   uint64_t missed = rust_actor_deadline_misses("rust_strategy", &worst_ns);
+ *   uint64_t all = rust_actor_deadline_misses(NULL, NULL);  // every actor
+ *
+ * Only handler time counts, not the wait in the mailbox (see ttl.h).
+ * Handlers are not interrupted.
+ */
+
+#ifndef INTEROP_DEADLINES_H
+#define INTEROP_DEADLINES_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* msg_type 0: every type without a deadline of its own. deadline_us 0
+ * removes the deadline. Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad
+ * name. */
+INTEROP_API int rust_actor_set_deadline(const char* actor_name, int32_t msg_type, uint32_t deadline_us);
+
+/* Handlers that overran their deadline; worst_ns (may be NULL) gets the
+ * longest of them. actor_name NULL: every actor, worst_ns untouched. */
+INTEROP_API uint64_t rust_actor_deadline_misses(const char* actor_name, uint64_t* worst_ns);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_DEADLINES_H */
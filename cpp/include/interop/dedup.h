@@ -0,0 +1,34 @@
+/*
+ * Drop duplicate messages sent to a Rust actor
+ *
+ * For actors that must not see a message twice (order events), the bridge
+ * can remember what it delivered and drop an equal message (same type and
+ * field values) arriving within the window:
+ *
+ *   rust_actor_set_dedup("rust_oms", 5000, 10000);   // 5 s, up to 10000 messages
+ *
+ * A dropped duplicate is reported as a successful send. Rust code can key
+ * on a correlation id instead (dedup::DedupConfig::by_key).
+ */
+
+#ifndef INTEROP_DEDUP_H
+#define INTEROP_DEDUP_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* window_ms = 0 turns dedup off. Returns 0, or INTEROP_E_INVALID_ARGUMENT on
+ * a bad name. */
+INTEROP_API int rust_actor_set_dedup(const char* actor_name, uint32_t window_ms, size_t max_entries);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_DEDUP_H */
@@ -0,0 +1,58 @@
+/*
+ * Startup dependencies between actors
+ *
+ * Holds Rust Start messages until the actors they need are registered in
+ * either runtime, instead of each actor looking them up lazily:
+ *
+ *   register_rust_subscriber();
+ *   interop_require("rust_price_monitor", "cpp_price_feed");
+ *   rust_manager_init();        // returns; Start sent once cpp_price_feed exists
+ *   cpp_mgr.init();
+ *
+ *   if (interop_dependencies_wait(5000) < 0) {
+ *       char report[512];
+ *       interop_dependency_report(report, sizeof report);
+ *       // "dependencies not met after 5000 ms: rust_price_monitor requires cpp_price_feed;"
+ *   }
+ *
+ * interop_require_for_start() holds only that actor's Start; the others
+ * start at once. After the timeout it is started anyway.
+ */
+
+#ifndef INTEROP_DEPENDENCIES_H
+#define INTEROP_DEPENDENCIES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Call before rust_manager_init(). Returns 0, or INTEROP_E_INVALID_ARGUMENT
+ * on a bad name. */
+INTEROP_API int interop_require(const char* actor, const char* dependency);
+
+/* Hold only `actor`'s Start until `dependency` is registered. Call before
+ * rust_manager_init(). Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad
+ * name. */
+INTEROP_API int interop_require_for_start(const char* actor, const char* dependency);
+
+/* How long Start is held for missing dependencies (default 5000 ms);
+ * after that Rust actors are not started (those with start requirements
+ * are started anyway) */
+INTEROP_API void interop_set_dependency_timeout(uint32_t timeout_ms);
+
+/* 1 = met (or none declared), 0 = still waiting, INTEROP_E_TIMEOUT = failed */
+INTEROP_API int interop_dependencies_wait(uint32_t timeout_ms);
+
+/* Failure report, NUL-terminated; returns its full length like snprintf */
+INTEROP_API int interop_dependency_report(char* out, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_DEPENDENCIES_H */
@@ -0,0 +1,67 @@
+/*
+ * MarketDepth snapshots published whole
+ *
+ * A C++ feed handler that updates a book level by level builds it in a
+ * depth publisher and publishes it when it is consistent; subscribers
+ * never see a book half updated:
+ *
+ *   int64_t books = interop_depth_new("cpp_market_data");
+ *   interop_depth_set_level(books, "AAPL", INTEROP_DEPTH_BID, 0, 189.50, 300);
+ *   interop_depth_set_level(books, "AAPL", INTEROP_DEPTH_ASK, 0, 189.52, 100);
+ *   interop_depth_publish(books, "AAPL");   // both levels, or neither
+ *
+ * Each symbol has two MarketDepth buffers: the one being built and the
+ * last one published, which is sent to the subscribers of the topic named
+ * after the symbol while the next is built. The next book starts as a
+ * copy of the last, so only changed levels are set again.
+ */
+
+#ifndef INTEROP_DEPTH_H
+#define INTEROP_DEPTH_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define INTEROP_DEPTH_BID 0
+#define INTEROP_DEPTH_ASK 1
+
+/* Returns the publisher id (> 0), or INTEROP_E_INVALID_ARGUMENT on a bad
+ * name. */
+INTEROP_API int64_t interop_depth_new(const char* publisher);
+
+/* Set a level (0..4) of the book being built, raising its num_levels to
+ * include it. Returns 0, INTEROP_E_NOT_FOUND on an unknown publisher, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad symbol, side or level. */
+INTEROP_API int interop_depth_set_level(int64_t depth, const char* symbol, int side, int level, double price,
+                                        int32_t size);
+
+/* Set num_levels (0..5) of the book being built, clearing the levels past
+ * it. Returns 0, INTEROP_E_NOT_FOUND on an unknown publisher, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad symbol or count. */
+INTEROP_API int interop_depth_set_levels(int64_t depth, const char* symbol, int levels);
+
+/* Publish the book being built. Returns the subscribers sent to (0 if
+ * unchanged since the last publish), INTEROP_E_NOT_FOUND on an unknown
+ * publisher, INTEROP_E_INVALID_ARGUMENT on a bad symbol, or
+ * INTEROP_E_WRONG_TYPE if the topic carries another message type (see
+ * topic.h). */
+INTEROP_API int interop_depth_publish(int64_t depth, const char* symbol);
+
+/* Copy the last published book into out, a MarketDepth. Returns 0,
+ * INTEROP_E_NOT_FOUND on an unknown publisher or none published yet, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad symbol or NULL out. */
+INTEROP_API int interop_depth_snapshot(int64_t depth, const char* symbol, void* out);
+
+/* Returns 0, or INTEROP_E_NOT_FOUND on an unknown publisher. */
+INTEROP_API int interop_depth_free(int64_t depth);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_DEPTH_H */
@@ -0,0 +1,37 @@
+/*
+ * Echo actor for latency probes (see probe.h)
+ *
+ * Answers every LatencyProbe with a LatencyEcho of the same fields, so a
+ * Rust probe can time the round trip into this runtime and back:
+ *
+ *   manage(new interop::EchoActor("cpp_echo"));
+ *   ...
+ *   interop_probe_start("cpp_echo", 1000, 500);
+ *
+ * It runs on a thread of its own like any actor, so the round trip also
+ * covers its mailbox, not only the bridge.
+ */
+
+#pragma once
+
+#include <cstring>
+
+#include "InteropMessages.hpp"
+#include "actors/Actor.hpp"
+
+namespace interop {
+
+class EchoActor : public actors::Actor {
+public:
+    explicit EchoActor(const char* actor_name = "cpp_echo") {
+        strncpy(name, actor_name, sizeof(name) - 1);
+        name[sizeof(name) - 1] = '\0';
+        MESSAGE_HANDLER(msg::LatencyProbe, on_probe);
+    }
+
+    void on_probe(const msg::LatencyProbe* m) noexcept {
+        reply(new msg::LatencyEcho(m->probe_id, m->seq, m->sent_at));
+    }
+};
+
+}  // namespace interop
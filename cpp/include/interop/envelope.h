@@ -0,0 +1,35 @@
+/*
+ * Envelope metadata for Rust handlers
+ *
+ * With envelopes on for a Rust actor, the bridge records who sent each
+ * message it queues for the actor, when, its sequence number and the span
+ * it was sent in. Handlers written with handle_messages_with_envelope!
+ * receive it as an extra argument (see rust/src/envelope.rs):
+ *
+ *   rust_actor_set_envelope("rust_strategy", 1);
+ *   rust_strategy.send(new msg::MarketUpdate(...));   // handler sees sender, seq, ...
+ *
+ * Covers rust_actor_send() and rust_actor_post(), not
+ * rust_actor_fast_send().
+ */
+
+#ifndef INTEROP_ENVELOPE_H
+#define INTEROP_ENVELOPE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Record envelopes for actor_name (on 1) or stop (0). Returns 0, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad name. */
+INTEROP_API int rust_actor_set_envelope(const char* actor_name, int on);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_ENVELOPE_H */
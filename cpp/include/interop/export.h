@@ -0,0 +1,36 @@
+/*
+ * Symbol import/export and calling convention of the interop FFI
+ *
+ * Every interop header marks the Rust functions it declares INTEROP_API
+ * and the callbacks it takes INTEROP_CALL. On Linux and macOS both are
+ * empty. On Windows:
+ *
+ *   cl /DINTEROP_DLL ... host.cpp actors_interop.dll.lib   // Rust built as a DLL
+ *   cl ... host.cpp actors_interop.lib                     // Rust linked statically
+ *
+ * INTEROP_DLL makes INTEROP_API __declspec(dllimport). INTEROP_EXPORT
+ * marks what C++ exports to Rust: the generated cpp_actor_* bridge and
+ * the interop_plugin_* entry points of hot-reloaded libraries (see
+ * hot_reload.h). INTEROP_CALL pins callbacks handed to Rust to __cdecl,
+ * what Rust's extern "C" means on 32-bit Windows; 64-bit Windows has a
+ * single convention.
+ */
+
+#ifndef INTEROP_EXPORT_H
+#define INTEROP_EXPORT_H
+
+#if defined(_WIN32)
+#  if defined(INTEROP_DLL)
+#    define INTEROP_API __declspec(dllimport)
+#  else
+#    define INTEROP_API
+#  endif
+#  define INTEROP_EXPORT __declspec(dllexport)
+#  define INTEROP_CALL __cdecl
+#else
+#  define INTEROP_API
+#  define INTEROP_EXPORT __attribute__((visibility("default")))
+#  define INTEROP_CALL
+#endif
+
+#endif /* INTEROP_EXPORT_H */
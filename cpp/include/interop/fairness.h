@@ -0,0 +1,54 @@
+/*
+ * Fair fan-out of Rust publishers
+ *
+ * A Rust publisher sends to its subscribers one after another, so one slow
+ * subscriber (a C++ actor whose sends retry, a full INTEROP_SUB_BLOCK
+ * queue) delays the rest. With fairness on, fan-out starts at a different
+ * subscriber on each publish, and a subscriber whose send takes longer
+ * than the budget has its messages sent from a backlog of its own, by a
+ * flusher thread serving the backlogs round-robin:
+ *
+ *   rust_fanout_set_fair(200, 4096);   // 200us budget, 4096 held
+ *
+ *   RustFanoutStats s;
+ *   if (rust_fanout_stats("rust_publisher", "cpp_recorder", &s) == 0 && s.dropped)
+ *       log_starved("cpp_recorder", s.dropped, s.max_wait_us);
+ *
+ * A full backlog drops its oldest message: publishers never wait, not even
+ * on INTEROP_SUB_BLOCK subscribers.
+ */
+
+#ifndef INTEROP_FAIRNESS_H
+#define INTEROP_FAIRNESS_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RustFanoutStats {
+    uint64_t overruns;     /* sends that took longer than the budget */
+    uint64_t deferred;     /* messages put in the backlog */
+    uint64_t dropped;      /* messages dropped from a full backlog */
+    uint64_t backlog;      /* messages in the backlog now */
+    uint64_t max_wait_us;  /* longest a message waited in the backlog */
+    int32_t slow;          /* 1 while messages go by the backlog */
+} RustFanoutStats;
+
+/* Fan out fairly: a send slower than budget_us (0: 500) defers the
+ * subscriber's messages to a backlog of backlog (0: 1024). Negative
+ * budget_us turns fairness off once the backlogs are sent. */
+INTEROP_API void rust_fanout_set_fair(int32_t budget_us, uint32_t backlog);
+
+/* Fill out with the counters of subscriber_name of publisher_name.
+ * Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad name or null out. */
+INTEROP_API int rust_fanout_stats(const char* publisher_name, const char* subscriber_name, RustFanoutStats* out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_FAIRNESS_H */
@@ -0,0 +1,136 @@
+/*
+ * Reading FlatBuffers frames in place (C++ receivers)
+ *
+ * With the FlatBuffers codec (link codec 5, see codec.rs), messages marked
+ * INTEROP_FLATBUFFERS in the IDL arrive as a FlatBuffers table after the
+ * usual [i32 msg_id][u32 len] header; other messages keep the raw struct
+ * frame. The generated views (InteropFlatBuffers.hpp) read fields straight
+ * from the received bytes, without building a message:
+ *
+ *   interop::fb::Frame frame(data, len);
+ *   if (frame.valid() && frame.msg_id == interop::fb::MarketDepthView::ID) {
+ *       interop::fb::MarketDepthView depth(frame.body, frame.body_len);
+ *       for (uint32_t i = 0; i < depth.bid_prices().size(); ++i) {
+ *           use(depth.bid_prices()[i]);
+ *       }
+ *   }
+ *
+ * Views and the vectors and strings they return point into the buffer, so
+ * they are valid only while it is. Every read is bounds-checked: a field
+ * that is absent or lies outside the buffer reads as zero or empty.
+ */
+
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <cstring>
+#include <string_view>
+
+namespace interop::fb {
+
+template <class T>
+inline T read(const uint8_t* p) {
+    T v;
+    std::memcpy(&v, p, sizeof(T));  // little-endian hosts, as for raw frames
+    return v;
+}
+
+// The header of a codec frame
+struct Frame {
+    int32_t msg_id = 0;
+    const uint8_t* body = nullptr;
+    size_t body_len = 0;
+
+    Frame(const uint8_t* data, size_t len) {
+        if (len < 8) return;
+        uint32_t n = read<uint32_t>(data + 4);
+        if (n > len - 8) return;
+        msg_id = read<int32_t>(data);
+        body = data + 8;
+        body_len = n;
+    }
+
+    bool valid() const { return body != nullptr; }
+};
+
+// Elements of a [T] field, read in place
+template <class T>
+class Vector {
+public:
+    Vector() = default;
+    Vector(const uint8_t* data, uint32_t size) : data_(data), size_(size) {}
+
+    uint32_t size() const { return size_; }
+    bool empty() const { return size_ == 0; }
+    T operator[](uint32_t i) const { return i < size_ ? read<T>(data_ + i * sizeof(T)) : T{}; }
+    const uint8_t* data() const { return data_; }
+
+private:
+    const uint8_t* data_ = nullptr;
+    uint32_t size_ = 0;
+};
+
+// The root table of a FlatBuffers buffer
+class Table {
+public:
+    Table(const uint8_t* buf, size_t len) : buf_(buf), len_(len) {
+        if (len < 4) return;
+        uint32_t pos = read<uint32_t>(buf);
+        if (!in(pos, 4)) return;
+        int64_t vtable = static_cast<int64_t>(pos) - read<int32_t>(buf + pos);
+        if (vtable < 0 || !in(static_cast<size_t>(vtable), 4)) return;
+        uint16_t vtable_len = read<uint16_t>(buf + vtable);
+        if (vtable_len < 4 || !in(static_cast<size_t>(vtable), vtable_len)) return;
+        pos_ = pos;
+        vtable_ = static_cast<size_t>(vtable);
+        vtable_len_ = vtable_len;
+    }
+
+    bool valid() const { return vtable_len_ != 0; }
+
+    template <class T>
+    T scalar(int slot) const {
+        size_t at = field(slot);
+        return at && in(at, sizeof(T)) ? read<T>(buf_ + at) : T{};
+    }
+
+    // Struct fields (Decimal, Uuid, Timestamp) share the C struct layout
+    template <class T>
+    T structure(int slot) const { return scalar<T>(slot); }
+
+    std::string_view string(int slot) const {
+        Vector<char> v = vector<char>(slot);
+        return std::string_view(reinterpret_cast<const char*>(v.data()), v.size());
+    }
+
+    template <class T>
+    Vector<T> vector(int slot) const {
+        size_t at = field(slot);
+        if (!at || !in(at, 4)) return {};
+        size_t start = at + read<uint32_t>(buf_ + at);
+        if (!in(start, 4)) return {};
+        uint32_t count = read<uint32_t>(buf_ + start);
+        if (count > (len_ - start - 4) / sizeof(T)) return {};
+        return Vector<T>(buf_ + start + 4, count);
+    }
+
+private:
+    // Position of field `slot`, 0 if absent
+    size_t field(int slot) const {
+        size_t entry = 4 + 2 * static_cast<size_t>(slot);
+        if (entry + 2 > vtable_len_) return 0;
+        uint16_t off = read<uint16_t>(buf_ + vtable_ + entry);
+        return off ? pos_ + off : 0;
+    }
+
+    bool in(size_t at, size_t n) const { return at <= len_ && n <= len_ - at; }
+
+    const uint8_t* buf_;
+    size_t len_;
+    size_t pos_ = 0;
+    size_t vtable_ = 0;
+    size_t vtable_len_ = 0;
+};
+
+}  // namespace interop::fb
@@ -0,0 +1,58 @@
+/*
+ * Freeze a Rust actor and edit the messages waiting for it
+ *
+ * A frozen actor stays registered, but each message its thread reaches is
+ * kept instead of handled, whichever side sent it. The kept messages can
+ * be listed as JSON, dropped or reordered; thawing hands them to the
+ * actor in their new order, then the rest of its mailbox.
+ *
+ * Usage:
+ *   rust_actor_freeze("rust_price_monitor");
+ *   char* json = rust_actor_pending_json("rust_price_monitor");
+ *   if (json) { log(json); interop_free_json(json); }
+ *   rust_actor_pending_remove("rust_price_monitor", 3);   // the poison message
+ *   rust_actor_pending_move("rust_price_monitor", 5, 0);  // handle this one first
+ *   rust_actor_thaw("rust_price_monitor");
+ *
+ * The JSON is an array of {"index", "msg_id", "message", "sender",
+ * "payload"}; sender is null unless the message had an envelope.
+ * Messages behind a handler that never returns are not seen. Replies
+ * from a kept message go to no one. interop_free_json() is in tap.h.
+ */
+
+#ifndef INTEROP_FREEZE_H
+#define INTEROP_FREEZE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* 0, also if already frozen; INTEROP_E_NOT_FOUND if actor_name is not a Rust
+ * actor */
+INTEROP_API int32_t rust_actor_freeze(const char* actor_name);
+
+/* Messages kept from a frozen actor as a JSON array, or NULL if it is not
+ * frozen. Free with interop_free_json(). */
+INTEROP_API char* rust_actor_pending_json(const char* actor_name);
+
+/* 0, or INTEROP_E_NOT_FOUND if the actor is not frozen or has no kept
+ * message index */
+INTEROP_API int32_t rust_actor_pending_remove(const char* actor_name, uint32_t index);
+
+/* Move kept message from to position to. 0, or INTEROP_E_NOT_FOUND if the
+ * actor is not frozen or either index is out of range. */
+INTEROP_API int32_t rust_actor_pending_move(const char* actor_name, uint32_t from, uint32_t to);
+
+/* Number of kept messages to be handled, or INTEROP_E_NOT_FOUND if not a
+ * frozen Rust actor */
+INTEROP_API int32_t rust_actor_thaw(const char* actor_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_FREEZE_H */
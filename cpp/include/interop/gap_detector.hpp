@@ -0,0 +1,66 @@
+/*
+ * Gap detection for sequenced streams (C++ subscribers)
+ *
+ * Mirrors sequencing::GapDetector in Rust. Enable stamping with
+ * interop_sequencing_enable(1), then check each MarketUpdate:
+ *
+ *   interop::GapDetector gaps_;
+ *
+ *   void on_update(const msg::MarketUpdate* m) noexcept {
+ *       msg::GapDetected gap;
+ *       if (gaps_.check("rust_publisher", m->symbol.data(), m->seq, gap)) {
+ *           // request a snapshot
+ *       }
+ *   }
+ */
+
+#pragma once
+
+#include <algorithm>
+#include <cstdint>
+#include <map>
+#include <string>
+#include <utility>
+
+#include "InteropMessages.hpp"
+#include "export.h"
+
+extern "C" INTEROP_API void interop_sequencing_enable(int on);
+
+namespace interop {
+
+class GapDetector {
+public:
+    // Returns true and fills `gap` when updates before `seq` are missing.
+    // seq 0 (unsequenced) and duplicates are ignored; seq 1 after a higher
+    // number is treated as a publisher restart.
+    bool check(const std::string& publisher, const std::string& topic,
+               int64_t seq, msg::GapDetected& gap) {
+        if (seq <= 0) return false;
+        int64_t& last = last_[{publisher, topic}];
+        int64_t expected = last + 1;
+        if (seq == 1 || seq >= expected) last = seq;
+        if (seq <= expected) return false;
+
+        copy_name(gap.publisher, publisher);
+        copy_name(gap.topic, topic);
+        gap.expected_seq = expected;
+        gap.received_seq = seq;
+        return true;
+    }
+
+    void reset(const std::string& publisher, const std::string& topic) {
+        last_.erase({publisher, topic});
+    }
+
+private:
+    template <size_t N>
+    static void copy_name(std::array<char, N>& dst, const std::string& src) {
+        dst.fill('\0');
+        std::copy_n(src.begin(), std::min(src.size(), N - 1), dst.begin());
+    }
+
+    std::map<std::pair<std::string, std::string>, int64_t> last_;
+};
+
+}  // namespace interop
@@ -0,0 +1,55 @@
+/*
+ * Named groups of actors, shared by both runtimes
+ *
+ * C++ and Rust actors join the same groups, and either side lists them,
+ * so "all pricing actors" does not depend on a naming convention. Rust
+ * routes or broadcasts to a group (group::group_router, group::broadcast)
+ * and the health summary lists every group.
+ *
+ * Usage:
+ *   interop_group_join("pricing", "cpp_pricer");
+ *
+ *   char buf[1024];
+ *   int n = interop_group_members("pricing", buf, sizeof(buf));  // "rust_pricer\ncpp_pricer"
+ *   if (n >= (int)sizeof(buf)) { ... retry with n + 1 bytes ... }
+ *
+ *   interop_group_leave("pricing", "cpp_pricer");     // e.g. before it stops
+ *
+ * Instances spawned with spawn_actor_group("MdWorker", "md_worker", 4)
+ * are in group "md_worker"; interop_group_size() counts such instances.
+ */
+
+#ifndef INTEROP_GROUP_H
+#define INTEROP_GROUP_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 0, 1 if already a member, or INTEROP_E_INVALID_ARGUMENT on a bad
+ * name */
+INTEROP_API int32_t interop_group_join(const char* group, const char* actor_name);
+
+/* Returns 0, INTEROP_E_NOT_FOUND if not a member, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad name */
+INTEROP_API int32_t interop_group_leave(const char* group, const char* actor_name);
+
+/* Newline-separated member names, NUL-terminated and truncated to len.
+ * Returns the full length like snprintf, or INTEROP_E_INVALID_ARGUMENT on a
+ * bad name. */
+INTEROP_API int32_t interop_group_members(const char* group, char* out, size_t len);
+
+/* Instances spawned in group base, INTEROP_E_NOT_FOUND if none were, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad name */
+INTEROP_API int32_t interop_group_size(const char* base);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_GROUP_H */
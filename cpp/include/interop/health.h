@@ -0,0 +1,97 @@
+/*
+ * Health of the Rust side of the interop layer
+ *
+ * rust_interop_health() writes a JSON object with the Rust Manager state,
+ * registered actors and groups, dead-letter, invalid message, string
+ * repair and transport error counts, and whether this (C++) runtime's heartbeat is current.
+ * Embed it in the host's own health endpoint.
+ *
+ * Usage:
+ *   rust_interop_heartbeat();                 // from a periodic timer
+ *
+ *   char buf[4096];
+ *   int n = rust_interop_health(buf, sizeof(buf));
+ *   if (n >= (int)sizeof(buf)) { ... retry with n + 1 bytes ... }
+ *
+ * For periodic sampling, rust_manager_stats() fills a fixed struct without
+ * allocating. Rate and utilization cover the time since the previous call,
+ * so call it from one monitoring thread only:
+ *
+ *   RustManagerStats st;
+ *   rust_manager_stats(&st);
+ *
+ * st.bytes_to_rust, st.bytes_to_cpp and st.conversion_rate count the C
+ * structs converted at the boundary, for sizing an out-of-process
+ * transport; the health JSON breaks them down per message type.
+ *
+ * When st.live_envelope_bytes grows, rust_actor_alloc_stats() shows which
+ * Rust actor's backlog holds it:
+ *
+ *   RustActorAllocStats a;
+ *   rust_actor_alloc_stats("rust_strategy", &a);
+ *
+ * If the Rust side stops responding, rust_manager_restart() ends every Rust
+ * actor and starts a fresh instance of each, without stopping C++:
+ *
+ *   if (rust_manager_restart() < 0) { ... no Rust Manager ... }
+ */
+
+#ifndef INTEROP_HEALTH_H
+#define INTEROP_HEALTH_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RustManagerStats {
+    uint64_t uptime_ms;               /* since rust_manager_init(); 0 before */
+    uint64_t messages_dispatched;     /* handled by Rust actors since start */
+    double dispatch_rate;             /* messages/s since the previous call */
+    double mean_thread_utilization;   /* 0..1, handler time per actor thread */
+    double max_thread_utilization;    /* 0..1, busiest actor thread */
+    uint32_t actor_count;
+    uint32_t manager_state;           /* 0 none, 1 created, 2 running, 3 stopped */
+    uint64_t live_envelope_bytes;     /* held for messages queued for Rust actors */
+    uint64_t bytes_to_rust;           /* C struct bytes converted C++ -> Rust since start */
+    uint64_t bytes_to_cpp;            /* C struct bytes converted Rust -> C++ since start */
+    double conversion_rate;           /* conversions either way/s since the previous call */
+} RustManagerStats;
+
+/* Bytes the bridge holds for one Rust actor's queued messages: the
+ * converted message, its envelope and envelope metadata */
+typedef struct RustActorAllocStats {
+    uint64_t live_bytes;              /* queued or being handled */
+    uint64_t peak_bytes;              /* highest live_bytes */
+    uint64_t total_bytes;             /* charged since the first message */
+    uint64_t allocations;             /* messages charged */
+} RustActorAllocStats;
+
+/* Mark the C++ runtime alive; reported dead after 5 s without a call */
+INTEROP_API void rust_interop_heartbeat(void);
+
+/* NUL-terminated JSON, truncated to len. Returns the full length. */
+INTEROP_API int rust_interop_health(char* out_json, size_t len);
+
+/* Returns 0, or INTEROP_E_INVALID_ARGUMENT if out is NULL */
+INTEROP_API int rust_manager_stats(RustManagerStats* out);
+
+/* Fill out for actor_name (zeros before its first bridged message).
+ * Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad name or if out is NULL. */
+INTEROP_API int rust_actor_alloc_stats(const char* actor_name, RustActorAllocStats* out);
+
+/* End all Rust actors, recreate them as registered and start them again.
+ * Rust actor state is lost unless the actor implements snapshot::Snapshot.
+ * Returns the number of actors restarted, or INTEROP_E_NO_MANAGER if
+ * create_rust_manager() was not called. */
+INTEROP_API int rust_manager_restart(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_HEALTH_H */
@@ -0,0 +1,51 @@
+/*
+ * Load and reload C++ actor libraries (Rust feature hot_reload)
+ *
+ * The library is attached as a C++ runtime (see cpp_runtime.h). Besides
+ * the generated bridge functions it exports:
+ *
+ *   INTEROP_EXPORT int  interop_plugin_init(void);      // create and start its actors, call cpp_actor_init()
+ *   INTEROP_EXPORT void interop_plugin_shutdown(void);  // stop them, call cpp_actor_shutdown()
+ *
+ * On Windows compile the library with INTEROP_PLUGIN defined, so its
+ * cpp_actor_init() does not replace the host's bridge functions (see
+ * interop_set_linked_cpp_runtime() in cpp_runtime.h).
+ *
+ * Usage:
+ *   interop_load_cpp_library("pricing", "./libpricing.so", "pricing.");
+ *   interop_reload_cpp_library("pricing", "./libpricing-v2.so");  // NULL: same path
+ *
+ * Sends to the library's actors fail with INTEROP_E_NOT_FOUND while it is
+ * reloaded. Rust ActorRefs to them keep working afterwards.
+ *
+ * The calls return 0 or, with the reason logged:
+ *   INTEROP_E_INVALID_ARGUMENT  a null name or path, or a library that does not load
+ *   INTEROP_E_NOT_FOUND         no library loaded under the name (reload, unload)
+ *   INTEROP_E_NAME_TAKEN        the name or prefix is in use
+ *   INTEROP_E_INCOMPATIBLE      a missing export or capability, or interop_plugin_init() failed
+ *   INTEROP_E_TIMEOUT           calls into the old library still running; it stays loaded
+ */
+
+#ifndef INTEROP_HOT_RELOAD_H
+#define INTEROP_HOT_RELOAD_H
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* prefix may be NULL */
+INTEROP_API int interop_load_cpp_library(const char* name, const char* path, const char* prefix);
+
+/* path NULL reloads from the current path */
+INTEROP_API int interop_reload_cpp_library(const char* name, const char* path);
+
+/* Stop and unload the library */
+INTEROP_API int interop_unload_cpp_library(const char* name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_HOT_RELOAD_H */
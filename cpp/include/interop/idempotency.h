@@ -0,0 +1,38 @@
+/*
+ * Replay protection for order-style messages sent to a Rust actor
+ *
+ * Messages with an idempotency key field (NewOrder.order_id) carry the
+ * same key on every resend. With idempotency enabled, the bridge delivers
+ * each key once; a replay within the retention is not delivered, and the
+ * sender gets the OrderAck the Rust actor recorded for the first delivery
+ * again:
+ *
+ *   rust_actor_set_idempotency("rust_oms", 300000, 100000);  // 5 min, 100000 keys
+ *
+ *   auto* order = new msg::NewOrder();
+ *   order->order_id = next_order_id++;   // nonzero; keep it for resends
+ *
+ * A replay is reported as a successful send. Key 0 is never a replay.
+ */
+
+#ifndef INTEROP_IDEMPOTENCY_H
+#define INTEROP_IDEMPOTENCY_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* retention_ms = 0 turns it off. Returns 0, or INTEROP_E_INVALID_ARGUMENT on
+ * a bad name. */
+INTEROP_API int rust_actor_set_idempotency(const char* actor_name, uint32_t retention_ms, size_t max_entries);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_IDEMPOTENCY_H */
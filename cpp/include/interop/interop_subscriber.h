@@ -0,0 +1,55 @@
+/*
+ * A ready-made Rust subscriber with C++ callbacks
+ *
+ * Builds an InteropSubscriber (rust/src/interop_subscriber.rs) under its
+ * name, then registers it with the Rust Manager. On Start it subscribes to
+ * its topics; each message of a type with a callback is passed as its C
+ * struct from interop_messages.h, valid only during the call, on the
+ * subscriber's thread:
+ *
+ *   interop_subscriber_new("rust_price_monitor", "cpp_price_feed");
+ *   interop_subscriber_topic("rust_price_monitor", "AAPL", 0);
+ *   interop_subscriber_on("rust_price_monitor", 1012, on_update, this);  // MarketUpdate
+ *   const void* rust_mgr = interop_subscriber_register("rust_price_monitor");
+ *
+ * It answers rust_subscriber_subscribe() / _unsubscribe() (subscriber.h)
+ * and subscribes again when its publisher restarts.
+ */
+
+#ifndef INTEROP_INTEROP_SUBSCRIBER_H
+#define INTEROP_INTEROP_SUBSCRIBER_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef void (INTEROP_CALL *interop_subscriber_fn)(void* user_data, int32_t msg_type, const void* msg_data);
+
+/* INTEROP_OK, or INTEROP_E_INVALID_ARGUMENT on a NULL or empty argument.
+ * Replaces an unregistered subscriber of the same name. */
+INTEROP_API int32_t interop_subscriber_new(const char* name, const char* publisher);
+
+/* INTEROP_OK, INTEROP_E_NOT_FOUND if name is not being built, or
+ * INTEROP_E_INVALID_ARGUMENT if topic is NULL, not UTF-8 or longer than
+ * 31 bytes */
+INTEROP_API int32_t interop_subscriber_topic(const char* name, const char* topic, int32_t qos);
+
+/* INTEROP_OK, INTEROP_E_NOT_FOUND if name is not being built,
+ * INTEROP_E_INVALID_ARGUMENT if callback is NULL, or
+ * INTEROP_E_UNKNOWN_MESSAGE on an unknown message ID */
+INTEROP_API int32_t interop_subscriber_on(const char* name, int32_t msg_type,
+                                          interop_subscriber_fn callback, void* user_data);
+
+/* Returns the Rust Manager pointer for rust_actor_init(), or NULL if name
+ * is not being built, there is no Manager or the name is taken */
+INTEROP_API const void* interop_subscriber_register(const char* name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_INTEROP_SUBSCRIBER_H */
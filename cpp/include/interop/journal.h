@@ -0,0 +1,40 @@
+/*
+ * Write-ahead journal of messages sent to a Rust actor
+ *
+ * Every interop message queued for the actor through rust_actor_send() or
+ * rust_actor_post() is appended to {dir}/{actor}.journal before it is
+ * queued, and marked done once the actor has handled it. After a crash,
+ * enabling the journal again, before rust_manager_init(), has the actor
+ * handle what was left undone right after its Start:
+ *
+ *   int recovered = rust_actor_enable_journal("rust_strategy", "/var/lib/interop", 1);
+ *
+ * Handling is at least once: make the actor's handlers idempotent.
+ * fsync_every: 1 syncs before each send returns, n every n messages,
+ * 0 leaves it to the OS (a process crash loses nothing either way).
+ */
+
+#ifndef INTEROP_JOURNAL_H
+#define INTEROP_JOURNAL_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Messages recovered from a previous run, or INTEROP_E_INVALID_ARGUMENT on a
+ * NULL or empty argument or if the journal cannot be opened */
+INTEROP_API int32_t rust_actor_enable_journal(const char* actor_name, const char* dir, uint32_t fsync_every);
+
+/* Journaled messages not yet handled, INTEROP_E_NOT_FOUND if the actor has
+ * no journal */
+INTEROP_API int32_t rust_actor_journal_pending(const char* actor_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_JOURNAL_H */
@@ -0,0 +1,56 @@
+/*
+ * Struct layout check with Rust
+ *
+ * Besides its capabilities, the generated cpp_actor_init() sends the size
+ * and alignment of every message struct and the offset, size and
+ * alignment of each field, as this compiler laid them out. Rust compares
+ * them with its own, and init_cpp_actor_lookup() refuses to connect
+ * (INTEROP_E_INCOMPATIBLE) on any difference, e.g. a struct built under
+ * another #pragma pack:
+ *
+ *   cpp_actor_init(&cpp_mgr);
+ *   if (init_cpp_actor_lookup() != 0) {
+ *       char report[2048];
+ *       rust_interop_layout_report(report, sizeof report);
+ *       // "C++ struct layouts differ from Rust's:
+ *       //    MarketUpdate (1012): size 64 in Rust, 72 in C++
+ *       //    MarketUpdate.price: offset 40 in Rust, 48 in C++"
+ *   }
+ *
+ * A hand-written bridge may call interop_cpp_layouts() itself; without it
+ * layouts are not checked.
+ */
+
+#ifndef INTEROP_LAYOUT_H
+#define INTEROP_LAYOUT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct InteropFieldLayout {
+    int32_t msg_id;
+    const char* field;  /* NULL for the struct itself (offset 0) */
+    uint32_t offset;
+    uint32_t size;
+    uint32_t align;
+} InteropFieldLayout;
+
+/* Returns 0, or INTEROP_E_INVALID_ARGUMENT on a null array with a nonzero
+ * count */
+INTEROP_API int interop_cpp_layouts(const InteropFieldLayout* entries, uint32_t count);
+
+/* Where the C++ and Rust layouts differ, NUL-terminated, empty if they
+ * agree; returns its full length like snprintf */
+INTEROP_API int rust_interop_layout_report(char* out, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_LAYOUT_H */
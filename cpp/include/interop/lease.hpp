@@ -0,0 +1,83 @@
+/*
+ * Subscription leases (C++ publishers)
+ *
+ * Mirrors lease::LeaseTable in Rust. With leases on, the bridge renews
+ * subscriptions for its subscribers by sending LeaseRenew; a subscriber
+ * that is gone stops renewing and its subscription expires here:
+ *
+ *   interop_set_subscription_lease(10000);  // before rust_manager_init()
+ *
+ *   interop::LeaseTable leases_;
+ *
+ *   void on_subscribe(...)  { leases_.grant(sender_name, topic); }
+ *   void on_lease_renew(const msg::LeaseRenew* m) {
+ *       leases_.renew(get_reply_to()->get_name(), topic_of(m), m->lease_ms);
+ *   }
+ *   void publish() {
+ *       for (auto& [sub, topic] : leases_.expire()) { ... drop it ... }
+ *   }
+ */
+
+#pragma once
+
+#include <chrono>
+#include <cstdint>
+#include <map>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "export.h"
+
+extern "C" {
+INTEROP_API int interop_set_subscription_lease(int lease_ms);
+/* 0 while leases are off */
+INTEROP_API int interop_subscription_lease_ms(void);
+}
+
+namespace interop {
+
+class LeaseTable {
+public:
+    using Clock = std::chrono::steady_clock;
+    using Key = std::pair<std::string, std::string>;  // (subscriber, topic)
+
+    // Start or extend a lease on subscribe; no-op while leases are off
+    void grant(const std::string& subscriber, const std::string& topic) {
+        int ms = interop_subscription_lease_ms();
+        if (ms > 0) expiry_[{subscriber, topic}] = Clock::now() + std::chrono::milliseconds(ms);
+    }
+
+    // Extend a live lease; an expired or unknown one is not revived
+    bool renew(const std::string& subscriber, const std::string& topic, int32_t lease_ms) {
+        auto it = expiry_.find({subscriber, topic});
+        auto now = Clock::now();
+        if (it == expiry_.end() || it->second <= now) return false;
+        it->second = now + std::chrono::milliseconds(lease_ms);
+        return true;
+    }
+
+    void revoke(const std::string& subscriber, const std::string& topic) {
+        expiry_.erase({subscriber, topic});
+    }
+
+    // Remove and return every expired lease
+    std::vector<Key> expire() {
+        std::vector<Key> expired;
+        auto now = Clock::now();
+        for (auto it = expiry_.begin(); it != expiry_.end();) {
+            if (it->second <= now) {
+                expired.push_back(it->first);
+                it = expiry_.erase(it);
+            } else {
+                ++it;
+            }
+        }
+        return expired;
+    }
+
+private:
+    std::map<Key, Clock::time_point> expiry_;
+};
+
+} // namespace interop
@@ -0,0 +1,61 @@
+/*
+ * Lifecycle events of Rust-managed actors
+ *
+ * Lets a C++ supervisor show Rust actors next to its own. The callback
+ * runs on the Rust actor's thread; copy actor_name if it is kept.
+ *
+ * Usage:
+ *   void on_lifecycle(const char* actor_name, int event) {
+ *       if (event == INTEROP_LIFECYCLE_PANICKED) { ... }
+ *   }
+ *
+ *   rust_register_lifecycle_callback(on_lifecycle);  // before rust_manager_init()
+ *
+ * C++ actors restarted by the C++ side are reported back with
+ * interop_report_cpp_restart(), so Rust subscribers of a restarted C++
+ * publisher subscribe again.
+ *
+ * A message that panics its handler on every delivery (3 attempts, with a
+ * restart after each) is quarantined and reported to the error callback.
+ */
+
+#ifndef INTEROP_LIFECYCLE_H
+#define INTEROP_LIFECYCLE_H
+
+#include "export.h"
+
+#define INTEROP_LIFECYCLE_STARTED   0  /* handled Start */
+#define INTEROP_LIFECYCLE_STOPPED   1  /* dropped at shutdown */
+#define INTEROP_LIFECYCLE_PANICKED  2  /* a handler panicked; message dropped */
+#define INTEROP_LIFECYCLE_RESTARTED 3  /* fresh instance built and started */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef void (INTEROP_CALL *interop_lifecycle_fn)(const char* actor_name, int event);
+
+/* Pass NULL to stop receiving events */
+INTEROP_API void rust_register_lifecycle_callback(interop_lifecycle_fn cb);
+
+/* error is the handler's panic message, followed by "; state: <json>"
+ * if the actor gives a state dump (Rust: PanicContext) */
+typedef void (INTEROP_CALL *interop_error_fn)(const char* actor_name, int msg_type, const char* error);
+
+/* Called for each quarantined message; pass NULL to clear */
+INTEROP_API void rust_register_error_callback(interop_error_fn cb);
+
+/* C++ actor actor_name was restarted and lost its state; published on
+ * $sys/lifecycle as INTEROP_SYS_RESTARTED. Returns 0, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad name. */
+INTEROP_API int interop_report_cpp_restart(const char* actor_name);
+
+/* Quarantined messages held for actor_name, INTEROP_E_INVALID_ARGUMENT on a
+ * bad name */
+INTEROP_API int rust_quarantine_count(const char* actor_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_LIFECYCLE_H */
@@ -0,0 +1,43 @@
+/*
+ * Verbose bridge logging, switched on at runtime
+ *
+ * Prints sends, lookups and conversions across the bridge to stderr, for
+ * chasing cross-language problems without a debug build. Set a level for
+ * the process, or for the actors involved:
+ *
+ *   rust_set_interop_log_level(INTEROP_LOG_INFO);               // every send
+ *   rust_set_actor_log_level("rust_risk", INTEROP_LOG_TRACE);   // with payloads
+ *   rust_set_actor_log_level("rust_risk", INTEROP_LOG_DEFAULT); // back to the process level
+ *
+ * An event is logged if the process level or the level of its sender or
+ * target allows it. `interop-ctl log LEVEL [ACTOR]` does the same.
+ */
+
+#ifndef INTEROP_LOG_H
+#define INTEROP_LOG_H
+
+#include "export.h"
+
+#define INTEROP_LOG_DEFAULT -1  /* rust_set_actor_log_level() only */
+#define INTEROP_LOG_OFF      0  /* default */
+#define INTEROP_LOG_ERROR    1  /* failed sends and lookups */
+#define INTEROP_LOG_INFO     2  /* every send and lookup */
+#define INTEROP_LOG_DEBUG    3  /* and every conversion */
+#define INTEROP_LOG_TRACE    4  /* conversions with the message as JSON */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 0, or INTEROP_E_INVALID_ARGUMENT for an unknown level */
+INTEROP_API int rust_set_interop_log_level(int level);
+
+/* Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad name or unknown
+ * level */
+INTEROP_API int rust_set_actor_log_level(const char* actor, int level);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_LOG_H */
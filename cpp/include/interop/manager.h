@@ -0,0 +1,92 @@
+/*
+ * The Rust Manager and the bridge into it
+ *
+ * The calls every host makes, in order (see ARCHITECTURE.md,
+ * "Initialization Sequence"):
+ *
+ *   create_rust_manager();
+ *   register_actor_by_type_name("RustPublisher", "rust_publisher");
+ *   rust_actor_init(get_rust_manager());
+ *   if (init_cpp_actor_lookup() != 0) { ... C++ side incomplete ... }
+ *   rust_manager_init();
+ *   ...
+ *   rust_actor_send("rust_publisher", "cpp_gui", Subscribe::ID, &c_msg);
+ *   ...
+ *   rust_manager_end();
+ *
+ * The generated RustActorIF wraps the sends. Sends return 0 or an
+ * InteropErrorCode (interop_errors.h).
+ */
+
+#ifndef INTEROP_MANAGER_H
+#define INTEROP_MANAGER_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Create the Rust Manager; actors are registered on it until
+ * rust_manager_init() */
+INTEROP_API void create_rust_manager(void);
+
+/* The Manager, for rust_actor_init(); NULL before create_rust_manager() */
+INTEROP_API const void* get_rust_manager(void);
+
+/* Send Start to the Manager's actors (held by the startup barrier, see
+ * startup_barrier.h) */
+INTEROP_API void rust_manager_init(void);
+
+/* Cancel every task (see tasks.h), then stop the actors and wait for
+ * their threads */
+INTEROP_API void rust_manager_end(void);
+
+/* Register an #[interop_actor] type under instance_name (NULL: the
+ * type's default name), on a thread of its own or on the shared thread of
+ * group_name. Return the Manager, or NULL if the type is unknown or the
+ * name taken. */
+INTEROP_API const void* register_actor_by_type_name(const char* type_name, const char* instance_name);
+INTEROP_API const void* register_actor_shared(const char* type_name, const char* instance_name, const char* group_name);
+
+/* Register n instances of type_name named base_name-0 .. base_name-(n-1)
+ * as one group (see group.h). Returns the Manager, or NULL. */
+INTEROP_API const void* spawn_actor_group(const char* type_name, const char* base_name, int n);
+
+/* Point the bridge at the Manager, or detach it. */
+INTEROP_API void rust_actor_init(const void* mgr);
+INTEROP_API void rust_actor_shutdown(void);
+
+/* Let Rust look up C++ actors. Returns 0, or INTEROP_E_INCOMPATIBLE if this
+ * side lacks functions or messages Rust needs (see capabilities.h). */
+INTEROP_API int init_cpp_actor_lookup(void);
+
+/* Send C struct msg_data (message msg_type) to Rust actor actor_name;
+ * sender_name (may be NULL) gets replies. fast_send blocks until the
+ * actor has handled it. */
+INTEROP_API int32_t rust_actor_send(const char* actor_name, const char* sender_name, int32_t msg_type,
+                                    const void* msg_data);
+INTEROP_API int32_t rust_actor_fast_send(const char* actor_name, const char* sender_name, int32_t msg_type,
+                                         const void* msg_data);
+
+/* 1 if Rust actor name exists, else 0 */
+INTEROP_API int32_t rust_actor_exists(const char* name);
+
+/* Messages sent to actor_name and not yet handled, or INTEROP_E_NOT_FOUND if
+ * unknown */
+INTEROP_API int32_t rust_actor_queue_depth(const char* name);
+
+/* Drop Rust's cached lookup of C++ actor name (NULL: all of them), e.g.
+ * after removing it; see also interop_cpp_actor_removed() (orphans.h). */
+INTEROP_API void interop_invalidate_lookup(const char* name);
+
+/* How long Rust keeps a "not found" lookup answer; 0 does not keep it */
+INTEROP_API void interop_set_lookup_negative_ttl_ms(uint32_t ms);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_MANAGER_H */
@@ -0,0 +1,57 @@
+/*
+ * Actor names shared by the C++ and Rust registries
+ *
+ * Registering a Rust actor under the name of a C++ actor (or another Rust
+ * actor) fails: the register_* function prints the conflict and returns
+ * NULL. Register C++ actors and call cpp_actor_init() first so the check
+ * can see them, and check the other direction before manage():
+ *
+ *   if (rust_actor_exists("md_feed")) { ... name taken by a Rust actor ... }
+ *
+ * To shadow a C++ actor on purpose (Rust lookups then find the Rust actor):
+ *
+ *   interop_allow_shadowing("md_feed");               // this name only
+ *   interop_set_shadow_policy(INTEROP_SHADOW_ALLOW);  // every name
+ *
+ * Two Rust actors can never share a name.
+ *
+ * Names must be non-empty UTF-8. Check a C++ actor's name before manage():
+ *
+ *   if (interop_validate_actor_name(name) != INTEROP_OK) { ... }
+ *
+ * Rust refuses such names at registration (INTEROP_REGISTER_INVALID_NAME)
+ * and on sends (INTEROP_E_INVALID_NAME, a "name" dead letter).
+ */
+
+#ifndef INTEROP_NAMES_H
+#define INTEROP_NAMES_H
+
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_SHADOW_REJECT 0  /* default */
+#define INTEROP_SHADOW_ALLOW  1
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* 1 if a Rust actor is registered under name; valid before rust_actor_init() */
+INTEROP_API int32_t rust_actor_exists(const char* name);
+
+/* Returns 0, or INTEROP_E_INVALID_ARGUMENT for an unknown policy */
+INTEROP_API int interop_set_shadow_policy(int policy);
+
+/* Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad name */
+INTEROP_API int interop_allow_shadowing(const char* name);
+
+/* INTEROP_OK, or INTEROP_E_INVALID_NAME for a null, empty or non-UTF-8 name */
+INTEROP_API int interop_validate_actor_name(const char* name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_NAMES_H */
@@ -0,0 +1,60 @@
+/*
+ * Refs held across the bridge to actors that are gone
+ *
+ * With ref tracking on, the bridge records which actors hold refs to
+ * actors of the other language and reports the holders when the target
+ * goes, to find refs cached once and never refreshed:
+ *
+ *   interop_set_ref_tracking(1);
+ *   ...
+ *   manager.remove(feed);                        // C++ actor destroyed
+ *   interop_cpp_actor_removed("cpp_price_feed");
+ *   // [Orphans] C++ actor 'cpp_price_feed' removed; still referenced by
+ *   //           rust_price_monitor (1 lookup)
+ *
+ * Rust refs to C++ actors are counted per lookup, under the name the Rust
+ * actor looked up as. Each RustActorIF counts as a ref to its Rust actor
+ * under its sender name while it lives, and is reported when that actor
+ * stops. A send through a ref to a gone actor is logged once.
+ *
+ *   char buf[4096];
+ *   interop_orphaned_refs(buf, sizeof(buf));
+ *   [{"target":"cpp_price_feed","side":"cpp","holder":"rust_price_monitor","refs":1}]
+ */
+
+#ifndef INTEROP_ORPHANS_H
+#define INTEROP_ORPHANS_H
+
+#include <stddef.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Non-zero turns tracking on, 0 off (the default) and forgets every ref.
+ * Returns 0. */
+INTEROP_API int interop_set_ref_tracking(int enabled);
+
+/* A C++ actor named holder (NULL: unnamed) took or dropped a ref to Rust
+ * actor target. RustActorIF calls these. Return 0, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad name. */
+INTEROP_API int interop_ref_acquired(const char* holder, const char* target);
+INTEROP_API int interop_ref_released(const char* holder, const char* target);
+
+/* C++ actor name was destroyed: drops Rust's cached lookup of it, like
+ * interop_invalidate_lookup(), and reports the Rust actors holding refs
+ * to it. Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad name. */
+INTEROP_API int interop_cpp_actor_removed(const char* name);
+
+/* Write the refs held to gone actors as a JSON array into out_json
+ * (NUL-terminated, truncated to fit len). Returns the full length, like
+ * snprintf. */
+INTEROP_API int interop_orphaned_refs(char* out_json, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_ORPHANS_H */
@@ -0,0 +1,46 @@
+/*
+ * Outbound queues for Rust -> C++ sends
+ *
+ * Without a queue a Rust actor's send to a C++ actor calls into C++ on the
+ * Rust actor's thread. With one, the send is queued and a flusher thread
+ * per C++ runtime ("linked", or a name from interop_attach_cpp_runtime)
+ * makes the call, so Rust handlers never wait on a slow C++ mailbox:
+ *
+ *   interop_set_outbound_queue("linked", 10000, INTEROP_OUTBOUND_DROP_OLDEST);
+ *
+ * A queued send reports success; failures at flush time go to the Rust
+ * failure callback and health counters.
+ */
+
+#ifndef INTEROP_OUTBOUND_H
+#define INTEROP_OUTBOUND_H
+
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_OUTBOUND_DROP_NEWEST 0  /* discard the new message; the send reports success */
+#define INTEROP_OUTBOUND_DROP_OLDEST 1  /* discard the oldest queued message */
+#define INTEROP_OUTBOUND_BLOCK       2  /* the Rust sender waits for room */
+#define INTEROP_OUTBOUND_REJECT      3  /* discard; the send returns INTEROP_OUTBOUND_FULL */
+
+/* Send return code when an INTEROP_OUTBOUND_REJECT queue is full */
+#define INTEROP_OUTBOUND_FULL INTEROP_E_OUTBOUND_FULL
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* capacity 0 removes the queue once flushed. Returns 0, or
+ * INTEROP_E_INVALID_ARGUMENT on a bad name or policy. */
+INTEROP_API int interop_set_outbound_queue(const char* link, uint32_t capacity, int overflow);
+
+/* Messages waiting in link's queue */
+INTEROP_API uint64_t interop_outbound_pending(const char* link);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_OUTBOUND_H */
@@ -0,0 +1,43 @@
+/*
+ * Partitioned topics: a topic split into N partitions by a key field
+ *
+ * Consumers scale out by each subscribing to some partitions, with a plain
+ * Subscribe to "<topic>#<n>"; a subscriber of "<topic>" still gets all of
+ * them. Messages with the same key always land in the same partition, so
+ * each key is seen in order by the one consumer that owns it.
+ *
+ * Usage:
+ *   interop_declare_partitions("trades", 4, "symbol");
+ *
+ *   // C++ publisher fanning out by itself
+ *   int p = interop_topic_partition("trades", MarketUpdate::ID, &c_msg);
+ *   // send to the subscribers of "trades" and of "trades#<p>"
+ *
+ * Rust publishers using broker::publish() route by partition already.
+ */
+
+#ifndef INTEROP_PARTITIONS_H
+#define INTEROP_PARTITIONS_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 0, INTEROP_E_INVALID_ARGUMENT on a bad argument, or
+ * INTEROP_E_NAME_TAKEN if topic is partitioned otherwise */
+INTEROP_API int interop_declare_partitions(const char* topic, uint32_t partitions, const char* key_field);
+
+/* Partition of the C struct msg_data on topic, INTEROP_E_NOT_FOUND if the
+ * topic is not partitioned, or INTEROP_E_UNKNOWN_MESSAGE. Messages without
+ * key_field go to 0. */
+INTEROP_API int interop_topic_partition(const char* topic, int msg_id, const void* msg_data);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_PARTITIONS_H */
@@ -0,0 +1,41 @@
+/*
+ * Idle actors passivated and re-activated on demand
+ *
+ * A Rust actor with an idle timeout that handles no message for that long
+ * is passivated: snapshotted (it needs Snapshot hooks) and its instance
+ * dropped. The next message sent to it, from C++ or Rust, rebuilds it,
+ * restores the snapshot and delivers Start before handling the message.
+ *
+ * Usage:
+ *   register_actor_shared("SymbolMonitor", "monitor_aapl", "monitors");
+ *   interop_set_idle_timeout("monitor_aapl", 60000);
+ *
+ * A dormant actor with a thread of its own keeps the thread; members of a
+ * shared group hold none. The health summary shows dormant actors with
+ * status "dormant".
+ */
+
+#ifndef INTEROP_PASSIVATION_H
+#define INTEROP_PASSIVATION_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* 0 never passivates. May be set before the actor is registered.
+ * Return 0, or INTEROP_E_INVALID_ARGUMENT on a null or non-UTF-8 name. */
+INTEROP_API int interop_set_idle_timeout(const char* actor_name, uint64_t idle_ms);
+
+/* 1 if passivated, 0 if not, INTEROP_E_INVALID_ARGUMENT on a null or
+ * non-UTF-8 name */
+INTEROP_API int interop_is_dormant(const char* actor_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_PASSIVATION_H */
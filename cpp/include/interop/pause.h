@@ -0,0 +1,38 @@
+/*
+ * Pause and resume dispatch to a Rust actor
+ *
+ * A paused actor stays registered and keeps queueing what C++ sends it
+ * (rust_actor_send, rust_actor_fast_send, rust_actor_post), but its
+ * handlers are not called until it is resumed. Held messages count in
+ * rust_actor_queue_depth(), so rust_actor_set_post_limit() bounds them.
+ *
+ * Usage:
+ *   rust_actor_pause("rust_price_monitor");
+ *   reload_reference_data();
+ *   rust_actor_resume("rust_price_monitor");  // held messages first, in order
+ */
+
+#ifndef INTEROP_PAUSE_H
+#define INTEROP_PAUSE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* 0, also if already paused; INTEROP_E_NOT_FOUND if actor_name is not a Rust
+ * actor */
+INTEROP_API int32_t rust_actor_pause(const char* actor_name);
+
+/* Number of held messages delivered, or INTEROP_E_NOT_FOUND if not a paused
+ * Rust actor */
+INTEROP_API int32_t rust_actor_resume(const char* actor_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_PAUSE_H */
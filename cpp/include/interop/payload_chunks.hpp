@@ -0,0 +1,110 @@
+/*
+ * RawPayloads larger than the bridge size limit (C++ side)
+ *
+ * With a limit and chunking set for INTEROP_BRIDGE_LINK (size_limit.h),
+ * Rust sends an oversized RawPayload as PayloadChunks. C++ senders do the
+ * same with send_payload(), and receivers on either side reassemble:
+ *
+ *   // Sender
+ *   interop::send_payload(rust_sink, std::move(payload), this);
+ *
+ *   // Receiver, on PayloadChunk
+ *   if (auto whole = assembler_.add(static_cast<const msg::PayloadChunk*>(m))) {
+ *       on_payload(*whole);
+ *   }
+ */
+
+#pragma once
+
+#include <algorithm>
+#include <array>
+#include <cstdint>
+#include <optional>
+#include <random>
+#include <unordered_map>
+#include <utility>
+#include <vector>
+
+#include "InteropMessages.hpp"
+#include "actors/ActorRef.hpp"
+#include "interop/size_limit.h"
+
+namespace interop {
+
+// Send `payload` whole, or as PayloadChunks if it is over the bridge limit
+// and chunking is on. Returns false if it is over the limit and cannot be
+// chunked (the Rust side would refuse it with INTEROP_MESSAGE_TOO_LARGE).
+inline bool send_payload(actors::ActorRef& to, msg::RawPayload payload, actors::Actor* self) {
+    int chunking = 0;
+    uint64_t max = interop_max_message_size(INTEROP_BRIDGE_LINK, &chunking);
+    size_t size = sizeof(::RawPayload) + payload.bytes.size();
+    if (max == 0 || size <= max) {
+        to.send(new msg::RawPayload(std::move(payload)), self);
+        return true;
+    }
+    // Same room per chunk as size_limit.rs: the limit minus the chunk
+    // struct and 16 bytes of slack
+    size_t overhead = sizeof(::PayloadChunk) + 16;
+    if (!chunking || max <= overhead) return false;
+    size_t room = static_cast<size_t>(max) - overhead;
+
+    static thread_local std::mt19937_64 rng{std::random_device{}()};
+    int64_t id = 0;
+    while (id == 0) id = static_cast<int64_t>(rng());
+    const auto& bytes = payload.bytes;
+    int32_t count = static_cast<int32_t>(std::max<size_t>((bytes.size() + room - 1) / room, 1));
+    for (int32_t i = 0; i < count; ++i) {
+        size_t begin = std::min(bytes.size(), static_cast<size_t>(i) * room);
+        size_t end = std::min(bytes.size(), begin + room);
+        std::vector<uint8_t> data(bytes.begin() + begin, bytes.begin() + end);
+        to.send(new msg::PayloadChunk(id, i, count, payload.content_type, std::move(data)), self);
+    }
+    return true;
+}
+
+// Reassembles PayloadChunks into the RawPayload they were cut from
+class PayloadAssembler {
+public:
+    // The payload once every chunk has arrived; a chunk that does not
+    // match its transfer drops the transfer
+    std::optional<msg::RawPayload> add(const msg::PayloadChunk* c) {
+        if (c->count <= 0) return std::nullopt;
+        auto [it, inserted] = partial_.try_emplace(c->transfer_id);
+        Partial& p = it->second;
+        if (inserted) {
+            p.content_type = c->content_type;
+            p.parts.resize(static_cast<size_t>(c->count));
+        }
+        if (c->index < 0 || c->index >= c->count || p.parts.size() != static_cast<size_t>(c->count)) {
+            partial_.erase(it);
+            return std::nullopt;
+        }
+        auto& part = p.parts[static_cast<size_t>(c->index)];
+        if (!part) {
+            part = c->data;
+            ++p.received;
+        }
+        if (p.received < p.parts.size()) return std::nullopt;
+
+        msg::RawPayload whole;
+        whole.content_type = p.content_type;
+        for (auto& piece : p.parts) whole.bytes.insert(whole.bytes.end(), piece->begin(), piece->end());
+        partial_.erase(it);
+        return whole;
+    }
+
+    // Transfers still missing chunks
+    size_t pending() const { return partial_.size(); }
+
+    void discard(int64_t transfer_id) { partial_.erase(transfer_id); }
+
+private:
+    struct Partial {
+        std::array<char, 32> content_type{};
+        std::vector<std::optional<std::vector<uint8_t>>> parts;
+        size_t received = 0;
+    };
+    std::unordered_map<int64_t, Partial> partial_;
+};
+
+}  // namespace interop
@@ -0,0 +1,60 @@
+/*
+ * Allow/deny policy for messages crossing between C++ and Rust
+ *
+ * Every message sent across the bridge, in either direction, is checked
+ * against the rules in the order they were added; the first match decides.
+ * NULL names and INTEROP_POLICY_ANY_MSG are wildcards. Configure before
+ * rust_manager_init(), then seal:
+ *
+ *   interop_set_policy_audit_callback(on_denied);
+ *   interop_policy_set_default(INTEROP_POLICY_DENY);
+ *   interop_policy_deny("pricing", "execution", INTEROP_POLICY_ANY_MSG);
+ *   interop_policy_allow("pricing", NULL, INTEROP_POLICY_ANY_MSG);
+ *   interop_policy_allow(NULL, "pricing", 1012);
+ *   interop_policy_seal();
+ *
+ * A denied send returns INTEROP_POLICY_DENIED (INTEROP_POST_DENIED from
+ * rust_actor_post) and is logged and reported to the audit callback, on
+ * the sending thread. Posts have sender "" and match wildcard senders only.
+ */
+
+#ifndef INTEROP_POLICY_H
+#define INTEROP_POLICY_H
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_POLICY_DENY    0
+#define INTEROP_POLICY_ALLOW   1
+#define INTEROP_POLICY_ANY_MSG -1
+
+/* Send return code for a message the policy does not allow */
+#define INTEROP_POLICY_DENIED INTEROP_E_POLICY_DENIED
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef void (INTEROP_CALL *InteropPolicyAuditCallback)(const char* sender, const char* target, int msg_id);
+
+/* Append a rule. Return 0, INTEROP_E_POLICY_DENIED once sealed, or
+ * INTEROP_E_INVALID_ARGUMENT on a non-UTF-8 name. */
+INTEROP_API int interop_policy_allow(const char* sender, const char* target, int msg_id);
+INTEROP_API int interop_policy_deny(const char* sender, const char* target, int msg_id);
+
+/* Verdict when no rule matches; INTEROP_POLICY_ALLOW initially. Returns 0,
+ * INTEROP_E_POLICY_DENIED once sealed, or INTEROP_E_INVALID_ARGUMENT for an
+ * unknown verdict. */
+INTEROP_API int interop_policy_set_default(int verdict);
+
+/* No more changes for the life of the process */
+INTEROP_API void interop_policy_seal(void);
+
+/* NULL clears */
+INTEROP_API void interop_set_policy_audit_callback(InteropPolicyAuditCallback cb);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_POLICY_H */
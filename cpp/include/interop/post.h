@@ -0,0 +1,51 @@
+/*
+ * Post messages to Rust actors from any C++ thread
+ *
+ * rust_actor_send() is for C++ actor threads. Network threads, timers and
+ * library callbacks use rust_actor_post() instead:
+ *   - callable from any thread, concurrently
+ *   - never blocks; msg_data is copied before it returns
+ *   - returns INTEROP_POST_QUEUE_FULL instead of queueing when the target
+ *     is at the limit set with rust_actor_set_post_limit()
+ *
+ * Usage:
+ *   rust_actor_set_post_limit("rust_price_monitor", 10000);
+ *
+ *   MarketUpdate c_msg = ...;   // from interop_messages.h
+ *   int rc = rust_actor_post("rust_price_monitor", 1012, &c_msg);
+ *   if (rc == INTEROP_POST_QUEUE_FULL) { ... drop or retry later ... }
+ */
+
+#ifndef INTEROP_POST_H
+#define INTEROP_POST_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_POST_OK              INTEROP_OK
+#define INTEROP_POST_NOT_FOUND       INTEROP_E_NOT_FOUND         /* no such Rust actor (yet) */
+#define INTEROP_POST_UNKNOWN_MESSAGE INTEROP_E_UNKNOWN_MESSAGE
+#define INTEROP_POST_QUEUE_FULL      INTEROP_E_QUEUE_FULL        /* backpressure: nothing queued */
+#define INTEROP_POST_INVALID         INTEROP_E_INVALID_ARGUMENT  /* null name or data */
+#define INTEROP_POST_DENIED          INTEROP_E_POLICY_DENIED     /* refused by the policy or sender auth */
+#define INTEROP_POST_TOO_LARGE       INTEROP_E_TOO_LARGE         /* over the size limit, see size_limit.h */
+#define INTEROP_POST_SHUTTING_DOWN   INTEROP_E_SHUTTING_DOWN     /* shutdown requested, see shutdown.h */
+#define INTEROP_POST_INVALID_MESSAGE INTEROP_E_INVALID_MESSAGE   /* breaks a field rule, see validation.h */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+INTEROP_API int32_t rust_actor_post(const char* actor_name, int32_t msg_type, const void* msg_data);
+
+/* max_depth = 0 removes the limit */
+INTEROP_API int32_t rust_actor_set_post_limit(const char* actor_name, size_t max_depth);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_POST_H */
@@ -0,0 +1,70 @@
+/*
+ * Latency probes across the bridge
+ *
+ * A probe sends a timestamped LatencyProbe to an echo actor every interval
+ * and keeps the round trips of the LatencyEcho answers. Run an echo actor
+ * on each side (interop::EchoActor from echo_actor.hpp in C++,
+ * interop_register_echo_actor() in Rust) and probe it:
+ *
+ *   int64_t probe = interop_probe_start("cpp_echo", 1000, 500);  // Rust -> C++ -> Rust
+ *   ...
+ *   InteropProbeStats st;
+ *   if (interop_probe_stats(probe, &st) == 0 && st.p99_ns > 100000) { ... alert ... }
+ *
+ * Percentiles cover the last 1024 echoes. A probe not echoed within the
+ * timeout is lost. Probes only run while the Rust Manager is running;
+ * rust_interop_health() lists them under "probes".
+ */
+
+#ifndef INTEROP_PROBE_H
+#define INTEROP_PROBE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Mirrors probe::ProbeStats in Rust; round trips in nanoseconds, 0 before
+ * the first echo */
+typedef struct {
+    uint64_t sent;
+    uint64_t echoed;       /* in time */
+    uint64_t lost;         /* not echoed within the timeout */
+    uint64_t late;         /* echoes of probes already counted lost */
+    uint64_t unreachable;  /* intervals the target could not be looked up */
+    uint64_t p50_ns;
+    uint64_t p90_ns;
+    uint64_t p99_ns;
+    uint64_t max_ns;
+    uint64_t last_ns;
+} InteropProbeStats;
+
+/* Register a Rust echo actor as name (NULL: "rust_echo"). Returns 0,
+ * INTEROP_E_INVALID_ARGUMENT on a bad name, or INTEROP_E_NAME_TAKEN if
+ * there is no Manager or the name is taken. */
+INTEROP_API int interop_register_echo_actor(const char* name);
+
+/* Probe echo actor target (C++ or Rust) every interval_ms, counting a
+ * probe lost after timeout_ms. Returns the probe id (> 0),
+ * INTEROP_E_INVALID_ARGUMENT on a bad name or zero interval, or
+ * INTEROP_E_QUEUE_FULL if the probe task cannot start. */
+INTEROP_API int64_t interop_probe_start(const char* target, uint32_t interval_ms, uint32_t timeout_ms);
+
+/* Returns 0, or INTEROP_E_NOT_FOUND on an unknown probe; stats also
+ * INTEROP_E_INVALID_ARGUMENT on a NULL out */
+INTEROP_API int interop_probe_stats(int64_t probe, InteropProbeStats* out);
+INTEROP_API int interop_probe_stop(int64_t probe);
+
+/* Every running probe as a JSON array, round trips in microseconds.
+ * Returns the full length like snprintf. */
+INTEROP_API int interop_probes(char* out_json, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_PROBE_H */
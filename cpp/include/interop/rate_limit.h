@@ -0,0 +1,49 @@
+/*
+ * Limits on how fast Rust actors send to C++ actors
+ *
+ * Token bucket per Rust sender (all targets) or per sender -> target pair.
+ * A send over the limit is dropped, delayed until a token is free, or
+ * refused with INTEROP_RATE_LIMITED, depending on the policy:
+ *
+ *   // strategy may send 500 msg/s to the gateway, bursts of 50, else error
+ *   interop_set_rate_limit("strategy", "order_gateway", 500.0, 50, INTEROP_RATE_ERROR);
+ *   // and 2000 msg/s overall, waiting for tokens
+ *   interop_set_rate_limit("strategy", NULL, 2000.0, 100, INTEROP_RATE_DELAY);
+ *
+ * A send needs a token from every limit that applies. Delayed sends block
+ * the Rust actor's thread.
+ */
+
+#ifndef INTEROP_RATE_LIMIT_H
+#define INTEROP_RATE_LIMIT_H
+
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_RATE_DROP  0  /* discard; the send reports success */
+#define INTEROP_RATE_DELAY 1  /* sleep until a token is free */
+#define INTEROP_RATE_ERROR 2  /* discard; the send returns INTEROP_RATE_LIMITED */
+
+/* Send return code for a message refused by an INTEROP_RATE_ERROR limit */
+#define INTEROP_RATE_LIMITED INTEROP_E_RATE_LIMITED
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* target NULL: every send by sender. Replaces an existing limit with a full
+ * bucket. Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad name, a rate <= 0
+ * or an unknown policy. */
+INTEROP_API int interop_set_rate_limit(const char* sender, const char* target,
+                                       double per_second, uint32_t burst, int policy);
+
+/* Remove the limit set for the same sender and target */
+INTEROP_API void interop_clear_rate_limit(const char* sender, const char* target);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_RATE_LIMIT_H */
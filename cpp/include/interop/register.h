@@ -0,0 +1,56 @@
+/*
+ * Registering many Rust actors in one call
+ *
+ * A host starting dozens of #[interop_actor] instances can pass them as
+ * one array instead of calling register_actor_by_type_name() or
+ * register_actor_shared() for each:
+ *
+ *   InteropActorDescriptor actors[] = {
+ *       {"PriceMonitor", "monitor_aapl", "monitors"},
+ *       {"PriceMonitor", "monitor_msft", "monitors"},
+ *       {"MdFeed",       NULL,           NULL},        // default name, own thread
+ *   };
+ *   int32_t status[3];
+ *   int32_t n = rust_register_actors(actors, 3, status);
+ *   if (n != 3) { ... status[i] says why entry i failed ... }
+ *   rust_actor_init(get_rust_manager());
+ *
+ * A failed entry does not stop the others. Each registered actor runs on
+ * the default thread config, or on the shared thread of thread_group.
+ */
+
+#ifndef INTEROP_REGISTER_H
+#define INTEROP_REGISTER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_REGISTER_OK             INTEROP_OK
+#define INTEROP_REGISTER_BAD_DESCRIPTOR INTEROP_E_INVALID_ARGUMENT  /* null type name or empty group */
+#define INTEROP_REGISTER_UNKNOWN_TYPE   INTEROP_E_UNKNOWN_TYPE
+#define INTEROP_REGISTER_NAME_TAKEN     INTEROP_E_NAME_TAKEN
+#define INTEROP_REGISTER_NO_MANAGER     INTEROP_E_NO_MANAGER        /* create_rust_manager() not called */
+#define INTEROP_REGISTER_INVALID_NAME   INTEROP_E_INVALID_NAME      /* empty instance name */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct {
+    const char* factory_name;   /* #[interop_actor] type name */
+    const char* instance_name;  /* NULL for the type's default name */
+    const char* thread_group;   /* NULL for a thread of its own */
+} InteropActorDescriptor;
+
+/* Registers count actors, writing an INTEROP_REGISTER_* code per entry to
+ * status (may be NULL). Returns how many registered. */
+INTEROP_API int32_t rust_register_actors(const InteropActorDescriptor* descriptors, size_t count, int32_t* status);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_REGISTER_H */
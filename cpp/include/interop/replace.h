@@ -0,0 +1,37 @@
+/*
+ * Replace a running Rust actor with a new version (blue/green)
+ *
+ * Upgrades one Rust actor while C++ keeps sending to it:
+ *
+ *   if (interop_replace_actor("rust_strategy", "StrategyV2") != 0) {
+ *       // the old version is still running
+ *   }
+ *
+ * type_name is an #[interop_actor] type linked into the process. The
+ * replacement is built off the actor's thread, takes over once the old
+ * instance has handled everything queued before the call, and starts with
+ * its state if both have Snapshot hooks. Sends by name or actor id keep
+ * working throughout. The call blocks until then, at most 5 s.
+ */
+
+#ifndef INTEROP_REPLACE_H
+#define INTEROP_REPLACE_H
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns INTEROP_OK, INTEROP_E_INVALID_ARGUMENT on a NULL or non-UTF-8
+ * name, INTEROP_E_UNKNOWN_TYPE, INTEROP_E_NOT_FOUND if actor_name is not a
+ * registered (running) Rust actor, INTEROP_E_TIMEOUT, or
+ * INTEROP_E_REPLACE_FAILED if the replacement panicked (see stderr). On
+ * any error the old instance stays. */
+INTEROP_API int interop_replace_actor(const char* actor_name, const char* type_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_REPLACE_H */
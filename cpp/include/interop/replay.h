@@ -0,0 +1,46 @@
+/*
+ * Replaying recorded market data for backtests
+ *
+ * A replay source publishes recorded MarketUpdate / MarketDepth messages
+ * through the broker under their symbol, like a live feed. Strategies
+ * subscribe to it by name with the usual Subscribe:
+ *
+ *   create_rust_manager();
+ *   interop_replay_source(NULL, "ticks.csv", INTEROP_REPLAY_CSV, 10.0, 1);
+ *
+ *   // in a C++ strategy, with replay_ref_ resolved from "replay_source":
+ *   replay_ref_.send(sub, this);   // msg::Subscribe for "AAPL"; replay starts
+ *
+ * CSV lines are time,symbol,price,volume (time in Unix nanoseconds or RFC
+ * 3339); recordings are written by replay::Recorder in Rust. speed 1.0
+ * keeps the recorded pace, 0 replays as fast as possible. With a simulated
+ * clock (interop/clock.h) virtual time follows the recorded times.
+ */
+
+#ifndef INTEROP_REPLAY_H
+#define INTEROP_REPLAY_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#define INTEROP_REPLAY_RECORDING 0
+#define INTEROP_REPLAY_CSV       1
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Register a replay source named name (NULL for "replay_source") that
+ * starts once min_subscribers Subscribes arrived, or on Start with 0.
+ * Returns 0, INTEROP_E_INVALID_ARGUMENT on bad arguments or a missing file,
+ * or INTEROP_E_NAME_TAKEN if there is no Rust Manager or the name is
+ * taken. */
+INTEROP_API int32_t interop_replay_source(const char* name, const char* path, int32_t format,
+                                          double speed, uint32_t min_subscribers);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_REPLAY_H */
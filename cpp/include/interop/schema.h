@@ -0,0 +1,44 @@
+/*
+ * Message schemas at run time
+ *
+ * Names, IDs and fields of every message in the IDL, for tools that were
+ * not compiled against the generated types (gateways, bindings for other
+ * languages, generic printers). Schemas are JSON:
+ *
+ *   int id = interop_message_id("MarketUpdate");     // < 0 if unknown
+ *
+ *   char* json = interop_message_schema("MarketUpdate");
+ *   // {"id":..,"name":"MarketUpdate","c_size":..,"fields":[{"name":"symbol",
+ *   //   "c_type":"char","kind":"text","array_len":16,"offset":0,...},...]}
+ *   if (json) { ...; interop_free_json(json); }
+ *
+ * Field offsets are into the C struct, so a tool can read fields from the
+ * message data it is given. interop_free_json() is in tap.h.
+ */
+
+#ifndef INTEROP_SCHEMA_H
+#define INTEROP_SCHEMA_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* ID of message name, or INTEROP_E_UNKNOWN_MESSAGE if there is none */
+INTEROP_API int interop_message_id(const char* name);
+
+/* Schema of message name as JSON; every schema as a JSON array if name is
+ * NULL. NULL for an unknown name. Free with interop_free_json(). */
+INTEROP_API char* interop_message_schema(const char* name);
+
+/* interop_message_schema() by message ID; NULL for an unknown ID */
+INTEROP_API char* interop_message_schema_by_id(int msg_id);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_SCHEMA_H */
@@ -0,0 +1,57 @@
+/*
+ * Authentication of senders of messages entering Rust
+ *
+ * A hook set here is asked about every message entering Rust through the
+ * bridge, after the policy (policy.h), on the sending thread. It sees the
+ * sender ("" for posts, asks and anonymous C++ code), the target with
+ * aliases resolved, the message ID and the entry point:
+ *
+ *   int only_gateway(const char* sender, const char* target, int msg_id, int entry) {
+ *       return strcmp(target, "rust_risk") != 0 || !is_order_control(msg_id)
+ *           || strcmp(sender, "cpp_exec_gateway") == 0;
+ *   }
+ *   interop_set_sender_auth(only_gateway);
+ *   interop_set_sender_auth_audit_callback(on_rejected);
+ *
+ * A rejected send returns INTEROP_POLICY_DENIED (INTEROP_POST_DENIED,
+ * INTEROP_ASK_DENIED). Rejects are counted per sender, logged and
+ * reported to the audit callback.
+ */
+
+#ifndef INTEROP_SENDER_AUTH_H
+#define INTEROP_SENDER_AUTH_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+/* Entry points, as passed to the hook */
+#define INTEROP_ENTRY_SEND        0  /* rust_actor_send, rust_actor_send_id */
+#define INTEROP_ENTRY_FAST_SEND   1
+#define INTEROP_ENTRY_BORROW_SEND 2
+#define INTEROP_ENTRY_POST        3
+#define INTEROP_ENTRY_ASK         4
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Nonzero delivers the message */
+typedef int (INTEROP_CALL *InteropSenderAuthHook)(const char* sender, const char* target, int msg_id, int entry);
+
+typedef void (INTEROP_CALL *InteropSenderAuthAuditCallback)(const char* sender, const char* target, int msg_id, int entry);
+
+/* Replaces an earlier hook; NULL delivers without asking */
+INTEROP_API void interop_set_sender_auth(InteropSenderAuthHook hook);
+
+/* NULL clears */
+INTEROP_API void interop_set_sender_auth_audit_callback(InteropSenderAuthAuditCallback cb);
+
+/* Messages from sender_name rejected so far; every sender's if NULL */
+INTEROP_API uint64_t interop_sender_auth_rejected(const char* sender_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_SENDER_AUTH_H */
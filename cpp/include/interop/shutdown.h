@@ -0,0 +1,75 @@
+/*
+ * Coordinated shutdown of the Rust and C++ managers
+ *
+ * Either side requests shutdown; from then on interop sends in both
+ * directions fail with INTEROP_SEND_SHUTTING_DOWN. Each side drains and
+ * confirms, and only when both have confirmed are the managers ended:
+ *
+ *   void on_shutdown_requested(const char* initiator) {
+ *       // stop producing, let C++ mailboxes empty, then:
+ *       interop_shutdown_cpp_ready();
+ *   }
+ *
+ *   interop_on_shutdown_requested(on_shutdown_requested);  // at startup
+ *   ...
+ *   interop_shutdown_request("cpp_main");   // or a Rust actor calls shutdown::request()
+ *   if (interop_shutdown_wait(10000)) {
+ *       rust_manager_end();
+ *       cpp_mgr.end();
+ *   }
+ *
+ * The callback runs once, on the thread that requested shutdown. The Rust
+ * side confirms by itself once its bridge and outbound queues are empty,
+ * or after the drain timeout (default 5 s). Both steps are also published
+ * on $sys/shutdown (see sys_topics.h).
+ *
+ * Once both managers have ended, interop_shutdown_all() frees the Rust
+ * Manager and what the bridge caches and unregisters every callback, so
+ * LeakSanitizer and Miri see no leaks from the interop layer.
+ */
+
+#ifndef INTEROP_SHUTDOWN_H
+#define INTEROP_SHUTDOWN_H
+
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_SEND_SHUTTING_DOWN INTEROP_E_SHUTTING_DOWN
+
+#define INTEROP_SHUTDOWN_RUNNING    0
+#define INTEROP_SHUTDOWN_DRAINING   1  /* requested, a side is still draining */
+#define INTEROP_SHUTDOWN_READY      2  /* both confirmed: end the managers */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef void (INTEROP_CALL *interop_shutdown_fn)(const char* initiator);
+
+/* initiator may be NULL. Returns 0, or 1 if already requested. */
+INTEROP_API int interop_shutdown_request(const char* initiator);
+
+/* Pass NULL to clear */
+INTEROP_API void interop_on_shutdown_requested(interop_shutdown_fn cb);
+
+/* The C++ side has drained */
+INTEROP_API void interop_shutdown_cpp_ready(void);
+
+INTEROP_API int interop_shutdown_state(void);
+
+/* Returns 1 once both sides are ready, 0 on timeout */
+INTEROP_API int interop_shutdown_wait(uint32_t timeout_ms);
+
+INTEROP_API void interop_set_shutdown_drain_timeout(uint32_t timeout_ms);
+
+/* Free the Rust side after rust_manager_end() and the C++ end(); not from a
+ * Rust actor's thread. Returns 0, or 1 if there was no Rust Manager. */
+INTEROP_API int interop_shutdown_all(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_SHUTDOWN_H */
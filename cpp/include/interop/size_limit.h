@@ -0,0 +1,47 @@
+/*
+ * Maximum message size per transport
+ *
+ * INTEROP_BRIDGE_LINK is the in-process C++ <-> Rust bridge, where the
+ * size is the C struct plus its interop_bytes payloads. Other names are
+ * transport links (as for interop_set_link_codec), where it is the
+ * encoded frame. Links without a limit are unchecked.
+ *
+ *   interop_set_max_message_size(INTEROP_BRIDGE_LINK, 1 << 20, 1);
+ *   interop_set_max_message_size("kafka_sink", 900 * 1024, 0);
+ *
+ * A message over the limit is not sent: rust_actor_send() and Rust -> C++
+ * sends return INTEROP_MESSAGE_TOO_LARGE (INTEROP_POST_TOO_LARGE from
+ * rust_actor_post), transports log and drop it. With chunking on, a
+ * RawPayload over the limit goes as PayloadChunks instead; from C++ send
+ * it with interop::send_payload() (interop/payload_chunks.hpp).
+ */
+
+#ifndef INTEROP_SIZE_LIMIT_H
+#define INTEROP_SIZE_LIMIT_H
+
+#include <stdint.h>
+
+#include "export.h"
+#include "interop_errors.h"
+
+#define INTEROP_BRIDGE_LINK "bridge"
+
+/* Send return code for a message over the size limit */
+#define INTEROP_MESSAGE_TOO_LARGE INTEROP_E_TOO_LARGE
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* max_bytes = 0 removes the limit. Returns 0, or INTEROP_E_INVALID_ARGUMENT
+ * on a bad name. */
+INTEROP_API int interop_set_max_message_size(const char* link, uint64_t max_bytes, int chunking);
+
+/* Limit on link, 0 if none; *chunking (may be NULL) is 1 if payloads are chunked */
+INTEROP_API uint64_t interop_max_message_size(const char* link, int* chunking);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_SIZE_LIMIT_H */
@@ -0,0 +1,43 @@
+/*
+ * Startup barrier between the C++ and Rust runtimes
+ *
+ * Holds Rust Start messages until both sides have confirmed they are
+ * ready, so sends made from Start handlers cannot race actor registration.
+ *
+ * Usage:
+ *   interop_barrier_enable();
+ *   rust_manager_init();      // Rust ready, Start held
+ *   cpp_actor_init(&cpp_mgr);
+ *   cpp_mgr.init();
+ *   interop_cpp_ready();      // C++ ready, Rust Start released
+ *
+ * Other threads can block on interop_barrier_wait() until both are ready.
+ */
+
+#ifndef INTEROP_STARTUP_BARRIER_H
+#define INTEROP_STARTUP_BARRIER_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Call before rust_manager_init() */
+INTEROP_API void interop_barrier_enable(void);
+
+/* C++ actors registered and bridge initialized; also installs the C++ lookup.
+ * Returns INTEROP_E_INCOMPATIBLE if the C++ side lacks what Rust needs
+ * (interop/capabilities.h) */
+INTEROP_API int interop_cpp_ready(void);
+
+/* Returns 1 once both sides are ready, 0 on timeout */
+INTEROP_API int interop_barrier_wait(uint32_t timeout_ms);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_STARTUP_BARRIER_H */
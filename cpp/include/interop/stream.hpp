@@ -0,0 +1,183 @@
+/*
+ * Streams between two actors (C++ side)
+ *
+ * Mirrors stream::StreamHandle in Rust: ordered StreamChunks in both
+ * directions, each side sending only as many chunks as the peer has
+ * granted credit for.
+ *
+ *   // Opener
+ *   auto s = interop::Stream::open(manager->get_ref("rust_positions"), this, "cpp_risk", 8);
+ *   for (auto& part : parts) s.send(part);   // queued until credit arrives
+ *   s.finish();
+ *
+ *   // Acceptor, on StreamOpen
+ *   auto peer = manager->get_ref(interop::Stream::origin_of(m));
+ *   streams_.emplace(m->stream_id, interop::Stream::accept(m, peer, this, 8));
+ *
+ *   // Either side, on StreamChunk / StreamCredit / StreamEnd
+ *   std::vector<uint8_t> chunk;
+ *   switch (s.handle(msg, chunk)) {
+ *       case interop::Stream::Event::Chunk:     ... use chunk ...
+ *       case interop::Stream::Event::Finished:  ... peer sent everything ...
+ *       case interop::Stream::Event::Cancelled:
+ *       case interop::Stream::Event::Failed:    ... stream is over ...
+ *       case interop::Stream::Event::None:      break;
+ *   }
+ */
+
+#pragma once
+
+#include <algorithm>
+#include <array>
+#include <cstdint>
+#include <cstring>
+#include <deque>
+#include <random>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "InteropMessages.hpp"
+#include "actors/ActorRef.hpp"
+
+namespace interop {
+
+class Stream {
+public:
+    enum class Event { None, Chunk, Finished, Cancelled, Failed };
+
+    // Open a stream to `peer`; `my_name` is where the peer sends back to
+    static Stream open(actors::ActorRef peer, actors::Actor* self,
+                       const std::string& my_name, int32_t window) {
+        static thread_local std::mt19937_64 rng{std::random_device{}()};
+        int64_t id = 0;
+        while (id == 0) id = static_cast<int64_t>(rng());
+        window = std::max(window, 1);
+
+        std::array<char, 32> origin{};
+        std::copy_n(my_name.begin(), std::min(my_name.size(), origin.size() - 1), origin.begin());
+        peer.send(new msg::StreamOpen(id, origin, window), self);
+        return Stream(id, std::move(peer), self, 0, window);
+    }
+
+    // Accept a StreamOpen; `peer` is the opener (see origin_of)
+    static Stream accept(const msg::StreamOpen* m, actors::ActorRef peer,
+                         actors::Actor* self, int32_t window) {
+        window = std::max(window, 1);
+        peer.send(new msg::StreamCredit(m->stream_id, window), self);
+        return Stream(m->stream_id, std::move(peer), self, std::max(m->credit, 0), window);
+    }
+
+    static std::string origin_of(const msg::StreamOpen* m) {
+        return std::string(m->origin.data(), strnlen(m->origin.data(), m->origin.size()));
+    }
+
+    int64_t id() const { return id_; }
+    size_t queued() const { return queue_.size(); }
+    bool closed() const { return sent_end_ && peer_done_; }
+
+    // Queue a chunk; false after finish() or cancel()
+    bool send(std::vector<uint8_t> chunk) {
+        if (finishing_ || sent_end_) return false;
+        queue_.push_back(std::move(chunk));
+        flush();
+        return true;
+    }
+
+    // End this direction after the queued chunks
+    void finish() {
+        finishing_ = true;
+        flush();
+    }
+
+    // Abandon both directions; queued chunks are dropped
+    void cancel() {
+        if (!sent_end_) {
+            queue_.clear();
+            end(INTEROP_STREAM_CANCEL);
+        }
+        peer_done_ = true;
+    }
+
+    // Handle a stream message for this stream; `chunk` is filled on Chunk
+    Event handle(const actors::Message* m, std::vector<uint8_t>& chunk) {
+        if (peer_done_) return Event::None;
+        switch (m->get_message_id()) {
+            case msg::StreamCredit::ID: {
+                auto* c = static_cast<const msg::StreamCredit*>(m);
+                if (c->stream_id != id_) return Event::None;
+                credit_ += std::max(c->credit, 0);
+                flush();
+                return Event::None;
+            }
+            case msg::StreamChunk::ID: {
+                auto* c = static_cast<const msg::StreamChunk*>(m);
+                if (c->stream_id != id_) return Event::None;
+                if (c->index != received_) {
+                    cancel();
+                    return Event::Failed;  // a chunk was skipped
+                }
+                ++received_;
+                if (++ungranted_ >= (window_ + 1) / 2) {
+                    peer_.send(new msg::StreamCredit(id_, ungranted_), self_);
+                    ungranted_ = 0;
+                }
+                chunk = c->data;
+                return Event::Chunk;
+            }
+            case msg::StreamEnd::ID: {
+                auto* e = static_cast<const msg::StreamEnd*>(m);
+                if (e->stream_id != id_) return Event::None;
+                if (e->status == INTEROP_STREAM_CANCEL) {
+                    peer_done_ = sent_end_ = true;
+                    queue_.clear();
+                    return Event::Cancelled;
+                }
+                if (e->chunks != received_) {
+                    cancel();
+                    return Event::Failed;  // ended before every chunk arrived
+                }
+                peer_done_ = true;
+                return Event::Finished;
+            }
+            default:
+                return Event::None;
+        }
+    }
+
+private:
+    Stream(int64_t id, actors::ActorRef peer, actors::Actor* self, int32_t credit, int32_t window)
+        : id_(id), peer_(std::move(peer)), self_(self), credit_(credit), window_(window) {}
+
+    void end(int32_t status) {
+        sent_end_ = true;
+        peer_.send(new msg::StreamEnd(id_, status, sent_), self_);
+    }
+
+    void flush() {
+        while (credit_ > 0 && !sent_end_ && !queue_.empty()) {
+            peer_.send(new msg::StreamChunk(id_, sent_, std::move(queue_.front())), self_);
+            queue_.pop_front();
+            ++sent_;
+            --credit_;
+        }
+        if (finishing_ && queue_.empty() && !sent_end_) end(INTEROP_STREAM_COMPLETE);
+    }
+
+    int64_t id_;
+    actors::ActorRef peer_;
+    actors::Actor* self_;
+    // Sending
+    int32_t credit_;
+    std::deque<std::vector<uint8_t>> queue_;
+    int64_t sent_ = 0;
+    bool finishing_ = false;
+    bool sent_end_ = false;
+    // Receiving
+    int32_t window_;
+    int64_t received_ = 0;
+    int32_t ungranted_ = 0;
+    bool peer_done_ = false;
+};
+
+}  // namespace interop
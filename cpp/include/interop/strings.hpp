@@ -0,0 +1,68 @@
+/*
+ * Fixed-size char fields (char name[N]) without silent truncation
+ *
+ * Mirrors InteropString in Rust: at most N-1 bytes of text and a NUL,
+ * cut only at a UTF-8 character boundary.
+ *
+ *   if (!interop::set_str(update->symbol, symbol)) {
+ *       // did not fit: truncated and counted
+ *   }
+ *   if (!interop::set_str(sub->topic, topic, interop::Fit::Reject)) {
+ *       return;  // topic left empty rather than subscribing to a prefix
+ *   }
+ *   std::string topic = interop::get_str(m->topic);  // stops at N without a NUL
+ *
+ * The Rust bridge also repairs, or in strict mode rejects
+ * (interop_strings_set_strict(1)), received fields that are not
+ * NUL-terminated UTF-8.
+ */
+
+#pragma once
+
+#include <algorithm>
+#include <array>
+#include <atomic>
+#include <cstdint>
+#include <string>
+#include <string_view>
+
+#include "export.h"
+
+extern "C" INTEROP_API void interop_strings_set_strict(int strict);
+
+namespace interop {
+
+enum class Fit { Truncate, Reject };
+
+// Texts cut to fit by set_str() in this process
+inline std::atomic<uint64_t>& truncated_strings() {
+    static std::atomic<uint64_t> count{0};
+    return count;
+}
+
+// Copy `s` into `dst` with a trailing NUL. Returns false if it did not
+// fit: cut at a character boundary (Truncate), or `dst` left empty (Reject).
+template <size_t N>
+bool set_str(std::array<char, N>& dst, std::string_view s, Fit fit = Fit::Truncate) {
+    static_assert(N > 0, "char field needs room for the NUL");
+    dst.fill('\0');
+    size_t end = s.size();
+    bool fits = end <= N - 1;
+    if (!fits) {
+        truncated_strings().fetch_add(1, std::memory_order_relaxed);
+        if (fit == Fit::Reject) return false;
+        end = N - 1;
+        // Back up over UTF-8 continuation bytes (10xxxxxx)
+        while (end > 0 && (static_cast<unsigned char>(s[end]) & 0xC0) == 0x80) --end;
+    }
+    std::copy_n(s.begin(), end, dst.begin());
+    return fits;
+}
+
+// Text up to the first NUL, or all N bytes if there is none
+template <size_t N>
+std::string get_str(const std::array<char, N>& src) {
+    return std::string(src.begin(), std::find(src.begin(), src.end(), '\0'));
+}
+
+}  // namespace interop
@@ -0,0 +1,55 @@
+/*
+ * Subscription filters: what a publisher sends one subscriber, cut down
+ * before it crosses the bridge
+ *
+ * A filter is stages separated by '|':
+ *   volume > 1000 && symbol != "TEST"   conditions on fields (== != < <= > >=)
+ *   every 100ms by symbol               at most one message per interval (per key)
+ *   @name                               a transform registered from Rust
+ *
+ * Usage:
+ *   // Subscriber, before or after sending its Subscribe
+ *   interop_set_subscription_filter("cpp_monitor", "rust_feed", "trades", "volume > 1000");
+ *
+ *   // C++ publisher fanning out by itself
+ *   if (interop_subscription_filter_check(sub, "cpp_feed", "trades", MarketUpdate::ID, &c_msg) == 1) {
+ *       // send to sub
+ *   }
+ *
+ * Rust publishers using broker::publish() apply filters already. A filter
+ * goes away with its subscription.
+ */
+
+#ifndef INTEROP_SUB_FILTER_H
+#define INTEROP_SUB_FILTER_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Filter what publisher sends subscriber on topic; null or "" clears it.
+ * Returns 0, INTEROP_E_INVALID_NAME on a bad name, INTEROP_E_INVALID_ARGUMENT
+ * if spec does not parse, or INTEROP_E_NOT_FOUND if it names an unknown
+ * transform (see stderr). */
+INTEROP_API int interop_set_subscription_filter(const char* subscriber, const char* publisher, const char* topic,
+                                                const char* spec);
+
+/* 1 to send the C struct msg_data to subscriber, 0 not to,
+ * INTEROP_E_INVALID_ARGUMENT on a bad name, or INTEROP_E_UNKNOWN_MESSAGE for
+ * an unknown msg_id. Counts as a send for "every" stages. */
+INTEROP_API int interop_subscription_filter_check(const char* subscriber, const char* publisher, const char* topic,
+                                                  int msg_id, const void* msg_data);
+
+/* Messages the subscription's filter has kept from subscriber */
+INTEROP_API uint64_t interop_subscription_filter_dropped(const char* subscriber, const char* publisher,
+                                                         const char* topic);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_SUB_FILTER_H */
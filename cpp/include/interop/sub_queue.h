@@ -0,0 +1,38 @@
+/*
+ * Overflow policies of Rust subscribers
+ *
+ * A Rust actor that or's an INTEROP_SUB_* overflow policy into the qos of
+ * its Subscribe gets a bounded queue of its own for that publisher. The
+ * bridge hands it one message at a time; what waits is dropped, conflated
+ * by type and symbol, or makes the publisher's rust_actor_send() wait,
+ * per subscriber:
+ *
+ *   Subscribe sub;   // from rust_monitor
+ *   sub.qos = INTEROP_QOS_BEST_EFFORT | INTEROP_SUB_CONFLATE | INTEROP_SUB_CAPACITY(64);
+ *
+ *   uint64_t lost = rust_subscriber_dropped("cpp_feed", "rust_monitor");
+ *
+ * The flags are in interop_messages.h. Messages sent with
+ * rust_actor_fast_send() bypass the queue.
+ */
+
+#ifndef INTEROP_SUB_QUEUE_H
+#define INTEROP_SUB_QUEUE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Messages from publisher_name dropped or replaced by the overflow policy
+ * of subscriber_name's queue (0 without one or on a bad name) */
+INTEROP_API uint64_t rust_subscriber_dropped(const char* publisher_name, const char* subscriber_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_SUB_QUEUE_H */
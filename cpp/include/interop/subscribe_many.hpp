@@ -0,0 +1,62 @@
+/*
+ * Topic lists for SubscribeMany / UnsubscribeMany
+ *
+ * Mirrors subscribe_many in Rust: each name is at most 31 bytes (as in
+ * Subscribe.topic) and followed by a NUL.
+ *
+ *   auto* sub = new msg::SubscribeMany();
+ *   if (!interop::topic_list(sub->topics, symbols)) {
+ *       return;  // a name too long for Subscribe.topic; nothing subscribed
+ *   }
+ *   sub->qos = QOS_BEST_EFFORT;
+ *   publisher.send(sub, this);
+ *
+ *   void on_subscribe_many(const msg::SubscribeMany* m) noexcept {
+ *       for (auto& topic : interop::topics_of(m->topics)) start(topic);
+ *   }
+ */
+
+#pragma once
+
+#include <cstdint>
+#include <string>
+#include <string_view>
+#include <vector>
+
+namespace interop {
+
+// Longest topic name, as in Subscribe.topic
+constexpr size_t kTopicMax = 31;
+
+// Fill `out` with `topics`. Returns false, leaving `out` empty, if a name
+// is empty, too long or holds a NUL.
+template <typename Topics>
+bool topic_list(std::vector<uint8_t>& out, const Topics& topics) {
+    out.clear();
+    for (std::string_view t : topics) {
+        if (t.empty() || t.size() > kTopicMax || t.find('\0') != std::string_view::npos) {
+            out.clear();
+            return false;
+        }
+        out.insert(out.end(), t.begin(), t.end());
+        out.push_back(0);
+    }
+    return true;
+}
+
+// The names in topic list `list`; names that are too long are skipped
+inline std::vector<std::string> topics_of(const std::vector<uint8_t>& list) {
+    std::vector<std::string> topics;
+    size_t start = 0;
+    for (size_t i = 0; i <= list.size(); ++i) {
+        if (i < list.size() && list[i] != 0) continue;
+        size_t len = i - start;
+        if (len > 0 && len <= kTopicMax) {
+            topics.emplace_back(reinterpret_cast<const char*>(list.data()) + start, len);
+        }
+        start = i + 1;
+    }
+    return topics;
+}
+
+} // namespace interop
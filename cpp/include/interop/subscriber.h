@@ -0,0 +1,35 @@
+/*
+ * Changing what a Rust subscriber watches from C++
+ *
+ * A Rust actor implementing DynamicSubscriber (rust/src/subscriber.rs)
+ * handles SubscribeRequest / UnsubscribeRequest by subscribing to or
+ * unsubscribing from its default publisher through the broker:
+ *
+ *   rust_subscriber_subscribe("rust_price_monitor", "MSFT");
+ *   rust_subscriber_unsubscribe("rust_price_monitor", "AAPL");
+ *
+ * To name the publisher or a qos, send SubscribeRequest directly.
+ * The change is applied on the actor's thread after the call returns.
+ */
+
+#ifndef INTEROP_SUBSCRIBER_H
+#define INTEROP_SUBSCRIBER_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Return rust_actor_send()'s result, or INTEROP_E_INVALID_ARGUMENT if topic
+ * is NULL, not UTF-8 or longer than 31 bytes */
+INTEROP_API int32_t rust_subscriber_subscribe(const char* actor_name, const char* topic);
+INTEROP_API int32_t rust_subscriber_unsubscribe(const char* actor_name, const char* topic);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_SUBSCRIBER_H */
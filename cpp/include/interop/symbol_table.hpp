@@ -0,0 +1,61 @@
+/*
+ * Symbol ids for CompactMarketUpdate (C++ subscribers)
+ *
+ * Mirrors symbols::SymbolTable in Rust. Subscribe with
+ * INTEROP_SUB_COMPACT or'd into qos, learn each SymbolDirectory entry the
+ * publisher sends, and expand compact updates where strings are wanted:
+ *
+ *   interop::SymbolTable symbols_;
+ *
+ *   void on_directory(const msg::SymbolDirectory* m) noexcept {
+ *       symbols_.learn(*m);
+ *   }
+ *
+ *   void on_compact(const msg::CompactMarketUpdate* m) noexcept {
+ *       msg::MarketUpdate update;
+ *       if (symbols_.expand(*m, update)) {
+ *           on_update(&update);
+ *       }
+ *   }
+ */
+
+#pragma once
+
+#include <array>
+#include <cstdint>
+#include <unordered_map>
+
+#include "InteropMessages.hpp"
+
+namespace interop {
+
+class SymbolTable {
+public:
+    // A repeated id replaces the old symbol
+    void learn(const msg::SymbolDirectory& entry) {
+        symbols_[entry.symbol_id] = entry.symbol;
+    }
+
+    // Returns nullptr if the id has not been announced
+    const char* symbol(uint32_t symbol_id) const {
+        auto it = symbols_.find(symbol_id);
+        return it == symbols_.end() ? nullptr : it->second.data();
+    }
+
+    // Fills `out` and returns true if the id has been announced
+    bool expand(const msg::CompactMarketUpdate& m, msg::MarketUpdate& out) const {
+        auto it = symbols_.find(m.symbol_id);
+        if (it == symbols_.end()) return false;
+        out.symbol = it->second;
+        out.price = m.price;
+        out.timestamp = m.timestamp;
+        out.volume = m.volume;
+        out.seq = m.seq;
+        return true;
+    }
+
+private:
+    std::unordered_map<uint32_t, std::array<char, 8>> symbols_;
+};
+
+}  // namespace interop
@@ -0,0 +1,73 @@
+/*
+ * Runtime events on well-known $sys/ topics
+ *
+ * Monitoring actors subscribe with the normal Subscribe message, sent to
+ * the reserved publisher "$sys", which resolves like a Rust actor, and
+ * receive SystemEvent messages.
+ *
+ * Usage:
+ *   auto* sub = new msg::Subscribe();
+ *   std::strncpy(sub->topic.data(), INTEROP_SYS_TOPIC_LIFECYCLE, sub->topic.size() - 1);
+ *   manager->get_ref(INTEROP_SYS_PUBLISHER).send(sub, this);
+ *
+ *   void on_event(const msg::SystemEvent* ev) noexcept {
+ *       if (ev->kind == INTEROP_SYS_PANICKED) { ... ev->subject ... }
+ *   }
+ *
+ * HeartbeatMissed is published once per silence longer than the peer
+ * timeout of health.h, and only while $sys/heartbeat has subscribers;
+ * HeartbeatResumed when heartbeats come back. Restarted is also published
+ * for C++ actors reported with interop_report_cpp_restart() (lifecycle.h).
+ * QueueHigh is published when a Rust actor's bridge queue depth reaches
+ * the high watermark (default 1000). DeadlineMissed is published for each
+ * handler that overran its deadline (deadlines.h).
+ */
+
+#ifndef INTEROP_SYS_TOPICS_H
+#define INTEROP_SYS_TOPICS_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#define INTEROP_SYS_PUBLISHER          "$sys"
+
+#define INTEROP_SYS_TOPIC_LIFECYCLE    "$sys/lifecycle"
+#define INTEROP_SYS_TOPIC_DEAD_LETTERS "$sys/dead_letters"
+#define INTEROP_SYS_TOPIC_HEARTBEAT    "$sys/heartbeat"
+#define INTEROP_SYS_TOPIC_QUEUE_DEPTH  "$sys/queue_depth"
+#define INTEROP_SYS_TOPIC_CIRCUIT      "$sys/circuit"
+#define INTEROP_SYS_TOPIC_SHUTDOWN     "$sys/shutdown"
+#define INTEROP_SYS_TOPIC_DEADLINES    "$sys/deadlines"
+#define INTEROP_SYS_TOPIC_CPP_RUNTIME  "$sys/cpp_runtime"
+
+/* SystemEvent.kind */
+#define INTEROP_SYS_STARTED          0   /* $sys/lifecycle, as INTEROP_LIFECYCLE_* */
+#define INTEROP_SYS_STOPPED          1
+#define INTEROP_SYS_PANICKED         2
+#define INTEROP_SYS_RESTARTED        3
+#define INTEROP_SYS_DEAD_LETTER      10  /* $sys/dead_letters; subject: missing target */
+#define INTEROP_SYS_INVALID_MESSAGE  11  /* $sys/dead_letters; subject: target, value: message ID */
+#define INTEROP_SYS_HEARTBEAT_MISSED 20  /* $sys/heartbeat; value: ms since the last one */
+#define INTEROP_SYS_HEARTBEAT_RESUMED 21 /* $sys/heartbeat; after HEARTBEAT_MISSED */
+#define INTEROP_SYS_QUEUE_HIGH       30  /* $sys/queue_depth; value: the depth */
+#define INTEROP_SYS_CIRCUIT_OPENED   40  /* $sys/circuit; subject: C++ target, value: failed sends */
+#define INTEROP_SYS_CIRCUIT_CLOSED   41  /* $sys/circuit; a probe got through */
+#define INTEROP_SYS_SHUTDOWN_REQUESTED 50  /* $sys/shutdown; subject: initiator */
+#define INTEROP_SYS_SHUTDOWN_READY     51  /* $sys/shutdown; subject: "rust" or "cpp" */
+#define INTEROP_SYS_DEADLINE_MISSED    60  /* $sys/deadlines; subject: Rust actor, value: handler ns */
+#define INTEROP_SYS_CPP_RUNTIME_DETACHED 70  /* $sys/cpp_runtime; subject: runtime ("linked" or attached name) */
+#define INTEROP_SYS_CPP_RUNTIME_ATTACHED 71  /* $sys/cpp_runtime; a detached runtime is back */
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Queue depth that publishes INTEROP_SYS_QUEUE_HIGH; 0 turns it off */
+INTEROP_API void interop_set_queue_high_watermark(uint32_t depth);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_SYS_TOPICS_H */
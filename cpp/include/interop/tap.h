@@ -0,0 +1,62 @@
+/*
+ * Wire taps: observe interop messages crossing the bridge
+ *
+ * The callback gets a copy of every matching message, on the sending
+ * thread, without the sender or target knowing. Patterns are actor names
+ * where '*' matches anything. msg_data points to the C struct (see
+ * interop_messages.h) for the duration of the call, or is NULL for
+ * messages with interop_bytes fields. Rust -> Rust traffic is not seen.
+ *
+ * Usage:
+ *   void on_tap(const char* sender, const char* target, int msg_type,
+ *               const void* msg_data, void* user_data) {
+ *       printf("%s -> %s: %d\n", sender, target, msg_type);
+ *   }
+ *
+ *   int64_t id = interop_tap("*", "rust_publisher", on_tap, NULL);
+ *   ...
+ *   interop_untap(id);
+ *
+ * To just see the traffic, interop_tap_print("*", "*") logs each message
+ * to stderr as JSON. interop_message_to_json() gives the same JSON for any
+ * C struct, for the host's own log statements:
+ *
+ *   char* json = interop_message_to_json(msg_type, msg_data);
+ *   if (json) { log(json); interop_free_json(json); }
+ */
+
+#ifndef INTEROP_TAP_H
+#define INTEROP_TAP_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef void (INTEROP_CALL *interop_tap_fn)(const char* sender_name, const char* target_name,
+                                            int msg_type, const void* msg_data, void* user_data);
+
+/* Returns a tap id, or INTEROP_E_INVALID_ARGUMENT on bad arguments */
+INTEROP_API int64_t interop_tap(const char* sender_pattern, const char* target_pattern,
+                                interop_tap_fn cb, void* user_data);
+
+/* Returns 0, or INTEROP_E_NOT_FOUND if id is not a registered tap */
+INTEROP_API int interop_untap(int64_t id);
+
+/* Tap that prints JSON to stderr. Returns a tap id, or
+ * INTEROP_E_INVALID_ARGUMENT on bad arguments */
+INTEROP_API int64_t interop_tap_print(const char* sender_pattern, const char* target_pattern);
+
+/* {"type":..,"msg_id":..,"fields":{..}}, or NULL for an unknown msg_type.
+ * Free with interop_free_json(). */
+INTEROP_API char* interop_message_to_json(int msg_type, const void* msg_data);
+INTEROP_API void interop_free_json(char* json);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_TAP_H */
@@ -0,0 +1,52 @@
+/*
+ * Periodic background tasks run by the Rust Manager
+ *
+ * Instead of starting a thread of its own that calls into the FFI, C++
+ * hands a periodic job (cache refresh, metrics flush) to the Rust side:
+ *
+ *   int32_t refresh_cache(void* user_data) {
+ *       static_cast<Cache*>(user_data)->refresh();   // may send to actors
+ *       return 0;                                     // nonzero: stop
+ *   }
+ *
+ *   int64_t task = rust_manager_spawn_task(refresh_cache, &cache, 5000);
+ *   ...
+ *   rust_manager_cancel_task(task);   // cache may be destroyed after this
+ *
+ * Each task runs on its own Rust-owned thread, first one interval after
+ * it is spawned. Runs are skipped while the Rust Manager is not running
+ * (before rust_manager_init(), during rust_manager_restart());
+ * rust_manager_end() cancels every task. Intervals follow the interop
+ * clock (see clock.h).
+ */
+
+#ifndef INTEROP_TASKS_H
+#define INTEROP_TASKS_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 0 to keep running, nonzero to stop */
+typedef int32_t (INTEROP_CALL *interop_task_fn)(void* user_data);
+
+/* Returns the task id (> 0), INTEROP_E_INVALID_ARGUMENT for a null task or
+ * an interval of 0, or INTEROP_E_QUEUE_FULL if no thread could be started.
+ * user_data must stay valid until
+ * the task stops or is cancelled. */
+INTEROP_API int64_t rust_manager_spawn_task(interop_task_fn task, void* user_data, uint32_t interval_ms);
+
+/* Waits for a run in progress to finish unless called from the task.
+ * Returns 0, or INTEROP_E_NOT_FOUND for an unknown or already finished
+ * task. */
+INTEROP_API int32_t rust_manager_cancel_task(int64_t task_id);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_TASKS_H */
@@ -0,0 +1,62 @@
+/*
+ * Tenant labels: several desks' actors in one host, kept apart
+ *
+ * Label actors (C++ or Rust, by name) with a tenant. A message crossing
+ * the bridge between actors of different tenants is refused like a
+ * policy denial (INTEROP_POLICY_DENIED, audit callback) unless a tenant
+ * rule allows it; so is a Rust -> Rust message between tenants. Unlabelled
+ * actors and non-actor C++ code are shared, or with INTEROP_TENANT_ISOLATED
+ * a tenant of their own, named "" in rules. Configure before
+ * rust_manager_init(); interop_policy_seal() also seals the labels and
+ * rules:
+ *
+ *   interop_set_tenant("rust_rates_strategy", "rates");
+ *   interop_set_tenant("rust_fx_strategy", "fx");
+ *   interop_tenant_allow("rates", "fx", 1012);    // MarketUpdate rates -> fx
+ *   interop_policy_seal();
+ *
+ * interop_tenant_view() narrows what the introspection calls made on the
+ * calling thread show (rust_interop_health, rust_dump_subscriptions,
+ * interop_dead_letters_json, interop_capture_json) to one tenant's actors:
+ *
+ *   interop_tenant_view("fx");
+ *   int n = rust_interop_health(buf, sizeof(buf));
+ *   interop_tenant_view(NULL);
+ */
+
+#ifndef INTEROP_TENANT_H
+#define INTEROP_TENANT_H
+
+#include "export.h"
+#include "policy.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Unlabelled actors and senders */
+#define INTEROP_TENANT_SHARED   0  /* talk to every tenant (default) */
+#define INTEROP_TENANT_ISOLATED 1  /* tenant "", needs rules like any other */
+
+/* NULL tenant removes the label. Return 0, INTEROP_E_INVALID_ARGUMENT on a
+ * NULL actor or non-UTF-8 name, or INTEROP_E_POLICY_DENIED once sealed. */
+INTEROP_API int interop_set_tenant(const char* actor, const char* tenant);
+
+/* One way; NULL tenants and INTEROP_POLICY_ANY_MSG are wildcards.
+ * Return 0, INTEROP_E_INVALID_ARGUMENT on a non-UTF-8 name, or
+ * INTEROP_E_POLICY_DENIED once sealed. */
+INTEROP_API int interop_tenant_allow(const char* from_tenant, const char* to_tenant, int msg_id);
+
+/* INTEROP_TENANT_SHARED or INTEROP_TENANT_ISOLATED. Return 0,
+ * INTEROP_E_INVALID_ARGUMENT for another value or INTEROP_E_POLICY_DENIED
+ * once sealed. */
+INTEROP_API int interop_tenant_set_unlabelled(int unlabelled);
+
+/* Calling thread only; NULL shows everything */
+INTEROP_API int interop_tenant_view(const char* tenant);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_TENANT_H */
@@ -0,0 +1,53 @@
+/*
+ * CPU time and context switches of Rust actor threads
+ *
+ * The C++ host and the Rust actors share one process. Each Rust actor
+ * records its thread when it starts, and the kernel's counters for it are
+ * read from /proc (Linux only), so CPU use can be split between Rust
+ * actors and C++ threads:
+ *
+ *   RustThreadUsage strategy, rust, all;
+ *   if (rust_actor_thread_usage("rust_strategy", &strategy) == 0)
+ *       report("rust_strategy", strategy.user_ns + strategy.system_ns);
+ *   rust_thread_usage_totals(&rust, &all);
+ *   uint64_t cpp_ns = (all.user_ns + all.system_ns) - (rust.user_ns + rust.system_ns);
+ *
+ * Times have the kernel's tick resolution (10 ms). Actors sharing a
+ * thread report that thread each; the totals count it once.
+ */
+
+#ifndef INTEROP_THREAD_USAGE_H
+#define INTEROP_THREAD_USAGE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RustThreadUsage {
+    uint64_t user_ns;
+    uint64_t system_ns;
+    uint64_t voluntary_switches;    /* the thread blocked */
+    uint64_t involuntary_switches;  /* the scheduler preempted it */
+    uint32_t tid;                   /* kernel thread id; 0 for totals */
+} RustThreadUsage;
+
+/* Fill out with the counters of the thread actor_name runs on. Returns 0,
+ * INTEROP_E_INVALID_ARGUMENT on a bad name or null out, or
+ * INTEROP_E_NOT_FOUND if the actor has not started or the counters cannot
+ * be read. */
+INTEROP_API int rust_actor_thread_usage(const char* actor_name, RustThreadUsage* out);
+
+/* Fill rust_actors (if non-null) with the counters summed over Rust actor
+ * threads and process (if non-null) with those of the whole process.
+ * Returns 0, or INTEROP_E_NOT_FOUND if the counters cannot be read. */
+INTEROP_API int rust_thread_usage_totals(RustThreadUsage* rust_actors, RustThreadUsage* process);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_THREAD_USAGE_H */
@@ -0,0 +1,61 @@
+/*
+ * Throttled publishing through the Rust broker
+ *
+ * A C++ feed handler producing quotes faster than subscribers want them
+ * hands each one to a throttle, which publishes every topic at most
+ * max_per_sec times a second and keeps only the latest update of a
+ * topic in between:
+ *
+ *   int64_t quotes = interop_throttle_new("rust_publisher", 10);
+ *   MarketUpdate c_msg;
+ *   while (next_quote(&c_msg)) {
+ *       interop_throttle_update(quotes, "AAPL", MarketUpdate::ID, &c_msg);
+ *   }
+ *   interop_throttle_flush(quotes);   // send what is still held
+ *   interop_throttle_free(quotes);
+ *
+ * The first update of a topic after a quiet interval is published at
+ * once; the last one always goes out, at the end of its interval.
+ * Updates are published to the subscribers the Rust broker holds for
+ * the publisher name given, in either language.
+ */
+
+#ifndef INTEROP_THROTTLE_H
+#define INTEROP_THROTTLE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* max_per_sec of 0 publishes every update. Returns the throttle id (> 0),
+ * or INTEROP_E_INVALID_ARGUMENT on a bad name. */
+INTEROP_API int64_t interop_throttle_new(const char* publisher, uint32_t max_per_sec);
+
+/* Make msg_data the latest update of topic. Returns 0,
+ * INTEROP_E_NOT_FOUND on an unknown throttle, INTEROP_E_INVALID_ARGUMENT
+ * on a bad topic or NULL msg_data, INTEROP_E_UNKNOWN_MESSAGE on an unknown
+ * message, or INTEROP_E_WRONG_TYPE if the topic carries another message
+ * type (see topic.h). */
+INTEROP_API int interop_throttle_update(int64_t throttle, const char* topic, int msg_id, const void* msg_data);
+
+/* Publish the updates still held now. Returns 0, or INTEROP_E_NOT_FOUND
+ * on an unknown throttle. */
+INTEROP_API int interop_throttle_flush(int64_t throttle);
+
+/* Either pointer may be NULL. Returns 0, or INTEROP_E_NOT_FOUND on an
+ * unknown throttle. */
+INTEROP_API int interop_throttle_stats(int64_t throttle, uint64_t* published, uint64_t* superseded);
+
+/* Updates still held are dropped. Returns 0, or INTEROP_E_NOT_FOUND on
+ * an unknown throttle. */
+INTEROP_API int interop_throttle_free(int64_t throttle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_THROTTLE_H */
@@ -0,0 +1,38 @@
+/*
+ * Typed topics shared with Rust's Topic<M>
+ *
+ * A topic may be declared as carrying one message type (by message ID).
+ * Rust subscribers declare it with Topic::<MarketUpdate>::new("AAPL"); a
+ * C++ publisher checks before sending, or declares what it produces:
+ *
+ *   interop_declare_topic("AAPL", MarketUpdate::ID);  // INTEROP_E_WRONG_TYPE: declared otherwise
+ *
+ *   if (interop_check_topic(topic.c_str(), MarketUpdate::ID) == 0) {
+ *       subscriber.send(update);
+ *   }
+ *
+ * Undeclared topics accept any message.
+ */
+
+#ifndef INTEROP_TOPIC_H
+#define INTEROP_TOPIC_H
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns 0, INTEROP_E_WRONG_TYPE if topic is declared with another message
+ * type, or INTEROP_E_INVALID_ARGUMENT on a bad topic */
+INTEROP_API int interop_declare_topic(const char* topic, int msg_id);
+
+/* Returns 0 if msg_id may be published on topic, INTEROP_E_WRONG_TYPE if
+ * not, or INTEROP_E_INVALID_ARGUMENT on a bad topic */
+INTEROP_API int interop_check_topic(const char* topic, int msg_id);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_TOPIC_H */
@@ -0,0 +1,35 @@
+/*
+ * Tracing spans from C++ (Rust feature `tracing`)
+ *
+ * Marks a C++ section so profilers show it as the parent of the FFI sends
+ * made inside it and alongside the Rust handlers they run:
+ *
+ *   uint64_t span = rust_trace_begin("on_quote");
+ *   ...
+ *   rust_trace_end(span);   // on the same thread
+ *
+ * Without the feature both functions do nothing.
+ */
+
+#ifndef INTEROP_TRACE_H
+#define INTEROP_TRACE_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns the span id, or 0 if tracing is off */
+INTEROP_API uint64_t rust_trace_begin(const char* name);
+
+/* Returns 0, or INTEROP_E_NOT_FOUND if span_id is not open on this thread */
+INTEROP_API int rust_trace_end(uint64_t span_id);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_TRACE_H */
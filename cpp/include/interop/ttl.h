@@ -0,0 +1,39 @@
+/*
+ * Drop stale messages queued to a Rust actor
+ *
+ * With a TTL set, the bridge stamps a deadline on each message it queues
+ * for the actor. A message still in the mailbox after its deadline (e.g.
+ * a MarketUpdate behind a backlog) is dropped when dequeued instead of
+ * being handled, and counted:
+ *
+ *   rust_actor_set_ttl("rust_strategy", 1012, 50);   // MarketUpdate: 50 ms
+ *   rust_actor_set_ttl("rust_strategy", 0, 5000);    // every other type
+ *   uint64_t stale = rust_actor_expired_count("rust_strategy");
+ *
+ * Applies to messages sent through the bridge; time is the interop clock
+ * (see clock.h).
+ */
+
+#ifndef INTEROP_TTL_H
+#define INTEROP_TTL_H
+
+#include <stdint.h>
+
+#include "export.h"
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* msg_type 0: every type without a TTL of its own. ttl_ms 0 removes the
+ * TTL. Returns 0, or INTEROP_E_INVALID_ARGUMENT on a bad name. */
+INTEROP_API int rust_actor_set_ttl(const char* actor_name, int32_t msg_type, uint32_t ttl_ms);
+
+/* Messages dropped unhandled because they expired (0 on a bad name) */
+INTEROP_API uint64_t rust_actor_expired_count(const char* actor_name);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* INTEROP_TTL_H */
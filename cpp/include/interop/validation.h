@@ -0,0 +1,24 @@
+/*
+ * Field rules checked on messages sent to Rust
+ *
+ * Fields in interop_messages.h (or a .proto schema) can declare rules:
+ *
+ *   int32_t num_levels;  / * valid: >= 0, <= len(bid_prices) * /
+ *   double price;        / * valid: > 0 * /
+ *   char topic[32];      / * valid: utf8, nonempty * /
+ *
+ * The Rust bridge checks them on receipt. A message that breaks one never
+ * reaches the handler: rust_actor_send() and rust_actor_fast_send() return
+ * INTEROP_SEND_INVALID_MESSAGE (INTEROP_POST_INVALID_MESSAGE,
+ * INTEROP_ASK_INVALID_MESSAGE), and it is published on $sys/dead_letters
+ * as INTEROP_SYS_INVALID_MESSAGE (see sys_topics.h).
+ */
+
+#ifndef INTEROP_VALIDATION_H
+#define INTEROP_VALIDATION_H
+
+#include "interop_errors.h"
+
+#define INTEROP_SEND_INVALID_MESSAGE INTEROP_E_INVALID_MESSAGE
+
+#endif /* INTEROP_VALIDATION_H */
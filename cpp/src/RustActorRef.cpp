@@ -7,6 +7,10 @@
 
 #include "actors/ActorRef.hpp"
 #include "InteropMessages.hpp"
+#include "actor_ids.h"
+
+#include <string>
+#include <unordered_map>
 
 // Forward declare the Rust bridge function
 extern "C" {
@@ -18,51 +22,208 @@ extern "C" {
     );
 }
 
+namespace {
+
+// Rust actor ids by target name (see actor_ids.h). One entry per Rust
+// actor this thread has sent to, whatever refs come and go; an id gone
+// stale by a restart or re-registration is looked up again.
+thread_local std::unordered_map<std::string, uint32_t> rust_ids;
+
+// rust_actor_send() by id, falling back to the name if the id is stale
+int32_t send_to_rust(const std::string& target, const char* sender_name, int32_t msg_type, const void* c_msg) {
+    uint32_t& id = rust_ids[target];
+    if (id == 0) {
+        id = rust_actor_lookup_id(target.c_str());
+    }
+    if (id != 0) {
+        int32_t rc = rust_actor_send_id(id, sender_name, msg_type, c_msg);
+        if (rc != INTEROP_STALE_ACTOR_ID) return rc;
+        id = 0;
+    }
+    return rust_actor_send(target.c_str(), sender_name, msg_type, c_msg);
+}
+
+} // anonymous namespace
+
 namespace actors {
 
-void RustActorRef::send(const Message* m, [[maybe_unused]] Actor* sender) {
-    const char* sender_name_cstr = sender_name_.empty() ? nullptr : sender_name_.c_str();
+void RustActorRef::send(const Message* m, Actor* sender) {
+    // Prefer the sender fixed at lookup, else the sending actor, so the
+    // Rust side can ctx.reply() to whoever sent this
+    const char* sender_name_cstr = !sender_name_.empty() ? sender_name_.c_str()
+        : (sender && sender->name[0] != '\0') ? sender->name
+        : nullptr;
 
     // Dispatch by message ID
     switch (m->get_message_id()) {
         case 1000: {  // Ping
             auto c_msg = static_cast<const msg::Ping*>(m)->to_c_struct();
-            rust_actor_send(target_name_.c_str(), sender_name_cstr, 1000, &c_msg);
+            send_to_rust(target_name_, sender_name_cstr, 1000, &c_msg);
             break;
         }
         case 1001: {  // Pong
             auto c_msg = static_cast<const msg::Pong*>(m)->to_c_struct();
-            rust_actor_send(target_name_.c_str(), sender_name_cstr, 1001, &c_msg);
+            send_to_rust(target_name_, sender_name_cstr, 1001, &c_msg);
             break;
         }
         case 1002: {  // DataRequest
             auto c_msg = static_cast<const msg::DataRequest*>(m)->to_c_struct();
-            rust_actor_send(target_name_.c_str(), sender_name_cstr, 1002, &c_msg);
+            send_to_rust(target_name_, sender_name_cstr, 1002, &c_msg);
             break;
         }
         case 1003: {  // DataResponse
             auto c_msg = static_cast<const msg::DataResponse*>(m)->to_c_struct();
-            rust_actor_send(target_name_.c_str(), sender_name_cstr, 1003, &c_msg);
+            send_to_rust(target_name_, sender_name_cstr, 1003, &c_msg);
             break;
         }
         case 1010: {  // Subscribe
             auto c_msg = static_cast<const msg::Subscribe*>(m)->to_c_struct();
-            rust_actor_send(target_name_.c_str(), sender_name_cstr, 1010, &c_msg);
+            send_to_rust(target_name_, sender_name_cstr, 1010, &c_msg);
             break;
         }
         case 1011: {  // Unsubscribe
             auto c_msg = static_cast<const msg::Unsubscribe*>(m)->to_c_struct();
-            rust_actor_send(target_name_.c_str(), sender_name_cstr, 1011, &c_msg);
+            send_to_rust(target_name_, sender_name_cstr, 1011, &c_msg);
             break;
         }
         case 1012: {  // MarketUpdate
             auto c_msg = static_cast<const msg::MarketUpdate*>(m)->to_c_struct();
-            rust_actor_send(target_name_.c_str(), sender_name_cstr, 1012, &c_msg);
+            send_to_rust(target_name_, sender_name_cstr, 1012, &c_msg);
             break;
         }
         case 1013: {  // MarketDepth
             auto c_msg = static_cast<const msg::MarketDepth*>(m)->to_c_struct();
-            rust_actor_send(target_name_.c_str(), sender_name_cstr, 1013, &c_msg);
+            send_to_rust(target_name_, sender_name_cstr, 1013, &c_msg);
+            break;
+        }
+        case 1014: {  // TopicAck
+            auto c_msg = static_cast<const msg::TopicAck*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1014, &c_msg);
+            break;
+        }
+        case 1015: {  // GapDetected
+            auto c_msg = static_cast<const msg::GapDetected*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1015, &c_msg);
+            break;
+        }
+        case 1016: {  // SymbolDirectory
+            auto c_msg = static_cast<const msg::SymbolDirectory*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1016, &c_msg);
+            break;
+        }
+        case 1017: {  // CompactMarketUpdate
+            auto c_msg = static_cast<const msg::CompactMarketUpdate*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1017, &c_msg);
+            break;
+        }
+        case 1018: {  // LeaseRenew
+            auto c_msg = static_cast<const msg::LeaseRenew*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1018, &c_msg);
+            break;
+        }
+        case 1019: {  // BondQuote
+            auto c_msg = static_cast<const msg::BondQuote*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1019, &c_msg);
+            break;
+        }
+        case 1020: {  // RiskLimits
+            auto c_msg = static_cast<const msg::RiskLimits*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1020, &c_msg);
+            break;
+        }
+        case 1021: {  // NewOrder
+            auto c_msg = static_cast<const msg::NewOrder*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1021, &c_msg);
+            break;
+        }
+        case 1022: {  // OrderAck
+            auto c_msg = static_cast<const msg::OrderAck*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1022, &c_msg);
+            break;
+        }
+        case 1030: {  // RawPayload
+            auto c_msg = static_cast<const msg::RawPayload*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1030, &c_msg);
+            break;
+        }
+        case 1031: {  // PayloadChunk
+            auto c_msg = static_cast<const msg::PayloadChunk*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1031, &c_msg);
+            break;
+        }
+        case 1040: {  // StreamOpen
+            auto c_msg = static_cast<const msg::StreamOpen*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1040, &c_msg);
+            break;
+        }
+        case 1041: {  // StreamChunk
+            auto c_msg = static_cast<const msg::StreamChunk*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1041, &c_msg);
+            break;
+        }
+        case 1042: {  // StreamCredit
+            auto c_msg = static_cast<const msg::StreamCredit*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1042, &c_msg);
+            break;
+        }
+        case 1043: {  // StreamEnd
+            auto c_msg = static_cast<const msg::StreamEnd*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1043, &c_msg);
+            break;
+        }
+        case 1050: {  // SystemEvent
+            auto c_msg = static_cast<const msg::SystemEvent*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1050, &c_msg);
+            break;
+        }
+        case 1051: {  // SubscribeRequest
+            auto c_msg = static_cast<const msg::SubscribeRequest*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1051, &c_msg);
+            break;
+        }
+        case 1052: {  // UnsubscribeRequest
+            auto c_msg = static_cast<const msg::UnsubscribeRequest*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1052, &c_msg);
+            break;
+        }
+        case 1053: {  // InteropError
+            auto c_msg = static_cast<const msg::InteropError*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1053, &c_msg);
+            break;
+        }
+        case 1054: {  // AskCancel
+            auto c_msg = static_cast<const msg::AskCancel*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1054, &c_msg);
+            break;
+        }
+        case 1055: {  // SubscribeMany
+            auto c_msg = static_cast<const msg::SubscribeMany*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1055, &c_msg);
+            break;
+        }
+        case 1056: {  // UnsubscribeMany
+            auto c_msg = static_cast<const msg::UnsubscribeMany*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1056, &c_msg);
+            break;
+        }
+        case 1057: {  // ConfigSet
+            auto c_msg = static_cast<const msg::ConfigSet*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1057, &c_msg);
+            break;
+        }
+        case 1058: {  // ConfigChanged
+            auto c_msg = static_cast<const msg::ConfigChanged*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1058, &c_msg);
+            break;
+        }
+        case 1060: {  // LatencyProbe
+            auto c_msg = static_cast<const msg::LatencyProbe*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1060, &c_msg);
+            break;
+        }
+        case 1061: {  // LatencyEcho
+            auto c_msg = static_cast<const msg::LatencyEcho*>(m)->to_c_struct();
+            send_to_rust(target_name_, sender_name_cstr, 1061, &c_msg);
             break;
         }
         default:
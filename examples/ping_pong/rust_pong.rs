@@ -1,16 +1,17 @@
 //! Rust PongActor for cross-language ping-pong
 //!
-//! Receives Ping from C++ PingActor, sends Pong back via FFI.
+//! Receives Ping from any actor and replies with Pong.
+//! The bridge passes the sender along, so ctx.reply() reaches whoever
+//! pinged - C++ or Rust - without this actor knowing its peer.
 //! Uses the standard Actor trait with handle_messages! macro.
 
 use actors::{handle_messages, ActorContext, ManagerHandle};
 use crate::interop_messages::{Ping, Pong};
-use crate::cpp_actor_if::CppActorIF;
+use crate::registry::interop_actor;
 
-/// Rust Pong Actor - receives Ping, sends Pong back
+/// Rust Pong Actor - receives Ping, replies with Pong
+#[interop_actor("rust_pong")]
 pub struct RustPongActor {
-    /// Interface to send back to C++ actors
-    cpp_ping: CppActorIF,
     #[allow(dead_code)]
     manager_handle: ManagerHandle,
 }
@@ -18,18 +19,17 @@ pub struct RustPongActor {
 impl RustPongActor {
     pub fn new(manager_handle: ManagerHandle) -> Self {
         RustPongActor {
-            cpp_ping: CppActorIF::new("cpp_ping", Some("rust_pong")),
             manager_handle,
         }
     }
 
-    fn on_ping(&mut self, msg: &Ping, _ctx: &mut ActorContext) {
+    fn on_ping(&mut self, msg: &Ping, ctx: &mut ActorContext) {
         println!("[Rust Pong] Received Ping #{}", msg.count);
 
-        // Send Pong back to C++
+        // Reply to the sender - location transparent!
         let pong = Pong { count: msg.count };
-        println!("[Rust Pong] Sending Pong #{} back to C++...", pong.count);
-        self.cpp_ping.send(&pong);
+        println!("[Rust Pong] Replying with Pong #{}...", pong.count);
+        ctx.reply(Box::new(pong));
     }
 }
 
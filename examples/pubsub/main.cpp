@@ -8,7 +8,8 @@
  *
  * Flow:
  * - C++ MarketSubscriber sends Subscribe("AAPL") to Rust RustPublisher
- * - Rust stores subscription and sends 3 MarketUpdate via reply()
+ * - Rust stores subscription and publishes 3 MarketUpdate, throttled to
+ *   10 a second per topic: the first and the latest arrive
  * - C++ receives updates and prints them
  */
 
@@ -33,7 +34,7 @@ extern "C" {
     void rust_manager_end();
     void rust_actor_init(const void* mgr);
     void rust_actor_shutdown();
-    void init_cpp_actor_lookup();  // Register C++ actor lookup for Rust
+    int init_cpp_actor_lookup();   // Register C++ actor lookup for Rust (-1: C++ side incomplete)
 }
 
 /**
@@ -85,7 +86,8 @@ public:
              << " @ $" << m->price
              << " vol=" << m->volume << endl;
 
-        if (update_count_ >= 3) {
+        // The last update; throttling may supersede the ones before it
+        if (m->volume >= 300) {
             cout << "[C++ Subscriber] Received all updates, done!" << endl;
             manager_->terminate();
         }
@@ -124,7 +126,10 @@ int main() {
     rust_actor_init(rust_mgr);
 
     // 6. Register C++ actor lookup so Rust can find C++ actors
-    init_cpp_actor_lookup();
+    //    (fails if this C++ build lacks messages Rust knows)
+    if (init_cpp_actor_lookup() != 0) {
+        return 1;
+    }
 
     cout << "[Main] Starting actors..." << endl;
     cout << endl;
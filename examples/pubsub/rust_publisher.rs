@@ -1,22 +1,57 @@
 //! Rust Publisher for pub/sub example
 //!
-//! Receives Subscribe from subscribers, sends MarketUpdates back.
-//! Uses ActorRef for location transparency - doesn't know if subscribers are C++ or Rust.
+//! Receives Subscribe from subscribers, sends MarketUpdates back, or a
+//! SymbolDirectory entry and CompactMarketUpdates if the subscriber asked
+//! for compact updates. Updates go out through a ThrottledPublisher, at
+//! most MAX_UPDATES_PER_SEC per topic with the latest one winning; reliable
+//! topics get every update, acknowledged and resent by the broker (see
+//! reliable). With subscription leases on, topics whose lease ran out
+//! (subscriber gone without unsubscribing) are dropped.
+//! Publishes through the broker, so it never names its subscribers and
+//! doesn't know if they are C++ or Rust.
 //!
 //! Uses the standard Actor trait with handle_messages! macro.
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 
-use actors::{handle_messages, ActorContext, ActorRef, ManagerHandle};
+use actors::{handle_messages, ActorContext, ManagerHandle, Message};
 use actors::messages::Start;
-use crate::interop_messages::{Subscribe, MarketUpdate};
-use crate::rust_manager_ffi::get_actor_ref;
+use crate::broker::{self, Subscription};
+use crate::interop_messages::{Subscribe, MarketUpdate, TopicAck, LeaseRenew, Timestamp};
+use crate::lease::LeaseTable;
+use crate::qos::Qos;
+use crate::symbols::{wants_compact, SymbolInterner};
+use crate::registry::interop_actor;
+use crate::throttle::ThrottledPublisher;
+
+/// This actor's name, which subscriptions are recorded under
+const NAME: &str = "rust_publisher";
+
+/// Max updates per second per best-effort topic
+const MAX_UPDATES_PER_SEC: u32 = 10;
+
+/// The update as `sub` asked for it: compact or full
+fn for_subscriber(symbols: &Mutex<SymbolInterner>, sub: &Subscription, update: &MarketUpdate) -> Box<dyn Message> {
+    if wants_compact(sub.qos) {
+        Box::new(symbols.lock().unwrap().compact(update))
+    } else {
+        Box::new(update.clone())
+    }
+}
 
+#[interop_actor("rust_publisher")]
 pub struct RustPublisher {
-    // ActorRef to subscriber - location transparent!
-    cpp_subscriber: Option<ActorRef>,
     // Track subscribed topics
     topics: Vec<String>,
+    // Topics subscribed with reliable QoS
+    reliable: HashSet<String>,
+    // Symbol ids for compact subscribers, shared with the throttle's flusher
+    symbols: Arc<Mutex<SymbolInterner>>,
+    // Best-effort topics, downsampled
+    quotes: ThrottledPublisher,
+    // Subscription leases by topic
+    leases: LeaseTable<String>,
     // Count of updates sent (for demo purposes)
     update_count: i32,
     #[allow(dead_code)]
@@ -25,72 +60,106 @@ pub struct RustPublisher {
 
 impl RustPublisher {
     pub fn new(manager_handle: ManagerHandle) -> Self {
+        let symbols = Arc::new(Mutex::new(SymbolInterner::new()));
+        let shared = symbols.clone();
+        let quotes = ThrottledPublisher::with_build(NAME, MAX_UPDATES_PER_SEC, move |sub, latest| {
+            let update = latest.as_any().downcast_ref::<MarketUpdate>()?;
+            Some(for_subscriber(&shared, sub, update))
+        });
         RustPublisher {
-            // Will be looked up on first use
-            cpp_subscriber: None,
             topics: Vec::new(),
+            reliable: HashSet::new(),
+            symbols,
+            quotes,
+            leases: LeaseTable::new(),
             update_count: 0,
             manager_handle,
         }
     }
 
-    /// Get the subscriber ActorRef, looking it up if needed
-    fn get_subscriber(&mut self) -> Option<ActorRef> {
-        if self.cpp_subscriber.is_none() {
-            // Look up by name - works for C++ or Rust subscribers!
-            self.cpp_subscriber = get_actor_ref("cpp_subscriber", "rust_publisher");
+    /// Forget topics whose subscriber stopped renewing
+    fn expire_leases(&mut self) {
+        for topic in self.leases.expire() {
+            println!("[Rust Publisher] Lease on '{}' expired, dropping subscription", topic);
+            self.topics.retain(|t| *t != topic);
+            self.reliable.remove(&topic);
         }
-        self.cpp_subscriber.clone()
     }
 
     fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
         println!("[Rust Publisher] Started");
     }
 
-    fn on_subscribe(&mut self, msg: &Subscribe, _ctx: &mut ActorContext) {
-        let topic = std::str::from_utf8(&msg.topic)
-            .unwrap_or("")
-            .trim_end_matches('\0')
-            .to_string();
+    fn on_subscribe(&mut self, msg: &Subscribe, ctx: &mut ActorContext) {
+        let topic = msg.topic.as_str().to_string();
 
-        println!("[Rust Publisher] Subscriber subscribing to '{}'", topic);
+        let qos = Qos::from_flag(msg.qos);
+        println!("[Rust Publisher] Subscriber subscribing to '{}' ({:?})", topic, qos);
 
+        self.expire_leases();
         if !self.topics.contains(&topic) {
             self.topics.push(topic.clone());
         }
+        self.leases.grant(topic.clone());
+        if qos == Qos::Reliable {
+            self.reliable.insert(topic.clone());
+        }
 
-        // Get subscriber ActorRef and send updates
-        let subscriber = self.get_subscriber();
+        // Compact subscribers learn the symbol id before the first update
+        if wants_compact(msg.qos) {
+            let entry = self.symbols.lock().unwrap().directory_entry(&topic);
+            println!("[Rust Publisher] {} is symbol #{} for compact updates", topic, entry.symbol_id);
+            ctx.reply(Box::new(entry));
+        }
 
-        // Send 3 updates via ActorRef - location transparent!
+        // Publish 3 updates to everyone subscribed to the topic
         for i in 0..3 {
             self.update_count += 1;
             let price = 150.0 + (i as f64 * 0.25);
 
-            let mut update = MarketUpdate {
-                symbol: [0u8; 8],
+            let update = MarketUpdate {
+                symbol: topic.as_str().into(),
                 price,
-                timestamp: SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64,
+                timestamp: Timestamp::now(),
                 volume: (i + 1) * 100,
+                seq: 0,  // stamped by the bridge when sequencing is on
             };
 
-            let bytes = topic.as_bytes();
-            update.symbol[..bytes.len().min(7)].copy_from_slice(&bytes[..bytes.len().min(7)]);
-
-            println!("[Rust Publisher] Sending update: {} @ ${:.2}", topic, price);
+            // Reliable topics get every update; the broker holds back
+            // when the subscriber falls too far behind
+            if self.reliable.contains(&topic) {
+                println!("[Rust Publisher] Sending update: {} @ ${:.2}", topic, price);
+                let symbols = &self.symbols;
+                broker::publish_with(NAME, &topic, |sub| Some(for_subscriber(symbols, sub, &update)));
+                continue;
+            }
 
-            if let Some(ref sub) = subscriber {
-                sub.send(Box::new(update), None);
+            // Others get the latest, at most MAX_UPDATES_PER_SEC
+            println!("[Rust Publisher] Updating: {} @ ${:.2}", topic, price);
+            if let Err(e) = self.quotes.update(&topic, Box::new(update)) {
+                println!("[Rust Publisher] Not published: {}", e);
             }
         }
     }
+
+    fn on_lease_renew(&mut self, msg: &LeaseRenew, _ctx: &mut ActorContext) {
+        let lease_ms = msg.lease_ms.max(0) as u32;
+        if !self.leases.renew(&msg.topic.as_str().to_string(), lease_ms) {
+            println!("[Rust Publisher] Renewal for '{}' after expiry, ignored", msg.topic.as_str());
+        }
+        self.expire_leases();
+    }
+
+    // The broker has already released what this acknowledges
+    fn on_topic_ack(&mut self, msg: &TopicAck, _ctx: &mut ActorContext) {
+        println!("[Rust Publisher] {} updates on '{}' acknowledged", msg.seq, msg.topic.as_str());
+    }
 }
 
 // Register message handlers
 handle_messages!(RustPublisher,
     Start => on_start,
-    Subscribe => on_subscribe
+    Subscribe => on_subscribe,
+    TopicAck => on_topic_ack,
+    LeaseRenew => on_lease_renew
 );
@@ -10,7 +10,8 @@
  * - Rust sends Ping #1 to C++
  * - C++ sends Pong #1 back to Rust
  * - Rust sends Ping #2, etc.
- * - After 3 rounds, Rust signals done
+ * - After 3 rounds, Rust requests shutdown; both sides drain and
+ *   confirm, then main ends the managers
  */
 
 #include <iostream>
@@ -36,7 +37,19 @@ extern "C" {
     // Rust actor bridge (from generated code)
     void rust_actor_init(const void* mgr);
     void rust_actor_shutdown();
-    void init_cpp_actor_lookup();  // Register C++ actor lookup for Rust
+    int init_cpp_actor_lookup();   // Register C++ actor lookup for Rust (-1: C++ side incomplete)
+
+    // Shutdown handshake
+    typedef void (*interop_shutdown_fn)(const char* initiator);
+    void interop_on_shutdown_requested(interop_shutdown_fn cb);
+    void interop_shutdown_cpp_ready();
+    int interop_shutdown_wait(uint32_t timeout_ms);
+}
+
+// cpp_pong only replies, so the C++ side has nothing left to drain
+static void on_shutdown_requested(const char* initiator) {
+    cout << "[C++] Shutdown requested by " << initiator << endl;
+    interop_shutdown_cpp_ready();
 }
 
 // C++ Pong Actor - receives Ping, uses reply() to send Pong back
@@ -85,17 +98,23 @@ int main() {
 
     // 4. Initialize Rust actor bridge with Rust Manager pointer
     rust_actor_init(rust_mgr);
+    interop_on_shutdown_requested(on_shutdown_requested);
 
-    // 5. Register C++ actor lookup so Rust can find C++ actors
-    init_cpp_actor_lookup();
-
-    // 6. Start both Managers (sends Start message to actors)
+    // 5. Start Rust; rust_ping declares requires = ["cpp_pong"], so its
+    //    Start waits until cpp_pong can be looked up
     cout << "[Main] Starting actors..." << endl << endl;
+    rust_manager_init();
+
+    // 6. Start C++ and register the C++ lookup, which releases rust_ping's Start
     cpp_mgr.init();       // C++ actors receive Start
-    rust_manager_init();  // Rust actors receive Start
+    if (init_cpp_actor_lookup() != 0) {
+        return 1;
+    }
 
-    // Wait for ping-pong to complete (3 rounds)
-    this_thread::sleep_for(chrono::milliseconds(500));
+    // Wait for ping-pong to complete (3 rounds) and both sides to drain
+    if (!interop_shutdown_wait(5000)) {
+        cout << "[Main] Shutdown handshake timed out" << endl;
+    }
 
     // Shutdown
     rust_manager_end();
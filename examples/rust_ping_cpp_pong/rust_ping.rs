@@ -10,10 +10,15 @@ use actors::{handle_messages, ActorContext, ActorRef, ManagerHandle};
 use actors::messages::Start;
 use crate::interop_messages::{Ping, Pong};
 use crate::rust_manager_ffi::get_actor_ref;
+use crate::registry::interop_actor;
+use crate::shutdown;
 
+// Start waits until cpp_pong is registered, so the first Ping is not lost
+#[interop_actor("rust_ping", requires = ["cpp_pong"])]
 pub struct RustPingActor {
     /// ActorRef to C++ pong actor - location transparent!
     cpp_pong: Option<ActorRef>,
+    #[allow(dead_code)]
     manager_handle: ManagerHandle,
 }
 
@@ -55,7 +60,8 @@ impl RustPingActor {
             }
         } else {
             println!("[Rust Ping] Ping-pong complete!");
-            self.manager_handle.terminate();
+            // Both managers drain and confirm before main ends them
+            shutdown::request("rust_ping");
         }
     }
 }
@@ -7,6 +7,7 @@
  * - Receiving Subscribe messages from any actor
  * - Storing subscriber ActorRefs for later updates
  * - The pub/sub pattern across language boundaries
+ * - Subscription leases: subscribers that stop renewing are dropped
  */
 
 #include <iostream>
@@ -19,6 +20,8 @@
 #include "actors/Actor.hpp"
 #include "actors/ActorRef.hpp"
 #include "InteropMessages.hpp"
+#include "lease.hpp"
+#include "strings.hpp"
 
 /// Subscriber info - stores ActorRef and subscribed topics
 struct SubscriberInfo {
@@ -32,6 +35,7 @@ public:
         // Register message handlers
         MESSAGE_HANDLER(msg::Subscribe, on_subscribe);
         MESSAGE_HANDLER(msg::Unsubscribe, on_unsubscribe);
+        MESSAGE_HANDLER(msg::LeaseRenew, on_lease_renew);
 
         // Initialize prices
         prices_["AAPL"] = 150.0;
@@ -50,6 +54,13 @@ public:
             price *= (1.0 + change);
         }
 
+        // Drop subscriptions whose subscriber stopped renewing
+        for (const auto& [sub_name, topic] : leases_.expire()) {
+            std::cout << "[C++ Publisher] Lease of " << sub_name << " on " << topic
+                      << " expired" << std::endl;
+            remove_topic(sub_name, topic);
+        }
+
         // Send to all subscribers
         for (const auto& [sub_name, sub_info] : subscribers_) {
             for (const auto& topic : sub_info.topics) {
@@ -64,6 +75,7 @@ public:
 private:
     std::unordered_map<std::string, SubscriberInfo> subscribers_;
     std::unordered_map<std::string, double> prices_;
+    interop::LeaseTable leases_;
 
     void on_subscribe(const msg::Subscribe* msg) noexcept {
         // Extract topic from fixed-size array
@@ -96,6 +108,7 @@ private:
                 == subscriber.topics.end()) {
             subscriber.topics.push_back(topic);
         }
+        leases_.grant(sender_name, topic);
 
         // Send initial price update
         auto price_it = prices_.find(topic);
@@ -116,6 +129,21 @@ private:
         std::cout << "[C++ Publisher] " << sender_name
                   << " unsubscribing from " << topic << std::endl;
 
+        leases_.revoke(sender_name, topic);
+        remove_topic(sender_name, topic);
+    }
+
+    void on_lease_renew(const msg::LeaseRenew* msg) noexcept {
+        std::string topic(msg->topic.data(),
+            std::find(msg->topic.begin(), msg->topic.end(), '\0'));
+
+        auto* sender = get_reply_to();
+        if (!sender) return;
+
+        leases_.renew(sender->get_name(), topic, msg->lease_ms);
+    }
+
+    void remove_topic(const std::string& sender_name, const std::string& topic) {
         auto it = subscribers_.find(sender_name);
         if (it != subscribers_.end()) {
             auto& topics = it->second.topics;
@@ -131,14 +159,13 @@ private:
                      const std::string& symbol, double price) {
         auto* update = new msg::MarketUpdate();
 
-        // Copy symbol to fixed-size array
-        std::fill(update->symbol.begin(), update->symbol.end(), '\0');
-        std::copy(symbol.begin(),
-                  symbol.begin() + std::min(symbol.size(), update->symbol.size() - 1),
-                  update->symbol.begin());
+        // Copy symbol to fixed-size array; a long one is cut and counted
+        if (!interop::set_str(update->symbol, symbol)) {
+            std::cerr << "[C++ Publisher] symbol " << symbol << " truncated" << std::endl;
+        }
 
         update->price = price;
-        update->timestamp = static_cast<int64_t>(std::time(nullptr)) * 1000;
+        update->timestamp = interop::Timestamp::now();
         update->volume = rand() % 10000;
 
         // Location-transparent send - works for C++ or Rust subscribers
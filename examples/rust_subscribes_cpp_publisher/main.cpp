@@ -37,6 +37,7 @@
 #include "InteropMessages.hpp"
 #include "InteropManager.hpp"
 #include "CppActorBridge.hpp"
+#include "strings.hpp"
 
 using namespace std;
 
@@ -52,7 +53,14 @@ extern "C" {
     // Rust actor bridge (from generated code)
     void rust_actor_init(const void* mgr);
     void rust_actor_shutdown();
-    void init_cpp_actor_lookup();  // Register C++ actor lookup for Rust
+    int init_cpp_actor_lookup();   // Register C++ actor lookup for Rust (-1: C++ side incomplete)
+
+    // Startup dependencies
+    int interop_require(const char* actor, const char* dependency);
+    int interop_dependencies_wait(uint32_t timeout_ms);
+
+    // Interop clock (virtual in a backtest)
+    void interop_clock_sleep_ns(uint64_t ns);
 }
 
 // Subscriber info - now uses ActorRef for location transparency
@@ -156,14 +164,14 @@ private:
 
     void send_update(SubscriberInfo& sub, const string& symbol, double price) {
         auto* update = new msg::MarketUpdate;
-        fill(update->symbol.begin(), update->symbol.end(), '\0');
-        copy(symbol.begin(),
-             symbol.begin() + min(symbol.size(), update->symbol.size() - 1),
-             update->symbol.begin());
+        if (!interop::set_str(update->symbol, symbol)) {
+            cerr << "[C++ Publisher] symbol " << symbol << " truncated" << endl;
+        }
 
         update->price = price;
-        update->timestamp = static_cast<int64_t>(time(nullptr)) * 1000;
+        update->timestamp = interop::Timestamp::now();
         update->volume = rand() % 10000;
+        update->seq = 0;  // stamped by the bridge when sequencing is on
 
         cout << "[C++ Publisher] Sending " << symbol << " @ $"
              << fixed << setprecision(2) << price << endl;
@@ -203,11 +211,18 @@ int main() {
     // 4. Register rust_price_monitor actor with Rust Manager
     void* rust_mgr = register_rust_subscriber();
 
+    //    It subscribes from its Start handler, so hold Start until the
+    //    C++ publisher is registered
+    interop_require("rust_price_monitor", "cpp_price_feed");
+
     // 5. Initialize Rust actor bridge with Manager pointer
     rust_actor_init(rust_mgr);
 
     // 6. Register C++ actor lookup so Rust can find C++ actors
-    init_cpp_actor_lookup();
+    //    (fails if this C++ build lacks messages Rust knows)
+    if (init_cpp_actor_lookup() != 0) {
+        return 1;
+    }
 
     cout << "[Main] Starting actors..." << endl;
     cout << endl;
@@ -218,6 +233,10 @@ int main() {
     // 7. Start Rust actors (sends Start message)
     //    RustSubscriber receives Start, sends Subscribe to C++
     rust_manager_init();
+    if (interop_dependencies_wait(5000) < 0) {
+        cerr << "[Main] cpp_price_feed never registered" << endl;
+        return 1;
+    }
 
     // Give Rust time to subscribe
     this_thread::sleep_for(chrono::milliseconds(100));
@@ -226,7 +245,7 @@ int main() {
     for (int i = 0; i < 3; i++) {
         cout << endl << "[Main] Publishing update round #" << (i + 1) << "..." << endl;
         cpp_mgr.publish();
-        this_thread::sleep_for(chrono::milliseconds(100));
+        interop_clock_sleep_ns(100 * 1000000);
     }
 
     cpp_mgr.end();
@@ -6,6 +6,7 @@
 //! - Using get_actor_ref() to look up actors by name
 //! - Receiving MarketUpdate messages from the publisher
 //! - The pub/sub pattern across language boundaries
+//! - Keeping subscriptions and the update count across restarts (Snapshot)
 //!
 //! Uses the standard Actor trait with handle_messages! macro.
 
@@ -13,9 +14,15 @@ use actors::{handle_messages, ActorContext, ActorRef, ManagerHandle};
 use actors::messages::Start;
 
 use crate::interop_messages::{Subscribe, Unsubscribe, MarketUpdate, MarketDepth};
+use crate::interop_string::{InteropString, TruncationPolicy};
+use crate::qos::QOS_BEST_EFFORT;
 use crate::rust_manager_ffi::get_actor_ref;
+use crate::sequencing::GapDetector;
+use crate::registry::interop_actor;
+use crate::snapshot::Snapshot;
 
 /// Price Monitor - subscribes to price feed and monitors updates
+#[interop_actor("rust_price_monitor", snapshot)]
 pub struct RustSubscriber {
     /// ActorRef to the publisher (could be C++ or Rust - we don't know or care!)
     publisher: Option<ActorRef>,
@@ -23,6 +30,8 @@ pub struct RustSubscriber {
     update_count: i32,
     /// Subscribed topics
     subscribed_topics: Vec<String>,
+    /// Missed updates on sequenced streams
+    gaps: GapDetector,
     #[allow(dead_code)]
     manager_handle: ManagerHandle,
 }
@@ -34,6 +43,7 @@ impl RustSubscriber {
             publisher: None,
             update_count: 0,
             subscribed_topics: Vec::new(),
+            gaps: GapDetector::new(),
             manager_handle,
         }
     }
@@ -48,6 +58,17 @@ impl RustSubscriber {
     }
 
     fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        if !self.subscribed_topics.is_empty() {
+            // Restarted with a snapshot: subscribe again to what we had
+            println!(
+                "[Rust Subscriber] Restored after {} updates, resubscribing to {:?}",
+                self.update_count, self.subscribed_topics
+            );
+            for topic in std::mem::take(&mut self.subscribed_topics) {
+                self.subscribe(&topic);
+            }
+            return;
+        }
         println!("[Rust Subscriber] Started, subscribing to AAPL and GOOG...");
         self.subscribe("AAPL");
         self.subscribe("GOOG");
@@ -57,14 +78,15 @@ impl RustSubscriber {
     pub fn subscribe(&mut self, symbol: &str) {
         println!("[Rust Subscriber] Subscribing to {}", symbol);
 
-        let mut sub = Subscribe {
-            topic: [0u8; 32],
+        // A topic that does not fit is refused, not cut to a prefix
+        let topic = match InteropString::with_policy(symbol, TruncationPolicy::Reject) {
+            Ok(topic) => topic,
+            Err(e) => {
+                eprintln!("[Rust Subscriber] cannot subscribe to {}: {}", symbol, e);
+                return;
+            }
         };
-
-        // Copy symbol to fixed-size array
-        let bytes = symbol.as_bytes();
-        let copy_len = bytes.len().min(31);
-        sub.topic[..copy_len].copy_from_slice(&bytes[..copy_len]);
+        let sub = Subscribe { topic, qos: QOS_BEST_EFFORT };
 
         // Send via ActorRef - location transparent!
         if let Some(publisher) = self.get_publisher() {
@@ -77,26 +99,25 @@ impl RustSubscriber {
     fn on_market_update(&mut self, msg: &MarketUpdate, _ctx: &mut ActorContext) {
         self.update_count += 1;
 
-        // Extract symbol from fixed-size array
-        let symbol = std::str::from_utf8(&msg.symbol)
-            .unwrap_or("")
-            .trim_end_matches('\0');
-
         println!(
             "[Rust Subscriber] Update #{}: {} @ {:.2} vol={} ts={}",
-            self.update_count, symbol, msg.price, msg.volume, msg.timestamp
+            self.update_count, msg.symbol, msg.price, msg.volume, msg.timestamp
         );
 
+        if let Some(gap) = self.gaps.check("cpp_price_feed", msg.symbol.as_str(), msg.seq) {
+            println!(
+                "[Rust Subscriber] Gap on {}: expected #{}, got #{} - snapshot needed",
+                gap.topic, gap.expected_seq, gap.received_seq
+            );
+        }
+
         // After 10 updates, unsubscribe from first topic
         if self.update_count == 10 && !self.subscribed_topics.is_empty() {
             let topic = self.subscribed_topics.remove(0);
 
-            let mut unsub = Unsubscribe {
-                topic: [0u8; 32],
+            let unsub = Unsubscribe {
+                topic: topic.as_str().into(),
             };
-            let bytes = topic.as_bytes();
-            let copy_len = bytes.len().min(31);
-            unsub.topic[..copy_len].copy_from_slice(&bytes[..copy_len]);
 
             if let Some(publisher) = self.get_publisher() {
                 publisher.send(Box::new(unsub), None);
@@ -107,11 +128,7 @@ impl RustSubscriber {
 
     /// Handle incoming MarketDepth message (demonstrates array handling)
     fn on_market_depth(&mut self, msg: &MarketDepth, _ctx: &mut ActorContext) {
-        let symbol = std::str::from_utf8(&msg.symbol)
-            .unwrap_or("")
-            .trim_end_matches('\0');
-
-        println!("[Rust Subscriber] Market Depth for {}:", symbol);
+        println!("[Rust Subscriber] Market Depth for {}:", msg.symbol);
         for i in 0..msg.num_levels as usize {
             println!(
                 "  Level {}: bid {:.2} x {} | ask {:.2} x {}",
@@ -125,6 +142,28 @@ impl RustSubscriber {
     }
 }
 
+/// Update count (4 bytes, little-endian) then the topics, one per line
+impl Snapshot for RustSubscriber {
+    fn snapshot(&self) -> Vec<u8> {
+        let mut state = self.update_count.to_le_bytes().to_vec();
+        state.extend_from_slice(self.subscribed_topics.join("\n").as_bytes());
+        state
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        if state.len() < 4 {
+            return;
+        }
+        let (count, topics) = state.split_at(4);
+        self.update_count = i32::from_le_bytes([count[0], count[1], count[2], count[3]]);
+        self.subscribed_topics = String::from_utf8_lossy(topics)
+            .split('\n')
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+}
+
 // Register message handlers
 handle_messages!(RustSubscriber,
     Start => on_start,
@@ -56,6 +56,33 @@ https://opensource.org/licenses/MIT
 /* Annotation macro for code generator - parsed but not compiled */
 #define INTEROP_MESSAGE(name, id)
 
+/* After a message: Rust handlers may read it in place, without a copy
+ * (rust_actor_borrow_send, see interop/borrow.h). Fixed-size fields only. */
+#define INTEROP_BORROWED(name)
+
+/* After a message: the FlatBuffers link codec sends it as a FlatBuffers
+ * table that receivers read in place (see rust/src/flatbuf.rs). For large
+ * messages; the rest keep the raw struct frame. No map fields. */
+#define INTEROP_FLATBUFFERS(name)
+
+/* After a message: C++ instances the bridge delivers are recycled when the
+ * receiving actor deletes them, instead of freed (interop::EnvelopePool in
+ * InteropMessages.hpp). For high-rate messages sent to C++. */
+#define INTEROP_POOLED(name)
+
+/* A Rust actor's interface: the messages it takes, each with the reply it
+ * sends back, if any. Generates a C++ test double (RustActorStubs.hpp)
+ * for tests without the Rust library:
+ *   INTEROP_ACTOR(rust_pong, Ping -> Pong)
+ *   INTEROP_ACTOR(rust_price_monitor, MarketUpdate, MarketDepth) */
+#define INTEROP_ACTOR(name, ...)
+
+/* What an INTEROP_ACTOR sends besides its replies. In contract checking
+ * mode (interop/contracts.h) the bridge flags other messages it sends;
+ * without this line they are not checked:
+ *   INTEROP_EMITS(rust_price_monitor, Subscribe, Unsubscribe) */
+#define INTEROP_EMITS(name, ...)
+
 /* Fixed-size string for FFI (no heap allocation) */
 #define INTEROP_STRING_MAX 64
 
@@ -64,6 +91,67 @@ typedef struct {
     uint32_t len;
 } interop_string;
 
+/*
+ * Variable-length bytes: Vec<u8> in Rust, std::vector<uint8_t> in C++.
+ * take = 0: copy. The receiver copies during the send call; the sender
+ *           keeps ownership.
+ * take = 1: the buffer came from interop_bytes_alloc(). On a successful
+ *           send the receiver owns it; on failure the sender must call
+ *           interop_bytes_free().
+ */
+typedef struct {
+    uint8_t* data;
+    uint32_t len;
+    int32_t take;
+} interop_bytes;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+interop_bytes interop_bytes_alloc(uint32_t len);
+void interop_bytes_free(interop_bytes bytes);
+#ifdef __cplusplus
+}
+#endif
+
+/*
+ * Fixed-point decimal: value = units * 10^-scale, scale 0..18.
+ * Decimal in Rust, interop::Decimal in C++; both keep prices exact
+ * where double would round (99.875 is units 99875, scale 3).
+ */
+typedef struct {
+    int64_t units;
+    int32_t scale;
+} interop_decimal;
+
+/*
+ * 16-byte id: RFC 4122 UUID bytes in network order.
+ * Uuid in Rust, interop::Uuid in C++ (generate, parse, to_string).
+ */
+typedef struct {
+    uint8_t bytes[16];
+} interop_uuid;
+
+/*
+ * Point in time: nanoseconds since the Unix epoch, UTC.
+ * Timestamp in Rust (SystemTime / chrono), interop::Timestamp in C++
+ * (std::chrono::system_clock). Covers 1677 to 2262.
+ */
+typedef struct {
+    int64_t nanos;
+} interop_timestamp;
+
+/*
+ * String-keyed map field: HashMap<String, V> in Rust, unordered_map in C++.
+ * Expands to a count plus parallel key/value arrays of the given capacity.
+ *   INTEROP_MAP(double, per_symbol_limit, 16);
+ */
+#define INTEROP_MAP_KEY_MAX 16
+#define INTEROP_MAP(value_type, name, capacity) \
+    int32_t name##_count; \
+    char name##_keys[capacity][INTEROP_MAP_KEY_MAX]; \
+    value_type name##_values[capacity]
+
 /* ============================================================
  * Message Definitions
  * ============================================================ */
@@ -95,23 +183,54 @@ typedef struct {
  * Pub/Sub Messages for cross-language subscription patterns
  * ============================================================ */
 
+/* Subscription QoS (Subscribe.qos) */
+#define INTEROP_QOS_BEST_EFFORT 0  /* may conflate/drop under pressure */
+#define INTEROP_QOS_RELIABLE    1  /* acknowledged, bounded retransmit */
+#define INTEROP_SUB_COMPACT     0x100  /* flag: send CompactMarketUpdate */
+
+/* Overflow policy of a Rust subscriber's own queue (Subscribe.qos, or'd in;
+ * none: straight to the mailbox). See rust/src/sub_queue.rs. */
+#define INTEROP_SUB_DROP_OLDEST 0x1000  /* full: drop the oldest queued */
+#define INTEROP_SUB_DROP_NEWEST 0x2000  /* full: drop the new message */
+#define INTEROP_SUB_CONFLATE    0x3000  /* replace same type and symbol, else drop oldest */
+#define INTEROP_SUB_BLOCK       0x4000  /* full: the publisher waits */
+#define INTEROP_SUB_CAPACITY(n) (((n) & 0x7FFF) << 16)  /* queue size, default 1024 */
+
 INTEROP_MESSAGE(Subscribe, 1010)
 typedef struct {
-    char topic[32];
+    char topic[32];  /* valid: utf8 */
+    int32_t qos;  /* INTEROP_QOS_* */
 } Subscribe;
 
 INTEROP_MESSAGE(Unsubscribe, 1011)
 typedef struct {
-    char topic[32];
+    char topic[32];  /* valid: utf8 */
 } Unsubscribe;
 
+/* Subscribe / Unsubscribe for many topics in one message. topics holds
+ * the topic names, each NUL-terminated and at most 31 bytes as in
+ * Subscribe (interop::topic_list() in interop/subscribe_many.hpp). The
+ * bridge records them as that many Subscribes / Unsubscribes. */
+INTEROP_MESSAGE(SubscribeMany, 1055)
+typedef struct {
+    interop_bytes topics;  /* valid: nonempty */
+    int32_t qos;  /* INTEROP_QOS_*, for every topic */
+} SubscribeMany;
+
+INTEROP_MESSAGE(UnsubscribeMany, 1056)
+typedef struct {
+    interop_bytes topics;  /* valid: nonempty */
+} UnsubscribeMany;
+
 INTEROP_MESSAGE(MarketUpdate, 1012)
 typedef struct {
     char symbol[8];
-    double price;
-    int64_t timestamp;
+    double price;  /* valid: > 0 */
+    interop_timestamp timestamp;
     int32_t volume;
+    int64_t seq;  /* sequenced: 0 = none, else per publisher/symbol/subscriber */
 } MarketUpdate;
+INTEROP_POOLED(MarketUpdate)
 
 /* ============================================================
  * Example: Market Data with arrays
@@ -120,11 +239,271 @@ typedef struct {
 INTEROP_MESSAGE(MarketDepth, 1013)
 typedef struct {
     char symbol[8];
-    int32_t num_levels;
+    int32_t num_levels;  /* valid: >= 0, <= len(bid_prices) */
     double bid_prices[5];
     double ask_prices[5];
     int32_t bid_sizes[5];
     int32_t ask_sizes[5];
 } MarketDepth;
+INTEROP_BORROWED(MarketDepth)
+INTEROP_FLATBUFFERS(MarketDepth)
+
+/* Cumulative ack for reliable topics: seq = number of updates received */
+INTEROP_MESSAGE(TopicAck, 1014)
+typedef struct {
+    char topic[32];
+    int64_t seq;
+} TopicAck;
+
+/* Raised on the subscriber side when a sequenced stream skips updates */
+INTEROP_MESSAGE(GapDetected, 1015)
+typedef struct {
+    char publisher[32];
+    char topic[32];
+    int64_t expected_seq;
+    int64_t received_seq;
+} GapDetected;
+
+/* Publisher -> subscriber: symbol_id used by CompactMarketUpdate for
+ * symbol. Sent in reply to a Subscribe with INTEROP_SUB_COMPACT, before
+ * the first compact update. Ids are per publisher. */
+INTEROP_MESSAGE(SymbolDirectory, 1016)
+typedef struct {
+    char symbol[8];
+    uint32_t symbol_id;
+} SymbolDirectory;
+
+/* MarketUpdate with the symbol replaced by its SymbolDirectory id */
+INTEROP_MESSAGE(CompactMarketUpdate, 1017)
+typedef struct {
+    double price;  /* valid: > 0 */
+    interop_timestamp timestamp;
+    int64_t seq;  /* numbered by the publisher, 0 = none */
+    uint32_t symbol_id;
+    int32_t volume;
+} CompactMarketUpdate;
+
+/* Subscriber -> publisher: keep the subscription to topic for lease_ms
+ * more. Sent by the bridge while subscription leases are on (see
+ * interop/lease.hpp); a subscription not renewed in time expires. */
+INTEROP_MESSAGE(LeaseRenew, 1018)
+typedef struct {
+    char topic[32];  /* valid: utf8 */
+    int32_t lease_ms;
+} LeaseRenew;
+
+/* Fixed-income quote with exact prices (interop_decimal) */
+INTEROP_MESSAGE(BondQuote, 1019)
+typedef struct {
+    char isin[16];
+    interop_decimal bid;
+    interop_decimal ask;
+    interop_decimal yield_rate;
+    interop_timestamp timestamp;
+} BondQuote;
+
+/* ============================================================
+ * Risk
+ * ============================================================ */
+
+INTEROP_MESSAGE(RiskLimits, 1020)
+typedef struct {
+    int32_t account_id;
+    INTEROP_MAP(double, per_symbol_limit, 16);
+} RiskLimits;
+
+/* ============================================================
+ * Orders
+ * ============================================================ */
+
+/* A resend carries the same order_id; a receiver with idempotency enabled
+ * answers it with the first OrderAck instead of executing it again */
+INTEROP_MESSAGE(NewOrder, 1021)
+typedef struct {
+    int64_t order_id;  /* idempotency key: nonzero, unique per order */
+    char symbol[8];
+    int32_t side;  /* 1 = buy, -1 = sell */
+    interop_decimal price;
+    int64_t quantity;
+    interop_uuid client_order_id;  /* the client's own id, echoed in the OrderAck */
+} NewOrder;
+
+INTEROP_MESSAGE(OrderAck, 1022)
+typedef struct {
+    int64_t order_id;
+    int32_t accepted;  /* bool: 1=true, 0=false */
+    char reason[32];   /* empty when accepted */
+    interop_uuid client_order_id;
+} OrderAck;
+
+/* ============================================================
+ * Opaque payloads (serialized protobufs, images, ...)
+ * ============================================================ */
+
+INTEROP_MESSAGE(RawPayload, 1030)
+typedef struct {
+    char content_type[32];  /* e.g. "application/x-protobuf" */
+    interop_bytes bytes;
+} RawPayload;
+INTEROP_FLATBUFFERS(RawPayload)
+
+/* Part of a RawPayload split to fit a size limit (size_limit.rs,
+ * interop/payload_chunks.hpp); receivers reassemble by transfer_id */
+INTEROP_MESSAGE(PayloadChunk, 1031)
+typedef struct {
+    int64_t transfer_id;
+    int32_t index;  /* 0..count-1 */
+    int32_t count;
+    char content_type[32];  /* of the whole payload */
+    interop_bytes data;
+} PayloadChunk;
+
+/* ============================================================
+ * Streams: ordered chunks with credit-based flow control
+ * (stream.rs / interop/stream.hpp)
+ * ============================================================ */
+
+#define INTEROP_STREAM_COMPLETE 0  /* sender is done; chunks == count sent */
+#define INTEROP_STREAM_CANCEL   1  /* both directions abandoned */
+
+/* Opens stream_id; credit is how many chunks the receiver may send back
+ * before the opener grants more */
+INTEROP_MESSAGE(StreamOpen, 1040)
+typedef struct {
+    int64_t stream_id;
+    char origin[32];  /* opener's actor name, where the receiver replies */
+    int32_t credit;
+} StreamOpen;
+
+INTEROP_MESSAGE(StreamChunk, 1041)
+typedef struct {
+    int64_t stream_id;
+    int64_t index;  /* 0, 1, 2, ... per direction */
+    interop_bytes data;
+} StreamChunk;
+
+/* The peer may send `credit` more chunks */
+INTEROP_MESSAGE(StreamCredit, 1042)
+typedef struct {
+    int64_t stream_id;
+    int32_t credit;
+} StreamCredit;
+
+INTEROP_MESSAGE(StreamEnd, 1043)
+typedef struct {
+    int64_t stream_id;
+    int32_t status;  /* INTEROP_STREAM_* */
+    int64_t chunks;  /* chunks sent in this direction */
+} StreamEnd;
+
+/* Runtime event on a $sys/ topic; subscribe with Subscribe sent to "$sys"
+ * (see interop/sys_topics.h) */
+INTEROP_MESSAGE(SystemEvent, 1050)
+typedef struct {
+    int32_t kind;       /* INTEROP_SYS_* */
+    char subject[32];   /* actor the event is about */
+    int64_t value;      /* queue depth, or ms since the last heartbeat */
+    interop_timestamp timestamp;
+} SystemEvent;
+
+/* Host -> Rust subscriber: subscribe to topic of publisher, or of the
+ * subscriber's default publisher if publisher is empty. Handled by
+ * actors implementing DynamicSubscriber (see interop/subscriber.h). */
+INTEROP_MESSAGE(SubscribeRequest, 1051)
+typedef struct {
+    char publisher[32];  /* valid: utf8 */
+    char topic[32];      /* valid: utf8, nonempty */
+    int32_t qos;         /* INTEROP_QOS_* */
+} SubscribeRequest;
+
+/* Host -> Rust subscriber: drop its subscription to topic of publisher */
+INTEROP_MESSAGE(UnsubscribeRequest, 1052)
+typedef struct {
+    char publisher[32];  /* valid: utf8 */
+    char topic[32];      /* valid: utf8, nonempty */
+} UnsubscribeRequest;
+
+/* ============================================================
+ * Errors
+ * ============================================================ */
+
+/* Sent by a bridge to the sender of a message it could not dispatch */
+#define INTEROP_ERR_UNKNOWN_MESSAGE 1  /* the receiving side has no such message id */
+#define INTEROP_ERR_INVALID_MESSAGE 2  /* broke a field rule; text names it */
+#define INTEROP_ERR_PAUSED          3  /* fast_send held: the target is paused */
+#define INTEROP_ERR_POLICY_DENIED   4  /* forbidden by the bridge policy */
+#define INTEROP_ERR_TOO_LARGE       5  /* over the bridge size limit */
+#define INTEROP_ERR_SHUTTING_DOWN   6  /* the receiving side is shutting down */
+#define INTEROP_ERR_BACKLOG_FULL    7  /* a reliable subscriber's backlog is full; the update was refused */
+
+INTEROP_MESSAGE(InteropError, 1053)
+typedef struct {
+    int32_t code;         /* INTEROP_ERR_* */
+    char text[64];
+    int32_t in_reply_to;  /* message id of the failed message */
+} InteropError;
+
+/* ============================================================
+ * Requests
+ * ============================================================ */
+
+/* Asker -> responder: the ask with this id (reply mailbox "__ask-<id>")
+ * ended without a reply; stop working on it (see interop/ask.h) */
+INTEROP_MESSAGE(AskCancel, 1054)
+typedef struct {
+    int64_t correlation_id;
+} AskCancel;
+
+/* ============================================================
+ * Configuration
+ * ============================================================ */
+
+/* Anyone -> "$config": set key to value (see interop/config.h) */
+INTEROP_MESSAGE(ConfigSet, 1057)
+typedef struct {
+    char key[32];  /* valid: utf8, nonempty */
+    interop_string value;
+} ConfigSet;
+
+/* "$config" -> subscribers of key or of "*": key changed. A new
+ * subscriber of a key that is set is sent its current value at once. */
+INTEROP_MESSAGE(ConfigChanged, 1058)
+typedef struct {
+    char key[32];
+    interop_string value;  /* empty if removed */
+    int64_t version;       /* times key has been set, 0 if removed */
+} ConfigChanged;
+
+/* ============================================================
+ * Latency Probes
+ * ============================================================ */
+
+/* Prober "$probe-<id>" -> echo actor: answer with a LatencyEcho of the
+ * same fields (see interop/probe.h) */
+INTEROP_MESSAGE(LatencyProbe, 1060)
+typedef struct {
+    int64_t probe_id;
+    int64_t seq;
+    interop_timestamp sent_at;
+} LatencyProbe;
+
+/* Echo actor -> prober: the LatencyProbe's fields, unchanged */
+INTEROP_MESSAGE(LatencyEcho, 1061)
+typedef struct {
+    int64_t probe_id;
+    int64_t seq;
+    interop_timestamp sent_at;
+} LatencyEcho;
+
+/* ============================================================
+ * Rust Actor Interfaces (C++ test doubles, see INTEROP_ACTOR)
+ * ============================================================ */
+
+/* examples/ping_pong */
+INTEROP_ACTOR(rust_pong, Ping -> Pong)
+
+/* examples/rust_subscribes_cpp_publisher */
+INTEROP_ACTOR(rust_price_monitor, MarketUpdate, MarketDepth)
+INTEROP_EMITS(rust_price_monitor, Subscribe, Unsubscribe)
 
 #endif /* INTEROP_MESSAGES_H */
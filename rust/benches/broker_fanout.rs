@@ -0,0 +1,95 @@
+//! Publish throughput against subscriber count and fan-out workers
+//!
+//! Each subscriber is a C++ actor; the stand-in bridge spends a fixed time
+//! per send, like a C++ mailbox push. A publish is timed until every
+//! subscriber has received it. Run with `cargo bench --bench broker_fanout`.
+
+use std::hint::black_box;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use actors_interop::broker;
+use actors_interop::capabilities::{self, Capabilities, CAPABILITIES_VERSION, REQUIRED_FUNCTIONS};
+use actors_interop::interop_messages::{MarketUpdate, Timestamp, MESSAGE_IDS};
+use actors_interop::rust_manager_ffi::{create_rust_manager, init_cpp_actor_lookup};
+
+static DELIVERED: AtomicUsize = AtomicUsize::new(0);
+
+const SEND_COST: Duration = Duration::from_micros(2);
+
+// Stand-ins for the C++ bridge so the benchmark links without C++
+#[no_mangle]
+pub extern "C" fn cpp_actor_send(_: *const c_char, _: *const c_char, _: c_int, data: *const c_void) -> c_int {
+    let start = Instant::now();
+    while start.elapsed() < SEND_COST {
+        black_box(data);
+    }
+    DELIVERED.fetch_add(1, Ordering::Release);
+    0
+}
+#[no_mangle]
+pub extern "C" fn cpp_actor_fast_send(a: *const c_char, s: *const c_char, t: c_int, data: *const c_void) -> c_int {
+    cpp_actor_send(a, s, t, data)
+}
+#[no_mangle]
+pub extern "C" fn cpp_actor_exists(_: *const c_char) -> c_int {
+    1
+}
+
+const PUBLISHES: usize = 20;
+
+fn update(i: usize) -> MarketUpdate {
+    MarketUpdate {
+        symbol: "AAPL".into(),
+        price: 150.0 + i as f64 * 0.01,
+        timestamp: Timestamp::from_millis(i as i64),
+        volume: 100,
+        seq: 0,
+    }
+}
+
+fn report(subscribers: usize, workers: usize) {
+    broker::set_fanout_workers(workers);
+    let expected = DELIVERED.load(Ordering::Acquire) + PUBLISHES * subscribers;
+    let start = Instant::now();
+    for i in 0..PUBLISHES {
+        black_box(broker::publish("bench_publisher", "AAPL", &update(i)).unwrap());
+    }
+    while DELIVERED.load(Ordering::Acquire) < expected {
+        assert!(start.elapsed() < Duration::from_secs(60), "deliveries missing; is the C++ lookup installed?");
+        std::thread::yield_now();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:>6} subscribers  {:>2} workers  {:>8.2} ms/publish  {:>10.0} deliveries/s",
+        subscribers,
+        workers,
+        elapsed.as_secs_f64() * 1000.0 / PUBLISHES as f64,
+        (PUBLISHES * subscribers) as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    create_rust_manager();
+    capabilities::register(Capabilities {
+        version: CAPABILITIES_VERSION,
+        msg_ids: MESSAGE_IDS.to_vec(),
+        functions: REQUIRED_FUNCTIONS.iter().map(|f| f.to_string()).collect(),
+    });
+    assert_eq!(init_cpp_actor_lookup(), 0);
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    println!("{} publishes of MarketUpdate, {:?} per send, {} cores", PUBLISHES, SEND_COST, cores);
+
+    let mut subscribed = 0;
+    for subscribers in [1_000, 10_000] {
+        for i in subscribed..subscribers {
+            broker::add("bench_publisher", "AAPL", &format!("cpp_subscriber_{}", i), 0);
+        }
+        subscribed = subscribers;
+        for workers in [0, 1, 2, 4, 8] {
+            report(subscribers, workers);
+        }
+    }
+    broker::set_fanout_workers(0);
+}
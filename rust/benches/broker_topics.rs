@@ -0,0 +1,111 @@
+//! Subscribe and publish rates against topic count and publisher threads
+//!
+//! One subscriber per topic, a C++ actor whose stand-in bridge returns at
+//! once, so the registry lookup dominates. Publisher threads publish to
+//! topics spread over the whole range; with one registry lock they would
+//! queue behind each other, with the sharded registry they scale with
+//! cores. Run with `cargo bench --bench broker_topics`.
+
+use std::hint::black_box;
+use std::os::raw::{c_char, c_int, c_void};
+use std::time::Instant;
+
+use actors_interop::broker;
+use actors_interop::capabilities::{self, Capabilities, CAPABILITIES_VERSION, REQUIRED_FUNCTIONS};
+use actors_interop::interop_messages::{MarketUpdate, Timestamp, MESSAGE_IDS};
+use actors_interop::rust_manager_ffi::{create_rust_manager, init_cpp_actor_lookup};
+
+// Stand-ins for the C++ bridge so the benchmark links without C++
+#[no_mangle]
+pub extern "C" fn cpp_actor_send(_: *const c_char, _: *const c_char, _: c_int, data: *const c_void) -> c_int {
+    black_box(data);
+    0
+}
+#[no_mangle]
+pub extern "C" fn cpp_actor_fast_send(a: *const c_char, s: *const c_char, t: c_int, data: *const c_void) -> c_int {
+    cpp_actor_send(a, s, t, data)
+}
+#[no_mangle]
+pub extern "C" fn cpp_actor_exists(_: *const c_char) -> c_int {
+    1
+}
+
+const PUBLISHES_PER_THREAD: usize = 200_000;
+
+fn topic(i: usize) -> String {
+    format!("SYM{:06}", i)
+}
+
+fn update(symbol: &str, i: usize) -> MarketUpdate {
+    MarketUpdate {
+        symbol: symbol.into(),
+        price: 150.0 + i as f64 * 0.01,
+        timestamp: Timestamp::from_millis(i as i64),
+        volume: 100,
+        seq: 0,
+    }
+}
+
+fn subscribe(topics: usize) {
+    let start = Instant::now();
+    for i in 0..topics {
+        broker::add("bench_publisher", &topic(i), &format!("cpp_subscriber_{}", i % 64), 0);
+    }
+    let elapsed = start.elapsed();
+    println!("{:>7} topics  subscribe  {:>10.0} subscriptions/s", topics, topics as f64 / elapsed.as_secs_f64());
+}
+
+fn publish(topics: usize, threads: usize) {
+    // Symbols built ahead so the loop times the broker, not formatting
+    let names: Vec<String> = (0..topics).map(topic).collect();
+    let start = Instant::now();
+    std::thread::scope(|scope| {
+        for t in 0..threads {
+            let names = &names;
+            scope.spawn(move || {
+                for i in 0..PUBLISHES_PER_THREAD {
+                    // A stride per thread so threads hit different shards
+                    let name = &names[(i * 7919 + t * 104_729) % topics];
+                    black_box(broker::publish("bench_publisher", name, &update(name, i)).unwrap());
+                }
+            });
+        }
+    });
+    let elapsed = start.elapsed();
+    println!(
+        "{:>7} topics  {:>2} threads  {:>10.0} publishes/s",
+        topics,
+        threads,
+        (PUBLISHES_PER_THREAD * threads) as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn unsubscribe(topics: usize) {
+    let start = Instant::now();
+    for i in 0..topics {
+        broker::remove("bench_publisher", &topic(i), &format!("cpp_subscriber_{}", i % 64));
+    }
+    let elapsed = start.elapsed();
+    println!("{:>7} topics  unsubscribe {:>9.0} removals/s", topics, topics as f64 / elapsed.as_secs_f64());
+}
+
+fn main() {
+    create_rust_manager();
+    capabilities::register(Capabilities {
+        version: CAPABILITIES_VERSION,
+        msg_ids: MESSAGE_IDS.to_vec(),
+        functions: REQUIRED_FUNCTIONS.iter().map(|f| f.to_string()).collect(),
+    });
+    assert_eq!(init_cpp_actor_lookup(), 0);
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    println!("{} shards, {} cores", broker::SHARDS, cores);
+
+    for topics in [1_000, 10_000, 100_000] {
+        subscribe(topics);
+        assert_eq!(broker::topic_count(), topics);
+        for threads in [1, 2, 4, 8] {
+            publish(topics, threads);
+        }
+        unsubscribe(topics);
+    }
+}
@@ -0,0 +1,159 @@
+//! Allocations per Rust -> C++ MarketUpdate send
+//!
+//! Compares `ActorRef::Cpp` from `get_actor_ref()` (Box + CString target +
+//! CString sender) with `CachedSender::send_pooled()`, both through the
+//! real bridge send path. Rust allocations are counted by this binary's
+//! allocator. By default C++ is a stand-in that only takes the C struct;
+//! `--features cpp_bench` links the real C++ bridge and actors (`make
+//! bench-cpp`) and also reports the C++ messages `interop::EnvelopePool`
+//! allocated and reused.
+//!
+//! ```text
+//! cargo bench --bench envelope_alloc
+//! cargo bench --bench envelope_alloc --features cpp_bench
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use actors_interop::cached_send::CachedSender;
+use actors_interop::interop_messages::{MarketUpdate, Timestamp};
+use actors_interop::rust_actor_bridge::rust_actor_init;
+use actors_interop::rust_manager_ffi::{create_rust_manager, get_actor_ref, get_rust_manager, init_cpp_actor_lookup};
+use actors_interop::send_error::TrySend;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// Stand-ins for the C++ bridge so the benchmark links without C++
+#[cfg(not(feature = "cpp_bench"))]
+mod cpp {
+    use std::hint::black_box;
+    use std::os::raw::{c_char, c_int, c_void};
+
+    use actors_interop::capabilities::{self, Capabilities, CAPABILITIES_VERSION, REQUIRED_FUNCTIONS};
+    use actors_interop::interop_messages::MESSAGE_IDS;
+
+    pub const TARGET: &str = "cpp_subscriber";
+
+    #[no_mangle]
+    pub extern "C" fn cpp_actor_send(_: *const c_char, _: *const c_char, _: c_int, data: *const c_void) -> c_int {
+        black_box(data);
+        0
+    }
+    #[no_mangle]
+    pub extern "C" fn cpp_actor_fast_send(a: *const c_char, s: *const c_char, t: c_int, data: *const c_void) -> c_int {
+        cpp_actor_send(a, s, t, data)
+    }
+    #[no_mangle]
+    pub extern "C" fn cpp_actor_exists(_: *const c_char) -> c_int {
+        1
+    }
+
+    pub fn start() {
+        capabilities::register(Capabilities {
+            version: CAPABILITIES_VERSION,
+            msg_ids: MESSAGE_IDS.to_vec(),
+            functions: REQUIRED_FUNCTIONS.iter().map(|f| f.to_string()).collect(),
+        });
+    }
+
+    /// No C++ messages are built by the stand-in
+    pub fn pool_stats() -> Option<(u64, u64)> {
+        None
+    }
+}
+
+// cpp/bench/bench_actors.cpp and the generated C++ bridge
+#[cfg(feature = "cpp_bench")]
+mod cpp {
+    use actors_interop::interop_messages::MSG_MARKETUPDATE;
+
+    extern "C" {
+        fn interop_bench_cpp_start(subscribers: i32);
+        fn interop_envelope_pool_stats(msg_type: i32, allocated: *mut u64, reused: *mut u64) -> i32;
+    }
+
+    pub const TARGET: &str = "cpp_sub_0";
+
+    pub fn start() {
+        unsafe { interop_bench_cpp_start(1) };
+    }
+
+    /// MarketUpdates the C++ bridge allocated and reused so far
+    pub fn pool_stats() -> Option<(u64, u64)> {
+        let (mut allocated, mut reused) = (0, 0);
+        let rc = unsafe { interop_envelope_pool_stats(MSG_MARKETUPDATE, &mut allocated, &mut reused) };
+        (rc == 0).then_some((allocated, reused))
+    }
+}
+
+const SENDS: usize = 1_000_000;
+
+fn update(i: usize) -> MarketUpdate {
+    MarketUpdate {
+        symbol: "AAPL".into(),
+        price: 150.0 + i as f64 * 0.01,
+        timestamp: Timestamp::from_millis(i as i64),
+        volume: 100,
+        seq: 0,
+    }
+}
+
+fn report(name: &str, mut f: impl FnMut(usize)) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let pool_before = cpp::pool_stats();
+    let start = Instant::now();
+    for i in 0..SENDS {
+        f(i);
+    }
+    let elapsed = start.elapsed();
+    let allocs = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    print!(
+        "{:<12} {:>10} allocs  {:>6.2} allocs/send  {:>7.1} ns/send",
+        name,
+        allocs,
+        allocs as f64 / SENDS as f64,
+        elapsed.as_nanos() as f64 / SENDS as f64
+    );
+    match (pool_before, cpp::pool_stats()) {
+        (Some((allocated, reused)), Some((allocated_after, reused_after))) => {
+            println!("  C++: {} allocated, {} reused", allocated_after - allocated, reused_after - reused)
+        }
+        _ => println!(),
+    }
+}
+
+fn main() {
+    create_rust_manager();
+    rust_actor_init(get_rust_manager());
+    cpp::start();
+    assert_eq!(init_cpp_actor_lookup(), 0, "C++ side lacks capabilities");
+    println!("{} MarketUpdate sends to C++ actor {}", SENDS, cpp::TARGET);
+
+    let subscriber = get_actor_ref(cpp::TARGET, "rust_publisher").expect("C++ subscriber not found");
+    report("actor_ref", |i| {
+        black_box(subscriber.try_send(Box::new(update(i)), None)).unwrap();
+    });
+
+    let mut pooled = CachedSender::new("rust_publisher");
+    report("send_pooled", |i| {
+        black_box(pooled.send_pooled(cpp::TARGET, &update(i))).unwrap();
+    });
+}
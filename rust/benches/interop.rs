@@ -0,0 +1,170 @@
+//! Cross-language throughput and latency, comparable across releases
+//!
+//! - `ping_pong`: one Rust -> C++ Ping and C++ -> Rust Pong round trip
+//!   (`ask::ask_message` to `cpp_pong`)
+//! - `fanout/<n>`: one `broker::publish` of a MarketUpdate to n C++
+//!   subscribers, timed until every subscriber has it
+//! - `to_c/<Message>`, `from_c/<Message>`: conversion of every message
+//!   type to and from its C struct
+//!
+//! By default C++ is a stand-in linked into this binary: `cpp_pong` replies
+//! on the sending thread and subscribers only count, so the numbers are
+//! the bridge's own cost. `--features cpp_bench` links the real C++ bridge
+//! and actors instead (`make bench-cpp` builds them into lib/).
+//!
+//! ```text
+//! cargo bench --bench interop -- --save-baseline v0.1.0
+//! cargo bench --bench interop -- --baseline v0.1.0      # a later release against it
+//! ```
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use actors_interop::ask::ask_message;
+use actors_interop::broker;
+use actors_interop::interop_messages::{
+    c_struct_size, message_from_c, message_name, message_to_c_bytes, MarketUpdate, Ping, Timestamp, MESSAGE_IDS,
+};
+use actors_interop::rust_actor_bridge::rust_actor_init;
+use actors_interop::rust_manager_ffi::{create_rust_manager, get_actor_ref, get_rust_manager, init_cpp_actor_lookup};
+
+const SUBSCRIBERS: [usize; 3] = [10, 100, 1_000];
+
+// Stand-ins for the C++ bridge and actors
+#[cfg(not(feature = "cpp_bench"))]
+mod cpp {
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use actors_interop::capabilities::{self, Capabilities, CAPABILITIES_VERSION, REQUIRED_FUNCTIONS};
+    use actors_interop::interop_messages::{CPing, CPong, MESSAGE_IDS, MSG_PING, MSG_PONG};
+    use actors_interop::rust_actor_bridge::rust_actor_send;
+
+    static DELIVERED: AtomicU64 = AtomicU64::new(0);
+
+    #[no_mangle]
+    pub extern "C" fn cpp_actor_send(target: *const c_char, sender: *const c_char, msg_type: c_int, data: *const c_void) -> c_int {
+        if msg_type == MSG_PING {
+            // cpp_pong: reply() to the sender, as RustSenderProxy does
+            let pong = CPong { count: unsafe { (*(data as *const CPing)).count } };
+            return rust_actor_send(sender, target, MSG_PONG, &pong as *const CPong as *const c_void);
+        }
+        DELIVERED.fetch_add(1, Ordering::Release);
+        0
+    }
+    #[no_mangle]
+    pub extern "C" fn cpp_actor_fast_send(a: *const c_char, s: *const c_char, t: c_int, data: *const c_void) -> c_int {
+        cpp_actor_send(a, s, t, data)
+    }
+    #[no_mangle]
+    pub extern "C" fn cpp_actor_exists(_: *const c_char) -> c_int {
+        1
+    }
+
+    pub fn start(_subscribers: usize) {
+        capabilities::register(Capabilities {
+            version: CAPABILITIES_VERSION,
+            msg_ids: MESSAGE_IDS.to_vec(),
+            functions: REQUIRED_FUNCTIONS.iter().map(|f| f.to_string()).collect(),
+        });
+    }
+
+    pub fn delivered() -> u64 {
+        DELIVERED.load(Ordering::Acquire)
+    }
+}
+
+// cpp/bench/bench_actors.cpp
+#[cfg(feature = "cpp_bench")]
+mod cpp {
+    extern "C" {
+        fn interop_bench_cpp_start(subscribers: i32);
+        fn interop_bench_cpp_delivered() -> u64;
+    }
+
+    pub fn start(subscribers: usize) {
+        unsafe { interop_bench_cpp_start(subscribers as i32) };
+    }
+
+    pub fn delivered() -> u64 {
+        unsafe { interop_bench_cpp_delivered() }
+    }
+}
+
+fn setup() {
+    let most = SUBSCRIBERS[SUBSCRIBERS.len() - 1];
+    create_rust_manager();
+    rust_actor_init(get_rust_manager());
+    cpp::start(most);
+    assert_eq!(init_cpp_actor_lookup(), 0, "C++ side lacks capabilities");
+}
+
+fn ping_pong(c: &mut Criterion) {
+    let pong = get_actor_ref("cpp_pong", "bench").expect("cpp_pong not found");
+    c.bench_function("ping_pong", |b| {
+        b.iter(|| {
+            let reply = ask_message(&pong, Box::new(Ping { count: 1 }), Duration::from_secs(1)).expect("no Pong");
+            black_box(reply);
+        })
+    });
+}
+
+fn fanout(c: &mut Criterion) {
+    let update = MarketUpdate { symbol: "AAPL".into(), price: 150.25, timestamp: Timestamp::now(), volume: 100, seq: 0 };
+    let mut group = c.benchmark_group("fanout");
+    let mut subscribed = 0;
+    for n in SUBSCRIBERS {
+        for i in subscribed..n {
+            broker::add("bench_publisher", "AAPL", &format!("cpp_sub_{}", i), 0);
+        }
+        subscribed = n;
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+            b.iter_custom(|iters| {
+                let expected = cpp::delivered() + iters * n as u64;
+                let start = Instant::now();
+                for _ in 0..iters {
+                    black_box(broker::publish("bench_publisher", "AAPL", &update).unwrap());
+                }
+                while cpp::delivered() < expected {
+                    assert!(start.elapsed() < Duration::from_secs(60), "deliveries missing");
+                    std::thread::yield_now();
+                }
+                start.elapsed()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn conversion(c: &mut Criterion) {
+    for (direction, to_c) in [("to_c", true), ("from_c", false)] {
+        let mut group = c.benchmark_group(direction);
+        for &id in MESSAGE_IDS {
+            let size = c_struct_size(id).unwrap();
+            // A zeroed C struct is a valid message of every type
+            let zeroed = vec![0u64; size.div_ceil(8)];
+            let data = zeroed.as_ptr() as *const std::os::raw::c_void;
+            let msg = unsafe { message_from_c(id, data) }.unwrap();
+            let name = message_name(id).unwrap();
+            if to_c {
+                group.bench_function(name, |b| b.iter(|| black_box(message_to_c_bytes(msg.as_ref()))));
+            } else {
+                group.bench_function(name, |b| b.iter(|| black_box(unsafe { message_from_c(id, data) })));
+            }
+        }
+        group.finish();
+    }
+}
+
+fn benches(c: &mut Criterion) {
+    setup();
+    ping_pong(c);
+    fanout(c);
+    conversion(c);
+}
+
+criterion_group!(interop, benches);
+criterion_main!(interop);
@@ -0,0 +1,20 @@
+fn main() {
+    // Link flags for loading the cdylib as a Node.js addon
+    #[cfg(feature = "node")]
+    napi_build::setup();
+
+    // The real C++ side for benches/interop.rs, from `make bench-cpp`
+    #[cfg(feature = "cpp_bench")]
+    {
+        let lib_dir = std::env::var("INTEROP_BENCH_LIB_DIR").unwrap_or_else(|_| "../lib".to_string());
+        let actors_dir = std::env::var("ACTORS_CPP_LIB_DIR")
+            .unwrap_or_else(|_| format!("{}/actors-cpp/src", std::env::var("HOME").unwrap_or_default()));
+        println!("cargo:rerun-if-env-changed=INTEROP_BENCH_LIB_DIR");
+        println!("cargo:rerun-if-env-changed=ACTORS_CPP_LIB_DIR");
+        println!("cargo:rustc-link-search=native={}", lib_dir);
+        println!("cargo:rustc-link-search=native={}", actors_dir);
+        println!("cargo:rustc-link-lib=static=interop_bench");
+        println!("cargo:rustc-link-lib=static=actors");
+        println!("cargo:rustc-link-lib=dylib=stdc++");
+    }
+}
@@ -0,0 +1,124 @@
+//! Attribute macros for actors-interop
+//!
+//! `#[interop_actor("rust_pong")]` on an actor struct registers it in
+//! `actors_interop::registry` under its type name, so C++ can create it with
+//! `register_actor_by_type_name("RustPongActor", ...)`. The actor must have
+//! `fn new(ManagerHandle) -> Self`.
+//!
+//! `#[interop_actor("rust_price_monitor", snapshot)]` registers an actor
+//! that implements `actors_interop::snapshot::Snapshot` with its hooks.
+//!
+//! `#[interop_actor("rust_price_monitor", panic_context)]` registers an
+//! actor that implements `actors_interop::panic_context::PanicContext`, so
+//! its handler panics are reported with its state dump.
+//!
+//! `#[interop_actor("rust_ping", requires = ["cpp_pong"])]` holds each
+//! instance's Start until `cpp_pong` is registered in either runtime.
+//!
+//! `#[interop_actor("rust_strategy", deadline_us(MarketUpdate = 500))]`
+//! sets each instance's handler deadline per message type (see
+//! `actors_interop::deadlines`).
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parenthesized, parse_macro_input, Ident, ItemStruct, LitInt, LitStr, Path, Token};
+
+/// `"default_name"`, then optionally `snapshot`, `panic_context`,
+/// `requires = ["name", ...]` and `deadline_us(Message = µs, ...)` in any
+/// order
+struct Args {
+    default_name: LitStr,
+    snapshot: bool,
+    panic_context: bool,
+    requires: Vec<LitStr>,
+    deadlines: Vec<Deadline>,
+}
+
+/// `MarketUpdate = 500`
+struct Deadline {
+    message: Path,
+    micros: LitInt,
+}
+
+impl Parse for Deadline {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let message = input.parse()?;
+        input.parse::<Token![=]>()?;
+        Ok(Deadline { message, micros: input.parse()? })
+    }
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let default_name = input.parse()?;
+        let mut snapshot = false;
+        let mut panic_context = false;
+        let mut requires = Vec::new();
+        let mut deadlines = Vec::new();
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let option: Ident = input.parse()?;
+            if option == "snapshot" {
+                snapshot = true;
+            } else if option == "panic_context" {
+                panic_context = true;
+            } else if option == "requires" {
+                input.parse::<Token![=]>()?;
+                let names;
+                bracketed!(names in input);
+                requires.extend(Punctuated::<LitStr, Token![,]>::parse_terminated(&names)?);
+            } else if option == "deadline_us" {
+                let entries;
+                parenthesized!(entries in input);
+                deadlines.extend(Punctuated::<Deadline, Token![,]>::parse_terminated(&entries)?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    option,
+                    "expected `snapshot`, `panic_context`, `requires = [...]` or `deadline_us(...)`",
+                ));
+            }
+        }
+        Ok(Args { default_name, snapshot, panic_context, requires, deadlines })
+    }
+}
+
+#[proc_macro_attribute]
+pub fn interop_actor(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let Args { default_name, snapshot, panic_context, requires, deadlines } = parse_macro_input!(attr as Args);
+    let input = parse_macro_input!(item as ItemStruct);
+    if !input.generics.params.is_empty() {
+        return syn::Error::new_spanned(&input.generics, "#[interop_actor] does not support generic actors")
+            .to_compile_error()
+            .into();
+    }
+
+    let ident = &input.ident;
+    let type_name = ident.to_string();
+    let actor = if panic_context {
+        quote! { ::actors_interop::panic_context::WithContext::new(#ident::new(handle)) }
+    } else {
+        quote! { #ident::new(handle) }
+    };
+    let build = if snapshot {
+        quote! { ::actors_interop::snapshot::Instance::stateful(#actor) }
+    } else {
+        quote! { ::actors_interop::snapshot::Instance::plain(#actor) }
+    };
+    let deadline_messages = deadlines.iter().map(|d| &d.message);
+    let deadline_micros = deadlines.iter().map(|d| &d.micros);
+    quote! {
+        #input
+
+        ::actors_interop::registry::inventory::submit! {
+            ::actors_interop::registry::ActorType {
+                type_name: #type_name,
+                default_name: #default_name,
+                build: |handle| #build,
+                requires: &[#(#requires),*],
+                deadlines_us: &[#((<#deadline_messages as ::actors_interop::InteropMessage>::MSG_ID, #deadline_micros)),*],
+            }
+        }
+    }
+    .into()
+}
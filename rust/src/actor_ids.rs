@@ -0,0 +1,229 @@
+//! Numeric actor ids negotiated at lookup, for sends without names
+//!
+//! Every send across the bridge names its target, and the receiving side
+//! looks the name up again: a C string built per Rust -> C++ send, a
+//! registry lookup by string per message on either side. At lookup the two
+//! registries also agree on a `u32` id for the name, and sends carry that:
+//!
+//! - C++ -> Rust: `rust_actor_lookup_id(name)` gives the id of a Rust
+//!   actor (aliases resolved); `rust_actor_send_id(id, ..)` delivers to the
+//!   ref kept for it, without a Manager lookup. `RustActorRef` does this by
+//!   itself.
+//! - Rust -> C++: when a lookup finds a C++ actor in a runtime that
+//!   registered id functions (`interop_set_cpp_runtime_ids()`, done by the
+//!   generated `cpp_actor_init()` for the linked C++ code), the runtime is
+//!   asked `cpp_actor_lookup_id(name)`, and `cpp_runtime::send` goes to
+//!   `cpp_actor_send_id(id, ..)` from then on.
+//!
+//! Ids are never reused. They are forgotten with the lookups of their name
+//! (see lookup_cache: lifecycle events, names claimed, C++ calling
+//! `interop_invalidate_lookup()`) and on alias changes; a C++ runtime is
+//! told with `cpp_actor_forget_id()`. A send by a forgotten id returns
+//! `STALE_ACTOR_ID` and the sender falls back to the name, looking the id
+//! up again on the next lookup. Runtimes without id functions and names
+//! that are not actors in a registry (ask mailboxes, shared-thread
+//! members) are always addressed by name.
+
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::{Arc, RwLock};
+
+use actors::ActorRef;
+
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::{aliases, cpp_runtime, dead_letters, interop_error, rust_actor_bridge};
+
+/// Send by an id that is unknown or was forgotten; send by name instead
+pub const STALE_ACTOR_ID: c_int = InteropErrorCode::StaleActorId as c_int;
+
+pub type CppLookupIdFn = unsafe extern "C" fn(name: *const c_char) -> u32;
+pub type CppSendIdFn =
+    unsafe extern "C" fn(actor_id: u32, sender_name: *const c_char, msg_type: c_int, msg_data: *const c_void) -> c_int;
+pub type CppForgetIdFn = unsafe extern "C" fn(actor_id: u32);
+
+/// Id functions of one C++ runtime; mirrors InteropCppRuntimeIds in
+/// interop/actor_ids.h
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CppRuntimeIds {
+    pub lookup_id: CppLookupIdFn,
+    pub send_id: CppSendIdFn,
+    pub fast_send_id: CppSendIdFn,
+    pub forget_id: CppForgetIdFn,
+}
+
+struct RustSlot {
+    name: Arc<CStr>,
+    actor: ActorRef,
+}
+
+#[derive(Default)]
+struct RustIds {
+    by_name: HashMap<String, u32>,
+    // Slot of id n at n - 1; None once forgotten
+    slots: Vec<Option<RustSlot>>,
+}
+
+/// Id a C++ runtime gave one of its actors
+#[derive(Clone)]
+struct CppId {
+    runtime: String,
+    id: u32,
+}
+
+lazy_static::lazy_static! {
+    static ref RUST_IDS: RwLock<RustIds> = RwLock::new(RustIds::default());
+    // C++ actor name -> id in the runtime that owns it
+    static ref CPP_IDS: RwLock<HashMap<String, CppId>> = RwLock::new(HashMap::new());
+}
+
+/// Id of Rust actor `name` (aliases resolved), assigned on first lookup;
+/// None if it is not a Rust actor
+pub fn lookup_id(name: &str) -> Option<u32> {
+    let resolved = aliases::resolve(name);
+    let name = &*resolved;
+    if let Some(&id) = RUST_IDS.read().unwrap().by_name.get(name) {
+        return Some(id);
+    }
+    let actor = match get_actor_ref(name, "") {
+        Some(ActorRef::Cpp(_)) | None => return None,
+        Some(actor) => actor,
+    };
+    let c_name = CString::new(name).ok()?;
+    let mut ids = RUST_IDS.write().unwrap();
+    if let Some(&id) = ids.by_name.get(name) {
+        return Some(id);
+    }
+    ids.slots.push(Some(RustSlot { name: Arc::from(c_name.as_c_str()), actor }));
+    let id = ids.slots.len() as u32;
+    ids.by_name.insert(name.to_string(), id);
+    Some(id)
+}
+
+/// Name and ref of the Rust actor with id `id`
+fn rust_actor(id: u32) -> Option<(Arc<CStr>, ActorRef)> {
+    let ids = RUST_IDS.read().unwrap();
+    let slot = ids.slots.get(id.checked_sub(1)? as usize)?.as_ref()?;
+    Some((slot.name.clone(), slot.actor.clone()))
+}
+
+/// Id C++ actor `name` has in `runtime`, asking it with `lookup_id` if
+/// there is none yet. Called when a lookup finds the actor there.
+pub(crate) fn negotiate_cpp(runtime: &str, ids: CppRuntimeIds, name: &str, name_c: &CStr) -> Option<u32> {
+    if let Some(known) = CPP_IDS.read().unwrap().get(name) {
+        if known.runtime == runtime {
+            return Some(known.id);
+        }
+    }
+    let id = unsafe { (ids.lookup_id)(name_c.as_ptr()) };
+    if id == 0 {
+        return None;
+    }
+    let previous = CPP_IDS.write().unwrap().insert(name.to_string(), CppId { runtime: runtime.to_string(), id });
+    if let Some(previous) = previous.filter(|p| p.runtime != runtime || p.id != id) {
+        forget_in_runtime(previous);
+    }
+    Some(id)
+}
+
+/// Send to C++ actor `name` of `runtime` by id with `send`. None if it has
+/// no id or the id went stale: the caller sends by name.
+pub(crate) fn send_cpp(
+    runtime: &str,
+    ids: CppRuntimeIds,
+    name: &str,
+    name_c: &CStr,
+    send: impl FnOnce(u32) -> c_int,
+) -> Option<c_int> {
+    let id = negotiate_cpp(runtime, ids, name, name_c)?;
+    let rc = send(id);
+    if rc == STALE_ACTOR_ID {
+        CPP_IDS.write().unwrap().remove(name);
+        return None;
+    }
+    Some(rc)
+}
+
+fn forget_in_runtime(cpp: CppId) {
+    if let Some(ids) = cpp_runtime::ids_of(&cpp.runtime) {
+        unsafe { (ids.forget_id)(cpp.id) };
+    }
+}
+
+/// Forget the ids of `name`, Rust or C++. Called with the name's lookups
+/// (see lookup_cache).
+pub fn forget(name: &str) {
+    {
+        let mut ids = RUST_IDS.write().unwrap();
+        if let Some(id) = ids.by_name.remove(name) {
+            ids.slots[id as usize - 1] = None;
+        }
+    }
+    let cpp = CPP_IDS.write().unwrap().remove(name);
+    if let Some(cpp) = cpp {
+        forget_in_runtime(cpp);
+    }
+}
+
+/// Forget every id
+pub fn forget_all() {
+    {
+        let mut ids = RUST_IDS.write().unwrap();
+        ids.by_name.clear();
+        ids.slots.iter_mut().for_each(|slot| *slot = None);
+    }
+    let cpp: Vec<CppId> = CPP_IDS.write().unwrap().drain().map(|(_, cpp)| cpp).collect();
+    cpp.into_iter().for_each(forget_in_runtime);
+}
+
+/// Forget the ids C++ runtime `runtime` gave, without telling it (it is
+/// detached or registered new id functions)
+pub(crate) fn forget_runtime(runtime: &str) {
+    CPP_IDS.write().unwrap().retain(|_, cpp| cpp.runtime != runtime);
+}
+
+/// Id of Rust actor `name` for rust_actor_send_id(), 0 if it is not one
+#[export_name = c_symbol!("rust_actor_lookup_id")]
+pub extern "C" fn rust_actor_lookup_id(name: *const c_char) -> u32 {
+    c_str(name).and_then(lookup_id).unwrap_or(0)
+}
+
+/// rust_actor_send() to the Rust actor with id `actor_id`. Returns as
+/// rust_actor_send() does, or STALE_ACTOR_ID if the id is unknown or was
+/// forgotten.
+#[export_name = c_symbol!("rust_actor_send_id")]
+pub extern "C" fn rust_actor_send_id(
+    actor_id: u32,
+    sender_name: *const c_char,
+    msg_type: c_int,
+    msg_data: *const c_void,
+) -> c_int {
+    let (name, actor) = match rust_actor(actor_id) {
+        Some(a) => a,
+        None => return STALE_ACTOR_ID,
+    };
+    let rc = dead_letters::from_cpp(|| {
+        rust_actor_bridge::send_resolved(name.to_str().unwrap_or(""), Some(actor), sender_name, msg_type, msg_data)
+    });
+    interop_error::report_c(name.as_ptr(), sender_name, msg_type, rc);
+    rc
+}
+
+/// Send to `runtime`'s actors by id (NULL: the linked C++ code). Returns
+/// 0, or NotFound if no such runtime is attached.
+#[export_name = c_symbol!("interop_set_cpp_runtime_ids")]
+pub extern "C" fn interop_set_cpp_runtime_ids(runtime: *const c_char, ids: CppRuntimeIds) -> c_int {
+    let runtime = if runtime.is_null() {
+        cpp_runtime::LINKED
+    } else {
+        match c_str(runtime) {
+            Some(r) => r,
+            None => return InteropErrorCode::NotFound as c_int,
+        }
+    };
+    let rc = if cpp_runtime::set_ids(runtime, ids) { InteropErrorCode::Ok } else { InteropErrorCode::NotFound };
+    rc as c_int
+}
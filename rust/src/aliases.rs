@@ -0,0 +1,213 @@
+//! Logical actor names remapped to concrete actors
+//!
+//! Actors embed the names they send to ("cpp_price_feed"). An alias points
+//! such a name at another actor, Rust or C++, without recompiling them:
+//!
+//! ```ignore
+//! aliases::alias("price_feed", "cpp_price_feed")?;     // logical -> concrete
+//! aliases::alias("cpp_price_feed", "replay_source")?;  // backtest: swap the feed
+//! aliases::load("deploy/aliases.conf")?;               // `name = target` lines
+//! ```
+//!
+//! `get_actor_ref()`, Manager lookups that fall through to the resolver,
+//! and C++ sends to Rust (`rust_actor_send`, `rust_actor_fast_send`,
+//! `rust_actor_borrow_send`, `rust_actor_exists`) resolve aliases first,
+//! so an alias wins over an actor of the same name. Targets may be
+//! aliases themselves; an alias that would close a cycle is refused.
+//! Handlers see the concrete name as the target. Changing an alias
+//! forgets every actor id (see actor_ids), since C++ may hold an id it
+//! got for the alias.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::c_char;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use crate::actor_ids;
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+
+/// Longest alias chain followed
+const MAX_DEPTH: usize = 8;
+
+// Fast path: no lock while no alias is set
+static ANY_ALIASES: AtomicBool = AtomicBool::new(false);
+static ALIASES: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AliasError {
+    /// Empty name or target, or a name aliased to itself
+    BadName,
+    /// The target already resolves to the name
+    Cycle { name: String, target: String },
+    /// More than MAX_DEPTH aliases in a row
+    TooDeep(String),
+    /// A config line that is not `name = target` (1-based)
+    Parse(usize),
+    Io(String),
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AliasError::BadName => write!(f, "empty or self-referencing alias"),
+            AliasError::Cycle { name, target } => write!(f, "{} -> {} would close a cycle", name, target),
+            AliasError::TooDeep(name) => write!(f, "alias chain from {} is longer than {}", name, MAX_DEPTH),
+            AliasError::Parse(line) => write!(f, "line {}: expected name = target", line),
+            AliasError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for AliasError {}
+
+// Follow `name` through `map`, None past MAX_DEPTH (a chain lengthened
+// by aliasing its end)
+fn follow<'a>(map: &'a HashMap<String, String>, name: &'a str) -> Option<&'a str> {
+    let mut current = name;
+    for _ in 0..=MAX_DEPTH {
+        match map.get(current) {
+            Some(next) => current = next,
+            None => return Some(current),
+        }
+    }
+    None
+}
+
+/// Resolve lookups of `name` to `target`, replacing an earlier alias of
+/// `name`
+pub fn alias(name: &str, target: &str) -> Result<(), AliasError> {
+    if name.is_empty() || target.is_empty() || name == target {
+        return Err(AliasError::BadName);
+    }
+    let mut guard = ALIASES.write().unwrap();
+    let map = guard.get_or_insert_with(HashMap::new);
+    // The chain from `target` must not pass `name`
+    let (mut current, mut depth) = (target, 1);
+    while let Some(next) = map.get(current) {
+        if next == name {
+            return Err(AliasError::Cycle { name: name.to_string(), target: target.to_string() });
+        }
+        depth += 1;
+        if depth > MAX_DEPTH {
+            return Err(AliasError::TooDeep(name.to_string()));
+        }
+        current = next;
+    }
+    map.insert(name.to_string(), target.to_string());
+    ANY_ALIASES.store(true, Ordering::Release);
+    drop(guard);
+    actor_ids::forget_all();
+    Ok(())
+}
+
+/// Remove the alias `name`. False if there was none.
+pub fn unalias(name: &str) -> bool {
+    let mut guard = ALIASES.write().unwrap();
+    let removed = guard.as_mut().map_or(false, |map| map.remove(name).is_some());
+    if guard.as_ref().map_or(true, HashMap::is_empty) {
+        ANY_ALIASES.store(false, Ordering::Release);
+    }
+    drop(guard);
+    if removed {
+        actor_ids::forget_all();
+    }
+    removed
+}
+
+/// Remove every alias
+pub fn clear() {
+    *ALIASES.write().unwrap() = None;
+    ANY_ALIASES.store(false, Ordering::Release);
+    actor_ids::forget_all();
+}
+
+/// The concrete name lookups of `name` go to: `name` itself unless it is
+/// an alias
+pub fn resolve(name: &str) -> Cow<'_, str> {
+    if !ANY_ALIASES.load(Ordering::Acquire) {
+        return Cow::Borrowed(name);
+    }
+    let guard = ALIASES.read().unwrap();
+    match guard.as_ref().and_then(|map| follow(map, name)) {
+        Some(target) if target != name => Cow::Owned(target.to_string()),
+        _ => Cow::Borrowed(name),
+    }
+}
+
+/// Every alias and its direct target, sorted by alias
+pub fn aliases() -> Vec<(String, String)> {
+    let guard = ALIASES.read().unwrap();
+    let mut all: Vec<(String, String)> =
+        guard.iter().flat_map(|map| map.iter().map(|(n, t)| (n.clone(), t.clone()))).collect();
+    all.sort();
+    all
+}
+
+/// Set the aliases in `text`: `name = target` per line; blank lines and
+/// `#` comments are skipped. Returns how many were set; stops at the first
+/// bad line, keeping those before it.
+pub fn load_str(text: &str) -> Result<usize, AliasError> {
+    let mut set = 0;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, target) = line.split_once('=').ok_or(AliasError::Parse(i + 1))?;
+        alias(name.trim(), target.trim())?;
+        set += 1;
+    }
+    Ok(set)
+}
+
+/// load_str() with the contents of `path`
+pub fn load(path: impl AsRef<Path>) -> Result<usize, AliasError> {
+    let text = std::fs::read_to_string(path).map_err(|e| AliasError::Io(e.to_string()))?;
+    load_str(&text)
+}
+
+/// Returns 0, InvalidName on a bad name, or InvalidArgument if it would
+/// close a cycle or chain too many aliases
+#[export_name = c_symbol!("interop_alias")]
+pub extern "C" fn interop_alias(name: *const c_char, target: *const c_char) -> i32 {
+    let rc = match (c_str(name), c_str(target)) {
+        (Some(n), Some(t)) => match alias(n, t) {
+            Ok(()) => InteropErrorCode::Ok,
+            Err(AliasError::BadName) => InteropErrorCode::InvalidName,
+            Err(_) => InteropErrorCode::InvalidArgument,
+        },
+        _ => InteropErrorCode::InvalidName,
+    };
+    rc as i32
+}
+
+/// Returns 0, or NotFound if name was not an alias
+#[export_name = c_symbol!("interop_unalias")]
+pub extern "C" fn interop_unalias(name: *const c_char) -> i32 {
+    match c_str(name) {
+        Some(n) if unalias(n) => InteropErrorCode::Ok as i32,
+        _ => InteropErrorCode::NotFound as i32,
+    }
+}
+
+/// Load `name = target` lines from path. Returns how many aliases were
+/// set, or InvalidArgument if the file cannot be read or a line is bad
+/// (see stderr).
+#[export_name = c_symbol!("interop_alias_load")]
+pub extern "C" fn interop_alias_load(path: *const c_char) -> i32 {
+    let path = match c_str(path) {
+        Some(p) => p,
+        None => return InteropErrorCode::InvalidArgument as i32,
+    };
+    match load(path) {
+        Ok(n) => n.min(i32::MAX as usize) as i32,
+        Err(e) => {
+            eprintln!("[Aliases] {}: {}", path, e);
+            InteropErrorCode::InvalidArgument as i32
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Bytes the bridge holds on behalf of each Rust actor
+//!
+//! Every message the bridge queues for a Rust actor is converted from its C
+//! struct and boxed in a queue_depth envelope, with envelope metadata
+//! if the actor takes it (see envelope). Those bytes are charged to the
+//! target actor when the message is queued and given back when it is
+//! dropped after handling, so an actor whose backlog balloons shows up as
+//! the owner of the memory:
+//!
+//! ```cpp
+//! RustActorAllocStats st;
+//! if (rust_actor_alloc_stats("rust_strategy", &st) == 0 && st.live_bytes > (64 << 20)) { ... }
+//! ```
+//!
+//! Sizes are what the bridge allocates: the envelope with the converted
+//! message inline, plus the variable-length part of its C size (byte
+//! payloads, arrays, maps). Allocator overhead and what handlers allocate
+//! are not counted. `live_envelope_bytes` in `rust_manager_stats()` is the
+//! total over all actors; the health summary has each actor's `alloc_bytes`.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use actors::Message;
+
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{c_struct_size, message_size};
+use crate::names::c_str;
+
+/// Mirrors RustActorAllocStats in interop/health.h
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RustActorAllocStats {
+    /// Held now: queued or being handled
+    pub live_bytes: u64,
+    /// Highest live_bytes seen
+    pub peak_bytes: u64,
+    /// Charged since the actor's first message
+    pub total_bytes: u64,
+    /// Messages charged since the actor's first message
+    pub allocations: u64,
+}
+
+#[derive(Default)]
+struct Account {
+    live: AtomicU64,
+    peak: AtomicU64,
+    total: AtomicU64,
+    allocations: AtomicU64,
+}
+
+lazy_static::lazy_static! {
+    static ref ACCOUNTS: Mutex<HashMap<String, Arc<Account>>> = Mutex::new(HashMap::new());
+}
+
+fn account(name: &str) -> Arc<Account> {
+    let mut accounts = ACCOUNTS.lock().unwrap();
+    // Look up by &str first so the hot path does not allocate a key
+    if let Some(a) = accounts.get(name) {
+        return a.clone();
+    }
+    let a = Arc::new(Account::default());
+    accounts.insert(name.to_string(), a.clone());
+    a
+}
+
+/// Bytes charged to an actor; given back on drop
+pub(crate) struct Charge {
+    account: Arc<Account>,
+    bytes: u64,
+}
+
+impl Charge {
+    /// Charge `extra` more bytes, e.g. envelope metadata recorded later
+    pub(crate) fn add(&mut self, extra: usize) {
+        let extra = extra as u64;
+        self.bytes += extra;
+        self.account.total.fetch_add(extra, Ordering::Relaxed);
+        let live = self.account.live.fetch_add(extra, Ordering::Relaxed) + extra;
+        self.account.peak.fetch_max(live, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Charge {
+    fn drop(&mut self) {
+        self.account.live.fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+/// Charge `bytes` for one message queued for `actor`
+pub(crate) fn charge(actor: &str, bytes: usize) -> Charge {
+    let account = account(actor);
+    account.allocations.fetch_add(1, Ordering::Relaxed);
+    let mut charge = Charge { account, bytes: 0 };
+    charge.add(bytes);
+    charge
+}
+
+/// Heap bytes of `msg` outside its struct: the variable-length part of its
+/// C size
+pub(crate) fn heap_bytes(msg: &dyn Message) -> usize {
+    match (message_size(msg), c_struct_size(msg.message_id())) {
+        (Some(size), Some(fixed)) => size.saturating_sub(fixed),
+        _ => 0,
+    }
+}
+
+/// Counters of `actor`; None before its first bridged message
+pub fn actor_stats(actor: &str) -> Option<RustActorAllocStats> {
+    let account = ACCOUNTS.lock().unwrap().get(actor)?.clone();
+    Some(RustActorAllocStats {
+        live_bytes: account.live.load(Ordering::Relaxed),
+        peak_bytes: account.peak.load(Ordering::Relaxed),
+        total_bytes: account.total.load(Ordering::Relaxed),
+        allocations: account.allocations.load(Ordering::Relaxed),
+    })
+}
+
+/// Bytes held now for all actors
+pub fn total_live_bytes() -> u64 {
+    ACCOUNTS.lock().unwrap().values().map(|a| a.live.load(Ordering::Relaxed)).sum()
+}
+
+/// Actors by bytes held now, most first
+pub fn top_actors(n: usize) -> Vec<(String, u64)> {
+    let mut all: Vec<(String, u64)> = ACCOUNTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, a)| (name.clone(), a.live.load(Ordering::Relaxed)))
+        .collect();
+    all.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    all.truncate(n);
+    all
+}
+
+fn c_out<'a, T>(out: *mut T) -> Option<&'a mut T> {
+    unsafe { out.as_mut() }
+}
+
+/// Fill `out` with the counters of `actor_name`; zeros before its first
+/// bridged message. Returns 0, or InvalidArgument on a bad name or null
+/// `out`.
+#[export_name = c_symbol!("rust_actor_alloc_stats")]
+pub extern "C" fn rust_actor_alloc_stats(actor_name: *const c_char, out: *mut RustActorAllocStats) -> c_int {
+    let (name, out) = match (c_str(actor_name), c_out(out)) {
+        (Some(n), Some(o)) => (n, o),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    *out = actor_stats(name).unwrap_or_default();
+    0
+}
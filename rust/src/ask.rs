@@ -0,0 +1,547 @@
+//! Request/response with a typed reply and a timeout
+//!
+//! ```ignore
+//! use actors_interop::ask::Ask;
+//!
+//! let pong: Pong = pong_ref.ask(Ping { count: 1 }, Duration::from_millis(100))?;
+//! ```
+//!
+//! Each ask opens a temporary reply mailbox named `__ask-<id>`, where the
+//! id correlates the reply with the request, and sends the request with
+//! that mailbox as sender. The target answers with its usual
+//! `ctx.reply()` (Rust) or `reply()` (C++, through the bridge's sender
+//! proxy, which calls `rust_actor_send("__ask-<id>", ..)`). The first
+//! message that arrives is the reply and closes the mailbox.
+//!
+//! `ask` blocks the calling thread. Called from an actor, that actor
+//! handles nothing else until the reply or the timeout; never ask an actor
+//! that may ask back. `ask_start` sends the request and returns a
+//! `PendingAsk` to wait on later:
+//!
+//! ```ignore
+//! let pending = ask::ask_start(&pricer_ref, Box::new(request));
+//! let id = pending.id();               // ask::cancel(id) from anywhere
+//! match pending.wait(Duration::from_secs(2)) { .. }
+//! ```
+//!
+//! An actor that must not block asks with `ctx.ask_then()` instead. The
+//! reply is queued to the asking actor like any message and the
+//! continuation runs with it on the actor's thread, with the actor and
+//! context, as a handler would:
+//!
+//! ```ignore
+//! use actors_interop::ask::AskThen;
+//!
+//! fn on_tick(&mut self, _tick: &Tick, ctx: &mut ActorContext) {
+//!     let pricer = get_actor_ref("cpp_pricer", "rust_quoter").unwrap();
+//!     let asked = ctx.ask_then::<Pong, Self>(&pricer, Ping { count: self.seq }, |this, pong, _ctx| {
+//!         this.last = pong.count;      // later, on this actor's thread
+//!     });
+//!     self.pending = asked.ok();
+//! }
+//! ```
+//!
+//! The actor must run under supervision (see lifecycle) and dispatch with
+//! `handle_messages!`, or call `run_continuation()` first in its own
+//! `process_message`. There is no timeout: `cancel(id)` with the returned
+//! id drops the continuation. A reply of another type is dropped with a
+//! warning.
+//!
+//! An ask that ends without a reply - cancelled with `cancel(id)`, its
+//! `PendingAsk` dropped, or timed out - sends an `AskCancel` with its id
+//! to the target, so the responder (Rust or C++) can stop expensive work
+//! on it; `is_cancelled(reply_to)` answers the same while a handler runs.
+//! Replies arriving after the mailbox is closed are dead letters.
+//!
+//! `scatter_gather` asks several actors, Rust or C++, the same question at
+//! once and waits for all the answers, in target order:
+//!
+//! ```ignore
+//! let quotes: Vec<Result<Quote, AskError>> =
+//!     ask::scatter_gather(&[pricer_a, pricer_b, cpp_pricer], QuoteRequest { qty: 100 }, Duration::from_millis(50));
+//! ```
+//!
+//! Each target gets its own copy and reply mailbox, so a reply is matched
+//! to its request whatever order they arrive in. The timeout covers the
+//! whole call; a target still silent at the deadline gets
+//! `Err(Timeout)` and an `AskCancel`. It blocks like `ask`.
+//!
+//! C++ asks a Rust actor with `rust_actor_ask()`, which copies the reply's
+//! C struct into a caller buffer. Replies with `interop_bytes` fields hold
+//! pointers and cannot be returned this way.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actors::{ActorContext, ActorRef, CppActorRef, Message};
+
+use crate::boundary::{self, Direction};
+use crate::dead_letters;
+use crate::health;
+use crate::inbound;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_log;
+use crate::interop_messages::{
+    AskCancel, c_struct_is_portable, c_struct_size, clone_message, give_back_message_bytes, message_from_c,
+    message_to_c_bytes, normalize_message, validate_message,
+};
+use crate::names::c_str;
+use crate::policy;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::sender_auth::Entry;
+use crate::shutdown;
+use crate::size_limit;
+use crate::transaction;
+use crate::validation;
+
+/// Reply copied out
+pub const ASK_OK: c_int = InteropErrorCode::Ok as c_int;
+/// No Rust actor with that name, or no Manager yet
+pub const ASK_NOT_FOUND: c_int = InteropErrorCode::NotFound as c_int;
+/// Unknown request ID, or a reply that has no C struct to copy
+pub const ASK_UNKNOWN_MESSAGE: c_int = InteropErrorCode::UnknownMessage as c_int;
+/// No reply within the timeout
+pub const ASK_TIMEOUT: c_int = InteropErrorCode::Timeout as c_int;
+/// Null name, request or out-parameter
+pub const ASK_INVALID: c_int = InteropErrorCode::InvalidArgument as c_int;
+/// Reply larger than the buffer; its ID is stored, the reply is dropped
+pub const ASK_BUFFER_TOO_SMALL: c_int = InteropErrorCode::BufferTooSmall as c_int;
+/// Refused by the bridge policy (see policy.rs) or sender auth (see
+/// sender_auth.rs)
+pub const ASK_DENIED: c_int = policy::POLICY_DENIED;
+/// Over the bridge size limit (see size_limit.rs)
+pub const ASK_TOO_LARGE: c_int = size_limit::MESSAGE_TOO_LARGE;
+/// Shutdown has been requested (see shutdown.rs)
+pub const ASK_SHUTTING_DOWN: c_int = shutdown::SHUTTING_DOWN;
+/// Request breaks a field rule of its type (see validation.rs)
+pub const ASK_INVALID_MESSAGE: c_int = validation::INVALID_MESSAGE;
+
+/// Prefix of reply mailbox names; not for actor names
+pub const ASK_PREFIX: &str = "__ask-";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AskError {
+    /// No reply within the timeout
+    Timeout,
+    /// The reply was not the expected type; carries its message ID
+    UnexpectedReply(i32),
+    /// Cancelled with `cancel()` before the reply
+    Cancelled,
+    /// `ask_then` outside a supervised actor's handler
+    NotInHandler,
+}
+
+impl fmt::Display for AskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AskError::Timeout => write!(f, "no reply before the timeout"),
+            AskError::UnexpectedReply(id) => write!(f, "unexpected reply type (message {})", id),
+            AskError::Cancelled => write!(f, "ask cancelled"),
+            AskError::NotInHandler => write!(f, "ask_then called outside a supervised actor's handler"),
+        }
+    }
+}
+
+impl std::error::Error for AskError {}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+// Where a reply goes
+enum Reply {
+    // A thread in PendingAsk::wait
+    Wait(Sender<Box<dyn Message>>),
+    // A continuation queued to the asking actor (see ask_then)
+    Then(Box<dyn FnOnce(Box<dyn Message>) + Send>),
+}
+
+// An open reply mailbox
+struct Waiter {
+    reply: Reply,
+    // The request's target, told when the ask ends without a reply
+    target: ActorRef,
+}
+
+lazy_static::lazy_static! {
+    // Open reply mailboxes by name
+    static ref PENDING: Mutex<HashMap<String, Waiter>> = Mutex::new(HashMap::new());
+}
+
+fn mailbox_name(id: u64) -> String {
+    format!("{}{}", ASK_PREFIX, id)
+}
+
+/// Id of the ask whose reply mailbox is `reply_to`
+pub fn correlation_id(reply_to: &str) -> Option<u64> {
+    reply_to.strip_prefix(ASK_PREFIX)?.parse().ok()
+}
+
+// Tell the target of an ask that ended without a reply
+fn send_cancel(id: u64, waiter: Waiter) {
+    waiter.target.send(Box::new(AskCancel { correlation_id: id as i64 }), None);
+}
+
+// Closes the mailbox however the ask ends
+struct Mailbox {
+    id: u64,
+    name: String,
+    rx: Receiver<Box<dyn Message>>,
+}
+
+impl Mailbox {
+    fn open() -> (Self, Sender<Box<dyn Message>>) {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel();
+        (Mailbox { id, name: mailbox_name(id), rx }, tx)
+    }
+}
+
+impl Drop for Mailbox {
+    fn drop(&mut self) {
+        // Still open: no reply came and nobody cancelled
+        let waiter = PENDING.lock().unwrap().remove(&self.name);
+        if let Some(waiter) = waiter {
+            send_cancel(self.id, waiter);
+        }
+    }
+}
+
+/// send_fn of reply refs: move the reply into the mailbox, closing it
+fn reply_send_fn(target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    let waiter = PENDING.lock().unwrap().remove(target);
+    let waiter = match waiter {
+        Some(w) => w,
+        None => {
+            // Answered, cancelled or timed out before
+            health::record_dead_letter(sender, target, msg.message_id());
+            return ASK_NOT_FOUND;
+        }
+    };
+    match (clone_message(msg), waiter.reply) {
+        (Some(copy), Reply::Wait(tx)) => {
+            let _ = tx.send(copy);
+            ASK_OK
+        }
+        (Some(copy), Reply::Then(then)) => {
+            then(copy);
+            ASK_OK
+        }
+        (None, _) => ASK_UNKNOWN_MESSAGE,
+    }
+}
+
+/// ActorRef for an open reply mailbox, None if `name` is not one.
+/// The bridge uses this to route C++ replies.
+pub fn reply_ref(name: &str) -> Option<ActorRef> {
+    if !name.starts_with(ASK_PREFIX) || !PENDING.lock().unwrap().contains_key(name) {
+        return None;
+    }
+    Some(ActorRef::Cpp(CppActorRef::new(name, "", reply_send_fn)))
+}
+
+/// Synchronous request/response on an ActorRef
+pub trait Ask {
+    /// Send `msg` and wait up to `timeout` for a reply of type `R`
+    fn ask<R, M>(&self, msg: M, timeout: Duration) -> Result<R, AskError>
+    where
+        R: Message + Clone,
+        M: Message;
+}
+
+impl Ask for ActorRef {
+    fn ask<R, M>(&self, msg: M, timeout: Duration) -> Result<R, AskError>
+    where
+        R: Message + Clone,
+        M: Message,
+    {
+        let reply = ask_message(self, Box::new(msg), timeout)?;
+        reply
+            .as_any()
+            .downcast_ref::<R>()
+            .cloned()
+            .ok_or(AskError::UnexpectedReply(reply.message_id()))
+    }
+}
+
+/// An ask sent but not waited for; dropping it before the reply cancels it
+pub struct PendingAsk {
+    mailbox: Mailbox,
+}
+
+impl PendingAsk {
+    /// Correlation id, for `cancel()`; the reply mailbox is `__ask-<id>`
+    pub fn id(&self) -> u64 {
+        self.mailbox.id
+    }
+
+    /// The reply if it has arrived
+    pub fn try_reply(&self) -> Option<Box<dyn Message>> {
+        self.mailbox.rx.try_recv().ok()
+    }
+
+    /// Wait up to `timeout` for the reply; cancels the ask on a timeout
+    pub fn wait(self, timeout: Duration) -> Result<Box<dyn Message>, AskError> {
+        self.mailbox.rx.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => AskError::Timeout,
+            RecvTimeoutError::Disconnected => AskError::Cancelled,
+        })
+    }
+
+    /// Cancel the ask: same as dropping it
+    pub fn cancel(self) {}
+}
+
+// Register mailbox `name` and send the request with it as the reply-to
+fn send_request(target: &ActorRef, name: &str, reply: Reply, msg: Box<dyn Message>) {
+    let (request_ref, reply_to) = match target {
+        // The C++ side replies to the sender name carried by the ref
+        ActorRef::Cpp(cpp) => (ActorRef::Cpp(CppActorRef::new(&cpp.target, name, cpp.send_fn)), None),
+        other => (other.clone(), Some(ActorRef::Cpp(CppActorRef::new(name, "", reply_send_fn)))),
+    };
+    PENDING.lock().unwrap().insert(name.to_string(), Waiter { reply, target: request_ref.clone() });
+    request_ref.send(msg, reply_to);
+}
+
+/// Send `msg` to `target` with a fresh reply mailbox and return without
+/// waiting
+pub fn ask_start(target: &ActorRef, msg: Box<dyn Message>) -> PendingAsk {
+    let (mailbox, tx) = Mailbox::open();
+    send_request(target, &mailbox.name, Reply::Wait(tx), msg);
+    PendingAsk { mailbox }
+}
+
+type ContinuationFn = Box<dyn FnOnce(&mut dyn Any, &mut ActorContext) + Send>;
+
+/// A reply to `ask_then`, queued to the asking actor; handled by
+/// `run_continuation()`
+pub struct Continuation {
+    msg_id: i32,
+    run: Mutex<Option<ContinuationFn>>,
+}
+
+impl Message for Continuation {
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+    fn message_id(&self) -> i32 { self.msg_id }
+}
+
+/// `ActorContext::ask_then()`
+pub trait AskThen {
+    /// Send `msg` to `target` without waiting. The reply, if of type `R`,
+    /// is queued to this actor (of type `A`) and `then` runs with it on the
+    /// actor's thread. Returns the ask's id, for `cancel()`.
+    fn ask_then<R, A>(
+        &mut self,
+        target: &ActorRef,
+        msg: impl Message + 'static,
+        then: impl FnOnce(&mut A, R, &mut ActorContext) + Send + 'static,
+    ) -> Result<u64, AskError>
+    where
+        R: Message + Clone + 'static,
+        A: 'static;
+}
+
+impl AskThen for ActorContext {
+    fn ask_then<R, A>(
+        &mut self,
+        target: &ActorRef,
+        msg: impl Message + 'static,
+        then: impl FnOnce(&mut A, R, &mut ActorContext) + Send + 'static,
+    ) -> Result<u64, AskError>
+    where
+        R: Message + Clone + 'static,
+        A: 'static,
+    {
+        let asker = transaction::current_actor().ok_or(AskError::NotInHandler)?;
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let on_reply = move |reply: Box<dyn Message>| {
+            let msg_id = reply.message_id();
+            let reply = match reply.as_any().downcast_ref::<R>() {
+                Some(r) => r.clone(),
+                None => {
+                    eprintln!("[Ask] {}: ask {} answered with unexpected message {}", asker, id, msg_id);
+                    return;
+                }
+            };
+            let run: ContinuationFn = Box::new(move |actor, ctx| match actor.downcast_mut::<A>() {
+                Some(actor) => then(actor, reply, ctx),
+                None => eprintln!("[Ask] continuation of ask {} run by another actor type", id),
+            });
+            match get_actor_ref(&asker, "") {
+                Some(actor) => actor.send(Box::new(Continuation { msg_id, run: Mutex::new(Some(run)) }), None),
+                None => health::record_dead_letter("", &asker, msg_id),
+            }
+        };
+        send_request(target, &mailbox_name(id), Reply::Then(Box::new(on_reply)), Box::new(msg));
+        Ok(id)
+    }
+}
+
+/// Run `msg` on `actor` if it is an `ask_then` reply; false for any other
+/// message. `handle_messages!` calls it first.
+pub fn run_continuation<A: 'static>(actor: &mut A, msg: &dyn Message, ctx: &mut ActorContext) -> bool {
+    let continuation = match msg.as_any().downcast_ref::<Continuation>() {
+        Some(c) => c,
+        None => return false,
+    };
+    let run = continuation.run.lock().unwrap().take();
+    if let Some(run) = run {
+        run(actor, ctx);
+    }
+    true
+}
+
+/// `ask` for callers that do not know the reply type: returns whatever
+/// message arrives first
+pub fn ask_message(target: &ActorRef, msg: Box<dyn Message>, timeout: Duration) -> Result<Box<dyn Message>, AskError> {
+    ask_start(target, msg).wait(timeout)
+}
+
+/// Ask every target for `msg` at once and wait up to `timeout` in all for
+/// replies of type `R`; one result per target, in the order of `targets`
+pub fn scatter_gather<R, M>(targets: &[ActorRef], msg: M, timeout: Duration) -> Vec<Result<R, AskError>>
+where
+    R: Message + Clone,
+    M: Message + Clone,
+{
+    let deadline = Instant::now() + timeout;
+    let pending: Vec<PendingAsk> = targets.iter().map(|target| ask_start(target, Box::new(msg.clone()))).collect();
+    pending
+        .into_iter()
+        .map(|ask| {
+            let reply = ask.wait(deadline.saturating_duration_since(Instant::now()))?;
+            reply.as_any().downcast_ref::<R>().cloned().ok_or(AskError::UnexpectedReply(reply.message_id()))
+        })
+        .collect()
+}
+
+/// Cancel ask `id`: its waiter gets `AskError::Cancelled`, the target an
+/// `AskCancel`, and a later reply goes to dead letters. False if the ask
+/// is not waiting for a reply.
+pub fn cancel(id: u64) -> bool {
+    let waiter = PENDING.lock().unwrap().remove(&mailbox_name(id));
+    match waiter {
+        Some(waiter) => {
+            send_cancel(id, waiter);
+            true
+        }
+        None => false,
+    }
+}
+
+/// True once nobody waits for a reply to `reply_to`, an ask mailbox
+/// (`__ask-<id>`): the ask was answered, cancelled or timed out. For
+/// responders checking during long work; false for other senders.
+pub fn is_cancelled(reply_to: &str) -> bool {
+    correlation_id(reply_to).is_some_and(|id| id < NEXT_ID.load(Ordering::Relaxed))
+        && !PENDING.lock().unwrap().contains_key(reply_to)
+}
+
+/// Send `msg_data` (C struct of `msg_type`) to Rust actor `actor_name` and
+/// block up to `timeout_ms` for its reply. On ASK_OK the reply's message ID
+/// is in `*reply_msg_id_out` and its C struct in `reply_buf`. Returns
+/// ASK_OK, ASK_NOT_FOUND, ASK_UNKNOWN_MESSAGE, ASK_TIMEOUT, ASK_INVALID,
+/// ASK_BUFFER_TOO_SMALL, ASK_DENIED, ASK_TOO_LARGE, ASK_SHUTTING_DOWN or
+/// ASK_INVALID_MESSAGE.
+#[export_name = c_symbol!("rust_actor_ask")]
+pub extern "C" fn rust_actor_ask(
+    actor_name: *const c_char,
+    msg_type: c_int,
+    msg_data: *const c_void,
+    reply_msg_id_out: *mut c_int,
+    reply_buf: *mut c_void,
+    reply_len: usize,
+    timeout_ms: u32,
+) -> c_int {
+    let rc = dead_letters::from_cpp(|| {
+        ask_actor(actor_name, msg_type, msg_data, reply_msg_id_out, reply_buf, reply_len, timeout_ms)
+    });
+    interop_log::send_c(actor_name, std::ptr::null(), msg_type, rc);
+    rc
+}
+
+fn ask_actor(
+    actor_name: *const c_char,
+    msg_type: c_int,
+    msg_data: *const c_void,
+    reply_msg_id_out: *mut c_int,
+    reply_buf: *mut c_void,
+    reply_len: usize,
+    timeout_ms: u32,
+) -> c_int {
+    if msg_data.is_null() || reply_msg_id_out.is_null() || reply_buf.is_null() {
+        return ASK_INVALID;
+    }
+    let name = match c_str(actor_name) {
+        Some(s) => s,
+        None => return ASK_INVALID,
+    };
+    if !shutdown::accepting() {
+        return ASK_SHUTTING_DOWN;
+    }
+    if c_struct_size(msg_type).is_none() {
+        health::record_unknown_message("", name, msg_type);
+        return ASK_UNKNOWN_MESSAGE;
+    }
+    if let Err(rc) = unsafe { inbound::admit(name, "", msg_type, Entry::Ask, msg_data) } {
+        return rc;
+    }
+    let actor = match get_actor_ref(name, "") {
+        Some(ActorRef::Cpp(_)) | None => {
+            health::record_dead_letter("", name, msg_type);
+            return ASK_NOT_FOUND;
+        }
+        Some(actor) => actor,
+    };
+    let mut msg = match unsafe { message_from_c(msg_type, msg_data) } {
+        Some(m) => m,
+        None => return ASK_UNKNOWN_MESSAGE,
+    };
+    boundary::record(Direction::ToRust, msg_type);
+    interop_log::conversion(Direction::ToRust, "", name, msg.as_ref());
+    if let Err(e) = validate_message(msg.as_ref()).and_then(|()| normalize_message(msg.as_mut())) {
+        // A take-mode buffer stays the asker's to free
+        unsafe { give_back_message_bytes(msg.as_mut(), msg_data) };
+        validation::reject(name, "", &e);
+        return ASK_INVALID_MESSAGE;
+    }
+    let reply = match ask_message(&actor, msg, Duration::from_millis(timeout_ms as u64)) {
+        Ok(r) => r,
+        Err(_) => return ASK_TIMEOUT,
+    };
+    let reply_id = reply.message_id();
+    let bytes = match message_to_c_bytes(reply.as_ref()).filter(|_| c_struct_is_portable(reply_id)) {
+        Some(b) => b,
+        None => {
+            eprintln!("[Ask] {}: reply {} has no C struct to return", name, reply_id);
+            return ASK_UNKNOWN_MESSAGE;
+        }
+    };
+    unsafe { *reply_msg_id_out = reply_id };
+    if bytes.len() > reply_len {
+        return ASK_BUFFER_TOO_SMALL;
+    }
+    boundary::record(Direction::ToCpp, reply_id);
+    interop_log::conversion(Direction::ToCpp, name, "", reply.as_ref());
+    unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), reply_buf as *mut u8, bytes.len()) };
+    ASK_OK
+}
+
+/// Cancel ask `correlation_id` (see interop/ask.h). Returns 0, or
+/// NotFound if it is not waiting for a reply.
+#[export_name = c_symbol!("rust_ask_cancel")]
+pub extern "C" fn rust_ask_cancel(correlation_id: u64) -> c_int {
+    if cancel(correlation_id) { ASK_OK } else { ASK_NOT_FOUND }
+}
+
+/// 1 once nobody waits for a reply to `reply_to` (an `__ask-<id>` sender
+/// name), else 0
+#[export_name = c_symbol!("rust_ask_cancelled")]
+pub extern "C" fn rust_ask_cancelled(reply_to: *const c_char) -> c_int {
+    c_str(reply_to).map_or(0, |name| is_cancelled(name) as c_int)
+}
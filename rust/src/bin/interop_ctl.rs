@@ -0,0 +1,106 @@
+//! interop-ctl: inspect a running interop process over its control socket
+//!
+//! ```text
+//! interop-ctl actors                              # Rust actors, status, queue depth
+//! interop-ctl metrics                             # health summary as JSON
+//! interop-ctl samples                             # sampled bridge messages as JSON
+//! interop-ctl threads                             # CPU time per thread, Rust actors marked
+//! interop-ctl subscriptions                       # broker subscriptions as JSON, Rust / C++ marked
+//! interop-ctl tap cpp_feed quotes                 # print messages on a topic until ^C
+//! interop-ctl send rust_pong Ping count=7         # send a test message
+//! interop-ctl freeze rust_pong                    # stop it handling messages
+//! interop-ctl pending rust_pong                   # what it has been sent since, as JSON
+//! interop-ctl drop rust_pong 2                    # discard one
+//! interop-ctl move rust_pong 3 0                  # handle one first
+//! interop-ctl thaw rust_pong                      # handle them, then carry on
+//! interop-ctl log trace rust_pong                 # log its bridge traffic with payloads
+//! interop-ctl log off                             # bridge logging off for the process
+//! interop-ctl --tenant fx metrics                 # only the fx desk's actors
+//! ```
+//!
+//! The process opens the socket with `control::listen()` or
+//! `interop_control_listen()`. The socket is `--socket PATH`, else
+//! `$INTEROP_CTL_SOCKET`, else `/tmp/actors-interop.sock`. `--tenant NAME`
+//! narrows the reply to actors labelled with that tenant.
+//!
+//! Exits 0 on success, 1 if the process reports an error, 2 on a usage or
+//! connection error.
+
+use std::env;
+use std::process;
+
+use actors_interop::control::DEFAULT_SOCKET;
+
+fn fail(what: impl std::fmt::Display) -> ! {
+    eprintln!("interop-ctl: {}", what);
+    process::exit(2);
+}
+
+const USAGE: &str = "usage: interop-ctl [--socket PATH] [--tenant NAME] actors | metrics | samples | threads | subscriptions | tap PUBLISHER TOPIC | send TARGET MESSAGE [FIELD=VALUE ...] | freeze|pending|thaw ACTOR | drop ACTOR INDEX | move ACTOR FROM TO | log LEVEL [ACTOR]";
+
+#[cfg(unix)]
+fn run(socket: &str, args: &[String]) -> bool {
+    use std::io::{self, BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket).unwrap_or_else(|e| fail(format_args!("cannot connect to {}: {}", socket, e)));
+    if let Err(e) = writeln!(stream, "{}", args.join("\t")) {
+        fail(format_args!("cannot send to {}: {}", socket, e));
+    }
+    let mut ok = true;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => fail(format_args!("lost {}: {}", socket, e)),
+        };
+        if line.starts_with("error:") {
+            ok = false;
+            eprintln!("{}", line);
+        } else if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+            break;  // stdout closed, e.g. tap | head
+        }
+    }
+    ok
+}
+
+#[cfg(not(unix))]
+fn run(_socket: &str, _args: &[String]) -> bool {
+    fail("the control socket needs unix sockets")
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut socket = env::var("INTEROP_CTL_SOCKET").unwrap_or_else(|_| DEFAULT_SOCKET.to_string());
+    if args.first().map(String::as_str) == Some("--socket") {
+        if args.len() < 2 {
+            fail(USAGE);
+        }
+        socket = args.remove(1);
+        args.remove(0);
+    }
+    let mut tenant = None;
+    if args.first().map(String::as_str) == Some("--tenant") {
+        if args.len() < 2 {
+            fail(USAGE);
+        }
+        tenant = Some(args.remove(1));
+        args.remove(0);
+    }
+    match args.first().map(String::as_str) {
+        Some("actors") | Some("metrics") | Some("samples") | Some("threads") | Some("subscriptions")
+            if args.len() == 1 => {}
+        Some("tap") if args.len() == 3 => {}
+        Some("send") if args.len() >= 3 => {}
+        Some("freeze") | Some("pending") | Some("thaw") if args.len() == 2 => {}
+        Some("drop") if args.len() == 3 => {}
+        Some("move") if args.len() == 4 => {}
+        Some("log") if args.len() == 2 || args.len() == 3 => {}
+        _ => fail(USAGE),
+    }
+    if let Some(tenant) = tenant {
+        args.splice(0..0, ["tenant".to_string(), tenant]);
+    }
+    process::exit(if run(&socket, &args) { 0 } else { 1 });
+}
@@ -0,0 +1,199 @@
+//! interop-gen: regenerate, diff and validate the generated modules
+//!
+//! ```text
+//! cargo run --bin interop-gen -- generate   # generated/ from the message schemas
+//! cargo run --bin interop-gen -- diff       # generated/ matches the schemas?
+//! cargo run --bin interop-gen -- validate   # C header and Rust structs agree?
+//! cargo run --bin interop-gen -- check      # diff + validate, for CI
+//! ```
+//!
+//! The schemas are `messages/interop_messages.h` plus every .proto in
+//! `messages/` except `interop.proto`, as in the Makefile. `diff`
+//! regenerates into a temporary directory and reports files of
+//! `generated/` that are missing, extra or different, so a schema edit
+//! without a regenerate is caught. `validate` compiles a C program (with
+//! `$CC`, default `cc`) that prints the size and alignment of every
+//! message struct as the C compiler lays it out, and compares them with
+//! the Rust `#[repr(C)]` structs this binary was built with.
+//!
+//! Exits 0 when everything matches, 1 on drift, 2 on a usage or tool error.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use actors_interop::interop_messages::C_STRUCT_LAYOUTS;
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+/// Schemas in the order the Makefile passes them
+fn schemas(root: &Path) -> Vec<PathBuf> {
+    let mut protos: Vec<PathBuf> = fs::read_dir(root.join("messages"))
+        .map(|dir| dir.filter_map(|e| e.ok().map(|e| e.path())).collect())
+        .unwrap_or_default();
+    protos.retain(|p| p.extension().is_some_and(|e| e == "proto") && !p.ends_with("interop.proto"));
+    protos.sort();
+    let mut all = vec![root.join("messages/interop_messages.h")];
+    all.extend(protos);
+    all
+}
+
+fn fail(what: impl std::fmt::Display) -> ! {
+    eprintln!("interop-gen: {}", what);
+    process::exit(2);
+}
+
+/// Run codegen/generate.py into `out`
+fn generate(root: &Path, out: &Path) {
+    let python = env::var("PYTHON").unwrap_or_else(|_| "python3".to_string());
+    let output = Command::new(&python)
+        .arg(root.join("codegen/generate.py"))
+        .args(schemas(root))
+        .arg(out)
+        .output()
+        .unwrap_or_else(|e| fail(format_args!("cannot run {}: {}", python, e)));
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        fail("codegen/generate.py failed");
+    }
+}
+
+fn temp_dir(what: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("interop-gen-{}-{}", what, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap_or_else(|e| fail(format_args!("{}: {}", dir.display(), e)));
+    dir
+}
+
+/// Files under `dir`, relative to it
+fn files(dir: &Path) -> BTreeSet<PathBuf> {
+    fn walk(base: &Path, dir: &Path, out: &mut BTreeSet<PathBuf>) {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(base, &path, out);
+            } else if let Ok(rel) = path.strip_prefix(base) {
+                out.insert(rel.to_path_buf());
+            }
+        }
+    }
+    let mut out = BTreeSet::new();
+    walk(dir, dir, &mut out);
+    out
+}
+
+/// Differences between generated/ and a fresh generation; one line each
+fn diff(root: &Path) -> Vec<String> {
+    let fresh = temp_dir("diff");
+    generate(root, &fresh);
+    let current = root.join("generated");
+    let (want, have) = (files(&fresh), files(&current));
+    let mut problems = Vec::new();
+    for rel in want.difference(&have) {
+        problems.push(format!("generated/{}: missing", rel.display()));
+    }
+    for rel in have.difference(&want) {
+        problems.push(format!("generated/{}: not produced by the schemas", rel.display()));
+    }
+    for rel in want.intersection(&have) {
+        let a = fs::read_to_string(fresh.join(rel)).unwrap_or_default();
+        let b = fs::read_to_string(current.join(rel)).unwrap_or_default();
+        if a != b {
+            let line = a.lines().zip(b.lines()).position(|(x, y)| x != y).unwrap_or(a.lines().count().min(b.lines().count()));
+            problems.push(format!("generated/{}: differs from line {}", rel.display(), line + 1));
+        }
+    }
+    let _ = fs::remove_dir_all(&fresh);
+    problems
+}
+
+/// Size and alignment mismatches between the C compiler and Rust
+fn validate(root: &Path) -> Vec<String> {
+    let dir = temp_dir("validate");
+    let mut program = String::from("#include <stdio.h>\n#include \"interop_messages.h\"\n");
+    if root.join("generated/cpp/proto_messages.h").exists() {
+        program.push_str("#include \"proto_messages.h\"\n");
+    }
+    program.push_str("int main(void) {\n");
+    for (_, name, _, _) in C_STRUCT_LAYOUTS {
+        let _ = writeln!(program, "    printf(\"{0} %zu %zu\\n\", sizeof({0}), _Alignof({0}));", name);
+    }
+    program.push_str("    return 0;\n}\n");
+    let source = dir.join("layouts.c");
+    let binary = dir.join("layouts");
+    fs::write(&source, program).unwrap_or_else(|e| fail(format_args!("{}: {}", source.display(), e)));
+
+    let cc = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let compiled = Command::new(&cc)
+        .args(["-std=c11", "-o"])
+        .arg(&binary)
+        .arg("-I")
+        .arg(root.join("messages"))
+        .arg("-I")
+        .arg(root.join("generated/cpp"))
+        .arg(&source)
+        .output()
+        .unwrap_or_else(|e| fail(format_args!("cannot run {}: {}", cc, e)));
+    if !compiled.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&compiled.stderr));
+        fail("the C header does not compile");
+    }
+    let run = Command::new(&binary).output().unwrap_or_else(|e| fail(format_args!("{}: {}", binary.display(), e)));
+    let _ = fs::remove_dir_all(&dir);
+
+    let printed = String::from_utf8_lossy(&run.stdout);
+    let mut problems = Vec::new();
+    for ((id, name, size, align), line) in C_STRUCT_LAYOUTS.iter().zip(printed.lines()) {
+        let c: Vec<usize> = line.split_whitespace().skip(1).filter_map(|v| v.parse().ok()).collect();
+        if c.as_slice() != [*size, *align] {
+            problems.push(format!(
+                "{} ({}): C size {} align {}, Rust size {} align {}",
+                name,
+                id,
+                c.first().map_or("?".to_string(), |v| v.to_string()),
+                c.get(1).map_or("?".to_string(), |v| v.to_string()),
+                size,
+                align
+            ));
+        }
+    }
+    if printed.lines().count() != C_STRUCT_LAYOUTS.len() {
+        problems.push("layout program printed the wrong number of structs".to_string());
+    }
+    problems
+}
+
+fn report(what: &str, problems: &[String]) -> bool {
+    if problems.is_empty() {
+        println!("{}: ok", what);
+        return true;
+    }
+    for p in problems {
+        println!("{}: {}", what, p);
+    }
+    false
+}
+
+fn main() {
+    let root = root();
+    let ok = match env::args().nth(1).as_deref() {
+        Some("generate") => {
+            generate(&root, &root.join("generated"));
+            println!("generated/ regenerated; rebuild for validate to see new structs");
+            true
+        }
+        Some("diff") => report("diff", &diff(&root)),
+        Some("validate") => report("validate", &validate(&root)),
+        Some("check") => {
+            let diffed = report("diff", &diff(&root));
+            report("validate", &validate(&root)) && diffed
+        }
+        _ => fail("usage: interop-gen generate | diff | validate | check"),
+    };
+    process::exit(if ok { 0 } else { 1 });
+}
@@ -0,0 +1,216 @@
+//! Copy-free inbound delivery from caller-owned C buffers
+//!
+//! `rust_actor_send` copies every field of the C struct into an owned
+//! message before it is queued. For hot, wide messages (MarketDepth) a
+//! Rust handler can instead read the caller's buffer in place. Messages
+//! marked `INTEROP_BORROWED(Name)` in the IDL get a generated `NameView`
+//! whose accessors borrow from the C struct, and a handler registered for
+//! an actor is called with it:
+//!
+//! ```ignore
+//! borrow::register_outside_mailbox::<MarketDepth, _>("rust_book", move |depth, _sender| {
+//!     let best_bid = depth.bid_prices()[0];        // read in place
+//!     if best_bid > threshold {
+//!         alerts.push(depth.to_message());         // copy only what escapes
+//!     }
+//! });
+//! ```
+//!
+//! The contract (see interop/borrow.h): C++ calls
+//! `rust_actor_borrow_send()`, the handler runs on the calling thread
+//! before it returns, and the buffer belongs to the caller again once it
+//! has. A view cannot outlive the call in safe Rust (its lifetime is the
+//! call's) and is not `Send`. Debug builds also assert that:
+//!
+//! - no view is read after its call returned (one smuggled out through
+//!   unsafe code, or a raw pointer kept into the buffer through it);
+//! - the caller did not change the buffer while the handler ran.
+//!
+//! Field rules are checked on the view, and the message is admitted as
+//! `rust_actor_fast_send` would (see inbound): shutdown, the bridge policy
+//! with its tenants and contracts, sender auth and the size limit apply.
+//!
+//! The handler is not an actor delivery, which is why registering one is
+//! named for it. It runs on the sender's thread, concurrently with the
+//! actor's own handlers and while the actor is paused, so state it shares
+//! with the actor needs its own synchronization. Taps, dedup, idempotency,
+//! sequencing, the journal and the mailbox do not see the message. An actor
+//! that must handle its messages one at a time takes them lazily instead
+//! (see lazy), which queues the C struct. Messages with no handler for the
+//! target fall back to `rust_actor_fast_send`.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use actors::Message;
+
+use crate::aliases;
+use crate::inbound;
+use crate::interop_errors::InteropErrorCode;
+#[cfg(debug_assertions)]
+use crate::interop_messages::message_to_portable;
+use crate::names;
+use crate::rust_actor_bridge::rust_actor_fast_send;
+use crate::sender_auth::Entry;
+use crate::validation::{self, ValidationError, INVALID_MESSAGE};
+
+/// A message with a generated borrowed view (`INTEROP_BORROWED`)
+pub trait BorrowedMessage: Message + Sized {
+    const MSG_ID: i32;
+
+    /// The `#[repr(C)]` struct the caller passes
+    type C;
+
+    type View<'a>;
+
+    fn view(c: &Self::C, borrow: Borrow) -> Self::View<'_>;
+
+    fn validate_view(view: &Self::View<'_>) -> Result<(), ValidationError>;
+
+    /// Owned copy of the whole message
+    fn from_c(c: &Self::C) -> Self;
+}
+
+// Epochs of the borrows in progress on this thread, innermost last
+thread_local! {
+    static ACTIVE: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+static NEXT_EPOCH: AtomicU64 = AtomicU64::new(1);
+
+/// Proof that a view is read within its call; checked in debug builds
+#[derive(Clone, Copy)]
+pub struct Borrow {
+    #[cfg(debug_assertions)]
+    epoch: u64,
+    // A view stays on the calling thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Borrow {
+    /// Called by every generated view accessor
+    #[inline]
+    pub fn check(&self) {
+        #[cfg(debug_assertions)]
+        assert!(
+            ACTIVE.with(|a| a.borrow().contains(&self.epoch)),
+            "borrowed message read after its rust_actor_borrow_send() call returned"
+        );
+    }
+}
+
+// Marks one borrow as in progress on this thread while alive
+struct Scope {
+    epoch: u64,
+}
+
+impl Scope {
+    fn begin() -> (Scope, Borrow) {
+        let epoch = NEXT_EPOCH.fetch_add(1, Ordering::Relaxed);
+        ACTIVE.with(|a| a.borrow_mut().push(epoch));
+        #[cfg(not(debug_assertions))]
+        let borrow = Borrow { _not_send: PhantomData };
+        #[cfg(debug_assertions)]
+        let borrow = Borrow { epoch, _not_send: PhantomData };
+        (Scope { epoch }, borrow)
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        ACTIVE.with(|a| a.borrow_mut().retain(|&e| e != self.epoch));
+    }
+}
+
+/// Run `f` with a Borrow that is valid until it returns, for views of a
+/// C struct the caller owns (see lazy)
+pub(crate) fn scoped<R>(f: impl FnOnce(Borrow) -> R) -> R {
+    let (_scope, borrow) = Scope::begin();
+    f(borrow)
+}
+
+// Type-erased handler: (C struct, target, sender, borrow) -> send code
+type Erased = Box<dyn FnMut(*const c_void, &str, &str, Borrow) -> c_int + Send>;
+type Handler = Arc<Mutex<Erased>>;
+
+lazy_static::lazy_static! {
+    static ref HANDLERS: Mutex<HashMap<(String, i32), Handler>> = Mutex::new(HashMap::new());
+}
+
+/// Handle `M` sent to `actor` with `rust_actor_borrow_send()` by calling
+/// `handler(view, sender)` on the sending thread, outside the actor's
+/// mailbox (see the module doc). Replaces an earlier handler for the same
+/// actor and message.
+pub fn register_outside_mailbox<M, F>(actor: &str, mut handler: F)
+where
+    M: BorrowedMessage,
+    F: for<'a> FnMut(&M::View<'a>, &str) + Send + 'static,
+{
+    let erased: Erased = Box::new(move |data, target, sender, borrow| {
+        // SAFETY: rust_actor_borrow_send() was given a non-null M::C for
+        // M::MSG_ID, which the caller keeps valid until it returns
+        let c = unsafe { &*(data as *const M::C) };
+        #[cfg(debug_assertions)]
+        let before = message_to_portable(&M::from_c(c));
+        let view = M::view(c, borrow);
+        if let Err(err) = M::validate_view(&view) {
+            validation::reject(target, sender, &err);
+            return INVALID_MESSAGE;
+        }
+        handler(&view, sender);
+        #[cfg(debug_assertions)]
+        assert!(
+            message_to_portable(&M::from_c(c)) == before,
+            "caller changed a borrowed message during rust_actor_borrow_send()"
+        );
+        InteropErrorCode::Ok as c_int
+    });
+    HANDLERS.lock().unwrap().insert((actor.to_string(), M::MSG_ID), Arc::new(Mutex::new(erased)));
+}
+
+/// Returns false if `actor` had no handler for `msg_id`
+pub fn unregister(actor: &str, msg_id: i32) -> bool {
+    HANDLERS.lock().unwrap().remove(&(actor.to_string(), msg_id)).is_some()
+}
+
+pub fn is_registered(actor: &str, msg_id: i32) -> bool {
+    HANDLERS.lock().unwrap().contains_key(&(actor.to_string(), msg_id))
+}
+
+/// Deliver a caller-owned message to the borrowed handler of the target,
+/// which reads it in place before this returns. Without one, same as
+/// rust_actor_fast_send(). Returns an InteropErrorCode: Ok, InvalidArgument
+/// (null or non-UTF-8 name, null data), the admission codes of
+/// rust_actor_fast_send() or, without a handler, any of its codes.
+#[export_name = c_symbol!("rust_actor_borrow_send")]
+pub extern "C" fn rust_actor_borrow_send(
+    actor_name: *const c_char,
+    sender_name: *const c_char,
+    msg_type: c_int,
+    msg_data: *const c_void,
+) -> c_int {
+    let name = match names::c_str(actor_name) {
+        Some(s) if !msg_data.is_null() => s,
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let resolved = aliases::resolve(name);
+    let name = &*resolved;
+    let handler = HANDLERS.lock().unwrap().get(&(name.to_string(), msg_type)).cloned();
+    let handler = match handler {
+        Some(h) => h,
+        None => return rust_actor_fast_send(actor_name, sender_name, msg_type, msg_data),
+    };
+
+    let sender = names::c_str(sender_name).unwrap_or("");
+    if let Err(rc) = unsafe { inbound::admit(name, sender, msg_type, Entry::BorrowSend, msg_data) } {
+        return rc;
+    }
+
+    let (_scope, borrow) = Scope::begin();
+    let mut handler = handler.lock().unwrap();
+    (*handler)(msg_data, name, sender, borrow)
+}
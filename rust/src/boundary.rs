@@ -0,0 +1,135 @@
+//! Bytes crossing the language boundary, for capacity planning
+//!
+//! Every message the bridge converts between its C struct and the Rust
+//! type is counted here by direction and message ID, with the size of the
+//! C struct. Moved out of process, the Rust actors would put about these
+//! bytes on an IPC transport, so the counts and rates say whether one
+//! could keep up:
+//!
+//! - `rust_manager_stats()` adds the bytes each way since start and the
+//!   conversions per second since the previous call.
+//! - The health summary (`rust_interop_health()`, `interop-ctl metrics`)
+//!   has a `boundary` object with both directions and, per message type,
+//!   the conversions each way and the average size:
+//!
+//! ```json
+//! "boundary":{"to_rust":{"conversions":1200,"bytes":76800},
+//!             "to_cpp":{"conversions":40,"bytes":2560},
+//!             "messages":[{"msg_id":1012,"name":"MarketUpdate","to_rust":1200,"to_cpp":40,"avg_bytes":64}]}
+//! ```
+//!
+//! Counted: `rust_actor_send`, `rust_actor_fast_send`, `rust_actor_post`
+//! and `rust_actor_ask` requests one way; every send to a C++ actor and
+//! every ask reply the other. Sizes are those of the C structs; the data
+//! behind `interop_bytes` fields is not included. Copy-free paths (borrow,
+//! lazy) still count, since the struct crossed.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::interop_messages::{c_struct_size, message_name, MESSAGE_IDS};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// C++ -> Rust
+    ToRust = 0,
+    /// Rust -> C++
+    ToCpp = 1,
+}
+
+#[derive(Default)]
+struct Counts {
+    conversions: AtomicU64,
+    bytes: AtomicU64,
+}
+
+/// Conversions and bytes one way
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Traffic {
+    pub conversions: u64,
+    pub bytes: u64,
+}
+
+impl Counts {
+    fn add(&self, bytes: usize) {
+        self.conversions.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> Traffic {
+        Traffic { conversions: self.conversions.load(Ordering::Relaxed), bytes: self.bytes.load(Ordering::Relaxed) }
+    }
+}
+
+static TOTALS: [Counts; 2] = [
+    Counts { conversions: AtomicU64::new(0), bytes: AtomicU64::new(0) },
+    Counts { conversions: AtomicU64::new(0), bytes: AtomicU64::new(0) },
+];
+
+lazy_static::lazy_static! {
+    // Fixed once built, so recording takes no lock
+    static ref BY_ID: HashMap<i32, [Counts; 2]> =
+        MESSAGE_IDS.iter().map(|&id| (id, [Counts::default(), Counts::default()])).collect();
+}
+
+/// Count one message of `msg_id` converted in `direction`; unknown IDs
+/// are ignored
+pub fn record(direction: Direction, msg_id: i32) {
+    let (size, counts) = match (c_struct_size(msg_id), BY_ID.get(&msg_id)) {
+        (Some(size), Some(counts)) => (size, counts),
+        _ => return,
+    };
+    TOTALS[direction as usize].add(size);
+    counts[direction as usize].add(size);
+}
+
+/// Everything converted `direction` since start
+pub fn totals(direction: Direction) -> Traffic {
+    TOTALS[direction as usize].get()
+}
+
+/// Conversions each way since start, for rates
+pub(crate) fn conversions() -> u64 {
+    totals(Direction::ToRust).conversions + totals(Direction::ToCpp).conversions
+}
+
+/// (message ID, to Rust, to C++) for every type converted at least once,
+/// by ID
+pub fn by_message() -> Vec<(i32, Traffic, Traffic)> {
+    let mut all: Vec<(i32, Traffic, Traffic)> = BY_ID
+        .iter()
+        .map(|(&id, counts)| (id, counts[0].get(), counts[1].get()))
+        .filter(|(_, to_rust, to_cpp)| to_rust.conversions + to_cpp.conversions > 0)
+        .collect();
+    all.sort_by_key(|(id, _, _)| *id);
+    all
+}
+
+/// The `boundary` object of the health summary
+pub fn boundary_json() -> String {
+    let mut out = String::new();
+    for (i, (key, dir)) in [("to_rust", Direction::ToRust), ("to_cpp", Direction::ToCpp)].iter().enumerate() {
+        let t = totals(*dir);
+        let sep = if i == 0 { "{" } else { "," };
+        let _ = write!(out, "{}\"{}\":{{\"conversions\":{},\"bytes\":{}}}", sep, key, t.conversions, t.bytes);
+    }
+    out.push_str(",\"messages\":[");
+    for (i, (id, to_rust, to_cpp)) in by_message().iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let conversions = to_rust.conversions + to_cpp.conversions;
+        let _ = write!(
+            out,
+            "{{\"msg_id\":{},\"name\":\"{}\",\"to_rust\":{},\"to_cpp\":{},\"avg_bytes\":{}}}",
+            id,
+            message_name(*id).unwrap_or("?"),
+            to_rust.conversions,
+            to_cpp.conversions,
+            (to_rust.bytes + to_cpp.bytes) / conversions
+        );
+    }
+    out.push_str("]}");
+    out
+}
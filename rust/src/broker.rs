@@ -0,0 +1,571 @@
+//! Publish by topic, without knowing who subscribed
+//!
+//! Publishers used to look their subscribers up by name, which ties the
+//! publisher to one subscriber and defeats pub/sub. The broker keeps the
+//! subscriptions instead, and `publish` sends to whoever holds one:
+//!
+//! ```ignore
+//! // Publisher
+//! broker::publish("rust_publisher", "AAPL", &update)?;
+//!
+//! // Rust subscriber (Subscribes from C++ are recorded by the bridge)
+//! broker::subscribe("rust_monitor", "rust_publisher", "AAPL", QOS_BEST_EFFORT);
+//! ```
+//!
+//! - Every Subscribe / Unsubscribe crossing the bridge, in either
+//!   direction, updates the registry under (publisher, topic). A Subscribe
+//!   from C++ code that is not an actor has no sender and is not recorded.
+//! - Rust -> Rust subscriptions do not cross the bridge; subscribers use
+//!   `subscribe()` / `unsubscribe()` here, which record and send the message.
+//! - SubscribeMany / UnsubscribeMany (see subscribe_many) are recorded as
+//!   one Subscribe / Unsubscribe per topic; `subscribe_many()` sends one.
+//! - Subscribers are looked up on each publish, so one that has gone away
+//!   is skipped (and dropped from the registry).
+//!
+//! `publish` checks the topic's declared type (see topic) before sending.
+//! On a partitioned topic (see partitions) it also sends to the subscribers
+//! of the message's partition, `"<topic>#<n>"`; a subscriber of both the
+//! topic and the partition gets one copy.
+//! `publish_with` builds the message per subscriber, e.g. compact updates
+//! for subscribers that asked for them. Both count only the sends that
+//! succeeded; failures are logged.
+//!
+//! With thousands of subscribers the publisher thread spends its time on
+//! lookups and sends. `set_fanout_workers(n)` moves them to `n` worker
+//! threads: the publisher still builds each message, then hands it to the
+//! worker its subscriber hashes to. A subscriber is always served by the
+//! same worker, so it sees a publisher's messages in publish order. In this
+//! mode `publish` returns how many messages were handed off; send failures
+//! are logged by the workers.
+//!
+//! A subscription's filter (see sub_filter) is applied to what is built
+//! for it before sending; what it drops is not counted as sent.
+//!
+//! A subscriber whose Subscribe carries an overflow policy is sent to
+//! through its own bounded queue (see sub_queue); a message that queue
+//! holds or drops still counts as sent.
+//!
+//! A reliable subscriber (`QOS_RELIABLE`) is sent to through an
+//! acknowledged stream instead, on the publisher thread (see reliable). A
+//! `TopicAck` crossing the bridge acknowledges updates of its stream.
+//!
+//! The registry is split into `SHARDS` shards by topic hash, each behind
+//! its own read-write lock, so publishes look their subscribers up in
+//! parallel and a subscribe to one symbol does not hold up publishes of
+//! the others, with 100k topics as with ten (`cargo bench --bench
+//! broker_topics`).
+//!
+//! With fairness enabled (see fairness) fan-out starts at a different
+//! subscriber on each publish, and a slow subscriber's messages are sent
+//! from a backlog instead of holding up the others.
+//!
+//! `subscriptions_json()` (C++: `rust_dump_subscriptions()`, interop-ctl:
+//! `subscriptions`) lists the whole registry with the side each subscriber
+//! is on, to check that subscriptions made from C++ reached a Rust
+//! publisher:
+//!
+//! ```text
+//! [{"topic":"AAPL","publisher":"rust_publisher","subscribers":[{"name":"cpp_gui","side":"cpp","qos":0}]}]
+//! ```
+//!
+//! `side` is `"rust"`, `"cpp"`, or null for a subscriber that can no longer
+//! be found (dropped on the next publish).
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::os::raw::{c_char, c_int};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+
+use actors::{ActorRef, Message};
+
+use crate::codec::JsonValue;
+use crate::conversion_cache;
+use crate::dead_letters::{self, Route, SendContext};
+use crate::fairness;
+use crate::hash_router::hash;
+use crate::interop_messages::{Subscribe, SubscribeMany, TopicAck, Unsubscribe, UnsubscribeMany};
+use crate::interop_string::{InteropString, TruncationPolicy};
+use crate::partitions;
+use crate::qos::Qos;
+use crate::reliable;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::send_error::TrySend;
+use crate::sub_filter;
+use crate::sub_queue;
+use crate::tenant;
+use crate::topic::{self, TopicError};
+
+/// One subscriber of a topic
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subscription {
+    pub subscriber: String,
+    /// `Subscribe.qos` as sent, flags included
+    pub qos: i32,
+}
+
+/// Shards of the subscription registry; a topic's hash picks its shard
+pub const SHARDS: usize = 64;
+
+// Topic -> publisher -> subscribers in subscription order
+type Shard = RwLock<HashMap<String, HashMap<String, Vec<Subscription>>>>;
+
+lazy_static::lazy_static! {
+    static ref SUBSCRIPTIONS: Vec<Shard> = (0..SHARDS).map(|_| RwLock::new(HashMap::new())).collect();
+    // (publisher, subscriber) -> topics subscribed, so a removal need not
+    // look through every topic to know whether it was the last
+    static ref TOPIC_COUNTS: Mutex<HashMap<(String, String), usize>> = Mutex::new(HashMap::new());
+    // One channel per fan-out worker; None sends on the publisher thread
+    static ref FANOUT: RwLock<Option<Arc<Vec<Sender<Delivery>>>>> = RwLock::new(None);
+    static ref FANOUT_THREADS: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+}
+
+/// One message for one subscriber, handed to a fan-out worker
+struct Delivery {
+    publisher: String,
+    topic: String,
+    subscriber: String,
+    msg: Box<dyn Message>,
+    // Origin of the publish, for dead letters on the worker
+    context: SendContext,
+}
+
+fn shard(topic: &str) -> &'static Shard {
+    &SUBSCRIPTIONS[(hash(topic.as_bytes()) % SHARDS as u64) as usize]
+}
+
+/// Record that `subscriber` subscribed to `topic` of `publisher`; a repeat
+/// updates its qos. Sets up the subscriber's queue (see sub_queue), and
+/// has a reliable subscriber that subscribes again start over (see reliable).
+pub fn add(publisher: &str, topic: &str, subscriber: &str, qos: i32) {
+    let mut subs = shard(topic).write().unwrap();
+    let list = subs.entry(topic.to_string()).or_default().entry(publisher.to_string()).or_default();
+    let added = match list.iter_mut().find(|s| s.subscriber == subscriber) {
+        Some(s) => {
+            s.qos = qos;
+            false
+        }
+        None => {
+            list.push(Subscription { subscriber: subscriber.to_string(), qos });
+            true
+        }
+    };
+    // Counted under the shard lock, so a concurrent remove of another of
+    // the pair's topics sees this one
+    if added {
+        *TOPIC_COUNTS.lock().unwrap().entry((publisher.to_string(), subscriber.to_string())).or_insert(0) += 1;
+    }
+    drop(subs);
+    sub_queue::configure(publisher, subscriber, qos);
+    reliable::subscribed(publisher, topic, subscriber, qos);
+}
+
+pub fn remove(publisher: &str, topic: &str, subscriber: &str) {
+    // Counted under the shard lock, like add
+    let subscribed = {
+        let mut subs = shard(topic).write().unwrap();
+        let mut removed = false;
+        if let Some(publishers) = subs.get_mut(topic) {
+            if let Some(list) = publishers.get_mut(publisher) {
+                let before = list.len();
+                list.retain(|s| s.subscriber != subscriber);
+                removed = list.len() < before;
+                if list.is_empty() {
+                    publishers.remove(publisher);
+                }
+            }
+            if publishers.is_empty() {
+                subs.remove(topic);
+            }
+        }
+        let mut counts = TOPIC_COUNTS.lock().unwrap();
+        let key = (publisher.to_string(), subscriber.to_string());
+        match counts.get_mut(&key) {
+            Some(n) if removed && *n <= 1 => {
+                counts.remove(&key);
+                false
+            }
+            Some(n) if removed => {
+                *n -= 1;
+                true
+            }
+            Some(_) => true,
+            None => false,
+        }
+    };
+    sub_filter::forget(publisher, topic, subscriber);
+    reliable::forget(publisher, topic, subscriber);
+    if !subscribed {
+        sub_queue::remove(publisher, subscriber);
+        fairness::remove(publisher, subscriber);
+    }
+}
+
+/// Forget every subscription (see teardown)
+pub(crate) fn clear() {
+    for shard in SUBSCRIPTIONS.iter() {
+        shard.write().unwrap().clear();
+    }
+    TOPIC_COUNTS.lock().unwrap().clear();
+    reliable::clear();
+}
+
+/// Subscribers of `topic` of `publisher`
+pub fn subscribers(publisher: &str, topic: &str) -> Vec<Subscription> {
+    let subs = shard(topic).read().unwrap();
+    subs.get(topic).and_then(|p| p.get(publisher)).cloned().unwrap_or_default()
+}
+
+/// Topics with at least one subscriber, over every publisher
+pub fn topic_count() -> usize {
+    SUBSCRIPTIONS.iter().map(|shard| shard.read().unwrap().len()).sum()
+}
+
+/// Every subscription as (topic, publisher, subscribers), sorted by topic
+/// then publisher
+pub fn all() -> Vec<(String, String, Vec<Subscription>)> {
+    let mut all = Vec::new();
+    for shard in SUBSCRIPTIONS.iter() {
+        for (topic, publishers) in shard.read().unwrap().iter() {
+            for (publisher, subs) in publishers {
+                all.push((topic.clone(), publisher.clone(), subs.clone()));
+            }
+        }
+    }
+    all.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+    all
+}
+
+/// all() as a JSON array, with the side each subscriber is on. Within a
+/// tenant view, only its subscribers, and its publishers' topics (see
+/// tenant).
+pub fn subscriptions_json() -> String {
+    let shown: Vec<(String, String, Vec<Subscription>)> = all()
+        .into_iter()
+        .filter_map(|(topic, publisher, mut subs)| {
+            subs.retain(|sub| tenant::visible(&sub.subscriber));
+            (tenant::visible(&publisher) || !subs.is_empty()).then_some((topic, publisher, subs))
+        })
+        .collect();
+    let mut out = String::from("[");
+    for (i, (topic, publisher, subs)) in shown.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"topic\":");
+        topic.write_json(&mut out);
+        out.push_str(",\"publisher\":");
+        publisher.write_json(&mut out);
+        out.push_str(",\"subscribers\":[");
+        for (j, sub) in subs.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            sub.subscriber.write_json(&mut out);
+            let side = match get_actor_ref(&sub.subscriber, publisher) {
+                Some(ActorRef::Cpp(_)) => "\"cpp\"",
+                Some(_) => "\"rust\"",
+                None => "null",
+            };
+            let _ = write!(out, ",\"side\":{},\"qos\":{}}}", side, sub.qos);
+        }
+        out.push_str("]}");
+    }
+    out.push(']');
+    out
+}
+
+/// Write subscriptions_json() into `out_json` (NUL-terminated, truncated
+/// to fit `len`). Returns the full JSON length like snprintf, so a return
+/// value >= len means the buffer was too small.
+#[export_name = c_symbol!("rust_dump_subscriptions")]
+pub extern "C" fn rust_dump_subscriptions(out_json: *mut c_char, len: usize) -> c_int {
+    let json = subscriptions_json();
+    if !out_json.is_null() && len > 0 {
+        let n = json.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(json.as_ptr(), out_json as *mut u8, n);
+            *out_json.add(n) = 0;
+        }
+    }
+    json.len().min(c_int::MAX as usize) as c_int
+}
+
+/// Track subscriptions and reliable acks crossing the bridge. Called by
+/// the bridge.
+pub fn observe(sender: &str, target: &str, msg: &dyn Message) {
+    if sender.is_empty() {
+        return;
+    }
+    if let Some(sub) = msg.as_any().downcast_ref::<Subscribe>() {
+        add(target, sub.topic.as_str(), sender, sub.qos);
+    } else if let Some(unsub) = msg.as_any().downcast_ref::<Unsubscribe>() {
+        remove(target, unsub.topic.as_str(), sender);
+    } else if let Some(many) = msg.as_any().downcast_ref::<SubscribeMany>() {
+        for topic in many.topics() {
+            add(target, topic, sender, many.qos);
+        }
+    } else if let Some(many) = msg.as_any().downcast_ref::<UnsubscribeMany>() {
+        for topic in many.topics() {
+            remove(target, topic, sender);
+        }
+    } else if let Some(ack) = msg.as_any().downcast_ref::<TopicAck>() {
+        reliable::ack(target, ack.topic.as_str(), sender, ack.seq);
+    }
+}
+
+/// Subscribe Rust actor `me` to `topic` of `publisher` (Rust or C++).
+/// Returns false if the publisher is not found or the topic is too long.
+pub fn subscribe(me: &str, publisher: &str, topic: &str, qos: i32) -> bool {
+    let (publisher_ref, name) = match (
+        get_actor_ref(publisher, me),
+        InteropString::with_policy(topic, TruncationPolicy::Reject),
+    ) {
+        (Some(r), Ok(name)) => (r, name),
+        _ => return false,
+    };
+    add(publisher, topic, me, qos);
+    publisher_ref.send(Box::new(Subscribe { topic: name, qos }), None);
+    true
+}
+
+pub fn unsubscribe(me: &str, publisher: &str, topic: &str) {
+    remove(publisher, topic, me);
+    if let (Some(r), Ok(name)) = (
+        get_actor_ref(publisher, me),
+        InteropString::with_policy(topic, TruncationPolicy::Reject),
+    ) {
+        r.send(Box::new(Unsubscribe { topic: name }), None);
+    }
+}
+
+/// Subscribe Rust actor `me` to every one of `topics` of `publisher` with
+/// one SubscribeMany. Returns false, subscribing to none, if the publisher
+/// is not found or a topic is too long.
+pub fn subscribe_many<S: AsRef<str>>(me: &str, publisher: &str, topics: &[S], qos: i32) -> bool {
+    let (publisher_ref, msg) = match (get_actor_ref(publisher, me), SubscribeMany::new(topics, qos)) {
+        (Some(r), Ok(msg)) => (r, msg),
+        _ => return false,
+    };
+    for topic in topics {
+        add(publisher, topic.as_ref(), me, qos);
+    }
+    publisher_ref.send(Box::new(msg), None);
+    true
+}
+
+pub fn unsubscribe_many<S: AsRef<str>>(me: &str, publisher: &str, topics: &[S]) {
+    for topic in topics {
+        remove(publisher, topic.as_ref(), me);
+    }
+    if let (Some(r), Ok(msg)) = (get_actor_ref(publisher, me), UnsubscribeMany::new(topics)) {
+        r.send(Box::new(msg), None);
+    }
+}
+
+/// Send a copy of `msg` to every subscriber of `topic` of `publisher`,
+/// and of its partition if the topic is partitioned. Returns how many
+/// subscribers it was sent to.
+pub fn publish<M: Message + Clone>(publisher: &str, topic: &str, msg: &M) -> Result<usize, TopicError> {
+    topic::check(topic, msg)?;
+    let partition = partitions::partition_of(topic, msg);
+    Ok(publish_partition_with(publisher, topic, partition, |_| Some(Box::new(msg.clone()))))
+}
+
+/// Send `build(subscription)` to every subscriber of `topic` of
+/// `publisher`; None skips that subscriber. Returns how many were sent.
+pub fn publish_with<F>(publisher: &str, topic: &str, build: F) -> usize
+where
+    F: FnMut(&Subscription) -> Option<Box<dyn Message>>,
+{
+    publish_partition_with(publisher, topic, None, build)
+}
+
+/// `publish_with` that also reaches the subscribers of partition
+/// `partition` of `topic`
+pub fn publish_partition_with<F>(publisher: &str, topic: &str, partition: Option<u32>, build: F) -> usize
+where
+    F: FnMut(&Subscription) -> Option<Box<dyn Message>>,
+{
+    // Equal messages to C++ subscribers are converted once (see conversion_cache)
+    conversion_cache::fan_out(|| fan_out_to(publisher, topic, partition, build))
+}
+
+fn fan_out_to<F>(publisher: &str, topic: &str, partition: Option<u32>, mut build: F) -> usize
+where
+    F: FnMut(&Subscription) -> Option<Box<dyn Message>>,
+{
+    let mut targets: Vec<(String, Subscription)> =
+        subscribers(publisher, topic).into_iter().map(|s| (topic.to_string(), s)).collect();
+    if let Some(p) = partition {
+        let part = partitions::partition_topic(topic, p);
+        for sub in subscribers(publisher, &part) {
+            if !targets.iter().any(|(_, s)| s.subscriber == sub.subscriber) {
+                targets.push((part.clone(), sub));
+            }
+        }
+    }
+
+    let fair = fairness::is_enabled();
+    if fair && targets.len() > 1 {
+        let start = fairness::start_offset(targets.len());
+        targets.rotate_left(start);
+    }
+
+    // What each subscription's filter (see sub_filter) lets through
+    let mut filtered = |sub_topic: &str, sub: &Subscription| {
+        let msg = build(sub)?;
+        sub_filter::apply(publisher, sub_topic, &sub.subscriber, msg)
+    };
+
+    let workers = FANOUT.read().unwrap().clone();
+    let mut sent = 0;
+    for (sub_topic, sub) in targets {
+        if Qos::from_flag(sub.qos) == Qos::Reliable {
+            if let Some(msg) = filtered(&sub_topic, &sub) {
+                if reliable::offer(publisher, &sub_topic, &sub.subscriber, sub.qos, msg) {
+                    sent += 1;
+                }
+            }
+            continue;
+        }
+        if let Some(workers) = &workers {
+            if let Some(msg) = filtered(&sub_topic, &sub) {
+                let shard = (hash(sub.subscriber.as_bytes()) % workers.len() as u64) as usize;
+                let delivery = Delivery {
+                    publisher: publisher.to_string(),
+                    topic: sub_topic,
+                    subscriber: sub.subscriber,
+                    msg,
+                    context: SendContext { route: Route::Broker, ..dead_letters::current() },
+                };
+                if workers[shard].send(delivery).is_ok() {
+                    sent += 1;
+                }
+            }
+            continue;
+        }
+        if fair {
+            if let Some(msg) = filtered(&sub_topic, &sub) {
+                let context = SendContext { route: Route::Broker, ..dead_letters::current() };
+                if fairness::send(publisher, &sub_topic, &sub.subscriber, msg, context) {
+                    sent += 1;
+                }
+            }
+            continue;
+        }
+        let sub_ref = match get_actor_ref(&sub.subscriber, publisher) {
+            Some(r) => r,
+            None => {
+                remove(publisher, &sub_topic, &sub.subscriber);
+                continue;
+            }
+        };
+        if let Some(msg) = filtered(&sub_topic, &sub) {
+            let msg = match sub_queue::offer(publisher, &sub.subscriber, msg, None) {
+                Some(msg) => msg,
+                None => {
+                    sent += 1;
+                    continue;
+                }
+            };
+            match dead_letters::via(Route::Broker, || sub_ref.try_send(msg, None)) {
+                Ok(()) => sent += 1,
+                Err(e) => eprintln!("[Broker] {} -> {} on {}: {}", publisher, sub.subscriber, sub_topic, e),
+            }
+        }
+    }
+    sent
+}
+
+/// Fan publishes out on `workers` threads, or on the publisher thread with
+/// 0 (the default). Returns once the old workers have delivered what they
+/// were handed; publishes wait meanwhile, so order is kept.
+pub fn set_fanout_workers(workers: usize) {
+    let mut fanout = FANOUT.write().unwrap();
+    let old = fanout.take();
+    let old_threads = std::mem::take(&mut *FANOUT_THREADS.lock().unwrap());
+    drop(old);  // workers exit once publishes in flight drop their clones
+    for t in old_threads {
+        let _ = t.join();
+    }
+    if workers == 0 {
+        return;
+    }
+    let mut threads = FANOUT_THREADS.lock().unwrap();
+    let channels = (0..workers)
+        .map(|_| {
+            let (tx, rx) = mpsc::channel::<Delivery>();
+            threads.push(thread::spawn(move || {
+                for d in rx {
+                    deliver(d);
+                }
+            }));
+            tx
+        })
+        .collect();
+    *fanout = Some(Arc::new(channels));
+}
+
+/// Number of fan-out worker threads, 0 if publishes fan out inline
+pub fn fanout_workers() -> usize {
+    FANOUT.read().unwrap().as_ref().map_or(0, |w| w.len())
+}
+
+fn deliver(d: Delivery) {
+    if fairness::is_enabled() {
+        fairness::send(&d.publisher, &d.topic, &d.subscriber, d.msg, d.context);
+    } else {
+        send_to(&d.publisher, &d.topic, &d.subscriber, d.msg, d.context, true);
+    }
+}
+
+/// What became of a message for one subscriber
+pub(crate) enum Outcome {
+    /// Sent, or held or dropped by the subscriber's queue
+    Sent,
+    /// Subscriber gone, or the send failed (logged)
+    Failed,
+    /// Its `SUB_BLOCK` queue is full; only when not waiting
+    Full(Box<dyn Message>),
+}
+
+/// Send `msg` published on `topic` to `subscriber`, through its queue if
+/// it has one, waiting for room in a full `SUB_BLOCK` queue if `wait`
+pub(crate) fn send_to(
+    publisher: &str,
+    topic: &str,
+    subscriber: &str,
+    msg: Box<dyn Message>,
+    context: SendContext,
+    wait: bool,
+) -> Outcome {
+    let sub_ref = match get_actor_ref(subscriber, publisher) {
+        Some(r) => r,
+        None => {
+            remove(publisher, topic, subscriber);
+            return Outcome::Failed;
+        }
+    };
+    let unqueued = if wait {
+        sub_queue::offer(publisher, subscriber, msg, None)
+    } else {
+        match sub_queue::try_offer(publisher, subscriber, msg, None) {
+            Ok(unqueued) => unqueued,
+            Err(msg) => return Outcome::Full(msg),
+        }
+    };
+    let msg = match unqueued {
+        Some(msg) => msg,
+        None => return Outcome::Sent,
+    };
+    match dead_letters::with_context(context, || sub_ref.try_send(msg, None)) {
+        Ok(()) => Outcome::Sent,
+        Err(e) => {
+            eprintln!("[Broker] {} -> {} on {}: {}", publisher, subscriber, topic, e);
+            Outcome::Failed
+        }
+    }
+}
@@ -0,0 +1,99 @@
+//! Spin before parking, for latency-critical Rust actors
+//!
+//! An idle actor thread parks on its queue, and waking it costs the
+//! sender a futex call and the actor a scheduler wakeup. On the
+//! tick-to-trade path (C++ feed handler -> Rust strategy) that wakeup is
+//! most of the latency. With a spin budget, the actor's thread spins after
+//! each message until the next bridge message is queued or the budget runs
+//! out, and only then parks:
+//!
+//! ```c
+//! rust_actor_set_busy_poll("rust_strategy", 50);   // spin up to 50 us
+//! rust_actor_set_busy_poll("rust_strategy", 0);    // park at once again
+//! ```
+//!
+//! The setting is per actor and may be changed at any time, before or
+//! after registration. A spinning thread keeps its core busy, so give
+//! busy-poll actors their own cores. The spin watches bridge traffic (see
+//! queue_depth): a message from another Rust actor does not end it early
+//! and waits out the rest of the budget.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actors::{Actor, ActorContext, Message};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::queue_depth;
+
+lazy_static::lazy_static! {
+    // Actor name -> spin budget in ns, 0 = off
+    static ref BUDGETS: Mutex<HashMap<String, Arc<AtomicU64>>> = Mutex::new(HashMap::new());
+}
+
+fn budget_of(name: &str) -> Arc<AtomicU64> {
+    BUDGETS.lock().unwrap().entry(name.to_string()).or_default().clone()
+}
+
+/// Spin up to `budget` after each message to `name`; zero turns it off
+pub fn set_budget(name: &str, budget: Duration) {
+    budget_of(name).store(budget.as_nanos().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+}
+
+pub fn budget(name: &str) -> Duration {
+    Duration::from_nanos(budget_of(name).load(Ordering::Relaxed))
+}
+
+/// Actor wrapper that spins for the actor's budget after each message.
+/// `rust_manager_ffi` wraps every actor it registers.
+pub struct BusyPoll {
+    actor: Box<dyn Actor>,
+    budget_ns: Arc<AtomicU64>,
+    depth: Arc<AtomicUsize>,
+}
+
+impl BusyPoll {
+    pub fn new(name: &str, actor: Box<dyn Actor>) -> Self {
+        BusyPoll { actor, budget_ns: budget_of(name), depth: queue_depth::counter(name) }
+    }
+
+    fn spin(&self) {
+        let budget = self.budget_ns.load(Ordering::Relaxed);
+        // The message just handled is still counted if it came through the
+        // bridge; two or more means another one is already queued
+        let depth = self.depth.load(Ordering::Acquire);
+        if budget == 0 || depth >= 2 {
+            return;
+        }
+        let deadline = Instant::now() + Duration::from_nanos(budget);
+        while self.depth.load(Ordering::Acquire) <= depth && Instant::now() < deadline {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+impl Actor for BusyPoll {
+    fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+        self.actor.process_message(msg, ctx);
+        self.spin();
+    }
+}
+
+/// Spin up to `spin_budget_us` microseconds after each message before
+/// parking Rust actor `actor_name`'s thread; 0 turns it off. Returns 0, or
+/// InvalidArgument on a bad name.
+#[export_name = c_symbol!("rust_actor_set_busy_poll")]
+pub extern "C" fn rust_actor_set_busy_poll(actor_name: *const c_char, spin_budget_us: u32) -> c_int {
+    let rc = match c_str(actor_name) {
+        Some(name) if !name.is_empty() => {
+            set_budget(name, Duration::from_micros(spin_budget_us as u64));
+            InteropErrorCode::Ok
+        }
+        _ => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
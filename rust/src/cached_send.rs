@@ -0,0 +1,101 @@
+//! Allocation-free sends for high-rate Rust -> C++ traffic
+//!
+//! `ActorRef::send()` to a C++ actor costs a `Box` for the message plus a
+//! `CString` each for target and sender in `cpp_send_fn`, and the C++
+//! bridge `new`s the C++ message the actor is queued. For a MarketUpdate
+//! firehose that is four heap allocations per update.
+//!
+//! A `CachedSender` is owned by one actor and keeps the C strings for
+//! every target it has sent to and for the sender name. `send_pooled()`
+//! takes the message by reference, so there is no `Box`, and after the
+//! first send to a target no name is allocated either. The send goes
+//! through the same path as `ActorRef::send()` (shutdown gate, policy,
+//! rate limits, sequencing, tap, circuit, retry, health and the interop
+//! log) and the C struct is built on the stack there.
+//!
+//! On the C++ side, messages marked `INTEROP_POOLED(Name)` in the IDL
+//! (MarketUpdate) come from `interop::EnvelopePool`: the receiving actor's
+//! delete after its handler has run keeps the block for the next send.
+//! That applies to every send of the type; `send_pooled()` makes the rest
+//! of the path allocation-free too. `interop_envelope_pool_stats()` (see
+//! CppActorBridge.hpp) reports how many were allocated and reused.
+//!
+//! The target is resolved as for an `ActorRef::Cpp` (a Rust actor that
+//! took the name gets the message, see locality); a name no C++ runtime
+//! knows fails with ActorNotFound.
+//! `benches/envelope_alloc.rs` counts allocations for both paths.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+
+use crate::cpp_actor_if::InteropMessage;
+use crate::names;
+use crate::rust_manager_ffi::{cpp_send_named, CNames};
+use crate::send_error::SendError;
+
+/// C strings by name, allocated once per distinct name
+#[derive(Default)]
+pub struct NameCache {
+    names: HashMap<String, CString>,
+}
+
+impl NameCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// C string for `name`; None if it cannot cross to C (see names)
+    pub fn get(&mut self, name: &str) -> Option<&CString> {
+        if !self.names.contains_key(name) {
+            names::validate(name).ok()?;
+            let cstr = CString::new(name).ok()?;
+            self.names.insert(name.to_string(), cstr);
+        }
+        self.names.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// Per-actor sender for Rust -> C++ messages that keeps its C names
+pub struct CachedSender {
+    name: String,
+    sender: Option<CString>,
+    targets: NameCache,
+}
+
+impl CachedSender {
+    /// `sender_name` is passed to C++ so it can reply(); empty for none.
+    /// A sender name that cannot cross to C makes every send fail with
+    /// InvalidName.
+    pub fn new(sender_name: &str) -> Self {
+        let sender = match sender_name {
+            "" => None,
+            name => names::validate(name).ok().and_then(|_| CString::new(name).ok()),
+        };
+        CachedSender { name: sender_name.to_string(), sender, targets: NameCache::new() }
+    }
+
+    /// Send to a C++ actor without boxing the message or allocating its
+    /// names (after the first send to each target); the C++ message is
+    /// pooled if its type is `INTEROP_POOLED`. Fails as
+    /// `ActorRef::send()` to the target would (see send_error), with
+    /// InvalidName if a name cannot cross to C.
+    pub fn send_pooled<M: InteropMessage + actors::Message>(&mut self, target: &str, msg: &M) -> Result<(), SendError> {
+        if !self.name.is_empty() && self.sender.is_none() {
+            return Err(SendError::InvalidName);
+        }
+        let sender = self.sender.as_ref().map_or(std::ptr::null(), |s| s.as_ptr());
+        let target_c = match self.targets.get(target) {
+            Some(t) => t.as_c_str(),
+            None => return Err(SendError::InvalidName),
+        };
+        SendError::check(cpp_send_named(target, &self.name, msg, Some(CNames { target: target_c, sender })))
+    }
+}
@@ -0,0 +1,329 @@
+//! Capabilities exchange with the C++ peer
+//!
+//! Rust assumes the C++ side implements the bridge functions and converts
+//! every message ID Rust knows. A C++ build from an older header or
+//! generator links fine but then drops messages or misbehaves at run time.
+//! Instead, the generated `cpp_actor_init()` advertises what it was built
+//! with through `interop_cpp_capabilities()`, and `init_cpp_actor_lookup()`
+//! (and `interop_cpp_ready()`) check it before connecting the runtimes:
+//!
+//! - the exchange happened and its version is `CAPABILITIES_VERSION`,
+//! - every function in `REQUIRED_FUNCTIONS` is listed,
+//! - every message ID in `MESSAGE_IDS` is listed,
+//! - the C structs are laid out as Rust lays them out, if C++ sent its
+//!   layouts (see layout).
+//!
+//! On failure they return Incompatible without installing the C++ lookup, and
+//! `interop_capability_report()` lists what is missing.
+//!
+//! Each check also prints a one-line banner of what the deployment
+//! negotiated, and `summary()` (C: `interop_capability_summary()`, JSON;
+//! also under `capabilities` in the health summary) returns it for support
+//! to compare deployments at a glance:
+//!
+//! ```text
+//! [Rust Manager] interop abi 1 (C++ 1), schema 5d3c0a9e21f4b7c6 (C++ 5d3c0a9e21f4b7c6), 42 messages (C++ 42), features: tracing, transports: cpp, cpp:pricing; ok
+//! ```
+//!
+//! ```json
+//! {"abi_version":1,"peer_abi_version":1,"schema_hash":"5d3c0a9e21f4b7c6",
+//!  "peer_schema_hash":"5d3c0a9e21f4b7c6","messages":42,"peer_messages":42,
+//!  "features":["tracing"],"transports":["cpp","cpp:pricing"],"error":null}
+//! ```
+//!
+//! The C++ values are null before the exchange (`peer_schema_hash` also if
+//! C++ sent no layouts). Features are the Cargo features built in, plus
+//! `reliable_delivery` while a subscriber has asked for it (see qos).
+//! Transports are the C++ runtimes Rust sends to (`cpp` for the linked one,
+//! none with feature `standalone`; see cpp_runtime) and the MQTT bridge if
+//! registered.
+
+use std::fmt::{self, Write};
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use crate::broker;
+use crate::codec::JsonValue;
+use crate::cpp_runtime;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_name, MESSAGE_IDS};
+use crate::layout::{self, LayoutError};
+use crate::names::c_str;
+use crate::qos::Qos;
+
+/// Layout of the exchange; bumped when its meaning changes
+pub const CAPABILITIES_VERSION: u32 = 1;
+
+/// C functions Rust calls on the C++ side
+pub const REQUIRED_FUNCTIONS: &[&str] = &["cpp_actor_send", "cpp_actor_fast_send", "cpp_actor_exists"];
+
+/// What the C++ peer advertised
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub version: u32,
+    pub msg_ids: Vec<i32>,
+    pub functions: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CapabilityError {
+    /// cpp_actor_init() never called interop_cpp_capabilities()
+    NotExchanged,
+    Version { expected: u32, got: u32 },
+    Missing { functions: Vec<String>, msg_ids: Vec<i32> },
+    Layout(LayoutError),
+}
+
+impl fmt::Display for CapabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CapabilityError::NotExchanged => {
+                write!(f, "C++ peer sent no capabilities (cpp_actor_init() not called, or built with an old generator)")
+            }
+            CapabilityError::Version { expected, got } => {
+                write!(f, "C++ peer capabilities version {}, expected {}", got, expected)
+            }
+            CapabilityError::Missing { functions, msg_ids } => {
+                write!(f, "C++ peer is missing")?;
+                if !functions.is_empty() {
+                    write!(f, " functions: {};", functions.join(", "))?;
+                }
+                if !msg_ids.is_empty() {
+                    let names: Vec<String> = msg_ids
+                        .iter()
+                        .map(|&id| format!("{} ({})", message_name(id).unwrap_or("?"), id))
+                        .collect();
+                    write!(f, " messages: {};", names.join(", "))?;
+                }
+                Ok(())
+            }
+            CapabilityError::Layout(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CapabilityError {}
+
+static PEER: Mutex<Option<Capabilities>> = Mutex::new(None);
+static LAST_ERROR: Mutex<Option<CapabilityError>> = Mutex::new(None);
+
+/// Replace the peer; layouts sent by an earlier one are forgotten
+pub fn register(caps: Capabilities) {
+    layout::clear();
+    *PEER.lock().unwrap() = Some(caps);
+}
+
+pub fn peer() -> Option<Capabilities> {
+    PEER.lock().unwrap().clone()
+}
+
+/// Check the peer against what this build needs; the result is kept for
+/// interop_capability_report()
+pub fn verify() -> Result<(), CapabilityError> {
+    let result = match peer() {
+        None => Err(CapabilityError::NotExchanged),
+        Some(caps) if caps.version != CAPABILITIES_VERSION => {
+            Err(CapabilityError::Version { expected: CAPABILITIES_VERSION, got: caps.version })
+        }
+        Some(caps) => {
+            let functions: Vec<String> = REQUIRED_FUNCTIONS
+                .iter()
+                .filter(|f| !caps.functions.iter().any(|g| g == *f))
+                .map(|f| f.to_string())
+                .collect();
+            let msg_ids: Vec<i32> = MESSAGE_IDS.iter().copied().filter(|id| !caps.msg_ids.contains(id)).collect();
+            if functions.is_empty() && msg_ids.is_empty() {
+                layout::verify().map_err(CapabilityError::Layout)
+            } else {
+                Err(CapabilityError::Missing { functions, msg_ids })
+            }
+        }
+    };
+    *LAST_ERROR.lock().unwrap() = result.clone().err();
+    eprintln!("[Rust Manager] {}", summary());
+    result
+}
+
+// Cargo features reported in the summary
+const FEATURES: &[(&str, bool)] = &[
+    ("capnp", cfg!(feature = "capnp")),
+    ("chrono", cfg!(feature = "chrono")),
+    ("hot_reload", cfg!(feature = "hot_reload")),
+    ("kafka", cfg!(feature = "kafka")),
+    ("mqtt", cfg!(feature = "mqtt")),
+    ("node", cfg!(feature = "node")),
+    ("serde", cfg!(feature = "serde")),
+    ("sim", cfg!(feature = "sim")),
+    ("standalone", cfg!(feature = "standalone")),
+    ("tracing", cfg!(feature = "tracing")),
+    ("wasm", cfg!(feature = "wasm")),
+];
+
+/// What this deployment negotiated with its C++ peer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapabilitySummary {
+    /// `CAPABILITIES_VERSION` of this build
+    pub abi_version: u32,
+    pub peer_abi_version: Option<u32>,
+    /// See layout::rust_schema_hash()
+    pub schema_hash: u64,
+    pub peer_schema_hash: Option<u64>,
+    pub messages: usize,
+    pub peer_messages: Option<usize>,
+    pub features: Vec<&'static str>,
+    pub transports: Vec<String>,
+    /// Why the last check failed, None if it passed or none was made
+    pub error: Option<String>,
+}
+
+impl CapabilitySummary {
+    pub fn to_json(&self) -> String {
+        let mut out = String::with_capacity(256);
+        let opt = |v: Option<String>| v.unwrap_or_else(|| "null".to_string());
+        let _ = write!(
+            out,
+            "{{\"abi_version\":{},\"peer_abi_version\":{},\"schema_hash\":\"{:016x}\",\"peer_schema_hash\":{},\"messages\":{},\"peer_messages\":{},\"features\":[",
+            self.abi_version,
+            opt(self.peer_abi_version.map(|v| v.to_string())),
+            self.schema_hash,
+            opt(self.peer_schema_hash.map(|h| format!("\"{:016x}\"", h))),
+            self.messages,
+            opt(self.peer_messages.map(|n| n.to_string()))
+        );
+        for (i, feature) in self.features.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            feature.write_json(&mut out);
+        }
+        out.push_str("],\"transports\":[");
+        for (i, transport) in self.transports.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            transport.write_json(&mut out);
+        }
+        out.push_str("],\"error\":");
+        match &self.error {
+            Some(e) => e.write_json(&mut out),
+            None => out.push_str("null"),
+        }
+        out.push('}');
+        out
+    }
+}
+
+impl fmt::Display for CapabilitySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let peer = |v: Option<String>| v.unwrap_or_else(|| "none".to_string());
+        write!(
+            f,
+            "interop abi {} (C++ {}), schema {:016x} (C++ {}), {} messages (C++ {}), features: {}, transports: {}; {}",
+            self.abi_version,
+            peer(self.peer_abi_version.map(|v| v.to_string())),
+            self.schema_hash,
+            peer(self.peer_schema_hash.map(|h| format!("{:016x}", h))),
+            self.messages,
+            peer(self.peer_messages.map(|n| n.to_string())),
+            if self.features.is_empty() { "none".to_string() } else { self.features.join(", ") },
+            if self.transports.is_empty() { "none".to_string() } else { self.transports.join(", ") },
+            self.error.as_deref().unwrap_or("ok")
+        )
+    }
+}
+
+fn reliable_in_use() -> bool {
+    broker::all().iter().any(|(_, _, subs)| subs.iter().any(|s| Qos::from_flag(s.qos) == Qos::Reliable))
+}
+
+fn transports() -> Vec<String> {
+    let mut transports = Vec::new();
+    if !cfg!(feature = "standalone") {
+        transports.push("cpp".to_string());
+    }
+    transports.extend(cpp_runtime::attached().into_iter().map(|name| format!("cpp:{}", name)));
+    #[cfg(feature = "mqtt")]
+    if crate::names::is_rust(crate::mqtt_bridge::MQTT_BRIDGE_NAME) {
+        transports.push("mqtt".to_string());
+    }
+    transports
+}
+
+/// The capability summary as of now
+pub fn summary() -> CapabilitySummary {
+    let peer = peer();
+    let mut features: Vec<&'static str> = FEATURES.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+    if reliable_in_use() {
+        features.push("reliable_delivery");
+    }
+    CapabilitySummary {
+        abi_version: CAPABILITIES_VERSION,
+        peer_abi_version: peer.as_ref().map(|p| p.version),
+        schema_hash: layout::rust_schema_hash(),
+        peer_schema_hash: layout::peer_schema_hash(),
+        messages: MESSAGE_IDS.len(),
+        peer_messages: peer.as_ref().map(|p| p.msg_ids.len()),
+        features,
+        transports: transports(),
+        error: LAST_ERROR.lock().unwrap().as_ref().map(|e| e.to_string()),
+    }
+}
+
+/// Advertise what the C++ side implements. Called by the generated
+/// cpp_actor_init(). Returns 0, or InvalidArgument on null arrays with nonzero
+/// counts.
+#[export_name = c_symbol!("interop_cpp_capabilities")]
+pub extern "C" fn interop_cpp_capabilities(
+    version: u32,
+    msg_ids: *const i32,
+    msg_count: u32,
+    functions: *const *const c_char,
+    function_count: u32,
+) -> c_int {
+    if (msg_ids.is_null() && msg_count > 0) || (functions.is_null() && function_count > 0) {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    let msg_ids = if msg_count == 0 {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(msg_ids, msg_count as usize) }.to_vec()
+    };
+    let mut names = Vec::with_capacity(function_count as usize);
+    for i in 0..function_count as usize {
+        if let Some(s) = c_str(unsafe { *functions.add(i) }) {
+            names.push(s.to_string());
+        }
+    }
+    register(Capabilities { version, msg_ids, functions: names });
+    0
+}
+
+/// Write why the last check failed into `out` (NUL-terminated, truncated
+/// to fit `len`); empty if it passed. Returns the full length like snprintf.
+#[export_name = c_symbol!("interop_capability_report")]
+pub extern "C" fn interop_capability_report(out: *mut c_char, len: usize) -> c_int {
+    let report = LAST_ERROR.lock().unwrap().as_ref().map(|e| e.to_string()).unwrap_or_default();
+    if !out.is_null() && len > 0 {
+        let n = report.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(report.as_ptr(), out as *mut u8, n);
+            *out.add(n) = 0;
+        }
+    }
+    report.len().min(c_int::MAX as usize) as c_int
+}
+
+/// Write the capability summary as JSON into `out` (NUL-terminated,
+/// truncated to fit `len`). Returns the full length like snprintf.
+#[export_name = c_symbol!("interop_capability_summary")]
+pub extern "C" fn interop_capability_summary(out: *mut c_char, len: usize) -> c_int {
+    let json = summary().to_json();
+    if !out.is_null() && len > 0 {
+        let n = json.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(json.as_ptr(), out as *mut u8, n);
+            *out.add(n) = 0;
+        }
+    }
+    json.len().min(c_int::MAX as usize) as c_int
+}
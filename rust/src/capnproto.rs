@@ -0,0 +1,597 @@
+//! Cap'n Proto encoding of the interop messages (feature `capnp`)
+//!
+//! Every message gets `encode_capnp()` / `decode_capnp()` and a
+//! `<Name>CapnpView` that reads fields where they lie in the received
+//! bytes; `codec::CapnProtoCodec` carries them after the usual frame
+//! header. The generator writes the schema they follow,
+//! `generated/capnp/interop_messages.capnp`, so peers elsewhere in the
+//! stack build readers with capnpc and RPC interfaces can import the
+//! message structs.
+//!
+//! | Field | Cap'n Proto type |
+//! |-------|------------------|
+//! | integers, float, double | `Int32`, `Int64`, `UInt32`, `UInt64`, `Float32`, `Float64` |
+//! | `/* bool */` int32 | `Bool` |
+//! | `char[N]`, `interop_string` | `Text` |
+//! | `interop_bytes` | `Data` |
+//! | numeric `T[N]` | `List(T)`; fewer elements decode as zeros |
+//! | `INTEROP_MAP(T, ...)` | `List(<T>Entry)`, sorted by key |
+//! | `interop_decimal` | struct `Decimal` |
+//! | `interop_uuid` | `Data` of 16 bytes |
+//! | `interop_timestamp` | `Int64`, UTC nanoseconds |
+//!
+//! Field ordinals follow declaration order and the generator lays fields
+//! out as the capnp compiler does, so the bytes are a standard serialized
+//! message. That gives the schema Cap'n Proto's evolution rules: fields
+//! may be added at the end of a message (older readers skip them, newer
+//! readers see zero or empty where older writers had none) and renamed,
+//! but not removed, reordered or retyped.
+//!
+//! Messages are written as one segment. Reading follows far pointers, so
+//! multi-segment messages from other implementations decode too;
+//! capabilities do not.
+
+use std::collections::HashMap;
+
+use crate::decimal::Decimal;
+use crate::interop_string::InteropString;
+use crate::timestamp::Timestamp;
+use crate::uuid::Uuid;
+
+const WORD: usize = 8;
+
+// List element sizes (the pointer's C field)
+const BYTE: u64 = 2;
+const COMPOSITE: u64 = 7;
+
+/// Position of a struct being built
+#[derive(Clone, Copy, Debug)]
+pub struct StructAt {
+    data: usize,
+    ptrs: usize,
+}
+
+impl StructAt {
+    fn pointer(&self, at: u32) -> usize {
+        self.ptrs + at as usize * WORD
+    }
+}
+
+/// Builds a single-segment message, front to back: root pointer, root
+/// struct, then each struct, list or text as it is added
+pub struct MessageBuilder {
+    seg: Vec<u8>,
+}
+
+impl Default for MessageBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MessageBuilder {
+    pub fn new() -> Self {
+        MessageBuilder { seg: vec![0; WORD] }
+    }
+
+    /// The root struct, with its data and pointer section sizes in words
+    pub fn root(&mut self, data_words: u16, ptrs: u16) -> StructAt {
+        self.init_struct(0, data_words, ptrs)
+    }
+
+    /// Set field `at` of `s`: offset in the field's own width for data
+    /// fields, pointer index for the rest
+    pub fn put<T: CapnpField>(&mut self, s: StructAt, at: u32, value: &T) {
+        value.put_capnp(self, s, at);
+    }
+
+    /// The message with its segment table
+    pub fn finish(self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(WORD + self.seg.len());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&((self.seg.len() / WORD) as u32).to_le_bytes());
+        out.extend_from_slice(&self.seg);
+        out
+    }
+
+    fn alloc(&mut self, words: usize) -> usize {
+        let at = self.seg.len();
+        self.seg.resize(at + words * WORD, 0);
+        at
+    }
+
+    // Pointer word at `ptr` to `target`: kind in bits 0-1, offset in words
+    // after the pointer in bits 2-31, `upper` in bits 32-63
+    fn point(&mut self, ptr: usize, target: usize, kind: u64, upper: u64) {
+        let offset = ((target - ptr - WORD) / WORD) as u64;
+        self.set_word(ptr, kind | offset << 2 | upper << 32);
+    }
+
+    fn set_word(&mut self, at: usize, word: u64) {
+        self.seg[at..at + WORD].copy_from_slice(&word.to_le_bytes());
+    }
+
+    fn init_struct(&mut self, ptr: usize, data_words: u16, ptrs: u16) -> StructAt {
+        let data = self.alloc(data_words as usize + ptrs as usize);
+        if data_words == 0 && ptrs == 0 {
+            // Offset -1: an empty struct, not a null pointer
+            self.set_word(ptr, 0xffff_fffc);
+        } else {
+            self.point(ptr, data, 0, data_words as u64 | (ptrs as u64) << 16);
+        }
+        StructAt { data, ptrs: data + data_words as usize * WORD }
+    }
+
+    /// Struct for pointer `at` of `s`
+    pub fn put_struct(&mut self, s: StructAt, at: u32, data_words: u16, ptrs: u16) -> StructAt {
+        self.init_struct(s.pointer(at), data_words, ptrs)
+    }
+
+    /// `count` structs in a list for pointer `at` of `s`
+    pub fn put_struct_list(&mut self, s: StructAt, at: u32, count: usize, data_words: u16, ptrs: u16) -> Vec<StructAt> {
+        let stride = data_words as usize + ptrs as usize;
+        let tag = self.alloc(1 + count * stride);
+        self.point(s.pointer(at), tag, 1, COMPOSITE | ((count * stride) as u64) << 3);
+        // The tag word is a struct pointer whose offset is the element count
+        self.set_word(tag, (count as u64) << 2 | (data_words as u64 | (ptrs as u64) << 16) << 32);
+        (0..count)
+            .map(|i| {
+                let data = tag + WORD + i * stride * WORD;
+                StructAt { data, ptrs: data + data_words as usize * WORD }
+            })
+            .collect()
+    }
+
+    /// List of `count` elements of size code `size` for pointer `at` of `s`
+    pub fn put_list(&mut self, s: StructAt, at: u32, size: u64, count: usize, body: &[u8]) {
+        let list = self.alloc(body.len().div_ceil(WORD));
+        self.seg[list..list + body.len()].copy_from_slice(body);
+        self.point(s.pointer(at), list, 1, size | (count as u64) << 3);
+    }
+
+    /// Text for pointer `at` of `s`; empty text stays a null pointer
+    pub fn put_text(&mut self, s: StructAt, at: u32, text: &str) {
+        if !text.is_empty() {
+            let mut body = Vec::with_capacity(text.len() + 1);
+            body.extend_from_slice(text.as_bytes());
+            body.push(0);
+            self.put_list(s, at, BYTE, body.len(), &body);
+        }
+    }
+
+    /// Data for pointer `at` of `s`; empty data stays a null pointer
+    pub fn put_data(&mut self, s: StructAt, at: u32, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            self.put_list(s, at, BYTE, bytes.len(), bytes);
+        }
+    }
+
+    /// `bytes` at byte `offset` of the data section of `s`
+    pub fn put_bytes(&mut self, s: StructAt, offset: usize, bytes: &[u8]) {
+        self.seg[s.data + offset..s.data + offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// Bit `bit` of the data section of `s`
+    pub fn put_bit(&mut self, s: StructAt, bit: u32, value: bool) {
+        let at = s.data + bit as usize / 8;
+        if value {
+            self.seg[at] |= 1 << (bit % 8);
+        } else {
+            self.seg[at] &= !(1 << (bit % 8));
+        }
+    }
+}
+
+/// A struct read in place from a serialized message
+#[derive(Clone, Copy, Debug)]
+pub struct StructReader<'a> {
+    buf: &'a [u8],
+    // Segment holding the struct, as a byte range of `buf`
+    seg: (usize, usize),
+    data: usize,
+    data_len: usize,
+    ptrs: usize,
+    ptr_count: usize,
+}
+
+/// The root struct of a serialized message (with its segment table); None
+/// if the table or root pointer is malformed
+pub fn root(buf: &[u8]) -> Option<StructReader<'_>> {
+    let seg = segment(buf, 0)?;
+    if seg.1 - seg.0 < WORD {
+        return None;
+    }
+    StructReader::empty(buf, seg).struct_at(seg.0)
+}
+
+impl<'a> StructReader<'a> {
+    // Every field reads as its default
+    fn empty(buf: &'a [u8], seg: (usize, usize)) -> Self {
+        StructReader { buf, seg, data: seg.0, data_len: 0, ptrs: seg.0, ptr_count: 0 }
+    }
+
+    /// `N` bytes at byte `offset` of the data section, zeros past its end
+    pub fn bytes<const N: usize>(&self, offset: usize) -> [u8; N] {
+        let mut out = [0u8; N];
+        if offset + N <= self.data_len {
+            out.copy_from_slice(&self.buf[self.data + offset..self.data + offset + N]);
+        }
+        out
+    }
+
+    /// Bit `bit` of the data section, false past its end
+    pub fn bit(&self, bit: u32) -> bool {
+        let at = bit as usize / 8;
+        at < self.data_len && self.buf[self.data + at] & (1 << (bit % 8)) != 0
+    }
+
+    /// Position of pointer `at`, None past the pointer section (a null
+    /// pointer, as written by an older schema)
+    fn pointer(&self, at: u32) -> Option<usize> {
+        ((at as usize) < self.ptr_count).then(|| self.ptrs + at as usize * WORD)
+    }
+
+    // Struct pointed to from the pointer word at `ptr` in this segment;
+    // null reads as an empty struct
+    fn struct_at(&self, ptr: usize) -> Option<Self> {
+        let (seg, target, word) = match follow(self.buf, self.seg, ptr)? {
+            Some(t) => t,
+            None => return Some(StructReader::empty(self.buf, self.seg)),
+        };
+        if word & 3 != 0 {
+            return None;
+        }
+        let data_len = ((word >> 32) & 0xffff) as usize * WORD;
+        let ptr_count = (word >> 48) as usize;
+        let end = target.checked_add(data_len + ptr_count * WORD)?;
+        if target < seg.0 || end > seg.1 {
+            return None;
+        }
+        Some(StructReader { buf: self.buf, seg, data: target, data_len, ptrs: target + data_len, ptr_count })
+    }
+
+    /// Struct at pointer `at`. None if malformed; a null pointer reads as a
+    /// struct of defaults.
+    pub fn get_struct(&self, at: u32) -> Option<Self> {
+        match self.pointer(at) {
+            Some(ptr) => self.struct_at(ptr),
+            None => Some(StructReader::empty(self.buf, self.seg)),
+        }
+    }
+
+    /// Elements of the list at pointer `at`, whose element size code must
+    /// be `size` (2 for bytes, 4 for 32-bit, 5 for 64-bit). None if
+    /// malformed, Some(None) if null.
+    pub fn list(&self, at: u32, size: u64) -> Option<Option<&'a [u8]>> {
+        let ptr = match self.pointer(at) {
+            Some(ptr) => ptr,
+            None => return Some(None),
+        };
+        let (seg, target, word) = match follow(self.buf, self.seg, ptr)? {
+            Some(t) => t,
+            None => return Some(None),
+        };
+        if word & 3 != 1 || (word >> 32) & 7 != size {
+            return None;
+        }
+        let len = ((word >> 35) as usize).checked_mul(1 << (size - 2))?;
+        let end = target.checked_add(len)?;
+        if target < seg.0 || end > seg.1 {
+            return None;
+        }
+        Some(Some(&self.buf[target..end]))
+    }
+
+    /// Text at pointer `at`, "" if null
+    pub fn text(&self, at: u32) -> Option<&'a str> {
+        match self.list(at, BYTE)? {
+            Some([text @ .., 0]) => std::str::from_utf8(text).ok(),
+            Some(_) => None,  // not NUL-terminated
+            None => Some(""),
+        }
+    }
+
+    /// Data at pointer `at`, empty if null
+    pub fn data(&self, at: u32) -> Option<&'a [u8]> {
+        Some(self.list(at, BYTE)?.unwrap_or_default())
+    }
+
+    /// Numeric list at pointer `at`, empty if null
+    pub fn scalars<T: CapnpScalar>(&self, at: u32) -> Option<Scalars<'a, T>> {
+        let bytes = self.list(at, T::SIZE_CODE)?.unwrap_or_default();
+        Some(Scalars { bytes, _elem: std::marker::PhantomData })
+    }
+
+    /// Structs of the list at pointer `at`, empty if null
+    pub fn struct_list(&self, at: u32) -> Option<Vec<Self>> {
+        let ptr = match self.pointer(at) {
+            Some(ptr) => ptr,
+            None => return Some(Vec::new()),
+        };
+        let (seg, tag, word) = match follow(self.buf, self.seg, ptr)? {
+            Some(t) => t,
+            None => return Some(Vec::new()),
+        };
+        if word & 3 != 1 || (word >> 32) & 7 != COMPOSITE {
+            return None;
+        }
+        let words = (word >> 35) as usize;
+        let tag_word = read_u64(self.buf, tag)?;
+        let count = (tag_word as u32 >> 2) as usize;
+        let data_len = ((tag_word >> 32) & 0xffff) as usize * WORD;
+        let ptr_count = (tag_word >> 48) as usize;
+        let stride = data_len + ptr_count * WORD;
+        // Empty structs take no room: bound them by the segment instead
+        if tag_word & 3 != 0 || count.checked_mul(stride)? > words * WORD || count > (seg.1 - seg.0) / WORD {
+            return None;
+        }
+        let first = tag + WORD;
+        if tag < seg.0 || first.checked_add(words * WORD)? > seg.1 {
+            return None;
+        }
+        Some(
+            (0..count)
+                .map(|i| {
+                    let data = first + i * stride;
+                    StructReader { buf: self.buf, seg, data, data_len, ptrs: data + data_len, ptr_count }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A numeric list read in place
+#[derive(Clone, Copy, Debug)]
+pub struct Scalars<'a, T> {
+    bytes: &'a [u8],
+    _elem: std::marker::PhantomData<T>,
+}
+
+impl<'a, T: CapnpScalar> Scalars<'a, T> {
+    pub fn len(&self) -> usize {
+        self.bytes.len() / T::SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<T> {
+        Some(T::get_le(self.bytes.get(i * T::SIZE..(i + 1) * T::SIZE)?))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = T> + 'a {
+        self.bytes.chunks_exact(T::SIZE).map(T::get_le)
+    }
+}
+
+fn read_u32(buf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(at..at.checked_add(4)?)?.try_into().ok()?))
+}
+
+fn read_u64(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(buf.get(at..at.checked_add(WORD)?)?.try_into().ok()?))
+}
+
+/// Byte range of segment `id` in a message with its segment table
+fn segment(buf: &[u8], id: u32) -> Option<(usize, usize)> {
+    let count = read_u32(buf, 0)? as usize + 1;
+    if id as usize >= count {
+        return None;
+    }
+    let mut start = (4 + 4 * count).div_ceil(WORD) * WORD;
+    for i in 0..id as usize {
+        start = start.checked_add(read_u32(buf, 4 + 4 * i)? as usize * WORD)?;
+    }
+    let end = start.checked_add(read_u32(buf, 4 + 4 * id as usize)? as usize * WORD)?;
+    (end <= buf.len()).then_some((start, end))
+}
+
+// Start of what a struct or list pointer at `at` points to
+fn target(at: usize, word: u64) -> Option<usize> {
+    let offset = (word as u32 as i32 >> 2) as isize;
+    at.checked_add(WORD)?.checked_add_signed(offset.checked_mul(WORD as isize)?)
+}
+
+// Segment, start and describing pointer word of what a pointer points to
+type Target = ((usize, usize), usize, u64);
+
+/// What the pointer at `at` in segment `seg` points to. None if malformed
+/// or a capability, Some(None) if null. Far pointers are followed.
+fn follow(buf: &[u8], seg: (usize, usize), at: usize) -> Option<Option<Target>> {
+    let word = read_u64(buf, at)?;
+    if word == 0 {
+        return Some(None);
+    }
+    match word & 3 {
+        0 | 1 => Some(Some((seg, target(at, word)?, word))),
+        2 => {
+            let pad_seg = segment(buf, (word >> 32) as u32)?;
+            let pad = pad_seg.0.checked_add((word as u32 >> 3) as usize * WORD)?;
+            if word & 4 == 0 {
+                // Landing pad: an ordinary pointer in the other segment
+                let landing = read_u64(buf, pad)?;
+                if landing & 3 > 1 || pad + WORD > pad_seg.1 {
+                    return None;
+                }
+                return Some(Some((pad_seg, target(pad, landing)?, landing)));
+            }
+            // Double far: a far pointer to the content, then its tag
+            let far = read_u64(buf, pad)?;
+            let tag = read_u64(buf, pad + WORD)?;
+            if far & 7 != 2 || tag & 3 > 1 || pad + 2 * WORD > pad_seg.1 {
+                return None;
+            }
+            let seg = segment(buf, (far >> 32) as u32)?;
+            Some(Some((seg, seg.0.checked_add((far as u32 >> 3) as usize * WORD)?, tag)))
+        }
+        _ => None,
+    }
+}
+
+/// A message field in a struct; `get_capnp` is None on a malformed message
+pub trait CapnpField: Sized {
+    /// Lives in the pointer section (else the data section)
+    const POINTER: bool;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32);
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self>;
+}
+
+/// Element of a numeric list
+pub trait CapnpScalar: Copy + Default + 'static {
+    const SIZE: usize;
+    const SIZE_CODE: u64;
+    fn put_le(self, out: &mut Vec<u8>);
+    fn get_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! capnp_scalar {
+    ($($t:ty => $code:expr),*) => {$(
+        impl CapnpScalar for $t {
+            const SIZE: usize = std::mem::size_of::<$t>();
+            const SIZE_CODE: u64 = $code;
+            fn put_le(self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn get_le(bytes: &[u8]) -> Self {
+                let mut le = [0u8; std::mem::size_of::<$t>()];
+                le.copy_from_slice(&bytes[..Self::SIZE]);
+                <$t>::from_le_bytes(le)
+            }
+        }
+
+        impl CapnpField for $t {
+            const POINTER: bool = false;
+            fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+                m.put_bytes(s, at as usize * Self::SIZE, &self.to_le_bytes());
+            }
+            fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+                Some(<$t>::from_le_bytes(s.bytes(at as usize * Self::SIZE)))
+            }
+        }
+    )*};
+}
+
+capnp_scalar!(i32 => 4, u32 => 4, f32 => 4, i64 => 5, u64 => 5, f64 => 5);
+
+impl CapnpField for bool {
+    const POINTER: bool = false;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        m.put_bit(s, at, *self);
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        Some(s.bit(at))
+    }
+}
+
+/// `Int64` nanoseconds
+impl CapnpField for Timestamp {
+    const POINTER: bool = false;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        self.nanos.put_capnp(m, s, at);
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        Some(Timestamp::from_nanos(i64::get_capnp(s, at)?))
+    }
+}
+
+impl<T: CapnpScalar, const N: usize> CapnpField for [T; N] {
+    const POINTER: bool = true;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        let mut bytes = Vec::with_capacity(N * T::SIZE);
+        for v in self {
+            v.put_le(&mut bytes);
+        }
+        m.put_list(s, at, T::SIZE_CODE, N, &bytes);
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        let list = s.scalars::<T>(at)?;
+        if list.len() > N {
+            return None;
+        }
+        let mut arr = [T::default(); N];
+        for (v, x) in arr.iter_mut().zip(list.iter()) {
+            *v = x;
+        }
+        Some(arr)
+    }
+}
+
+impl CapnpField for Vec<u8> {
+    const POINTER: bool = true;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        m.put_data(s, at, self);
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        Some(s.data(at)?.to_vec())
+    }
+}
+
+impl CapnpField for String {
+    const POINTER: bool = true;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        m.put_text(s, at, self);
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        Some(s.text(at)?.to_string())
+    }
+}
+
+impl<const N: usize> CapnpField for InteropString<N> {
+    const POINTER: bool = true;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        m.put_text(s, at, self.as_str());
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        Some(InteropString::from(s.text(at)?))
+    }
+}
+
+/// `Data` of 16 bytes; null reads as the nil UUID
+impl CapnpField for Uuid {
+    const POINTER: bool = true;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        m.put_list(s, at, BYTE, 16, &self.bytes);
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        match s.data(at)? {
+            [] => Some(Uuid::default()),
+            bytes => Some(Uuid::from_bytes(bytes.try_into().ok()?)),
+        }
+    }
+}
+
+/// struct Decimal { units @0 :Int64; scale @1 :Int32; } - two data words
+impl CapnpField for Decimal {
+    const POINTER: bool = true;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        let d = m.put_struct(s, at, 2, 0);
+        m.put(d, 0, &self.units);
+        m.put(d, 2, &self.scale);
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        let d = s.get_struct(at)?;
+        Some(Decimal::new(i64::get_capnp(&d, 0)?, i32::get_capnp(&d, 2)?))
+    }
+}
+
+/// List of entry structs, `key @0 :Text` then `value @1`: one data word
+/// and one pointer for a numeric value, two pointers otherwise
+impl<V: CapnpField> CapnpField for HashMap<String, V> {
+    const POINTER: bool = true;
+    fn put_capnp(&self, m: &mut MessageBuilder, s: StructAt, at: u32) {
+        // Sorted so equal maps encode identically
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        let (data_words, ptrs, value_at) = if V::POINTER { (0, 2, 1) } else { (1, 1, 0) };
+        let structs = m.put_struct_list(s, at, entries.len(), data_words, ptrs);
+        for (e, (k, v)) in structs.into_iter().zip(entries) {
+            m.put_text(e, 0, k);
+            m.put(e, value_at, v);
+        }
+    }
+    fn get_capnp(s: &StructReader, at: u32) -> Option<Self> {
+        let value_at = if V::POINTER { 1 } else { 0 };
+        s.struct_list(at)?.iter().map(|e| Some((e.text(0)?.to_string(), V::get_capnp(e, value_at)?))).collect()
+    }
+}
@@ -0,0 +1,238 @@
+//! Sampled payloads of bridge traffic for production debugging
+//!
+//! Full recording (see replay) is too heavy to leave on in production.
+//! Capture instead keeps 1 in N messages of each message type that cross
+//! the bridge, in a ring of the last M, and dumps them as JSON on demand:
+//!
+//! ```ignore
+//! capture::start(100, 512);                  // 1 in 100 per type, last 512
+//! capture::set_every(MSG_NEWORDER, 1);       // but every order
+//! println!("{}", capture::recent_json(50));
+//! ```
+//!
+//! ```json
+//! [{"time_ms":1760000000123,"sender":"cpp_feed","target":"rust_strategy",
+//!   "msg_id":1002,"message":"MarketUpdate","payload":{"symbol":"AAPL",...}},...]
+//! ```
+//!
+//! The first message of a type is kept, then every N-th. Sampling sees
+//! what wire taps see (see tap), on the sending thread: while capture is
+//! off it costs one atomic load per message, while on a counter update,
+//! and only kept messages are converted to JSON. `payload` is null for
+//! messages `to_json()` cannot show. C++ uses `interop_capture_start()`
+//! and `interop_capture_json()` (`interop/capture.h`); `interop-ctl
+//! samples` reads the ring of a running process.
+
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
+use std::fmt::Write;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use actors::Message;
+
+use crate::clock;
+use crate::codec::JsonValue;
+use crate::interop_messages::message_to_debug_json;
+use crate::schema;
+use crate::tenant;
+
+/// Samples kept by default
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// One kept message
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sample {
+    /// Wall clock, ms since the epoch (see clock)
+    pub time_ms: u64,
+    /// Empty if the sender gave no name
+    pub sender: String,
+    pub target: String,
+    pub msg_id: i32,
+    /// The message as JSON, None if it has no JSON form
+    pub payload: Option<String>,
+}
+
+impl Sample {
+    pub fn to_json(&self) -> String {
+        let mut out = String::with_capacity(128 + self.payload.as_ref().map_or(0, String::len));
+        let _ = write!(out, "{{\"time_ms\":{},\"sender\":", self.time_ms);
+        self.sender.write_json(&mut out);
+        out.push_str(",\"target\":");
+        self.target.write_json(&mut out);
+        let _ = write!(out, ",\"msg_id\":{},\"message\":", self.msg_id);
+        match schema::by_id(self.msg_id) {
+            Some(s) => s.name.write_json(&mut out),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"payload\":");
+        out.push_str(self.payload.as_deref().unwrap_or("null"));
+        out.push('}');
+        out
+    }
+}
+
+// Fast path: no lock while capture is off
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static EVERY: AtomicU32 = AtomicU32::new(1);
+static CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_CAPACITY);
+static KEPT: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Default)]
+struct Counters {
+    // Messages seen per type since start()
+    seen: HashMap<i32, u64>,
+    // Rates set with set_every()
+    every: HashMap<i32, u32>,
+}
+
+static COUNTERS: Mutex<Option<Counters>> = Mutex::new(None);
+static RING: Mutex<VecDeque<Sample>> = Mutex::new(VecDeque::new());
+
+/// Keep 1 in `every` messages of each type (0 is taken as 1) in a ring of
+/// the last `capacity`. Restarts the per-type counts; the ring and rates
+/// set with set_every() are kept.
+pub fn start(every: u32, capacity: usize) {
+    EVERY.store(every.max(1), Ordering::Relaxed);
+    set_capacity(capacity);
+    let mut counters = COUNTERS.lock().unwrap();
+    counters.get_or_insert_with(Counters::default).seen.clear();
+    ACTIVE.store(capacity > 0, Ordering::Release);
+}
+
+/// Stop sampling; what was kept stays readable
+pub fn stop() {
+    ACTIVE.store(false, Ordering::Release);
+}
+
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Acquire)
+}
+
+/// Keep 1 in `every` messages of type `msg_id` instead of the rate given
+/// to start(); 0 goes back to that rate
+pub fn set_every(msg_id: i32, every: u32) {
+    let mut counters = COUNTERS.lock().unwrap();
+    let counters = counters.get_or_insert_with(Counters::default);
+    if every == 0 {
+        counters.every.remove(&msg_id);
+    } else {
+        counters.every.insert(msg_id, every);
+    }
+}
+
+/// Keep the last `capacity` samples
+pub fn set_capacity(capacity: usize) {
+    CAPACITY.store(capacity, Ordering::Relaxed);
+    let mut ring = RING.lock().unwrap();
+    while ring.len() > capacity {
+        ring.pop_front();
+    }
+}
+
+pub fn capacity() -> usize {
+    CAPACITY.load(Ordering::Relaxed)
+}
+
+/// Messages kept since the process started
+pub fn kept() -> u64 {
+    KEPT.load(Ordering::Relaxed)
+}
+
+/// Keep `msg` if it is its type's turn. Called for every message the
+/// bridge passes to taps.
+pub(crate) fn sample(sender: &str, target: &str, msg: &dyn Message) {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return;
+    }
+    let msg_id = msg.message_id();
+    {
+        let mut counters = COUNTERS.lock().unwrap();
+        let counters = counters.get_or_insert_with(Counters::default);
+        let every = counters.every.get(&msg_id).copied().unwrap_or_else(|| EVERY.load(Ordering::Relaxed));
+        let seen = counters.seen.entry(msg_id).or_insert(0);
+        let turn = *seen % every as u64 == 0;
+        *seen += 1;
+        if !turn {
+            return;
+        }
+    }
+    let capacity = capacity();
+    if capacity == 0 {
+        return;
+    }
+    let sample = Sample {
+        time_ms: clock::wall().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+        sender: sender.to_string(),
+        target: target.to_string(),
+        msg_id,
+        payload: message_to_debug_json(msg),
+    };
+    let mut ring = RING.lock().unwrap();
+    while ring.len() >= capacity {
+        ring.pop_front();
+    }
+    ring.push_back(sample);
+    KEPT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The last `n` samples, oldest first
+pub fn recent(n: usize) -> Vec<Sample> {
+    let ring = RING.lock().unwrap();
+    ring.iter().skip(ring.len().saturating_sub(n)).cloned().collect()
+}
+
+pub fn clear() {
+    RING.lock().unwrap().clear();
+}
+
+/// The last `n` samples as a JSON array, oldest first. Within a tenant view,
+/// only those from or to its actors (see tenant).
+pub fn recent_json(n: usize) -> String {
+    let shown: Vec<Sample> = match tenant::in_view() {
+        Some(_) => recent(usize::MAX)
+            .into_iter()
+            .filter(|r| tenant::visible(&r.sender) || tenant::visible(&r.target))
+            .collect(),
+        None => recent(n),
+    };
+    let parts: Vec<String> = shown[shown.len().saturating_sub(n)..].iter().map(Sample::to_json).collect();
+    format!("[{}]", parts.join(","))
+}
+
+/// Keep 1 in `every` messages of each type crossing the bridge, the last
+/// `capacity` of them (0: DEFAULT_CAPACITY)
+#[export_name = c_symbol!("interop_capture_start")]
+pub extern "C" fn interop_capture_start(every: u32, capacity: u32) {
+    let capacity = if capacity == 0 { DEFAULT_CAPACITY } else { capacity as usize };
+    start(every, capacity);
+}
+
+/// Stop sampling; kept samples stay readable
+#[export_name = c_symbol!("interop_capture_stop")]
+pub extern "C" fn interop_capture_stop() {
+    stop();
+}
+
+/// Keep 1 in `every` messages of type `msg_type`; 0 goes back to the rate
+/// given to interop_capture_start()
+#[export_name = c_symbol!("interop_capture_set_every")]
+pub extern "C" fn interop_capture_set_every(msg_type: i32, every: u32) {
+    set_every(msg_type, every);
+}
+
+/// The last `max` samples (all kept if 0) as a JSON array, oldest first;
+/// free with interop_free_json()
+#[export_name = c_symbol!("interop_capture_json")]
+pub extern "C" fn interop_capture_json(max: u32) -> *mut c_char {
+    let n = if max == 0 { usize::MAX } else { max as usize };
+    CString::new(recent_json(n)).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Drop the kept samples
+#[export_name = c_symbol!("interop_capture_clear")]
+pub extern "C" fn interop_capture_clear() {
+    clear();
+}
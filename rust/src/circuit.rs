@@ -0,0 +1,216 @@
+//! Circuit breaker per sender -> C++ target pair
+//!
+//! When a C++ actor is dead or wedged, every Rust send to it pays the FFI
+//! call, the failure and any retry backoff. With a breaker policy set, a
+//! pair whose sends keep failing is cut off for a while instead:
+//!
+//! ```ignore
+//! circuit::set_policy(Some(BreakerPolicy::new(5, Duration::from_secs(2))));
+//! ```
+//!
+//! - Closed: sends go through; `failures` consecutive failed sends (after
+//!   retries) open the circuit.
+//! - Open: sends fail at once with `CIRCUIT_OPEN`, no FFI call, for
+//!   `open_for`.
+//! - Half-open: the next send is let through as a probe while the pair's
+//!   other sends still fail fast. Success closes the circuit, failure opens
+//!   it for another `open_for`.
+//!
+//! Opening and closing are logged and published on `$sys/circuit` (see
+//! sys_topics) with the target as subject. Failures counted are the C++
+//! side's: not found, full queues and C++ errors, but not a message C++
+//! cannot convert, validate or take at its size, or bad arguments, which
+//! are the sender's. Fast failures go to the retry failure callback like
+//! other failed sends. C++ configures it with `interop_set_circuit_breaker()`.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::sys_topics;
+
+/// Send return code while the pair's circuit is open
+pub const CIRCUIT_OPEN: i32 = InteropErrorCode::CircuitOpen as i32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BreakerPolicy {
+    /// Consecutive failed sends that open the circuit
+    pub failures: u32,
+    /// How long an open circuit fails fast before a probe
+    pub open_for: Duration,
+}
+
+impl BreakerPolicy {
+    pub fn new(failures: u32, open_for: Duration) -> Self {
+        BreakerPolicy { failures: failures.max(1), open_for }
+    }
+}
+
+/// Mirrors INTEROP_CIRCUIT_* in interop/circuit.h
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum CircuitState {
+    Closed = 0,
+    Open = 1,
+    HalfOpen = 2,
+}
+
+enum Breaker {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref POLICY: Mutex<Option<BreakerPolicy>> = Mutex::new(None);
+    // target -> sender -> breaker; pairs without failures are not kept
+    static ref BREAKERS: Mutex<HashMap<String, HashMap<String, Breaker>>> = Mutex::new(HashMap::new());
+}
+
+/// Break circuits under `policy`, or never with None (the default).
+/// Clears every pair's state.
+pub fn set_policy(policy: Option<BreakerPolicy>) {
+    *POLICY.lock().unwrap() = policy;
+    BREAKERS.lock().unwrap().clear();
+    ENABLED.store(policy.is_some(), Ordering::Release);
+}
+
+pub fn policy() -> Option<BreakerPolicy> {
+    *POLICY.lock().unwrap()
+}
+
+/// State of the `sender` -> `target` circuit
+pub fn state(sender: &str, target: &str) -> CircuitState {
+    match BREAKERS.lock().unwrap().get(target).and_then(|s| s.get(sender)) {
+        None | Some(Breaker::Closed { .. }) => CircuitState::Closed,
+        Some(Breaker::Open { .. }) => CircuitState::Open,
+        Some(Breaker::HalfOpen) => CircuitState::HalfOpen,
+    }
+}
+
+/// Whether `admit()` would let a send through, without taking the probe:
+/// closed, or open past its time. For routers choosing between targets.
+pub fn available(sender: &str, target: &str) -> bool {
+    if !ENABLED.load(Ordering::Acquire) {
+        return true;
+    }
+    match BREAKERS.lock().unwrap().get(target).and_then(|s| s.get(sender)) {
+        None | Some(Breaker::Closed { .. }) => true,
+        Some(Breaker::Open { until }) => clock::now() >= *until,
+        Some(Breaker::HalfOpen) => false,
+    }
+}
+
+/// Whether a send from `sender` to `target` may go to C++ now. An open
+/// circuit past its time lets this one send through as the probe.
+pub fn admit(sender: &str, target: &str) -> bool {
+    if !ENABLED.load(Ordering::Acquire) {
+        return true;
+    }
+    let mut breakers = BREAKERS.lock().unwrap();
+    let breaker = match breakers.get_mut(target).and_then(|s| s.get_mut(sender)) {
+        Some(b) => b,
+        None => return true,
+    };
+    match breaker {
+        Breaker::Closed { .. } => true,
+        Breaker::Open { until } if clock::now() >= *until => {
+            *breaker = Breaker::HalfOpen;
+            true
+        }
+        Breaker::Open { .. } | Breaker::HalfOpen => false,
+    }
+}
+
+/// Whether `rc` says the C++ side failed, rather than the message
+fn is_failure(rc: i32) -> bool {
+    !matches!(
+        InteropErrorCode::from_code(rc),
+        Some(
+            InteropErrorCode::Ok
+                | InteropErrorCode::UnknownMessage
+                | InteropErrorCode::WrongType
+                | InteropErrorCode::InvalidMessage
+                | InteropErrorCode::TooLarge
+                | InteropErrorCode::InvalidArgument
+                | InteropErrorCode::InvalidName
+        )
+    )
+}
+
+/// Record the result of an admitted send
+pub fn record(sender: &str, target: &str, rc: i32) {
+    if !ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+    let policy = match policy() {
+        Some(p) => p,
+        None => return,
+    };
+    let mut breakers = BREAKERS.lock().unwrap();
+    if !is_failure(rc) {
+        let closed = match breakers.get_mut(target) {
+            Some(senders) => {
+                let was = senders.remove(sender);
+                if senders.is_empty() {
+                    breakers.remove(target);
+                }
+                matches!(was, Some(Breaker::HalfOpen))
+            }
+            None => false,
+        };
+        drop(breakers);
+        if closed {
+            eprintln!("[Circuit] {} -> {} closed", sender, target);
+            sys_topics::circuit(target, false, 0);
+        }
+        return;
+    }
+
+    let senders = breakers.entry(target.to_string()).or_default();
+    let breaker = senders.entry(sender.to_string()).or_insert(Breaker::Closed { failures: 0 });
+    let opened = match breaker {
+        Breaker::Closed { failures } => {
+            *failures += 1;
+            (*failures >= policy.failures).then_some(*failures)
+        }
+        // A probe failed; a send admitted before opening adds nothing
+        Breaker::HalfOpen => Some(1),
+        Breaker::Open { .. } => None,
+    };
+    if let Some(failures) = opened {
+        let reopened = matches!(breaker, Breaker::HalfOpen);
+        *breaker = Breaker::Open { until: clock::now() + policy.open_for };
+        drop(breakers);
+        if !reopened {
+            eprintln!("[Circuit] {} -> {} opened after {} failed sends ({})", sender, target, failures, rc);
+            sys_topics::circuit(target, true, failures as i64);
+        }
+    }
+}
+
+/// Open the circuit of a sender -> C++ target pair after `failures`
+/// consecutive failed sends, failing its sends with CIRCUIT_OPEN for
+/// `open_ms` before a probe. `failures` 0 turns circuit breaking off.
+#[export_name = c_symbol!("interop_set_circuit_breaker")]
+pub extern "C" fn interop_set_circuit_breaker(failures: u32, open_ms: u32) {
+    let policy = (failures > 0).then(|| BreakerPolicy::new(failures, Duration::from_millis(open_ms as u64)));
+    set_policy(policy);
+}
+
+/// INTEROP_CIRCUIT_* state of the `sender` -> `target` circuit, or
+/// InvalidArgument on a bad name
+#[export_name = c_symbol!("interop_circuit_state")]
+pub extern "C" fn interop_circuit_state(sender: *const c_char, target: *const c_char) -> c_int {
+    match (c_str(sender), c_str(target)) {
+        (Some(s), Some(t)) => state(s, t) as c_int,
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
@@ -0,0 +1,266 @@
+//! Clock behind every timer and time stamp in the crate
+//!
+//! Lease expiry, circuit breakers, rate limits, dedup and idempotency
+//! windows, heartbeat ages, retry backoff, dispatch timings and
+//! `Timestamp::now()` (in Rust and, through `interop_clock_now_ns`, in
+//! C++) all read the installed clock. By default it is the system clock.
+//! A backtest installs a `SimulatedClock` instead and runs the whole
+//! interop system in virtual time:
+//!
+//! ```ignore
+//! // 2024-01-02 09:30 UTC, running 60x faster than real time
+//! let sim = clock::simulate(Timestamp::from_secs(1_704_187_800), 60.0);
+//! sim.advance(Duration::from_secs(5));   // jump ahead; expires leases etc.
+//! clock::use_system_clock();
+//! ```
+//!
+//! ```c
+//! interop_clock_simulate(1704187800000000000LL, 0.0);  // interop/clock.h
+//! interop_clock_advance_ns(1000000);
+//! ```
+//!
+//! With speed 0 virtual time moves only by `advance` / `set`.
+//! `clock::sleep` waits for virtual time, so code that sleeps on the clock
+//! wakes once the backtest has advanced past its deadline. Virtual time
+//! never runs backwards.
+
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::timestamp::Timestamp;
+
+/// How often `SimulatedClock::sleep` looks at virtual time
+const SLEEP_POLL: Duration = Duration::from_millis(1);
+
+pub trait Clock: Send + Sync {
+    /// Monotonic time, for deadlines and elapsed times
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time, for time stamps
+    fn wall(&self) -> SystemTime;
+
+    /// Block the calling thread for `d` of this clock's time
+    fn sleep(&self, d: Duration);
+}
+
+/// The operating system's clocks
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, d: Duration) {
+        thread::sleep(d);
+    }
+}
+
+struct SimState {
+    // Virtual time at `real_origin`
+    virtual_origin: Duration,
+    real_origin: Instant,
+}
+
+/// Virtual time: moves at `speed` times real time plus explicit jumps
+pub struct SimulatedClock {
+    // Instant and SystemTime that virtual time zero maps to
+    base: Instant,
+    base_wall: SystemTime,
+    speed: f64,
+    state: Mutex<SimState>,
+}
+
+impl SimulatedClock {
+    /// Virtual time starts at `start`; `speed` 0 stands still between jumps
+    pub fn new(start: Timestamp, speed: f64) -> Self {
+        let now = Instant::now();
+        SimulatedClock {
+            base: now,
+            base_wall: start.into(),
+            speed: if speed.is_finite() { speed.max(0.0) } else { 0.0 },
+            state: Mutex::new(SimState { virtual_origin: Duration::ZERO, real_origin: now }),
+        }
+    }
+
+    /// Virtual time since the start
+    pub fn elapsed(&self) -> Duration {
+        let state = self.state.lock().unwrap();
+        self.elapsed_locked(&state)
+    }
+
+    fn elapsed_locked(&self, state: &SimState) -> Duration {
+        if self.speed == 0.0 {
+            return state.virtual_origin;
+        }
+        state.virtual_origin + state.real_origin.elapsed().mul_f64(self.speed)
+    }
+
+    /// Move virtual time forward by `d`
+    pub fn advance(&self, d: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.virtual_origin = self.elapsed_locked(&state) + d;
+        state.real_origin = Instant::now();
+    }
+
+    /// Move virtual time forward to `t`. Returns false, leaving it alone,
+    /// if `t` is in the virtual past.
+    pub fn set(&self, t: Timestamp) -> bool {
+        let target = match SystemTime::from(t).duration_since(self.base_wall) {
+            Ok(d) => d,
+            Err(_) => return false,
+        };
+        let mut state = self.state.lock().unwrap();
+        if target < self.elapsed_locked(&state) {
+            return false;
+        }
+        state.virtual_origin = target;
+        state.real_origin = Instant::now();
+        true
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Instant {
+        self.base + self.elapsed()
+    }
+
+    fn wall(&self) -> SystemTime {
+        self.base_wall + self.elapsed()
+    }
+
+    fn sleep(&self, d: Duration) {
+        let deadline = self.elapsed() + d;
+        loop {
+            let left = deadline.saturating_sub(self.elapsed());
+            if left.is_zero() {
+                return;
+            }
+            // At speed s, `left` of virtual time is left / s of real time
+            let real = if self.speed > 0.0 { left.div_f64(self.speed) } else { SLEEP_POLL };
+            thread::sleep(real.min(SLEEP_POLL));
+        }
+    }
+}
+
+// False while the system clock is installed, so the common case skips the lock
+static SIMULATED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref CLOCK: RwLock<Arc<dyn Clock>> = RwLock::new(Arc::new(SystemClock));
+    static ref SIM: Mutex<Option<Arc<SimulatedClock>>> = Mutex::new(None);
+}
+
+/// Install `clock` for the whole process
+pub fn set_clock(clock: Arc<dyn Clock>) {
+    *SIM.lock().unwrap() = None;
+    *CLOCK.write().unwrap() = clock;
+    SIMULATED.store(true, Ordering::Release);
+}
+
+/// Install a `SimulatedClock` starting at `start` and return it
+pub fn simulate(start: Timestamp, speed: f64) -> Arc<SimulatedClock> {
+    let sim = Arc::new(SimulatedClock::new(start, speed));
+    set_clock(sim.clone());
+    *SIM.lock().unwrap() = Some(sim.clone());
+    sim
+}
+
+/// Go back to the system clock
+pub fn use_system_clock() {
+    SIMULATED.store(false, Ordering::Release);
+    *SIM.lock().unwrap() = None;
+    *CLOCK.write().unwrap() = Arc::new(SystemClock);
+}
+
+/// The installed clock
+pub fn clock() -> Arc<dyn Clock> {
+    CLOCK.read().unwrap().clone()
+}
+
+/// Whether the system clock is installed
+pub fn is_system_clock() -> bool {
+    !SIMULATED.load(Ordering::Acquire)
+}
+
+/// The SimulatedClock installed by `simulate`, if any
+pub fn simulated() -> Option<Arc<SimulatedClock>> {
+    SIM.lock().unwrap().clone()
+}
+
+pub fn now() -> Instant {
+    if !SIMULATED.load(Ordering::Acquire) {
+        return Instant::now();
+    }
+    CLOCK.read().unwrap().now()
+}
+
+pub fn wall() -> SystemTime {
+    if !SIMULATED.load(Ordering::Acquire) {
+        return SystemTime::now();
+    }
+    CLOCK.read().unwrap().wall()
+}
+
+pub fn sleep(d: Duration) {
+    if !SIMULATED.load(Ordering::Acquire) {
+        return thread::sleep(d);
+    }
+    clock().sleep(d)
+}
+
+/// Install a simulated clock starting at `start_unix_ns`, running at
+/// `speed` times real time (0: only advance / set move it)
+#[export_name = c_symbol!("interop_clock_simulate")]
+pub extern "C" fn interop_clock_simulate(start_unix_ns: i64, speed: f64) {
+    simulate(Timestamp::from_nanos(start_unix_ns), speed);
+}
+
+#[export_name = c_symbol!("interop_clock_use_system")]
+pub extern "C" fn interop_clock_use_system() {
+    use_system_clock();
+}
+
+/// Returns 0, or NotFound if no simulated clock is installed
+#[export_name = c_symbol!("interop_clock_advance_ns")]
+pub extern "C" fn interop_clock_advance_ns(ns: u64) -> c_int {
+    match simulated() {
+        Some(sim) => {
+            sim.advance(Duration::from_nanos(ns));
+            InteropErrorCode::Ok as c_int
+        }
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Returns 0, NotFound if no simulated clock is installed, or
+/// InvalidArgument if `unix_ns` is in the virtual past
+#[export_name = c_symbol!("interop_clock_set_ns")]
+pub extern "C" fn interop_clock_set_ns(unix_ns: i64) -> c_int {
+    let rc = match simulated() {
+        Some(sim) if sim.set(Timestamp::from_nanos(unix_ns)) => InteropErrorCode::Ok,
+        Some(_) => InteropErrorCode::InvalidArgument,
+        None => InteropErrorCode::NotFound,
+    };
+    rc as c_int
+}
+
+/// Wall-clock time of the installed clock, nanoseconds since the Unix epoch
+#[export_name = c_symbol!("interop_clock_now_ns")]
+pub extern "C" fn interop_clock_now_ns() -> i64 {
+    Timestamp::from(wall()).as_nanos()
+}
+
+/// Sleep `ns` of the installed clock's time
+#[export_name = c_symbol!("interop_clock_sleep_ns")]
+pub extern "C" fn interop_clock_sleep_ns(ns: u64) {
+    sleep(Duration::from_nanos(ns));
+}
@@ -0,0 +1,614 @@
+//! Message encodings for transports, selectable per link
+//!
+//! The in-process bridge never goes through a codec: it passes C structs
+//! directly. Anything that leaves the process (MQTT, Kafka, future TCP/IPC)
+//! encodes with the `Codec` configured for its link:
+//!
+//! | Kind | Format | Use |
+//! |------|--------|-----|
+//! | `Raw` | `wire` frame of the C struct | Same ABI on both ends, fastest |
+//! | `Portable` | `[i32 id][u32 len]` + fields, little-endian | Other ABIs, other languages |
+//! | `JsonDebug` | JSON object with `msg_id` and fields | Logs and inspection, encode only |
+//! | `Protobuf` | `[i32 id][u32 len]` + protobuf body | Peers that already speak the .proto schema |
+//! | `Bincode` | `[i32 id][u32 len]` + bincode body | Rust peers, recorded traffic (feature `serde`) |
+//! | `FlatBuffers` | `[i32 id][u32 len]` + FlatBuffers table for INTEROP_FLATBUFFERS messages, `Raw` for the rest | Large messages read in place by C++ (see flatbuf) |
+//! | `CapnProto` | `[i32 id][u32 len]` + Cap'n Proto message | Peers and RPC interfaces on the generated .capnp schema (see capnproto, feature `capnp`) |
+//!
+//! Links default to `Portable`, since the peer's struct layout is not
+//! known. Pick another with `set_link_codec(link, kind)`
+//! or `interop_set_link_codec()` from C++ before the transport starts.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use actors::Message;
+
+#[cfg(feature = "serde")]
+use crate::interop_messages::{message_from_bincode, message_to_bincode};
+#[cfg(feature = "capnp")]
+use crate::interop_messages::{message_from_capnp, message_to_capnp};
+use crate::interop_messages::{
+    message_from_c_borrowed, message_from_flatbuffers, message_from_portable, message_from_protobuf,
+    message_to_debug_json, message_to_flatbuffers, message_to_json, message_to_portable, message_to_protobuf,
+    FLATBUFFERS_MESSAGE_IDS,
+};
+use crate::decimal::Decimal;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_string::InteropString;
+use crate::names::c_str;
+use crate::timestamp::Timestamp;
+use crate::uuid::Uuid;
+use crate::wire::{self, WireError, HEADER_LEN};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CodecError {
+    /// Raw frame rejected by `wire`
+    Wire(WireError),
+    /// Not an interop message, or an ID this build does not know
+    UnknownMessage(i32),
+    /// Body does not parse for its message ID
+    Malformed(i32),
+    /// The codec cannot do this (JsonDebug decode)
+    Unsupported,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Wire(e) => write!(f, "{}", e),
+            CodecError::UnknownMessage(id) => write!(f, "unknown message id {}", id),
+            CodecError::Malformed(id) => write!(f, "malformed body for message {}", id),
+            CodecError::Unsupported => write!(f, "not supported by this codec"),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<WireError> for CodecError {
+    fn from(e: WireError) -> Self {
+        CodecError::Wire(e)
+    }
+}
+
+/// Converts interop messages to and from bytes for one transport
+pub trait Codec: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn encode(&self, msg: &dyn Message) -> Result<Vec<u8>, CodecError>;
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Message>, CodecError>;
+}
+
+/// C struct bytes in a `wire` frame
+pub struct RawCodec;
+
+impl Codec for RawCodec {
+    fn name(&self) -> &'static str { "raw" }
+
+    fn encode(&self, msg: &dyn Message) -> Result<Vec<u8>, CodecError> {
+        wire::encode_dyn(msg).ok_or(CodecError::UnknownMessage(msg.message_id()))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Message>, CodecError> {
+        Ok(wire::decode_message(bytes)?)
+    }
+}
+
+/// Field-by-field little-endian encoding, independent of struct layout
+pub struct PortableCodec;
+
+impl Codec for PortableCodec {
+    fn name(&self) -> &'static str { "portable" }
+
+    fn encode(&self, msg: &dyn Message) -> Result<Vec<u8>, CodecError> {
+        let body = message_to_portable(msg).ok_or(CodecError::UnknownMessage(msg.message_id()))?;
+        Ok(frame(msg.message_id(), &body))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Message>, CodecError> {
+        let (msg_id, body) = unframe(bytes)?;
+        message_from_portable(msg_id, body).ok_or(CodecError::Malformed(msg_id))
+    }
+}
+
+// `[i32 id][u32 len]` + body, for codecs whose body has no message ID
+fn frame(msg_id: i32, body: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_LEN + body.len());
+    frame.extend_from_slice(&msg_id.to_le_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(body);
+    frame
+}
+
+fn unframe(bytes: &[u8]) -> Result<(i32, &[u8]), CodecError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(WireError::Truncated.into());
+    }
+    let msg_id = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let len = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as usize;
+    let body = bytes.get(HEADER_LEN..HEADER_LEN + len).ok_or(WireError::Truncated)?;
+    if crate::interop_messages::c_struct_size(msg_id).is_none() {
+        return Err(CodecError::UnknownMessage(msg_id));
+    }
+    Ok((msg_id, body))
+}
+
+/// Protobuf body (see protobuf.rs) after the message ID and length
+pub struct ProtobufCodec;
+
+impl Codec for ProtobufCodec {
+    fn name(&self) -> &'static str { "protobuf" }
+
+    fn encode(&self, msg: &dyn Message) -> Result<Vec<u8>, CodecError> {
+        let body = message_to_protobuf(msg).ok_or(CodecError::UnknownMessage(msg.message_id()))?;
+        Ok(frame(msg.message_id(), &body))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Message>, CodecError> {
+        let (msg_id, body) = unframe(bytes)?;
+        message_from_protobuf(msg_id, body).ok_or(CodecError::Malformed(msg_id))
+    }
+}
+
+/// Bincode body of the serde-derived message struct after the message ID
+/// and length. Needs the same build of the schemas on both ends.
+#[cfg(feature = "serde")]
+pub struct BincodeCodec;
+
+#[cfg(feature = "serde")]
+impl Codec for BincodeCodec {
+    fn name(&self) -> &'static str { "bincode" }
+
+    fn encode(&self, msg: &dyn Message) -> Result<Vec<u8>, CodecError> {
+        let body = message_to_bincode(msg).ok_or(CodecError::UnknownMessage(msg.message_id()))?;
+        Ok(frame(msg.message_id(), &body))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Message>, CodecError> {
+        let (msg_id, body) = unframe(bytes)?;
+        message_from_bincode(msg_id, body).ok_or(CodecError::Malformed(msg_id))
+    }
+}
+
+/// FlatBuffers table after the message ID and length for the messages the
+/// IDL marks INTEROP_FLATBUFFERS, so the receiver reads large ones
+/// (MarketDepth, snapshots) in place; a raw struct frame for the rest
+pub struct FlatBuffersCodec;
+
+impl Codec for FlatBuffersCodec {
+    fn name(&self) -> &'static str { "flatbuffers" }
+
+    fn encode(&self, msg: &dyn Message) -> Result<Vec<u8>, CodecError> {
+        match message_to_flatbuffers(msg) {
+            Some(body) => Ok(frame(msg.message_id(), &body)),
+            None => RAW.encode(msg),
+        }
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Message>, CodecError> {
+        let (msg_id, body) = unframe(bytes)?;
+        if !FLATBUFFERS_MESSAGE_IDS.contains(&msg_id) {
+            return RAW.decode(bytes);
+        }
+        message_from_flatbuffers(msg_id, body).ok_or(CodecError::Malformed(msg_id))
+    }
+}
+
+/// Cap'n Proto message (see capnproto) after the message ID and length,
+/// readable by any implementation built from the generated schema
+#[cfg(feature = "capnp")]
+pub struct CapnProtoCodec;
+
+#[cfg(feature = "capnp")]
+impl Codec for CapnProtoCodec {
+    fn name(&self) -> &'static str { "capnp" }
+
+    fn encode(&self, msg: &dyn Message) -> Result<Vec<u8>, CodecError> {
+        let body = message_to_capnp(msg).ok_or(CodecError::UnknownMessage(msg.message_id()))?;
+        Ok(frame(msg.message_id(), &body))
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Box<dyn Message>, CodecError> {
+        let (msg_id, body) = unframe(bytes)?;
+        message_from_capnp(msg_id, body).ok_or(CodecError::Malformed(msg_id))
+    }
+}
+
+/// Human-readable JSON; decoding is not supported
+pub struct JsonDebugCodec;
+
+impl Codec for JsonDebugCodec {
+    fn name(&self) -> &'static str { "json-debug" }
+
+    fn encode(&self, msg: &dyn Message) -> Result<Vec<u8>, CodecError> {
+        let fields = message_to_json(msg).ok_or(CodecError::UnknownMessage(msg.message_id()))?;
+        Ok(format!("{{\"msg_id\":{},\"fields\":{}}}", msg.message_id(), fields).into_bytes())
+    }
+
+    fn decode(&self, _bytes: &[u8]) -> Result<Box<dyn Message>, CodecError> {
+        Err(CodecError::Unsupported)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(i32)]
+pub enum CodecKind {
+    Raw = 0,
+    #[default]
+    Portable = 1,
+    JsonDebug = 2,
+    Protobuf = 3,
+    #[cfg(feature = "serde")]
+    Bincode = 4,
+    FlatBuffers = 5,
+    #[cfg(feature = "capnp")]
+    CapnProto = 6,
+}
+
+impl CodecKind {
+    pub fn from_i32(v: i32) -> Option<Self> {
+        match v {
+            0 => Some(CodecKind::Raw),
+            1 => Some(CodecKind::Portable),
+            2 => Some(CodecKind::JsonDebug),
+            3 => Some(CodecKind::Protobuf),
+            #[cfg(feature = "serde")]
+            4 => Some(CodecKind::Bincode),
+            5 => Some(CodecKind::FlatBuffers),
+            #[cfg(feature = "capnp")]
+            6 => Some(CodecKind::CapnProto),
+            _ => None,
+        }
+    }
+}
+
+static RAW: RawCodec = RawCodec;
+static PORTABLE: PortableCodec = PortableCodec;
+static JSON_DEBUG: JsonDebugCodec = JsonDebugCodec;
+static PROTOBUF: ProtobufCodec = ProtobufCodec;
+#[cfg(feature = "serde")]
+static BINCODE: BincodeCodec = BincodeCodec;
+static FLATBUFFERS: FlatBuffersCodec = FlatBuffersCodec;
+#[cfg(feature = "capnp")]
+static CAPNP: CapnProtoCodec = CapnProtoCodec;
+
+pub fn codec(kind: CodecKind) -> &'static dyn Codec {
+    match kind {
+        CodecKind::Raw => &RAW,
+        CodecKind::Portable => &PORTABLE,
+        CodecKind::JsonDebug => &JSON_DEBUG,
+        CodecKind::Protobuf => &PROTOBUF,
+        #[cfg(feature = "serde")]
+        CodecKind::Bincode => &BINCODE,
+        CodecKind::FlatBuffers => &FLATBUFFERS,
+        #[cfg(feature = "capnp")]
+        CodecKind::CapnProto => &CAPNP,
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref LINKS: Mutex<HashMap<String, CodecKind>> = Mutex::new(HashMap::new());
+}
+
+/// Choose the codec for a link (transport actor name, e.g. "mqtt_bridge")
+pub fn set_link_codec(link: &str, kind: CodecKind) {
+    LINKS.lock().unwrap().insert(link.to_string(), kind);
+}
+
+/// Codec configured for a link, `Portable` if none was set
+pub fn link_codec(link: &str) -> &'static dyn Codec {
+    codec(LINKS.lock().unwrap().get(link).copied().unwrap_or_default())
+}
+
+/// Choose the codec for a link: 0 = raw, 1 = portable, 2 = JSON debug,
+/// 3 = protobuf, 4 = bincode (feature `serde`), 5 = FlatBuffers,
+/// 6 = Cap'n Proto (feature `capnp`).
+/// Returns 0, or InvalidArgument on a bad name or codec.
+#[export_name = c_symbol!("interop_set_link_codec")]
+pub extern "C" fn interop_set_link_codec(link: *const c_char, kind: c_int) -> c_int {
+    let rc = match (c_str(link), CodecKind::from_i32(kind)) {
+        (Some(link), Some(k)) => {
+            set_link_codec(link, k);
+            InteropErrorCode::Ok
+        }
+        _ => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// JSON for a C struct, as from the generated `to_json()`, for logging on
+/// the C++ side. Returns null for null data or an unknown ID; free the
+/// string with interop_free_json().
+#[export_name = c_symbol!("interop_message_to_json")]
+pub extern "C" fn interop_message_to_json(msg_id: c_int, data: *const std::os::raw::c_void) -> *mut c_char {
+    if data.is_null() {
+        return std::ptr::null_mut();
+    }
+    let json = unsafe { message_from_c_borrowed(msg_id, data) }.and_then(|m| message_to_debug_json(m.as_ref()));
+    match json.and_then(|j| std::ffi::CString::new(j).ok()) {
+        Some(c) => c.into_raw(),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Free a string from interop_message_to_json(); null is ignored
+#[export_name = c_symbol!("interop_free_json")]
+pub extern "C" fn interop_free_json(json: *mut c_char) {
+    if !json.is_null() {
+        drop(unsafe { std::ffi::CString::from_raw(json) });
+    }
+}
+
+// ============================================================================
+// Field encodings used by the generated encode_portable / write_json
+// ============================================================================
+
+/// Little-endian field encoding for the portable codec
+pub trait Portable: Sized {
+    fn put(&self, out: &mut Vec<u8>);
+    fn get(input: &mut &[u8]) -> Option<Self>;
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if input.len() < n {
+        return None;
+    }
+    let (head, rest) = input.split_at(n);
+    *input = rest;
+    Some(head)
+}
+
+macro_rules! portable_number {
+    ($($t:ty),*) => {$(
+        impl Portable for $t {
+            fn put(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn get(input: &mut &[u8]) -> Option<Self> {
+                let bytes = take(input, std::mem::size_of::<$t>())?;
+                Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+            }
+        }
+    )*};
+}
+
+portable_number!(u8, i32, i64, u32, u64, f32, f64);
+
+impl Portable for bool {
+    fn put(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        Some(take(input, 1)?[0] != 0)
+    }
+}
+
+impl Portable for Vec<u8> {
+    fn put(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).put(out);
+        out.extend_from_slice(self);
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        let len = u32::get(input)? as usize;
+        Some(take(input, len)?.to_vec())
+    }
+}
+
+impl Portable for String {
+    fn put(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).put(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        let len = u32::get(input)? as usize;
+        String::from_utf8(take(input, len)?.to_vec()).ok()
+    }
+}
+
+impl<const N: usize> Portable for InteropString<N> {
+    fn put(&self, out: &mut Vec<u8>) {
+        (self.len() as u32).put(out);
+        out.extend_from_slice(self.as_str().as_bytes());
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        let s = String::get(input)?;
+        Some(InteropString::from(s.as_str()))
+    }
+}
+
+impl<T: Portable + Copy + Default, const N: usize> Portable for [T; N] {
+    fn put(&self, out: &mut Vec<u8>) {
+        for v in self {
+            v.put(out);
+        }
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        let mut arr = [T::default(); N];
+        for slot in arr.iter_mut() {
+            *slot = T::get(input)?;
+        }
+        Some(arr)
+    }
+}
+
+impl Portable for Decimal {
+    fn put(&self, out: &mut Vec<u8>) {
+        self.units.put(out);
+        self.scale.put(out);
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        Some(Decimal::new(i64::get(input)?, i32::get(input)?))
+    }
+}
+
+impl Portable for Uuid {
+    fn put(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.bytes);
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        Some(Uuid::from_bytes(take(input, 16)?.try_into().ok()?))
+    }
+}
+
+impl Portable for Timestamp {
+    fn put(&self, out: &mut Vec<u8>) {
+        self.nanos.put(out);
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        Some(Timestamp::from_nanos(i64::get(input)?))
+    }
+}
+
+impl<V: Portable> Portable for HashMap<String, V> {
+    fn put(&self, out: &mut Vec<u8>) {
+        // Sorted so equal maps encode identically
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        (entries.len() as u32).put(out);
+        for (k, v) in entries {
+            k.put(out);
+            v.put(out);
+        }
+    }
+    fn get(input: &mut &[u8]) -> Option<Self> {
+        let count = u32::get(input)? as usize;
+        let mut map = HashMap::new();
+        for _ in 0..count {
+            let k = String::get(input)?;
+            map.insert(k, V::get(input)?);
+        }
+        Some(map)
+    }
+}
+
+/// JSON rendering of a field for the JSON-debug codec
+pub trait JsonValue {
+    fn write_json(&self, out: &mut String);
+}
+
+macro_rules! json_number {
+    ($($t:ty),*) => {$(
+        impl JsonValue for $t {
+            fn write_json(&self, out: &mut String) {
+                out.push_str(&self.to_string());
+            }
+        }
+    )*};
+}
+
+json_number!(u8, i32, i64, u32, u64);
+
+macro_rules! json_float {
+    ($($t:ty),*) => {$(
+        impl JsonValue for $t {
+            fn write_json(&self, out: &mut String) {
+                // JSON has no NaN/inf
+                if self.is_finite() { out.push_str(&self.to_string()) } else { out.push_str("null") }
+            }
+        }
+    )*};
+}
+
+json_float!(f32, f64);
+
+impl JsonValue for bool {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+impl JsonValue for str {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+        for ch in self.chars() {
+            match ch {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl JsonValue for String {
+    fn write_json(&self, out: &mut String) {
+        self.as_str().write_json(out);
+    }
+}
+
+impl<const N: usize> JsonValue for InteropString<N> {
+    fn write_json(&self, out: &mut String) {
+        self.as_str().write_json(out);
+    }
+}
+
+impl JsonValue for Vec<u8> {
+    fn write_json(&self, out: &mut String) {
+        // Hex keeps debug output readable for binary payloads
+        out.push('"');
+        for b in self {
+            out.push_str(&format!("{:02x}", b));
+        }
+        out.push('"');
+    }
+}
+
+/// A string, so no digit is lost to a JSON number
+impl JsonValue for Decimal {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+        out.push_str(&self.to_string());
+        out.push('"');
+    }
+}
+
+/// Hyphenated hex
+impl JsonValue for Uuid {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+        out.push_str(&self.to_string());
+        out.push('"');
+    }
+}
+
+/// RFC 3339 text; nanoseconds do not fit a JSON (double) number
+impl JsonValue for Timestamp {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+        out.push_str(&self.to_string());
+        out.push('"');
+    }
+}
+
+impl<T: JsonValue, const N: usize> JsonValue for [T; N] {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+        for (i, v) in self.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            v.write_json(out);
+        }
+        out.push(']');
+    }
+}
+
+impl<V: JsonValue> JsonValue for HashMap<String, V> {
+    fn write_json(&self, out: &mut String) {
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        out.push('{');
+        for (i, (k, v)) in entries.into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            k.write_json(out);
+            out.push(':');
+            v.write_json(out);
+        }
+        out.push('}');
+    }
+}
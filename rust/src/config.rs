@@ -0,0 +1,300 @@
+//! Configuration values shared and watched by both runtimes
+//!
+//! Throttle rates and enabled symbols each used to travel in a config
+//! message of their own. The config service keeps one table of string
+//! values by key, set from either side, and tells the watchers of a key
+//! when it changes. Watching is subscribing: the service is the publisher
+//! `$config`, each key is a topic and `*` stands for every key:
+//!
+//! ```ignore
+//! // Rust
+//! config::set("throttle_ms", "250")?;
+//! let ms: u64 = config::get_parsed("throttle_ms").unwrap_or(100);
+//! broker::subscribe("rust_strategy", CONFIG_PUBLISHER, "throttle_ms", QOS_RELIABLE);
+//! // ConfigChanged => on_config_changed
+//! ```
+//!
+//! ```cpp
+//! // C++: "$config" resolves like a Rust actor
+//! interop_config_set("enabled_symbols", "AAPL,MSFT");
+//! auto* sub = new msg::Subscribe();
+//! interop::set_str(sub->topic, "enabled_symbols");
+//! manager->get_ref("$config").send(sub, this);
+//! ```
+//!
+//! - A ConfigSet sent to `$config` sets its key, as `set()` does.
+//! - Watchers are sent ConfigChanged for each change of the key, with its
+//!   version (times it has been set); a removal has version 0 and an empty
+//!   value. A watcher of both the key and `*` is sent one copy.
+//! - A new watcher of a key that is set is sent its value at once (of `*`,
+//!   every value), so it needs no separate read at startup.
+//! - Setting a key to the value it has changes nothing.
+//!
+//! Keys are at most 31 bytes (a topic name), values at most 63 (an
+//! interop_string). The table is per process and starts empty; `load()`
+//! reads `key = value` lines.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use actors::{ActorRef, CppActorRef, Message};
+
+use crate::broker;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{ConfigChanged, ConfigSet, Subscribe, SubscribeMany};
+use crate::names::c_str;
+use crate::rust_manager_ffi::get_actor_ref;
+
+/// Publisher name to subscribe to and send ConfigSet to
+pub const CONFIG_PUBLISHER: &str = "$config";
+
+/// Topic of every key
+pub const CONFIG_ALL: &str = "*";
+
+/// Longest key, as a topic name
+pub const KEY_MAX: usize = 31;
+
+/// Longest value, as an interop_string
+pub const VALUE_MAX: usize = 63;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Empty, `*`, or longer than KEY_MAX
+    BadKey(String),
+    /// Longer than VALUE_MAX
+    ValueTooLong { key: String, len: usize },
+    /// A config line that is not `key = value` (1-based)
+    Parse(usize),
+    Io(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::BadKey(key) => write!(f, "bad config key '{}'", key),
+            ConfigError::ValueTooLong { key, len } => write!(f, "value of {} is {} bytes, over {}", key, len, VALUE_MAX),
+            ConfigError::Parse(line) => write!(f, "line {}: expected key = value", line),
+            ConfigError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+struct Entry {
+    value: String,
+    version: i64,
+}
+
+lazy_static::lazy_static! {
+    static ref CONFIG: RwLock<HashMap<String, Entry>> = RwLock::new(HashMap::new());
+}
+
+/// Set `key` to `value` and notify its watchers. Returns false if it
+/// already had that value.
+pub fn set(key: &str, value: &str) -> Result<bool, ConfigError> {
+    if key.is_empty() || key == CONFIG_ALL || key.len() > KEY_MAX {
+        return Err(ConfigError::BadKey(key.to_string()));
+    }
+    if value.len() > VALUE_MAX {
+        return Err(ConfigError::ValueTooLong { key: key.to_string(), len: value.len() });
+    }
+    let version = {
+        let mut config = CONFIG.write().unwrap();
+        let entry = config.entry(key.to_string()).or_insert(Entry { value: String::new(), version: 0 });
+        if entry.version > 0 && entry.value == value {
+            return Ok(false);
+        }
+        entry.value = value.to_string();
+        entry.version += 1;
+        entry.version
+    };
+    notify(key, value, version);
+    Ok(true)
+}
+
+/// Remove `key` and notify its watchers. False if it was not set.
+pub fn remove(key: &str) -> bool {
+    if CONFIG.write().unwrap().remove(key).is_none() {
+        return false;
+    }
+    notify(key, "", 0);
+    true
+}
+
+pub fn get(key: &str) -> Option<String> {
+    CONFIG.read().unwrap().get(key).map(|e| e.value.clone())
+}
+
+/// The value of `key` parsed as `T`; None if not set or not a `T`
+pub fn get_parsed<T: FromStr>(key: &str) -> Option<T> {
+    CONFIG.read().unwrap().get(key)?.value.trim().parse().ok()
+}
+
+/// Times `key` has been set since it was last removed; 0 if not set
+pub fn version(key: &str) -> i64 {
+    CONFIG.read().unwrap().get(key).map_or(0, |e| e.version)
+}
+
+/// Every (key, value, version), sorted by key
+pub fn entries() -> Vec<(String, String, i64)> {
+    let mut all: Vec<(String, String, i64)> =
+        CONFIG.read().unwrap().iter().map(|(k, e)| (k.clone(), e.value.clone(), e.version)).collect();
+    all.sort();
+    all
+}
+
+/// Set the values in `text`: `key = value` per line; blank lines and `#`
+/// comments are skipped. Returns how many keys changed; stops at the first
+/// bad line, keeping those before it.
+pub fn load_str(text: &str) -> Result<usize, ConfigError> {
+    let mut changed = 0;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or(ConfigError::Parse(i + 1))?;
+        if set(key.trim(), value.trim())? {
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// load_str() with the contents of `path`
+pub fn load(path: impl AsRef<Path>) -> Result<usize, ConfigError> {
+    let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Io(e.to_string()))?;
+    load_str(&text)
+}
+
+fn changed(key: &str, value: &str, version: i64) -> ConfigChanged {
+    ConfigChanged { key: key.into(), value: value.to_string(), version }
+}
+
+/// Send the change to the watchers of `key`, then to those of `*` that do
+/// not watch `key`
+fn notify(key: &str, value: &str, version: i64) {
+    let msg = changed(key, value, version);
+    broker::publish_with(CONFIG_PUBLISHER, key, |_| Some(Box::new(msg.clone())));
+    let watching: Vec<String> = broker::subscribers(CONFIG_PUBLISHER, key).into_iter().map(|s| s.subscriber).collect();
+    broker::publish_with(CONFIG_PUBLISHER, CONFIG_ALL, |s| {
+        (!watching.contains(&s.subscriber)).then(|| Box::new(msg.clone()) as Box<dyn Message>)
+    });
+}
+
+/// Send a new watcher of `topic` the current value(s)
+fn send_current(subscriber: &str, topic: &str) {
+    let current = match topic {
+        CONFIG_ALL => entries(),
+        key => match CONFIG.read().unwrap().get(key) {
+            Some(e) => vec![(key.to_string(), e.value.clone(), e.version)],
+            None => return,
+        },
+    };
+    if current.is_empty() {
+        return;
+    }
+    if let Some(watcher) = get_actor_ref(subscriber, CONFIG_PUBLISHER) {
+        for (key, value, version) in current {
+            watcher.send(Box::new(changed(&key, &value, version)), None);
+        }
+    }
+}
+
+/// ConfigSet, Subscribe and Unsubscribe sent to `$config`; subscriptions
+/// are recorded by the broker, where the bridge or `broker::subscribe`
+/// sees them
+fn config_send_fn(_target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    broker::observe(sender, CONFIG_PUBLISHER, msg);
+    if let Some(m) = msg.as_any().downcast_ref::<ConfigSet>() {
+        if let Err(e) = set(m.key.as_str(), &m.value) {
+            eprintln!("[Config] ConfigSet from '{}': {}", sender, e);
+            return InteropErrorCode::InvalidArgument as i32;
+        }
+    } else if sender.is_empty() {
+        return 0;  // no one to send the current value to
+    } else if let Some(sub) = msg.as_any().downcast_ref::<Subscribe>() {
+        send_current(sender, sub.topic.as_str());
+    } else if let Some(many) = msg.as_any().downcast_ref::<SubscribeMany>() {
+        for topic in many.topics() {
+            send_current(sender, topic);
+        }
+    }
+    0
+}
+
+/// ActorRef for `$config`, None for any other name. Lookups from Rust
+/// (`get_actor_ref`) and sends from C++ (`rust_actor_send`) use this.
+pub fn config_ref(name: &str, sender: &str) -> Option<ActorRef> {
+    (name == CONFIG_PUBLISHER).then(|| ActorRef::Cpp(CppActorRef::new(CONFIG_PUBLISHER, sender, config_send_fn)))
+}
+
+/// Set `key` to `value` and notify its watchers. Returns 1 if it changed,
+/// 0 if it already had that value, or InvalidArgument on a bad key or a
+/// value over 63 bytes.
+#[export_name = c_symbol!("interop_config_set")]
+pub extern "C" fn interop_config_set(key: *const c_char, value: *const c_char) -> c_int {
+    match (c_str(key), c_str(value)) {
+        (Some(k), Some(v)) => match set(k, v) {
+            Ok(changed) => changed as c_int,
+            Err(_) => InteropErrorCode::InvalidArgument as c_int,
+        },
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Write the value of `key` into `out` (NUL-terminated, truncated to fit
+/// `len`). Returns its full length like snprintf, or NotFound if not set.
+#[export_name = c_symbol!("interop_config_get")]
+pub extern "C" fn interop_config_get(key: *const c_char, out: *mut c_char, len: usize) -> c_int {
+    let value = match c_str(key).and_then(get) {
+        Some(v) => v,
+        None => return InteropErrorCode::NotFound as c_int,
+    };
+    if !out.is_null() && len > 0 {
+        let n = value.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(value.as_ptr(), out as *mut u8, n);
+            *out.add(n) = 0;
+        }
+    }
+    value.len() as c_int
+}
+
+/// Times `key` has been set since it was last removed; 0 if not set
+#[export_name = c_symbol!("interop_config_version")]
+pub extern "C" fn interop_config_version(key: *const c_char) -> i64 {
+    c_str(key).map_or(0, version)
+}
+
+/// Returns 0, or NotFound if `key` was not set
+#[export_name = c_symbol!("interop_config_remove")]
+pub extern "C" fn interop_config_remove(key: *const c_char) -> c_int {
+    match c_str(key) {
+        Some(k) if remove(k) => InteropErrorCode::Ok as c_int,
+        _ => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Load `key = value` lines from path. Returns how many keys changed, or
+/// InvalidArgument if the file cannot be read or a line is bad (see
+/// stderr).
+#[export_name = c_symbol!("interop_config_load")]
+pub extern "C" fn interop_config_load(path: *const c_char) -> c_int {
+    let path = match c_str(path) {
+        Some(p) => p,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    match load(path) {
+        Ok(n) => n.min(c_int::MAX as usize) as c_int,
+        Err(e) => {
+            eprintln!("[Config] {}: {}", path, e);
+            InteropErrorCode::InvalidArgument as c_int
+        }
+    }
+}
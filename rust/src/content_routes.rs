@@ -0,0 +1,352 @@
+//! Routes that pick the target of each message by its content
+//!
+//! During a phased migration the same message goes to the old engine or
+//! the new one depending on what it carries: orders for symbols A-M stay on
+//! the C++ legacy engine while N-Z move to the Rust one. A content route is
+//! a name senders use in place of either engine; the bridge matches each
+//! message against the route's rules and forwards it to the first rule's
+//! target:
+//!
+//! ```text
+//! # deploy/routes.conf: route: conditions -> target
+//! orders: symbol < N -> cpp_legacy_engine
+//! orders: symbol >= N -> rust_order_engine
+//! orders: * -> rust_order_engine
+//! ```
+//!
+//! ```ignore
+//! content_routes::load("deploy/routes.conf")?;
+//! let orders = get_actor_ref("orders", "rust_gateway").unwrap();   // C++: rust_actor_send("orders", ...)
+//! ```
+//!
+//! - Conditions are a filter's (see sub_filter): `field op value` joined by
+//!   `&&`, text compared with string fields, numbers with numeric ones. `*`
+//!   matches every message.
+//! - Rules are tried in the order given; a message no rule matches is a
+//!   dead letter (see dead_letters) and the send returns NotFound.
+//! - Targets are looked up like `get_actor_ref()`, so they can be Rust or
+//!   C++ actors or aliases. The target sees the original sender and can
+//!   reply to it.
+//! - Loading replaces the rules of each route the text names and keeps the
+//!   others, so moving symbols means editing the file and loading it again.
+//!   A route name hides an actor of the same name.
+//!
+//! `get_actor_ref()` and C++ sends through the bridge (`rust_actor_send`)
+//! resolve route names; C++ actors sending straight to a C++ engine
+//! through their Manager bypass them.
+
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use actors::{ActorRef, CppActorRef, Message};
+
+use crate::codec::JsonValue;
+use crate::health;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::clone_message;
+use crate::names::c_str;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::sub_filter::{Conditions, FilterError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RouteError {
+    /// Empty route or target, or a rule routing to its own route
+    BadName,
+    /// Conditions that do not parse
+    Filter(FilterError),
+    /// A config line that is not `route: conditions -> target` (1-based)
+    Parse(usize),
+    Io(String),
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::BadName => write!(f, "empty route or target, or a route to itself"),
+            RouteError::Filter(e) => write!(f, "{}", e),
+            RouteError::Parse(line) => write!(f, "line {}: expected route: conditions -> target", line),
+            RouteError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RouteError {}
+
+#[derive(Clone)]
+struct Rule {
+    // As given, `*` for every message
+    when: String,
+    // None for `*`
+    conditions: Option<Conditions>,
+    target: String,
+    // Shared by the copies a rule added later makes
+    hits: Arc<AtomicU64>,
+}
+
+impl Rule {
+    fn new(route: &str, when: &str, target: &str) -> Result<Rule, RouteError> {
+        let when = when.trim();
+        let target = target.trim();
+        if route.is_empty() || target.is_empty() || target == route || when.is_empty() {
+            return Err(RouteError::BadName);
+        }
+        let conditions = match when {
+            "*" => None,
+            _ => Some(Conditions::parse(when).map_err(RouteError::Filter)?),
+        };
+        Ok(Rule { when: when.to_string(), conditions, target: target.to_string(), hits: Arc::default() })
+    }
+
+    fn matches(&self, msg: &dyn Message) -> bool {
+        self.conditions.as_ref().map_or(true, |c| c.hold(msg))
+    }
+}
+
+/// One rule of a route, with the messages it has routed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleInfo {
+    pub route: String,
+    pub when: String,
+    pub target: String,
+    pub hits: u64,
+}
+
+// Fast path: no lock while no route is set
+static ANY_ROUTES: AtomicBool = AtomicBool::new(false);
+static UNMATCHED: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref ROUTES: RwLock<HashMap<String, Arc<Vec<Rule>>>> = RwLock::new(HashMap::new());
+}
+
+fn replace(routes: HashMap<String, Vec<Rule>>) {
+    let mut all = ROUTES.write().unwrap();
+    for (route, rules) in routes {
+        all.insert(route, Arc::new(rules));
+    }
+    ANY_ROUTES.store(!all.is_empty(), Ordering::Release);
+}
+
+/// Set the rules of `route`, each `(conditions, target)`, replacing those
+/// it had
+pub fn set_rules(route: &str, rules: &[(&str, &str)]) -> Result<(), RouteError> {
+    let rules = rules.iter().map(|(when, target)| Rule::new(route, when, target)).collect::<Result<Vec<_>, _>>()?;
+    replace(HashMap::from([(route.to_string(), rules)]));
+    Ok(())
+}
+
+/// Add a rule after the rules `route` has
+pub fn add_rule(route: &str, when: &str, target: &str) -> Result<(), RouteError> {
+    let rule = Rule::new(route, when, target)?;
+    let mut all = ROUTES.write().unwrap();
+    let rules = all.entry(route.to_string()).or_default();
+    // A copy: sends in progress keep the rules they started with
+    let mut next = Vec::clone(rules);
+    next.push(rule);
+    *rules = Arc::new(next);
+    ANY_ROUTES.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// Remove `route`. False if there was none.
+pub fn remove(route: &str) -> bool {
+    let mut all = ROUTES.write().unwrap();
+    let removed = all.remove(route).is_some();
+    ANY_ROUTES.store(!all.is_empty(), Ordering::Release);
+    removed
+}
+
+/// Remove every route
+pub fn clear() {
+    ROUTES.write().unwrap().clear();
+    ANY_ROUTES.store(false, Ordering::Release);
+}
+
+/// True if `name` is a route
+pub fn is_route(name: &str) -> bool {
+    ANY_ROUTES.load(Ordering::Acquire) && ROUTES.read().unwrap().contains_key(name)
+}
+
+/// The target `route` sends `msg` to, None if `route` is not a route or
+/// no rule matches
+pub fn target_of(route: &str, msg: &dyn Message) -> Option<String> {
+    let rules = ROUTES.read().unwrap().get(route).cloned()?;
+    rules.iter().find(|r| r.matches(msg)).map(|r| r.target.clone())
+}
+
+/// Every rule, by route in name order, then in the order tried
+pub fn rules() -> Vec<RuleInfo> {
+    let all = ROUTES.read().unwrap();
+    let mut routes: Vec<&String> = all.keys().collect();
+    routes.sort();
+    routes
+        .into_iter()
+        .flat_map(|route| {
+            all[route].iter().map(move |r| RuleInfo {
+                route: route.clone(),
+                when: r.when.clone(),
+                target: r.target.clone(),
+                hits: r.hits.load(Ordering::Relaxed),
+            })
+        })
+        .collect()
+}
+
+/// Messages sent to a route that no rule matched
+pub fn unmatched() -> u64 {
+    UNMATCHED.load(Ordering::Relaxed)
+}
+
+/// rules() as a JSON array
+pub fn rules_json() -> String {
+    let mut out = String::from("[");
+    for (i, r) in rules().iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"route\":");
+        r.route.write_json(&mut out);
+        out.push_str(",\"when\":");
+        r.when.write_json(&mut out);
+        out.push_str(",\"target\":");
+        r.target.write_json(&mut out);
+        let _ = write!(out, ",\"hits\":{}}}", r.hits);
+    }
+    out.push(']');
+    out
+}
+
+/// Set the routes in `text`: `route: conditions -> target` per line, rules
+/// in the order given; blank lines and `#` comments are skipped. Each
+/// route named replaces the rules it had; nothing is set if a line is
+/// bad. Returns how many rules were set.
+pub fn load_str(text: &str) -> Result<usize, RouteError> {
+    let mut routes: HashMap<String, Vec<Rule>> = HashMap::new();
+    let mut set = 0;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (route, rule) = line.split_once(':').ok_or(RouteError::Parse(i + 1))?;
+        let (when, target) = rule.rsplit_once("->").ok_or(RouteError::Parse(i + 1))?;
+        let route = route.trim();
+        routes.entry(route.to_string()).or_default().push(Rule::new(route, when, target)?);
+        set += 1;
+    }
+    replace(routes);
+    Ok(set)
+}
+
+/// load_str() with the contents of `path`
+pub fn load(path: impl AsRef<Path>) -> Result<usize, RouteError> {
+    let text = std::fs::read_to_string(path).map_err(|e| RouteError::Io(e.to_string()))?;
+    load_str(&text)
+}
+
+/// Forward a send to a route to the target of the first rule it matches.
+/// NotFound if the route is gone, no rule matches or the target does not
+/// exist; UnknownMessage if `msg` is not an interop message.
+fn route_send_fn(route: &str, sender: &str, msg: &dyn Message) -> i32 {
+    let rules = match ROUTES.read().unwrap().get(route).cloned() {
+        Some(rules) => rules,
+        None => return InteropErrorCode::NotFound as i32,
+    };
+    let rule = match rules.iter().find(|r| r.matches(msg)) {
+        Some(rule) => rule,
+        None => {
+            UNMATCHED.fetch_add(1, Ordering::Relaxed);
+            health::record_dead_letter(sender, route, msg.message_id());
+            return InteropErrorCode::NotFound as i32;
+        }
+    };
+    let target = match get_actor_ref(&rule.target, sender) {
+        Some(t) => t,
+        None => {
+            health::record_dead_letter(sender, &rule.target, msg.message_id());
+            return InteropErrorCode::NotFound as i32;
+        }
+    };
+    let copy = match clone_message(msg) {
+        Some(c) => c,
+        None => return InteropErrorCode::UnknownMessage as i32,
+    };
+    rule.hits.fetch_add(1, Ordering::Relaxed);
+    let sender_ref = if sender.is_empty() { None } else { get_actor_ref(sender, &rule.target) };
+    target.send(copy, sender_ref);
+    0
+}
+
+/// ActorRef for a route, None for any other name. Lookups from Rust
+/// (`get_actor_ref`) and sends from C++ use this.
+pub fn route_ref(name: &str, sender: &str) -> Option<ActorRef> {
+    if !is_route(name) {
+        return None;
+    }
+    Some(ActorRef::Cpp(CppActorRef::new(name, sender, route_send_fn)))
+}
+
+/// Load `route: conditions -> target` lines from path. Returns how many
+/// rules were set, or InvalidArgument if the path is null, the file cannot
+/// be read or a line is bad (see stderr); nothing is set then.
+#[export_name = c_symbol!("interop_routes_load")]
+pub extern "C" fn interop_routes_load(path: *const c_char) -> c_int {
+    let path = match c_str(path) {
+        Some(p) => p,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    match load(path) {
+        Ok(n) => n.min(c_int::MAX as usize) as c_int,
+        Err(e) => {
+            eprintln!("[Routes] {}: {}", path, e);
+            InteropErrorCode::InvalidArgument as c_int
+        }
+    }
+}
+
+/// Add a rule after those of `route`; `when` is `*` or conditions.
+/// Returns 0, or InvalidArgument on a bad name or conditions.
+#[export_name = c_symbol!("interop_route_add")]
+pub extern "C" fn interop_route_add(route: *const c_char, when: *const c_char, target: *const c_char) -> c_int {
+    match (c_str(route), c_str(when), c_str(target)) {
+        (Some(r), Some(w), Some(t)) => match add_rule(r, w, t) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("[Routes] {}: {}", r, e);
+                InteropErrorCode::InvalidArgument as c_int
+            }
+        },
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Returns 0, NotFound if `route` was not a route, or InvalidArgument on
+/// a bad name
+#[export_name = c_symbol!("interop_route_remove")]
+pub extern "C" fn interop_route_remove(route: *const c_char) -> c_int {
+    let rc = match c_str(route) {
+        Some(r) if remove(r) => InteropErrorCode::Ok,
+        Some(_) => InteropErrorCode::NotFound,
+        None => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Write rules_json() into `out_json` (NUL-terminated, truncated to fit
+/// `len`). Returns the full length like snprintf.
+#[export_name = c_symbol!("interop_routes")]
+pub extern "C" fn interop_routes(out_json: *mut c_char, len: usize) -> c_int {
+    let json = rules_json();
+    if !out_json.is_null() && len > 0 {
+        let n = json.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(json.as_ptr(), out_json as *mut u8, n);
+            *out_json.add(n) = 0;
+        }
+    }
+    json.len().min(c_int::MAX as usize) as c_int
+}
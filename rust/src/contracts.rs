@@ -0,0 +1,207 @@
+//! Message contracts of Rust actors, checked at the bridge
+//!
+//! The C++ and Rust teams agree on what each Rust actor takes and sends in
+//! its `INTEROP_ACTOR` (and `INTEROP_EMITS`) declaration. Mis-wiring, a C++
+//! component sending `MarketDepth` to an actor that only handles
+//! `MarketUpdate`, otherwise shows up as a message silently ignored by
+//! `handle_messages!`. In contract checking mode the bridge compares each
+//! message crossing it with the contracts:
+//!
+//! ```c
+//! interop_set_contract_mode(INTEROP_CONTRACT_LOG);     // or _REJECT
+//! ```
+//!
+//! - Inbound: a message to a Rust actor with a contract must be one it
+//!   takes.
+//! - Outbound: a message from a Rust actor whose contract lists what it
+//!   sends (`INTEROP_EMITS`, plus the replies of its `INTEROP_ACTOR`) must
+//!   be one of those.
+//! - `Log` logs each kind of violation once and counts every one; `Reject`
+//!   also refuses the message like a policy denial (`POLICY_DENIED`,
+//!   counted, logged, audited; see policy).
+//!
+//! Contracts come from the generated `ACTOR_CONTRACTS`; `declare()` adds or
+//! replaces one at run time. Actors without a contract, C++ actors, and
+//! Rust -> Rust sends are not checked. Checked on the same sends as the
+//! policy.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_name, ACTOR_CONTRACTS};
+use crate::names::c_str;
+
+/// Mirrors INTEROP_CONTRACT_* in interop/contracts.h
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub enum ContractMode {
+    Off = 0,
+    Log = 1,
+    Reject = 2,
+}
+
+impl ContractMode {
+    pub fn from_i32(mode: i32) -> Option<ContractMode> {
+        match mode {
+            0 => Some(ContractMode::Off),
+            1 => Some(ContractMode::Log),
+            2 => Some(ContractMode::Reject),
+            _ => None,
+        }
+    }
+}
+
+/// What a Rust actor takes and sends
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Contract {
+    pub handles: Vec<i32>,
+    /// None if what it sends is not declared, and not checked
+    pub emits: Option<Vec<i32>>,
+}
+
+/// A message outside a contract
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Violation {
+    /// The target does not take it
+    NotHandled,
+    /// The sender does not send it
+    NotEmitted,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::NotHandled => write!(f, "not a message the target takes"),
+            Violation::NotEmitted => write!(f, "not a message the sender sends"),
+        }
+    }
+}
+
+static MODE: AtomicU8 = AtomicU8::new(ContractMode::Off as u8);
+static VIOLATIONS: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref CONTRACTS: RwLock<HashMap<String, Contract>> = RwLock::new(
+        ACTOR_CONTRACTS
+            .iter()
+            .map(|(name, handles, emits)| {
+                (name.to_string(), Contract { handles: handles.to_vec(), emits: emits.map(<[i32]>::to_vec) })
+            })
+            .collect()
+    );
+    // (actor, message ID, violation) already logged
+    static ref LOGGED: Mutex<HashSet<(String, i32, Violation)>> = Mutex::new(HashSet::new());
+}
+
+pub fn set_mode(mode: ContractMode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn mode() -> ContractMode {
+    ContractMode::from_i32(MODE.load(Ordering::Relaxed) as i32).unwrap_or(ContractMode::Off)
+}
+
+/// Set the contract of `actor`, replacing any declared in the header
+pub fn declare(actor: &str, contract: Contract) {
+    CONTRACTS.write().unwrap().insert(actor.to_string(), contract);
+}
+
+pub fn contract_of(actor: &str) -> Option<Contract> {
+    CONTRACTS.read().unwrap().get(actor).cloned()
+}
+
+/// Violations seen since start, in `Log` and `Reject` mode
+pub fn violations() -> u64 {
+    VIOLATIONS.load(Ordering::Relaxed)
+}
+
+/// How `msg_id` from `sender` to `target` breaks a contract, whatever the
+/// mode; None if it does not
+pub fn check(sender: &str, target: &str, msg_id: i32) -> Option<Violation> {
+    let contracts = CONTRACTS.read().unwrap();
+    if contracts.get(target).is_some_and(|c| !c.handles.contains(&msg_id)) {
+        return Some(Violation::NotHandled);
+    }
+    match contracts.get(sender).and_then(|c| c.emits.as_ref()) {
+        Some(emits) if !emits.contains(&msg_id) => Some(Violation::NotEmitted),
+        _ => None,
+    }
+}
+
+/// Check a message about to cross the bridge. False if it breaks a
+/// contract in `Reject` mode; the caller refuses it.
+pub(crate) fn permits(sender: &str, target: &str, msg_id: i32) -> bool {
+    let mode = mode();
+    if mode == ContractMode::Off {
+        return true;
+    }
+    let violation = match check(sender, target, msg_id) {
+        Some(v) => v,
+        None => return true,
+    };
+    VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+    let actor = if violation == Violation::NotHandled { target } else { sender };
+    if LOGGED.lock().unwrap().insert((actor.to_string(), msg_id, violation)) {
+        eprintln!(
+            "[Contract] {} ({}) from '{}' to '{}': {}{}",
+            message_name(msg_id).unwrap_or("?"),
+            msg_id,
+            sender,
+            target,
+            violation,
+            if mode == ContractMode::Reject { ", rejected" } else { "" }
+        );
+    }
+    mode != ContractMode::Reject
+}
+
+/// Set the contract checking mode (INTEROP_CONTRACT_*). Returns 0, or
+/// InvalidArgument for an unknown mode.
+#[export_name = c_symbol!("interop_set_contract_mode")]
+pub extern "C" fn interop_set_contract_mode(mode: c_int) -> c_int {
+    match ContractMode::from_i32(mode) {
+        Some(mode) => {
+            set_mode(mode);
+            InteropErrorCode::Ok as c_int
+        }
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Set the contract of `actor`: the `handles_len` message IDs it takes and
+/// the `emits_len` it sends; null `emits` leaves what it sends unchecked.
+/// Returns 0, or InvalidArgument on a bad name or null `handles` with a
+/// length.
+#[export_name = c_symbol!("interop_declare_contract")]
+pub extern "C" fn interop_declare_contract(
+    actor: *const c_char,
+    handles: *const c_int,
+    handles_len: usize,
+    emits: *const c_int,
+    emits_len: usize,
+) -> c_int {
+    if handles.is_null() && handles_len > 0 {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    let actor = match c_str(actor) {
+        Some(a) => a,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let ids = |p: *const c_int, len: usize| match len {
+        0 => Vec::new(),
+        _ => unsafe { std::slice::from_raw_parts(p, len) }.to_vec(),
+    };
+    let emits = if emits.is_null() { None } else { Some(ids(emits, emits_len)) };
+    declare(actor, Contract { handles: ids(handles, handles_len), emits });
+    InteropErrorCode::Ok as c_int
+}
+
+/// Contract violations seen since start
+#[export_name = c_symbol!("interop_contract_violations")]
+pub extern "C" fn interop_contract_violations() -> u64 {
+    violations()
+}
@@ -0,0 +1,422 @@
+//! Control socket for the interop-ctl tool
+//!
+//! `listen(path)` (C++: `interop_control_listen()`) opens a unix socket in
+//! the running process. `interop-ctl` connects to it and sends one command
+//! line; the reply is text, and the process closes the connection once it
+//! is complete:
+//!
+//! | Command | Reply |
+//! |---------|-------|
+//! | `actors` | one line per Rust actor: name, status, queue depth, `paused`, `frozen` |
+//! | `metrics` | the health summary (see health), one JSON line |
+//! | `tap <publisher> <topic>` | each message published on the topic, one JSON line each, until the client leaves |
+//! | `samples` | the sampled messages kept by capture, one JSON line |
+//! | `threads` | CPU time and switches of every thread (see thread_usage), one JSON line |
+//! | `subscriptions` | every subscription the broker holds, subscribers marked Rust or C++ (see broker), one JSON line |
+//! | `send <target> <Message> [field=value ...]` | `ok` once sent to the target with sender `interop-ctl` |
+//! | `freeze <actor>` | `ok` once the Rust actor stops handling messages (see freeze) |
+//! | `pending <actor>` | the messages kept from a frozen actor, one JSON line |
+//! | `drop <actor> <index>` | `ok` once the kept message is discarded |
+//! | `move <actor> <from> <to>` | `ok` once the kept message is moved |
+//! | `thaw <actor>` | `ok <n>` once the actor is set to handle its `n` kept messages |
+//! | `log <level> [actor]` | `ok` once the bridge log level is set for the process or one actor; `default` clears an actor's (see interop_log) |
+//! | `tenant <tenant> <command ...>` | the command's reply, narrowed to the tenant's actors (see tenant) |
+//!
+//! ```text
+//! interop-ctl --socket /run/pricing.sock actors
+//! interop-ctl --socket /run/pricing.sock tap cpp_feed AAPL
+//! interop-ctl --socket /run/pricing.sock send rust_pong Ping count=7
+//! ```
+//!
+//! Arguments are separated by whitespace, or by tabs if the line has any
+//! (interop-ctl sends tabs, so a field value may hold spaces). A failed
+//! command replies with one line starting with `error:`.
+//!
+//! A tap subscribes a mailbox named `__ctl-<id>` to the topic, routed like
+//! the reply mailboxes of `ask`, and unsubscribes when the client leaves.
+//! `send` builds the message from a zeroed C struct and the given fields
+//! (see `set_message_field`). The socket is bound in a fresh 0700
+//! directory and moved to its path once it is mode 0600, so no other user
+//! can connect in between. Command lines longer than `MAX_LINE` are
+//! refused. Listening is unix only.
+
+use std::collections::HashMap;
+use std::io;
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+
+use actors::{ActorRef, CppActorRef, Message};
+
+use crate::broker;
+use crate::capture;
+use crate::freeze;
+use crate::health;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_log::{self, LogLevel};
+use crate::interop_messages::{
+    c_struct_size, clone_message, message_from_c, message_to_debug_json, set_message_field, validate_message,
+};
+use crate::names::{self, c_str};
+use crate::pause;
+use crate::qos::QOS_BEST_EFFORT;
+use crate::queue_depth;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::schema;
+use crate::send_error::TrySend;
+use crate::tenant;
+use crate::thread_usage;
+
+/// Prefix of tap mailbox names; not for actor names
+pub const CTL_PREFIX: &str = "__ctl-";
+/// Sender of messages from `send`
+pub const CTL_SENDER: &str = "interop-ctl";
+/// Longest command line accepted, newline included
+pub const MAX_LINE: usize = 64 * 1024;
+
+/// Socket interop-ctl uses without `--socket`
+pub const DEFAULT_SOCKET: &str = "/tmp/actors-interop.sock";
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static::lazy_static! {
+    // Open tap mailboxes by name
+    static ref TAPS: Mutex<HashMap<String, Sender<Box<dyn Message>>>> = Mutex::new(HashMap::new());
+}
+
+/// send_fn of tap refs: copy the message into the mailbox
+fn tap_send_fn(target: &str, _sender: &str, msg: &dyn Message) -> i32 {
+    let tx = match TAPS.lock().unwrap().get(target) {
+        Some(tx) => tx.clone(),
+        None => return InteropErrorCode::NotFound as i32, // client gone
+    };
+    match clone_message(msg) {
+        Some(copy) => {
+            let _ = tx.send(copy);
+            InteropErrorCode::Ok as i32
+        }
+        None => InteropErrorCode::UnknownMessage as i32,
+    }
+}
+
+/// ActorRef for an open tap mailbox, None if `name` is not one. The bridge
+/// and `get_actor_ref` use this to route published messages.
+pub fn mailbox_ref(name: &str) -> Option<ActorRef> {
+    if !name.starts_with(CTL_PREFIX) || !TAPS.lock().unwrap().contains_key(name) {
+        return None;
+    }
+    Some(ActorRef::Cpp(CppActorRef::new(name, "", tap_send_fn)))
+}
+
+/// Reply to `actors`
+fn actors_reply() -> String {
+    let mut out = String::new();
+    for name in health::actor_names().into_iter().filter(|name| tenant::visible(name)) {
+        out.push_str(&format!("{} {} {}", name, health::actor_status(&name), queue_depth::pending(&name)));
+        if pause::is_paused(&name) {
+            out.push_str(" paused");
+        }
+        if freeze::is_frozen(&name) {
+            out.push_str(" frozen");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn message_id(name: &str) -> Option<i32> {
+    name.parse().ok().filter(|&id| schema::by_id(id).is_some()).or_else(|| schema::id_of(name))
+}
+
+/// Build message `name` from `field=value` pairs
+fn build_message(name: &str, fields: &[&str]) -> Result<Box<dyn Message>, String> {
+    let id = message_id(name).ok_or_else(|| format!("unknown message {}", name))?;
+    let size = c_struct_size(id).ok_or_else(|| format!("message {} has no C struct", name))?;
+    // u64 words so the struct is aligned
+    let zeroed = vec![0u64; size.div_ceil(8).max(1)];
+    let mut msg = unsafe { message_from_c(id, zeroed.as_ptr() as *const _) }
+        .ok_or_else(|| format!("unknown message {}", name))?;
+    for pair in fields {
+        let (field, value) = pair.split_once('=').ok_or_else(|| format!("expected field=value, got {}", pair))?;
+        if !set_message_field(msg.as_mut(), field, value) {
+            return Err(format!("cannot set {}.{} to {:?}", name, field, value));
+        }
+    }
+    validate_message(msg.as_ref()).map_err(|e| format!("{}: {}", name, e))?;
+    Ok(msg)
+}
+
+fn send_command(args: &[&str]) -> Result<(), String> {
+    let (target, name) = match args {
+        [target, name, ..] => (*target, *name),
+        _ => return Err("usage: send <target> <Message> [field=value ...]".to_string()),
+    };
+    let msg = build_message(name, &args[2..])?;
+    let target_ref = get_actor_ref(target, CTL_SENDER).ok_or_else(|| format!("actor {} not found", target))?;
+    target_ref.try_send(msg, None).map_err(|e| format!("send to {}: {}", target, e))
+}
+
+/// Reply to `freeze`, `pending`, `drop`, `move` and `thaw`
+fn freeze_command(cmd: &str, args: &[&str]) -> Result<String, String> {
+    let index = |arg: &str| arg.parse::<usize>().map_err(|_| format!("bad index {}", arg));
+    let not_frozen = |name: &str| format!("{} is not frozen", name);
+    match (cmd, args) {
+        ("freeze", [name]) if names::is_rust(name) => {
+            freeze::freeze(name);
+            Ok("ok\n".to_string())
+        }
+        ("freeze", [name]) => Err(format!("{} is not a Rust actor", name)),
+        ("pending", [name]) => freeze::pending_json(name).map(|json| json + "\n").ok_or_else(|| not_frozen(*name)),
+        ("drop", [name, i]) => match freeze::remove(name, index(*i)?) {
+            true => Ok("ok\n".to_string()),
+            false => Err(format!("{} has no kept message {}", name, i)),
+        },
+        ("move", [name, from, to]) => match freeze::move_to(name, index(*from)?, index(*to)?) {
+            true => Ok("ok\n".to_string()),
+            false => Err(format!("{} has no kept messages {} and {}", name, from, to)),
+        },
+        ("thaw", [name]) => freeze::thaw(name).map(|n| format!("ok {}\n", n)).ok_or_else(|| not_frozen(*name)),
+        ("drop", _) => Err("usage: drop <actor> <index>".to_string()),
+        ("move", _) => Err("usage: move <actor> <from> <to>".to_string()),
+        _ => Err(format!("usage: {} <actor>", cmd)),
+    }
+}
+
+/// `log <level>` or `log <level> <actor>`
+fn log_command(args: &[&str]) -> Result<String, String> {
+    let level = |arg: &str| {
+        LogLevel::parse(arg).ok_or_else(|| format!("unknown level {}; expected off, error, info, debug or trace", arg))
+    };
+    match args {
+        [l] => interop_log::set_level(level(l)?),
+        ["default", actor] => interop_log::set_actor_level(actor, None),
+        [l, actor] => interop_log::set_actor_level(actor, Some(level(l)?)),
+        _ => return Err("usage: log <level> [actor]".to_string()),
+    }
+    Ok("ok\n".to_string())
+}
+
+#[cfg(unix)]
+mod server {
+    use std::fs;
+    use std::io::{self, BufRead, BufReader, Read, Write};
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    use super::*;
+
+    // How often an idle tap checks that its client is still there
+    const TAP_POLL: Duration = Duration::from_millis(500);
+
+    // Suffix of the staging directory of each bind
+    static NEXT_STAGE: AtomicU64 = AtomicU64::new(0);
+
+    struct Listening {
+        path: PathBuf,
+        stop: Arc<AtomicBool>,
+        thread: JoinHandle<()>,
+    }
+
+    static LISTENING: Mutex<Option<Listening>> = Mutex::new(None);
+
+    pub fn listen(path: &Path) -> io::Result<()> {
+        close();
+        // A socket left by a process that died is in the way; anything else is an error
+        if fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+            fs::remove_file(path)?;
+        }
+        let listener = bind_private(path)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopping = stop.clone();
+        let thread = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopping.load(Ordering::Acquire) {
+                    return;
+                }
+                match stream {
+                    Ok(stream) => {
+                        thread::spawn(move || handle(stream));
+                    }
+                    Err(e) => eprintln!("[Control] accept failed: {}", e),
+                }
+            }
+        });
+        *LISTENING.lock().unwrap() = Some(Listening { path: path.to_path_buf(), stop, thread });
+        Ok(())
+    }
+
+    /// Bind `path` mode 0600 from the first moment it can be connected to:
+    /// the socket is bound in a new 0700 directory beside `path`, which
+    /// only we can enter, and renamed into place once its mode is set
+    fn bind_private(path: &Path) -> io::Result<UnixListener> {
+        if fs::symlink_metadata(path).is_ok() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} exists", path.display())));
+        }
+        let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let stage = NEXT_STAGE.fetch_add(1, Ordering::Relaxed);
+        let dir = parent.join(format!(".interop-ctl-{}-{}", std::process::id(), stage));
+        fs::DirBuilder::new().mode(0o700).create(&dir)?;
+        let staged = dir.join("sock");
+        let bound = UnixListener::bind(&staged).and_then(|listener| {
+            fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+            fs::rename(&staged, path)?;
+            Ok(listener)
+        });
+        let _ = fs::remove_file(&staged);
+        let _ = fs::remove_dir(&dir);
+        bound
+    }
+
+    pub fn close() {
+        let listening = match LISTENING.lock().unwrap().take() {
+            Some(l) => l,
+            None => return,
+        };
+        listening.stop.store(true, Ordering::Release);
+        let _ = UnixStream::connect(&listening.path);  // wake accept()
+        let _ = listening.thread.join();
+        let _ = fs::remove_file(&listening.path);
+    }
+
+    fn handle(mut stream: UnixStream) {
+        let mut line = String::new();
+        match BufReader::new(&stream).take(MAX_LINE as u64).read_line(&mut line) {
+            Ok(n) if n >= MAX_LINE && !line.ends_with('\n') => {
+                let _ = stream.write_all(format!("error: command longer than {} bytes\n", MAX_LINE).as_bytes());
+                return;
+            }
+            Ok(_) => {}
+            Err(_) => return,
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        let args: Vec<&str> = if line.contains('\t') {
+            line.split('\t').collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        let (view, args) = match args.as_slice() {
+            ["tenant", t, rest @ ..] => (Some(*t), rest),
+            all => (None, all),
+        };
+        if let ["tap", publisher, topic] = args {
+            return tap(stream, publisher, topic);
+        }
+        let reply = tenant::view(view, || match args {
+            ["actors"] => actors_reply(),
+            ["metrics"] => format!("{}\n", health::health_json()),
+            ["samples"] => format!("{}\n", capture::recent_json(usize::MAX)),
+            ["threads"] => format!("{}\n", thread_usage::threads_json()),
+            ["subscriptions"] => format!("{}\n", broker::subscriptions_json()),
+            ["send", rest @ ..] => match send_command(rest) {
+                Ok(()) => "ok\n".to_string(),
+                Err(e) => format!("error: {}\n", e),
+            },
+            [cmd @ ("freeze" | "pending" | "drop" | "move" | "thaw"), rest @ ..] => {
+                freeze_command(cmd, rest).unwrap_or_else(|e| format!("error: {}\n", e))
+            }
+            ["log", rest @ ..] => log_command(rest).unwrap_or_else(|e| format!("error: {}\n", e)),
+            [] => "error: empty command\n".to_string(),
+            [cmd, ..] => format!(
+                "error: unknown command {}; expected actors, metrics, samples, threads, subscriptions, tap, send, freeze, pending, drop, move, thaw, log or tenant\n",
+                cmd
+            ),
+        });
+        let _ = stream.write_all(reply.as_bytes());
+    }
+
+    // True once the client has closed its end
+    fn client_left(stream: &mut UnixStream) -> bool {
+        if stream.set_nonblocking(true).is_err() {
+            return true;
+        }
+        let left = match stream.read(&mut [0u8; 64]) {
+            Ok(0) => true,
+            Ok(_) => false,
+            Err(e) => e.kind() != io::ErrorKind::WouldBlock,
+        };
+        let _ = stream.set_nonblocking(false);
+        left
+    }
+
+    fn tap(mut stream: UnixStream, publisher: &str, topic: &str) {
+        let name = format!("{}{}", CTL_PREFIX, NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let (tx, rx) = mpsc::channel();
+        TAPS.lock().unwrap().insert(name.clone(), tx);
+        if !broker::subscribe(&name, publisher, topic, QOS_BEST_EFFORT) {
+            TAPS.lock().unwrap().remove(&name);
+            let _ = writeln!(stream, "error: publisher {} not found or topic {} too long", publisher, topic);
+            return;
+        }
+        loop {
+            match rx.recv_timeout(TAP_POLL) {
+                Ok(msg) => {
+                    let json = message_to_debug_json(msg.as_ref())
+                        .unwrap_or_else(|| format!("{{\"msg_id\":{}}}", msg.message_id()));
+                    if writeln!(stream, "{}", json).is_err() {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) if !client_left(&mut stream) => {}
+                Err(_) => break,
+            }
+        }
+        broker::unsubscribe(&name, publisher, topic);
+        TAPS.lock().unwrap().remove(&name);
+    }
+}
+
+/// Open the control socket at `path`, closing one already open. Unix only.
+pub fn listen(path: impl AsRef<Path>) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        server::listen(path.as_ref())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Err(io::Error::new(io::ErrorKind::Unsupported, "the control socket needs unix sockets"))
+    }
+}
+
+/// Close the control socket and remove its file; open taps end when their
+/// clients leave
+pub fn close() {
+    #[cfg(unix)]
+    server::close();
+}
+
+/// Open the control socket at `path` (DEFAULT_SOCKET if null). Returns 0,
+/// or InvalidArgument if it cannot be opened or on a non-unix platform.
+#[export_name = c_symbol!("interop_control_listen")]
+pub extern "C" fn interop_control_listen(path: *const c_char) -> c_int {
+    let path = if path.is_null() {
+        DEFAULT_SOCKET
+    } else {
+        match c_str(path) {
+            Some(p) => p,
+            None => return InteropErrorCode::InvalidArgument as c_int,
+        }
+    };
+    let rc = match listen(path) {
+        Ok(()) => InteropErrorCode::Ok,
+        Err(e) => {
+            eprintln!("[Control] cannot listen on {}: {}", path, e);
+            InteropErrorCode::InvalidArgument
+        }
+    };
+    rc as c_int
+}
+
+/// Close the control socket
+#[export_name = c_symbol!("interop_control_close")]
+pub extern "C" fn interop_control_close() {
+    close();
+}
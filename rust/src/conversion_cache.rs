@@ -0,0 +1,151 @@
+//! One conversion to the C struct per fan-out, not one per subscriber
+//!
+//! A publisher sending the same snapshot or acknowledgement to a hundred
+//! C++ subscribers converts it to its C struct a hundred times, once in
+//! each send. With the cache enabled, a fan-out (`broker::publish` and
+//! friends) keeps the C structs it has built, keyed by message ID and
+//! content: a send whose message equals one already converted in the same
+//! fan-out hands C++ the same struct.
+//!
+//! ```c
+//! interop_set_conversion_cache(1);
+//! uint64_t hits, misses;
+//! interop_conversion_cache_stats(&hits, &misses);
+//! ```
+//!
+//! - Off by default; from Rust, `set_enabled()`.
+//! - Scoped to one fan-out on one thread and dropped when it returns, so
+//!   nothing is kept between publishes. Fan-out workers (see broker)
+//!   convert per send.
+//! - Messages with `interop_bytes` fields (pointers into the Rust message)
+//!   or a `sequenced` field (stamped per subscriber) differ per send and
+//!   are converted as before.
+//! - C++ receives a pointer valid for the duration of its send call, as
+//!   before; the struct is shared only between sends.
+
+use std::cell::RefCell;
+use std::os::raw::{c_int, c_void};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use actors::Message;
+
+use crate::interop_messages::{
+    c_struct_is_portable, clone_message, message_to_c_bytes, messages_equal, C_STRUCT_LAYOUTS,
+};
+use crate::schema;
+
+/// C structs kept per fan-out; a filtered fan-out may send a few variants
+const MAX_ENTRIES: usize = 8;
+
+struct Entry {
+    msg: Box<dyn Message>,
+    // u64 words, so the struct is aligned as C++ expects
+    c_struct: Rc<Vec<u64>>,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // Some while a fan-out runs on this thread
+    static SCOPE: RefCell<Option<Vec<Entry>>> = const { RefCell::new(None) };
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Sends that reused a C struct since start
+pub fn hits() -> u64 {
+    HITS.load(Ordering::Relaxed)
+}
+
+/// Cacheable sends that converted since start
+pub fn misses() -> u64 {
+    MISSES.load(Ordering::Relaxed)
+}
+
+/// Run the sends of one fan-out in `f`, sharing conversions of equal
+/// messages. Nested fan-outs share the outer one's.
+pub fn fan_out<R>(f: impl FnOnce() -> R) -> R {
+    if !is_enabled() || SCOPE.with(|s| s.borrow().is_some()) {
+        return f();
+    }
+    struct Scope;
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            SCOPE.with(|s| *s.borrow_mut() = None);
+        }
+    }
+    SCOPE.with(|s| *s.borrow_mut() = Some(Vec::new()));
+    let _scope = Scope;  // closed even if a send panics
+    f()
+}
+
+fn cacheable(msg_id: i32) -> bool {
+    c_struct_is_portable(msg_id)
+        && C_STRUCT_LAYOUTS.iter().any(|&(id, _, _, align)| id == msg_id && align <= std::mem::align_of::<u64>())
+        && schema::by_id(msg_id).is_some_and(|s| !s.fields.iter().any(|f| f.sequenced))
+}
+
+/// The C struct of `msg` from this fan-out, converted and kept if it is
+/// the first of its kind
+fn lookup(msg: &dyn Message) -> Option<Rc<Vec<u64>>> {
+    if let Some(hit) = SCOPE.with(|s| {
+        let scope = s.borrow();
+        scope.as_ref()?.iter().find(|e| messages_equal(e.msg.as_ref(), msg)).map(|e| e.c_struct.clone())
+    }) {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return Some(hit);
+    }
+    let bytes = message_to_c_bytes(msg)?;
+    let mut words = vec![0u64; bytes.len().div_ceil(8)];
+    unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), words.as_mut_ptr() as *mut u8, bytes.len()) };
+    let c_struct = Rc::new(words);
+    let copy = clone_message(msg)?;
+    SCOPE.with(|s| {
+        if let Some(entries) = s.borrow_mut().as_mut() {
+            if entries.len() < MAX_ENTRIES {
+                entries.push(Entry { msg: copy, c_struct: c_struct.clone() });
+            }
+        }
+    });
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    Some(c_struct)
+}
+
+/// Call `f` with the C struct of `msg` shared within the current fan-out.
+/// None outside a fan-out or for a message that is not cached; the caller
+/// converts it itself.
+pub(crate) fn with_cached<R>(msg: &dyn Message, f: impl FnOnce(*const c_void) -> R) -> Option<R> {
+    if !is_enabled() || !SCOPE.with(|s| s.borrow().is_some()) || !cacheable(msg.message_id()) {
+        return None;
+    }
+    // Held outside the scope's borrow: C++ may publish from inside the send
+    let c_struct = lookup(msg)?;
+    Some(f(c_struct.as_ptr() as *const c_void))
+}
+
+/// Turn the cache on (non-zero) or off. Returns 0.
+#[export_name = c_symbol!("interop_set_conversion_cache")]
+pub extern "C" fn interop_set_conversion_cache(enabled: c_int) -> c_int {
+    set_enabled(enabled != 0);
+    0
+}
+
+/// Write the hit and miss counts; either pointer may be null
+#[export_name = c_symbol!("interop_conversion_cache_stats")]
+pub extern "C" fn interop_conversion_cache_stats(hits_out: *mut u64, misses_out: *mut u64) {
+    if !hits_out.is_null() {
+        unsafe { *hits_out = hits() };
+    }
+    if !misses_out.is_null() {
+        unsafe { *misses_out = misses() };
+    }
+}
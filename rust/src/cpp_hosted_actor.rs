@@ -0,0 +1,110 @@
+//! C++-implemented actors hosted by the Rust Manager
+//!
+//! C++ supplies a table of function pointers and the Rust Manager schedules
+//! the actor on its own thread like any Rust actor. Incoming messages are
+//! converted to their C structs and handed to `on_message`.
+//!
+//! This lets actors be migrated one at a time without running a parallel
+//! C++ Manager.
+//!
+//! The Manager may replace an actor's instance while it stays registered:
+//! after a panic (see lifecycle), on passivation and re-activation, on
+//! replacement and on `rust_manager_restart()`. Every instance is built
+//! from the same vtable, so `on_stop` (an instance stopped) can be followed
+//! by `on_start` on the same `user_data`. `on_release` is called once, when
+//! the registration is forgotten and no instance is left (see teardown);
+//! only then may C++ free `user_data`.
+
+use std::os::raw::{c_int, c_void};
+use std::sync::Arc;
+
+use actors::ActorContext;
+use actors::messages::Start;
+use crate::fallback::InteropEnvelope;
+use crate::handle_messages;
+use crate::interop_messages::with_c_struct;
+
+/// Callback table supplied by C++ for an actor hosted by the Rust Manager.
+///
+/// Every callback receives `user_data` unchanged. Any callback may be null.
+/// `on_message` receives the message ID and a pointer to the C struct, which
+/// is only valid for the duration of the call. `on_release` comes last so
+/// tables written before it existed leave it null.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CppActorVTable {
+    pub on_start: Option<extern "C" fn(user_data: *mut c_void)>,
+    pub on_message: Option<extern "C" fn(user_data: *mut c_void, msg_type: c_int, msg_data: *const c_void)>,
+    pub on_stop: Option<extern "C" fn(user_data: *mut c_void)>,
+    pub user_data: *mut c_void,
+    pub on_release: Option<extern "C" fn(user_data: *mut c_void)>,
+}
+
+// on_start and on_message run on the actor's thread, but on_stop runs from
+// Drop on whichever thread drops the actor, which need not be that one, and
+// on_release wherever the last holder drops. C++ must accept calls on
+// user_data from any thread; they never overlap, since an instance is
+// dropped only once nothing else holds it and a replacement is built after.
+unsafe impl Send for CppActorVTable {}
+
+/// Calls `on_release` when the registration's builder and its last
+/// instance are gone
+struct Release(CppActorVTable);
+
+// Only Drop reads the table, once
+unsafe impl Sync for Release {}
+
+impl Drop for Release {
+    fn drop(&mut self) {
+        if let Some(on_release) = self.0.on_release {
+            on_release(self.0.user_data);
+        }
+    }
+}
+
+/// Rust actor that forwards its lifecycle and messages to a C++ vtable
+pub struct CppHostedActor {
+    vtable: CppActorVTable,
+    _release: Arc<Release>,
+}
+
+impl CppHostedActor {
+    /// Builder of the instances of one registration; `on_release` is
+    /// called once it and every instance it built are dropped
+    pub fn factory(vtable: CppActorVTable) -> impl FnMut() -> CppHostedActor + Send + 'static {
+        let release = Arc::new(Release(vtable));
+        move || CppHostedActor { vtable, _release: release.clone() }
+    }
+
+    /// Convert to the C struct and hand it to C++. An UnknownMessage (see
+    /// fallback) has no C struct and is not passed on.
+    fn on_message(&mut self, env: &InteropEnvelope, _ctx: &mut ActorContext) {
+        if let Some(on_message) = self.vtable.on_message {
+            let user_data = self.vtable.user_data;
+            with_c_struct(env.message, |_, _| {}, |data| on_message(user_data, env.msg_id, data));
+        }
+    }
+
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        if let Some(on_start) = self.vtable.on_start {
+            on_start(self.vtable.user_data);
+        }
+    }
+}
+
+/// The Manager drops an instance when its thread exits or it is replaced
+/// (see the module doc), which is our stop signal
+impl Drop for CppHostedActor {
+    fn drop(&mut self) {
+        if let Some(on_stop) = self.vtable.on_stop {
+            on_stop(self.vtable.user_data);
+        }
+    }
+}
+
+// Register message handlers; every other message goes to C++ as its C
+// struct
+handle_messages!(CppHostedActor,
+    Start => on_start,
+    _ => on_message
+);
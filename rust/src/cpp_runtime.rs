@@ -0,0 +1,584 @@
+//! Several C++ runtimes behind one Rust Manager
+//!
+//! The C++ side linked into the process (`cpp_actor_send` and friends,
+//! resolved at link time) is the `LINKED` runtime. More C++ runtimes, e.g.
+//! independently built actor libraries loaded with dlopen, are attached
+//! with their bridge functions:
+//!
+//! ```c
+//! void* lib = dlopen("libpricing.so", RTLD_NOW | RTLD_LOCAL);
+//! InteropCppRuntime rt = {
+//!     dlsym(lib, "cpp_actor_send"), dlsym(lib, "cpp_actor_fast_send"), dlsym(lib, "cpp_actor_exists")
+//! };
+//! interop_attach_cpp_runtime("pricing", "pricing.", rt);   // owns pricing.*
+//! interop_attach_cpp_runtime("risk", NULL, rt2);           // consulted in order
+//! ```
+//!
+//! A Rust lookup or send for a C++ actor goes to:
+//!
+//! - the runtime whose prefix the name starts with (first attached wins),
+//! - else the runtime that answered for the name before,
+//! - else the first of `LINKED` and the unprefixed runtimes, in attach
+//!   order, whose `cpp_actor_exists` knows the name.
+//!
+//! Names are passed through unchanged; the prefix only routes. Each
+//! library still runs its own `cpp_actor_init()` and its C++ actors reach
+//! Rust through the usual `rust_actor_send()`. A runtime that registers
+//! id functions with `interop_set_cpp_runtime_ids()` is sent to by id
+//! instead of by name (see actor_ids).
+//!
+//! A Windows DLL cannot import from the executable that loads it, so on
+//! Windows the `LINKED` functions are not resolved at link time: the
+//! generated `cpp_actor_init()` passes them to
+//! `interop_set_linked_cpp_runtime()`. Until then `LINKED` knows no actors.
+//!
+//! With feature `standalone` nothing is imported from C++ on any platform
+//! either: a pure-Rust service links the crate without a C++ side, and
+//! `LINKED` knows no actors unless a runtime is handed over the same way.
+//! Rust actors, broker and pubsub work as usual; sends to unknown names
+//! are dead letters.
+//!
+//! A C++ runtime about to go away mid-flight (its library unloaded, its
+//! threads exiting) says so first, so no Rust send calls into freed code:
+//!
+//! ```c
+//! interop_cpp_runtime_detached(NULL, 1000);   // the linked runtime; waits up to 1 s
+//! ```
+//!
+//! From then on every send to an actor of the runtime, through any
+//! `ActorRef` already held, fails with `RuntimeDetached` without calling
+//! C++ and is a dead letter of stage `detached`; lookups no longer find
+//! its actors. `$sys/cpp_runtime` subscribers are sent CppRuntimeDetached
+//! (see sys_topics) so they can fail over, and CppRuntimeAttached when a
+//! runtime of that name is attached, or the linked one handed over,
+//! again.
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::actor_ids::{self, CppRuntimeIds};
+use crate::interop_errors::InteropErrorCode;
+use crate::lookup_cache;
+use crate::names::c_str;
+use crate::sys_topics;
+
+/// Name of the C++ runtime linked into the process
+pub const LINKED: &str = "linked";
+
+/// Returned for sends to an actor of a detached runtime
+pub const RUNTIME_DETACHED: i32 = InteropErrorCode::RuntimeDetached as i32;
+
+pub type CppSendFn =
+    unsafe extern "C" fn(actor_name: *const c_char, sender_name: *const c_char, msg_type: c_int, msg_data: *const c_void) -> c_int;
+pub type CppExistsFn = unsafe extern "C" fn(name: *const c_char) -> c_int;
+
+/// Bridge functions of one C++ runtime; mirrors InteropCppRuntime in
+/// interop/cpp_runtime.h
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CppRuntimeVTable {
+    pub send: CppSendFn,
+    pub fast_send: CppSendFn,
+    pub exists: CppExistsFn,
+}
+
+// C++ bridge functions of the linked runtime - resolved at final link time
+#[cfg(not(any(windows, feature = "standalone")))]
+extern "C" {
+    fn cpp_actor_send(
+        actor_name: *const c_char,
+        sender_name: *const c_char,
+        msg_type: c_int,
+        msg_data: *const c_void,
+    ) -> c_int;
+
+    fn cpp_actor_fast_send(
+        actor_name: *const c_char,
+        sender_name: *const c_char,
+        msg_type: c_int,
+        msg_data: *const c_void,
+    ) -> c_int;
+
+    fn cpp_actor_exists(name: *const c_char) -> c_int;
+}
+
+// On Windows: registered by the generated cpp_actor_init()
+#[cfg(any(windows, feature = "standalone"))]
+static LINKED_VTABLE: RwLock<Option<CppRuntimeVTable>> = RwLock::new(None);
+
+#[cfg(any(windows, feature = "standalone"))]
+unsafe extern "C" fn cpp_actor_send(
+    actor_name: *const c_char,
+    sender_name: *const c_char,
+    msg_type: c_int,
+    msg_data: *const c_void,
+) -> c_int {
+    match *LINKED_VTABLE.read().unwrap() {
+        Some(vtable) => (vtable.send)(actor_name, sender_name, msg_type, msg_data),
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+#[cfg(any(windows, feature = "standalone"))]
+unsafe extern "C" fn cpp_actor_fast_send(
+    actor_name: *const c_char,
+    sender_name: *const c_char,
+    msg_type: c_int,
+    msg_data: *const c_void,
+) -> c_int {
+    match *LINKED_VTABLE.read().unwrap() {
+        Some(vtable) => (vtable.fast_send)(actor_name, sender_name, msg_type, msg_data),
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+#[cfg(any(windows, feature = "standalone"))]
+unsafe extern "C" fn cpp_actor_exists(name: *const c_char) -> c_int {
+    match *LINKED_VTABLE.read().unwrap() {
+        Some(vtable) => (vtable.exists)(name),
+        None => 0,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AttachError {
+    /// `LINKED` or an attached runtime already has the name
+    NameTaken(String),
+    /// Another runtime already owns the prefix
+    PrefixTaken(String),
+}
+
+impl fmt::Display for AttachError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttachError::NameTaken(name) => write!(f, "C++ runtime {} is already attached", name),
+            AttachError::PrefixTaken(prefix) => write!(f, "prefix {} is owned by another C++ runtime", prefix),
+        }
+    }
+}
+
+impl std::error::Error for AttachError {}
+
+struct Runtime {
+    name: String,
+    /// Empty: consulted in order rather than by prefix
+    prefix: String,
+    vtable: CppRuntimeVTable,
+    /// Set by interop_set_cpp_runtime_ids()
+    ids: RwLock<Option<CppRuntimeIds>>,
+    /// Reported gone: nothing calls its functions any more
+    detached: AtomicBool,
+}
+
+impl Runtime {
+    fn new(name: &str, prefix: &str, vtable: CppRuntimeVTable) -> Self {
+        Runtime {
+            name: name.to_string(),
+            prefix: prefix.to_string(),
+            vtable,
+            ids: RwLock::new(None),
+            detached: AtomicBool::new(false),
+        }
+    }
+
+    fn is_detached(&self) -> bool {
+        self.detached.load(Ordering::Acquire)
+    }
+
+    fn ids(&self) -> Option<CppRuntimeIds> {
+        *self.ids.read().unwrap()
+    }
+}
+
+// Fast path: only the linked runtime
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+// Fast path: no runtime reported gone
+static ANY_GONE: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref LINKED_RUNTIME: Arc<Runtime> = Arc::new(Runtime::new(
+        LINKED,
+        "",
+        CppRuntimeVTable { send: cpp_actor_send, fast_send: cpp_actor_fast_send, exists: cpp_actor_exists },
+    ));
+    // Attached runtimes in attach order; a clone is held for each call
+    // into a runtime, so detach_and_wait() can tell when calls are done
+    static ref RUNTIMES: RwLock<Vec<Arc<Runtime>>> = RwLock::new(Vec::new());
+    // C++ actor name -> runtime that answered for it (LINKED included)
+    static ref OWNERS: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+    // Runtimes reported gone and not attached again: their prefix and the
+    // actor names they had answered for
+    static ref GONE: Mutex<HashMap<String, Gone>> = Mutex::new(HashMap::new());
+}
+
+struct Gone {
+    prefix: String,
+    actors: HashSet<String>,
+}
+
+/// Attach a C++ runtime. With a non-empty `prefix` it owns every actor
+/// name starting with it; without one it is asked after `LINKED` and the
+/// runtimes attached before it.
+pub fn attach(name: &str, prefix: &str, vtable: CppRuntimeVTable) -> Result<(), AttachError> {
+    let mut runtimes = RUNTIMES.write().unwrap();
+    if name == LINKED || runtimes.iter().any(|r| r.name == name) {
+        return Err(AttachError::NameTaken(name.to_string()));
+    }
+    if !prefix.is_empty() && runtimes.iter().any(|r| r.prefix == prefix) {
+        return Err(AttachError::PrefixTaken(prefix.to_string()));
+    }
+    runtimes.push(Arc::new(Runtime::new(name, prefix, vtable)));
+    ACTIVE.store(true, Ordering::Release);
+    drop(runtimes);
+    // Names that were missing may be found now
+    OWNERS.lock().unwrap().clear();
+    lookup_cache::invalidate_all();
+    if forget_gone(name) {
+        sys_topics::cpp_runtime(name, true);
+    }
+    Ok(())
+}
+
+/// Detach runtime `name`; its actors are no longer found. False if it is
+/// not attached.
+pub fn detach(name: &str) -> bool {
+    take(name).is_some()
+}
+
+/// Detach runtime `name` and wait up to `timeout` for calls already in it
+/// to return, e.g. before unloading its library. False if it is not
+/// attached or calls are still running.
+pub fn detach_and_wait(name: &str, timeout: Duration) -> bool {
+    match take(name) {
+        Some(runtime) => wait_for_calls(&runtime, timeout),
+        None => false,
+    }
+}
+
+/// Runtime `name` (`LINKED` included) is going away: stop every call into
+/// it and wait up to `timeout` for calls already in it to return. Sends to
+/// its actors fail with `RuntimeDetached` from now on and subscribers of
+/// `$sys/cpp_runtime` are told. Some(false) if calls are still running,
+/// None if no runtime has the name.
+pub fn runtime_detached(name: &str, timeout: Duration) -> Option<bool> {
+    let runtime = if name == LINKED {
+        LINKED_RUNTIME.clone()
+    } else {
+        RUNTIMES.read().unwrap().iter().find(|r| r.name == name).cloned()?
+    };
+    if runtime.detached.swap(true, Ordering::AcqRel) {
+        return Some(wait_for_calls(&runtime, timeout));
+    }
+    let actors: HashSet<String> = {
+        let owners = OWNERS.lock().unwrap();
+        owners.iter().filter(|(_, owner)| *owner == name).map(|(actor, _)| actor.clone()).collect()
+    };
+    GONE.lock().unwrap().insert(name.to_string(), Gone { prefix: runtime.prefix.clone(), actors });
+    ANY_GONE.store(true, Ordering::Release);
+    if name == LINKED {
+        actor_ids::forget_runtime(LINKED);
+    } else {
+        take(name);
+    }
+    lookup_cache::invalidate_all();
+    let drained = wait_for_calls(&runtime, timeout);
+    eprintln!("[C++ Runtime] {} detached; sends to its actors are dead letters", name);
+    sys_topics::cpp_runtime(name, false);
+    Some(drained)
+}
+
+// True once no call holds `runtime`, false after `timeout`
+fn wait_for_calls(runtime: &Arc<Runtime>, timeout: Duration) -> bool {
+    // LINKED_RUNTIME itself holds one more
+    let idle = if runtime.name == LINKED { 2 } else { 1 };
+    let deadline = Instant::now() + timeout;
+    while Arc::strong_count(runtime) > idle {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    true
+}
+
+// Runtime `name` is back; true if it had been reported gone
+fn forget_gone(name: &str) -> bool {
+    let mut gone = GONE.lock().unwrap();
+    let was = gone.remove(name).is_some();
+    ANY_GONE.store(!gone.is_empty(), Ordering::Release);
+    was
+}
+
+/// True if runtime `name` was reported gone and not attached again
+pub fn is_detached(name: &str) -> bool {
+    ANY_GONE.load(Ordering::Acquire) && GONE.lock().unwrap().contains_key(name)
+}
+
+/// The detached runtime that owned C++ actor `name`, if no live runtime
+/// answers for it
+pub(crate) fn detached_owner(name: &str) -> Option<String> {
+    if !ANY_GONE.load(Ordering::Acquire) {
+        return None;
+    }
+    let runtime = route(name);
+    if runtime.is_detached() {
+        return Some(runtime.name.clone());
+    }
+    // A live runtime by prefix, or one that has answered for the name
+    if runtime.name != LINKED || OWNERS.lock().unwrap().contains_key(name) {
+        return None;
+    }
+    let gone = GONE.lock().unwrap();
+    gone.iter()
+        .find(|(_, g)| g.actors.contains(name) || (!g.prefix.is_empty() && name.starts_with(&g.prefix)))
+        .map(|(runtime, _)| runtime.clone())
+}
+
+/// Detach every runtime and drop the linked runtime's functions, so
+/// nothing calls into C++ any more (see teardown)
+pub(crate) fn detach_all() {
+    for name in attached() {
+        take(&name);
+    }
+    #[cfg(any(windows, feature = "standalone"))]
+    {
+        *LINKED_VTABLE.write().unwrap() = None;
+    }
+    OWNERS.lock().unwrap().clear();
+    GONE.lock().unwrap().clear();
+    ANY_GONE.store(false, Ordering::Release);
+    LINKED_RUNTIME.detached.store(false, Ordering::Release);
+    lookup_cache::invalidate_all();
+}
+
+fn take(name: &str) -> Option<Arc<Runtime>> {
+    let mut runtimes = RUNTIMES.write().unwrap();
+    let index = runtimes.iter().position(|r| r.name == name)?;
+    let runtime = runtimes.remove(index);
+    ACTIVE.store(!runtimes.is_empty(), Ordering::Release);
+    drop(runtimes);
+    OWNERS.lock().unwrap().retain(|_, owner| owner != name);
+    actor_ids::forget_runtime(name);
+    lookup_cache::invalidate_all();
+    Some(runtime)
+}
+
+fn runtime_named(name: &str) -> Option<Arc<Runtime>> {
+    if name == LINKED {
+        return Some(LINKED_RUNTIME.clone());
+    }
+    RUNTIMES.read().unwrap().iter().find(|r| r.name == name).cloned()
+}
+
+/// Send to the actors of runtime `name` (`LINKED` included) by id with
+/// `ids` (see actor_ids). False if it is not attached.
+pub fn set_ids(name: &str, ids: CppRuntimeIds) -> bool {
+    let runtime = match runtime_named(name) {
+        Some(r) => r,
+        None => return false,
+    };
+    *runtime.ids.write().unwrap() = Some(ids);
+    // Ids from functions set before mean nothing to these
+    actor_ids::forget_runtime(name);
+    true
+}
+
+/// Id functions of runtime `name`, if it registered any
+pub(crate) fn ids_of(name: &str) -> Option<CppRuntimeIds> {
+    runtime_named(name).filter(|r| !r.is_detached())?.ids()
+}
+
+/// Attached runtimes in attach order, `LINKED` not included
+pub fn attached() -> Vec<String> {
+    RUNTIMES.read().unwrap().iter().map(|r| r.name.clone()).collect()
+}
+
+/// Runtime that owns `name`, if any
+pub fn owner(name: &str) -> Option<String> {
+    find(name).map(|r| r.name.clone())
+}
+
+fn find(name: &str) -> Option<Arc<Runtime>> {
+    let c_name = CString::new(name).ok()?;
+    // Checked on a clone of the runtime, see runtime_detached()
+    let knows = |r: &Runtime| !r.is_detached() && unsafe { (r.vtable.exists)(c_name.as_ptr()) != 0 };
+    if !ACTIVE.load(Ordering::Acquire) {
+        if !knows(&LINKED_RUNTIME) {
+            return None;
+        }
+        negotiate_id(&LINKED_RUNTIME, name, &c_name);
+        return Some(LINKED_RUNTIME.clone());
+    }
+
+    let candidates: Vec<Arc<Runtime>> = {
+        let runtimes = RUNTIMES.read().unwrap();
+        match runtimes.iter().find(|r| !r.prefix.is_empty() && name.starts_with(&r.prefix)) {
+            Some(r) => vec![r.clone()],
+            None => std::iter::once(LINKED_RUNTIME.clone())
+                .chain(runtimes.iter().filter(|r| r.prefix.is_empty()).cloned())
+                .collect(),
+        }
+    };
+    let found = candidates.into_iter().find(|r| knows(r))?;
+    if found.prefix.is_empty() {
+        OWNERS.lock().unwrap().insert(name.to_string(), found.name.clone());
+    }
+    negotiate_id(&found, name, &c_name);
+    Some(found)
+}
+
+// Agree on an id for `name` with the runtime that knows it
+fn negotiate_id(runtime: &Runtime, name: &str, name_c: &CStr) {
+    if let Some(ids) = runtime.ids() {
+        actor_ids::negotiate_cpp(&runtime.name, ids, name, name_c);
+    }
+}
+
+/// Name of the runtime sends to C++ actor `name` go to
+pub fn runtime_of(name: &str) -> String {
+    route(name).name.clone()
+}
+
+/// Runtime to reach C++ actor `name`; `LINKED` if none has it, whose send
+/// then reports not found
+fn route(name: &str) -> Arc<Runtime> {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return LINKED_RUNTIME.clone();
+    }
+    {
+        let runtimes = RUNTIMES.read().unwrap();
+        if let Some(r) = runtimes.iter().find(|r| !r.prefix.is_empty() && name.starts_with(&r.prefix)) {
+            return r.clone();
+        }
+        let owners = OWNERS.lock().unwrap();
+        match owners.get(name).map(String::as_str) {
+            Some(LINKED) => return LINKED_RUNTIME.clone(),
+            Some(owner) => {
+                if let Some(r) = runtimes.iter().find(|r| r.name == owner) {
+                    return r.clone();
+                }
+            }
+            None => {}
+        }
+    }
+    find(name).unwrap_or_else(|| LINKED_RUNTIME.clone())
+}
+
+/// True if C++ actor `name` exists in any runtime
+pub fn exists(name: &str) -> bool {
+    find(name).is_some()
+}
+
+/// cpp_actor_send() on the runtime that owns `target`
+///
+/// # Safety
+/// `target_c` must be `target` as a C string; `sender` null or a C string;
+/// `data` a valid C struct for `msg_type`
+pub unsafe fn send(target: &str, target_c: &CStr, sender: *const c_char, msg_type: c_int, data: *const c_void) -> c_int {
+    let runtime = route(target);
+    if runtime.is_detached() {
+        return RUNTIME_DETACHED;
+    }
+    if let Some(ids) = runtime.ids() {
+        let by_id = actor_ids::send_cpp(&runtime.name, ids, target, target_c, |id| (ids.send_id)(id, sender, msg_type, data));
+        if let Some(rc) = by_id {
+            return rc;
+        }
+    }
+    (runtime.vtable.send)(target_c.as_ptr(), sender, msg_type, data)
+}
+
+/// cpp_actor_fast_send() on the runtime that owns `target`
+///
+/// # Safety
+/// As for `send`
+pub unsafe fn fast_send(target: &str, target_c: &CStr, sender: *const c_char, msg_type: c_int, data: *const c_void) -> c_int {
+    let runtime = route(target);
+    if runtime.is_detached() {
+        return RUNTIME_DETACHED;
+    }
+    if let Some(ids) = runtime.ids() {
+        let by_id =
+            actor_ids::send_cpp(&runtime.name, ids, target, target_c, |id| (ids.fast_send_id)(id, sender, msg_type, data));
+        if let Some(rc) = by_id {
+            return rc;
+        }
+    }
+    (runtime.vtable.fast_send)(target_c.as_ptr(), sender, msg_type, data)
+}
+
+/// Attach a C++ runtime as `name`. `prefix` (may be null or empty) routes
+/// every actor name starting with it to this runtime. Returns 0,
+/// InvalidArgument on a bad name or prefix, or NameTaken if either is in
+/// use.
+#[export_name = c_symbol!("interop_attach_cpp_runtime")]
+pub extern "C" fn interop_attach_cpp_runtime(name: *const c_char, prefix: *const c_char, vtable: CppRuntimeVTable) -> c_int {
+    let name = match c_str(name) {
+        Some(n) if !n.is_empty() => n,
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    if !prefix.is_null() && c_str(prefix).is_none() {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    let rc = match attach(name, c_str(prefix).unwrap_or(""), vtable) {
+        Ok(()) => InteropErrorCode::Ok,
+        Err(e) => {
+            eprintln!("[C++ Runtime] {}", e);
+            InteropErrorCode::NameTaken
+        }
+    };
+    rc as c_int
+}
+
+/// Detach C++ runtime `name`. Returns 0, or NotFound if it is not
+/// attached.
+#[export_name = c_symbol!("interop_detach_cpp_runtime")]
+pub extern "C" fn interop_detach_cpp_runtime(name: *const c_char) -> c_int {
+    match c_str(name) {
+        Some(name) if detach(name) => InteropErrorCode::Ok as c_int,
+        _ => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// C++ runtime `name` (NULL: the linked one) is going away: no send calls
+/// into it from now on (see runtime_detached). Waits up to `timeout_ms`
+/// for calls already in it. Returns 0, 1 if calls are still running, or
+/// NotFound if no runtime has the name.
+#[export_name = c_symbol!("interop_cpp_runtime_detached")]
+pub extern "C" fn interop_cpp_runtime_detached(name: *const c_char, timeout_ms: u32) -> c_int {
+    let name = if name.is_null() { Some(LINKED) } else { c_str(name) };
+    match name.and_then(|n| runtime_detached(n, Duration::from_millis(timeout_ms as u64))) {
+        Some(true) => 0,
+        Some(false) => 1,
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Hand over the bridge functions of the C++ code linked into the process.
+/// Only Windows and `standalone` builds need this (see the module docs);
+/// elsewhere they are resolved at link time and this only undoes a
+/// detach (see runtime_detached). Returns 0.
+#[export_name = c_symbol!("interop_set_linked_cpp_runtime")]
+pub extern "C" fn interop_set_linked_cpp_runtime(vtable: CppRuntimeVTable) -> c_int {
+    #[cfg(any(windows, feature = "standalone"))]
+    {
+        *LINKED_VTABLE.write().unwrap() = Some(vtable);
+        // Names looked up before were answered by the stubs
+        OWNERS.lock().unwrap().clear();
+        lookup_cache::invalidate_all();
+    }
+    #[cfg(not(any(windows, feature = "standalone")))]
+    let _ = vtable;
+    // Handed over again after it was reported gone
+    LINKED_RUNTIME.detached.store(false, Ordering::Release);
+    if forget_gone(LINKED) {
+        lookup_cache::invalidate_all();
+        sys_topics::cpp_runtime(LINKED, true);
+    }
+    0
+}
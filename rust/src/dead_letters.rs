@@ -0,0 +1,278 @@
+//! Records of failed deliveries
+//!
+//! The health summary counts dead letters; this keeps the last
+//! `capacity()` of them (default `DEFAULT_CAPACITY`) with what is needed to
+//! tell where each was lost:
+//!
+//! ```json
+//! {"time_ms":1760000000123,"origin":"cpp","route":"broker","stage":"lookup",
+//!  "sender":"cpp_feed","target":"rust_monitor","msg_id":1000,"message":"Ping","code":-1}
+//! ```
+//!
+//! - `origin`: the side the send started on: `cpp` for sends through the
+//!   bridge (`rust_actor_send`, `rust_actor_post`, `rust_actor_ask`),
+//!   `rust` for sends from Rust code.
+//! - `route`: how the target was picked: `direct`, `broker` (a publish
+//!   fanned out to subscribers, see broker) or `router` (see router,
+//!   hash_router).
+//! - `stage`: `lookup` (no such target), `conversion` (unknown message ID,
+//!   wrong type, broken field rule), `transport` (C++ refused or failed
+//!   the send after retries), `mailbox_full` (a post over its queue
+//!   limit, a full outbound queue set to reject), `name` (a target or
+//!   sender name that cannot cross to C, see names), `panic` (delivered,
+//!   but the handler panicked on every attempt and the message was
+//!   quarantined, see quarantine) or `detached` (the target's C++ runtime
+//!   went away, see cpp_runtime).
+//! - `code`: the return code the sender got; `message` is null for an
+//!   unknown ID.
+//! - `context`: only on `panic` records of actors that give one, their
+//!   state dump (see panic_context).
+//!
+//! Origin and route are captured when the message is sent, so a send
+//! queued on another thread (a shared thread, an outbound queue) keeps
+//! them. C++ reads the records with `interop_dead_letters_json(n)`
+//! (`interop/dead_letters.h`).
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::fmt::Write;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use crate::clock;
+use crate::codec::JsonValue;
+use crate::envelope::Origin;
+use crate::schema;
+use crate::tenant;
+
+/// Records kept by default
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// How the target of a send was picked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Route {
+    Direct,
+    Broker,
+    Router,
+}
+
+impl Route {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Route::Direct => "direct",
+            Route::Broker => "broker",
+            Route::Router => "router",
+        }
+    }
+}
+
+/// Where delivery failed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Lookup,
+    Conversion,
+    Transport,
+    MailboxFull,
+    Name,
+    Panic,
+    /// The target's C++ runtime was detached (see cpp_runtime)
+    Detached,
+}
+
+impl Stage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::Lookup => "lookup",
+            Stage::Conversion => "conversion",
+            Stage::Transport => "transport",
+            Stage::MailboxFull => "mailbox_full",
+            Stage::Name => "name",
+            Stage::Panic => "panic",
+            Stage::Detached => "detached",
+        }
+    }
+}
+
+fn origin_str(origin: Origin) -> &'static str {
+    match origin {
+        Origin::Cpp => "cpp",
+        Origin::Rust => "rust",
+    }
+}
+
+/// Origin and route of the send running on this thread
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SendContext {
+    pub origin: Origin,
+    pub route: Route,
+}
+
+impl Default for SendContext {
+    fn default() -> Self {
+        SendContext { origin: Origin::Rust, route: Route::Direct }
+    }
+}
+
+/// One failed delivery
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeadLetter {
+    /// Wall clock, ms since the epoch (see clock)
+    pub time_ms: u64,
+    pub origin: Origin,
+    pub route: Route,
+    pub stage: Stage,
+    /// Empty if the sender gave no name
+    pub sender: String,
+    pub target: String,
+    pub msg_id: i32,
+    pub code: i32,
+    /// State dump of the target actor, for `Panic`
+    pub context: Option<String>,
+}
+
+impl DeadLetter {
+    pub fn to_json(&self) -> String {
+        let mut out = String::with_capacity(192);
+        let _ = write!(
+            out,
+            "{{\"time_ms\":{},\"origin\":\"{}\",\"route\":\"{}\",\"stage\":\"{}\",\"sender\":",
+            self.time_ms,
+            origin_str(self.origin),
+            self.route.as_str(),
+            self.stage.as_str()
+        );
+        self.sender.write_json(&mut out);
+        out.push_str(",\"target\":");
+        self.target.write_json(&mut out);
+        let _ = write!(out, ",\"msg_id\":{},\"message\":", self.msg_id);
+        match schema::by_id(self.msg_id) {
+            Some(s) => s.name.write_json(&mut out),
+            None => out.push_str("null"),
+        }
+        let _ = write!(out, ",\"code\":{}", self.code);
+        if let Some(context) = &self.context {
+            let _ = write!(out, ",\"context\":{}", context);
+        }
+        out.push('}');
+        out
+    }
+}
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_CAPACITY);
+static RECORDS: Mutex<VecDeque<DeadLetter>> = Mutex::new(VecDeque::new());
+
+thread_local! {
+    static CONTEXT: Cell<SendContext> = Cell::new(SendContext::default());
+}
+
+/// Context of the send running on this thread; Rust and direct outside one
+pub fn current() -> SendContext {
+    CONTEXT.with(Cell::get)
+}
+
+/// Run `f` with `ctx` as this thread's send context
+pub(crate) fn with_context<R>(ctx: SendContext, f: impl FnOnce() -> R) -> R {
+    let saved = CONTEXT.with(|c| c.replace(ctx));
+    let result = f();
+    CONTEXT.with(|c| c.set(saved));
+    result
+}
+
+/// Run `f`, a send that picked its target by `route`
+pub(crate) fn via<R>(route: Route, f: impl FnOnce() -> R) -> R {
+    with_context(SendContext { route, ..current() }, f)
+}
+
+/// Run `f`, a send that came through the bridge from C++
+pub(crate) fn from_cpp<R>(f: impl FnOnce() -> R) -> R {
+    with_context(SendContext { origin: Origin::Cpp, ..current() }, f)
+}
+
+/// Record a failed delivery in this thread's send context
+pub(crate) fn record(stage: Stage, sender: &str, target: &str, msg_id: i32, code: i32) {
+    record_with(stage, sender, target, msg_id, code, None);
+}
+
+/// Record a message quarantined by `target`, with its state dump
+pub(crate) fn record_panic(sender: &str, target: &str, msg_id: i32, context: Option<&str>) {
+    record_with(Stage::Panic, sender, target, msg_id, 0, context);
+}
+
+fn record_with(stage: Stage, sender: &str, target: &str, msg_id: i32, code: i32, context: Option<&str>) {
+    let capacity = capacity();
+    if capacity == 0 {
+        return;
+    }
+    let ctx = current();
+    let letter = DeadLetter {
+        time_ms: clock::wall().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+        origin: ctx.origin,
+        route: ctx.route,
+        stage,
+        sender: sender.to_string(),
+        target: target.to_string(),
+        msg_id,
+        code,
+        context: context.map(str::to_string),
+    };
+    let mut records = RECORDS.lock().unwrap();
+    while records.len() >= capacity {
+        records.pop_front();
+    }
+    records.push_back(letter);
+}
+
+/// Keep the last `capacity` records; 0 keeps none
+pub fn set_capacity(capacity: usize) {
+    CAPACITY.store(capacity, Ordering::Relaxed);
+    let mut records = RECORDS.lock().unwrap();
+    while records.len() > capacity {
+        records.pop_front();
+    }
+}
+
+pub fn capacity() -> usize {
+    CAPACITY.load(Ordering::Relaxed)
+}
+
+/// The last `n` records, oldest first
+pub fn recent(n: usize) -> Vec<DeadLetter> {
+    let records = RECORDS.lock().unwrap();
+    records.iter().skip(records.len().saturating_sub(n)).cloned().collect()
+}
+
+pub fn clear() {
+    RECORDS.lock().unwrap().clear();
+}
+
+/// The last `n` records as a JSON array, oldest first. Within a tenant view,
+/// only those from or to its actors (see tenant).
+pub fn recent_json(n: usize) -> String {
+    let shown: Vec<DeadLetter> = match tenant::in_view() {
+        Some(_) => recent(usize::MAX)
+            .into_iter()
+            .filter(|r| tenant::visible(&r.sender) || tenant::visible(&r.target))
+            .collect(),
+        None => recent(n),
+    };
+    let parts: Vec<String> = shown[shown.len().saturating_sub(n)..].iter().map(DeadLetter::to_json).collect();
+    format!("[{}]", parts.join(","))
+}
+
+/// The last `max` dead-letter records (all kept if 0) as a JSON array,
+/// oldest first; free with interop_free_json()
+#[export_name = c_symbol!("interop_dead_letters_json")]
+pub extern "C" fn interop_dead_letters_json(max: u32) -> *mut c_char {
+    let n = if max == 0 { usize::MAX } else { max as usize };
+    CString::new(recent_json(n)).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Keep the last `capacity` records (default DEFAULT_CAPACITY); 0 keeps
+/// none
+#[export_name = c_symbol!("interop_set_dead_letter_capacity")]
+pub extern "C" fn interop_set_dead_letter_capacity(capacity: u32) {
+    set_capacity(capacity as usize);
+}
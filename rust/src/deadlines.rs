@@ -0,0 +1,160 @@
+//! Per-actor processing deadlines for soft real-time handlers
+//!
+//! The C++ engine budgets latency for what it hands to Rust: a
+//! MarketUpdate must be handled within, say, 500µs. A deadline set for an
+//! actor and message type is checked against the handler's run time after
+//! every dispatch; a handler that overruns it is counted and reported on
+//! `$sys/deadlines` (see sys_topics), with the actor as subject and the
+//! handler time in nanoseconds as value:
+//!
+//! ```ignore
+//! deadlines::set("rust_strategy", Some(MSG_MARKETUPDATE), Duration::from_micros(500));
+//! deadlines::set("rust_strategy", None, Duration::from_millis(5));   // every other type
+//! deadlines::missed_count("rust_strategy");
+//! ```
+//!
+//! Registrations declare them too:
+//! `#[interop_actor("rust_strategy", deadline_us(MarketUpdate = 500))]`
+//! sets them for every instance C++ creates (see registry).
+//!
+//! Only the handler is timed, not the time a message waited in the
+//! mailbox (see ttl for that), on the interop clock like dispatch stats.
+//! Nothing is interrupted: an overrun handler still completes. C++ uses
+//! `rust_actor_set_deadline()` and reads the counters with
+//! `rust_actor_deadline_misses()`.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::sys_topics;
+
+#[derive(Default)]
+struct ActorDeadlines {
+    default: Option<Duration>,
+    by_type: HashMap<i32, Duration>,
+}
+
+/// Overruns of one actor's deadlines
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Misses {
+    pub count: u64,
+    /// Longest handler time that overran
+    pub worst: Duration,
+    /// Message type of the last overrun
+    pub last_msg_id: i32,
+}
+
+// Fast path: no deadline anywhere
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static TOTAL_MISSED: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref DEADLINES: RwLock<HashMap<String, ActorDeadlines>> = RwLock::new(HashMap::new());
+    static ref MISSES: Mutex<HashMap<String, Misses>> = Mutex::new(HashMap::new());
+}
+
+/// Report `actor` handling a message of type `msg_id`, or of every type
+/// without a deadline of its own for None, in more than `deadline`. A zero
+/// `deadline` removes it.
+pub fn set(actor: &str, msg_id: Option<i32>, deadline: Duration) {
+    let mut deadlines = DEADLINES.write().unwrap();
+    let entry = deadlines.entry(actor.to_string()).or_default();
+    let deadline = (!deadline.is_zero()).then_some(deadline);
+    match (msg_id, deadline) {
+        (Some(id), Some(d)) => {
+            entry.by_type.insert(id, d);
+        }
+        (Some(id), None) => {
+            entry.by_type.remove(&id);
+        }
+        (None, d) => entry.default = d,
+    }
+    if entry.default.is_none() && entry.by_type.is_empty() {
+        deadlines.remove(actor);
+    }
+    ACTIVE.store(!deadlines.is_empty(), Ordering::Release);
+}
+
+/// Remove every deadline of `actor`
+pub fn clear(actor: &str) {
+    let mut deadlines = DEADLINES.write().unwrap();
+    deadlines.remove(actor);
+    ACTIVE.store(!deadlines.is_empty(), Ordering::Release);
+}
+
+/// Deadline of `actor` for messages of type `msg_id`
+pub fn deadline_of(actor: &str, msg_id: i32) -> Option<Duration> {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return None;
+    }
+    let deadlines = DEADLINES.read().unwrap();
+    let entry = deadlines.get(actor)?;
+    entry.by_type.get(&msg_id).copied().or(entry.default)
+}
+
+/// Overruns of `actor`'s deadlines so far
+pub fn misses(actor: &str) -> Misses {
+    MISSES.lock().unwrap().get(actor).copied().unwrap_or_default()
+}
+
+/// Handlers of `actor` that overran their deadline
+pub fn missed_count(actor: &str) -> u64 {
+    misses(actor).count
+}
+
+/// Handlers of any actor that overran their deadline
+pub fn total_missed() -> u64 {
+    TOTAL_MISSED.load(Ordering::Relaxed)
+}
+
+/// Called after `actor` handled a message of type `msg_id` in `elapsed`:
+/// counts and publishes an overrun
+pub(crate) fn check(actor: &str, msg_id: i32, elapsed: Duration) {
+    match deadline_of(actor, msg_id) {
+        Some(deadline) if elapsed > deadline => {}
+        _ => return,
+    }
+    {
+        let mut misses = MISSES.lock().unwrap();
+        let entry = misses.entry(actor.to_string()).or_default();
+        entry.count += 1;
+        entry.worst = entry.worst.max(elapsed);
+        entry.last_msg_id = msg_id;
+    }
+    TOTAL_MISSED.fetch_add(1, Ordering::Relaxed);
+    sys_topics::deadline_missed(actor, elapsed);
+}
+
+/// Set the deadline of `actor_name` handling messages of type `msg_type`
+/// (0: every type without its own). `deadline_us` 0 removes it. Returns 0,
+/// or InvalidArgument on a bad name.
+#[export_name = c_symbol!("rust_actor_set_deadline")]
+pub extern "C" fn rust_actor_set_deadline(actor_name: *const c_char, msg_type: i32, deadline_us: u32) -> c_int {
+    let name = match c_str(actor_name) {
+        Some(n) => n,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let msg_id = if msg_type == 0 { None } else { Some(msg_type) };
+    set(name, msg_id, Duration::from_micros(deadline_us as u64));
+    InteropErrorCode::Ok as c_int
+}
+
+/// Handlers of `actor_name` that overran their deadline; with
+/// `worst_ns` non-null, also the longest of them in nanoseconds.
+/// Null `actor_name` counts every actor (worst_ns is then left alone).
+#[export_name = c_symbol!("rust_actor_deadline_misses")]
+pub extern "C" fn rust_actor_deadline_misses(actor_name: *const c_char, worst_ns: *mut u64) -> u64 {
+    if actor_name.is_null() {
+        return total_missed();
+    }
+    let m = c_str(actor_name).map(misses).unwrap_or_default();
+    if !worst_ns.is_null() {
+        unsafe { *worst_ns = m.worst.as_nanos().min(u64::MAX as u128) as u64 };
+    }
+    m.count
+}
@@ -0,0 +1,224 @@
+//! Fixed-point decimal fields (`interop_decimal`)
+//!
+//! Prices carried as `double` pick up binary rounding (0.1 + 0.2 !=
+//! 0.3), which fixed-income books cannot tolerate. A field declared as
+//! `interop_decimal` carries `units * 10^-scale` exactly instead:
+//!
+//! ```ignore
+//! let px: Decimal = "99.875".parse()?;          // units 99875, scale 3
+//! let fee = Decimal::new(5, 4);                  // 0.0005
+//! let net = px.checked_sub(fee).ok_or(Overflow)?; // 99.8745
+//! ```
+//!
+//! The struct is `#[repr(C)]` and matches the C typedef, so generated C
+//! structs hold it directly. C++ gets `interop::Decimal` in
+//! InteropMessages.hpp with the same operations.
+//!
+//! Arithmetic is checked: results that do not fit an `i64` mantissa at a
+//! scale up to `MAX_SCALE` return None rather than wrapping or rounding
+//! silently. `==` compares mantissa and scale, so 1.50 != 1.5; use
+//! `cmp_value` to compare amounts.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// `units * 10^-scale`; matches the C `interop_decimal`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Decimal {
+    pub units: i64,
+    /// Digits after the decimal point, 0..=MAX_SCALE
+    pub scale: i32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseDecimalError {
+    Empty,
+    InvalidDigit,
+    /// More than MAX_SCALE fraction digits, or too many digits for i64
+    OutOfRange,
+}
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseDecimalError::Empty => write!(f, "empty decimal"),
+            ParseDecimalError::InvalidDigit => write!(f, "invalid digit in decimal"),
+            ParseDecimalError::OutOfRange => write!(f, "decimal out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+fn pow10(exp: i32) -> i128 {
+    10i128.pow(exp as u32)
+}
+
+impl Decimal {
+    /// Largest scale; 10^18 is the largest power of ten in an i64
+    pub const MAX_SCALE: i32 = 18;
+
+    pub const fn new(units: i64, scale: i32) -> Self {
+        Decimal { units, scale }
+    }
+
+    pub const fn from_int(value: i64) -> Self {
+        Decimal { units: value, scale: 0 }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        (0..=Self::MAX_SCALE).contains(&self.scale)
+    }
+
+    /// From a mantissa at `scale`, None if it does not fit
+    fn from_i128(units: i128, scale: i32) -> Option<Self> {
+        if !(0..=Self::MAX_SCALE).contains(&scale) {
+            return None;
+        }
+        Some(Decimal { units: i64::try_from(units).ok()?, scale })
+    }
+
+    /// Round `value` half away from zero to `scale` digits. None for NaN,
+    /// infinity, a bad scale or a value too large for the mantissa.
+    pub fn from_f64(value: f64, scale: i32) -> Option<Self> {
+        if !value.is_finite() || !(0..=Self::MAX_SCALE).contains(&scale) {
+            return None;
+        }
+        let scaled = (value * pow10(scale) as f64).round();
+        if scaled < i64::MIN as f64 || scaled >= i64::MAX as f64 {
+            return None;
+        }
+        Some(Decimal { units: scaled as i64, scale })
+    }
+
+    /// Nearest f64, for display or analytics; not exact
+    pub fn to_f64(&self) -> f64 {
+        self.units as f64 / 10f64.powi(self.scale)
+    }
+
+    /// Same amount at `scale`; fewer digits round half away from zero.
+    /// None if the result does not fit.
+    pub fn rescale(&self, scale: i32) -> Option<Self> {
+        if !self.is_valid() || !(0..=Self::MAX_SCALE).contains(&scale) {
+            return None;
+        }
+        let units = self.units as i128;
+        let units = match scale.cmp(&self.scale) {
+            Ordering::Equal => units,
+            Ordering::Greater => units * pow10(scale - self.scale),
+            Ordering::Less => {
+                let div = pow10(self.scale - scale);
+                let (q, r) = (units / div, units % div);
+                if r.abs() * 2 >= div { q + units.signum() } else { q }
+            }
+        };
+        Self::from_i128(units, scale)
+    }
+
+    /// Both mantissas at the larger of the two scales
+    fn aligned(&self, other: &Decimal) -> Option<(i128, i128, i32)> {
+        if !self.is_valid() || !other.is_valid() {
+            return None;
+        }
+        let scale = self.scale.max(other.scale);
+        let a = self.units as i128 * pow10(scale - self.scale);
+        let b = other.units as i128 * pow10(scale - other.scale);
+        Some((a, b, scale))
+    }
+
+    /// Exact sum at the larger scale
+    pub fn checked_add(&self, other: Decimal) -> Option<Self> {
+        let (a, b, scale) = self.aligned(&other)?;
+        Self::from_i128(a + b, scale)
+    }
+
+    pub fn checked_sub(&self, other: Decimal) -> Option<Self> {
+        let (a, b, scale) = self.aligned(&other)?;
+        Self::from_i128(a - b, scale)
+    }
+
+    /// Exact product; its scale is the sum of both scales
+    pub fn checked_mul(&self, other: Decimal) -> Option<Self> {
+        if !self.is_valid() || !other.is_valid() {
+            return None;
+        }
+        Self::from_i128(self.units as i128 * other.units as i128, self.scale + other.scale)
+    }
+
+    /// Product rounded to `scale`, e.g. price * quantity in cents
+    pub fn mul_rounded(&self, other: Decimal, scale: i32) -> Option<Self> {
+        if !self.is_valid() || !other.is_valid() || !(0..=Self::MAX_SCALE).contains(&scale) {
+            return None;
+        }
+        let product = self.units as i128 * other.units as i128;
+        let from = self.scale + other.scale;
+        let units = if scale >= from {
+            product.checked_mul(pow10(scale - from))?
+        } else {
+            let div = pow10(from - scale);
+            let (q, r) = (product / div, product % div);
+            if r.abs() * 2 >= div { q + product.signum() } else { q }
+        };
+        Self::from_i128(units, scale)
+    }
+
+    /// Compare amounts regardless of scale; None if either is invalid
+    pub fn cmp_value(&self, other: &Decimal) -> Option<Ordering> {
+        let (a, b, _) = self.aligned(other)?;
+        Some(a.cmp(&b))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.scale == 0 {
+            return write!(f, "{}", self.units);
+        }
+        if self.scale < 0 {
+            return write!(f, "{}e{}", self.units, -self.scale);  // not valid, shown as is
+        }
+        let digits = self.units.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        let sign = if self.units < 0 { "-" } else { "" };
+        write!(f, "{}{}.{}", sign, int, frac)
+    }
+}
+
+/// "-12.340" -> units -12340, scale 3; the scale is the number of digits
+/// written after the point
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, body) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (int, frac) = body.split_once('.').unwrap_or((body, ""));
+        if int.is_empty() && frac.is_empty() {
+            return Err(ParseDecimalError::Empty);
+        }
+        if !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit()) {
+            return Err(ParseDecimalError::InvalidDigit);
+        }
+        if frac.len() > Self::MAX_SCALE as usize {
+            return Err(ParseDecimalError::OutOfRange);
+        }
+        let mut units: i128 = 0;
+        for b in int.bytes().chain(frac.bytes()) {
+            units = units * 10 + (b - b'0') as i128;
+            if units > i64::MAX as i128 + 1 {
+                return Err(ParseDecimalError::OutOfRange);
+            }
+        }
+        if negative {
+            units = -units;
+        }
+        Decimal::from_i128(units, frac.len() as i32).ok_or(ParseDecimalError::OutOfRange)
+    }
+}
@@ -0,0 +1,176 @@
+//! Duplicate suppression on the receive path
+//!
+//! A transport that retries until acknowledged, or a C++ sender with a
+//! retry bug, can deliver the same message twice. For actors where that
+//! matters (order events) a dedup window drops repeats before they reach
+//! the mailbox:
+//!
+//! ```ignore
+//! dedup::enable("rust_oms", DedupConfig::fingerprint(Duration::from_secs(5), 10_000));
+//! dedup::enable("rust_data", DedupConfig::by_key(Duration::from_secs(60), 100_000, |m| {
+//!     m.as_any().downcast_ref::<DataRequest>().map(|r| r.request_id as u64)
+//! }));
+//! ```
+//!
+//! - `fingerprint`: a message equal to one received within the window
+//!   (same type, same field values) is a duplicate.
+//! - `by_key`: messages with the same correlation key within the window
+//!   are duplicates; messages the key function returns None for pass.
+//!
+//! The window keeps at most `max_entries` keys, oldest dropped first.
+//! Checked by `rust_actor_send` / `rust_actor_fast_send` before sequence
+//! stamping, and by `rust_actor_post`. A duplicate is dropped and the send
+//! reports success, so the sender does not retry it again. C++ enables the
+//! fingerprint mode with `rust_actor_set_dedup()`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actors::Message;
+
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::message_to_portable;
+use crate::names::c_str;
+
+/// Correlation key of a message, None to never treat it as a duplicate
+pub type KeyFn = Arc<dyn Fn(&dyn Message) -> Option<u64> + Send + Sync>;
+
+#[derive(Clone)]
+pub enum DedupKey {
+    /// Hash of the message type and field values
+    Fingerprint,
+    Correlation(KeyFn),
+}
+
+#[derive(Clone)]
+pub struct DedupConfig {
+    pub window: Duration,
+    pub max_entries: usize,
+    pub key: DedupKey,
+}
+
+impl DedupConfig {
+    pub fn fingerprint(window: Duration, max_entries: usize) -> Self {
+        DedupConfig { window, max_entries: max_entries.max(1), key: DedupKey::Fingerprint }
+    }
+
+    pub fn by_key<F>(window: Duration, max_entries: usize, key: F) -> Self
+    where
+        F: Fn(&dyn Message) -> Option<u64> + Send + Sync + 'static,
+    {
+        DedupConfig { window, max_entries: max_entries.max(1), key: DedupKey::Correlation(Arc::new(key)) }
+    }
+
+    fn key_of(&self, msg: &dyn Message) -> Option<u64> {
+        match &self.key {
+            DedupKey::Fingerprint => {
+                let mut h = DefaultHasher::new();
+                msg.message_id().hash(&mut h);
+                message_to_portable(msg)?.hash(&mut h);
+                Some(h.finish())
+            }
+            DedupKey::Correlation(f) => f(msg),
+        }
+    }
+}
+
+/// Keys seen within the window, oldest first
+struct Window {
+    config: DedupConfig,
+    order: VecDeque<(Instant, u64)>,
+    seen: HashSet<u64>,
+}
+
+impl Window {
+    fn new(config: DedupConfig) -> Self {
+        Window { config, order: VecDeque::new(), seen: HashSet::new() }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some(&(at, key)) = self.order.front() {
+            if now.duration_since(at) < self.config.window && self.order.len() < self.config.max_entries {
+                break;
+            }
+            self.order.pop_front();
+            self.seen.remove(&key);
+        }
+    }
+
+    /// Record `key`; true if it was already in the window
+    fn check(&mut self, key: u64) -> bool {
+        let now = clock::now();
+        self.expire(now);
+        if !self.seen.insert(key) {
+            return true;
+        }
+        self.order.push_back((now, key));
+        false
+    }
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static DUPLICATES: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref WINDOWS: Mutex<HashMap<String, Window>> = Mutex::new(HashMap::new());
+}
+
+/// Drop repeats of messages bridged to `actor` (replaces any earlier setting)
+pub fn enable(actor: &str, config: DedupConfig) {
+    WINDOWS.lock().unwrap().insert(actor.to_string(), Window::new(config));
+    ACTIVE.store(true, Ordering::Release);
+}
+
+pub fn disable(actor: &str) {
+    let mut windows = WINDOWS.lock().unwrap();
+    windows.remove(actor);
+    ACTIVE.store(!windows.is_empty(), Ordering::Release);
+}
+
+/// Whether `msg` for `actor` repeats one inside its window; records it if not
+pub fn is_duplicate(actor: &str, msg: &dyn Message) -> bool {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return false;
+    }
+    let mut windows = WINDOWS.lock().unwrap();
+    let window = match windows.get_mut(actor) {
+        Some(w) => w,
+        None => return false,
+    };
+    let dup = match window.config.key_of(msg) {
+        Some(key) => window.check(key),
+        None => false,
+    };
+    if dup {
+        DUPLICATES.fetch_add(1, Ordering::Relaxed);
+    }
+    dup
+}
+
+/// Messages dropped as duplicates
+pub fn duplicates() -> u64 {
+    DUPLICATES.load(Ordering::Relaxed)
+}
+
+/// Drop messages to Rust actor `actor_name` equal to one received in the
+/// last `window_ms` ms, remembering at most `max_entries`. `window_ms` 0
+/// turns dedup off. Returns 0, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("rust_actor_set_dedup")]
+pub extern "C" fn rust_actor_set_dedup(actor_name: *const c_char, window_ms: u32, max_entries: usize) -> c_int {
+    let name = match c_str(actor_name) {
+        Some(s) => s,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    if window_ms == 0 {
+        disable(name);
+    } else {
+        enable(name, DedupConfig::fingerprint(Duration::from_millis(window_ms as u64), max_entries));
+    }
+    0
+}
@@ -0,0 +1,264 @@
+//! Startup dependencies between actors across languages
+//!
+//! An actor that talks to another from its Start handler (a Rust monitor
+//! subscribing to a C++ feed) otherwise has to look the target up lazily
+//! and hope it is registered by then. Declaring the dependency instead
+//! holds Rust Start messages until every dependency is present in either
+//! registry:
+//!
+//! ```c
+//! register_rust_subscriber();
+//! interop_require("rust_price_monitor", "cpp_price_feed");
+//! ...
+//! rust_manager_init();                 // returns; Start sent once met
+//! if (interop_dependencies_wait(5000) < 0) {
+//!     char report[512];
+//!     interop_dependency_report(report, sizeof report);
+//! }
+//! ```
+//!
+//! With dependencies declared, `rust_manager_init()` does not block: the
+//! check runs on its own thread, polling both registries, so C++ actors
+//! registered after the call are still found. If some dependency is still
+//! missing after the timeout (default `DEFAULT_TIMEOUT_MS`), Rust actors
+//! are not started and the report names each actor and what it is missing.
+//!
+//! A requirement can also hold just the one actor's Start, leaving the
+//! others to start at once: `require_for_start()`
+//! (`interop_require_for_start()`), or at registration with
+//! `#[interop_actor("rust_ping", requires = ["cpp_pong"])]`. The actor's
+//! own thread waits when Start arrives, so messages sent to it meanwhile
+//! stay queued and are handled after Start, in order. After the timeout
+//! the actor is started anyway and the missing dependencies are printed.
+//! An actor sharing a thread (see shared_thread) holds up its group while
+//! it waits.
+//!
+//! Declarations are cleared by `create_rust_manager()`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+
+/// How long Start is held for missing dependencies by default
+pub const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+/// Interval between registry checks while waiting
+const POLL: Duration = Duration::from_millis(10);
+
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT_MS);
+
+/// Dependencies still missing when the wait gave up
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DependencyError {
+    pub waited: Duration,
+    /// actor -> dependencies not found
+    pub missing: BTreeMap<String, Vec<String>>,
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "dependencies not met after {} ms:", self.waited.as_millis())?;
+        for (actor, deps) in &self.missing {
+            write!(f, " {} requires {};", actor, deps.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DependencyError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Status {
+    /// No check has run since the last declaration
+    Pending,
+    /// Every dependency was found; Start was sent
+    Met,
+    Failed(DependencyError),
+}
+
+// (actor, dependency) in declaration order
+static REQUIRED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+static STATUS: Mutex<Status> = Mutex::new(Status::Pending);
+static SETTLED: Condvar = Condvar::new();
+// actor -> dependencies its own Start waits for
+static START_AFTER: Mutex<BTreeMap<String, Vec<String>>> = Mutex::new(BTreeMap::new());
+
+/// Hold Start until `dependency` exists in either runtime. `actor` is only
+/// used in the report.
+pub fn require(actor: &str, dependency: &str) {
+    let mut required = REQUIRED.lock().unwrap();
+    let entry = (actor.to_string(), dependency.to_string());
+    if !required.contains(&entry) {
+        required.push(entry);
+    }
+}
+
+/// Hold only `actor`'s Start until `dependency` exists in either runtime
+pub fn require_for_start(actor: &str, dependency: &str) {
+    let mut start_after = START_AFTER.lock().unwrap();
+    let deps = start_after.entry(actor.to_string()).or_default();
+    if !deps.iter().any(|d| d == dependency) {
+        deps.push(dependency.to_string());
+    }
+}
+
+/// Dependencies declared for `actor`'s own Start
+pub fn start_requirements(actor: &str) -> Vec<String> {
+    START_AFTER.lock().unwrap().get(actor).cloned().unwrap_or_default()
+}
+
+/// Poll until `exists` finds every start requirement of `actor` or the
+/// timeout passes. Called on the actor's thread when Start arrives.
+pub(crate) fn await_start(actor: &str, exists: impl Fn(&str) -> bool) -> Result<(), DependencyError> {
+    let deps = start_requirements(actor);
+    let started = Instant::now();
+    loop {
+        let unmet: Vec<String> = deps.iter().filter(|d| !exists(d)).cloned().collect();
+        if unmet.is_empty() {
+            return Ok(());
+        }
+        if started.elapsed() >= timeout() {
+            let missing = BTreeMap::from([(actor.to_string(), unmet)]);
+            return Err(DependencyError { waited: started.elapsed(), missing });
+        }
+        std::thread::sleep(POLL);
+    }
+}
+
+/// Forget every declaration (create_rust_manager)
+pub fn clear() {
+    REQUIRED.lock().unwrap().clear();
+    START_AFTER.lock().unwrap().clear();
+    *STATUS.lock().unwrap() = Status::Pending;
+}
+
+pub fn is_empty() -> bool {
+    REQUIRED.lock().unwrap().is_empty()
+}
+
+pub fn set_timeout(timeout: Duration) {
+    TIMEOUT_MS.store(timeout.as_millis() as u64, Ordering::Relaxed);
+}
+
+pub fn timeout() -> Duration {
+    Duration::from_millis(TIMEOUT_MS.load(Ordering::Relaxed))
+}
+
+/// Declared dependencies `exists` does not find, by actor
+pub fn missing(exists: impl Fn(&str) -> bool) -> BTreeMap<String, Vec<String>> {
+    let mut missing: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (actor, dep) in REQUIRED.lock().unwrap().iter() {
+        if !exists(dep) {
+            missing.entry(actor.clone()).or_default().push(dep.clone());
+        }
+    }
+    missing
+}
+
+/// Poll until `exists` finds every dependency or the timeout passes, and
+/// record the outcome for status() / interop_dependencies_wait()
+pub fn check(exists: impl Fn(&str) -> bool) -> Result<(), DependencyError> {
+    *STATUS.lock().unwrap() = Status::Pending;
+    let started = Instant::now();
+    let result = loop {
+        let unmet = missing(&exists);
+        if unmet.is_empty() {
+            break Ok(());
+        }
+        if started.elapsed() >= timeout() {
+            break Err(DependencyError { waited: started.elapsed(), missing: unmet });
+        }
+        std::thread::sleep(POLL);
+    };
+    *STATUS.lock().unwrap() = match &result {
+        Ok(()) => Status::Met,
+        Err(e) => Status::Failed(e.clone()),
+    };
+    SETTLED.notify_all();
+    result
+}
+
+pub fn status() -> Status {
+    STATUS.lock().unwrap().clone()
+}
+
+/// Block until the check has settled. Returns Pending on timeout.
+pub fn wait(timeout: Duration) -> Status {
+    let status = STATUS.lock().unwrap();
+    let (status, _) = SETTLED.wait_timeout_while(status, timeout, |s| *s == Status::Pending).unwrap();
+    status.clone()
+}
+
+/// Hold Rust Start until `dependency` (a Rust or C++ actor) is registered.
+/// Call before rust_manager_init(). Returns 0, or InvalidArgument on a bad
+/// name.
+#[export_name = c_symbol!("interop_require")]
+pub extern "C" fn interop_require(actor: *const c_char, dependency: *const c_char) -> c_int {
+    let rc = match (c_str(actor), c_str(dependency)) {
+        (Some(a), Some(d)) => {
+            require(a, d);
+            InteropErrorCode::Ok
+        }
+        _ => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Hold only `actor`'s Start until `dependency` is registered; other Rust
+/// actors start at once. Call before rust_manager_init(). Returns 0, or
+/// InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_require_for_start")]
+pub extern "C" fn interop_require_for_start(actor: *const c_char, dependency: *const c_char) -> c_int {
+    let rc = match (c_str(actor), c_str(dependency)) {
+        (Some(a), Some(d)) => {
+            require_for_start(a, d);
+            InteropErrorCode::Ok
+        }
+        _ => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+#[export_name = c_symbol!("interop_set_dependency_timeout")]
+pub extern "C" fn interop_set_dependency_timeout(timeout_ms: u32) {
+    set_timeout(Duration::from_millis(timeout_ms as u64));
+}
+
+/// Block up to `timeout_ms` for the dependency check. Returns 1 when met
+/// (or nothing was declared), 0 if still waiting, or Timeout if it failed.
+#[export_name = c_symbol!("interop_dependencies_wait")]
+pub extern "C" fn interop_dependencies_wait(timeout_ms: u32) -> c_int {
+    if is_empty() {
+        return 1;
+    }
+    match wait(Duration::from_millis(timeout_ms as u64)) {
+        Status::Met => 1,
+        Status::Pending => 0,
+        Status::Failed(_) => InteropErrorCode::Timeout as c_int,
+    }
+}
+
+/// Write the failure report into `out` (NUL-terminated, truncated to fit
+/// `len`); empty unless the check failed. Returns the full length like
+/// snprintf.
+#[export_name = c_symbol!("interop_dependency_report")]
+pub extern "C" fn interop_dependency_report(out: *mut c_char, len: usize) -> c_int {
+    let report = match status() {
+        Status::Failed(e) => e.to_string(),
+        _ => String::new(),
+    };
+    if !out.is_null() && len > 0 {
+        let n = report.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(report.as_ptr(), out as *mut u8, n);
+            *out.add(n) = 0;
+        }
+    }
+    report.len().min(c_int::MAX as usize) as c_int
+}
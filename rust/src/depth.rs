@@ -0,0 +1,336 @@
+//! Double-buffered MarketDepth snapshots, published whole
+//!
+//! A feed handler updates an order book one level at a time. Publishing the
+//! book it is still writing lets a subscriber see bid 0 of the new book with
+//! bid 1 of the old, or `num_levels` raised before the level is filled in. A
+//! `DepthPublisher` keeps two buffers per symbol: the producer edits the
+//! back one, and `publish()` makes it the stable snapshot and sends that,
+//! while the producer goes on building the next:
+//!
+//! ```ignore
+//! let books = DepthPublisher::new("rust_publisher");
+//! books.set_level("AAPL", BookSide::Bid, 0, 189.50, 300);
+//! books.set_level("AAPL", BookSide::Ask, 0, 189.52, 100);
+//! books.publish("AAPL")?;   // subscribers of "AAPL" get both levels or neither
+//! ```
+//!
+//! - The topic is the symbol. The back buffer starts as a copy of the last
+//!   snapshot, so only the levels that changed are set again.
+//! - A snapshot is shared by `Arc` and fanned out without the lock: a
+//!   producer on another thread keeps editing meanwhile. Of two snapshots
+//!   published at once, the older is skipped if the newer has replaced it.
+//! - `snapshot()` returns the last snapshot, e.g. for a late subscriber.
+//!   Updates carrying a partition key reach the partition's subscribers
+//!   too (see partitions); a topic declared for another message type is
+//!   refused (see topic).
+//!
+//! C++ publishers use `interop_depth_new()` / `interop_depth_set_level()` /
+//! `interop_depth_publish()` (`interop/depth.h`).
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::broker;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{CMarketDepth, MarketDepth};
+use crate::names::c_str;
+use crate::partitions;
+use crate::topic::{self, TopicError};
+
+/// Levels a MarketDepth holds per side
+pub const MAX_LEVELS: usize = 5;
+
+/// The side of the book a level is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+#[derive(Default)]
+struct Book {
+    // Being built
+    back: MarketDepth,
+    // Last published
+    front: Option<Arc<MarketDepth>>,
+    // Edited since the last publish
+    dirty: bool,
+}
+
+struct Inner {
+    publisher: String,
+    books: Mutex<HashMap<String, Book>>,
+    // Held during fan-out, so snapshots of a symbol go out in order
+    fan_out: Mutex<()>,
+    published: AtomicU64,
+}
+
+/// Depth books of one publisher, published as whole snapshots. Clones
+/// share the same books.
+#[derive(Clone)]
+pub struct DepthPublisher {
+    inner: Arc<Inner>,
+}
+
+impl DepthPublisher {
+    /// Publish as `publisher`, each symbol's book on the topic of its name
+    pub fn new(publisher: &str) -> Self {
+        DepthPublisher {
+            inner: Arc::new(Inner {
+                publisher: publisher.to_string(),
+                books: Mutex::new(HashMap::new()),
+                fan_out: Mutex::new(()),
+                published: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn publisher(&self) -> &str {
+        &self.inner.publisher
+    }
+
+    /// Edit the book being built for `symbol`; subscribers see the edit
+    /// with the next `publish()`
+    pub fn edit<R>(&self, symbol: &str, f: impl FnOnce(&mut MarketDepth) -> R) -> R {
+        let mut books = self.inner.books.lock().unwrap();
+        let book = books.entry(symbol.to_string()).or_insert_with(|| Book {
+            back: MarketDepth { symbol: symbol.into(), ..Default::default() },
+            ..Default::default()
+        });
+        book.dirty = true;
+        f(&mut book.back)
+    }
+
+    /// Set one level of the book being built, raising its `num_levels` to
+    /// include it. Returns false if `level` is not below MAX_LEVELS.
+    pub fn set_level(&self, symbol: &str, side: BookSide, level: usize, price: f64, size: i32) -> bool {
+        if level >= MAX_LEVELS {
+            return false;
+        }
+        self.edit(symbol, |depth| {
+            match side {
+                BookSide::Bid => {
+                    depth.bid_prices[level] = price;
+                    depth.bid_sizes[level] = size;
+                }
+                BookSide::Ask => {
+                    depth.ask_prices[level] = price;
+                    depth.ask_sizes[level] = size;
+                }
+            }
+            depth.num_levels = depth.num_levels.max(level as i32 + 1);
+        });
+        true
+    }
+
+    /// Set the levels the book being built holds, clearing those past
+    /// `levels` (at most MAX_LEVELS)
+    pub fn set_levels(&self, symbol: &str, levels: usize) {
+        let levels = levels.min(MAX_LEVELS);
+        self.edit(symbol, |depth| {
+            for i in levels..MAX_LEVELS {
+                depth.bid_prices[i] = 0.0;
+                depth.ask_prices[i] = 0.0;
+                depth.bid_sizes[i] = 0;
+                depth.ask_sizes[i] = 0;
+            }
+            depth.num_levels = levels as i32;
+        });
+    }
+
+    /// Make the book being built for `symbol` its snapshot and publish it.
+    /// Returns the subscribers it was sent to; 0 if nothing changed since
+    /// the last publish.
+    pub fn publish(&self, symbol: &str) -> Result<usize, TopicError> {
+        let snapshot = {
+            let mut books = self.inner.books.lock().unwrap();
+            let book = match books.get_mut(symbol) {
+                Some(book) if book.dirty => book,
+                _ => return Ok(0),
+            };
+            topic::check(symbol, &book.back)?;
+            // The back buffer stays as it is: the next book starts from this one
+            let snapshot = Arc::new(book.back.clone());
+            book.front = Some(snapshot.clone());
+            book.dirty = false;
+            snapshot
+        };
+        Ok(self.fan_out(symbol, snapshot))
+    }
+
+    /// Publish every book edited since its last publish. Returns the
+    /// subscribers sent to, over all symbols.
+    pub fn publish_all(&self) -> Result<usize, TopicError> {
+        let dirty: Vec<String> = {
+            let books = self.inner.books.lock().unwrap();
+            books.iter().filter(|(_, b)| b.dirty).map(|(s, _)| s.clone()).collect()
+        };
+        let mut sent = 0;
+        for symbol in dirty {
+            sent += self.publish(&symbol)?;
+        }
+        Ok(sent)
+    }
+
+    /// The last published snapshot of `symbol`
+    pub fn snapshot(&self, symbol: &str) -> Option<Arc<MarketDepth>> {
+        self.inner.books.lock().unwrap().get(symbol).and_then(|b| b.front.clone())
+    }
+
+    /// Symbols with a book, published or not
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self.inner.books.lock().unwrap().keys().cloned().collect();
+        symbols.sort();
+        symbols
+    }
+
+    /// Snapshots published since creation
+    pub fn published(&self) -> u64 {
+        self.inner.published.load(Ordering::Relaxed)
+    }
+
+    fn fan_out(&self, symbol: &str, snapshot: Arc<MarketDepth>) -> usize {
+        let _order = self.inner.fan_out.lock().unwrap();
+        // A later publish replaced it while this one waited: that one goes out
+        let current = self.snapshot(symbol).is_some_and(|front| Arc::ptr_eq(&front, &snapshot));
+        if !current {
+            return 0;
+        }
+        let partition = partitions::partition_of(symbol, snapshot.as_ref());
+        let sent = broker::publish_partition_with(&self.inner.publisher, symbol, partition, |_| {
+            Some(Box::new(MarketDepth::clone(&snapshot)))
+        });
+        self.inner.published.fetch_add(1, Ordering::Relaxed);
+        sent
+    }
+}
+
+static NEXT_ID: AtomicI64 = AtomicI64::new(1);
+
+lazy_static::lazy_static! {
+    static ref DEPTHS: Mutex<HashMap<i64, DepthPublisher>> = Mutex::new(HashMap::new());
+}
+
+fn by_id(depth: i64) -> Option<DepthPublisher> {
+    DEPTHS.lock().unwrap().get(&depth).cloned()
+}
+
+/// Start a depth publisher publishing as `publisher`. Returns its id
+/// (> 0), or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_depth_new")]
+pub extern "C" fn interop_depth_new(publisher: *const c_char) -> i64 {
+    match c_str(publisher) {
+        Some(name) => {
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            DEPTHS.lock().unwrap().insert(id, DepthPublisher::new(name));
+            id
+        }
+        None => InteropErrorCode::InvalidArgument as i64,
+    }
+}
+
+// The publisher and symbol of an FFI call, or its error code
+fn depth_and_symbol(depth: i64, symbol: *const c_char) -> Result<(DepthPublisher, &'static str), c_int> {
+    let d = by_id(depth).ok_or(InteropErrorCode::NotFound as c_int)?;
+    let symbol = c_str(symbol).ok_or(InteropErrorCode::InvalidArgument as c_int)?;
+    Ok((d, symbol))
+}
+
+/// Set `level` of the bid (`side` 0) or ask (1) side of the book being
+/// built for `symbol`. Returns 0, NotFound on an unknown publisher, or
+/// InvalidArgument on a bad symbol, side or level.
+#[export_name = c_symbol!("interop_depth_set_level")]
+pub extern "C" fn interop_depth_set_level(
+    depth: i64,
+    symbol: *const c_char,
+    side: c_int,
+    level: c_int,
+    price: f64,
+    size: i32,
+) -> c_int {
+    let (d, symbol) = match depth_and_symbol(depth, symbol) {
+        Ok(found) => found,
+        Err(rc) => return rc,
+    };
+    let side = match side {
+        0 => BookSide::Bid,
+        1 => BookSide::Ask,
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    if level >= 0 && d.set_level(symbol, side, level as usize, price, size) {
+        0
+    } else {
+        InteropErrorCode::InvalidArgument as c_int
+    }
+}
+
+/// Set the levels the book being built for `symbol` holds, clearing
+/// those past it. Returns 0, NotFound on an unknown publisher, or
+/// InvalidArgument on a bad symbol or a count outside 0..=5.
+#[export_name = c_symbol!("interop_depth_set_levels")]
+pub extern "C" fn interop_depth_set_levels(depth: i64, symbol: *const c_char, levels: c_int) -> c_int {
+    let (d, symbol) = match depth_and_symbol(depth, symbol) {
+        Ok(found) => found,
+        Err(rc) => return rc,
+    };
+    if !(0..=MAX_LEVELS as c_int).contains(&levels) {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    d.set_levels(symbol, levels as usize);
+    0
+}
+
+/// Publish the book being built for `symbol` as its snapshot. Returns
+/// the subscribers sent to, NotFound on an unknown publisher,
+/// InvalidArgument on a bad symbol, or WrongType if the topic carries
+/// another message type.
+#[export_name = c_symbol!("interop_depth_publish")]
+pub extern "C" fn interop_depth_publish(depth: i64, symbol: *const c_char) -> c_int {
+    let (d, symbol) = match depth_and_symbol(depth, symbol) {
+        Ok(found) => found,
+        Err(rc) => return rc,
+    };
+    match d.publish(symbol) {
+        Ok(sent) => sent.min(c_int::MAX as usize) as c_int,
+        Err(e) => {
+            eprintln!("[Depth] {}", e);
+            match e {
+                TopicError::Mismatch { .. } => InteropErrorCode::WrongType as c_int,
+                TopicError::NameTooLong(_) => InteropErrorCode::InvalidArgument as c_int,
+            }
+        }
+    }
+}
+
+/// Copy the last published snapshot of `symbol` into `out`. Returns 0,
+/// NotFound on an unknown publisher or none published yet, or
+/// InvalidArgument on a bad symbol or null `out`.
+#[export_name = c_symbol!("interop_depth_snapshot")]
+pub extern "C" fn interop_depth_snapshot(depth: i64, symbol: *const c_char, out: *mut CMarketDepth) -> c_int {
+    if out.is_null() {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    let (d, symbol) = match depth_and_symbol(depth, symbol) {
+        Ok(found) => found,
+        Err(rc) => return rc,
+    };
+    match d.snapshot(symbol) {
+        Some(snapshot) => {
+            unsafe { *out = snapshot.to_c_struct() };
+            0
+        }
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Drop `depth` and its books. Returns 0, or NotFound on an unknown
+/// publisher.
+#[export_name = c_symbol!("interop_depth_free")]
+pub extern "C" fn interop_depth_free(depth: i64) -> c_int {
+    match DEPTHS.lock().unwrap().remove(&depth) {
+        Some(_) => 0,
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
@@ -0,0 +1,205 @@
+//! Envelope metadata in handlers
+//!
+//! Handlers see the payload only. An actor that needs provenance (who sent
+//! a message, from which side, when the bridge queued it, its sequence
+//! number, the span it was sent in) has envelopes turned on and takes them
+//! as an extra handler argument:
+//!
+//! ```ignore
+//! envelope::enable("rust_strategy");       // or rust_actor_set_envelope() from C++
+//!
+//! impl Strategy {
+//!     fn on_market_update(&mut self, msg: &MarketUpdate, meta: &Envelope, ctx: &mut ActorContext) {
+//!         if meta.origin == Origin::Cpp && meta.seq.is_some() { ... }
+//!     }
+//! }
+//!
+//! handle_messages_with_envelope!(Strategy,
+//!     MarketUpdate => on_market_update
+//! );
+//! ```
+//!
+//! The bridge records the envelope as it queues a message for an actor
+//! with envelopes on (`rust_actor_send`, `rust_actor_post`, spilled
+//! messages), kept by message address like a TTL deadline (see ttl). The
+//! `Supervised` wrapper makes it current while the handler runs. Messages
+//! without one (Rust-to-Rust sends, `rust_actor_fast_send`, actors with
+//! envelopes off) get `Envelope::local()`.
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use actors::Message;
+
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::sequence_number;
+use crate::names::c_str;
+use crate::timestamp::Timestamp;
+use crate::trace;
+
+/// Which side a message came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Origin {
+    /// Through the bridge, from C++
+    Cpp,
+    /// Sent by Rust code without the bridge
+    Rust,
+}
+
+/// Metadata of a message, for handlers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Envelope {
+    /// Sending actor; None if the sender gave no name or is not known
+    pub sender: Option<String>,
+    pub origin: Origin,
+    /// When the bridge queued the message for the actor (interop clock)
+    pub received_at: Option<Timestamp>,
+    /// The message's `sequenced` field, if it has one and it is set
+    pub seq: Option<i64>,
+    /// Span the message was sent in (feature `tracing`): the bridge's
+    /// `ffi_send` span
+    pub trace_id: Option<u64>,
+}
+
+impl Envelope {
+    /// Envelope of a message the bridge did not queue
+    pub fn local() -> Self {
+        Envelope { sender: None, origin: Origin::Rust, received_at: None, seq: None, trace_id: None }
+    }
+}
+
+// Fast path: no actor takes envelopes
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref ENABLED: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+    // Address of a queued message -> its envelope
+    static ref ENVELOPES: Mutex<HashMap<usize, Envelope>> = Mutex::new(HashMap::new());
+}
+
+thread_local! {
+    // Envelope of the message being handled on this thread
+    static CURRENT: RefCell<Option<Envelope>> = const { RefCell::new(None) };
+}
+
+/// Record envelopes of messages queued for `actor` from now on
+pub fn enable(actor: &str) {
+    let mut enabled = ENABLED.write().unwrap();
+    enabled.insert(actor.to_string());
+    ACTIVE.store(true, Ordering::Release);
+}
+
+pub fn disable(actor: &str) {
+    let mut enabled = ENABLED.write().unwrap();
+    enabled.remove(actor);
+    ACTIVE.store(!enabled.is_empty(), Ordering::Release);
+}
+
+pub fn is_enabled(actor: &str) -> bool {
+    ACTIVE.load(Ordering::Acquire) && ENABLED.read().unwrap().contains(actor)
+}
+
+/// Call `f` with the envelope of the message being handled on this thread,
+/// `Envelope::local()` if it has none
+pub fn with_current<R>(f: impl FnOnce(&Envelope) -> R) -> R {
+    CURRENT.with(|c| match &*c.borrow() {
+        Some(envelope) => f(envelope),
+        None => f(&Envelope::local()),
+    })
+}
+
+/// Envelope of the message being handled on this thread, if it has one
+pub fn current() -> Option<Envelope> {
+    CURRENT.with(|c| c.borrow().clone())
+}
+
+// Zero-sized messages share one address and can't be told apart
+fn key(msg: &dyn Any) -> Option<usize> {
+    (std::mem::size_of_val(msg) != 0).then_some(msg as *const dyn Any as *const () as usize)
+}
+
+/// Record the envelope of `msg`, boxed and queued by the bridge for
+/// `target` from `sender` (""). False if `target` takes no envelopes or
+/// the message can't be tracked.
+pub(crate) fn stamp(target: &str, sender: &str, msg: &dyn Message) -> bool {
+    if !is_enabled(target) {
+        return false;
+    }
+    let k = match key(msg.as_any()) {
+        Some(k) => k,
+        None => return false,
+    };
+    let envelope = Envelope {
+        sender: (!sender.is_empty()).then(|| sender.to_string()),
+        origin: Origin::Cpp,
+        received_at: Some(Timestamp::now()),
+        seq: sequence_number(msg),
+        trace_id: trace::current_id(),
+    };
+    ENVELOPES.lock().unwrap().insert(k, envelope);
+    true
+}
+
+/// Bytes `stamp()` keeps for a message from `sender`
+pub(crate) fn stamped_size(sender: &str) -> usize {
+    std::mem::size_of::<(usize, Envelope)>() + sender.len()
+}
+
+/// Forget the envelope of a message that is being dropped
+pub(crate) fn unstamp(msg: &dyn Any) {
+    if let Some(k) = key(msg) {
+        ENVELOPES.lock().unwrap().remove(&k);
+    }
+}
+
+/// Current envelope while a handler runs; cleared on drop
+pub(crate) struct Entered {
+    active: bool,
+}
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        if self.active {
+            CURRENT.with(|c| *c.borrow_mut() = None);
+        }
+    }
+}
+
+/// Called as an actor dequeues `msg`: make its envelope current
+pub(crate) fn enter(msg: &dyn Message) -> Entered {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return Entered { active: false };
+    }
+    let envelope = key(msg.as_any()).and_then(|k| ENVELOPES.lock().unwrap().remove(&k));
+    let active = envelope.is_some();
+    if active {
+        CURRENT.with(|c| *c.borrow_mut() = envelope);
+    }
+    Entered { active }
+}
+
+/// `handle_messages!` for handlers that also take the message's `Envelope`:
+/// `fn on_x(&mut self, msg: &X, meta: &Envelope, ctx: &mut ActorContext)`.
+/// A last `_ => handler` arm gets the rest (see fallback).
+#[macro_export]
+macro_rules! handle_messages_with_envelope {
+    ($actor:ty, $($arms:tt)*) => {
+        $crate::__handle_arms!(envelope; $actor; []; $($arms)*);
+    };
+}
+
+/// Record envelopes of messages queued for `actor_name` (on 1) or stop (0).
+/// Returns 0, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("rust_actor_set_envelope")]
+pub extern "C" fn rust_actor_set_envelope(actor_name: *const c_char, on: c_int) -> c_int {
+    let name = match c_str(actor_name) {
+        Some(n) => n,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    if on != 0 { enable(name) } else { disable(name) }
+    InteropErrorCode::Ok as c_int
+}
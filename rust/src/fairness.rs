@@ -0,0 +1,350 @@
+//! Fair fan-out: one slow subscriber cannot hold up the others
+//!
+//! A publish sends to its subscribers one after another, in subscription
+//! order. A slow one (a C++ actor whose sends retry, a full `SUB_BLOCK`
+//! queue) delays every send after it, and those who subscribed first are
+//! always served first. With fairness enabled:
+//!
+//! ```ignore
+//! fairness::enable(FairnessConfig { send_budget: Duration::from_micros(200), backlog: 4096 });
+//! fairness::stats("rust_publisher", "cpp_recorder");   // deferred, dropped, max_wait, ..
+//! ```
+//!
+//! - Each publish starts its fan-out one subscriber further on, so none is
+//!   always first.
+//! - A send to a subscriber that takes longer than `send_budget`, or would
+//!   wait for room in its `SUB_BLOCK` queue, marks it slow. A slow
+//!   subscriber's messages go to a backlog of its own, in order, instead of
+//!   being sent by the publishing thread.
+//! - A flusher thread sends the backlogs round-robin, one message per
+//!   subscriber per round. A subscriber whose backlog empties with a send
+//!   within budget is sent to directly again.
+//!
+//! A backlog holds `backlog` messages; past that the oldest is dropped and
+//! counted, also for `SUB_BLOCK` subscribers: their publishers no longer
+//! wait. Starvation shows in `stats` per (publisher, subscriber) as
+//! dropped messages and a growing `max_wait`. Applies to inline fan-out and
+//! fan-out workers alike (see broker). C++ uses `rust_fanout_set_fair()`
+//! and `rust_fanout_stats()`.
+
+use std::collections::{HashMap, VecDeque};
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use actors::Message;
+
+use crate::broker::{self, Outcome};
+use crate::dead_letters::SendContext;
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+
+/// Send time past which a subscriber is slow, by default
+pub const DEFAULT_SEND_BUDGET: Duration = Duration::from_micros(500);
+/// Messages held for a slow subscriber, by default
+pub const DEFAULT_BACKLOG: usize = 1024;
+
+// How long the flusher waits when every backlog is stuck on a full queue
+const RETRY: Duration = Duration::from_millis(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FairnessConfig {
+    /// A send that takes longer marks the subscriber slow
+    pub send_budget: Duration,
+    /// Most messages held for one slow subscriber
+    pub backlog: usize,
+}
+
+impl Default for FairnessConfig {
+    fn default() -> Self {
+        FairnessConfig { send_budget: DEFAULT_SEND_BUDGET, backlog: DEFAULT_BACKLOG }
+    }
+}
+
+/// Starvation counters of one subscriber of one publisher
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FanoutStats {
+    /// Sends that took longer than the budget
+    pub overruns: u64,
+    /// Messages put in the backlog
+    pub deferred: u64,
+    /// Messages dropped from a full backlog
+    pub dropped: u64,
+    /// Messages in the backlog now
+    pub backlog: usize,
+    /// Longest a message waited in the backlog
+    pub max_wait: Duration,
+    pub slow: bool,
+}
+
+struct Deferred {
+    topic: String,
+    msg: Box<dyn Message>,
+    context: SendContext,
+    queued: Instant,
+}
+
+/// One publisher's sends to one subscriber
+#[derive(Default)]
+struct Lane {
+    slow: bool,
+    backlog: VecDeque<Deferred>,
+    stats: FanoutStats,
+}
+
+struct State {
+    config: FairnessConfig,
+    // False once disabled; the flusher exits when the backlogs are empty
+    running: bool,
+    // (publisher, subscriber) -> lane
+    lanes: HashMap<(String, String), Lane>,
+}
+
+// Fast path: fan out as before while disabled
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NEXT_START: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static::lazy_static! {
+    static ref STATE: Mutex<State> = Mutex::new(State {
+        config: FairnessConfig::default(),
+        running: false,
+        lanes: HashMap::new(),
+    });
+    // Signalled when a message is deferred or fairness is disabled
+    static ref WORK: Condvar = Condvar::new();
+    static ref FLUSHER: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+}
+
+/// Fan out fairly with `config`, or change the config if already enabled
+pub fn enable(config: FairnessConfig) {
+    let mut flusher = FLUSHER.lock().unwrap();
+    {
+        let mut state = STATE.lock().unwrap();
+        state.config = config;
+        state.running = true;
+    }
+    if flusher.is_none() {
+        *flusher = Some(thread::spawn(flush_loop));
+    }
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// Fan out in subscription order again. Returns once the backlogs have
+/// been sent.
+pub fn disable() {
+    let mut flusher = FLUSHER.lock().unwrap();
+    ENABLED.store(false, Ordering::Release);
+    STATE.lock().unwrap().running = false;
+    WORK.notify_all();
+    if let Some(t) = flusher.take() {
+        let _ = t.join();
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+pub fn config() -> FairnessConfig {
+    STATE.lock().unwrap().config
+}
+
+/// Counters of `subscriber` of `publisher`; zero if it was never sent to
+/// while fairness was enabled
+pub fn stats(publisher: &str, subscriber: &str) -> FanoutStats {
+    let state = STATE.lock().unwrap();
+    state.lanes.get(&(publisher.to_string(), subscriber.to_string())).map_or_else(FanoutStats::default, Lane::stats)
+}
+
+/// Counters of every (publisher, subscriber), sorted
+pub fn all_stats() -> Vec<(String, String, FanoutStats)> {
+    let state = STATE.lock().unwrap();
+    let mut all: Vec<_> = state.lanes.iter().map(|((p, s), lane)| (p.clone(), s.clone(), lane.stats())).collect();
+    all.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+    all
+}
+
+impl Lane {
+    fn stats(&self) -> FanoutStats {
+        FanoutStats { backlog: self.backlog.len(), slow: self.slow, ..self.stats }
+    }
+
+    fn defer(&mut self, limit: usize, d: Deferred) {
+        if self.backlog.len() >= limit.max(1) {
+            self.backlog.pop_front();
+            self.stats.dropped += 1;
+        }
+        self.backlog.push_back(d);
+        self.stats.deferred += 1;
+        WORK.notify_one();
+    }
+}
+
+/// Where the next publish of `len` subscribers starts its fan-out
+pub(crate) fn start_offset(len: usize) -> usize {
+    NEXT_START.fetch_add(1, Ordering::Relaxed) % len.max(1)
+}
+
+/// Send `msg` published on `topic` to `subscriber` while fairness is
+/// enabled: directly, or by its backlog if it is slow. False if the send
+/// failed.
+pub(crate) fn send(
+    publisher: &str,
+    topic: &str,
+    subscriber: &str,
+    msg: Box<dyn Message>,
+    context: SendContext,
+) -> bool {
+    let key = (publisher.to_string(), subscriber.to_string());
+    let budget = {
+        let mut guard = STATE.lock().unwrap();
+        let state = &mut *guard;
+        let lane = state.lanes.entry(key.clone()).or_default();
+        // Once disabled, only lanes the flusher is still draining defer
+        if !lane.backlog.is_empty() || (state.running && lane.slow) {
+            let d = Deferred { topic: topic.to_string(), msg, context, queued: Instant::now() };
+            lane.defer(state.config.backlog, d);
+            return true;
+        }
+        state.running.then_some(state.config.send_budget)
+    };
+    let budget = match budget {
+        Some(b) => b,
+        None => return matches!(broker::send_to(publisher, topic, subscriber, msg, context, true), Outcome::Sent),
+    };
+    let start = Instant::now();
+    let outcome = broker::send_to(publisher, topic, subscriber, msg, context, false);
+    let overran = start.elapsed() > budget;
+
+    let mut guard = STATE.lock().unwrap();
+    let state = &mut *guard;
+    let lane = state.lanes.entry(key).or_default();
+    if overran {
+        lane.slow = true;
+        lane.stats.overruns += 1;
+    }
+    match outcome {
+        Outcome::Sent => true,
+        Outcome::Failed => false,
+        Outcome::Full(msg) => {
+            lane.slow = true;
+            let d = Deferred { topic: topic.to_string(), msg, context, queued: start };
+            lane.defer(state.config.backlog, d);
+            true
+        }
+    }
+}
+
+/// Forget `subscriber` of `publisher`, dropping its backlog. Called when
+/// it holds no subscription of `publisher` any more.
+pub(crate) fn remove(publisher: &str, subscriber: &str) {
+    if is_enabled() {
+        STATE.lock().unwrap().lanes.remove(&(publisher.to_string(), subscriber.to_string()));
+    }
+}
+
+fn flush_loop() {
+    let mut state = STATE.lock().unwrap();
+    loop {
+        let keys: Vec<(String, String)> =
+            state.lanes.iter().filter(|(_, lane)| !lane.backlog.is_empty()).map(|(k, _)| k.clone()).collect();
+        if keys.is_empty() {
+            if !state.running {
+                return;
+            }
+            state = WORK.wait(state).unwrap();
+            continue;
+        }
+        // One round: a message per backlog
+        let mut progress = false;
+        for key in keys {
+            let next = state.lanes.get_mut(&key).and_then(|l| l.backlog.pop_front());
+            let Deferred { topic, msg, context, queued } = match next {
+                Some(d) => d,
+                None => continue,
+            };
+            let (budget, wait) = (state.config.send_budget, !state.running);
+            drop(state);
+            let start = Instant::now();
+            let outcome = broker::send_to(&key.0, &topic, &key.1, msg, context, wait);
+            let elapsed = start.elapsed();
+            state = STATE.lock().unwrap();
+            let lane = match state.lanes.get_mut(&key) {
+                Some(l) => l,
+                None => continue,  // removed meanwhile
+            };
+            if let Outcome::Full(msg) = outcome {
+                lane.backlog.push_front(Deferred { topic, msg, context, queued });
+                continue;
+            }
+            progress = true;
+            lane.stats.max_wait = lane.stats.max_wait.max(start.duration_since(queued));
+            if elapsed > budget {
+                lane.slow = true;
+                lane.stats.overruns += 1;
+            } else if lane.backlog.is_empty() {
+                lane.slow = false;
+            }
+        }
+        if !progress {
+            state = WORK.wait_timeout(state, RETRY).unwrap().0;
+        }
+    }
+}
+
+/// Counters of one subscriber; mirrors RustFanoutStats in
+/// interop/fairness.h
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RustFanoutStats {
+    pub overruns: u64,
+    pub deferred: u64,
+    pub dropped: u64,
+    pub backlog: u64,
+    pub max_wait_us: u64,
+    /// 1 while its messages go by the backlog
+    pub slow: i32,
+}
+
+/// Fan out fairly: a send slower than `budget_us` (0: 500) defers the
+/// subscriber's messages to a backlog of `backlog` (0: 1024). Negative
+/// `budget_us` disables fairness.
+#[export_name = c_symbol!("rust_fanout_set_fair")]
+pub extern "C" fn rust_fanout_set_fair(budget_us: i32, backlog: u32) {
+    if budget_us < 0 {
+        disable();
+        return;
+    }
+    enable(FairnessConfig {
+        send_budget: if budget_us == 0 { DEFAULT_SEND_BUDGET } else { Duration::from_micros(budget_us as u64) },
+        backlog: if backlog == 0 { DEFAULT_BACKLOG } else { backlog as usize },
+    });
+}
+
+/// Fill `out` with the counters of `subscriber` of `publisher`. Returns 0,
+/// or InvalidArgument on a bad name or null `out`.
+#[export_name = c_symbol!("rust_fanout_stats")]
+pub extern "C" fn rust_fanout_stats(
+    publisher: *const c_char,
+    subscriber: *const c_char,
+    out: *mut RustFanoutStats,
+) -> c_int {
+    let (publisher, subscriber) = match (c_str(publisher), c_str(subscriber)) {
+        (Some(p), Some(s)) if !out.is_null() => (p, s),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let s = stats(publisher, subscriber);
+    unsafe {
+        *out = RustFanoutStats {
+            overruns: s.overruns,
+            deferred: s.deferred,
+            dropped: s.dropped,
+            backlog: s.backlog as u64,
+            max_wait_us: s.max_wait.as_micros().min(u64::MAX as u128) as u64,
+            slow: s.slow as i32,
+        }
+    };
+    InteropErrorCode::Ok as c_int
+}
@@ -0,0 +1,158 @@
+//! Catch-all handlers
+//!
+//! `handle_messages!` from actors ignores a message no arm matches, and the
+//! bridge refuses message IDs this build has no type for (a newer C++ build
+//! sending a new message), so neither reaches the actor. This crate's
+//! `handle_messages!` takes the same arms plus an optional last `_` arm
+//! that receives everything else:
+//!
+//! ```ignore
+//! use actors_interop::handle_messages;
+//!
+//! impl Gateway {
+//!     fn on_unknown(&mut self, env: &InteropEnvelope, ctx: &mut ActorContext) {
+//!         match env.name {
+//!             Some(name) => eprintln!("unhandled {} from {:?}", name, env.meta.sender),
+//!             None => eprintln!("message ID {} unknown to this build", env.msg_id),
+//!         }
+//!     }
+//! }
+//!
+//! handle_messages!(Gateway,
+//!     Ping => on_ping,
+//!     _ => on_unknown
+//! );
+//! ```
+//!
+//! The bridge (`rust_actor_send`, `rust_actor_fast_send`, `rust_actor_post`)
+//! passes a message with an unknown ID to the target as an
+//! `UnknownMessage` carrying the ID; the data cannot be kept, as the bridge
+//! does not know its size. The C++ sender still gets the unknown-message
+//! result, and actors without a `_` arm ignore it as before.
+//! `handle_messages_with_envelope!` (see envelope) takes a `_` arm too.
+
+use std::any::Any;
+
+use actors::{ActorRef, Message};
+
+use crate::envelope::{self, Envelope};
+use crate::interop_messages::message_to_debug_json;
+use crate::pause;
+use crate::queue_depth;
+use crate::schema;
+
+/// A message from C++ whose ID this build has no type for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownMessage {
+    pub msg_id: i32,
+}
+
+impl Message for UnknownMessage {
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+    fn message_id(&self) -> i32 { self.msg_id }
+}
+
+/// What a `_` arm receives
+pub struct InteropEnvelope<'a> {
+    pub msg_id: i32,
+    /// Type name; None for an UnknownMessage or a message that is not an
+    /// interop message (e.g. from actors)
+    pub name: Option<&'static str>,
+    pub message: &'a dyn Message,
+    /// Provenance; `Envelope::local()` unless the actor takes envelopes
+    pub meta: Envelope,
+}
+
+impl<'a> InteropEnvelope<'a> {
+    pub fn new(message: &'a dyn Message) -> Self {
+        let msg_id = message.message_id();
+        let name = if message.as_any().is::<UnknownMessage>() {
+            None
+        } else {
+            schema::by_id(msg_id).map(|s| s.name)
+        };
+        InteropEnvelope { msg_id, name, message, meta: envelope::with_current(Envelope::clone) }
+    }
+
+    /// True if this build has no type for the message's ID
+    pub fn is_unknown(&self) -> bool {
+        self.message.as_any().is::<UnknownMessage>()
+    }
+
+    pub fn downcast<M: 'static>(&self) -> Option<&M> {
+        self.message.as_any().downcast_ref::<M>()
+    }
+
+    /// The message as JSON (see `to_json()`), None if it is not an interop
+    /// message
+    pub fn to_json(&self) -> Option<String> {
+        message_to_debug_json(self.message)
+    }
+}
+
+/// Queue an UnknownMessage for `target`, from the bridge
+pub(crate) fn deliver_unknown(actor: &ActorRef, target: &str, sender: &str, msg_id: i32, sender_ref: Option<ActorRef>) {
+    pause::send(actor, target, queue_depth::track(target, sender, UnknownMessage { msg_id }), sender_ref);
+}
+
+/// `handle_messages!` from actors with an optional last `_ => handler` arm:
+/// `fn on_x(&mut self, env: &InteropEnvelope, ctx: &mut ActorContext)`.
+/// Replies to `ask_then` run their continuation (see ask).
+#[macro_export]
+macro_rules! handle_messages {
+    ($actor:ty, $($arms:tt)*) => {
+        $crate::__handle_arms!(plain; $actor; []; $($arms)*);
+    };
+}
+
+// Splits handler arms from a trailing `_` arm; `plain` or `envelope`
+// handlers
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __handle_arms {
+    ($kind:ident; $actor:ty; [$($msg:ty => $handler:ident),*]; _ => $fallback:ident $(,)?) => {
+        $crate::__handle_impl!($kind; $actor; [$($msg => $handler),*]; [$fallback]);
+    };
+    ($kind:ident; $actor:ty; [$($msg:ty => $handler:ident),*]; $(,)?) => {
+        $crate::__handle_impl!($kind; $actor; [$($msg => $handler),*]; []);
+    };
+    ($kind:ident; $actor:ty; [$($msg:ty => $handler:ident),*]; $next:ty => $next_handler:ident $(, $($rest:tt)*)?) => {
+        $crate::__handle_arms!($kind; $actor; [$($msg => $handler,)* $next => $next_handler]; $($($rest)*)?);
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __handle_impl {
+    (plain; $actor:ty; [$($msg:ty => $handler:ident),*]; [$($fb:ident)?]) => {
+        impl ::actors::Actor for $actor {
+            fn process_message(&mut self, msg: &dyn ::actors::Message, ctx: &mut ::actors::ActorContext) {
+                if $crate::ask::run_continuation(self, msg, ctx) {
+                    return;
+                }
+                $(
+                    if let Some(m) = msg.as_any().downcast_ref::<$msg>() {
+                        return self.$handler(m, ctx);
+                    }
+                )*
+                $( self.$fb(&$crate::fallback::InteropEnvelope::new(msg), ctx); )?
+            }
+        }
+    };
+    (envelope; $actor:ty; [$($msg:ty => $handler:ident),*]; [$($fb:ident)?]) => {
+        impl ::actors::Actor for $actor {
+            fn process_message(&mut self, msg: &dyn ::actors::Message, ctx: &mut ::actors::ActorContext) {
+                if $crate::ask::run_continuation(self, msg, ctx) {
+                    return;
+                }
+                $(
+                    if let Some(m) = msg.as_any().downcast_ref::<$msg>() {
+                        return $crate::envelope::with_current(|meta| self.$handler(m, meta, ctx));
+                    }
+                )*
+                $( self.$fb(&$crate::fallback::InteropEnvelope::new(msg), ctx); )?
+            }
+        }
+    };
+}
@@ -0,0 +1,325 @@
+//! FlatBuffers encoding of large interop messages
+//!
+//! Messages marked `INTEROP_FLATBUFFERS(Name)` in `interop_messages.h` (or
+//! `option (interop.flatbuffers) = true;` in a .proto) get
+//! `encode_flatbuffers()` / `decode_flatbuffers()`, and
+//! `codec::FlatBuffersCodec` carries them as a FlatBuffers table. The
+//! receiver reads fields where they lie in the frame: C++ with the
+//! generated `interop::fb::<Name>View` (`InteropFlatBuffers.hpp`), other
+//! peers with code flatc generates from `generated/fbs/interop_messages.fbs`.
+//!
+//! Every field is a table slot numbered in declaration order:
+//!
+//! | Field | FlatBuffers type |
+//! |-------|------------------|
+//! | integers, float, double | scalar of the same width |
+//! | `/* bool */` int32 | `bool` |
+//! | `char[N]`, `interop_string` | `string` |
+//! | `interop_bytes` | `[ubyte]` |
+//! | numeric `T[N]` | `[T]`; fewer elements decode as zeros |
+//! | `interop_decimal`, `interop_uuid`, `interop_timestamp` | struct `Decimal`, `Uuid`, `Timestamp` |
+//!
+//! Maps are not supported. Scalars are always written; absent fields
+//! decode as zero or empty, as FlatBuffers defaults do.
+
+use crate::decimal::Decimal;
+use crate::interop_string::InteropString;
+use crate::timestamp::Timestamp;
+use crate::uuid::Uuid;
+
+/// Builds a buffer holding one root table, front to back: root offset,
+/// vtable, the table's inline fields, then strings and vectors
+pub struct TableBuilder {
+    buf: Vec<u8>,
+    vtable: usize,
+    table: usize,
+    // Offset of each field from the table start, 0 if absent
+    slots: Vec<u16>,
+    // Strings and vectors to write after the table
+    deferred: Vec<Deferred>,
+}
+
+struct Deferred {
+    // Position of the uoffset in the table
+    at: usize,
+    elem_size: usize,
+    bytes: Vec<u8>,
+    nul: bool,
+}
+
+impl TableBuilder {
+    pub fn new(fields: usize) -> Self {
+        let vtable = 4;
+        let mut buf = vec![0u8; vtable + 4 + 2 * fields];
+        pad_to(&mut buf, 8, 0);
+        let table = buf.len();
+        buf.extend_from_slice(&((table - vtable) as i32).to_le_bytes());
+        TableBuilder { buf, vtable, table, slots: vec![0; fields], deferred: Vec::new() }
+    }
+
+    /// Add field `slot`
+    pub fn add<T: FbField>(&mut self, slot: usize, value: &T) {
+        value.put_fb(slot, self);
+    }
+
+    /// Inline bytes for `slot`, aligned to `align`
+    pub fn inline(&mut self, slot: usize, align: usize, bytes: &[u8]) {
+        pad_to(&mut self.buf, align, 0);
+        self.slots[slot] = (self.buf.len() - self.table) as u16;
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Vector of `elem_size`-byte elements for `slot`, written after the
+    /// table
+    pub fn vector(&mut self, slot: usize, elem_size: usize, bytes: Vec<u8>) {
+        self.defer(slot, Deferred { at: 0, elem_size, bytes, nul: false });
+    }
+
+    pub fn string(&mut self, slot: usize, s: &str) {
+        self.defer(slot, Deferred { at: 0, elem_size: 1, bytes: s.as_bytes().to_vec(), nul: true });
+    }
+
+    fn defer(&mut self, slot: usize, mut obj: Deferred) {
+        self.inline(slot, 4, &[0; 4]);
+        obj.at = self.buf.len() - 4;
+        self.deferred.push(obj);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let table_size = (self.buf.len() - self.table) as u16;
+        let mut vt = self.vtable;
+        for v in [(4 + 2 * self.slots.len()) as u16, table_size].into_iter().chain(self.slots.iter().copied()) {
+            self.buf[vt..vt + 2].copy_from_slice(&v.to_le_bytes());
+            vt += 2;
+        }
+        for obj in std::mem::take(&mut self.deferred) {
+            // Length word just before the first element, which is aligned
+            pad_to(&mut self.buf, obj.elem_size.max(4), 4);
+            let start = self.buf.len();
+            self.buf[obj.at..obj.at + 4].copy_from_slice(&((start - obj.at) as u32).to_le_bytes());
+            self.buf.extend_from_slice(&((obj.bytes.len() / obj.elem_size) as u32).to_le_bytes());
+            self.buf.extend_from_slice(&obj.bytes);
+            if obj.nul {
+                self.buf.push(0);
+            }
+        }
+        let table = self.table as u32;
+        self.buf[0..4].copy_from_slice(&table.to_le_bytes());
+        self.buf
+    }
+}
+
+// Pad until `buf.len() % align == rem`
+fn pad_to(buf: &mut Vec<u8>, align: usize, rem: usize) {
+    while buf.len() % align != rem % align {
+        buf.push(0);
+    }
+}
+
+/// The root table of a buffer, read in place
+#[derive(Clone, Copy)]
+pub struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    vtable: usize,
+    vtable_len: usize,
+}
+
+impl<'a> Table<'a> {
+    /// None if `buf` is too short or its offsets point outside it
+    pub fn root(buf: &'a [u8]) -> Option<Self> {
+        let pos = read_u32(buf, 0)? as usize;
+        let vtable = i64::try_from(pos).ok()? - read_i32(buf, pos)? as i64;
+        let vtable = usize::try_from(vtable).ok()?;
+        let vtable_len = read_u16(buf, vtable)? as usize;
+        if vtable_len < 4 || buf.len() < vtable + vtable_len {
+            return None;
+        }
+        Some(Table { buf, pos, vtable, vtable_len })
+    }
+
+    /// Position of field `slot` in the buffer, None if absent
+    pub fn field(&self, slot: usize) -> Option<usize> {
+        let entry = 4 + 2 * slot;
+        if entry + 2 > self.vtable_len {
+            return None;
+        }
+        match read_u16(self.buf, self.vtable + entry)? {
+            0 => None,
+            off => Some(self.pos + off as usize),
+        }
+    }
+
+    /// `len` bytes of field `slot` stored inline
+    pub fn inline(&self, slot: usize, len: usize) -> Option<Option<&'a [u8]>> {
+        match self.field(slot) {
+            Some(at) => Some(Some(self.buf.get(at..at + len)?)),
+            None => Some(None),
+        }
+    }
+
+    /// Elements of a string or vector field (`size` bytes each; a string's
+    /// bytes without the NUL). None if malformed, Some(None) if absent.
+    pub fn vector(&self, slot: usize, size: usize) -> Option<Option<&'a [u8]>> {
+        let at = match self.field(slot) {
+            Some(at) => at,
+            None => return Some(None),
+        };
+        let start = at.checked_add(read_u32(self.buf, at)? as usize)?;
+        let count = read_u32(self.buf, start)? as usize;
+        let body = start + 4;
+        Some(Some(self.buf.get(body..body.checked_add(count.checked_mul(size)?)?)?))
+    }
+}
+
+fn read_u16(buf: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(buf.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(buf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_i32(buf: &[u8], at: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// A message field as a table slot; `get_fb` is None on a malformed buffer
+pub trait FbField: Sized {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder);
+    fn get_fb(t: &Table, slot: usize) -> Option<Self>;
+}
+
+/// Element of a numeric vector
+pub trait FbScalar: Copy + Default {
+    const SIZE: usize;
+    fn put_le(self, out: &mut Vec<u8>);
+    fn get_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! fb_scalar {
+    ($($t:ty),*) => {$(
+        impl FbScalar for $t {
+            const SIZE: usize = std::mem::size_of::<$t>();
+            fn put_le(self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn get_le(bytes: &[u8]) -> Self {
+                let mut le = [0u8; std::mem::size_of::<$t>()];
+                le.copy_from_slice(&bytes[..Self::SIZE]);
+                <$t>::from_le_bytes(le)
+            }
+        }
+
+        impl FbField for $t {
+            fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+                b.inline(slot, Self::SIZE, &self.to_le_bytes());
+            }
+            fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+                Some(t.inline(slot, Self::SIZE)?.map_or_else(Self::default, Self::get_le))
+            }
+        }
+    )*};
+}
+
+fb_scalar!(u8, i32, i64, u32, u64, f32, f64);
+
+impl FbField for bool {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+        b.inline(slot, 1, &[*self as u8]);
+    }
+    fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+        Some(t.inline(slot, 1)?.is_some_and(|v| v[0] != 0))
+    }
+}
+
+impl<T: FbScalar, const N: usize> FbField for [T; N] {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+        let mut bytes = Vec::with_capacity(N * T::SIZE);
+        for v in self {
+            v.put_le(&mut bytes);
+        }
+        b.vector(slot, T::SIZE, bytes);
+    }
+    fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+        let mut arr = [T::default(); N];
+        if let Some(bytes) = t.vector(slot, T::SIZE)? {
+            if bytes.len() > N * T::SIZE {
+                return None;
+            }
+            for (v, le) in arr.iter_mut().zip(bytes.chunks_exact(T::SIZE)) {
+                *v = T::get_le(le);
+            }
+        }
+        Some(arr)
+    }
+}
+
+impl FbField for Vec<u8> {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+        b.vector(slot, 1, self.clone());
+    }
+    fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+        Some(t.vector(slot, 1)?.map(<[u8]>::to_vec).unwrap_or_default())
+    }
+}
+
+impl FbField for String {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+        b.string(slot, self);
+    }
+    fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+        match t.vector(slot, 1)? {
+            Some(bytes) => String::from_utf8(bytes.to_vec()).ok(),
+            None => Some(String::new()),
+        }
+    }
+}
+
+impl<const N: usize> FbField for InteropString<N> {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+        b.string(slot, self.as_str());
+    }
+    fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+        Some(InteropString::from(String::get_fb(t, slot)?.as_str()))
+    }
+}
+
+/// struct Decimal { units:long; scale:int; } - 16 bytes, aligned to 8
+impl FbField for Decimal {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.units.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.scale.to_le_bytes());
+        b.inline(slot, 8, &bytes);
+    }
+    fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+        Some(match t.inline(slot, 16)? {
+            Some(v) => Decimal::new(i64::get_le(&v[..8]), i32::get_le(&v[8..12])),
+            None => Decimal::default(),
+        })
+    }
+}
+
+/// struct Uuid { bytes:[ubyte:16]; }
+impl FbField for Uuid {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+        b.inline(slot, 1, &self.bytes);
+    }
+    fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+        Some(match t.inline(slot, 16)? {
+            Some(v) => Uuid::from_bytes(v.try_into().ok()?),
+            None => Uuid::default(),
+        })
+    }
+}
+
+/// struct Timestamp { nanos:long; }
+impl FbField for Timestamp {
+    fn put_fb(&self, slot: usize, b: &mut TableBuilder) {
+        b.inline(slot, 8, &self.nanos.to_le_bytes());
+    }
+    fn get_fb(t: &Table, slot: usize) -> Option<Self> {
+        Some(Timestamp::from_nanos(i64::get_fb(t, slot)?))
+    }
+}
@@ -0,0 +1,297 @@
+//! Freeze a Rust actor and edit what is waiting in its mailbox
+//!
+//! A subscriber stuck behind a poison message shows only a growing queue
+//! depth. Freezing it stops it handling messages and collects what reaches
+//! it, so the queue can be read, cut down and let go:
+//!
+//! ```ignore
+//! freeze::freeze("rust_price_monitor");
+//! for p in freeze::pending("rust_price_monitor").unwrap() {
+//!     println!("{} {} from {:?}: {:?}", p.index, p.msg_id, p.sender, p.payload);
+//! }
+//! freeze::remove("rust_price_monitor", 3);      // the poison message
+//! freeze::move_to("rust_price_monitor", 5, 0);  // handle this one first
+//! freeze::thaw("rust_price_monitor");
+//! ```
+//!
+//! ```text
+//! interop-ctl freeze rust_price_monitor
+//! interop-ctl pending rust_price_monitor
+//! interop-ctl drop rust_price_monitor 3
+//! interop-ctl thaw rust_price_monitor
+//! ```
+//!
+//! - While frozen, the actor's supervisor (see lifecycle) takes each
+//!   interop message off the mailbox as its thread reaches it and keeps a
+//!   copy instead of handling it, in mailbox order, whichever side sent
+//!   it. Start, End and other runtime messages are still handled.
+//! - Messages are collected as the actor's thread gets to them: those
+//!   behind a handler that never returns are not seen.
+//! - On thaw, a marker is queued behind everything already sent; when the
+//!   actor reaches it, it handles the kept messages in their (edited)
+//!   order, then the rest of its mailbox. Freezing again before that
+//!   cancels the thaw.
+//! - Senders are known for messages with an envelope (see envelope);
+//!   replies from a kept message go to no one.
+//!
+//! Unlike pause (see pause), which holds bridge messages before they reach
+//! the mailbox, freezing sees Rust -> Rust sends and what was queued
+//! before. C++ uses `rust_actor_freeze()` and friends.
+
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
+use std::fmt::Write;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use actors::Message;
+
+use crate::codec::JsonValue;
+use crate::envelope;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{clone_message, message_to_debug_json};
+use crate::names;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::schema;
+use crate::shared_thread;
+
+/// One message kept from a frozen actor
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingMessage {
+    /// Position, 0 handled first on thaw
+    pub index: usize,
+    pub msg_id: i32,
+    /// Message type name, None if unknown to the schema
+    pub message: Option<&'static str>,
+    /// Sending actor, if the message had an envelope that named one
+    pub sender: Option<String>,
+    /// Fields as JSON (see `message_to_debug_json`)
+    pub payload: Option<String>,
+}
+
+struct Kept {
+    msg: Box<dyn Message>,
+    sender: Option<String>,
+}
+
+struct Frozen {
+    kept: VecDeque<Kept>,
+    // Thaw queued; the actor keeps collecting until it reaches the marker
+    thawing: bool,
+}
+
+/// Queued by `thaw()`; handled by the actor's supervisor
+pub(crate) struct Thaw;
+
+// Only the supervisor looks for it
+impl Message for Thaw {
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+    fn message_id(&self) -> i32 { 0 }
+}
+
+// Fast path: no lock while no actor is frozen
+static ANY_FROZEN: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref FROZEN: Mutex<HashMap<String, Frozen>> = Mutex::new(HashMap::new());
+}
+
+/// Stop `name` handling messages and keep them for inspection. Returns
+/// false if it was already frozen (a pending thaw is cancelled).
+pub fn freeze(name: &str) -> bool {
+    let mut frozen = FROZEN.lock().unwrap();
+    if let Some(f) = frozen.get_mut(name) {
+        let was_thawing = std::mem::replace(&mut f.thawing, false);
+        return was_thawing;
+    }
+    frozen.insert(name.to_string(), Frozen { kept: VecDeque::new(), thawing: false });
+    ANY_FROZEN.store(true, Ordering::Release);
+    true
+}
+
+/// Frozen and not being thawed
+pub fn is_frozen(name: &str) -> bool {
+    ANY_FROZEN.load(Ordering::Acquire) && FROZEN.lock().unwrap().get(name).is_some_and(|f| !f.thawing)
+}
+
+/// What has been kept from `name`, in the order it will be handled; None
+/// if it is not frozen
+pub fn pending(name: &str) -> Option<Vec<PendingMessage>> {
+    let frozen = FROZEN.lock().unwrap();
+    let kept = &frozen.get(name)?.kept;
+    Some(
+        kept.iter()
+            .enumerate()
+            .map(|(index, k)| PendingMessage {
+                index,
+                msg_id: k.msg.message_id(),
+                message: schema::by_id(k.msg.message_id()).map(|s| s.name),
+                sender: k.sender.clone(),
+                payload: message_to_debug_json(k.msg.as_ref()),
+            })
+            .collect(),
+    )
+}
+
+/// pending() as a JSON array, None if `name` is not frozen
+pub fn pending_json(name: &str) -> Option<String> {
+    let mut out = String::from("[");
+    for (i, p) in pending(name)?.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "{{\"index\":{},\"msg_id\":{},\"message\":", p.index, p.msg_id);
+        match p.message {
+            Some(m) => m.write_json(&mut out),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"sender\":");
+        match &p.sender {
+            Some(s) => s.write_json(&mut out),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"payload\":");
+        out.push_str(p.payload.as_deref().unwrap_or("null"));
+        out.push('}');
+    }
+    out.push(']');
+    Some(out)
+}
+
+/// Discard kept message `index`. False if `name` is not frozen or there
+/// is no such message.
+pub fn remove(name: &str, index: usize) -> bool {
+    let mut frozen = FROZEN.lock().unwrap();
+    frozen.get_mut(name).and_then(|f| f.kept.remove(index)).is_some()
+}
+
+/// Move kept message `from` to position `to`, shifting those between
+pub fn move_to(name: &str, from: usize, to: usize) -> bool {
+    let mut frozen = FROZEN.lock().unwrap();
+    let kept = match frozen.get_mut(name) {
+        Some(f) if from < f.kept.len() && to < f.kept.len() => &mut f.kept,
+        _ => return false,
+    };
+    if let Some(k) = kept.remove(from) {
+        kept.insert(to, k);
+    }
+    true
+}
+
+/// Let `name` handle its kept messages, then its mailbox. Returns how many
+/// are kept so far, None if it is not frozen or cannot be reached.
+pub fn thaw(name: &str) -> Option<usize> {
+    let count = {
+        let mut frozen = FROZEN.lock().unwrap();
+        let f = frozen.get_mut(name).filter(|f| !f.thawing)?;
+        f.thawing = true;
+        f.kept.len()
+    };
+    let sent = if shared_thread::group_of(name).is_some() {
+        shared_thread::send_to_member(name, Box::new(Thaw))
+    } else {
+        get_actor_ref(name, "").map(|r| r.send(Box::new(Thaw), None)).is_some()
+    };
+    if !sent {
+        // Nothing would ever reach the marker
+        let mut frozen = FROZEN.lock().unwrap();
+        frozen.remove(name);
+        ANY_FROZEN.store(!frozen.is_empty(), Ordering::Release);
+        return None;
+    }
+    Some(count)
+}
+
+/// Keep `msg` if `name` is frozen. Called by the supervisor before
+/// handling a message; false to handle it.
+pub(crate) fn divert(name: &str, msg: &dyn Message) -> bool {
+    if !ANY_FROZEN.load(Ordering::Acquire) {
+        return false;
+    }
+    let mut frozen = FROZEN.lock().unwrap();
+    let f = match frozen.get_mut(name) {
+        Some(f) => f,
+        None => return false,
+    };
+    match clone_message(msg) {
+        Some(copy) => {
+            let sender = envelope::current().and_then(|e| e.sender);
+            f.kept.push_back(Kept { msg: copy, sender });
+            true
+        }
+        None => false,  // Start, End and other runtime messages
+    }
+}
+
+/// The kept messages of `name` once its thaw marker is reached, which ends
+/// the freeze. Empty if the thaw was cancelled.
+pub(crate) fn take_thawed(name: &str) -> Vec<Box<dyn Message>> {
+    let mut frozen = FROZEN.lock().unwrap();
+    if !frozen.get(name).is_some_and(|f| f.thawing) {
+        return Vec::new();
+    }
+    let f = frozen.remove(name).unwrap();
+    ANY_FROZEN.store(!frozen.is_empty(), Ordering::Release);
+    f.kept.into_iter().map(|k| k.msg).collect()
+}
+
+fn rust_name<'a>(name: *const c_char) -> Option<&'a str> {
+    names::c_str(name).filter(|name| names::is_rust(name))
+}
+
+/// Freeze Rust actor `actor_name`. Returns 0 (also if already frozen), or
+/// NotFound if it is not a Rust actor.
+#[export_name = c_symbol!("rust_actor_freeze")]
+pub extern "C" fn rust_actor_freeze(actor_name: *const c_char) -> c_int {
+    match rust_name(actor_name) {
+        Some(name) => {
+            if freeze(name) {
+                eprintln!("[Freeze] {} frozen", name);
+            }
+            InteropErrorCode::Ok as c_int
+        }
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Messages kept from frozen actor `actor_name` as a JSON array; null if
+/// it is not frozen. Free with interop_free_json().
+#[export_name = c_symbol!("rust_actor_pending_json")]
+pub extern "C" fn rust_actor_pending_json(actor_name: *const c_char) -> *mut c_char {
+    rust_name(actor_name)
+        .and_then(pending_json)
+        .and_then(|json| CString::new(json).ok())
+        .map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// Discard kept message `index`. Returns 0, or NotFound if the actor is
+/// not frozen or has no such message.
+#[export_name = c_symbol!("rust_actor_pending_remove")]
+pub extern "C" fn rust_actor_pending_remove(actor_name: *const c_char, index: u32) -> c_int {
+    match rust_name(actor_name) {
+        Some(name) if remove(name, index as usize) => InteropErrorCode::Ok as c_int,
+        _ => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Move kept message `from` to position `to`. Returns 0, or NotFound if
+/// the actor is not frozen or either index is out of range.
+#[export_name = c_symbol!("rust_actor_pending_move")]
+pub extern "C" fn rust_actor_pending_move(actor_name: *const c_char, from: u32, to: u32) -> c_int {
+    match rust_name(actor_name) {
+        Some(name) if move_to(name, from as usize, to as usize) => InteropErrorCode::Ok as c_int,
+        _ => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Let `actor_name` handle its kept messages, then its mailbox. Returns
+/// how many were kept, or NotFound if it is not a frozen Rust actor.
+#[export_name = c_symbol!("rust_actor_thaw")]
+pub extern "C" fn rust_actor_thaw(actor_name: *const c_char) -> c_int {
+    match rust_name(actor_name).and_then(thaw) {
+        Some(count) => count.min(c_int::MAX as usize) as c_int,
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
@@ -0,0 +1,261 @@
+//! Named groups of actors, and groups of identical actors
+//!
+//! Any actor, Rust or C++, can join any number of named groups, so "all
+//! pricing actors" is a set both sides can query rather than a naming
+//! convention:
+//!
+//! ```ignore
+//! join_group("pricing", "rust_pricer");
+//! join_group("pricing", "cpp_pricer");
+//! let n = broadcast("pricing", "rust_risk", &RiskLimits { .. });
+//! let pricer = group_router("pricing", RoutingPolicy::RoundRobin, "rust_quoter");
+//! ```
+//!
+//! ```c
+//! interop_group_join("pricing", "cpp_pricer");   // interop/group.h
+//! ```
+//!
+//! Groups hold names and are resolved with `get_actor_ref` when used, so a
+//! member may join before it is registered. A group exists while it has
+//! members.
+//!
+//! `rust_manager_ffi::spawn_n("md_worker", factory, n)` registers `n`
+//! instances named `md_worker-0` .. `md_worker-{n-1}`, each on its own
+//! thread, and returns an `ActorGroup`. The group hands out its members as
+//! `ActorRef`s or as a `HashRouter`, so senders spread work across the
+//! instances without knowing how many there are:
+//!
+//! ```ignore
+//! let workers = spawn_n("md_worker", |i, handle| Box::new(MdWorker::new(i, handle)), 4).unwrap();
+//! let router = workers.router(symbol_key, "rust_feed");
+//! router.send(Box::new(update), None)?;
+//! ```
+//!
+//! Groups are also found by base name (`group("md_worker")`), and C++
+//! registers one from an `#[interop_actor]` type with `spawn_actor_group()`.
+//! The instances also join the named group `base`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use actors::{ActorRef, Message};
+
+use crate::hash_router::{HashRouter, KeyFn};
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::router::{Router, RoutingPolicy};
+use crate::rust_manager_ffi::get_actor_ref;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActorGroup {
+    base: String,
+    members: Vec<String>,
+}
+
+/// Name of instance `index` of group `base`
+pub fn member_name(base: &str, index: usize) -> String {
+    format!("{}-{}", base, index)
+}
+
+impl ActorGroup {
+    pub fn new(base: &str, n: usize) -> Self {
+        ActorGroup {
+            base: base.to_string(),
+            members: (0..n).map(|i| member_name(base, i)).collect(),
+        }
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// Member names, in index order
+    pub fn names(&self) -> &[String] {
+        &self.members
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// ActorRefs of the members, in index order, with `sender` for replies.
+    /// Members that cannot be found are skipped.
+    pub fn refs(&self, sender: &str) -> Vec<ActorRef> {
+        self.members.iter().filter_map(|name| get_actor_ref(name, sender)).collect()
+    }
+
+    /// Router over the members, keyed by `key_fn`
+    pub fn router(&self, key_fn: KeyFn, sender: &str) -> HashRouter {
+        let mut router = HashRouter::new(key_fn);
+        for name in &self.members {
+            if let Some(actor) = get_actor_ref(name, sender) {
+                router.add_member(name, actor);
+            }
+        }
+        router
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref GROUPS: Mutex<HashMap<String, ActorGroup>> = Mutex::new(HashMap::new());
+    // Named group -> member names, in join order
+    static ref MEMBERS: Mutex<BTreeMap<String, Vec<String>>> = Mutex::new(BTreeMap::new());
+}
+
+/// Remember a spawned group under its base name
+pub(crate) fn add(group: &ActorGroup) {
+    GROUPS.lock().unwrap().insert(group.base.clone(), group.clone());
+    for name in &group.members {
+        join_group(&group.base, name);
+    }
+}
+
+/// Add actor `name` to group `group`. Returns false if it was a member.
+pub fn join_group(group: &str, name: &str) -> bool {
+    let mut groups = MEMBERS.lock().unwrap();
+    let members = groups.entry(group.to_string()).or_default();
+    if members.iter().any(|m| m == name) {
+        return false;
+    }
+    members.push(name.to_string());
+    true
+}
+
+/// Remove actor `name` from group `group`. Returns false if it was not a member.
+pub fn leave_group(group: &str, name: &str) -> bool {
+    let mut groups = MEMBERS.lock().unwrap();
+    let members = match groups.get_mut(group) {
+        Some(m) => m,
+        None => return false,
+    };
+    let before = members.len();
+    members.retain(|m| m != name);
+    let left = members.len() != before;
+    if members.is_empty() {
+        groups.remove(group);
+    }
+    left
+}
+
+/// Remove actor `name` from every group, e.g. when it is gone for good
+pub fn leave_all_groups(name: &str) {
+    MEMBERS.lock().unwrap().retain(|_, members| {
+        members.retain(|m| m != name);
+        !members.is_empty()
+    });
+}
+
+/// Member names of group `group`, in join order; empty if there is none
+pub fn group_members(group: &str) -> Vec<String> {
+    MEMBERS.lock().unwrap().get(group).cloned().unwrap_or_default()
+}
+
+/// Groups that actor `name` is in, sorted
+pub fn groups_of(name: &str) -> Vec<String> {
+    MEMBERS.lock().unwrap().iter().filter(|(_, members)| members.iter().any(|m| m == name)).map(|(g, _)| g.clone()).collect()
+}
+
+/// Names of all groups, sorted
+pub fn group_names() -> Vec<String> {
+    MEMBERS.lock().unwrap().keys().cloned().collect()
+}
+
+/// ActorRefs of the members of `group`, with `sender` for replies.
+/// Members that cannot be found are skipped.
+pub fn group_refs(group: &str, sender: &str) -> Vec<ActorRef> {
+    group_members(group).iter().filter_map(|name| get_actor_ref(name, sender)).collect()
+}
+
+/// Router over the current members of `group`; later joins are not seen
+pub fn group_router(group: &str, policy: RoutingPolicy, sender: &str) -> Router {
+    let mut router = Router::new(policy);
+    for name in group_members(group) {
+        if let Some(actor) = get_actor_ref(&name, sender) {
+            router.add_member(&name, actor);
+        }
+    }
+    router
+}
+
+/// Send a copy of `msg` from `sender` to every member of `group`.
+/// Returns how many members were found and sent to.
+pub fn broadcast<M: Message + Clone>(group: &str, sender: &str, msg: &M) -> usize {
+    let mut sent = 0;
+    for name in group_members(group) {
+        if let Some(actor) = get_actor_ref(&name, sender) {
+            let reply_to = if sender.is_empty() { None } else { get_actor_ref(sender, &name) };
+            actor.send(Box::new(msg.clone()), reply_to);
+            sent += 1;
+        }
+    }
+    sent
+}
+
+fn c_name<'a>(name: *const c_char) -> Option<&'a str> {
+    c_str(name).filter(|s| !s.is_empty())
+}
+
+/// Group spawned under `base`
+pub fn group(base: &str) -> Option<ActorGroup> {
+    GROUPS.lock().unwrap().get(base).cloned()
+}
+
+/// Number of instances in group `base`, NotFound if there is none, or
+/// InvalidArgument on a bad name
+#[export_name = c_symbol!("interop_group_size")]
+pub extern "C" fn interop_group_size(base: *const c_char) -> c_int {
+    match c_str(base).map(group) {
+        Some(Some(g)) => g.len().min(c_int::MAX as usize) as c_int,
+        Some(None) => InteropErrorCode::NotFound as c_int,
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Add actor `actor_name` (either language) to group `group`. Returns 0,
+/// 1 if it was already a member, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_group_join")]
+pub extern "C" fn interop_group_join(group: *const c_char, actor_name: *const c_char) -> c_int {
+    match (c_name(group), c_name(actor_name)) {
+        (Some(g), Some(a)) if join_group(g, a) => 0,
+        (Some(_), Some(_)) => 1,
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Remove `actor_name` from `group`. Returns 0, NotFound if it was not a
+/// member, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_group_leave")]
+pub extern "C" fn interop_group_leave(group: *const c_char, actor_name: *const c_char) -> c_int {
+    let rc = match (c_name(group), c_name(actor_name)) {
+        (Some(g), Some(a)) if leave_group(g, a) => InteropErrorCode::Ok,
+        (Some(_), Some(_)) => InteropErrorCode::NotFound,
+        _ => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Write the members of `group` into `out` as newline-separated names
+/// (NUL-terminated, truncated to fit `len`). Returns the full length like
+/// snprintf, 0 for a group with no members, or InvalidArgument on a bad
+/// name.
+#[export_name = c_symbol!("interop_group_members")]
+pub extern "C" fn interop_group_members(group: *const c_char, out: *mut c_char, len: usize) -> c_int {
+    let group = match c_name(group) {
+        Some(g) => g,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let text = group_members(group).join("\n");
+    if !out.is_null() && len > 0 {
+        let n = text.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(text.as_ptr(), out as *mut u8, n);
+            *out.add(n) = 0;
+        }
+    }
+    text.len().min(c_int::MAX as usize) as c_int
+}
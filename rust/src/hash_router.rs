@@ -0,0 +1,221 @@
+//! Consistent-hash routing across worker actors
+//!
+//! `HashRouter` is used like an `ActorRef`: `send()` picks one member by
+//! hashing a key taken from the message, so every message for the same key
+//! (e.g. a symbol) goes to the same worker and keeps its order, while
+//! different keys spread across workers. Members are plain `ActorRef`s and
+//! can be Rust or C++ actors.
+//!
+//! Members sit on a hash ring with `VNODES` points each, so adding or
+//! removing a worker only moves about 1/N of the keys. Messages without a
+//! key are spread round-robin and have no ordering guarantee.
+//!
+//! ```ignore
+//! let mut router = HashRouter::new(symbol_key);
+//! for name in ["worker_0", "cpp_worker_1"] {
+//!     router.add_member(name, get_actor_ref(name, "rust_feed").unwrap());
+//! }
+//! router.send(Box::new(update), None)?;
+//! ```
+//!
+//! A router registered under a name is an actor to everyone else:
+//! `get_actor_ref()` returns an `ActorRef` for it and C++ sends through
+//! the bridge reach it, so it can subscribe to a topic or be handed out as
+//! a send target in place of the workers:
+//!
+//! ```ignore
+//! router.register("quote_workers");
+//! publisher.subscribe("quotes", get_actor_ref("quote_workers", "").unwrap());
+//! ```
+//!
+//! Each send through the name routes a copy of the message. A router name
+//! hides an actor of the same name. To change the members of a registered
+//! router, register a new one under the name.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use actors::{ActorRef, CppActorRef, Message};
+
+use crate::dead_letters::{self, Route};
+use crate::health;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{clone_message, MarketDepth, MarketUpdate, MSG_MARKETDEPTH, MSG_MARKETUPDATE};
+use crate::names;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::send_error::{SendError, TrySend};
+
+/// Ring points per member
+pub const VNODES: usize = 64;
+
+/// Routing key for a message, None if it has none
+pub type KeyFn = fn(&dyn Message) -> Option<String>;
+
+/// Key by `symbol` for MarketUpdate and MarketDepth
+pub fn symbol_key(msg: &dyn Message) -> Option<String> {
+    match msg.message_id() {
+        MSG_MARKETUPDATE => msg.as_any().downcast_ref::<MarketUpdate>().map(|m| m.symbol.as_str().to_string()),
+        MSG_MARKETDEPTH => msg.as_any().downcast_ref::<MarketDepth>().map(|m| m.symbol.as_str().to_string()),
+        _ => None,
+    }
+}
+
+// FNV-1a: stable across processes and Rust versions, unlike DefaultHasher
+pub(crate) fn hash(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+pub struct HashRouter {
+    key_fn: KeyFn,
+    members: Vec<(String, ActorRef)>,
+    // (point, index into members), sorted by point
+    ring: Vec<(u64, usize)>,
+    next: AtomicUsize,
+}
+
+impl HashRouter {
+    pub fn new(key_fn: KeyFn) -> Self {
+        HashRouter {
+            key_fn,
+            members: Vec::new(),
+            ring: Vec::new(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Add a worker; replaces an existing member with the same name
+    pub fn add_member(&mut self, name: &str, actor: ActorRef) {
+        self.members.retain(|(n, _)| n != name);
+        self.members.push((name.to_string(), actor));
+        self.rebuild();
+    }
+
+    /// Remove a worker; its keys move to the remaining members
+    pub fn remove_member(&mut self, name: &str) -> bool {
+        let before = self.members.len();
+        self.members.retain(|(n, _)| n != name);
+        if self.members.len() == before {
+            return false;
+        }
+        self.rebuild();
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    fn rebuild(&mut self) {
+        self.ring.clear();
+        for (idx, (name, _)) in self.members.iter().enumerate() {
+            for v in 0..VNODES {
+                self.ring.push((hash(format!("{}#{}", name, v).as_bytes()), idx));
+            }
+        }
+        self.ring.sort_unstable();
+    }
+
+    /// Name of the member that owns `key`
+    pub fn member_for(&self, key: &str) -> Option<&str> {
+        self.index_for(key).map(|i| self.members[i].0.as_str())
+    }
+
+    fn index_for(&self, key: &str) -> Option<usize> {
+        if self.ring.is_empty() {
+            return None;
+        }
+        let h = hash(key.as_bytes());
+        let pos = self.ring.partition_point(|(point, _)| *point < h);
+        Some(self.ring[pos % self.ring.len()].1)
+    }
+
+    /// Route a message to its member, with that member's send result.
+    /// ActorNotFound if there are no members.
+    pub fn send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) -> Result<(), SendError> {
+        let idx = match (self.key_fn)(msg.as_ref()) {
+            Some(key) => self.index_for(&key),
+            None if !self.members.is_empty() => {
+                Some(self.next.fetch_add(1, Ordering::Relaxed) % self.members.len())
+            }
+            None => None,
+        };
+        match idx {
+            Some(i) => dead_letters::via(Route::Router, || self.members[i].1.try_send(msg, sender)),
+            None => Err(SendError::ActorNotFound),
+        }
+    }
+
+    /// Make the router reachable as actor `name` (see the module docs),
+    /// replacing a router registered under it. False if `name` cannot
+    /// cross to C (see names).
+    pub fn register(self, name: &str) -> bool {
+        if names::validate(name).is_err() {
+            return false;
+        }
+        ROUTERS.write().unwrap().insert(name.to_string(), Arc::new(self));
+        ANY_ROUTERS.store(true, Ordering::Release);
+        true
+    }
+}
+
+static ANY_ROUTERS: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref ROUTERS: RwLock<HashMap<String, Arc<HashRouter>>> = RwLock::new(HashMap::new());
+}
+
+/// Remove the router registered as `name`. False if there was none.
+pub fn unregister(name: &str) -> bool {
+    let mut all = ROUTERS.write().unwrap();
+    let removed = all.remove(name).is_some();
+    ANY_ROUTERS.store(!all.is_empty(), Ordering::Release);
+    removed
+}
+
+/// Remove every registered router
+pub fn clear() {
+    ROUTERS.write().unwrap().clear();
+    ANY_ROUTERS.store(false, Ordering::Release);
+}
+
+/// Route a copy of a send to a registered router. NotFound if the router
+/// is gone or has no members, else the member's send result;
+/// UnknownMessage if `msg` is not an interop message.
+fn router_send_fn(name: &str, sender: &str, msg: &dyn Message) -> i32 {
+    let router = match ROUTERS.read().unwrap().get(name).cloned() {
+        Some(router) => router,
+        None => return InteropErrorCode::NotFound as i32,
+    };
+    let copy = match clone_message(msg) {
+        Some(c) => c,
+        None => return InteropErrorCode::UnknownMessage as i32,
+    };
+    let sender_ref = if sender.is_empty() { None } else { get_actor_ref(sender, name) };
+    match router.send(copy, sender_ref) {
+        Ok(()) => 0,
+        Err(SendError::ActorNotFound) if router.is_empty() => {
+            health::record_dead_letter(sender, name, msg.message_id());
+            InteropErrorCode::NotFound as i32
+        }
+        Err(e) => e.code(),
+    }
+}
+
+/// ActorRef for a registered router, None for any other name. Lookups
+/// from Rust (`get_actor_ref`) and sends from C++ use this.
+pub fn router_ref(name: &str, sender: &str) -> Option<ActorRef> {
+    if !ANY_ROUTERS.load(Ordering::Acquire) || !ROUTERS.read().unwrap().contains_key(name) {
+        return None;
+    }
+    Some(ActorRef::Cpp(CppActorRef::new(name, sender, router_send_fn)))
+}
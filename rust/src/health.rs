@@ -0,0 +1,354 @@
+//! Health summary of the Rust side, for the C++ host's health endpoint
+//!
+//! `rust_interop_health(out_json, len)` writes one JSON object:
+//!
+//! ```json
+//! {"manager":"running",
+//!  "actors":[{"name":"rust_pong","tenant":null,"status":"running","queue_depth":0,"alloc_bytes":0}],
+//!  "groups":{"pricing":["rust_pricer","cpp_pricer"]},
+//!  "dead_letters":0,
+//!  "invalid_messages":0,
+//!  "strings":{"truncated":0,"repaired":0},
+//!  "peer":{"alive":true,"last_heartbeat_ms_ago":120},
+//!  "transport_errors":0,
+//!  "boundary":{"to_rust":{"conversions":1200,"bytes":76800},"to_cpp":{"conversions":40,"bytes":2560},
+//!              "messages":[{"msg_id":1012,"name":"MarketUpdate","to_rust":1200,"to_cpp":40,"avg_bytes":64}]},
+//!  "probes":[{"id":1,"target":"cpp_echo","sent":60,"echoed":60,"lost":0,...,"p99_us":41.5}],
+//!  "capabilities":{"abi_version":1,"peer_abi_version":1,"schema_hash":"5d3c0a9e21f4b7c6",...}}
+//! ```
+//!
+//! - `status`: from the actor's last lifecycle event (see lifecycle):
+//!   `registered` before the Manager runs, `starting` until it has handled
+//!   Start, `running`, `panicked` if it panicked and its restart failed,
+//!   and `stopped`. `dormant` for a running actor that is passivated (see
+//!   passivation).
+//! - `tenant`: the actor's tenant label, if any. Within a tenant view only
+//!   its actors and group members are listed (see tenant).
+//! - `alloc_bytes`: bytes the bridge holds for messages queued for the
+//!   actor (see alloc_stats).
+//! - `groups`: named groups and their members (see group).
+//! - `dead_letters`: sends in either direction whose target did not exist.
+//!   The last ones are kept with their origin, route and failure stage
+//!   (see dead_letters).
+//! - `invalid_messages`: messages from C++ that broke a field rule (see
+//!   validation) and were dropped.
+//! - `strings`: text cut to fit a char field, and char fields from C++
+//!   that were not NUL-terminated UTF-8 and were repaired (see
+//!   interop_string).
+//! - `transport_errors`: sends that failed for any other reason (unknown
+//!   message ID, failed downcast, C++ rejected the message).
+//! - `peer`: the C++ runtime calls `rust_interop_heartbeat()` periodically;
+//!   it is reported dead after `PEER_TIMEOUT_MS` without one.
+//! - `boundary`: messages converted between C struct and Rust type each
+//!   way, their bytes, and the average size per message type (see
+//!   boundary).
+//! - `probes`: round-trip counts and percentiles of each running latency
+//!   probe (see probe).
+//! - `capabilities`: what was negotiated with the C++ peer: versions,
+//!   schema hashes, features, transports (see capabilities).
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use crate::alloc_stats;
+use crate::boundary;
+use crate::capabilities;
+use crate::clock;
+use crate::dead_letters::{self, Stage};
+use crate::group;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_string;
+use crate::lifecycle::LifecycleEvent;
+use crate::passivation;
+use crate::probe;
+use crate::queue_depth;
+use crate::sys_topics;
+use crate::tenant;
+use crate::validation;
+
+/// Peer is reported dead after this long without a heartbeat
+pub const PEER_TIMEOUT_MS: u64 = 5000;
+
+/// Lifecycle of the Rust Manager owned by rust_manager_ffi
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ManagerState {
+    None = 0,
+    Created = 1,
+    Running = 2,
+    Stopped = 3,
+}
+
+impl ManagerState {
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => ManagerState::Created,
+            2 => ManagerState::Running,
+            3 => ManagerState::Stopped,
+            _ => ManagerState::None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ManagerState::None => "none",
+            ManagerState::Created => "created",
+            ManagerState::Running => "running",
+            ManagerState::Stopped => "stopped",
+        }
+    }
+}
+
+static MANAGER_STATE: AtomicU8 = AtomicU8::new(ManagerState::None as u8);
+static DEAD_LETTERS: AtomicU64 = AtomicU64::new(0);
+static INVALID_MESSAGES: AtomicU64 = AtomicU64::new(0);
+static TRANSPORT_ERRORS: AtomicU64 = AtomicU64::new(0);
+// 0 = never
+static LAST_HEARTBEAT_MS: AtomicU64 = AtomicU64::new(0);
+static ACTORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+lazy_static::lazy_static! {
+    // Last lifecycle event of each actor
+    static ref LAST_EVENTS: Mutex<HashMap<String, LifecycleEvent>> = Mutex::new(HashMap::new());
+}
+
+fn now_ms() -> u64 {
+    clock::wall().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+pub fn set_manager_state(state: ManagerState) {
+    MANAGER_STATE.store(state as u8, Ordering::Relaxed);
+}
+
+pub fn manager_state() -> ManagerState {
+    ManagerState::from_u8(MANAGER_STATE.load(Ordering::Relaxed))
+}
+
+/// Record an actor registered with the Rust Manager
+pub fn register_actor(name: &str) {
+    LAST_EVENTS.lock().unwrap().remove(name);
+    let mut actors = ACTORS.lock().unwrap();
+    if !actors.iter().any(|a| a == name) {
+        actors.push(name.to_string());
+    }
+}
+
+/// Number of actors registered with the Rust Manager
+pub fn actor_count() -> usize {
+    ACTORS.lock().unwrap().len()
+}
+
+/// Names of the actors registered with the Rust Manager, in order
+pub fn actor_names() -> Vec<String> {
+    ACTORS.lock().unwrap().clone()
+}
+
+/// Record `name`'s latest lifecycle event, for its status
+pub(crate) fn record_lifecycle(name: &str, event: LifecycleEvent) {
+    LAST_EVENTS.lock().unwrap().insert(name.to_string(), event);
+}
+
+/// Status of actor `name`: registered, starting, running, panicked or
+/// stopped (see the module docs)
+pub fn actor_status(name: &str) -> &'static str {
+    match LAST_EVENTS.lock().unwrap().get(name) {
+        Some(LifecycleEvent::Started | LifecycleEvent::Restarted) => "running",
+        Some(LifecycleEvent::Panicked) => "panicked",
+        Some(LifecycleEvent::Stopped) => "stopped",
+        None => match manager_state() {
+            ManagerState::Running => "starting",
+            ManagerState::Stopped => "stopped",
+            _ => "registered",
+        },
+    }
+}
+
+/// Forget every actor's lifecycle events
+pub(crate) fn clear_lifecycle() {
+    LAST_EVENTS.lock().unwrap().clear();
+}
+
+/// Record a send to missing actor `target`; published on
+/// `$sys/dead_letters` (see sys_topics)
+pub fn record_dead_letter(sender: &str, target: &str, msg_id: i32) {
+    DEAD_LETTERS.fetch_add(1, Ordering::Relaxed);
+    dead_letters::record(Stage::Lookup, sender, target, msg_id, InteropErrorCode::NotFound as i32);
+    sys_topics::dead_letter(target);
+}
+
+/// Record a message to `target` dropped by validation; published on
+/// `$sys/dead_letters` (see sys_topics)
+pub fn record_invalid_message(sender: &str, target: &str, msg_id: i32) {
+    INVALID_MESSAGES.fetch_add(1, Ordering::Relaxed);
+    dead_letters::record(Stage::Conversion, sender, target, msg_id, validation::INVALID_MESSAGE);
+    sys_topics::invalid_message(target, msg_id);
+}
+
+pub fn record_transport_error() {
+    TRANSPORT_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record a message from C++ with an ID this build has no type for
+pub fn record_unknown_message(sender: &str, target: &str, msg_id: i32) {
+    record_transport_error();
+    dead_letters::record(Stage::Conversion, sender, target, msg_id, InteropErrorCode::UnknownMessage as i32);
+}
+
+/// Classify a bridge return code: `NotFound` is a missing target,
+/// `UnknownMessage` and `WrongType` (no C struct for the message) failed
+/// conversion, `RuntimeDetached` a dead letter (its runtime went away),
+/// other negative codes are transport errors
+pub fn record_send_result(sender: &str, target: &str, msg_id: i32, rc: i32) {
+    match InteropErrorCode::from_code(rc) {
+        Some(InteropErrorCode::Ok) => {}
+        Some(InteropErrorCode::NotFound) => record_dead_letter(sender, target, msg_id),
+        Some(InteropErrorCode::UnknownMessage | InteropErrorCode::WrongType) => {
+            record_transport_error();
+            dead_letters::record(Stage::Conversion, sender, target, msg_id, rc);
+        }
+        Some(InteropErrorCode::InvalidName) => {
+            record_transport_error();
+            dead_letters::record(Stage::Name, sender, target, msg_id, rc);
+        }
+        Some(InteropErrorCode::RuntimeDetached) => {
+            DEAD_LETTERS.fetch_add(1, Ordering::Relaxed);
+            dead_letters::record(Stage::Detached, sender, target, msg_id, rc);
+            sys_topics::dead_letter(target);
+        }
+        _ => {
+            record_transport_error();
+            dead_letters::record(Stage::Transport, sender, target, msg_id, rc);
+        }
+    }
+}
+
+pub fn dead_letters() -> u64 {
+    DEAD_LETTERS.load(Ordering::Relaxed)
+}
+
+pub fn invalid_messages() -> u64 {
+    INVALID_MESSAGES.load(Ordering::Relaxed)
+}
+
+pub fn transport_errors() -> u64 {
+    TRANSPORT_ERRORS.load(Ordering::Relaxed)
+}
+
+/// Time since the last C++ heartbeat, None if there has been none
+pub fn heartbeat_age_ms() -> Option<u64> {
+    match LAST_HEARTBEAT_MS.load(Ordering::Relaxed) {
+        0 => None,
+        last => Some(now_ms().saturating_sub(last)),
+    }
+}
+
+/// Called by the C++ runtime to show it is alive
+#[export_name = c_symbol!("rust_interop_heartbeat")]
+pub extern "C" fn rust_interop_heartbeat() {
+    LAST_HEARTBEAT_MS.store(now_ms(), Ordering::Relaxed);
+}
+
+fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Build the health summary as a JSON string
+pub fn health_json() -> String {
+    let state = manager_state();
+
+    let mut out = String::with_capacity(256);
+    out.push_str("{\"manager\":");
+    push_json_str(&mut out, state.as_str());
+
+    out.push_str(",\"actors\":[");
+    let actors: Vec<String> = ACTORS.lock().unwrap().iter().filter(|name| tenant::visible(name)).cloned().collect();
+    for (i, name) in actors.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"name\":");
+        push_json_str(&mut out, name);
+        out.push_str(",\"tenant\":");
+        match tenant::tenant_of(name) {
+            Some(t) => push_json_str(&mut out, &t),
+            None => out.push_str("null"),
+        }
+        let status = actor_status(name);
+        let _ = write!(
+            out,
+            ",\"status\":\"{}\",\"queue_depth\":{},\"alloc_bytes\":{}}}",
+            if passivation::is_dormant(name) && status == "running" { "dormant" } else { status },
+            queue_depth::pending(name),
+            alloc_stats::actor_stats(name).map_or(0, |s| s.live_bytes)
+        );
+    }
+    out.push(']');
+
+    out.push_str(",\"groups\":{");
+    let groups = group::group_names().into_iter().filter_map(|name| {
+        let members: Vec<String> = group::group_members(&name).into_iter().filter(|m| tenant::visible(m)).collect();
+        (!members.is_empty() || tenant::in_view().is_none()).then_some((name, members))
+    });
+    for (i, (name, members)) in groups.enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_json_str(&mut out, &name);
+        out.push_str(":[");
+        for (j, member) in members.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            push_json_str(&mut out, member);
+        }
+        out.push(']');
+    }
+    out.push('}');
+
+    let _ = write!(out, ",\"dead_letters\":{}", dead_letters());
+    let _ = write!(out, ",\"invalid_messages\":{}", invalid_messages());
+    let _ = write!(out, ",\"strings\":{{\"truncated\":{},\"repaired\":{}}}", interop_string::truncated(), interop_string::repaired());
+
+    let last = LAST_HEARTBEAT_MS.load(Ordering::Relaxed);
+    if last == 0 {
+        out.push_str(",\"peer\":{\"alive\":false,\"last_heartbeat_ms_ago\":null}");
+    } else {
+        let ago = now_ms().saturating_sub(last);
+        let _ = write!(out, ",\"peer\":{{\"alive\":{},\"last_heartbeat_ms_ago\":{}}}", ago <= PEER_TIMEOUT_MS, ago);
+    }
+
+    let _ = write!(out, ",\"transport_errors\":{}", transport_errors());
+    let _ = write!(out, ",\"boundary\":{}", boundary::boundary_json());
+    let _ = write!(out, ",\"probes\":{}", probe::probes_json());
+    let _ = write!(out, ",\"capabilities\":{}}}", capabilities::summary().to_json());
+    out
+}
+
+/// Write the health summary into `out_json` (NUL-terminated, truncated to
+/// fit `len`). Returns the full JSON length like snprintf, so a return
+/// value >= len means the buffer was too small.
+#[export_name = c_symbol!("rust_interop_health")]
+pub extern "C" fn rust_interop_health(out_json: *mut c_char, len: usize) -> c_int {
+    let json = health_json();
+    if !out_json.is_null() && len > 0 {
+        let n = json.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(json.as_ptr(), out_json as *mut u8, n);
+            *out_json.add(n) = 0;
+        }
+    }
+    json.len().min(c_int::MAX as usize) as c_int
+}
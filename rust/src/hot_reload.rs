@@ -0,0 +1,237 @@
+//! Reload a C++ actor library without restarting the process (feature
+//! `hot_reload`)
+//!
+//! A long-running Rust host loads C++ actor code as a shared library and
+//! attaches it as a C++ runtime (see cpp_runtime). Upgrading the C++ code
+//! is then a reload instead of a restart:
+//!
+//! ```c
+//! interop_load_cpp_library("pricing", "./libpricing.so", "pricing.");
+//! ...
+//! interop_reload_cpp_library("pricing", "./libpricing-v2.so");  // NULL: same path
+//! ```
+//!
+//! Besides the generated bridge functions the library exports:
+//! - `int interop_plugin_init(void)`: create its Manager and actors, call
+//!   `cpp_actor_init()`, start them; 0 on success
+//! - `void interop_plugin_shutdown(void)`: stop its actors, call
+//!   `cpp_actor_shutdown()`
+//!
+//! A reload detaches the runtime, waits for calls into it to return, runs
+//! `interop_plugin_shutdown`, unloads the library, loads the new one, runs
+//! its `interop_plugin_init`, checks its capabilities and attaches it under
+//! the same name and prefix. Sends to its actors fail with NotFound in
+//! between.
+//! `ActorRef`s Rust actors hold for its actors keep working: they are
+//! routed by name on every send, and cached lookups are dropped.
+//!
+//! The C++ code linked into the process cannot be reloaded.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use libloading::Library;
+
+use crate::capabilities::{self, CapabilityError};
+use crate::cpp_runtime::{self, AttachError, CppExistsFn, CppRuntimeVTable, CppSendFn};
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+
+/// How long an unload waits for calls into the library to return
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+type PluginInitFn = unsafe extern "C" fn() -> c_int;
+type PluginShutdownFn = unsafe extern "C" fn();
+
+#[derive(Debug)]
+pub enum ReloadError {
+    NotLoaded(String),
+    AlreadyLoaded(String),
+    Open { path: PathBuf, reason: String },
+    MissingSymbol(&'static str),
+    /// interop_plugin_init() returned nonzero
+    Init(i32),
+    Capabilities(CapabilityError),
+    Attach(AttachError),
+    /// Calls into the old library did not return within DRAIN_TIMEOUT; it
+    /// stays loaded
+    Busy(String),
+}
+
+impl ReloadError {
+    /// The code the C functions return
+    pub fn code(&self) -> i32 {
+        let code = match self {
+            ReloadError::NotLoaded(_) => InteropErrorCode::NotFound,
+            ReloadError::AlreadyLoaded(_) | ReloadError::Attach(_) => InteropErrorCode::NameTaken,
+            ReloadError::Open { .. } => InteropErrorCode::InvalidArgument,
+            ReloadError::MissingSymbol(_) | ReloadError::Init(_) | ReloadError::Capabilities(_) => {
+                InteropErrorCode::Incompatible
+            }
+            ReloadError::Busy(_) => InteropErrorCode::Timeout,
+        };
+        code as i32
+    }
+}
+
+impl fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReloadError::NotLoaded(name) => write!(f, "no C++ library loaded as {}", name),
+            ReloadError::AlreadyLoaded(name) => write!(f, "a C++ library is already loaded as {}", name),
+            ReloadError::Open { path, reason } => write!(f, "cannot load {}: {}", path.display(), reason),
+            ReloadError::MissingSymbol(symbol) => write!(f, "library does not export {}", symbol),
+            ReloadError::Init(rc) => write!(f, "interop_plugin_init() returned {}", rc),
+            ReloadError::Capabilities(e) => write!(f, "{}", e),
+            ReloadError::Attach(e) => write!(f, "{}", e),
+            ReloadError::Busy(name) => write!(f, "calls into {} still running, not unloaded", name),
+        }
+    }
+}
+
+impl std::error::Error for ReloadError {}
+
+/// One loaded library
+struct Plugin {
+    path: PathBuf,
+    prefix: String,
+    library: Library,
+    shutdown: PluginShutdownFn,
+}
+
+lazy_static::lazy_static! {
+    // Runtime name -> its library
+    static ref PLUGINS: Mutex<HashMap<String, Plugin>> = Mutex::new(HashMap::new());
+}
+
+// Serializes load / reload / unload
+static RELOADING: Mutex<()> = Mutex::new(());
+
+fn symbol<T: Copy>(library: &Library, name: &'static str) -> Result<T, ReloadError> {
+    let c_name = format!("{}\0", name);
+    unsafe { library.get::<T>(c_name.as_bytes()) }
+        .map(|s| *s)
+        .map_err(|_| ReloadError::MissingSymbol(name))
+}
+
+/// Open `path`, start its actors and attach it as runtime `name`
+fn open(name: &str, path: &Path, prefix: &str) -> Result<Plugin, ReloadError> {
+    let library = unsafe { Library::new(path) }
+        .map_err(|e| ReloadError::Open { path: path.to_path_buf(), reason: e.to_string() })?;
+    let vtable = CppRuntimeVTable {
+        send: symbol::<CppSendFn>(&library, "cpp_actor_send")?,
+        fast_send: symbol::<CppSendFn>(&library, "cpp_actor_fast_send")?,
+        exists: symbol::<CppExistsFn>(&library, "cpp_actor_exists")?,
+    };
+    let init = symbol::<PluginInitFn>(&library, "interop_plugin_init")?;
+    let shutdown = symbol::<PluginShutdownFn>(&library, "interop_plugin_shutdown")?;
+
+    let rc = unsafe { init() };
+    if rc != 0 {
+        return Err(ReloadError::Init(rc));
+    }
+    let attached = capabilities::verify()
+        .map_err(ReloadError::Capabilities)
+        .and_then(|()| cpp_runtime::attach(name, prefix, vtable).map_err(ReloadError::Attach));
+    if let Err(e) = attached {
+        unsafe { shutdown() };
+        return Err(e);
+    }
+    Ok(Plugin { path: path.to_path_buf(), prefix: prefix.to_string(), library, shutdown })
+}
+
+/// Detach, stop and unload `plugin`. On Busy it is leaked rather than
+/// unloaded under a running call.
+fn close(name: &str, plugin: Plugin) -> Result<(), ReloadError> {
+    if !cpp_runtime::detach_and_wait(name, DRAIN_TIMEOUT) {
+        std::mem::forget(plugin.library);
+        return Err(ReloadError::Busy(name.to_string()));
+    }
+    unsafe { (plugin.shutdown)() };
+    if let Err(e) = plugin.library.close() {
+        eprintln!("[Hot Reload] {}: unloading {} failed: {}", name, plugin.path.display(), e);
+    }
+    Ok(())
+}
+
+/// Load the C++ actor library at `path` as runtime `name`; `prefix` as in
+/// cpp_runtime::attach()
+pub fn load(name: &str, path: &Path, prefix: &str) -> Result<(), ReloadError> {
+    let _reloading = RELOADING.lock().unwrap();
+    if PLUGINS.lock().unwrap().contains_key(name) {
+        return Err(ReloadError::AlreadyLoaded(name.to_string()));
+    }
+    let plugin = open(name, path, prefix)?;
+    PLUGINS.lock().unwrap().insert(name.to_string(), plugin);
+    Ok(())
+}
+
+/// Replace library `name` with the one at `path` (None: the same path)
+pub fn reload(name: &str, path: Option<&Path>) -> Result<(), ReloadError> {
+    let _reloading = RELOADING.lock().unwrap();
+    let old = PLUGINS.lock().unwrap().remove(name).ok_or_else(|| ReloadError::NotLoaded(name.to_string()))?;
+    let path = path.map_or_else(|| old.path.clone(), Path::to_path_buf);
+    let prefix = old.prefix.clone();
+    close(name, old)?;
+    let plugin = open(name, &path, &prefix)?;
+    PLUGINS.lock().unwrap().insert(name.to_string(), plugin);
+    eprintln!("[Hot Reload] {}: reloaded from {}", name, path.display());
+    Ok(())
+}
+
+/// Stop and unload library `name`
+pub fn unload(name: &str) -> Result<(), ReloadError> {
+    let _reloading = RELOADING.lock().unwrap();
+    let plugin = PLUGINS.lock().unwrap().remove(name).ok_or_else(|| ReloadError::NotLoaded(name.to_string()))?;
+    close(name, plugin)
+}
+
+/// Names of the loaded libraries
+pub fn loaded() -> Vec<String> {
+    PLUGINS.lock().unwrap().keys().cloned().collect()
+}
+
+fn status(name: &str, result: Result<(), ReloadError>) -> c_int {
+    match result {
+        Ok(()) => InteropErrorCode::Ok as c_int,
+        Err(e) => {
+            eprintln!("[Hot Reload] {}: {}", name, e);
+            e.code()
+        }
+    }
+}
+
+/// Load the C++ actor library at `path` as runtime `name`. `prefix` may be
+/// null. Returns 0, or an InteropErrorCode (reason logged, see
+/// ReloadError::code).
+#[export_name = c_symbol!("interop_load_cpp_library")]
+pub extern "C" fn interop_load_cpp_library(name: *const c_char, path: *const c_char, prefix: *const c_char) -> c_int {
+    match (c_str(name), c_str(path)) {
+        (Some(name), Some(path)) => status(name, load(name, Path::new(path), c_str(prefix).unwrap_or(""))),
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Reload library `name` from `path`, or from its current path if `path`
+/// is null. Returns 0, or an InteropErrorCode (reason logged).
+#[export_name = c_symbol!("interop_reload_cpp_library")]
+pub extern "C" fn interop_reload_cpp_library(name: *const c_char, path: *const c_char) -> c_int {
+    match c_str(name) {
+        Some(name) => status(name, reload(name, c_str(path).map(Path::new))),
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Stop and unload library `name`. Returns 0, or an InteropErrorCode
+/// (reason logged).
+#[export_name = c_symbol!("interop_unload_cpp_library")]
+pub extern "C" fn interop_unload_cpp_library(name: *const c_char) -> c_int {
+    match c_str(name) {
+        Some(name) => status(name, unload(name)),
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
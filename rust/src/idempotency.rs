@@ -0,0 +1,165 @@
+//! Replay protection for order-style messages
+//!
+//! A sender that times out waiting for an OrderAck resends the NewOrder,
+//! and the receiver must not execute it twice. Messages carry an
+//! idempotency key for this: an `int64_t` field marked
+//! `/* idempotency key */` in interop_messages.h (`NewOrder.order_id`),
+//! the same on every resend. With idempotency enabled for a Rust actor,
+//! the bridge remembers the keys it delivered:
+//!
+//! ```ignore
+//! idempotency::enable("rust_oms", Retention::new(Duration::from_secs(300), 100_000));
+//!
+//! // in rust_oms, once the order is handled
+//! let ack = OrderAck { order_id: order.order_id, accepted: true, reason: "".into(), client_order_id: order.client_order_id };
+//! idempotency::acknowledge("rust_oms", order.order_id, &ack);
+//! ctx.reply(Box::new(ack));
+//! ```
+//!
+//! - A message whose key was seen within the retention is not delivered.
+//!   If the actor acknowledged it, the recorded ack is sent to the sender
+//!   again; if it is still being handled, the replay is only dropped and
+//!   the ack goes out when the first delivery finishes.
+//! - Either way the send reports success, so the sender stops resending.
+//! - At most `max_entries` keys are kept, oldest dropped first. Key 0
+//!   means "no key" and is never treated as a replay.
+//!
+//! Checked by `rust_actor_send` / `rust_actor_fast_send` after dedup, and
+//! by `rust_actor_post` (which has no sender to re-ack).
+//! C++ enables it with `rust_actor_set_idempotency()`.
+
+use std::collections::{HashMap, VecDeque};
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actors::Message;
+
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{clone_message, idempotency_key};
+use crate::names::c_str;
+use crate::rust_manager_ffi::get_actor_ref;
+
+/// How long, and how many, keys an actor remembers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Retention {
+    pub ttl: Duration,
+    pub max_entries: usize,
+}
+
+impl Retention {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Retention { ttl, max_entries: max_entries.max(1) }
+    }
+}
+
+/// Keys seen by one actor, oldest first
+struct Registry {
+    retention: Retention,
+    order: VecDeque<(Instant, i64)>,
+    // None until the actor acknowledges the key
+    acks: HashMap<i64, Option<Box<dyn Message>>>,
+}
+
+impl Registry {
+    fn expire(&mut self, now: Instant) {
+        while let Some(&(at, key)) = self.order.front() {
+            if now.duration_since(at) < self.retention.ttl && self.order.len() < self.retention.max_entries {
+                break;
+            }
+            self.order.pop_front();
+            self.acks.remove(&key);
+        }
+    }
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static REPLAYS: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref REGISTRIES: Mutex<HashMap<String, Registry>> = Mutex::new(HashMap::new());
+}
+
+/// Detect replays to `actor` (replaces any earlier setting and its keys)
+pub fn enable(actor: &str, retention: Retention) {
+    let registry = Registry { retention, order: VecDeque::new(), acks: HashMap::new() };
+    REGISTRIES.lock().unwrap().insert(actor.to_string(), registry);
+    ACTIVE.store(true, Ordering::Release);
+}
+
+pub fn disable(actor: &str) {
+    let mut registries = REGISTRIES.lock().unwrap();
+    registries.remove(actor);
+    ACTIVE.store(!registries.is_empty(), Ordering::Release);
+}
+
+/// Whether `msg` for `actor` replays a key seen within the retention; if
+/// so the recorded ack is sent to `sender`, if not the key is recorded
+pub fn is_replay(actor: &str, sender: &str, msg: &dyn Message) -> bool {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return false;
+    }
+    let key = match idempotency_key(msg) {
+        Some(k) => k,
+        None => return false,
+    };
+    let ack = {
+        let mut registries = REGISTRIES.lock().unwrap();
+        let registry = match registries.get_mut(actor) {
+            Some(r) => r,
+            None => return false,
+        };
+        let now = clock::now();
+        registry.expire(now);
+        match registry.acks.get(&key) {
+            Some(ack) => ack.as_deref().and_then(clone_message),
+            None => {
+                registry.acks.insert(key, None);
+                registry.order.push_back((now, key));
+                return false;
+            }
+        }
+    };
+    REPLAYS.fetch_add(1, Ordering::Relaxed);
+    if let (Some(ack), false) = (ack, sender.is_empty()) {
+        match get_actor_ref(sender, actor) {
+            Some(sender_ref) => sender_ref.send(ack, None),
+            None => eprintln!("[Idempotency] {}: cannot re-ack key {} to {}", actor, key, sender),
+        }
+    }
+    true
+}
+
+/// Record `ack` as `actor`'s answer to `key`, sent again for replays.
+/// Ignored for keys not seen or already expired.
+pub fn acknowledge(actor: &str, key: i64, ack: &dyn Message) {
+    let mut registries = REGISTRIES.lock().unwrap();
+    if let Some(slot) = registries.get_mut(actor).and_then(|r| r.acks.get_mut(&key)) {
+        *slot = clone_message(ack);
+    }
+}
+
+/// Replays detected and not delivered
+pub fn replays() -> u64 {
+    REPLAYS.load(Ordering::Relaxed)
+}
+
+/// Answer messages to Rust actor `actor_name` whose idempotency key was
+/// seen in the last `retention_ms` ms, remembering at most `max_entries`
+/// keys. `retention_ms` 0 turns it off. Returns 0, or InvalidArgument on a
+/// bad name.
+#[export_name = c_symbol!("rust_actor_set_idempotency")]
+pub extern "C" fn rust_actor_set_idempotency(actor_name: *const c_char, retention_ms: u32, max_entries: usize) -> c_int {
+    let name = match c_str(actor_name) {
+        Some(s) => s,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    if retention_ms == 0 {
+        disable(name);
+    } else {
+        enable(name, Retention::new(Duration::from_millis(retention_ms as u64), max_entries));
+    }
+    InteropErrorCode::Ok as c_int
+}
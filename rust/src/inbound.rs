@@ -0,0 +1,118 @@
+//! Admission and delivery shared by every C++ -> Rust entry point
+//!
+//! `rust_actor_send` (and with it `rust_actor_send_id` and the wire),
+//! `rust_actor_fast_send`, `rust_actor_post`, `rust_actor_borrow_send` and
+//! lazy messages all go through here, so a bridge feature added to one of
+//! these steps applies to every way a message reaches a Rust actor:
+//!
+//! - `admit()`, before the target is looked up: the shutdown gate,
+//!   orphaned refs, the bridge policy (tenants and contracts included),
+//!   sender auth and the size limit, in that order. The first that refuses
+//!   gives the send's return code.
+//! - `deliver()`, once the message is converted: the interop log, taps,
+//!   lease renewal, broker bookkeeping and the journal see it, then it is
+//!   spilled to disk or counted in the queue depth and queued through the
+//!   subscriber queue and pause.
+//! - `fast_deliver()`: the same observers and journal, then the actor
+//!   handles it before the call returns, or it is held like any send
+//!   while the actor is paused.
+//!
+//! Converting, validating, dedup and idempotency stay with each entry
+//! point, which has the message in its own form.
+
+use std::os::raw::{c_int, c_void};
+
+use actors::{ActorRef, Message};
+
+use crate::boundary::Direction;
+use crate::sender_auth::{self, Entry};
+use crate::{
+    broker, interop_error, interop_log, journal, lease, orphans, pause, policy, queue_depth, shutdown, size_limit,
+    spill, sub_queue, tap,
+};
+
+/// Admit a message of type `msg_id` from `sender` ("" for none) for
+/// `target`, come in through `entry`. Err holds the send's return code.
+///
+/// # Safety
+/// `data` must point to a valid C struct for `msg_id`, or `msg_id` must be
+/// unknown
+pub(crate) unsafe fn admit(
+    target: &str,
+    sender: &str,
+    msg_id: c_int,
+    entry: Entry,
+    data: *const c_void,
+) -> Result<(), c_int> {
+    if !shutdown::accepting() {
+        return Err(shutdown::SHUTTING_DOWN);
+    }
+    orphans::check_send(sender, target);
+    if !policy::allow(sender, target, msg_id) || !sender_auth::admit(sender, target, msg_id, entry) {
+        return Err(policy::POLICY_DENIED);
+    }
+    if !size_limit::admit_c(msg_id, data) {
+        return Err(size_limit::MESSAGE_TOO_LARGE);
+    }
+    Ok(())
+}
+
+fn observe(target: &str, sender: &str, msg: &dyn Message) {
+    interop_log::conversion(Direction::ToRust, sender, target, msg);
+    tap::observe(sender, target, msg);
+    lease::observe(sender, target, msg);
+    broker::observe(sender, target, msg);
+}
+
+/// Queue an admitted, converted message for `actor`, named `target`
+pub(crate) fn deliver<M: Message>(actor: &ActorRef, target: &str, sender: &str, sender_ref: Option<ActorRef>, msg: M) {
+    observe(target, sender, &msg);
+    journal::append(target, &msg);
+    if spill::offer(target, sender, &msg) {
+        return;
+    }
+    enqueue(actor, target, sender, sender_ref, queue_depth::track(target, sender, msg));
+}
+
+/// `deliver()` for a message that is already boxed (see post)
+pub(crate) fn deliver_boxed(
+    actor: &ActorRef,
+    target: &str,
+    sender: &str,
+    sender_ref: Option<ActorRef>,
+    msg: Box<dyn Message>,
+) {
+    observe(target, sender, msg.as_ref());
+    journal::append(target, msg.as_ref());
+    if spill::offer(target, sender, msg.as_ref()) {
+        return;
+    }
+    enqueue(actor, target, sender, sender_ref, queue_depth::track_boxed(target, sender, msg));
+}
+
+fn enqueue(actor: &ActorRef, target: &str, sender: &str, sender_ref: Option<ActorRef>, msg: Box<dyn Message>) {
+    if let Some(msg) = sub_queue::offer(sender, target, msg, sender_ref.clone()) {
+        pause::send(actor, target, msg, sender_ref);
+    }
+}
+
+/// Have `actor` handle an admitted, converted message before this returns.
+/// While it is paused the message is held like a `deliver()`ed one instead,
+/// and a named sender is told ERR_PAUSED.
+pub(crate) fn fast_deliver<M: Message>(
+    actor: &ActorRef,
+    target: &str,
+    sender: &str,
+    sender_ref: Option<ActorRef>,
+    msg: M,
+) {
+    observe(target, sender, &msg);
+    journal::append(target, &msg);
+    if pause::is_paused(target) {
+        let msg_id = msg.message_id();
+        pause::send(actor, target, queue_depth::track(target, sender, msg), sender_ref);
+        interop_error::notify(sender, target, interop_error::ERR_PAUSED, msg_id, "target paused; held until resumed");
+    } else {
+        actor.fast_send(Box::new(msg), sender_ref);
+    }
+}
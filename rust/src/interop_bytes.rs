@@ -0,0 +1,98 @@
+//! Variable-length byte fields (`interop_bytes` in interop_messages.h)
+//!
+//! Ownership rules across FFI:
+//! - Copy (`take == 0`): the receiver copies the bytes during the send call.
+//!   The sender keeps ownership and may free or reuse the buffer as soon as
+//!   the send returns.
+//! - Take (`take == 1`): the buffer must come from `interop_bytes_alloc()`.
+//!   If the send returns 0 the receiver owns it and the sender must not
+//!   touch it again. If the send fails the sender still owns it and must
+//!   call `interop_bytes_free()`. That includes a message the bridge
+//!   converted and then rejected (a field rule, a bad char field): it
+//!   gives the buffer back rather than freeing it.
+//!
+//! Buffers are allocated and freed by Rust on both sides so the allocator
+//! always matches. Structs holding pointers cannot go through `wire`.
+
+use std::os::raw::c_int;
+
+/// Matches C `interop_bytes`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct CInteropBytes {
+    pub data: *mut u8,
+    pub len: u32,
+    pub take: c_int,
+}
+
+impl Default for CInteropBytes {
+    fn default() -> Self {
+        CInteropBytes {
+            data: std::ptr::null_mut(),
+            len: 0,
+            take: 0,
+        }
+    }
+}
+
+impl CInteropBytes {
+    /// Copy-mode view of `bytes`; only valid while `bytes` is alive
+    pub fn borrow(bytes: &[u8]) -> Self {
+        CInteropBytes {
+            data: bytes.as_ptr() as *mut u8,
+            len: bytes.len() as u32,
+            take: 0,
+        }
+    }
+
+    /// Copy the bytes out, or adopt the buffer when `take` is set.
+    ///
+    /// # Safety
+    /// `data` must point to `len` readable bytes. With `take` set it must
+    /// come from `interop_bytes_alloc()` and this must be the only call
+    /// that consumes it.
+    pub unsafe fn to_vec(&self) -> Vec<u8> {
+        if self.data.is_null() || self.len == 0 {
+            return Vec::new();
+        }
+        let len = self.len as usize;
+        if self.take != 0 {
+            Box::from_raw(std::ptr::slice_from_raw_parts_mut(self.data, len)).into_vec()
+        } else {
+            std::slice::from_raw_parts(self.data, len).to_vec()
+        }
+    }
+}
+
+/// Hand `adopted`, converted from `c`, back to the sender of a rejected
+/// message: a take-mode buffer is left for it to free, a copy is dropped.
+pub fn give_back(c: &CInteropBytes, adopted: Vec<u8>) {
+    if c.take != 0 {
+        // to_vec() rebuilt the sender's allocation (capacity == len); it
+        // stays allocated for interop_bytes_free()
+        std::mem::forget(adopted);
+    }
+}
+
+/// Allocate a take-mode buffer of `len` zeroed bytes
+#[export_name = c_symbol!("interop_bytes_alloc")]
+pub extern "C" fn interop_bytes_alloc(len: u32) -> CInteropBytes {
+    if len == 0 {
+        return CInteropBytes { take: 1, ..CInteropBytes::default() };
+    }
+    let buf = vec![0u8; len as usize].into_boxed_slice();
+    CInteropBytes {
+        data: Box::into_raw(buf) as *mut u8,
+        len,
+        take: 1,
+    }
+}
+
+/// Free a buffer from `interop_bytes_alloc()`. Copy-mode buffers are
+/// owned by the caller and are ignored.
+#[export_name = c_symbol!("interop_bytes_free")]
+pub extern "C" fn interop_bytes_free(bytes: CInteropBytes) {
+    if bytes.take != 0 && !bytes.data.is_null() && bytes.len > 0 {
+        unsafe { drop(bytes.to_vec()) };
+    }
+}
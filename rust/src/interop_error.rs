@@ -0,0 +1,105 @@
+//! `InteropError` replies for messages a bridge could not dispatch
+//!
+//! A send that fails in the bridge returns a code to the sending thread,
+//! which for `ActorRef::send` from an actor is usually dropped, so the
+//! sending actor only learns of it from the logs. The bridges also send
+//! the sender an `InteropError`:
+//!
+//! ```ignore
+//! fn on_interop_error(&mut self, err: &InteropError, _ctx: &mut ActorContext) {
+//!     if err.code == ERR_INVALID_MESSAGE && err.in_reply_to == MSG_MARKETDEPTH {
+//!         self.depth_rejected += 1;    // err.text: "MarketDepth.num_levels breaks ..."
+//!     }
+//! }
+//! ```
+//!
+//! | Code | When |
+//! |------|------|
+//! | `ERR_UNKNOWN_MESSAGE` | the receiving side has no such message id |
+//! | `ERR_INVALID_MESSAGE` | the message broke a field rule (validation) |
+//! | `ERR_PAUSED` | a fast_send was held because the target is paused |
+//! | `ERR_POLICY_DENIED` | the bridge policy forbids the send |
+//! | `ERR_TOO_LARGE` | over the bridge size limit |
+//! | `ERR_SHUTTING_DOWN` | the receiving side is shutting down |
+//! | `ERR_BACKLOG_FULL` | a reliable subscriber's backlog is full; the update was refused (see reliable) |
+//!
+//! These are `InteropError.code` values, not the codes bridge calls return
+//! (`InteropErrorCode`, see interop_errors).
+//!
+//! Sent for messages from C++ to Rust (`rust_actor_send`,
+//! `rust_actor_fast_send`) and for Rust -> C++ sends the C++ bridge
+//! refused, and to a publisher whose reliable subscriber falls too far
+//! behind. Only to a named sender: `rust_actor_post` and anonymous sends
+//! have no one to tell. A missing target is a dead letter, not an
+//! `InteropError`, and an `InteropError` that fails is never answered.
+
+use std::os::raw::{c_char, c_int};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{InteropError, MSG_INTEROPERROR};
+use crate::interop_string::InteropString;
+use crate::names::c_str;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::send_error::SendError;
+use crate::validation::INVALID_MESSAGE;
+
+// InteropError.code (INTEROP_ERR_* in interop_messages.h)
+pub const ERR_UNKNOWN_MESSAGE: i32 = 1;
+pub const ERR_INVALID_MESSAGE: i32 = 2;
+pub const ERR_PAUSED: i32 = 3;
+pub const ERR_POLICY_DENIED: i32 = 4;
+pub const ERR_TOO_LARGE: i32 = 5;
+pub const ERR_SHUTTING_DOWN: i32 = 6;
+pub const ERR_BACKLOG_FULL: i32 = 7;
+
+/// InteropError code for a bridge send code, None if the sender is not told
+pub fn code_for(rc: i32) -> Option<i32> {
+    match InteropErrorCode::from_code(rc)? {
+        InteropErrorCode::UnknownMessage => Some(ERR_UNKNOWN_MESSAGE),
+        InteropErrorCode::InvalidMessage => Some(ERR_INVALID_MESSAGE),
+        InteropErrorCode::PolicyDenied => Some(ERR_POLICY_DENIED),
+        InteropErrorCode::TooLarge => Some(ERR_TOO_LARGE),
+        InteropErrorCode::ShuttingDown => Some(ERR_SHUTTING_DOWN),
+        _ => None,
+    }
+}
+
+/// Send `sender` an InteropError about message `in_reply_to` it sent to
+/// `target`. Does nothing without a sender, or if the failed message was
+/// itself an InteropError.
+pub fn notify(sender: &str, target: &str, code: i32, in_reply_to: i32, text: &str) {
+    if sender.is_empty() || in_reply_to == MSG_INTEROPERROR {
+        return;
+    }
+    let sender_ref = match get_actor_ref(sender, target) {
+        Some(r) => r,
+        None => return,
+    };
+    let err = InteropError { code, text: InteropString::from(text), in_reply_to };
+    sender_ref.send(Box::new(err), get_actor_ref(target, sender));
+}
+
+/// Tell `sender` about a failed send of `msg_id` to `target`, if `rc`
+/// is a code it is told about
+pub fn report(sender: &str, target: &str, msg_id: i32, rc: i32) {
+    if let Some(code) = code_for(rc) {
+        let text = match SendError::check(rc) {
+            Err(e) => e.to_string(),
+            Ok(()) => return,
+        };
+        notify(sender, target, code, msg_id, &text);
+    }
+}
+
+/// `report` for the C arguments of a bridge send. Validation failures are
+/// reported where the rule is known (validation::reject), not here.
+pub(crate) fn report_c(actor_name: *const c_char, sender_name: *const c_char, msg_type: c_int, rc: c_int) {
+    if rc >= 0 || rc == INVALID_MESSAGE {
+        return;
+    }
+    let (target, sender) = match (c_str(actor_name), c_str(sender_name)) {
+        (Some(t), Some(s)) => (t, s),
+        _ => return,
+    };
+    report(sender, target, msg_type, rc);
+}
@@ -0,0 +1,217 @@
+//! Verbose bridge logging, switched on at runtime
+//!
+//! For chasing an intermittent cross-language problem in staging without
+//! a debug build. Raise the level for the whole bridge or for the actors
+//! involved, and the bridge prints what it does to stderr:
+//!
+//! ```c
+//! rust_set_interop_log_level(INTEROP_LOG_INFO);              // every send
+//! rust_set_actor_log_level("rust_risk", INTEROP_LOG_TRACE);  // and payloads, for one actor
+//! rust_set_actor_log_level("rust_risk", INTEROP_LOG_DEFAULT);
+//! ```
+//!
+//! | Level | Logged |
+//! |-------|--------|
+//! | `Off` | nothing (default) |
+//! | `Error` | failed sends and lookups |
+//! | `Info` | every send with its result, every lookup |
+//! | `Debug` | also every conversion between C struct and Rust type |
+//! | `Trace` | conversions with the message as JSON |
+//!
+//! ```text
+//! [Interop] send cpp_feed -> rust_risk MarketUpdate (1012): ok
+//! [Interop] lookup cpp_gateway from rust_risk: not found
+//! [Interop] convert C++ -> Rust cpp_feed -> rust_risk MarketUpdate (1012) {"symbol":"AAPL",...}
+//! ```
+//!
+//! An event is logged if the process level or the level of its sender or
+//! target allows it. Interop-ctl sets both: `log <level> [actor]`.
+//!
+//! Covered: `rust_actor_send`, `rust_actor_fast_send`, `rust_actor_post`
+//! and `rust_actor_ask` one way, sends through `ActorRef::Cpp` the other,
+//! and lookups of C++ actors from Rust. Rust -> Rust sends are not logged.
+//! While everything is `Off` the cost is one atomic load per event.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::RwLock;
+
+use actors::Message;
+
+use crate::boundary::Direction;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_name, message_to_debug_json};
+use crate::names::c_str;
+
+/// Mirrors INTEROP_LOG_* in interop/log.h
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(C)]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Info = 2,
+    Debug = 3,
+    Trace = 4,
+}
+
+impl LogLevel {
+    pub fn from_i32(level: i32) -> Option<LogLevel> {
+        match level {
+            0 => Some(LogLevel::Off),
+            1 => Some(LogLevel::Error),
+            2 => Some(LogLevel::Info),
+            3 => Some(LogLevel::Debug),
+            4 => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+
+    /// `off`, `error`, `info`, `debug`, `trace`, or the number
+    pub fn parse(s: &str) -> Option<LogLevel> {
+        match s {
+            "off" => Some(LogLevel::Off),
+            "error" => Some(LogLevel::Error),
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            _ => s.parse().ok().and_then(LogLevel::from_i32),
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Off as u8);
+// Highest per-actor level, so events no override could log skip the lock
+static MAX_OVERRIDE: AtomicU8 = AtomicU8::new(LogLevel::Off as u8);
+
+lazy_static::lazy_static! {
+    static ref ACTORS: RwLock<HashMap<String, LogLevel>> = RwLock::new(HashMap::new());
+}
+
+pub fn set_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> LogLevel {
+    LogLevel::from_i32(LEVEL.load(Ordering::Relaxed) as i32).unwrap_or(LogLevel::Off)
+}
+
+/// Log events involving `actor` at `level` or below, whatever the process
+/// level; None goes back to the process level
+pub fn set_actor_level(actor: &str, level: Option<LogLevel>) {
+    let mut actors = ACTORS.write().unwrap();
+    match level {
+        Some(level) => actors.insert(actor.to_string(), level),
+        None => actors.remove(actor),
+    };
+    let max = actors.values().max().copied().unwrap_or(LogLevel::Off);
+    MAX_OVERRIDE.store(max as u8, Ordering::Relaxed);
+}
+
+pub fn actor_level(actor: &str) -> Option<LogLevel> {
+    ACTORS.read().unwrap().get(actor).copied()
+}
+
+/// Whether an event at `level` between `sender` and `target` is logged
+pub fn enabled(level: LogLevel, sender: &str, target: &str) -> bool {
+    if LEVEL.load(Ordering::Relaxed) >= level as u8 {
+        return true;
+    }
+    if MAX_OVERRIDE.load(Ordering::Relaxed) < level as u8 {
+        return false;
+    }
+    let actors = ACTORS.read().unwrap();
+    [sender, target].iter().any(|a| actors.get(*a).is_some_and(|l| *l >= level))
+}
+
+fn describe(msg_id: i32) -> String {
+    format!("{} ({})", message_name(msg_id).unwrap_or("?"), msg_id)
+}
+
+fn arrow(direction: Direction) -> &'static str {
+    match direction {
+        Direction::ToRust => "C++ -> Rust",
+        Direction::ToCpp => "Rust -> C++",
+    }
+}
+
+/// A send across the bridge and its return code
+pub(crate) fn send(sender: &str, target: &str, msg_id: i32, rc: i32) {
+    let level = if rc == 0 { LogLevel::Info } else { LogLevel::Error };
+    if !enabled(level, sender, target) {
+        return;
+    }
+    let result = match InteropErrorCode::from_code(rc) {
+        Some(InteropErrorCode::Ok) => "ok".to_string(),
+        Some(code) => format!("{} ({})", code.description(), rc),
+        None => format!("error {}", rc),
+    };
+    eprintln!("[Interop] send {} -> {} {}: {}", sender, target, describe(msg_id), result);
+}
+
+/// `send` for the C arguments of a bridge entry point; a null sender is ""
+pub(crate) fn send_c(actor_name: *const c_char, sender_name: *const c_char, msg_type: c_int, rc: c_int) {
+    if LEVEL.load(Ordering::Relaxed) == 0 && MAX_OVERRIDE.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    send(c_str(sender_name).unwrap_or(""), c_str(actor_name).unwrap_or(""), msg_type, rc);
+}
+
+/// A lookup of C++ actor `name` by `sender`
+pub(crate) fn lookup(sender: &str, name: &str, found: bool) {
+    let level = if found { LogLevel::Info } else { LogLevel::Error };
+    if !enabled(level, sender, name) {
+        return;
+    }
+    let what = if found { "C++ actor" } else { "not found" };
+    eprintln!("[Interop] lookup {} from {}: {}", name, if sender.is_empty() { "-" } else { sender }, what);
+}
+
+/// A message converted `direction` for a send from `sender` to `target`
+pub(crate) fn conversion(direction: Direction, sender: &str, target: &str, msg: &dyn Message) {
+    if !enabled(LogLevel::Debug, sender, target) {
+        return;
+    }
+    let payload = match enabled(LogLevel::Trace, sender, target) {
+        true => message_to_debug_json(msg).map(|json| format!(" {}", json)).unwrap_or_default(),
+        false => String::new(),
+    };
+    eprintln!(
+        "[Interop] convert {} {} -> {} {}{}",
+        arrow(direction),
+        sender,
+        target,
+        describe(msg.message_id()),
+        payload
+    );
+}
+
+/// Set the process log level (INTEROP_LOG_*). Returns 0, or
+/// InvalidArgument for an unknown level.
+#[export_name = c_symbol!("rust_set_interop_log_level")]
+pub extern "C" fn rust_set_interop_log_level(level: c_int) -> c_int {
+    match LogLevel::from_i32(level) {
+        Some(level) => {
+            set_level(level);
+            InteropErrorCode::Ok as c_int
+        }
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Set the log level for events involving `actor`; INTEROP_LOG_DEFAULT
+/// (-1) goes back to the process level. Returns 0, or InvalidArgument on
+/// a bad actor name or unknown level.
+#[export_name = c_symbol!("rust_set_actor_log_level")]
+pub extern "C" fn rust_set_actor_log_level(actor: *const c_char, level: c_int) -> c_int {
+    let actor = match c_str(actor) {
+        Some(a) => a,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    match (level, LogLevel::from_i32(level)) {
+        (-1, _) => set_actor_level(actor, None),
+        (_, Some(level)) => set_actor_level(actor, Some(level)),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    }
+    InteropErrorCode::Ok as c_int
+}
@@ -0,0 +1,269 @@
+//! Fixed-size string fields (`char name[N]` in interop_messages.h)
+//!
+//! `InteropString<N>` has the same layout as `[u8; N]` and always keeps a
+//! trailing NUL, so at most N-1 bytes of text are stored. The generated
+//! Rust message structs use it for every `char[N]` field.
+//!
+//! ```ignore
+//! let sub = Subscribe { topic: "AAPL".into(), qos: QOS_BEST_EFFORT };
+//! println!("{}", sub.topic);            // AAPL
+//! assert_eq!(sub.topic.as_str(), "AAPL");
+//! ```
+//!
+//! Text that does not fit is never cut silently: `From<&str>` truncates at
+//! a character boundary and records a warning (`truncated()`), and
+//! `with_policy(s, TruncationPolicy::Reject)` refuses it instead.
+//!
+//! Fields received from C++ are normalized by the bridge before a handler
+//! sees them: bytes that are not UTF-8 are cut off and a field with no
+//! NUL is shortened so it has one, each recorded as a repair
+//! (`repaired()`, and in the health summary). In strict mode
+//! (`set_strict(true)`, `interop_strings_set_strict(1)` from C) such
+//! messages are rejected with `INVALID_MESSAGE` instead.
+
+use std::fmt;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::validation::ValidationError;
+
+static TRUNCATED: AtomicU64 = AtomicU64::new(0);
+static REPAIRED: AtomicU64 = AtomicU64::new(0);
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Reject received fields that are not NUL-terminated UTF-8 instead of
+/// repairing them
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+pub fn is_strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+/// Strings cut to fit by `From<&str>`
+pub fn truncated() -> u64 {
+    TRUNCATED.load(Ordering::Relaxed)
+}
+
+/// Received fields repaired by `normalize`
+pub fn repaired() -> u64 {
+    REPAIRED.load(Ordering::Relaxed)
+}
+
+// Counted always, logged on the 1st, 2nd, 4th, 8th ... occurrence
+fn warn(counter: &AtomicU64, what: fmt::Arguments) {
+    let n = counter.fetch_add(1, Ordering::Relaxed) + 1;
+    if n.is_power_of_two() {
+        eprintln!("[InteropString] {} ({} so far)", what, n);
+    }
+}
+
+/// strict != 0: reject received fields that are not NUL-terminated UTF-8
+#[export_name = c_symbol!("interop_strings_set_strict")]
+pub extern "C" fn interop_strings_set_strict(strict: c_int) {
+    set_strict(strict != 0);
+}
+
+/// What to do when text does not fit in N-1 bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum TruncationPolicy {
+    /// Cut at the last UTF-8 character boundary that fits
+    #[default]
+    Truncate,
+    /// Refuse the value
+    Reject,
+}
+
+/// Returned by `InteropString::with_policy` under `TruncationPolicy::Reject`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooLong {
+    pub len: usize,
+    pub max: usize,
+}
+
+impl fmt::Display for TooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "string of {} bytes exceeds field capacity of {}", self.len, self.max)
+    }
+}
+
+impl std::error::Error for TooLong {}
+
+/// NUL-terminated fixed-size string, layout-compatible with `char[N]`.
+/// Equality and hashing look at the text only: C leaves whatever it
+/// likes after the NUL.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct InteropString<const N: usize>([u8; N]);
+
+impl<const N: usize> InteropString<N> {
+    /// Maximum number of text bytes (one byte is kept for the NUL)
+    pub const CAPACITY: usize = if N == 0 { 0 } else { N - 1 };
+
+    /// Build from text, applying `policy` if it is too long
+    pub fn with_policy(s: &str, policy: TruncationPolicy) -> Result<Self, TooLong> {
+        let mut end = s.len();
+        if end > Self::CAPACITY {
+            if policy == TruncationPolicy::Reject {
+                return Err(TooLong { len: s.len(), max: Self::CAPACITY });
+            }
+            end = Self::CAPACITY;
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+        }
+        let mut data = [0u8; N];
+        data[..end].copy_from_slice(&s.as_bytes()[..end]);
+        Ok(InteropString(data))
+    }
+
+    /// Wrap raw bytes received from C
+    pub fn from_bytes(data: [u8; N]) -> Self {
+        InteropString(data)
+    }
+
+    /// View bytes received from C in place, without copying
+    pub fn from_bytes_ref(data: &[u8; N]) -> &Self {
+        // SAFETY: InteropString<N> is a repr(transparent) [u8; N]
+        unsafe { &*(data as *const [u8; N] as *const InteropString<N>) }
+    }
+
+    /// Raw bytes for the C struct
+    pub fn to_bytes(&self) -> [u8; N] {
+        self.0
+    }
+
+    /// Text up to the first NUL. Invalid UTF-8 from C is cut at the
+    /// first bad byte rather than failing.
+    pub fn as_str(&self) -> &str {
+        let len = self.len();
+        match std::str::from_utf8(&self.0[..len]) {
+            Ok(s) => s,
+            // SAFETY: valid_up_to() marks the end of a valid UTF-8 prefix
+            Err(e) => unsafe { std::str::from_utf8_unchecked(&self.0[..e.valid_up_to()]) },
+        }
+    }
+
+    /// Number of bytes before the first NUL
+    pub fn len(&self) -> usize {
+        self.0.iter().position(|&b| b == 0).unwrap_or(N)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Make a field received from C valid UTF-8 with a trailing NUL,
+    /// recording the repair; in strict mode refuse it instead. Called by
+    /// the generated `normalize_strings()`.
+    pub fn normalize(&mut self, message_id: i32, message: &'static str, field: &'static str) -> Result<(), ValidationError> {
+        let len = self.len();
+        let valid = match std::str::from_utf8(&self.0[..len]) {
+            Ok(_) => len,
+            Err(e) => e.valid_up_to(),
+        };
+        if valid == len && (len < N || N == 0) {
+            return Ok(());
+        }
+        let rule = if valid < len { "utf8" } else { "nul-terminated" };
+        if is_strict() {
+            return Err(ValidationError { message_id, message, field, rule });
+        }
+        let mut end = valid.min(Self::CAPACITY);
+        while end > 0 && !is_char_boundary(&self.0[..valid], end) {
+            end -= 1;
+        }
+        self.0[end..].fill(0);
+        warn(&REPAIRED, format_args!("{}.{} was not {}; cut to {:?}", message, field, rule, self.as_str()));
+        Ok(())
+    }
+
+    /// Whether the bytes before the first NUL are valid UTF-8
+    pub fn is_utf8(&self) -> bool {
+        std::str::from_utf8(&self.0[..self.len()]).is_ok()
+    }
+}
+
+// Bytes up to the first NUL, as `len()` counts them
+impl<const N: usize> PartialEq for InteropString<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[..self.len()] == other.0[..other.len()]
+    }
+}
+
+impl<const N: usize> Eq for InteropString<N> {}
+
+impl<const N: usize> std::hash::Hash for InteropString<N> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0[..self.len()].hash(state);
+    }
+}
+
+impl<const N: usize> Default for InteropString<N> {
+    fn default() -> Self {
+        InteropString([0u8; N])
+    }
+}
+
+// Whether a cut at `at` keeps the valid UTF-8 `text` whole characters
+fn is_char_boundary(text: &[u8], at: usize) -> bool {
+    at >= text.len() || (text[at] as i8) >= -0x40
+}
+
+/// Truncates at a character boundary if the text is too long, recording
+/// a warning (see `truncated()`)
+impl<const N: usize> From<&str> for InteropString<N> {
+    fn from(s: &str) -> Self {
+        if s.len() > Self::CAPACITY {
+            warn(&TRUNCATED, format_args!("{} bytes truncated to {}: {:?}", s.len(), Self::CAPACITY, s));
+        }
+        Self::with_policy(s, TruncationPolicy::Truncate).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for InteropString<N> {
+    fn from(data: [u8; N]) -> Self {
+        InteropString(data)
+    }
+}
+
+impl<const N: usize> PartialEq<str> for InteropString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for InteropString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> fmt::Display for InteropString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for InteropString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// Serialized as its text; text over the capacity is refused on decode
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for InteropString<N> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for InteropString<N> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Self::with_policy(&s, TruncationPolicy::Reject).map_err(serde::de::Error::custom)
+    }
+}
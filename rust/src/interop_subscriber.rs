@@ -0,0 +1,299 @@
+//! A ready-made subscriber actor built from callbacks
+//!
+//! Most subscribers are the same actor: subscribe to a publisher's topics
+//! on Start, handle a few message types, subscribe again when the
+//! publisher restarts. `InteropSubscriber` is that actor with the handlers
+//! passed in:
+//!
+//! ```ignore
+//! InteropSubscriber::new("cpp_price_feed")
+//!     .topics(&["AAPL", "GOOG"])
+//!     .on::<MarketUpdate>(|m| println!("{} @ {:.2}", m.symbol, m.price))
+//!     .on::<MarketDepth>(|m| println!("{}: {} levels", m.symbol, m.num_levels))
+//!     .register("rust_price_monitor");
+//! ```
+//!
+//! C++ builds one under its name and gets each message as its C struct,
+//! valid during the call:
+//!
+//! ```c
+//! interop_subscriber_new("rust_price_monitor", "cpp_price_feed");   // interop/interop_subscriber.h
+//! interop_subscriber_topic("rust_price_monitor", "AAPL", 0);
+//! interop_subscriber_on("rust_price_monitor", 1012, on_update, this);  // MarketUpdate
+//! const void* rust_mgr = interop_subscriber_register("rust_price_monitor");
+//! ```
+//!
+//! - Handlers run on the subscriber's thread, one message at a time.
+//!   Message types without one are ignored.
+//! - SubscribeRequest / UnsubscribeRequest change the topics as for a
+//!   DynamicSubscriber (see subscriber), the publisher being the default.
+//! - Publisher restarts are watched on `$sys` and subscribed to again. A
+//!   SystemEvent handler still gets the events.
+//! - The subscriber is supervised like any Rust actor (see lifecycle). A
+//!   restarted instance keeps the handlers and what was subscribed to.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use actors::messages::Start;
+use actors::{Actor, ActorContext, Manager, Message, ThreadConfig};
+
+use crate::cpp_actor_if::InteropMessage;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{c_struct_size, with_c_struct, SubscribeRequest, SystemEvent, UnsubscribeRequest};
+use crate::interop_string::{InteropString, TruncationPolicy};
+use crate::names::c_str;
+use crate::qos::QOS_BEST_EFFORT;
+use crate::rust_manager_ffi::{get_rust_manager, register_with};
+use crate::subscriber::Subscriptions;
+
+type Handler = Arc<Mutex<dyn FnMut(&dyn Message) + Send>>;
+
+/// Subscriber actor under construction; `register` starts it
+#[derive(Clone)]
+pub struct InteropSubscriber {
+    publisher: String,
+    topics: Vec<(String, i32)>,
+    handlers: HashMap<i32, Handler>,
+}
+
+impl InteropSubscriber {
+    /// Subscriber to `publisher` (Rust or C++), with no topics yet
+    pub fn new(publisher: &str) -> Self {
+        InteropSubscriber { publisher: publisher.to_string(), topics: Vec::new(), handlers: HashMap::new() }
+    }
+
+    /// Subscribe to `topic` on Start, best effort
+    pub fn topic(self, topic: &str) -> Self {
+        self.topic_with_qos(topic, QOS_BEST_EFFORT)
+    }
+
+    pub fn topic_with_qos(mut self, topic: &str, qos: i32) -> Self {
+        match self.topics.iter_mut().find(|(t, _)| t == topic) {
+            Some(held) => held.1 = qos,
+            None => self.topics.push((topic.to_string(), qos)),
+        }
+        self
+    }
+
+    pub fn topics<S: AsRef<str>>(self, topics: &[S]) -> Self {
+        topics.iter().fold(self, |sub, topic| sub.topic(topic.as_ref()))
+    }
+
+    /// Call `handler` with every `M` received; replaces an earlier one
+    pub fn on<M, F>(self, mut handler: F) -> Self
+    where
+        M: Message + InteropMessage + 'static,
+        F: FnMut(&M) + Send + 'static,
+    {
+        self.on_id(M::MSG_ID, move |msg| {
+            if let Some(msg) = msg.as_any().downcast_ref::<M>() {
+                handler(msg);
+            }
+        })
+    }
+
+    /// Call `handler` with every message of ID `msg_id`
+    pub fn on_id<F>(mut self, msg_id: i32, handler: F) -> Self
+    where
+        F: FnMut(&dyn Message) + Send + 'static,
+    {
+        self.handlers.insert(msg_id, Arc::new(Mutex::new(handler)));
+        self
+    }
+
+    /// The actor, to manage as `name` with a Manager built in Rust.
+    /// Instances built from the same subscriber share its subscriptions.
+    pub fn actor(&self, name: &str) -> SubscriberActor {
+        self.instance(name, Arc::new(Mutex::new(Subscriptions::new())))
+    }
+
+    /// Register the subscriber as `name` with the Rust Manager. False if
+    /// there is no Manager or the name is taken (see names).
+    pub fn register(self, name: &str) -> bool {
+        let held = Arc::new(Mutex::new(Subscriptions::new()));
+        let me = name.to_string();
+        register_with(name, move |_| Box::new(self.instance(&me, held.clone())), ThreadConfig::default())
+    }
+
+    fn instance(&self, name: &str, held: Arc<Mutex<Subscriptions>>) -> SubscriberActor {
+        SubscriberActor {
+            name: name.to_string(),
+            publisher: self.publisher.clone(),
+            topics: self.topics.clone(),
+            handlers: self.handlers.clone(),
+            held,
+        }
+    }
+}
+
+/// The actor an InteropSubscriber runs as
+pub struct SubscriberActor {
+    name: String,
+    publisher: String,
+    topics: Vec<(String, i32)>,
+    handlers: HashMap<i32, Handler>,
+    // Outlives the instance, for a restarted one to take again
+    held: Arc<Mutex<Subscriptions>>,
+}
+
+impl SubscriberActor {
+    fn subscriptions(&self) -> MutexGuard<'_, Subscriptions> {
+        // A handler that panicked poisoned nothing of ours
+        self.held.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn on_start(&mut self) {
+        let mut subs = self.subscriptions();
+        if !subs.is_empty() {
+            subs.resubscribe(&self.name);
+        } else {
+            for (topic, qos) in &self.topics {
+                if !subs.subscribe(&self.name, &self.publisher, topic, *qos) {
+                    eprintln!("[Subscriber] {}: cannot subscribe to {} of {}", self.name, topic, self.publisher);
+                }
+            }
+        }
+        subs.watch_restarts(&self.name);
+    }
+
+    fn publisher_or<'a>(&'a self, publisher: &'a str) -> &'a str {
+        if publisher.is_empty() {
+            self.publisher.as_str()
+        } else {
+            publisher
+        }
+    }
+}
+
+impl Actor for SubscriberActor {
+    fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+        let any = msg.as_any();
+        if any.is::<Start>() {
+            self.on_start();
+        } else if let Some(request) = any.downcast_ref::<SubscribeRequest>() {
+            let publisher = self.publisher_or(request.publisher.as_str()).to_string();
+            let topic = request.topic.as_str();
+            if !self.subscriptions().subscribe(&self.name, &publisher, topic, request.qos) {
+                eprintln!("[Subscriber] {}: cannot subscribe to {} of {}", self.name, topic, publisher);
+            }
+        } else if let Some(request) = any.downcast_ref::<UnsubscribeRequest>() {
+            let publisher = self.publisher_or(request.publisher.as_str()).to_string();
+            self.subscriptions().unsubscribe(&self.name, &publisher, request.topic.as_str());
+        } else if let Some(event) = any.downcast_ref::<SystemEvent>() {
+            self.subscriptions().on_system_event(&self.name, event);
+        }
+        if let Some(handler) = self.handlers.get(&msg.message_id()) {
+            (handler.lock().unwrap_or_else(|e| e.into_inner()))(msg);
+        }
+    }
+}
+
+// ============================================================================
+// FFI: subscribers built by C++
+// ============================================================================
+
+/// Called with the message ID and its C struct, valid during the call
+pub type SubscriberCallback = extern "C" fn(user_data: *mut c_void, msg_type: c_int, msg_data: *const c_void);
+
+struct UserData(*mut c_void);
+
+// Only ever passed back to C++ on the subscriber's thread
+unsafe impl Send for UserData {}
+
+impl UserData {
+    fn get(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+lazy_static::lazy_static! {
+    // Subscribers created from C++ and not registered yet, by name
+    static ref BUILDING: Mutex<HashMap<String, InteropSubscriber>> = Mutex::new(HashMap::new());
+}
+
+/// Drop subscribers built from C++ and never registered
+pub(crate) fn clear() {
+    BUILDING.lock().unwrap().clear();
+}
+
+/// Start building subscriber `name` of `publisher`; an unregistered one
+/// of the same name is replaced. Returns 0, or InvalidArgument on a bad
+/// argument.
+#[export_name = c_symbol!("interop_subscriber_new")]
+pub extern "C" fn interop_subscriber_new(name: *const c_char, publisher: *const c_char) -> c_int {
+    match (c_str(name), c_str(publisher)) {
+        (Some(name), Some(publisher)) if !name.is_empty() && !publisher.is_empty() => {
+            BUILDING.lock().unwrap().insert(name.to_string(), InteropSubscriber::new(publisher));
+            0
+        }
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Have subscriber `name` subscribe to `topic` with `qos` on Start.
+/// Returns 0, NotFound if it is not being built, or InvalidArgument on a
+/// bad topic.
+#[export_name = c_symbol!("interop_subscriber_topic")]
+pub extern "C" fn interop_subscriber_topic(name: *const c_char, topic: *const c_char, qos: c_int) -> c_int {
+    let topic = match c_str(topic) {
+        Some(t) if InteropString::<32>::with_policy(t, TruncationPolicy::Reject).is_ok() => t,
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let mut building = BUILDING.lock().unwrap();
+    match c_str(name).and_then(|name| building.remove_entry(name)) {
+        Some((name, sub)) => {
+            building.insert(name, sub.topic_with_qos(topic, qos));
+            0
+        }
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Call `callback` with every message of ID `msg_type` subscriber `name`
+/// receives. Returns 0, NotFound if it is not being built,
+/// InvalidArgument if `callback` is null, or UnknownMessage on an unknown
+/// message ID.
+#[export_name = c_symbol!("interop_subscriber_on")]
+pub extern "C" fn interop_subscriber_on(
+    name: *const c_char,
+    msg_type: c_int,
+    callback: Option<SubscriberCallback>,
+    user_data: *mut c_void,
+) -> c_int {
+    if c_struct_size(msg_type).is_none() {
+        return InteropErrorCode::UnknownMessage as c_int;
+    }
+    let callback = match callback {
+        Some(cb) => cb,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let mut building = BUILDING.lock().unwrap();
+    let (name, sub) = match c_str(name).and_then(|name| building.remove_entry(name)) {
+        Some(entry) => entry,
+        None => return InteropErrorCode::NotFound as c_int,
+    };
+    let user_data = UserData(user_data);
+    let sub = sub.on_id(msg_type, move |msg| {
+        with_c_struct(msg, |_, _| {}, |data| callback(user_data.get(), msg_type, data));
+    });
+    building.insert(name, sub);
+    0
+}
+
+/// Register subscriber `name` with the Rust Manager; it is no longer being
+/// built. Returns the Manager pointer for rust_actor_init(), or null if it
+/// was not being built, there is no Manager or the name is taken.
+#[export_name = c_symbol!("interop_subscriber_register")]
+pub extern "C" fn interop_subscriber_register(name: *const c_char) -> *const Manager {
+    let (name, sub) = match c_str(name).and_then(|name| BUILDING.lock().unwrap().remove_entry(name)) {
+        Some(entry) => entry,
+        None => return std::ptr::null(),
+    };
+    if sub.register(&name) {
+        get_rust_manager()
+    } else {
+        std::ptr::null()
+    }
+}
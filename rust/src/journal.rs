@@ -0,0 +1,354 @@
+//! Write-ahead journal of inbound messages, redelivered after a crash
+//!
+//! A strategy that dies with fills in its mailbox never sees them again:
+//! the mailbox is memory. With a journal, every interop message the bridge
+//! queues for the actor is first appended to `{dir}/{name}.journal`, and
+//! marked done once the actor has handled it. Enabling the journal of an
+//! actor whose process crashed reads the file back, and what was never
+//! marked done is handled again right after the actor's Start:
+//!
+//! ```ignore
+//! let recovered = journal::enable("rust_strategy", Path::new("/var/lib/interop"), FsyncPolicy::Always)?;
+//! rust_manager_ffi::register_by_type_name("Strategy", Some("rust_strategy"));
+//! ```
+//!
+//! ```c
+//! rust_actor_enable_journal("rust_strategy", "/var/lib/interop", 1);   // interop/journal.h
+//! ```
+//!
+//! - Processing is at least once: a message handled just before the crash,
+//!   whose done mark was not written, is handled again. Handlers of
+//!   journaled actors should be idempotent (see idempotency).
+//! - The fsync policy bounds what a power loss or kernel crash can lose:
+//!   `Always` syncs before the send returns to C++, `Every(n)` every n
+//!   messages, `Os` leaves it to the OS. A crash of the process alone loses
+//!   nothing under any policy. Done marks are never synced.
+//! - Covered: `rust_actor_send` and `rust_actor_post`, spilled messages
+//!   included (see spill). Not covered: Rust-to-Rust sends and
+//!   `rust_actor_fast_send`.
+//! - A message counts as done once its handler returns, it is quarantined
+//!   after panicking (see quarantine) or dropped by a TTL (see ttl). Done
+//!   marks match the message by content, so reordering (see freeze) is
+//!   fine, but messages dropped by a subscriber queue policy (see
+//!   sub_queue) stay in the journal and come back after a crash.
+//! - A torn record at the end of the file, from a crash mid-write, is cut
+//!   off. The file is rewritten with only the pending entries once it has
+//!   grown past `COMPACT_BYTES`.
+//! - Enable the journal before Start: recovered messages are handled after
+//!   Start, ahead of the mailbox, and replies from them go to no one.
+//!
+//! Records are `[u32 body_len][u32 crc32][body]`, the body either
+//! `[1][u64 seq][portable frame]` (see `codec::PortableCodec`) or
+//! `[2][u64 seq]` marking entry `seq` done.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::raw::{c_char, c_int};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use actors::Message;
+
+use crate::codec::{codec, CodecKind};
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+
+/// Journal size past which it is rewritten with only the pending entries
+pub const COMPACT_BYTES: u64 = 4 << 20;
+
+const ENTRY: u8 = 1;
+const DONE: u8 = 2;
+
+/// When journal appends are synced to disk
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Before the send returns
+    Always,
+    /// Once every n appends
+    Every(u32),
+    /// When the OS writes back
+    Os,
+}
+
+impl FsyncPolicy {
+    /// From the C argument: 0 = Os, 1 = Always, n = Every(n)
+    pub fn from_every(n: u32) -> Self {
+        match n {
+            0 => FsyncPolicy::Os,
+            1 => FsyncPolicy::Always,
+            n => FsyncPolicy::Every(n),
+        }
+    }
+}
+
+struct Journal {
+    path: PathBuf,
+    file: File,
+    fsync: FsyncPolicy,
+    // Bytes in the file
+    len: u64,
+    next_seq: u64,
+    unsynced: u32,
+    // Appended, not yet done: (seq, frame) in append order
+    pending: VecDeque<(u64, Vec<u8>)>,
+    // Read back by enable(), for the actor to handle after Start
+    recovered: Vec<Box<dyn Message>>,
+}
+
+// Fast path: no lock while no actor has a journal
+static ANY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref JOURNALS: Mutex<HashMap<String, Journal>> = Mutex::new(HashMap::new());
+}
+
+/// Journal messages for `name` in `dir`, recovering those a previous run
+/// left unhandled. Returns how many were recovered.
+pub fn enable(name: &str, dir: &Path, fsync: FsyncPolicy) -> io::Result<usize> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.journal", name));
+    let mut file = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+
+    let (entries, good_len, next_seq) = scan(&bytes);
+    if good_len < bytes.len() {
+        eprintln!("[Journal] {}: cutting off {} bytes of a torn record", name, bytes.len() - good_len);
+        file.set_len(good_len as u64)?;
+        file.sync_data()?;
+    }
+    let mut pending = VecDeque::new();
+    let mut recovered = Vec::new();
+    for (seq, frame) in entries {
+        match codec(CodecKind::Portable).decode(&frame) {
+            Ok(msg) => {
+                recovered.push(msg);
+                pending.push_back((seq, frame));
+            }
+            Err(e) => eprintln!("[Journal] {}: skipping unreadable entry: {}", name, e),
+        }
+    }
+    if !recovered.is_empty() {
+        eprintln!("[Journal] {}: {} messages to handle again after Start", name, recovered.len());
+    }
+    let count = recovered.len();
+    let journal = Journal { path, file, fsync, len: good_len as u64, next_seq, unsynced: 0, pending, recovered };
+    JOURNALS.lock().unwrap().insert(name.to_string(), journal);
+    ANY_ENABLED.store(true, Ordering::Release);
+    Ok(count)
+}
+
+/// Stop journaling `name`. The file stays for the next enable().
+pub fn disable(name: &str) {
+    let mut journals = JOURNALS.lock().unwrap();
+    if let Some(j) = journals.remove(name) {
+        let _ = j.file.sync_data();
+    }
+    ANY_ENABLED.store(!journals.is_empty(), Ordering::Release);
+}
+
+/// Messages journaled for `name` and not yet handled, None without a journal
+pub fn pending(name: &str) -> Option<usize> {
+    JOURNALS.lock().unwrap().get(name).map(|j| j.pending.len())
+}
+
+/// Entries without a done mark, keyed by seq, the length of the readable
+/// prefix and the seq to continue from
+fn scan(bytes: &[u8]) -> (BTreeMap<u64, Vec<u8>>, usize, u64) {
+    let mut entries = BTreeMap::new();
+    let mut at = 0;
+    let mut next_seq = 0;
+    while let Some(header) = bytes.get(at..at + 8) {
+        let body_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let crc = u32::from_le_bytes(header[4..].try_into().unwrap());
+        let body = match bytes.get(at + 8..at + 8 + body_len) {
+            Some(body) if body.len() >= 9 && crc32(body) == crc => body,
+            _ => break,
+        };
+        let seq = u64::from_le_bytes(body[1..9].try_into().unwrap());
+        match body[0] {
+            ENTRY => {
+                next_seq = next_seq.max(seq + 1);
+                entries.insert(seq, body[9..].to_vec());
+            }
+            DONE => {
+                entries.remove(&seq);
+            }
+            _ => break,
+        }
+        at += 8 + body_len;
+    }
+    (entries, at, next_seq)
+}
+
+fn record(kind: u8, seq: u64, frame: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(9 + frame.len());
+    body.push(kind);
+    body.extend_from_slice(&seq.to_le_bytes());
+    body.extend_from_slice(frame);
+    let mut out = Vec::with_capacity(8 + body.len());
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(&crc32(&body).to_le_bytes());
+    out.extend_from_slice(&body);
+    out
+}
+
+impl Journal {
+    fn write(&mut self, record: &[u8]) -> io::Result<()> {
+        self.file.write_all(record)?;
+        self.len += record.len() as u64;
+        Ok(())
+    }
+
+    fn append(&mut self, frame: Vec<u8>) -> io::Result<()> {
+        let seq = self.next_seq;
+        self.write(&record(ENTRY, seq, &frame))?;
+        self.next_seq += 1;
+        self.pending.push_back((seq, frame));
+        self.unsynced += 1;
+        let sync = match self.fsync {
+            FsyncPolicy::Always => true,
+            FsyncPolicy::Every(n) => self.unsynced >= n,
+            FsyncPolicy::Os => false,
+        };
+        if sync {
+            self.file.sync_data()?;
+            self.unsynced = 0;
+        }
+        Ok(())
+    }
+
+    fn done(&mut self, frame: &[u8]) -> io::Result<()> {
+        let index = match self.pending.iter().position(|(_, f)| f == frame) {
+            Some(i) => i,
+            None => return Ok(()),  // not journaled, e.g. a Rust-to-Rust send
+        };
+        let (seq, _) = self.pending.remove(index).unwrap();
+        self.write(&record(DONE, seq, &[]))?;
+        if self.len > COMPACT_BYTES {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite the file with only the pending entries; the rename keeps
+    /// either the old file or the new one across a crash
+    fn compact(&mut self) -> io::Result<()> {
+        let tmp = self.path.with_extension("journal.tmp");
+        let mut out = File::create(&tmp)?;
+        let mut len = 0;
+        for (seq, frame) in &self.pending {
+            let r = record(ENTRY, *seq, frame);
+            out.write_all(&r)?;
+            len += r.len() as u64;
+        }
+        out.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.len = len;
+        self.unsynced = 0;
+        Ok(())
+    }
+}
+
+/// Append `msg`, queued for `name`, to its journal if it has one. Called by
+/// the bridge before the message is queued.
+pub(crate) fn append(name: &str, msg: &dyn Message) {
+    if !ANY_ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+    let mut journals = JOURNALS.lock().unwrap();
+    let journal = match journals.get_mut(name) {
+        Some(j) => j,
+        None => return,
+    };
+    let frame = match codec(CodecKind::Portable).encode(msg) {
+        Ok(f) => f,
+        Err(_) => return,  // not an interop message
+    };
+    if let Err(e) = journal.append(frame) {
+        eprintln!("[Journal] {}: append failed, message is not journaled: {}", name, e);
+    }
+}
+
+/// Mark `msg` handled by `name`. Called by the supervisor.
+pub(crate) fn done(name: &str, msg: &dyn Message) {
+    if !ANY_ENABLED.load(Ordering::Acquire) {
+        return;
+    }
+    let mut journals = JOURNALS.lock().unwrap();
+    let journal = match journals.get_mut(name) {
+        Some(j) if !j.pending.is_empty() => j,
+        _ => return,
+    };
+    let frame = match codec(CodecKind::Portable).encode(msg) {
+        Ok(f) => f,
+        Err(_) => return,
+    };
+    if let Err(e) = journal.done(&frame) {
+        eprintln!("[Journal] {}: cannot mark a message done: {}", name, e);
+    }
+}
+
+/// Messages recovered for `name`, to handle after Start; empty after the
+/// first call
+pub(crate) fn take_recovered(name: &str) -> Vec<Box<dyn Message>> {
+    if !ANY_ENABLED.load(Ordering::Acquire) {
+        return Vec::new();
+    }
+    JOURNALS.lock().unwrap().get_mut(name).map_or_else(Vec::new, |j| std::mem::take(&mut j.recovered))
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+static CRC32: [u32; 256] = crc32_table();
+
+/// CRC-32 (IEEE), as zlib computes it
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |c, &b| CRC32[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8))
+}
+
+/// Journal messages for `actor_name` in `dir`, syncing every
+/// `fsync_every` appends (1 = each, 0 = left to the OS). Returns how many
+/// unhandled messages a previous run left, or InvalidArgument on bad
+/// arguments or if the journal cannot be opened (see stderr).
+#[export_name = c_symbol!("rust_actor_enable_journal")]
+pub extern "C" fn rust_actor_enable_journal(actor_name: *const c_char, dir: *const c_char, fsync_every: u32) -> c_int {
+    let (name, dir) = match (c_str(actor_name), c_str(dir)) {
+        (Some(n), Some(d)) if !n.is_empty() => (n, d),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    match enable(name, Path::new(dir), FsyncPolicy::from_every(fsync_every)) {
+        Ok(recovered) => recovered.min(c_int::MAX as usize) as c_int,
+        Err(e) => {
+            eprintln!("[Journal] {}: cannot open journal in {}: {}", name, dir, e);
+            InteropErrorCode::InvalidArgument as c_int
+        }
+    }
+}
+
+/// Journaled messages for `actor_name` not yet handled, NotFound if it
+/// has no journal
+#[export_name = c_symbol!("rust_actor_journal_pending")]
+pub extern "C" fn rust_actor_journal_pending(actor_name: *const c_char) -> c_int {
+    match c_str(actor_name).and_then(pending) {
+        Some(n) => n.min(c_int::MAX as usize) as c_int,
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
@@ -0,0 +1,227 @@
+//! Kafka source and sink actors for interop messages (feature `kafka`)
+//!
+//! - `KafkaSource` consumes a Kafka topic on a background thread, decodes
+//!   each record with the link codec and sends the message to every
+//!   configured subscriber (Rust or C++, resolved with `get_actor_ref`).
+//! - `KafkaSink` subscribes to an interop publisher and writes the messages
+//!   it receives to a Kafka topic, keyed by symbol where the message has one.
+//!
+//! Records use the codec set for the `kafka_source` / `kafka_sink` link
+//! (`Portable` frames by default, see `codec`).
+//!
+//! The sink handles the data messages listed in its `handle_messages!`
+//! block; add a line there for new message types. `msg_ids` narrows that
+//! set further (empty = everything handled).
+
+use std::thread;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, ActorRef};
+use actors::messages::Start;
+use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
+use kafka::producer::{Producer, Record, RequiredAcks};
+
+use crate::codec::{link_codec, Codec};
+use crate::conversion_cache;
+use crate::cpp_actor_if::InteropMessage;
+use crate::interop_messages::{clone_message, DataResponse, MarketDepth, MarketUpdate, RiskLimits, Subscribe};
+use crate::qos::QOS_BEST_EFFORT;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::size_limit;
+
+/// Name the source is registered under
+pub const KAFKA_SOURCE_NAME: &str = "kafka_source";
+
+/// Name the sink is registered under
+pub const KAFKA_SINK_NAME: &str = "kafka_sink";
+
+#[derive(Clone, Debug)]
+pub struct KafkaSourceConfig {
+    pub brokers: Vec<String>,
+    /// Kafka topic to consume
+    pub topic: String,
+    /// Consumer group, offsets are committed to Kafka
+    pub group: String,
+    /// Actors receiving every decoded message
+    pub subscribers: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct KafkaSinkConfig {
+    pub brokers: Vec<String>,
+    /// Kafka topic to produce to
+    pub topic: String,
+    /// Interop publisher to subscribe to on Start
+    pub publisher: String,
+    /// Interop topics to subscribe to
+    pub topics: Vec<String>,
+    /// Message IDs to forward, empty for all handled messages
+    pub msg_ids: Vec<i32>,
+}
+
+pub struct KafkaSource {
+    config: KafkaSourceConfig,
+}
+
+impl KafkaSource {
+    pub fn new(config: KafkaSourceConfig) -> Self {
+        KafkaSource { config }
+    }
+
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        let config = self.config.clone();
+        let codec = link_codec(KAFKA_SOURCE_NAME);
+        thread::spawn(move || {
+            // Resolved once Start has run so C++ actors are visible
+            let subscribers: Vec<ActorRef> = config
+                .subscribers
+                .iter()
+                .filter_map(|name| get_actor_ref(name, KAFKA_SOURCE_NAME))
+                .collect();
+            if subscribers.len() != config.subscribers.len() {
+                eprintln!("[Kafka Source] some subscribers in {:?} were not found", config.subscribers);
+            }
+
+            loop {
+                let mut consumer = match Consumer::from_hosts(config.brokers.clone())
+                    .with_topic(config.topic.clone())
+                    .with_group(config.group.clone())
+                    .with_fallback_offset(FetchOffset::Latest)
+                    .with_offset_storage(Some(GroupOffsetStorage::Kafka))
+                    .create()
+                {
+                    Ok(c) => c,
+                    Err(e) => {
+                        eprintln!("[Kafka Source] connect failed: {}", e);
+                        thread::sleep(Duration::from_secs(1));
+                        continue;
+                    }
+                };
+
+                loop {
+                    let sets = match consumer.poll() {
+                        Ok(s) => s,
+                        Err(e) => {
+                            eprintln!("[Kafka Source] poll failed: {}", e);
+                            break;
+                        }
+                    };
+                    for set in sets.iter() {
+                        for record in set.messages() {
+                            match codec.decode(record.value) {
+                                Ok(msg) => publish(&subscribers, msg.as_ref()),
+                                Err(e) => eprintln!("[Kafka Source] dropping record at {}: {}", record.offset, e),
+                            }
+                        }
+                        let _ = consumer.consume_messageset(set);
+                    }
+                    if let Err(e) = consumer.commit_consumed() {
+                        eprintln!("[Kafka Source] commit failed: {}", e);
+                    }
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
+        });
+    }
+}
+
+fn publish(subscribers: &[ActorRef], msg: &dyn actors::Message) {
+    conversion_cache::fan_out(|| {
+        for sub in subscribers {
+            if let Some(copy) = clone_message(msg) {
+                sub.send(copy, None);
+            }
+        }
+    });
+}
+
+// Register message handlers
+handle_messages!(KafkaSource,
+    Start => on_start
+);
+
+pub struct KafkaSink {
+    config: KafkaSinkConfig,
+    codec: &'static dyn Codec,
+    producer: Option<Producer>,
+}
+
+impl KafkaSink {
+    pub fn new(config: KafkaSinkConfig) -> Self {
+        KafkaSink { config, codec: link_codec(KAFKA_SINK_NAME), producer: None }
+    }
+
+    fn connect(&mut self) -> Option<&mut Producer> {
+        if self.producer.is_none() {
+            match Producer::from_hosts(self.config.brokers.clone())
+                .with_ack_timeout(Duration::from_secs(1))
+                .with_required_acks(RequiredAcks::One)
+                .create()
+            {
+                Ok(p) => self.producer = Some(p),
+                Err(e) => eprintln!("[Kafka Sink] connect failed: {}", e),
+            }
+        }
+        self.producer.as_mut()
+    }
+
+    fn produce<M: InteropMessage + actors::Message>(&mut self, key: &str, msg: &M) {
+        if !self.config.msg_ids.is_empty() && !self.config.msg_ids.contains(&M::MSG_ID) {
+            return;
+        }
+        let frames = match size_limit::encode_frames(KAFKA_SINK_NAME, self.codec, msg) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("[Kafka Sink] {} encode failed: {}", self.codec.name(), e);
+                return;
+            }
+        };
+        let topic = self.config.topic.clone();
+        for frame in frames {
+            let producer = match self.connect() {
+                Some(p) => p,
+                None => return,
+            };
+            if let Err(e) = producer.send(&Record::from_key_value(&topic, key, frame)) {
+                eprintln!("[Kafka Sink] send failed: {}", e);
+                // Reconnect on the next message
+                self.producer = None;
+                return;
+            }
+        }
+    }
+
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        if let Some(publisher) = get_actor_ref(&self.config.publisher, KAFKA_SINK_NAME) {
+            for topic in &self.config.topics {
+                let sub = Subscribe { topic: topic.as_str().into(), qos: QOS_BEST_EFFORT };
+                publisher.send(Box::new(sub), None);
+            }
+        }
+    }
+
+    fn on_market_update(&mut self, msg: &MarketUpdate, _ctx: &mut ActorContext) {
+        self.produce(msg.symbol.as_str(), msg);
+    }
+
+    fn on_market_depth(&mut self, msg: &MarketDepth, _ctx: &mut ActorContext) {
+        self.produce(msg.symbol.as_str(), msg);
+    }
+
+    fn on_data_response(&mut self, msg: &DataResponse, _ctx: &mut ActorContext) {
+        self.produce("", msg);
+    }
+
+    fn on_risk_limits(&mut self, msg: &RiskLimits, _ctx: &mut ActorContext) {
+        self.produce(&msg.account_id.to_string(), msg);
+    }
+}
+
+// Register message handlers
+handle_messages!(KafkaSink,
+    Start => on_start,
+    MarketUpdate => on_market_update,
+    MarketDepth => on_market_depth,
+    DataResponse => on_data_response,
+    RiskLimits => on_risk_limits
+);
@@ -0,0 +1,229 @@
+//! Struct layout check against the C++ build
+//!
+//! Capabilities catch a C++ side that lacks a message, not one that lays
+//! its struct out differently: another `#pragma pack`, another compiler's
+//! idea of a type, a header edited on one side only. Rust would then read
+//! prices from the wrong offset. The generated `cpp_actor_init()` also
+//! sends the size and alignment of every C struct and the offset, size and
+//! alignment of each field as C++ compiled them (`interop_cpp_layouts()`).
+//! `capabilities::verify()` compares them with Rust's, so
+//! `init_cpp_actor_lookup()` refuses to connect on any difference and
+//! prints it field by field:
+//!
+//! ```text
+//! C++ struct layouts differ from Rust's:
+//!   MarketUpdate (1012): size 64 in Rust, 72 in C++
+//!   MarketUpdate.price: offset 40 in Rust, 48 in C++
+//!   MarketUpdate.venue: not in the C++ struct
+//! ```
+//!
+//! `rust_interop_layout_report()` returns the same text at any time, empty
+//! while the layouts agree. Only messages both sides know are compared;
+//! a C++ side that sends no layouts (hand-written bridge, older generator)
+//! is not checked.
+//!
+//! `rust_schema_hash()` and `peer_schema_hash()` reduce all the layouts of
+//! each side to one number, for the capability summary: equal hashes mean
+//! both sides know the same messages laid out the same way.
+
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_name, C_FIELD_LAYOUTS, C_STRUCT_LAYOUTS};
+
+/// One layout entry as C++ sends it; `field` is null for the struct itself
+#[repr(C)]
+pub struct InteropFieldLayout {
+    pub msg_id: i32,
+    pub field: *const c_char,
+    pub offset: u32,
+    pub size: u32,
+    pub align: u32,
+}
+
+/// Where a struct or field sits; a struct's offset is 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub offset: usize,
+    pub size: usize,
+    pub align: usize,
+}
+
+/// One difference. `field` is None for the struct itself; `rust` or `cpp`
+/// is None for a field only the other side has.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutMismatch {
+    pub msg_id: i32,
+    pub field: Option<String>,
+    pub rust: Option<Layout>,
+    pub cpp: Option<Layout>,
+}
+
+impl fmt::Display for LayoutMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = message_name(self.msg_id).unwrap_or("?");
+        match &self.field {
+            Some(field) => write!(f, "{}.{}: ", name, field)?,
+            None => write!(f, "{} ({}): ", name, self.msg_id)?,
+        }
+        match (self.rust, self.cpp) {
+            (Some(rust), Some(cpp)) => {
+                let mut diffs = Vec::new();
+                for (what, r, c) in [
+                    ("offset", rust.offset, cpp.offset),
+                    ("size", rust.size, cpp.size),
+                    ("align", rust.align, cpp.align),
+                ] {
+                    if r != c {
+                        diffs.push(format!("{} {} in Rust, {} in C++", what, r, c));
+                    }
+                }
+                write!(f, "{}", diffs.join("; "))
+            }
+            (Some(_), None) => write!(f, "not in the C++ struct"),
+            (None, Some(_)) => write!(f, "only in the C++ struct"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Every difference found, in message then field order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutError(pub Vec<LayoutMismatch>);
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "C++ struct layouts differ from Rust's:")?;
+        for mismatch in &self.0 {
+            write!(f, "\n  {}", mismatch)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for LayoutError {}
+
+// (message ID, field or None for the struct) -> layout
+type Layouts = BTreeMap<(i32, Option<String>), Layout>;
+
+static PEER: Mutex<Option<Layouts>> = Mutex::new(None);
+
+/// Forget the peer's layouts; a new peer that sends none is not checked
+pub(crate) fn clear() {
+    *PEER.lock().unwrap() = None;
+}
+
+pub fn register(layouts: impl IntoIterator<Item = (i32, Option<String>, Layout)>) {
+    let layouts = layouts.into_iter().map(|(id, field, layout)| ((id, field), layout)).collect();
+    *PEER.lock().unwrap() = Some(layouts);
+}
+
+fn rust_layouts() -> Layouts {
+    let structs = C_STRUCT_LAYOUTS.iter().map(|&(id, _, size, align)| ((id, None), Layout { offset: 0, size, align }));
+    let fields = C_FIELD_LAYOUTS
+        .iter()
+        .map(|&(id, field, offset, size, align)| ((id, Some(field.to_string())), Layout { offset, size, align }));
+    structs.chain(fields).collect()
+}
+
+// FNV-1a over every entry, in message then field order
+fn schema_hash(layouts: &Layouts) -> u64 {
+    fn eat(hash: u64, bytes: &[u8]) -> u64 {
+        bytes.iter().fold(hash, |h, b| (h ^ *b as u64).wrapping_mul(0x100000001b3))
+    }
+    layouts.iter().fold(0xcbf29ce484222325, |mut hash, ((id, field), layout)| {
+        hash = eat(hash, &id.to_le_bytes());
+        hash = eat(hash, field.as_deref().unwrap_or("").as_bytes());
+        hash = eat(hash, &[0]);
+        for n in [layout.offset, layout.size, layout.align] {
+            hash = eat(hash, &(n as u64).to_le_bytes());
+        }
+        hash
+    })
+}
+
+/// Hash of this build's struct layouts
+pub fn rust_schema_hash() -> u64 {
+    schema_hash(&rust_layouts())
+}
+
+/// Hash of the layouts C++ sent, None if it sent none
+pub fn peer_schema_hash() -> Option<u64> {
+    PEER.lock().unwrap().as_ref().map(schema_hash)
+}
+
+/// Compare the peer's layouts with Rust's; Ok if they agree or the peer
+/// sent none
+pub fn verify() -> Result<(), LayoutError> {
+    let peer = match PEER.lock().unwrap().clone() {
+        Some(peer) => peer,
+        None => return Ok(()),
+    };
+    let rust = rust_layouts();
+    let both = |id: i32| rust.contains_key(&(id, None)) && peer.contains_key(&(id, None));
+    let mut keys: Vec<&(i32, Option<String>)> = rust.keys().chain(peer.keys()).filter(|(id, _)| both(*id)).collect();
+    keys.sort();
+    keys.dedup();
+    let mismatches: Vec<LayoutMismatch> = keys
+        .into_iter()
+        .filter_map(|key| {
+            let (r, c) = (rust.get(key).copied(), peer.get(key).copied());
+            (r != c).then(|| LayoutMismatch { msg_id: key.0, field: key.1.clone(), rust: r, cpp: c })
+        })
+        .collect();
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(LayoutError(mismatches))
+    }
+}
+
+/// Send the C++ compiler's struct layouts. Called by the generated
+/// cpp_actor_init(). Returns 0, or InvalidArgument on a null array with a
+/// nonzero count.
+#[export_name = c_symbol!("interop_cpp_layouts")]
+pub extern "C" fn interop_cpp_layouts(entries: *const InteropFieldLayout, count: u32) -> c_int {
+    if entries.is_null() && count > 0 {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    register(slice(entries, count).iter().map(|e| {
+        let field = if e.field.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(e.field) }.to_string_lossy().into_owned())
+        };
+        (e.msg_id, field, Layout { offset: e.offset as usize, size: e.size as usize, align: e.align as usize })
+    }));
+    InteropErrorCode::Ok as c_int
+}
+
+/// Write the differences between the C++ and Rust layouts into `out`
+/// (NUL-terminated, truncated to fit `len`); empty if they agree or C++
+/// sent none. Returns the full length like snprintf.
+#[export_name = c_symbol!("rust_interop_layout_report")]
+pub extern "C" fn rust_interop_layout_report(out: *mut c_char, len: usize) -> c_int {
+    let report = verify().err().map(|e| e.to_string()).unwrap_or_default();
+    copy_out(&report, out, len)
+}
+
+fn slice<'a>(entries: *const InteropFieldLayout, count: u32) -> &'a [InteropFieldLayout] {
+    if entries.is_null() || count == 0 {
+        return &[];
+    }
+    unsafe { std::slice::from_raw_parts(entries, count as usize) }
+}
+
+fn copy_out(s: &str, out: *mut c_char, len: usize) -> c_int {
+    if !out.is_null() && len > 0 {
+        let n = s.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(s.as_ptr(), out as *mut u8, n);
+            *out.add(n) = 0;
+        }
+    }
+    s.len().min(c_int::MAX as usize) as c_int
+}
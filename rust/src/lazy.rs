@@ -0,0 +1,160 @@
+//! Two-phase dispatch: wide messages queued as their C struct
+//!
+//! `rust_actor_send` converts every field of MarketDepth, all four level
+//! arrays, on the C++ thread, though most handlers read the top of the
+//! book and drop the rest. An actor can take a borrowed message type (see
+//! borrow, `INTEROP_BORROWED`) lazily instead: the bridge queues a copy of
+//! the C struct as `Lazy<M>`, and the handler reads fields in place
+//! through the generated view. Only a message the handler keeps is
+//! converted, with `to_message()`:
+//!
+//! ```ignore
+//! lazy::enable::<MarketDepth>("rust_book");
+//!
+//! fn process_message(&mut self, msg: &dyn Message, _ctx: &mut ActorContext) {
+//!     if let Some(depth) = msg.as_any().downcast_ref::<Lazy<MarketDepth>>() {
+//!         let best_bid = depth.read(|d| d.bid_prices()[0]);
+//!         if best_bid > self.threshold {
+//!             self.alerts.push(depth.to_message());    // whole message, kept
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! - Applies to `rust_actor_send` and `rust_actor_fast_send`; posts and
+//!   Rust senders still deliver `M`. The handler must take both.
+//! - Field rules are checked on the view before the message is queued.
+//!   Char fields are read as sent, without the bridge's repair (see
+//!   interop_string).
+//! - Admitted and queued like any bridge send (see inbound): policy, size
+//!   limits, subscriber queues, pause and queue depth apply, and taps,
+//!   leases and the broker are shown the `Lazy<M>`. Features that need
+//!   the converted message (dedup, idempotency, sequence stamps, and the
+//!   journal and spill, which cannot encode it) skip lazy messages.
+
+use std::collections::HashSet;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use actors::{ActorRef, Message};
+
+use crate::borrow::{self, BorrowedMessage};
+use crate::inbound;
+use crate::interop_errors::InteropErrorCode;
+use crate::validation::{self, INVALID_MESSAGE};
+
+/// A borrowed message queued as its C struct; fields are read in place
+pub struct Lazy<M: BorrowedMessage> {
+    c: M::C,
+}
+
+impl<M> Lazy<M>
+where
+    M: BorrowedMessage,
+    M::C: Copy,
+{
+    pub fn new(c: &M::C) -> Self {
+        Lazy { c: *c }
+    }
+
+    /// Read fields through the view; nothing is converted but what `read`
+    /// returns
+    pub fn read<R, F>(&self, read: F) -> R
+    where
+        F: for<'a> FnOnce(&M::View<'a>) -> R,
+    {
+        borrow::scoped(|b| read(&M::view(&self.c, b)))
+    }
+
+    /// The whole message, for one that outlives the handler
+    pub fn to_message(&self) -> M {
+        M::from_c(&self.c)
+    }
+}
+
+impl<M> Message for Lazy<M>
+where
+    M: BorrowedMessage + 'static,
+    M::C: Send + 'static,
+{
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+    fn message_id(&self) -> i32 { M::MSG_ID }
+}
+
+// Fast path: no lock while no actor takes lazy messages
+static ANY_LAZY: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref LAZY: Mutex<HashSet<(String, i32)>> = Mutex::new(HashSet::new());
+}
+
+/// Have bridge sends of `M` to `actor` queued as `Lazy<M>`
+pub fn enable<M: BorrowedMessage>(actor: &str) {
+    LAZY.lock().unwrap().insert((actor.to_string(), M::MSG_ID));
+    ANY_LAZY.store(true, Ordering::Release);
+}
+
+/// Back to converted messages; false if `M` was not lazy for `actor`
+pub fn disable<M: BorrowedMessage>(actor: &str) -> bool {
+    let mut lazy = LAZY.lock().unwrap();
+    let removed = lazy.remove(&(actor.to_string(), M::MSG_ID));
+    ANY_LAZY.store(!lazy.is_empty(), Ordering::Release);
+    removed
+}
+
+pub fn is_enabled(actor: &str, msg_id: i32) -> bool {
+    ANY_LAZY.load(Ordering::Acquire) && LAZY.lock().unwrap().contains(&(actor.to_string(), msg_id))
+}
+
+fn admit<M>(name: &str, sender: &str, c: &M::C) -> Result<Lazy<M>, c_int>
+where
+    M: BorrowedMessage,
+    M::C: Copy,
+{
+    let msg = Lazy::<M>::new(c);
+    match msg.read(|view| M::validate_view(view)) {
+        Ok(()) => Ok(msg),
+        Err(err) => {
+            validation::reject(name, sender, &err);
+            Err(INVALID_MESSAGE)
+        }
+    }
+}
+
+/// rust_actor_send() of a lazy message, once the target is found
+pub(crate) fn send<M>(actor_ref: &ActorRef, name: &str, sender: &str, sender_ref: Option<ActorRef>, c: &M::C) -> c_int
+where
+    M: BorrowedMessage + 'static,
+    M::C: Copy + Send + 'static,
+{
+    match admit::<M>(name, sender, c) {
+        Ok(msg) => {
+            inbound::deliver(actor_ref, name, sender, sender_ref, msg);
+            InteropErrorCode::Ok as c_int
+        }
+        Err(rc) => rc,
+    }
+}
+
+/// rust_actor_fast_send() of a lazy message, once the target is found
+pub(crate) fn fast_send<M>(
+    actor_ref: &ActorRef,
+    name: &str,
+    sender: &str,
+    sender_ref: Option<ActorRef>,
+    c: &M::C,
+) -> c_int
+where
+    M: BorrowedMessage + 'static,
+    M::C: Copy + Send + 'static,
+{
+    match admit::<M>(name, sender, c) {
+        Ok(msg) => {
+            inbound::fast_deliver(actor_ref, name, sender, sender_ref, msg);
+            InteropErrorCode::Ok as c_int
+        }
+        Err(rc) => rc,
+    }
+}
@@ -0,0 +1,195 @@
+//! Subscription leases
+//!
+//! With `interop_set_subscription_lease(ms)`, a subscription lasts `ms`
+//! unless renewed. Publishers keep a `LeaseTable` and stop sending to
+//! subscriptions that have expired, so a subscriber that went away without
+//! an Unsubscribe stops costing the publisher anything.
+//!
+//! Subscribers do nothing: the bridge sees each Subscribe/Unsubscribe that
+//! crosses it, and a renewal thread sends `LeaseRenew` on the subscriber's
+//! behalf every third of the lease for as long as the subscriber actor
+//! exists (`cpp_actor_exists()` or a registered Rust actor). When it is
+//! gone, renewals stop and the lease runs out on the publisher.
+//!
+//! Rust -> Rust subscriptions do not cross the bridge; their subscribers
+//! send `LeaseRenew` themselves or the publisher does not lease them.
+//! Leases are off (0) by default.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actors::Message;
+
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{LeaseRenew, Subscribe, SubscribeMany, Unsubscribe, UnsubscribeMany};
+use crate::names;
+use crate::rust_manager_ffi::get_actor_ref;
+
+static LEASE_MS: AtomicU32 = AtomicU32::new(0);
+static RENEWER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    // (subscriber, publisher, topic) renewed by the bridge
+    static ref RENEWED: Mutex<HashSet<(String, String, String)>> = Mutex::new(HashSet::new());
+}
+
+/// Lease length in ms, 0 if leases are off
+pub fn lease_ms() -> u32 {
+    LEASE_MS.load(Ordering::Relaxed)
+}
+
+/// Lease length, None if leases are off
+pub fn lease() -> Option<Duration> {
+    match lease_ms() {
+        0 => None,
+        ms => Some(Duration::from_millis(ms as u64)),
+    }
+}
+
+/// Turn subscription leases on with a length of `lease_ms`, or off with 0.
+/// Call before rust_manager_init(). Returns 0, or InvalidArgument for a
+/// negative length.
+#[export_name = c_symbol!("interop_set_subscription_lease")]
+pub extern "C" fn interop_set_subscription_lease(lease_ms: c_int) -> c_int {
+    if lease_ms < 0 {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    LEASE_MS.store(lease_ms as u32, Ordering::Relaxed);
+    InteropErrorCode::Ok as c_int
+}
+
+/// Lease length in ms, 0 while leases are off
+#[export_name = c_symbol!("interop_subscription_lease_ms")]
+pub extern "C" fn interop_subscription_lease_ms() -> c_int {
+    lease_ms().min(c_int::MAX as u32) as c_int
+}
+
+/// Publisher-side leases, e.g. by topic or (subscriber, topic)
+pub struct LeaseTable<K> {
+    expiry: HashMap<K, Instant>,
+}
+
+impl<K: Hash + Eq + Clone> Default for LeaseTable<K> {
+    fn default() -> Self {
+        LeaseTable { expiry: HashMap::new() }
+    }
+}
+
+impl<K: Hash + Eq + Clone> LeaseTable<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start or extend a lease on subscribe. No-op while leases are off.
+    pub fn grant(&mut self, key: K) {
+        if let Some(lease) = lease() {
+            self.expiry.insert(key, clock::now() + lease);
+        }
+    }
+
+    /// Extend a live lease by `lease_ms` (from LeaseRenew). Returns false
+    /// for an unknown or expired lease, which is not revived: the
+    /// subscriber has to subscribe again.
+    pub fn renew(&mut self, key: &K, lease_ms: u32) -> bool {
+        let now = clock::now();
+        match self.expiry.get_mut(key) {
+            Some(at) if *at > now => {
+                *at = now + Duration::from_millis(lease_ms as u64);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Drop a lease on unsubscribe
+    pub fn revoke(&mut self, key: &K) {
+        self.expiry.remove(key);
+    }
+
+    /// True unless `key` has a lease that ran out. Always true while
+    /// leases are off, so unleased subscriptions behave as before.
+    pub fn is_live(&self, key: &K) -> bool {
+        match self.expiry.get(key) {
+            Some(at) => *at > clock::now(),
+            None => true,
+        }
+    }
+
+    /// Remove and return every expired lease
+    pub fn expire(&mut self) -> Vec<K> {
+        let now = clock::now();
+        let expired: Vec<K> = self.expiry.iter().filter(|(_, at)| **at <= now).map(|(k, _)| k.clone()).collect();
+        for k in &expired {
+            self.expiry.remove(k);
+        }
+        expired
+    }
+}
+
+/// Track subscriptions crossing the bridge. Called by the bridge.
+pub fn observe(sender: &str, target: &str, msg: &dyn Message) {
+    if lease_ms() == 0 || sender.is_empty() {
+        return;
+    }
+    if let Some(sub) = msg.as_any().downcast_ref::<Subscribe>() {
+        let key = (sender.to_string(), target.to_string(), sub.topic.as_str().to_string());
+        RENEWED.lock().unwrap().insert(key);
+        start_renewer();
+    } else if let Some(unsub) = msg.as_any().downcast_ref::<Unsubscribe>() {
+        let key = (sender.to_string(), target.to_string(), unsub.topic.as_str().to_string());
+        RENEWED.lock().unwrap().remove(&key);
+    } else if let Some(many) = msg.as_any().downcast_ref::<SubscribeMany>() {
+        let mut renewed = RENEWED.lock().unwrap();
+        for topic in many.topics() {
+            renewed.insert((sender.to_string(), target.to_string(), topic.to_string()));
+        }
+        drop(renewed);
+        start_renewer();
+    } else if let Some(many) = msg.as_any().downcast_ref::<UnsubscribeMany>() {
+        let mut renewed = RENEWED.lock().unwrap();
+        for topic in many.topics() {
+            renewed.remove(&(sender.to_string(), target.to_string(), topic.to_string()));
+        }
+    }
+}
+
+fn start_renewer() {
+    if !RENEWER_RUNNING.swap(true, Ordering::AcqRel) {
+        thread::spawn(renew_loop);
+    }
+}
+
+fn subscriber_exists(name: &str) -> bool {
+    names::is_rust(name) || names::cpp_has(name)
+}
+
+fn renew_loop() {
+    loop {
+        let ms = lease_ms();
+        if ms == 0 {
+            break;
+        }
+        clock::sleep(Duration::from_millis((ms / 3).max(1) as u64));
+
+        let subs: Vec<_> = RENEWED.lock().unwrap().iter().cloned().collect();
+        for (subscriber, publisher, topic) in subs {
+            let publisher_ref = match get_actor_ref(&publisher, &subscriber) {
+                Some(p) if subscriber_exists(&subscriber) => p,
+                _ => {
+                    // Either end is gone: let the lease run out
+                    RENEWED.lock().unwrap().remove(&(subscriber, publisher, topic));
+                    continue;
+                }
+            };
+            let renew = LeaseRenew { topic: topic.as_str().into(), lease_ms: ms.min(i32::MAX as u32) as i32 };
+            publisher_ref.send(Box::new(renew), None);
+        }
+    }
+    RENEWER_RUNNING.store(false, Ordering::Release);
+}
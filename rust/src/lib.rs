@@ -2,11 +2,171 @@
 //!
 //! This crate provides:
 //! - `interop_messages` - Message definitions matching the C header
+//! - `interop_string` - `InteropString<N>` for fixed-size `char[N]` fields, truncation and repair
+//! - `interop_bytes` - Variable-length byte fields and their FFI ownership rules
+//! - `decimal` - `Decimal` fixed-point fields (`interop_decimal`) for exact prices
+//! - `timestamp` - `Timestamp` fields (`interop_timestamp`): UTC nanoseconds with SystemTime/chrono conversions
+//! - `clock` - Pluggable clock for timers and time stamps; simulated for backtests
+//! - `replay` - `ReplaySource`: recorded market data published through the broker for backtests
+//! - `uuid` - `Uuid` fields (`interop_uuid`)
 //! - `rust_actor_bridge` - extern "C" functions for C++ to call Rust actors
 //! - `cpp_actor_if` - CppActorIF for Rust to call C++ actors
+//! - `interop_errors` - `InteropErrorCode`: the codes every bridge call returns, shared with C++
+//! - `send_error` - `Result<(), SendError>` for sends, `TrySend` for `ActorRef`
+//! - `retry` - Retry with backoff for sends to C++ actors
+//! - `circuit` - Per sender -> C++ target circuit breaker on repeated failures
+//! - `rate_limit` - Token-bucket limits per actor / ActorRef on sends to C++
+//! - `size_limit` - Per-transport message size limits and payload chunking
+//! - `outbound` - Queued Rust -> C++ sends flushed by a thread per C++ runtime
+//! - `policy` - Allow/deny rules the bridge applies to every crossing message
+//! - `sender_auth` - Host hook authenticating senders of inbound messages, rejects audited
+//! - `tenant` - Tenant labels: cross-tenant sends refused, introspection narrowed per tenant
+//! - `contracts` - Messages outside an actor's declared INTEROP_ACTOR contract logged or rejected
+//! - `validation` - Field rules from the IDL checked on messages from C++
+//! - `interop_error` - `InteropError` replies to senders of messages a bridge could not dispatch
+//! - `ask` - `ActorRef::ask()`: typed request/response with a timeout; `ctx.ask_then()` without blocking; C++ too
+//! - `transaction` - `ctx.transaction()`: a handler's sends made together after it returns, or not at all
+//! - `stream` - Ordered, credit-based chunk streams between two actors
+//! - `registry` - `#[interop_actor]` types C++ can create by type name
+//! - `names` - Actor names unique across both runtimes, with opt-in shadowing
+//! - `aliases` - Logical names remapped to concrete Rust or C++ actors
+//! - `lookup_cache` - Cached, coalesced C++ actor lookups behind `get_actor_ref`
+//! - `locality` - C++ refs to names now held by Rust actors, delivered in process
+//! - `actor_ids` - Numeric actor ids agreed at lookup, so sends skip name lookups
+//! - `orphans` - Refs held across the bridge to actors that were removed or stopped
+//! - `resolver` - Replaceable, chainable lookup for names the Rust Manager does not own
+//! - `cpp_runtime` - Several C++ runtimes (e.g. dlopen'd libraries) behind one Manager
 //! - `rust_manager_ffi` - FFI functions for C++ to manage Rust Manager
+//! - `system` - `InteropSystem::builder()`: the same setup driven from Rust
+//! - `capabilities` - Check the C++ peer implements what Rust needs before connecting; summary of what was negotiated
+//! - `layout` - Check the C++ build lays out every message struct as Rust does
+//! - `inbound` - Admission and delivery shared by every C++ -> Rust entry point
+//! - `post` - `rust_actor_post` for C++ threads that are not actors
+//! - `borrow` - `rust_actor_borrow_send`: handlers read caller-owned C buffers in place
+//! - `lazy` - `Lazy<M>`: borrowed messages queued as their C struct, converted only if kept
+//! - `cpp_hosted_actor` - C++ actors (vtable) scheduled by the Rust Manager
+//! - `shared_thread` - Low-rate actors scheduled together on one shared thread
+//! - `passivation` - Idle actors snapshotted and dropped, rebuilt by their next message
+//! - `tasks` - `rust_manager_spawn_task`: periodic C++ jobs on Rust-owned threads
+//! - `lifecycle` - Supervision and started/stopped/panicked/restarted callbacks
+//! - `quarantine` - Messages that keep panicking their handler
+//! - `panic_context` - Actor state dumps reported with handler panics
+//! - `snapshot` - Actor state kept across restarts
+//! - `replace` - Blue/green replacement of a running actor with a new version
+//! - `parallel` - Read-only handlers run concurrently on a worker pool
+//! - `startup_barrier` - Hold Start until both runtimes are ready
+//! - `shutdown` - ShutdownRequested / ShutdownReady handshake before the managers end
+//! - `teardown` - `interop_shutdown_all()`: free the Manager, caches and callbacks for leak checkers
+//! - `dependencies` - Hold Start until declared actor dependencies are registered
+//! - `sequencing` - Bridge-stamped sequence numbers and `GapDetector`
+//! - `symbols` - Symbol ids for `CompactMarketUpdate` and expansion back to strings
+//! - `topic` - `Topic<M>`: topics declared with the message type they carry
+//! - `broker` - Subscription registry behind `publish(topic, msg)`
+//! - `subscriber` - `DynamicSubscriber` mixin: subscriptions changed by the C++ host
+//! - `interop_subscriber` - `InteropSubscriber`: a subscriber actor built from per-message callbacks
+//! - `subscribe_many` - Topic lists for subscribing to many topics in one message
+//! - `partitions` - Topics split into keyed partitions for scaling consumers
+//! - `sub_filter` - Per-subscription filter expressions and transforms, applied before sending
+//! - `throttle` - `ThrottledPublisher`: latest-value publishing capped at a rate per topic
+//! - `depth` - `DepthPublisher`: double-buffered MarketDepth books published as whole snapshots
+//! - `sys_topics` - Runtime events on `$sys/` topics for monitoring actors
+//! - `config` - Key/value settings shared by both runtimes, watched through `$config`
+//! - `qos` - Per-topic best-effort / reliable delivery
+//! - `reliable` - Acknowledged, retransmitted delivery to reliable subscribers
+//! - `sub_queue` - Bounded per-subscriber queues: drop-oldest, drop-newest, conflate or block
+//! - `fairness` - Round-robin fan-out with backlogs for slow subscribers and starvation stats
+//! - `lease` - Subscription leases renewed by the bridge, expired by publishers
+//! - `queue_depth` - Inbound mailbox depth estimate for flow control
+//! - `dedup` - Per-actor window dropping repeated inbound messages
+//! - `ttl` - Per-actor / per-type message TTL; stale messages dropped on dequeue
+//! - `deadlines` - Per-actor / per-type handler deadlines; overruns counted and on `$sys/deadlines`
+//! - `envelope` - Sender, origin, receive time, seq and trace id for handlers
+//! - `fallback` - `handle_messages!` with a catch-all `_` arm; unknown IDs from C++
+//! - `idempotency` - Replayed idempotency keys (NewOrder) answered with the first ack
+//! - `spill` - Disk-backed overflow for inbound bursts
+//! - `journal` - Write-ahead journal of inbound messages, redelivered after a crash
+//! - `pause` - Hold bridge traffic for a Rust actor while the host quiesces it
+//! - `freeze` - Freeze a Rust actor to inspect, drop or reorder its pending messages
+//! - `busy_poll` - Per-actor spin budget before parking, for low wakeup latency
+//! - `wire` - Byte frames for transports outside the process
+//! - `codec` - Per-link encodings (raw C struct, portable binary, JSON debug, protobuf, FlatBuffers, Cap'n Proto)
+//! - `schema` - Message names, IDs and field descriptors at run time (`interop_message_schema`)
+//! - `protobuf` - Protobuf wire format of the generated messages
+//! - `flatbuf` - FlatBuffers tables for large messages read in place by C++
+//! - `capnproto` - Cap'n Proto messages on the generated schema, read in place (feature `capnp`)
+//! - `cached_send` - `send_pooled` with no message `Box` or name allocations for Rust -> C++ firehoses
+//! - `hash_router` - Consistent-hash routing by key (e.g. symbol) across workers
+//! - `router` - Round-robin, weighted and circuit-aware failover routing, optionally sticky per key
+//! - `content_routes` - Route names whose rules pick each message's target by its fields, loaded from config
+//! - `group` - Named actor groups across languages; `spawn_n` instance groups and their routers
+//! - `tap` - Wire taps that copy bridge traffic to an observer
+//! - `capture` - 1-in-N sampled bridge messages in a ring, dumped as JSON on demand
+//! - `interop_log` - Verbose logging of sends, lookups and conversions, switched on at runtime per process or actor
+//! - `health` - JSON health summary for the C++ host (`rust_interop_health`)
+//! - `probe` - Echo actor and periodic latency probes across the bridge, with round-trip percentiles
+//! - `dead_letters` - Last failed deliveries with origin, route and failure stage
+//! - `stats` - Allocation-free Manager statistics (`rust_manager_stats`)
+//! - `alloc_stats` - Bytes held for each Rust actor's queued messages (`rust_actor_alloc_stats`)
+//! - `boundary` - C struct bytes and conversions across the language boundary, per message type
+//! - `conversion_cache` - One C struct conversion per fan-out for equal messages
+//! - `thread_usage` - CPU time and context switches of each Rust actor's thread (`rust_actor_thread_usage`)
+//! - `control` - Unix socket control channel for `interop-ctl`
+//! - `trace` - `tracing` spans for C++ sections, FFI sends and handlers (feature `tracing`)
+//! - `mqtt_bridge` - Mirror pub/sub topics to MQTT (feature `mqtt`)
+//! - `kafka_connector` - Kafka source/sink actors (feature `kafka`)
+//! - `wasm_actor` - WASM modules hosted as sandboxed actors (feature `wasm`)
+//! - `node` - Node.js subscribe/send/ask bindings (feature `node`)
+//! - `sim` - Deterministic virtual-clock simulation for tests (feature `sim`)
+//! - `hot_reload` - Load and reload C++ actor libraries at run time (feature `hot_reload`)
 //!
 //! Uses Manager's actor registry instead of separate registries.
+//!
+//! With feature `versioned_symbols` every C function is exported with the
+//! prefix `C_SYMBOL_PREFIX` instead of its plain name, so two versions of
+//! the library can be loaded in one process; C++ uses the single header
+//! written by `codegen/c_api.py`.
+
+// Lets #[interop_actor] expand to ::actors_interop paths inside this crate
+extern crate self as actors_interop;
+
+// Exported name of a C function: `#[export_name = c_symbol!("rust_actor_send")]`
+#[cfg(not(feature = "versioned_symbols"))]
+macro_rules! c_symbol {
+    ($name:literal) => {
+        $name
+    };
+}
+#[cfg(feature = "versioned_symbols")]
+macro_rules! c_symbol {
+    ($name:literal) => {
+        concat!("ai1_", $name)
+    };
+}
+
+/// Prefix of the exported C functions: empty, or `ai<C API version>_`
+/// with feature `versioned_symbols`
+pub const C_SYMBOL_PREFIX: &str = c_symbol!("");
+
+// Fixed-size string type used by the generated messages
+pub mod interop_string;
+
+// Variable-length byte buffers shared with C++
+pub mod interop_bytes;
+
+// Fixed-point decimal fields
+pub mod decimal;
+
+// Nanosecond UTC timestamp fields
+pub mod timestamp;
+
+// System or virtual time for every timer and time stamp
+pub mod clock;
+
+// Recorded market data replayed through pub/sub
+pub mod replay;
+
+// 16-byte UUID fields
+pub mod uuid;
 
 // Include generated code
 #[path = "../../generated/rust/interop_messages.rs"]
@@ -18,12 +178,338 @@ pub mod rust_actor_bridge;
 #[path = "../../generated/rust/cpp_actor_if.rs"]
 pub mod cpp_actor_if;
 
+#[path = "../../generated/rust/interop_errors.rs"]
+pub mod interop_errors;
+
+// Send outcomes as Result instead of i32 codes
+pub mod send_error;
+
+// Retry policy for sends to C++
+pub mod retry;
+
+// Fail fast to C++ actors that keep failing
+pub mod circuit;
+
+// Outbound send limits
+pub mod rate_limit;
+
+// Maximum message size per transport
+pub mod size_limit;
+
+// Outbound queues so Rust handlers never wait on C++ mailboxes
+pub mod outbound;
+
+// Allow/deny rules for bridge traffic
+pub mod policy;
+
+// Host hook accepting or rejecting senders of inbound messages
+pub mod sender_auth;
+
+// Desks sharing one host, kept apart at the bridge
+pub mod tenant;
+
+// Declared actor interfaces checked at the bridge
+pub mod contracts;
+
+// Generated field rules checked on receipt
+pub mod validation;
+
+// Error replies for failed dispatch
+pub mod interop_error;
+
+// Blocking request/response on any ActorRef
+pub mod ask;
+
+// Sends buffered until the handler succeeds
+pub mod transaction;
+
+// Large transfers as flow-controlled chunk streams
+pub mod stream;
+
+// Actor types registrable by name
+pub mod registry;
+
+// Name conflicts between the Rust and C++ registries
+pub mod names;
+
+// Alias names resolved before every lookup
+pub mod aliases;
+
+// Fewer cpp_actor_exists() crossings during lookup storms
+pub mod lookup_cache;
+
+// C++ refs whose target has become a Rust actor, sent to in process
+pub mod locality;
+
+// Numeric actor ids in place of names on the send path
+pub mod actor_ids;
+
+// Who still holds refs to actors that are gone
+pub mod orphans;
+
+// Resolvers behind get_actor_ref() for tests and other transports
+pub mod resolver;
+
+// Routing to one of several attached C++ runtimes
+pub mod cpp_runtime;
+
 // FFI for Rust Manager management
 pub mod rust_manager_ffi;
 
+// Setup from a Rust binary instead of a C++ host
+pub mod system;
+
+// What the C++ side was built with
+pub mod capabilities;
+
+// Struct sizes and field offsets as the C++ compiler laid them out
+pub mod layout;
+
+// The checks and queueing every C++ -> Rust send goes through
+pub mod inbound;
+
+// Thread-safe posting from non-actor C++ threads
+pub mod post;
+
+// Copy-free delivery of INTEROP_BORROWED messages
+pub mod borrow;
+
+// INTEROP_BORROWED messages queued unconverted for opted-in actors
+pub mod lazy;
+
+// C++ actors hosted on Rust Manager threads
+pub mod cpp_hosted_actor;
+
+// Several small actors on one thread
+pub mod shared_thread;
+
+// Idle timeouts: dormant actors hold no instance
+pub mod passivation;
+
+// Periodic background jobs run for C++
+pub mod tasks;
+
+// Actor lifecycle events reported to C++
+pub mod lifecycle;
+
+// Poison messages set aside by the supervisor
+pub mod quarantine;
+
+// What an actor was doing when its handler panicked
+pub mod panic_context;
+
+// Snapshot / restore hooks for restarts
+pub mod snapshot;
+
+// Swap in a new version of a running actor
+pub mod replace;
+
+// Concurrent read-only handlers
+pub mod parallel;
+
+// Two-phase init across both runtimes
+pub mod startup_barrier;
+
+// Drain and confirm on both sides before ending the managers
+pub mod shutdown;
+
+// Nothing left allocated at exit
+pub mod teardown;
+
+// Start held for actors the Rust actors need
+pub mod dependencies;
+
+// Sequence numbers on subscriptions
+pub mod sequencing;
+
+// Symbol-interned compact market data
+pub mod symbols;
+
+// Topics typed by their message
+pub mod topic;
+
+// Who subscribed to what, for publishers
+pub mod broker;
+
+// Subscriptions a C++ host adds and drops at run time
+pub mod subscriber;
+
+// Subscriber actor assembled from callbacks, in Rust or C++
+pub mod interop_subscriber;
+
+// Many topics per Subscribe
+pub mod subscribe_many;
+
+// Topics split by a key field, ordered within each partition
+pub mod partitions;
+
+// Filters the publisher side applies for each subscriber
+pub mod sub_filter;
+
+// Publishers downsampled to a max rate per topic
+pub mod throttle;
+
+// MarketDepth books built level by level, published only when consistent
+pub mod depth;
+
+// Lifecycle, dead letter, heartbeat and queue events as topics
+pub mod sys_topics;
+
+// Settings either side sets and watches
+pub mod config;
+
+// Per-topic delivery QoS
+pub mod qos;
+
+// Acknowledged delivery for reliable subscriptions
+pub mod reliable;
+
+// Overflow policies of individual subscribers
+pub mod sub_queue;
+
+// Fan-out that slow subscribers cannot stall
+pub mod fairness;
+
+// Expiring subscriptions
+pub mod lease;
+
+// Mailbox depth tracking for bridge traffic
+pub mod queue_depth;
+
+// Duplicate suppression for bridged messages
+pub mod dedup;
+
+// Stale bridged messages dropped when dequeued
+pub mod ttl;
+
+// Handlers that overran their processing deadline
+pub mod deadlines;
+
+// Provenance of bridged messages, passed to handlers
+pub mod envelope;
+
+// Catch-all handlers and messages of unknown type
+pub mod fallback;
+
+// Replay protection keyed by an idempotency field
+pub mod idempotency;
+
+// Optional disk spill past a queue depth threshold
+pub mod spill;
+
+// Optional write-ahead journal for at-least-once handling across crashes
+pub mod journal;
+
+// Dispatch paused per actor, messages held until resume
+pub mod pause;
+
+// Actor handling stopped, pending messages kept for inspection until thawed
+pub mod freeze;
+
+// Spin-then-park receive for latency-critical actors
+pub mod busy_poll;
+
+// Byte frames for external transports
+pub mod wire;
+
+// Pluggable encodings for transports
+pub mod codec;
+
+// Message schemas for tools built without the generated types
+pub mod schema;
+
+// Protobuf field encodings used by the generated messages
+pub mod protobuf;
+
+// FlatBuffers tables of the INTEROP_FLATBUFFERS messages
+pub mod flatbuf;
+
+// Cached C names for sends to C++ without per-send allocations
+pub mod cached_send;
+
+// Per-key routing across Rust/C++ worker actors
+pub mod hash_router;
+
+// Weighted and failover target selection
+pub mod router;
+
+// Targets picked by message content, e.g. symbol ranges during a migration
+pub mod content_routes;
+
+// Named actor groups and indexed instances of one actor
+pub mod group;
+
+// Observers for cross-language traffic
+pub mod tap;
+
+// Sampled payloads of cross-language traffic
+pub mod capture;
+
+// Runtime-switchable bridge debug logging
+pub mod interop_log;
+
+// Health summary exported to the C++ host
+pub mod health;
+
+// Round trips to echo actors, for watching bridge latency in production
+pub mod probe;
+
+// Recent failed deliveries, for debugging cross-language sends
+pub mod dead_letters;
+
+// Control socket for the interop-ctl tool
+pub mod control;
+
+// Counters sampled by the C++ monitoring thread
+pub mod stats;
+
+// Per-actor accounting of bridge allocations
+pub mod alloc_stats;
+
+// Bytes crossing the language boundary, for sizing an IPC transport
+pub mod boundary;
+
+// C structs shared by the sends of one fan-out
+pub mod conversion_cache;
+
+// CPU accounting of actor threads against the rest of the process
+pub mod thread_usage;
+
+// Profiling spans across the FFI boundary
+pub mod trace;
+
+// Optional MQTT mirror of pub/sub topics
+#[cfg(feature = "mqtt")]
+pub mod mqtt_bridge;
+
+// Optional Kafka source/sink actors
+#[cfg(feature = "kafka")]
+pub mod kafka_connector;
+
+// Optional sandboxed WASM actors
+#[cfg(feature = "wasm")]
+pub mod wasm_actor;
+
+// Optional napi-rs addon for Node.js dashboards
+#[cfg(feature = "node")]
+pub mod node;
+
+// Seeded, virtual-clock scheduling of cross-language traffic for tests
+#[cfg(feature = "sim")]
+pub mod sim;
+
+// Optional C++ actor libraries reloaded without a restart
+#[cfg(feature = "hot_reload")]
+pub mod hot_reload;
+
+// Optional Cap'n Proto encoding of every message
+#[cfg(feature = "capnp")]
+pub mod capnproto;
+
 // Re-export commonly used items
 pub use interop_messages::*;
 pub use cpp_actor_if::{CppActorIF, InteropMessage};
+pub use send_error::{SendError, TrySend};
 
 // Example actors - included in the library so they can be called from C++
 #[path = "../../examples/ping_pong/rust_pong.rs"]
@@ -0,0 +1,346 @@
+//! Lifecycle events of Rust-managed actors, reported to C++
+//!
+//! Every actor registered through `rust_manager_ffi` runs inside a
+//! `Supervised` wrapper, which reports:
+//!
+//! - `Started` after the actor has handled Start
+//! - `Panicked` when a handler panics
+//! - `Restarted` once a fresh instance has been built by the actor's
+//!   factory and has handled Start; the message that panicked is then
+//!   delivered again, or quarantined after repeated panics (`quarantine`)
+//! - `Stopped` when the Manager drops the actor at shutdown
+//!
+//! Actors with `Snapshot` hooks keep their state across both kinds of
+//! restart (see snapshot). Messages dequeued past their deadline are
+//! dropped before the handler runs (see ttl). A message's envelope is
+//! current while it is handled (see envelope). Start of an actor with
+//! start requirements waits for them first (see dependencies). Sends
+//! buffered in transactions are made once the handler has returned, and
+//! discarded if it panicked (see transaction). A running actor's instance
+//! can be swapped for a new version, reported as Restarted (see replace).
+//! A frozen actor's messages are kept instead of handled, and handled
+//! when it is thawed (see freeze). A journaled actor's messages are marked
+//! done as they are handled, and those a crash left unhandled are handled
+//! right after Start (see journal). An idle actor with an idle timeout
+//! is passivated, its instance dropped after a snapshot, and rebuilt and
+//! restored by its next message (see passivation). A panic is
+//! reported with a dump of the actor's state if it provides one (see
+//! panic_context). An update of a reliable subscription is acknowledged
+//! once its handler has returned normally (see reliable).
+//!
+//! The callback set with `rust_register_lifecycle_callback()` is called on
+//! the actor's own thread with the actor name, which is only valid for the
+//! duration of the call.
+//!
+//! The same events are published on `$sys/lifecycle` (see sys_topics),
+//! together with C++ actor restarts reported by
+//! `interop_report_cpp_restart()`, so subscribers of a restarted C++
+//! publisher can subscribe again (see subscriber).
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
+
+use actors::messages::Start;
+use actors::{Actor, ActorContext, Message};
+
+use crate::clock;
+use crate::deadlines;
+use crate::dependencies;
+use crate::envelope;
+use crate::freeze::{self, Thaw};
+use crate::interop_errors::InteropErrorCode;
+use crate::journal;
+use crate::names::c_str;
+use crate::panic_context;
+use crate::passivation::{self, Dormant, Passivate};
+use crate::quarantine::{self, MAX_DELIVERY_ATTEMPTS};
+use crate::reliable;
+use crate::replace::{self, Swap};
+use crate::rust_manager_ffi;
+use crate::schema;
+use crate::snapshot::{self, Instance};
+use crate::stats;
+use crate::thread_usage;
+use crate::trace;
+use crate::transaction;
+use crate::ttl;
+
+/// Mirrors INTEROP_LIFECYCLE_* in interop/lifecycle.h
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub enum LifecycleEvent {
+    Started = 0,
+    Stopped = 1,
+    Panicked = 2,
+    Restarted = 3,
+}
+
+pub type LifecycleCallback = extern "C" fn(actor_name: *const c_char, event: c_int);
+
+static CALLBACK: Mutex<Option<LifecycleCallback>> = Mutex::new(None);
+
+/// Set the callback for lifecycle events, or clear it with null
+#[export_name = c_symbol!("rust_register_lifecycle_callback")]
+pub extern "C" fn rust_register_lifecycle_callback(cb: Option<LifecycleCallback>) {
+    *CALLBACK.lock().unwrap() = cb;
+}
+
+/// Report `event` for `name` to the registered callback, if any, and on
+/// `$sys/lifecycle`
+pub fn notify(name: &str, event: LifecycleEvent) {
+    crate::lookup_cache::invalidate(name);
+    crate::health::record_lifecycle(name, event);
+    match event {
+        LifecycleEvent::Stopped => crate::orphans::stopped(name),
+        LifecycleEvent::Started | LifecycleEvent::Restarted => crate::orphans::back(name),
+        LifecycleEvent::Panicked => {}
+    }
+    crate::sys_topics::lifecycle(name, event);
+    let cb = *CALLBACK.lock().unwrap();
+    if let (Some(cb), Ok(name)) = (cb, CString::new(name)) {
+        cb(name.as_ptr(), event as c_int);
+    }
+}
+
+/// Report that C++ actor `actor_name` was restarted and has lost its
+/// state, e.g. its subscribers. Published on `$sys/lifecycle` as
+/// Restarted. Returns 0, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_report_cpp_restart")]
+pub extern "C" fn interop_report_cpp_restart(actor_name: *const c_char) -> c_int {
+    let rc = match c_str(actor_name) {
+        Some(name) => {
+            crate::lookup_cache::invalidate(name);
+            crate::sys_topics::lifecycle(name, LifecycleEvent::Restarted);
+            InteropErrorCode::Ok
+        }
+        None => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Builds a fresh instance of an actor after a panic
+pub type ActorFactory = Box<dyn FnMut() -> Instance + Send>;
+
+/// Actor wrapper that reports lifecycle events and restarts on panic
+pub struct Supervised {
+    name: String,
+    factory: ActorFactory,
+    actor: Instance,
+    // Handler time, for stats
+    busy_ns: Arc<AtomicU64>,
+    // Passivated: `actor` is a placeholder and the state is in snapshot
+    dormant: bool,
+}
+
+impl Supervised {
+    pub fn new<F>(name: &str, mut factory: F) -> Self
+    where
+        F: FnMut() -> Instance + Send + 'static,
+    {
+        let actor = factory();
+        Supervised {
+            name: name.to_string(),
+            factory: Box::new(factory),
+            actor,
+            busy_ns: stats::actor_counter(),
+            dormant: false,
+        }
+    }
+
+    fn restart(&mut self, ctx: &mut ActorContext) {
+        let state = self.actor.snapshot();
+        self.actor = (self.factory)();
+        if let Some(state) = state {
+            self.restore(&state);
+        }
+        let actor = &mut self.actor;
+        let started = panic::catch_unwind(AssertUnwindSafe(|| actor.process_message(&Start, ctx)));
+        match started {
+            Ok(()) => notify(&self.name, LifecycleEvent::Restarted),
+            Err(_) => {
+                eprintln!(
+                    "[Lifecycle] {}: panicked again in Start after restart{}",
+                    self.name,
+                    state_suffix(panic_context::take().as_deref())
+                );
+                notify(&self.name, LifecycleEvent::Panicked);
+            }
+        }
+    }
+
+    /// Handle what the actor's journal kept from before a crash
+    fn handle_recovered(&mut self, ctx: &mut ActorContext) {
+        for msg in journal::take_recovered(&self.name) {
+            self.process_message(msg.as_ref(), ctx);
+        }
+    }
+
+    /// Snapshot the idle instance and drop it (see passivation)
+    fn passivate(&mut self) {
+        if self.dormant {
+            return;
+        }
+        if matches!(self.actor, Instance::Plain(_)) {
+            passivation::refuse(&self.name, "no Snapshot hooks");
+            return;
+        }
+        let state = match self.actor.snapshot() {
+            Some(state) => state,
+            None => return passivation::refuse(&self.name, "snapshot panicked"),
+        };
+        if !passivation::confirm(&self.name) {
+            return;  // a message came first
+        }
+        snapshot::save(&self.name, state);
+        self.actor = Instance::plain(Dormant);
+        self.dormant = true;
+    }
+
+    /// Rebuild a passivated instance from its snapshot and start it
+    fn activate(&mut self, ctx: &mut ActorContext) {
+        self.dormant = false;
+        self.actor = (self.factory)();
+        if let Some(state) = snapshot::take(&self.name) {
+            self.restore(&state);
+        }
+        let actor = &mut self.actor;
+        if panic::catch_unwind(AssertUnwindSafe(|| actor.process_message(&Start, ctx))).is_err() {
+            eprintln!(
+                "[Lifecycle] {}: panicked in Start on re-activation{}",
+                self.name,
+                state_suffix(panic_context::take().as_deref())
+            );
+            notify(&self.name, LifecycleEvent::Panicked);
+        }
+        passivation::activated(&self.name);
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        if !self.actor.restore(state) {
+            eprintln!("[Lifecycle] {}: restore panicked, starting empty", self.name);
+            self.actor = (self.factory)();
+        }
+    }
+}
+
+/// "; state: <dump>" for a panic with a state dump (see panic_context)
+fn state_suffix(context: Option<&str>) -> String {
+    context.map(|c| format!("; state: {}", c)).unwrap_or_default()
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s
+    } else {
+        "non-string panic payload"
+    }
+}
+
+impl Actor for Supervised {
+    fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+        if msg.as_any().is::<Passivate>() {
+            self.passivate();
+            return;
+        }
+        if self.dormant {
+            let any = msg.as_any();
+            let wakes = schema::by_id(msg.message_id()).is_some() || any.is::<Swap>() || any.is::<Thaw>();
+            if passivation::is_ending() && !wakes {
+                return;  // End needs no instance
+            }
+            self.activate(ctx);
+        }
+        passivation::touch(&self.name);
+        if let Some(swap) = msg.as_any().downcast_ref::<Swap>() {
+            replace::apply(swap, &self.name, &mut self.actor, &mut self.factory, ctx);
+            return;
+        }
+        if msg.as_any().is::<Thaw>() {
+            for kept in freeze::take_thawed(&self.name) {
+                self.process_message(kept.as_ref(), ctx);
+            }
+            return;
+        }
+        let _envelope = envelope::enter(msg);
+        if ttl::expired(&self.name, msg) {
+            journal::done(&self.name, msg);
+            return;  // stale: dropped rather than handled
+        }
+        if freeze::divert(&self.name, msg) {
+            reliable::held(msg);
+            return;  // kept until thawed
+        }
+        let is_start = msg.as_any().is::<Start>();
+        if is_start {
+            thread_usage::register(&self.name);
+            if let Err(e) = dependencies::await_start(&self.name, rust_manager_ffi::is_registered) {
+                eprintln!("[Lifecycle] {}: starting anyway: {}", self.name, e);
+            }
+            if let Some(state) = snapshot::take(&self.name) {
+                self.restore(&state);
+            }
+        }
+        let mut attempt = 1;
+        loop {
+            let actor = &mut self.actor;
+            let begin = clock::now();
+            let _span = trace::handler(&self.name, msg.message_id());
+            transaction::begin(&self.name);
+            let result = panic::catch_unwind(AssertUnwindSafe(|| actor.process_message(msg, ctx)));
+            transaction::end(result.is_ok());
+            let elapsed = clock::now().saturating_duration_since(begin);
+            stats::record_dispatch(&self.busy_ns, elapsed);
+            deadlines::check(&self.name, msg.message_id(), elapsed);
+            let payload = match result {
+                Ok(()) => {
+                    journal::done(&self.name, msg);
+                    reliable::handled(msg);
+                    if is_start {
+                        notify(&self.name, LifecycleEvent::Started);
+                        self.handle_recovered(ctx);
+                    }
+                    return;
+                }
+                Err(payload) => payload,
+            };
+            let reason = panic_message(payload.as_ref());
+            let context = panic_context::take();
+            eprintln!(
+                "[Lifecycle] {}: panicked handling message {} (attempt {}): {}{}",
+                self.name,
+                msg.message_id(),
+                attempt,
+                reason,
+                state_suffix(context.as_deref())
+            );
+            notify(&self.name, LifecycleEvent::Panicked);
+            self.restart(ctx);
+            if is_start {
+                self.handle_recovered(ctx);
+                return;  // restart() has delivered Start to the new instance
+            }
+            if attempt >= MAX_DELIVERY_ATTEMPTS {
+                journal::done(&self.name, msg);
+                reliable::held(msg);
+                quarantine::add(&self.name, msg, reason, context.as_deref());
+                return;
+            }
+            attempt += 1;
+        }
+    }
+}
+
+impl Drop for Supervised {
+    fn drop(&mut self) {
+        if let Some(state) = self.actor.snapshot() {
+            snapshot::save(&self.name, state);
+        }
+        thread_usage::forget(&self.name);
+        notify(&self.name, LifecycleEvent::Stopped);
+    }
+}
@@ -0,0 +1,85 @@
+//! In-process delivery for C++ refs that name a Rust actor
+//!
+//! An `ActorRef::Cpp` is fixed when it is looked up. If a Rust actor later
+//! takes the name (a Rust replacement registered after the lookup, a
+//! shadowing actor, a name first resolved through the C++ side), sends
+//! through the old ref still convert to the C struct, cross the FFI and
+//! come back, or fail if C++ no longer has the actor.
+//!
+//! `cpp_send_fn` admits the send as it would any send to C++ (shutdown
+//! gate, policy and tenants, size and rate limits, taps), then asks here.
+//! If the target is a Rust actor, the host's sender auth hook is asked
+//! too, as the bridge would on the way back in (see sender_auth), and the
+//! message is cloned and sent to it like any Rust -> Rust send, with no
+//! conversion, FFI, outbound queue, circuit or retries. The send returns
+//! the code of the first check that refuses it, else Ok. Otherwise the
+//! send crosses the bridge as before.
+//!
+//! Answers are cached per target and dropped with the C++ lookup cache
+//! (see lookup_cache): when a Rust actor claims or gives up the name, on
+//! its lifecycle events, on `cpp_actor_init()` and on
+//! `interop_invalidate_lookup()`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+
+use actors::{ActorRef, Message};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::clone_message;
+use crate::names;
+use crate::policy;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::sender_auth::{self, Entry};
+
+static LOCAL_SENDS: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    // Target name -> its Rust ref, or None while it is not a Rust actor
+    static ref LOCAL: RwLock<HashMap<String, Option<ActorRef>>> = RwLock::new(HashMap::new());
+}
+
+/// The Rust actor named `name`, if it is one
+pub fn local_ref(name: &str) -> Option<ActorRef> {
+    if let Some(cached) = LOCAL.read().unwrap().get(name) {
+        return cached.clone();
+    }
+    if !names::is_rust(name) {
+        LOCAL.write().unwrap().insert(name.to_string(), None);
+        return None;
+    }
+    // Claimed but not registered yet: not cached, asked again next time
+    let found = get_actor_ref(name, "").filter(|r| !matches!(r, ActorRef::Cpp(_)))?;
+    LOCAL.write().unwrap().insert(name.to_string(), Some(found.clone()));
+    Some(found)
+}
+
+/// Send an admitted `msg` to Rust actor `actor`, named `target`, in
+/// process. None if the message is not an interop type, and the send
+/// should cross the bridge.
+pub(crate) fn send(actor: &ActorRef, target: &str, sender: &str, msg: &dyn Message) -> Option<i32> {
+    let msg = clone_message(msg)?;
+    if !sender_auth::admit(sender, target, msg.message_id(), Entry::Send) {
+        return Some(policy::POLICY_DENIED);
+    }
+    let sender_ref = if sender.is_empty() { None } else { get_actor_ref(sender, target) };
+    actor.send(msg, sender_ref);
+    LOCAL_SENDS.fetch_add(1, Ordering::Relaxed);
+    Some(InteropErrorCode::Ok as i32)
+}
+
+/// Sends through C++ refs delivered in process
+pub fn local_sends() -> u64 {
+    LOCAL_SENDS.load(Ordering::Relaxed)
+}
+
+/// Forget the cached answer for `name`
+pub fn invalidate(name: &str) {
+    LOCAL.write().unwrap().remove(name);
+}
+
+/// Forget every cached answer
+pub fn invalidate_all() {
+    LOCAL.write().unwrap().clear();
+}
@@ -0,0 +1,95 @@
+//! Cache of C++ actor lookups
+//!
+//! `get_actor_ref()` falls back to `cpp_actor_exists()` for every name the
+//! Rust Manager does not know. At startup many actors look up the same few
+//! C++ actors, often before they exist, so answers are cached here:
+//!
+//! - "exists" is kept until invalidated.
+//! - "not found" is kept for `negative_ttl()` (default
+//!   `DEFAULT_NEGATIVE_TTL_MS`), so an actor registered later is found
+//!   soon without every caller probing in the meantime.
+//! - Concurrent lookups of one name are coalesced: the probe runs under the
+//!   cache lock, and callers that waited on it read its answer.
+//!
+//! Entries, and the answers kept by locality, are dropped on Rust
+//! lifecycle events for the name, when a Rust actor claims it, when
+//! `cpp_actor_init()` or `rust_manager_end()` runs, and on
+//! `interop_invalidate_lookup(name)` (NULL for all) from C++, which should
+//! call it when it removes an actor, or `interop_cpp_actor_removed(name)`,
+//! which also reports refs Rust actors still hold to it (see orphans).
+
+use std::collections::HashMap;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::names::c_str;
+
+/// How long a "not found" answer is reused
+pub const DEFAULT_NEGATIVE_TTL_MS: u64 = 200;
+
+static NEGATIVE_TTL_MS: AtomicU64 = AtomicU64::new(DEFAULT_NEGATIVE_TTL_MS);
+
+enum Entry {
+    Exists,
+    Missing(Instant),
+}
+
+lazy_static::lazy_static! {
+    static ref CACHE: Mutex<HashMap<String, Entry>> = Mutex::new(HashMap::new());
+}
+
+pub fn negative_ttl() -> Duration {
+    Duration::from_millis(NEGATIVE_TTL_MS.load(Ordering::Relaxed))
+}
+
+/// Set how long "not found" is cached; zero disables negative caching
+pub fn set_negative_ttl(ttl: Duration) {
+    NEGATIVE_TTL_MS.store(ttl.as_millis().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+}
+
+/// Whether C++ actor `name` exists, calling `probe` only on a cache miss
+pub fn cpp_exists(name: &str, probe: impl FnOnce() -> bool) -> bool {
+    let mut cache = CACHE.lock().unwrap();
+    match cache.get(name) {
+        Some(Entry::Exists) => return true,
+        Some(Entry::Missing(at)) if at.elapsed() < negative_ttl() => return false,
+        _ => {}
+    }
+    let exists = probe();
+    let entry = if exists { Entry::Exists } else { Entry::Missing(Instant::now()) };
+    cache.insert(name.to_string(), entry);
+    exists
+}
+
+/// Forget the cached answer for `name`, its actor ids (see actor_ids) and
+/// whether it is a Rust actor (see locality)
+pub fn invalidate(name: &str) {
+    CACHE.lock().unwrap().remove(name);
+    crate::actor_ids::forget(name);
+    crate::locality::invalidate(name);
+}
+
+/// Forget every cached answer, actor id and locality
+pub fn invalidate_all() {
+    CACHE.lock().unwrap().clear();
+    crate::actor_ids::forget_all();
+    crate::locality::invalidate_all();
+}
+
+/// Drop the cached lookup of `name`, or of every name if `name` is null
+#[export_name = c_symbol!("interop_invalidate_lookup")]
+pub extern "C" fn interop_invalidate_lookup(name: *const c_char) {
+    if name.is_null() {
+        invalidate_all();
+    } else if let Some(n) = c_str(name) {
+        invalidate(n);
+    }
+}
+
+/// Set the "not found" cache time in ms; 0 disables it
+#[export_name = c_symbol!("interop_set_lookup_negative_ttl_ms")]
+pub extern "C" fn interop_set_lookup_negative_ttl_ms(ms: u32) {
+    set_negative_ttl(Duration::from_millis(ms as u64));
+}
@@ -0,0 +1,134 @@
+//! MQTT bridge for the pub/sub topics (feature `mqtt`)
+//!
+//! Mirrors interop market data to an MQTT broker so dashboards and other
+//! external consumers see the same stream as the C++/Rust actors.
+//!
+//! Topic mapping, with `prefix` from the config:
+//! - Outbound: MarketUpdate / MarketDepth for `SYM` -> `{prefix}/SYM`
+//! - Inbound:  anything on `{prefix}/in/#` is delivered to `inbound_target`
+//!
+//! Payloads use the codec set for the `mqtt_bridge` link (`Portable`
+//! frames by default) in both directions.
+
+use std::thread;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, ActorRef};
+use actors::messages::Start;
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS};
+
+use crate::codec::{link_codec, Codec};
+use crate::interop_messages::{MarketDepth, MarketUpdate, Subscribe};
+use crate::qos::QOS_BEST_EFFORT;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::size_limit;
+
+/// Name the bridge is registered under
+pub const MQTT_BRIDGE_NAME: &str = "mqtt_bridge";
+
+#[derive(Clone, Debug)]
+pub struct MqttBridgeConfig {
+    pub host: String,
+    pub port: u16,
+    /// Root of all mirrored MQTT topics, e.g. "interop"
+    pub prefix: String,
+    /// Interop publisher to subscribe to on Start
+    pub publisher: String,
+    /// Interop topics to mirror
+    pub topics: Vec<String>,
+    /// Rust actor receiving inbound frames, None to ignore inbound traffic
+    pub inbound_target: Option<String>,
+}
+
+pub struct MqttBridge {
+    config: MqttBridgeConfig,
+    client: Client,
+    codec: &'static dyn Codec,
+    publisher: Option<ActorRef>,
+}
+
+impl MqttBridge {
+    /// Connect to the broker and start the inbound network thread
+    pub fn new(config: MqttBridgeConfig) -> Self {
+        let mut options = MqttOptions::new(MQTT_BRIDGE_NAME, config.host.clone(), config.port);
+        options.set_keep_alive(Duration::from_secs(5));
+        let (client, mut connection) = Client::new(options, 64);
+        let codec = link_codec(MQTT_BRIDGE_NAME);
+
+        if config.inbound_target.is_some() {
+            let inbound = format!("{}/in/#", config.prefix);
+            if let Err(e) = client.subscribe(inbound, QoS::AtMostOnce) {
+                eprintln!("[MQTT Bridge] subscribe failed: {}", e);
+            }
+        }
+
+        // rumqttc needs its event loop polled for outbound traffic too
+        let inbound_target = config.inbound_target.clone();
+        thread::spawn(move || {
+            for event in connection.iter() {
+                match event {
+                    Ok(Event::Incoming(Packet::Publish(p))) => {
+                        if let Some(ref target) = inbound_target {
+                            match (codec.decode(&p.payload), get_actor_ref(target, MQTT_BRIDGE_NAME)) {
+                                (Ok(msg), Some(actor)) => actor.send(msg, None),
+                                (Err(e), _) => eprintln!("[MQTT Bridge] dropping message on {}: {}", p.topic, e),
+                                (_, None) => eprintln!("[MQTT Bridge] inbound target {} not found", target),
+                            }
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        eprintln!("[MQTT Bridge] connection error: {}", e);
+                        thread::sleep(Duration::from_secs(1));
+                    }
+                }
+            }
+        });
+
+        MqttBridge { config, client, codec, publisher: None }
+    }
+
+    fn mqtt_topic(&self, symbol: &str) -> String {
+        format!("{}/{}", self.config.prefix, symbol)
+    }
+
+    fn publish(&self, symbol: &str, msg: &dyn actors::Message) {
+        let payloads = match size_limit::encode_frames(MQTT_BRIDGE_NAME, self.codec, msg) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("[MQTT Bridge] {} encode failed: {}", self.codec.name(), e);
+                return;
+            }
+        };
+        for payload in payloads {
+            if let Err(e) = self.client.try_publish(self.mqtt_topic(symbol), QoS::AtMostOnce, false, payload) {
+                eprintln!("[MQTT Bridge] publish failed: {}", e);
+            }
+        }
+    }
+
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        self.publisher = get_actor_ref(&self.config.publisher, MQTT_BRIDGE_NAME);
+        if let Some(ref publisher) = self.publisher {
+            for topic in &self.config.topics {
+                let sub = Subscribe { topic: topic.as_str().into(), qos: QOS_BEST_EFFORT };
+                publisher.send(Box::new(sub), None);
+            }
+        }
+    }
+
+    fn on_market_update(&mut self, msg: &MarketUpdate, _ctx: &mut ActorContext) {
+        self.publish(msg.symbol.as_str(), msg);
+    }
+
+    fn on_market_depth(&mut self, msg: &MarketDepth, _ctx: &mut ActorContext) {
+        self.publish(msg.symbol.as_str(), msg);
+    }
+}
+
+// Register message handlers
+handle_messages!(MqttBridge,
+    Start => on_start,
+    MarketUpdate => on_market_update,
+    MarketDepth => on_market_depth
+);
@@ -0,0 +1,222 @@
+//! One actor name, one actor, across both runtimes
+//!
+//! `get_ref("x")` checks the local registry first, so a Rust actor named
+//! like a C++ actor silently wins on the Rust side and loses on the C++
+//! side. Every `register_*` function in `rust_manager_ffi` therefore claims
+//! its name here first: the claim fails with an error naming the owner if a
+//! C++ actor (`cpp_actor_exists`) or another Rust actor already has it, and
+//! the register function returns null.
+//!
+//! C++ can check the other direction before `manage()` with
+//! `rust_actor_exists()`, which answers from the claimed names and so works
+//! before `rust_actor_init()`.
+//!
+//! Intentional shadowing (e.g. a Rust replacement for a C++ actor during a
+//! migration) is allowed per name with `interop_allow_shadowing()` or
+//! globally with `ShadowPolicy::Allow`. Two Rust actors with one name are
+//! always rejected, since the Manager cannot hold both.
+//!
+//! A name must also survive the trip to C: empty names and names with a
+//! NUL byte are refused at registration (`REGISTER_INVALID_NAME`) and at
+//! send time (`INVALID_NAME`, recorded as a `name` dead letter) instead of
+//! panicking in `CString::new`. `ActorName` is a name already checked and
+//! converted; C++ hosts can check theirs before `manage()` with
+//! `interop_validate_actor_name()`.
+
+use std::collections::BTreeSet;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use crate::cpp_runtime;
+use crate::interop_errors::InteropErrorCode;
+
+/// Why a string cannot be an actor name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NameError {
+    Empty,
+    /// A NUL byte at this offset; C would see the name cut short
+    InteriorNul(usize),
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NameError::Empty => write!(f, "actor name is empty"),
+            NameError::InteriorNul(at) => write!(f, "actor name has a NUL byte at offset {}", at),
+        }
+    }
+}
+
+impl std::error::Error for NameError {}
+
+/// A valid actor name with its C string, ready for the FFI
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActorName(CString);
+
+impl ActorName {
+    pub fn new(name: &str) -> Result<Self, NameError> {
+        validate(name)?;
+        CString::new(name).map(ActorName).map_err(|e| NameError::InteriorNul(e.nul_position()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Built from a &str
+        self.0.to_str().unwrap_or("")
+    }
+
+    pub fn as_c_str(&self) -> &CStr {
+        &self.0
+    }
+
+    pub fn as_ptr(&self) -> *const c_char {
+        self.0.as_ptr()
+    }
+}
+
+impl fmt::Display for ActorName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Ok if `name` can name an actor on both sides
+pub fn validate(name: &str) -> Result<(), NameError> {
+    if name.is_empty() {
+        return Err(NameError::Empty);
+    }
+    match name.bytes().position(|b| b == 0) {
+        Some(at) => Err(NameError::InteriorNul(at)),
+        None => Ok(()),
+    }
+}
+
+/// Target and sender of a send to C++; an empty sender is none
+pub fn send_names(target: &str, sender: &str) -> Result<(ActorName, Option<ActorName>), NameError> {
+    let target = ActorName::new(target)?;
+    let sender = if sender.is_empty() { None } else { Some(ActorName::new(sender)?) };
+    Ok((target, sender))
+}
+
+/// Mirrors INTEROP_SHADOW_* in interop/names.h
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub enum ShadowPolicy {
+    /// A Rust actor may not take the name of a C++ actor (default)
+    Reject = 0,
+    /// Rust actors may shadow C++ actors; a warning is printed
+    Allow = 1,
+}
+
+struct Names {
+    policy: ShadowPolicy,
+    /// Names that may shadow a C++ actor under `Reject`
+    shadowable: BTreeSet<String>,
+    /// Names claimed by Rust actors
+    rust: BTreeSet<String>,
+}
+
+static NAMES: Mutex<Names> = Mutex::new(Names {
+    policy: ShadowPolicy::Reject,
+    shadowable: BTreeSet::new(),
+    rust: BTreeSet::new(),
+});
+
+/// True if a C++ actor named `name` exists in any C++ runtime
+pub(crate) fn cpp_has(name: &str) -> bool {
+    cpp_runtime::exists(name)
+}
+
+pub fn set_shadow_policy(policy: ShadowPolicy) {
+    NAMES.lock().unwrap().policy = policy;
+}
+
+/// Let a Rust actor named `name` shadow a C++ actor under `Reject`
+pub fn allow_shadowing(name: &str) {
+    NAMES.lock().unwrap().shadowable.insert(name.to_string());
+}
+
+/// Claim `name` for a Rust actor about to be registered.
+/// Prints the conflict and returns false if the name is taken or invalid.
+pub fn claim(name: &str) -> bool {
+    if let Err(e) = validate(name) {
+        eprintln!("[Rust Manager] cannot register {:?}: {}", name, e);
+        return false;
+    }
+    crate::lookup_cache::invalidate(name);
+    let mut names = NAMES.lock().unwrap();
+    if names.rust.contains(name) {
+        eprintln!("[Rust Manager] cannot register {}: a Rust actor with this name is already registered", name);
+        return false;
+    }
+    if cpp_has(name) {
+        if names.policy == ShadowPolicy::Reject && !names.shadowable.contains(name) {
+            eprintln!(
+                "[Rust Manager] cannot register {}: a C++ actor with this name exists \
+                 (use interop_allow_shadowing(\"{}\") if this is intended)",
+                name, name
+            );
+            return false;
+        }
+        eprintln!("[Rust Manager] warning: Rust actor {} shadows the C++ actor of the same name", name);
+    }
+    names.rust.insert(name.to_string());
+    true
+}
+
+/// Give back a claim for an actor that was not registered after all
+pub fn release(name: &str) {
+    NAMES.lock().unwrap().rust.remove(name);
+    crate::locality::invalidate(name);
+}
+
+/// True if a Rust actor has claimed `name`
+pub fn is_rust(name: &str) -> bool {
+    NAMES.lock().unwrap().rust.contains(name)
+}
+
+/// Set the shadowing policy (INTEROP_SHADOW_REJECT / INTEROP_SHADOW_ALLOW).
+/// Returns 0, or InvalidArgument for an unknown policy.
+#[export_name = c_symbol!("interop_set_shadow_policy")]
+pub extern "C" fn interop_set_shadow_policy(policy: c_int) -> c_int {
+    match policy {
+        0 => set_shadow_policy(ShadowPolicy::Reject),
+        1 => set_shadow_policy(ShadowPolicy::Allow),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    }
+    InteropErrorCode::Ok as c_int
+}
+
+/// A string argument from C: None if null or not UTF-8. The caller
+/// picks the lifetime; C keeps `s` alive for the call.
+pub(crate) fn c_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+/// Allow a Rust actor named `name` to shadow a C++ actor.
+/// Returns 0, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_allow_shadowing")]
+pub extern "C" fn interop_allow_shadowing(name: *const c_char) -> c_int {
+    let rc = match c_str(name) {
+        Some(n) => {
+            allow_shadowing(n);
+            InteropErrorCode::Ok
+        }
+        None => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Check a name before registering it on either side. Returns 0, or
+/// INTEROP_E_INVALID_NAME for a null, empty or non-UTF-8 name.
+#[export_name = c_symbol!("interop_validate_actor_name")]
+pub extern "C" fn interop_validate_actor_name(name: *const c_char) -> c_int {
+    match c_str(name).map(validate) {
+        Some(Ok(())) => InteropErrorCode::Ok as c_int,
+        _ => InteropErrorCode::InvalidName as c_int,
+    }
+}
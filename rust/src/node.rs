@@ -0,0 +1,344 @@
+//! Node.js bindings (feature `node`)
+//!
+//! Built with the `node` feature, the cdylib is also a napi-rs addon, so a
+//! JavaScript dashboard can subscribe to the same topics as the C++ and
+//! Rust actors, send to any actor by name, and ask for a reply:
+//!
+//! ```js
+//! const interop = require('./actors_interop.node');
+//!
+//! const sub = interop.subscribe('market_publisher', 'AAPL', msg => {
+//!     // { type: 'MarketUpdate', msg_id: 1012, fields: { symbol: 'AAPL', ... } }
+//! });
+//! interop.send('risk_manager', 'RiskLimits', { account_id: 7, per_symbol_limit: { AAPL: 1e6 } });
+//! const pong = await interop.ask('pong_actor', 'Ping', { count: 1 }, 100);
+//! sub.unsubscribe();
+//! ```
+//!
+//! Messages reach JavaScript as the generated `to_json()` object: strings
+//! are cut at their NUL and binary fields are hex. Fields sent from
+//! JavaScript are converted by `JsField`; missing fields are zero/empty,
+//! and a wrong type or a string too long for its `char[N]` is an error.
+//!
+//! The addon joins the actor system of its process: the host must have
+//! run the usual initialization sequence (Rust Manager created and
+//! started, C++ lookup registered). Lookups, sends and replies go through
+//! `get_actor_ref`, so targets may be Rust or C++ actors.
+//!
+//! A subscription is a mailbox named `__node-<id>`, routed like the reply
+//! mailboxes of `ask`. Its callback runs on the Node.js main thread; while
+//! a subscription is open it keeps the event loop alive.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use actors::{ActorRef, CppActorRef, Message};
+use napi::bindgen_prelude::AsyncTask;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, Error, JsFunction, Result, Task};
+use napi_derive::napi;
+use serde_json::Value;
+
+use crate::ask;
+use crate::decimal::Decimal;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_from_js, message_to_debug_json, InteropString, Subscribe, Unsubscribe};
+use crate::interop_string::TruncationPolicy;
+use crate::qos::QOS_BEST_EFFORT;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::timestamp::Timestamp;
+use crate::uuid::Uuid;
+
+/// Prefix of subscription mailbox names; not for actor names
+pub const NODE_PREFIX: &str = "__node-";
+
+/// Sender name of `send` and `ask` from JavaScript
+pub const NODE_SENDER: &str = "node";
+
+/// Conversion of a JS value (as JSON) to a message field. `Null` (field
+/// missing) gives the zero/empty value.
+pub trait JsField: Sized {
+    fn from_js(v: &Value) -> Option<Self>;
+}
+
+macro_rules! js_int {
+    ($($t:ty),*) => {$(
+        impl JsField for $t {
+            fn from_js(v: &Value) -> Option<Self> {
+                match v {
+                    Value::Null => Some(0),
+                    Value::Number(n) => match n.as_i64() {
+                        Some(i) => <$t>::try_from(i).ok(),
+                        None => <$t>::try_from(n.as_u64()?).ok(),
+                    },
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+js_int!(u8, i32, i64, u32, u64);
+
+macro_rules! js_float {
+    ($($t:ty),*) => {$(
+        impl JsField for $t {
+            fn from_js(v: &Value) -> Option<Self> {
+                match v {
+                    Value::Null => Some(0.0),
+                    Value::Number(n) => Some(n.as_f64()? as $t),
+                    _ => None,
+                }
+            }
+        }
+    )*};
+}
+
+js_float!(f32, f64);
+
+impl JsField for bool {
+    fn from_js(v: &Value) -> Option<Self> {
+        match v {
+            Value::Null => Some(false),
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl JsField for String {
+    fn from_js(v: &Value) -> Option<Self> {
+        match v {
+            Value::Null => Some(String::new()),
+            Value::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<const N: usize> JsField for InteropString<N> {
+    fn from_js(v: &Value) -> Option<Self> {
+        match v {
+            Value::Null => Some(InteropString::default()),
+            Value::String(s) => InteropString::with_policy(s, TruncationPolicy::Reject).ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Hex, as written by `to_json()`, or an array of byte values
+impl JsField for Vec<u8> {
+    fn from_js(v: &Value) -> Option<Self> {
+        match v {
+            Value::Null => Some(Vec::new()),
+            Value::String(hex) if hex.len() % 2 == 0 => (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                .collect(),
+            Value::Array(items) => items.iter().map(u8::from_js).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// A decimal string ("99.875"), as written by `to_json()`, or an integer
+impl JsField for Decimal {
+    fn from_js(v: &Value) -> Option<Self> {
+        match v {
+            Value::Null => Some(Decimal::default()),
+            Value::String(s) => s.parse().ok(),
+            Value::Number(n) => Some(Decimal::from_int(n.as_i64()?)),
+            _ => None,
+        }
+    }
+}
+
+/// Hyphenated or plain hex, as written by `to_json()`
+impl JsField for Uuid {
+    fn from_js(v: &Value) -> Option<Self> {
+        match v {
+            Value::Null => Some(Uuid::NIL),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// RFC 3339 text, as written by `to_json()`, or milliseconds since the
+/// epoch as from `Date.now()`
+impl JsField for Timestamp {
+    fn from_js(v: &Value) -> Option<Self> {
+        match v {
+            Value::Null => Some(Timestamp::UNIX_EPOCH),
+            Value::String(s) => s.parse().ok(),
+            Value::Number(n) => Some(Timestamp::from_millis(n.as_i64()?)),
+            _ => None,
+        }
+    }
+}
+
+/// Up to N elements; the rest are zero
+impl<T: JsField, const N: usize> JsField for [T; N] {
+    fn from_js(v: &Value) -> Option<Self> {
+        let items: &[Value] = match v {
+            Value::Null => &[],
+            Value::Array(items) if items.len() <= N => items,
+            _ => return None,
+        };
+        let values = (0..N)
+            .map(|i| T::from_js(items.get(i).unwrap_or(&Value::Null)))
+            .collect::<Option<Vec<T>>>()?;
+        values.try_into().ok()
+    }
+}
+
+impl<V: JsField> JsField for HashMap<String, V> {
+    fn from_js(v: &Value) -> Option<Self> {
+        match v {
+            Value::Null => Some(HashMap::new()),
+            Value::Object(entries) => entries.iter().map(|(k, v)| Some((k.clone(), V::from_js(v)?))).collect(),
+            _ => None,
+        }
+    }
+}
+
+fn to_js(msg: &dyn Message) -> Result<Value> {
+    message_to_debug_json(msg)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .ok_or_else(|| Error::from_reason(format!("message {} has no JS form", msg.message_id())))
+}
+
+fn from_js(msg_type: &str, fields: &Value) -> Result<Box<dyn Message>> {
+    if !fields.is_object() && !fields.is_null() {
+        return Err(Error::from_reason(format!("fields of {} must be an object", msg_type)));
+    }
+    message_from_js(msg_type, fields)
+        .ok_or_else(|| Error::from_reason(format!("not a valid {} message", msg_type)))
+}
+
+fn lookup(name: &str, sender: &str) -> Result<ActorRef> {
+    get_actor_ref(name, sender).ok_or_else(|| Error::from_reason(format!("actor {} not found", name)))
+}
+
+type Callback = ThreadsafeFunction<Value, ErrorStrategy::Fatal>;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+lazy_static::lazy_static! {
+    // Open subscription mailboxes by name
+    static ref SUBSCRIPTIONS: Mutex<HashMap<String, Callback>> = Mutex::new(HashMap::new());
+}
+
+/// send_fn of subscription refs: hand the message to the JS callback
+fn subscription_send_fn(target: &str, _sender: &str, msg: &dyn Message) -> i32 {
+    let callback = match SUBSCRIPTIONS.lock().unwrap().get(target) {
+        Some(cb) => cb.clone(),
+        None => return InteropErrorCode::NotFound as i32,  // unsubscribed
+    };
+    match to_js(msg) {
+        Ok(value) => {
+            callback.call(value, ThreadsafeFunctionCallMode::NonBlocking);
+            InteropErrorCode::Ok as i32
+        }
+        Err(_) => InteropErrorCode::UnknownMessage as i32,
+    }
+}
+
+/// ActorRef for an open subscription mailbox, None if `name` is not one.
+/// The bridge uses this to route messages from C++ publishers.
+pub fn subscription_ref(name: &str) -> Option<ActorRef> {
+    if !name.starts_with(NODE_PREFIX) || !SUBSCRIPTIONS.lock().unwrap().contains_key(name) {
+        return None;
+    }
+    Some(ActorRef::Cpp(CppActorRef::new(name, "", subscription_send_fn)))
+}
+
+// C++ refs carry the sender name; local actors reply to the sender ref
+fn send_from(target: &ActorRef, msg: Box<dyn Message>, sender: &str) {
+    match target {
+        ActorRef::Cpp(_) => target.send(msg, None),
+        local => local.send(msg, subscription_ref(sender)),
+    }
+}
+
+/// Subscription to one topic of a publisher
+#[napi]
+pub struct Subscription {
+    name: String,
+    publisher: String,
+    topic: String,
+}
+
+#[napi]
+impl Subscription {
+    /// Send Unsubscribe and stop the callback; safe to call twice
+    #[napi]
+    pub fn unsubscribe(&self) {
+        if !SUBSCRIPTIONS.lock().unwrap().contains_key(&self.name) {
+            return;
+        }
+        if let Ok(publisher) = lookup(&self.publisher, &self.name) {
+            let msg = Unsubscribe { topic: self.topic.as_str().into() };
+            send_from(&publisher, Box::new(msg), &self.name);
+        }
+        SUBSCRIPTIONS.lock().unwrap().remove(&self.name);
+    }
+}
+
+/// Subscribe to `topic` of actor `publisher`; `callback` gets every
+/// message the publisher sends to the subscription
+#[napi]
+pub fn subscribe(publisher: String, topic: String, callback: JsFunction) -> Result<Subscription> {
+    let topic_field = InteropString::with_policy(&topic, TruncationPolicy::Reject)
+        .map_err(|e| Error::from_reason(format!("topic {}: {}", topic, e)))?;
+    let name = format!("{}{}", NODE_PREFIX, NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let publisher_ref = lookup(&publisher, &name)?;
+
+    let callback: Callback = callback.create_threadsafe_function(0, |ctx| Ok(vec![ctx.value]))?;
+    SUBSCRIPTIONS.lock().unwrap().insert(name.clone(), callback);
+
+    let msg = Subscribe { topic: topic_field, qos: QOS_BEST_EFFORT };
+    send_from(&publisher_ref, Box::new(msg), &name);
+    Ok(Subscription { name, publisher, topic })
+}
+
+/// Send a message of type `msg_type` (e.g. "Ping") to actor `target`
+#[napi]
+pub fn send(target: String, msg_type: String, fields: Value) -> Result<()> {
+    let msg = from_js(&msg_type, &fields)?;
+    lookup(&target, NODE_SENDER)?.send(msg, None);
+    Ok(())
+}
+
+/// Blocking part of `ask`, run on the libuv thread pool
+pub struct AskTask {
+    target: ActorRef,
+    msg: Option<Box<dyn Message>>,
+    timeout: Duration,
+}
+
+impl Task for AskTask {
+    type Output = Box<dyn Message>;
+    type JsValue = Value;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let msg = self.msg.take().ok_or_else(|| Error::from_reason("ask already sent"))?;
+        ask::ask_message(&self.target, msg, self.timeout).map_err(|e| Error::from_reason(e.to_string()))
+    }
+
+    fn resolve(&mut self, _env: Env, reply: Self::Output) -> Result<Self::JsValue> {
+        to_js(reply.as_ref())
+    }
+}
+
+/// Send a message to `target` and resolve with its reply; rejects after
+/// `timeout_ms` without one
+#[napi]
+pub fn ask(target: String, msg_type: String, fields: Value, timeout_ms: u32) -> Result<AsyncTask<AskTask>> {
+    let msg = from_js(&msg_type, &fields)?;
+    let target = lookup(&target, NODE_SENDER)?;
+    let timeout = Duration::from_millis(timeout_ms as u64);
+    Ok(AsyncTask::new(AskTask { target, msg: Some(msg), timeout }))
+}
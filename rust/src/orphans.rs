@@ -0,0 +1,342 @@
+//! Refs held across the bridge to actors that are gone
+//!
+//! An actor that looks up a peer once and keeps the ref, like
+//! RustSubscriber's `publisher` field, never notices the peer was removed:
+//! its sends fail with NotFound, or reach a new actor of the same name. With
+//! ref tracking on, the bridge records who holds refs to which actor of the
+//! other language, and reports the holders when the actor goes:
+//!
+//! ```c
+//! interop_set_ref_tracking(1);
+//! ...
+//! interop_cpp_actor_removed("cpp_price_feed");
+//! // [Orphans] C++ actor 'cpp_price_feed' removed; still referenced by rust_price_monitor (1 lookup)
+//! ```
+//!
+//! - Rust holders of C++ refs: each ref handed out by `get_actor_ref()` is
+//!   counted under the name the caller looked up as. Rust does not see the
+//!   ref dropped, so the count is of lookups, released when the holder
+//!   stops.
+//! - C++ holders of Rust refs: each `RustActorIF` counts itself under its
+//!   sender name while it lives (`interop_ref_acquired()` /
+//!   `interop_ref_released()`).
+//! - A C++ actor is gone once C++ reports it with
+//!   `interop_cpp_actor_removed()`; a Rust actor once it is Stopped (see
+//!   lifecycle). Either is back when found or started again.
+//! - A send through a ref to a gone actor is counted and logged once per
+//!   holder and target.
+//!
+//! Off by default; holders are not reported during shutdown. `orphans()`
+//! and `interop_orphaned_refs()` list the refs to gone actors.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::codec::JsonValue;
+use crate::health::{self, ManagerState};
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+
+/// The language an actor runs in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Rust,
+    Cpp,
+}
+
+impl Side {
+    fn label(self) -> &'static str {
+        match self {
+            Side::Rust => "Rust",
+            Side::Cpp => "C++",
+        }
+    }
+
+    fn gone_as(self) -> &'static str {
+        match self {
+            Side::Rust => "stopped",
+            Side::Cpp => "removed",
+        }
+    }
+}
+
+/// Refs one holder keeps to an actor that is gone
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Orphan {
+    pub target: String,
+    pub target_side: Side,
+    /// Empty if the ref was taken without a name
+    pub holder: String,
+    /// Lookups for a Rust holder, live refs for a C++ one
+    pub refs: u32,
+}
+
+#[derive(Default)]
+struct Refs {
+    side: Option<Side>,
+    holders: HashMap<String, u32>,
+    gone: bool,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STALE_SENDS: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    // By target name
+    static ref REFS: Mutex<HashMap<String, Refs>> = Mutex::new(HashMap::new());
+    // (holder, target) whose stale sends were logged
+    static ref LOGGED: Mutex<HashSet<(String, String)>> = Mutex::new(HashSet::new());
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if !enabled {
+        REFS.lock().unwrap().clear();
+        LOGGED.lock().unwrap().clear();
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// `holder` took a ref to `target`, an actor on `side`. Called by the
+/// bridge for each C++ ref handed out and for each `RustActorIF`.
+pub fn acquired(holder: &str, target: &str, side: Side) {
+    if !is_enabled() {
+        return;
+    }
+    if side == Side::Cpp {
+        // Found by a lookup, so it exists again
+        back(target);
+    }
+    let mut refs = REFS.lock().unwrap();
+    let entry = refs.entry(target.to_string()).or_default();
+    entry.side = Some(side);
+    *entry.holders.entry(holder.to_string()).or_insert(0) += 1;
+}
+
+/// `holder` dropped a ref to `target`
+pub fn released(holder: &str, target: &str) {
+    if !is_enabled() {
+        return;
+    }
+    let mut refs = REFS.lock().unwrap();
+    if let Some(entry) = refs.get_mut(target) {
+        if let Some(n) = entry.holders.get_mut(holder) {
+            *n = n.saturating_sub(1);
+            if *n == 0 {
+                entry.holders.remove(holder);
+            }
+        }
+        if entry.holders.is_empty() {
+            refs.remove(target);
+        }
+    }
+}
+
+/// `name`, an actor on `side`, is gone: report who still holds refs to it
+pub fn gone(name: &str, side: Side) {
+    if !is_enabled() {
+        return;
+    }
+    let holders: Vec<(String, u32)> = {
+        let mut refs = REFS.lock().unwrap();
+        match refs.get_mut(name) {
+            Some(entry) => {
+                entry.side = Some(side);
+                entry.gone = true;
+                let mut holders: Vec<(String, u32)> = entry.holders.iter().map(|(h, n)| (h.clone(), *n)).collect();
+                holders.sort();
+                holders
+            }
+            None => return,
+        }
+    };
+    if holders.is_empty() || health::manager_state() != ManagerState::Running {
+        return;
+    }
+    let unit = if side == Side::Cpp { "lookup" } else { "ref" };
+    let list: Vec<String> = holders
+        .iter()
+        .map(|(h, n)| format!("{} ({} {}{})", holder_label(h), n, unit, if *n == 1 { "" } else { "s" }))
+        .collect();
+    eprintln!(
+        "[Orphans] {} actor '{}' {}; still referenced by {}",
+        side.label(),
+        name,
+        side.gone_as(),
+        list.join(", ")
+    );
+}
+
+/// `name` runs again; refs to it are live
+pub fn back(name: &str) {
+    if !is_enabled() {
+        return;
+    }
+    if let Some(entry) = REFS.lock().unwrap().get_mut(name) {
+        entry.gone = false;
+    }
+    LOGGED.lock().unwrap().retain(|(_, target)| target != name);
+}
+
+/// `name` stopped: its own refs are released and, as a target, it is gone
+pub(crate) fn stopped(name: &str) {
+    if !is_enabled() {
+        return;
+    }
+    {
+        let mut refs = REFS.lock().unwrap();
+        for entry in refs.values_mut() {
+            entry.holders.remove(name);
+        }
+        refs.retain(|_, e| !e.holders.is_empty());
+    }
+    gone(name, Side::Rust);
+}
+
+/// Note a send from `sender` to `target` through the bridge; logged once
+/// if `target` is gone and `sender` holds a ref to it
+pub(crate) fn check_send(sender: &str, target: &str) {
+    if !is_enabled() {
+        return;
+    }
+    let side = match REFS.lock().unwrap().get(target) {
+        Some(e) if e.gone && e.holders.contains_key(sender) => e.side.unwrap_or(Side::Cpp),
+        _ => return,
+    };
+    STALE_SENDS.fetch_add(1, Ordering::Relaxed);
+    if LOGGED.lock().unwrap().insert((sender.to_string(), target.to_string())) {
+        eprintln!(
+            "[Orphans] {} sent to {} actor '{}' through a ref kept after it was {}; look it up again",
+            holder_label(sender),
+            side.label(),
+            target,
+            side.gone_as()
+        );
+    }
+}
+
+/// Sends through refs to gone actors since start
+pub fn stale_sends() -> u64 {
+    STALE_SENDS.load(Ordering::Relaxed)
+}
+
+/// Refs still held to actors that are gone, by target then holder
+pub fn orphans() -> Vec<Orphan> {
+    let refs = REFS.lock().unwrap();
+    let mut out: Vec<Orphan> = refs
+        .iter()
+        .filter(|(_, e)| e.gone)
+        .flat_map(|(target, e)| {
+            e.holders.iter().map(move |(holder, n)| Orphan {
+                target: target.clone(),
+                target_side: e.side.unwrap_or(Side::Cpp),
+                holder: holder.clone(),
+                refs: *n,
+            })
+        })
+        .collect();
+    out.sort_by(|a, b| (&a.target, &a.holder).cmp(&(&b.target, &b.holder)));
+    out
+}
+
+/// Holders of refs to `target`, gone or not, with their counts
+pub fn holders_of(target: &str) -> Vec<(String, u32)> {
+    let refs = REFS.lock().unwrap();
+    let mut holders: Vec<(String, u32)> =
+        refs.get(target).map(|e| e.holders.iter().map(|(h, n)| (h.clone(), *n)).collect()).unwrap_or_default();
+    holders.sort();
+    holders
+}
+
+/// orphans() as a JSON array
+pub fn orphans_json() -> String {
+    let mut out = String::from("[");
+    for (i, o) in orphans().iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("{\"target\":");
+        o.target.write_json(&mut out);
+        let side = if o.target_side == Side::Cpp { "cpp" } else { "rust" };
+        let _ = write!(out, ",\"side\":\"{}\",\"holder\":", side);
+        o.holder.write_json(&mut out);
+        let _ = write!(out, ",\"refs\":{}}}", o.refs);
+    }
+    out.push(']');
+    out
+}
+
+fn holder_label(holder: &str) -> &str {
+    if holder.is_empty() { "(unnamed)" } else { holder }
+}
+
+/// Turn ref tracking on (non-zero) or off; off forgets every ref. Returns 0.
+#[export_name = c_symbol!("interop_set_ref_tracking")]
+pub extern "C" fn interop_set_ref_tracking(enabled: c_int) -> c_int {
+    set_enabled(enabled != 0);
+    0
+}
+
+/// C++ `holder` (NULL: unnamed) took a ref to Rust actor `target`.
+/// Returns 0, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_ref_acquired")]
+pub extern "C" fn interop_ref_acquired(holder: *const c_char, target: *const c_char) -> c_int {
+    let holder = if holder.is_null() { Some("") } else { c_str(holder) };
+    match (holder, c_str(target)) {
+        (Some(h), Some(t)) => {
+            acquired(h, t, Side::Rust);
+            0
+        }
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// C++ `holder` (NULL: unnamed) dropped a ref to Rust actor `target`.
+/// Returns 0, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_ref_released")]
+pub extern "C" fn interop_ref_released(holder: *const c_char, target: *const c_char) -> c_int {
+    let holder = if holder.is_null() { Some("") } else { c_str(holder) };
+    match (holder, c_str(target)) {
+        (Some(h), Some(t)) => {
+            released(h, t);
+            0
+        }
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// C++ actor `name` was destroyed: drop Rust's cached lookup of it (see
+/// lookup_cache) and report the Rust actors still holding refs to it.
+/// Returns 0, or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_cpp_actor_removed")]
+pub extern "C" fn interop_cpp_actor_removed(name: *const c_char) -> c_int {
+    match c_str(name) {
+        Some(name) => {
+            crate::lookup_cache::invalidate(name);
+            gone(name, Side::Cpp);
+            0
+        }
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Write orphans_json() into `out_json` (NUL-terminated, truncated to fit
+/// `len`). Returns the full length like snprintf.
+#[export_name = c_symbol!("interop_orphaned_refs")]
+pub extern "C" fn interop_orphaned_refs(out_json: *mut c_char, len: usize) -> c_int {
+    let json = orphans_json();
+    if !out_json.is_null() && len > 0 {
+        let n = json.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(json.as_ptr(), out_json as *mut u8, n);
+            *out_json.add(n) = 0;
+        }
+    }
+    json.len().min(c_int::MAX as usize) as c_int
+}
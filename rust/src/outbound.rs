@@ -0,0 +1,257 @@
+//! Outbound queues for Rust -> C++ sends
+//!
+//! An `ActorRef::Cpp` send normally calls into C++ on the sending actor's
+//! thread, so a slow C++ mailbox (or a retry backoff) stalls the Rust
+//! handler. With a queue set for the C++ runtime the target lives in (a
+//! link: `cpp_runtime::LINKED` or an attached runtime's name), the send
+//! only copies the message into the queue and a flusher thread for that
+//! link makes the FFI call:
+//!
+//! ```ignore
+//! outbound::set_queue(cpp_runtime::LINKED, OutboundQueue::new(10_000, Overflow::DropOldest));
+//! ```
+//!
+//! - Policy, size and rate limits, taps and subscription tracking still
+//!   run on the sending thread; sequence numbers are stamped at flush time,
+//!   in queue order.
+//! - A queued send returns 0. Failures at flush time (after retries) go to
+//!   `retry::set_cpp_failure_callback()`, the health counters and the
+//!   dead letters, with the origin and route of the original send.
+//! - When the queue is full, `Overflow` decides: drop the new message,
+//!   drop the oldest queued one, block the sender until there is room, or
+//!   refuse with `OUTBOUND_FULL`.
+//!
+//! Removing a queue flushes what it holds before the thread exits.
+
+use std::collections::{HashMap, VecDeque};
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use actors::Message;
+
+use crate::cpp_runtime;
+use crate::dead_letters::{self, SendContext, Stage};
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::clone_message;
+use crate::names::c_str;
+use crate::rust_manager_ffi::cpp_send_retrying;
+
+/// Send return code when an `Overflow::Reject` queue is full
+pub const OUTBOUND_FULL: i32 = InteropErrorCode::OutboundFull as i32;
+
+/// What a send does when the queue is full; mirrors INTEROP_OUTBOUND_* in
+/// interop/outbound.h
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum Overflow {
+    /// Discard the new message; the send returns 0
+    DropNewest = 0,
+    /// Discard the oldest queued message to make room
+    DropOldest = 1,
+    /// Wait for room, like a rate limit's Delay
+    Block = 2,
+    /// Discard the new message; the send returns OUTBOUND_FULL
+    Reject = 3,
+}
+
+impl Overflow {
+    pub fn from_i32(v: i32) -> Option<Self> {
+        match v {
+            0 => Some(Overflow::DropNewest),
+            1 => Some(Overflow::DropOldest),
+            2 => Some(Overflow::Block),
+            3 => Some(Overflow::Reject),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutboundQueue {
+    pub capacity: usize,
+    pub overflow: Overflow,
+}
+
+impl OutboundQueue {
+    pub fn new(capacity: usize, overflow: Overflow) -> Self {
+        OutboundQueue { capacity: capacity.max(1), overflow }
+    }
+}
+
+struct Pending {
+    target: String,
+    sender: String,
+    msg: Box<dyn Message>,
+    // Origin and route of the send, for dead letters at flush time
+    context: SendContext,
+}
+
+struct State {
+    items: VecDeque<Pending>,
+    // Set when the queue is removed; the flusher exits once empty
+    closed: bool,
+}
+
+struct Link {
+    name: String,
+    config: OutboundQueue,
+    state: Mutex<State>,
+    // Signalled on push (flusher) and on pop (blocked senders)
+    changed: Condvar,
+    dropped: AtomicU64,
+}
+
+// Fast path: no route lookup while no link has a queue
+static ANY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref LINKS: Mutex<HashMap<String, Arc<Link>>> = Mutex::new(HashMap::new());
+}
+
+/// Queue sends to C++ runtime `link`, replacing its queue if it had one
+pub fn set_queue(link: &str, config: OutboundQueue) {
+    let queue = Arc::new(Link {
+        name: link.to_string(),
+        config,
+        state: Mutex::new(State { items: VecDeque::new(), closed: false }),
+        changed: Condvar::new(),
+        dropped: AtomicU64::new(0),
+    });
+    let flusher = queue.clone();
+    thread::spawn(move || flush(flusher));
+    let old = LINKS.lock().unwrap().insert(link.to_string(), queue);
+    ANY_ENABLED.store(true, Ordering::Release);
+    if let Some(old) = old {
+        close(&old);
+    }
+}
+
+/// Send to `link` directly again once its queue is flushed
+pub fn remove_queue(link: &str) {
+    let mut links = LINKS.lock().unwrap();
+    let old = links.remove(link);
+    ANY_ENABLED.store(!links.is_empty(), Ordering::Release);
+    drop(links);
+    if let Some(old) = old {
+        close(&old);
+    }
+}
+
+fn close(link: &Link) {
+    link.state.lock().unwrap().closed = true;
+    link.changed.notify_all();
+}
+
+/// Messages waiting in `link`'s queue
+pub fn pending(link: &str) -> usize {
+    LINKS.lock().unwrap().get(link).map_or(0, |l| l.state.lock().unwrap().items.len())
+}
+
+/// Messages waiting in every link's queue
+pub fn total_pending() -> usize {
+    LINKS.lock().unwrap().values().map(|l| l.state.lock().unwrap().items.len()).sum()
+}
+
+/// Messages `link`'s queue has discarded on overflow
+pub fn dropped(link: &str) -> u64 {
+    LINKS.lock().unwrap().get(link).map_or(0, |l| l.dropped.load(Ordering::Relaxed))
+}
+
+/// Queue `msg` if the runtime `target` lives in has a queue. None if it
+/// has none and the send must be made directly, else the send's result.
+pub fn offer(target: &str, sender: &str, msg: &dyn Message) -> Option<i32> {
+    if !ANY_ENABLED.load(Ordering::Acquire) {
+        return None;
+    }
+    let link = LINKS.lock().unwrap().get(&cpp_runtime::runtime_of(target)).cloned()?;
+    let msg = clone_message(msg)?;  // not an interop message: send directly
+    let mut state = link.state.lock().unwrap();
+    while state.items.len() >= link.config.capacity {
+        match link.config.overflow {
+            Overflow::DropNewest => {
+                link.dropped.fetch_add(1, Ordering::Relaxed);
+                return Some(0);
+            }
+            Overflow::DropOldest => {
+                state.items.pop_front();
+                link.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Overflow::Block if !state.closed => state = link.changed.wait(state).unwrap(),
+            Overflow::Block => break,
+            Overflow::Reject => {
+                dead_letters::record(Stage::MailboxFull, sender, target, msg.message_id(), OUTBOUND_FULL);
+                return Some(OUTBOUND_FULL);
+            }
+        }
+    }
+    state.items.push_back(Pending {
+        target: target.to_string(),
+        sender: sender.to_string(),
+        msg,
+        context: dead_letters::current(),
+    });
+    drop(state);
+    link.changed.notify_all();
+    Some(0)
+}
+
+fn flush(link: Arc<Link>) {
+    loop {
+        let next = {
+            let mut state = link.state.lock().unwrap();
+            while state.items.is_empty() && !state.closed {
+                state = link.changed.wait(state).unwrap();
+            }
+            match state.items.pop_front() {
+                Some(p) => p,
+                None => return,  // closed and flushed
+            }
+        };
+        link.changed.notify_all();
+        let rc = dead_letters::with_context(next.context, || {
+            cpp_send_retrying(&next.target, &next.sender, next.msg.as_ref())
+        });
+        if rc != 0 {
+            eprintln!(
+                "[Outbound] {}: {} -> {} message {} failed: {}",
+                link.name,
+                next.sender,
+                next.target,
+                next.msg.message_id(),
+                rc
+            );
+        }
+    }
+}
+
+/// Queue sends to C++ runtime `link` (`"linked"` for the C++ code linked
+/// into the process) with room for `capacity` messages, or remove its
+/// queue with capacity 0. Returns 0, or InvalidArgument on a bad name or
+/// policy.
+#[export_name = c_symbol!("interop_set_outbound_queue")]
+pub extern "C" fn interop_set_outbound_queue(link: *const c_char, capacity: u32, overflow: c_int) -> c_int {
+    let link = match c_str(link) {
+        Some(l) => l,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    if capacity == 0 {
+        remove_queue(link);
+        return InteropErrorCode::Ok as c_int;
+    }
+    let rc = match Overflow::from_i32(overflow) {
+        Some(overflow) => {
+            set_queue(link, OutboundQueue::new(capacity as usize, overflow));
+            InteropErrorCode::Ok
+        }
+        None => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Messages waiting in `link`'s queue, 0 without one
+#[export_name = c_symbol!("interop_outbound_pending")]
+pub extern "C" fn interop_outbound_pending(link: *const c_char) -> u64 {
+    c_str(link).map_or(0, |l| pending(l) as u64)
+}
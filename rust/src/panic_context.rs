@@ -0,0 +1,82 @@
+//! State dumps of actors whose handlers panic
+//!
+//! "panicked handling message 1012" says little about why. An actor that
+//! implements `PanicContext` is asked for a JSON summary of its state
+//! right after a handler panics, as the panic left it:
+//!
+//! ```ignore
+//! #[interop_actor("rust_price_monitor", snapshot, panic_context)]
+//! pub struct RustSubscriber { ... }
+//!
+//! impl PanicContext for RustSubscriber {
+//!     fn panic_context(&self) -> String {
+//!         format!("{{\"subscribed_topics\":{},\"update_count\":{}}}", self.topics.len(), self.update_count)
+//!     }
+//! }
+//! ```
+//!
+//! The dump goes with the panic wherever the supervisor (see lifecycle)
+//! reports it:
+//!
+//! - the `[Lifecycle]` log line of each panic;
+//! - a quarantined message (see quarantine): its record, the error
+//!   callback's text (`"<panic>; state: <dump>"`), and a dead letter at
+//!   stage `panic` with the dump as `context` (see dead_letters).
+//!
+//! Actors registered without the macro are wrapped by hand:
+//! `Instance::plain(WithContext::new(actor))`. The dump must be JSON; it
+//! is passed on as is. A provider that panics itself gives no dump.
+
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+use actors::{Actor, ActorContext, Message};
+
+use crate::snapshot::Snapshot;
+
+/// Summary of an actor's state for post-mortems
+pub trait PanicContext {
+    /// A JSON value, e.g. an object of counters and subscriptions
+    fn panic_context(&self) -> String;
+}
+
+/// An actor whose handler panics leave a dump of its `PanicContext`
+pub struct WithContext<A>(A);
+
+impl<A> WithContext<A> {
+    pub fn new(actor: A) -> Self {
+        WithContext(actor)
+    }
+}
+
+thread_local! {
+    // Dump of the last handler panic on this thread, until taken
+    static LAST: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+impl<A: Actor + PanicContext> Actor for WithContext<A> {
+    fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+        let actor = &mut self.0;
+        if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| actor.process_message(msg, ctx))) {
+            let dump = panic::catch_unwind(AssertUnwindSafe(|| self.0.panic_context())).ok();
+            LAST.with(|l| *l.borrow_mut() = dump);
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+impl<A: Snapshot> Snapshot for WithContext<A> {
+    fn snapshot(&self) -> Vec<u8> {
+        self.0.snapshot()
+    }
+
+    fn restore(&mut self, state: &[u8]) {
+        self.0.restore(state)
+    }
+}
+
+/// Dump left by the handler that just panicked on this thread. Called by
+/// the supervisor once it has caught the panic.
+pub(crate) fn take() -> Option<String> {
+    LAST.with(|l| l.borrow_mut().take())
+}
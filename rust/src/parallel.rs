@@ -0,0 +1,150 @@
+//! Read-only handlers run concurrently on a worker pool
+//!
+//! An actor handles one message at a time. For a fan-in heavy actor whose
+//! handler for some type only reads (e.g. analytics on `MarketUpdate`),
+//! `Parallel` moves that type off the actor's thread:
+//!
+//! ```ignore
+//! let model = Arc::new(model);  // shared, read-only
+//! let analytics = Parallel::new(Box::new(Analytics::new(handle)), 4)
+//!     .read_only(move |u: &MarketUpdate| model.score(u));
+//! mgr.manage("analytics", Box::new(analytics), ThreadConfig::default());
+//! ```
+//!
+//! - Messages of a read-only type are copied and queued to the pool; they
+//!   never reach the wrapped actor, and may run in any order relative to
+//!   each other and to other messages.
+//! - Every other message goes to the wrapped actor on its own thread, in
+//!   order, as before.
+//! - The queue holds `QUEUE_PER_WORKER` messages per worker; when it is
+//!   full the actor's thread waits, so a slow pool slows the actor down
+//!   instead of growing memory.
+//!
+//! A read-only handler gets only the message: anything else it reads must
+//! be shared state it captured (`Arc`, atomics, `RwLock`). It cannot reply.
+//! A panic in a handler is logged and the worker carries on.
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use actors::{Actor, ActorContext, Message};
+
+/// Queued read-only messages per worker before the actor's thread waits
+pub const QUEUE_PER_WORKER: usize = 64;
+
+type Handler = Arc<dyn Fn(&dyn Message) + Send + Sync>;
+
+struct ReadOnly {
+    copy: fn(&dyn Message) -> Option<Box<dyn Message>>,
+    handler: Handler,
+}
+
+fn copy_as<M: Message + Clone>(msg: &dyn Message) -> Option<Box<dyn Message>> {
+    msg.as_any().downcast_ref::<M>().map(|m| Box::new(m.clone()) as Box<dyn Message>)
+}
+
+type Job = (Handler, Box<dyn Message>);
+
+/// Actor wrapper that runs read-only handlers on `workers` threads
+pub struct Parallel {
+    actor: Box<dyn Actor>,
+    read_only: HashMap<i32, ReadOnly>,
+    workers: usize,
+    queue: Option<SyncSender<Job>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Parallel {
+    /// Wrap `actor`; the pool starts with the first read-only message
+    pub fn new(actor: Box<dyn Actor>, workers: usize) -> Self {
+        Parallel {
+            actor,
+            read_only: HashMap::new(),
+            workers: workers.max(1),
+            queue: None,
+            threads: Vec::new(),
+        }
+    }
+
+    /// Handle every `M` with `handler` on the pool instead of the actor.
+    /// `msg_id` is `M`'s message ID.
+    pub fn read_only_id<M, F>(mut self, msg_id: i32, handler: F) -> Self
+    where
+        M: Message + Clone,
+        F: Fn(&M) + Send + Sync + 'static,
+    {
+        let handler: Handler = Arc::new(move |msg: &dyn Message| {
+            if let Some(m) = msg.as_any().downcast_ref::<M>() {
+                handler(m);
+            }
+        });
+        self.read_only.insert(msg_id, ReadOnly { copy: copy_as::<M>, handler });
+        self
+    }
+
+    /// `read_only_id` for an interop message type
+    pub fn read_only<M, F>(self, handler: F) -> Self
+    where
+        M: Message + Clone + crate::cpp_actor_if::InteropMessage,
+        F: Fn(&M) + Send + Sync + 'static,
+    {
+        self.read_only_id(M::MSG_ID, handler)
+    }
+
+    fn queue(&mut self) -> &SyncSender<Job> {
+        let workers = self.workers;
+        let threads = &mut self.threads;
+        self.queue.get_or_insert_with(|| {
+            let (tx, rx) = mpsc::sync_channel(workers * QUEUE_PER_WORKER);
+            let rx = Arc::new(Mutex::new(rx));
+            for _ in 0..workers {
+                let rx = rx.clone();
+                threads.push(thread::spawn(move || work(rx)));
+            }
+            tx
+        })
+    }
+}
+
+fn work(rx: Arc<Mutex<Receiver<Job>>>) {
+    loop {
+        // Hold the lock only to take a job
+        let job = rx.lock().unwrap().recv();
+        let (handler, msg) = match job {
+            Ok(job) => job,
+            Err(_) => return,  // actor dropped
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| handler(msg.as_ref())));
+        if result.is_err() {
+            eprintln!("[Parallel] read-only handler panicked on message {}", msg.message_id());
+        }
+    }
+}
+
+impl Actor for Parallel {
+    fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+        let job = self
+            .read_only
+            .get(&msg.message_id())
+            .and_then(|r| Some((r.handler.clone(), (r.copy)(msg)?)));
+        match job {
+            Some(job) => {
+                let _ = self.queue().send(job);
+            }
+            None => self.actor.process_message(msg, ctx),
+        }
+    }
+}
+
+impl Drop for Parallel {
+    fn drop(&mut self) {
+        // Workers finish queued messages, then see the closed queue
+        self.queue = None;
+        for t in self.threads.drain(..) {
+            let _ = t.join();
+        }
+    }
+}
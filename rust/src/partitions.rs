@@ -0,0 +1,181 @@
+//! Partitioned topics: spread a topic's consumers, keep per-key order
+//!
+//! A busy topic ("trades") can be split into N partitions by a key field,
+//! so that N consumers, in either language, each take a share:
+//!
+//! ```ignore
+//! partitions::declare("trades", 4, "symbol")?;
+//!
+//! // Consumer 0 of 2 takes partitions 0 and 2
+//! partitions::subscribe("rust_trade_worker_0", "rust_feed", "trades", &[0, 2], QOS_BEST_EFFORT);
+//!
+//! // Publisher: unchanged, each message reaches the subscribers of its partition
+//! broker::publish("rust_feed", "trades", &trade)?;
+//! ```
+//!
+//! - A message's partition is the FNV-1a hash of its key field's text
+//!   modulo N, the same in every process. Messages without the field (or
+//!   with a field that is not a string or integer) go to partition 0.
+//! - Partition n is subscribed to as the topic `"<topic>#<n>"`, with a
+//!   plain Subscribe, so C++ consumers need nothing new. Subscribers of
+//!   the plain topic still get every partition.
+//! - Messages with the same key always land in the same partition, and a
+//!   subscriber receives a publisher's messages in publish order, so each
+//!   key's messages are seen in order by whichever consumer owns it.
+//!
+//! `broker::publish` routes by partition itself. C++ publishers that send
+//! to subscribers directly ask `interop_topic_partition()` for the
+//! partition of a C struct. A topic's partitioning cannot be changed once
+//! declared, since that would move keys between consumers.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::Mutex;
+
+use actors::Message;
+
+use crate::broker;
+use crate::hash_router::hash;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_field_key, message_from_c_borrowed};
+use crate::interop_string::InteropString;
+use crate::names::c_str;
+
+/// Separates a topic from its partition number in Subscribe.topic
+pub const PARTITION_SEP: char = '#';
+
+/// How a topic is partitioned
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Partitioning {
+    pub partitions: u32,
+    /// Field whose text selects the partition, e.g. "symbol"
+    pub key_field: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PartitionError {
+    /// Zero partitions
+    NoPartitions,
+    /// The topic is already partitioned another way
+    Conflict { topic: String, declared: Partitioning },
+    /// `"<topic>#<n>"` for the last partition does not fit in Subscribe.topic
+    NameTooLong(String),
+}
+
+impl fmt::Display for PartitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionError::NoPartitions => write!(f, "a partitioned topic needs at least one partition"),
+            PartitionError::Conflict { topic, declared } => write!(
+                f,
+                "topic '{}' is declared with {} partitions by '{}'",
+                topic, declared.partitions, declared.key_field
+            ),
+            PartitionError::NameTooLong(name) => write!(f, "partition topic '{}' is too long", name),
+        }
+    }
+}
+
+impl std::error::Error for PartitionError {}
+
+lazy_static::lazy_static! {
+    static ref PARTITIONED: Mutex<HashMap<String, Partitioning>> = Mutex::new(HashMap::new());
+}
+
+/// Name subscribers use for partition `partition` of `topic`
+pub fn partition_topic(topic: &str, partition: u32) -> String {
+    format!("{}{}{}", topic, PARTITION_SEP, partition)
+}
+
+/// Partition `topic` into `partitions` by `key_field`; a no-op if it
+/// already is
+pub fn declare(topic: &str, partitions: u32, key_field: &str) -> Result<(), PartitionError> {
+    if partitions == 0 {
+        return Err(PartitionError::NoPartitions);
+    }
+    let last = partition_topic(topic, partitions - 1);
+    if last.len() > InteropString::<32>::CAPACITY {
+        return Err(PartitionError::NameTooLong(last));
+    }
+    let wanted = Partitioning { partitions, key_field: key_field.to_string() };
+    let mut partitioned = PARTITIONED.lock().unwrap();
+    match partitioned.get(topic) {
+        Some(declared) if *declared != wanted => {
+            Err(PartitionError::Conflict { topic: topic.to_string(), declared: declared.clone() })
+        }
+        Some(_) => Ok(()),
+        None => {
+            partitioned.insert(topic.to_string(), wanted);
+            Ok(())
+        }
+    }
+}
+
+/// How `topic` is partitioned, None if it is not
+pub fn partitioning(topic: &str) -> Option<Partitioning> {
+    PARTITIONED.lock().unwrap().get(topic).cloned()
+}
+
+/// Partition of `msg` on `topic`, None if the topic is not partitioned
+pub fn partition_of(topic: &str, msg: &dyn Message) -> Option<u32> {
+    let p = partitioning(topic)?;
+    let partition = match message_field_key(msg, &p.key_field) {
+        Some(key) => (hash(key.as_bytes()) % p.partitions as u64) as u32,
+        None => 0,
+    };
+    Some(partition)
+}
+
+/// Subscribe Rust actor `me` to partitions `partitions` of `topic` of
+/// `publisher`. Returns false if any subscription failed.
+pub fn subscribe(me: &str, publisher: &str, topic: &str, partitions: &[u32], qos: i32) -> bool {
+    let mut ok = true;
+    for &p in partitions {
+        ok &= broker::subscribe(me, publisher, &partition_topic(topic, p), qos);
+    }
+    ok
+}
+
+pub fn unsubscribe(me: &str, publisher: &str, topic: &str, partitions: &[u32]) {
+    for &p in partitions {
+        broker::unsubscribe(me, publisher, &partition_topic(topic, p));
+    }
+}
+
+/// Partition `topic` into `partitions` by field `key_field`.
+/// Returns 0, InvalidArgument on a bad argument, or NameTaken if the topic
+/// is partitioned another way.
+#[export_name = c_symbol!("interop_declare_partitions")]
+pub extern "C" fn interop_declare_partitions(topic: *const c_char, partitions: u32, key_field: *const c_char) -> c_int {
+    match (c_str(topic), c_str(key_field)) {
+        (Some(t), Some(k)) => {
+            let rc = match declare(t, partitions, k) {
+                Ok(()) => InteropErrorCode::Ok,
+                Err(e) => {
+                    eprintln!("[Partitions] {}", e);
+                    match e {
+                        PartitionError::Conflict { .. } => InteropErrorCode::NameTaken,
+                        _ => InteropErrorCode::InvalidArgument,
+                    }
+                }
+            };
+            rc as c_int
+        }
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Partition of C struct `msg_data` (message `msg_id`) on `topic`, NotFound
+/// if the topic is not partitioned, or UnknownMessage
+#[export_name = c_symbol!("interop_topic_partition")]
+pub extern "C" fn interop_topic_partition(topic: *const c_char, msg_id: c_int, msg_data: *const c_void) -> c_int {
+    let topic = match c_str(topic) {
+        Some(t) if !msg_data.is_null() => t,
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    match unsafe { message_from_c_borrowed(msg_id, msg_data) } {
+        Some(msg) => partition_of(topic, msg.as_ref()).map_or(InteropErrorCode::NotFound as c_int, |p| p as c_int),
+        None => InteropErrorCode::UnknownMessage as c_int,
+    }
+}
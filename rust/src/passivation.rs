@@ -0,0 +1,287 @@
+//! Idle actors passivated and re-activated on demand
+//!
+//! A host registering hundreds of per-symbol actors keeps most of them
+//! doing nothing most of the day. Give those an idle timeout and an actor
+//! that has handled no message for that long is passivated: its state is
+//! snapshotted and its instance dropped. The next message sent to it,
+//! from either language, re-activates it first, so senders never see
+//! the difference:
+//!
+//! ```c
+//! register_actor_shared("SymbolMonitor", "monitor_aapl", "monitors");
+//! interop_set_idle_timeout("monitor_aapl", 60000);   // 0 turns it off
+//! ```
+//!
+//! - Passivating: a sweeper thread finds actors idle past their timeout
+//!   and queues a marker behind their mailbox. The actor's supervisor (see
+//!   lifecycle) passivates it on reaching the marker, unless a message
+//!   arrived in the meantime. Frozen actors are left alone (see freeze).
+//! - Re-activating: the supervisor builds a fresh instance from the
+//!   actor's factory, restores the snapshot and delivers Start, then
+//!   handles the message. Any message wakes a dormant actor, Rust-only
+//!   ones included, except while the Manager is ending its actors: then
+//!   only interop messages do, and End is taken without an instance.
+//! - Only actors with `Snapshot` hooks are passivated (see snapshot); for
+//!   any other actor the timeout is ignored with a warning.
+//! - Threads: each Manager actor keeps its thread for the life of the
+//!   Manager, so a dormant actor with a thread of its own still holds it,
+//!   parked on an empty mailbox. Members of a shared group (see
+//!   shared_thread) hold none, so register actors expected to sit idle in
+//!   groups and only the instances of those in use stay resident.
+//!
+//! The health summary reports dormant actors with status `dormant`.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use actors::{Actor, ActorContext, Message};
+
+use crate::clock;
+use crate::freeze;
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::shared_thread;
+
+/// Longest pause between two sweeps
+const MAX_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Awake,
+    // Marker queued; a message before it cancels
+    Asked,
+    Dormant,
+}
+
+struct Idle {
+    // None: removed while dormant, forgotten once re-activated
+    timeout: Option<Duration>,
+    last: Instant,
+    state: State,
+}
+
+/// Queued by the sweeper; handled by the actor's supervisor
+pub(crate) struct Passivate;
+
+// Only the supervisor looks for it
+impl Message for Passivate {
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+    fn message_id(&self) -> i32 { 0 }
+}
+
+/// Stands in for a passivated actor's instance
+pub(crate) struct Dormant;
+
+impl Actor for Dormant {
+    fn process_message(&mut self, _msg: &dyn Message, _ctx: &mut ActorContext) {}
+}
+
+// Fast path: no lock while no actor has a timeout
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+// The Manager is ending its actors
+static ENDING: AtomicBool = AtomicBool::new(false);
+static PASSIVATIONS: AtomicU64 = AtomicU64::new(0);
+static ACTIVATIONS: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref IDLE: Mutex<HashMap<String, Idle>> = Mutex::new(HashMap::new());
+    // True while the sweeper runs; it exits once no actor has a timeout
+    static ref SWEEPER: Mutex<bool> = Mutex::new(false);
+}
+
+/// Passivate `name` after `timeout` without a message, or never with
+/// None. May be set before the actor is registered.
+pub fn set_idle_timeout(name: &str, timeout: Option<Duration>) {
+    {
+        let mut idle = IDLE.lock().unwrap();
+        match timeout {
+            Some(_) => {
+                let entry =
+                    idle.entry(name.to_string()).or_insert(Idle { timeout, last: clock::now(), state: State::Awake });
+                entry.timeout = timeout;
+            }
+            // A dormant actor stays so until its next message
+            None => match idle.get_mut(name) {
+                Some(i) if i.state == State::Dormant => i.timeout = None,
+                _ => {
+                    idle.remove(name);
+                }
+            },
+        }
+        ACTIVE.store(!idle.is_empty(), Ordering::Release);
+    }
+    if timeout.is_some() {
+        let mut running = SWEEPER.lock().unwrap();
+        if !*running {
+            *running = true;
+            thread::spawn(sweep_loop);
+        }
+    }
+}
+
+/// Idle timeout of `name`, None if it is never passivated
+pub fn idle_timeout(name: &str) -> Option<Duration> {
+    IDLE.lock().unwrap().get(name).and_then(|i| i.timeout)
+}
+
+/// Passivated and not re-activated since
+pub fn is_dormant(name: &str) -> bool {
+    ACTIVE.load(Ordering::Acquire) && IDLE.lock().unwrap().get(name).is_some_and(|i| i.state == State::Dormant)
+}
+
+/// Names of the dormant actors, sorted
+pub fn dormant() -> Vec<String> {
+    let idle = IDLE.lock().unwrap();
+    let mut names: Vec<String> =
+        idle.iter().filter(|(_, i)| i.state == State::Dormant).map(|(n, _)| n.clone()).collect();
+    names.sort();
+    names
+}
+
+/// Passivations since start
+pub fn passivations() -> u64 {
+    PASSIVATIONS.load(Ordering::Relaxed)
+}
+
+/// Re-activations since start
+pub fn activations() -> u64 {
+    ACTIVATIONS.load(Ordering::Relaxed)
+}
+
+/// Set around the Manager's end, so End does not wake dormant actors
+pub(crate) fn set_ending(ending: bool) {
+    ENDING.store(ending, Ordering::Release);
+}
+
+pub(crate) fn is_ending() -> bool {
+    ENDING.load(Ordering::Acquire)
+}
+
+/// Note that `name` is handling a message. Called by the supervisor.
+pub(crate) fn touch(name: &str) {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return;
+    }
+    if let Some(i) = IDLE.lock().unwrap().get_mut(name) {
+        i.last = clock::now();
+        if i.state == State::Asked {
+            i.state = State::Awake;
+        }
+    }
+}
+
+/// Whether `name` should be passivated on reaching the marker: false if a
+/// message came first or the timeout was removed. Marks it dormant.
+pub(crate) fn confirm(name: &str) -> bool {
+    let mut idle = IDLE.lock().unwrap();
+    match idle.get_mut(name) {
+        Some(i) if i.state == State::Asked && i.timeout.is_some() => {
+            i.state = State::Dormant;
+            PASSIVATIONS.fetch_add(1, Ordering::Relaxed);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// `name` could not be passivated; it keeps running and is not asked again
+pub(crate) fn refuse(name: &str, reason: &str) {
+    eprintln!("[Passivation] {}: not passivated: {}", name, reason);
+    let mut idle = IDLE.lock().unwrap();
+    idle.remove(name);
+    ACTIVE.store(!idle.is_empty(), Ordering::Release);
+}
+
+/// `name` was re-activated. Called by the supervisor.
+pub(crate) fn activated(name: &str) {
+    ACTIVATIONS.fetch_add(1, Ordering::Relaxed);
+    let mut idle = IDLE.lock().unwrap();
+    match idle.get_mut(name) {
+        Some(i) if i.timeout.is_some() => {
+            i.state = State::Awake;
+            i.last = clock::now();
+        }
+        _ => {
+            idle.remove(name);
+            ACTIVE.store(!idle.is_empty(), Ordering::Release);
+        }
+    }
+}
+
+/// Actors idle past their timeout, marked as asked
+fn due() -> Vec<String> {
+    let now = clock::now();
+    let mut idle = IDLE.lock().unwrap();
+    let mut names = Vec::new();
+    for (name, i) in idle.iter_mut() {
+        let expired = i.timeout.is_some_and(|t| now.saturating_duration_since(i.last) >= t);
+        if i.state == State::Awake && expired {
+            i.state = State::Asked;
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+fn ask(name: &str) -> bool {
+    if shared_thread::group_of(name).is_some() {
+        shared_thread::send_to_member(name, Box::new(Passivate))
+    } else {
+        get_actor_ref(name, "").map(|r| r.send(Box::new(Passivate), None)).is_some()
+    }
+}
+
+fn sweep_loop() {
+    loop {
+        let interval = {
+            let idle = IDLE.lock().unwrap();
+            let mut running = SWEEPER.lock().unwrap();
+            if idle.is_empty() {
+                *running = false;
+                return;
+            }
+            // A quarter of the shortest timeout, so none is overshot by much
+            idle.values().filter_map(|i| i.timeout).map(|t| t / 4).min().unwrap_or(MAX_SWEEP_INTERVAL).min(MAX_SWEEP_INTERVAL)
+        };
+        clock::sleep(interval.max(Duration::from_millis(1)));
+        for name in due() {
+            if freeze::is_frozen(&name) || !ask(&name) {
+                // Not registered yet or frozen: asked again next sweep
+                if let Some(i) = IDLE.lock().unwrap().get_mut(&name) {
+                    i.state = State::Awake;
+                }
+            }
+        }
+    }
+}
+
+/// Passivate `actor_name` after `idle_ms` without a message; 0 never
+/// passivates it. Returns 0, or InvalidArgument on a null or non-UTF-8
+/// name.
+#[export_name = c_symbol!("interop_set_idle_timeout")]
+pub extern "C" fn interop_set_idle_timeout(actor_name: *const c_char, idle_ms: u64) -> c_int {
+    match c_str(actor_name) {
+        Some(name) => {
+            let timeout = if idle_ms == 0 { None } else { Some(Duration::from_millis(idle_ms)) };
+            set_idle_timeout(name, timeout);
+            InteropErrorCode::Ok as c_int
+        }
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// 1 if `actor_name` is dormant, 0 if not, InvalidArgument on a null or
+/// non-UTF-8 name
+#[export_name = c_symbol!("interop_is_dormant")]
+pub extern "C" fn interop_is_dormant(actor_name: *const c_char) -> c_int {
+    match c_str(actor_name) {
+        Some(name) => is_dormant(name) as c_int,
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
@@ -0,0 +1,119 @@
+//! Pause and resume dispatch to a Rust actor
+//!
+//! `rust_actor_pause(name)` keeps a Rust actor registered and its queue
+//! filling, but stops handing it messages, so a C++ host can quiesce a
+//! subscriber while reference data reloads:
+//!
+//! ```c
+//! rust_actor_pause("rust_price_monitor");
+//! reload_reference_data();
+//! rust_actor_resume("rust_price_monitor");   // held messages first, in order
+//! ```
+//!
+//! Messages arriving through the bridge (`rust_actor_send`,
+//! `rust_actor_fast_send`, `rust_actor_post` and spill drains) are held in
+//! arrival order. They still count in `queue_depth`, so the post limit
+//! (`rust_actor_set_post_limit`) and spill threshold bound them as they
+//! bound the mailbox. Held `fast_send`s are delivered asynchronously on
+//! resume. Rust -> Rust sends and messages from the Manager itself (Start)
+//! are not held.
+
+use std::collections::{HashMap, VecDeque};
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use actors::{ActorRef, Message};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::names;
+
+struct Held {
+    msg: Box<dyn Message>,
+    target: ActorRef,
+    sender: Option<ActorRef>,
+}
+
+lazy_static::lazy_static! {
+    static ref PAUSED: Mutex<HashMap<String, VecDeque<Held>>> = Mutex::new(HashMap::new());
+}
+
+// Fast path: no lock while no actor is paused
+static ANY_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Stop dispatching to `name`; its messages are held until `resume()`.
+/// Returns false if it was already paused.
+pub fn pause(name: &str) -> bool {
+    let mut paused = PAUSED.lock().unwrap();
+    if paused.contains_key(name) {
+        return false;
+    }
+    paused.insert(name.to_string(), VecDeque::new());
+    ANY_PAUSED.store(true, Ordering::Release);
+    true
+}
+
+/// Deliver the messages held for `name`, in order, and dispatch to it
+/// again. Returns the number delivered, None if it was not paused.
+pub fn resume(name: &str) -> Option<usize> {
+    // Sent under the lock so new messages cannot overtake held ones
+    let mut paused = PAUSED.lock().unwrap();
+    let held = paused.remove(name)?;
+    ANY_PAUSED.store(!paused.is_empty(), Ordering::Release);
+    let count = held.len();
+    for h in held {
+        h.target.send(h.msg, h.sender);
+    }
+    Some(count)
+}
+
+pub fn is_paused(name: &str) -> bool {
+    ANY_PAUSED.load(Ordering::Acquire) && PAUSED.lock().unwrap().contains_key(name)
+}
+
+/// Messages held for `name` while paused
+pub fn held(name: &str) -> usize {
+    PAUSED.lock().unwrap().get(name).map_or(0, |q| q.len())
+}
+
+/// Send `msg` to Rust actor `name`, or hold it if the actor is paused
+pub fn send(target: &ActorRef, name: &str, msg: Box<dyn Message>, sender: Option<ActorRef>) {
+    if ANY_PAUSED.load(Ordering::Acquire) {
+        if let Some(queue) = PAUSED.lock().unwrap().get_mut(name) {
+            queue.push_back(Held { msg, target: target.clone(), sender });
+            return;
+        }
+    }
+    target.send(msg, sender);
+}
+
+fn rust_name<'a>(name: *const c_char) -> Option<&'a str> {
+    names::c_str(name).filter(|name| names::is_rust(name))
+}
+
+/// Hold bridge messages for Rust actor `actor_name` until resumed.
+/// Returns 0 (also if already paused), or NotFound if it is not a Rust
+/// actor.
+#[export_name = c_symbol!("rust_actor_pause")]
+pub extern "C" fn rust_actor_pause(actor_name: *const c_char) -> c_int {
+    match rust_name(actor_name) {
+        Some(name) => {
+            if pause(name) {
+                eprintln!("[Pause] {} paused", name);
+            }
+            InteropErrorCode::Ok as c_int
+        }
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Deliver what was held for `actor_name` and dispatch to it again.
+/// Returns the number of messages delivered, or NotFound if it is not a
+/// Rust actor or not paused.
+#[export_name = c_symbol!("rust_actor_resume")]
+pub extern "C" fn rust_actor_resume(actor_name: *const c_char) -> c_int {
+    match rust_name(actor_name).and_then(resume) {
+        Some(count) => count as c_int,
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
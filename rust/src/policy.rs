@@ -0,0 +1,229 @@
+//! Allow/deny policy for messages crossing the bridge
+//!
+//! Compliance separation (e.g. pricing actors must never send orders to
+//! execution actors) is enforced where messages cross between runtimes:
+//! `rust_actor_send`, `rust_actor_fast_send`, `rust_actor_post` and every
+//! Rust -> C++ send (`ActorRef::Cpp`, `CppActorIF`) consult the policy
+//! before forwarding.
+//!
+//! A rule matches on sender, target and message ID, each of which may be
+//! a wildcard. Rules are checked in the order they were added; the first
+//! match decides, and a message no rule matches gets the default verdict
+//! (allow unless changed). Configure at init, then `seal()` so the policy
+//! cannot be changed at run time:
+//!
+//! ```c
+//! interop_policy_set_default(INTEROP_POLICY_DENY);
+//! interop_policy_allow("pricing", "md_gateway", INTEROP_POLICY_ANY_MSG);
+//! interop_policy_allow(NULL, "pricing", 1012);   // MarketUpdate from anyone
+//! interop_policy_seal();
+//! ```
+//!
+//! A denied message is not delivered: the send returns `POLICY_DENIED`, the
+//! denial is counted, logged and passed to the audit callback set with
+//! `interop_set_policy_audit_callback()`. Messages from C++ code that is
+//! not an actor have sender "" and only match wildcard senders.
+//!
+//! Messages between actors of different tenants are refused here too,
+//! unless a tenant rule allows them (see tenant); `seal()` also fixes the
+//! tenant labels and rules. In `Reject` contract mode, so are messages
+//! outside an actor's declared contract (see contracts).
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::contracts;
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::tenant;
+
+/// Send return code for a message the policy does not allow
+pub const POLICY_DENIED: i32 = InteropErrorCode::PolicyDenied as i32;
+
+/// Message ID wildcard for the C API
+pub const ANY_MSG: c_int = -1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum Verdict {
+    Deny = 0,
+    Allow = 1,
+}
+
+/// One policy rule; None matches anything
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub sender: Option<String>,
+    pub target: Option<String>,
+    pub msg_id: Option<i32>,
+    pub verdict: Verdict,
+}
+
+impl Rule {
+    fn matches(&self, sender: &str, target: &str, msg_id: i32) -> bool {
+        self.sender.as_deref().is_none_or(|s| s == sender)
+            && self.target.as_deref().is_none_or(|t| t == target)
+            && self.msg_id.is_none_or(|id| id == msg_id)
+    }
+}
+
+/// Called for every denied message with (sender, target, msg_id)
+pub type AuditCallback = extern "C" fn(sender: *const c_char, target: *const c_char, msg_id: c_int);
+
+struct Policy {
+    rules: Vec<Rule>,
+    default: Verdict,
+}
+
+static POLICY: Mutex<Policy> = Mutex::new(Policy { rules: Vec::new(), default: Verdict::Allow });
+// Skips the lock while there is nothing to check
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static SEALED: AtomicBool = AtomicBool::new(false);
+static DENIED: AtomicU64 = AtomicU64::new(0);
+static AUDIT: Mutex<Option<AuditCallback>> = Mutex::new(None);
+
+/// Add a rule after the existing ones. Returns false once sealed.
+pub fn add_rule(rule: Rule) -> bool {
+    if SEALED.load(Ordering::Acquire) {
+        return false;
+    }
+    POLICY.lock().unwrap().rules.push(rule);
+    ACTIVE.store(true, Ordering::Release);
+    true
+}
+
+/// Verdict for messages no rule matches. Returns false once sealed.
+pub fn set_default(verdict: Verdict) -> bool {
+    if SEALED.load(Ordering::Acquire) {
+        return false;
+    }
+    let mut policy = POLICY.lock().unwrap();
+    policy.default = verdict;
+    ACTIVE.store(!policy.rules.is_empty() || verdict == Verdict::Deny, Ordering::Release);
+    true
+}
+
+/// Refuse further changes for the life of the process
+pub fn seal() {
+    SEALED.store(true, Ordering::Release);
+}
+
+pub fn is_sealed() -> bool {
+    SEALED.load(Ordering::Acquire)
+}
+
+/// Messages denied so far
+pub fn denied() -> u64 {
+    DENIED.load(Ordering::Relaxed)
+}
+
+/// Verdict for one message, without auditing
+pub fn verdict(sender: &str, target: &str, msg_id: i32) -> Verdict {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return Verdict::Allow;
+    }
+    let policy = POLICY.lock().unwrap();
+    policy
+        .rules
+        .iter()
+        .find(|r| r.matches(sender, target, msg_id))
+        .map_or(policy.default, |r| r.verdict)
+}
+
+/// Check a message about to cross the bridge. Returns true if it may be
+/// forwarded; a denial is counted, logged and audited.
+pub fn allow(sender: &str, target: &str, msg_id: i32) -> bool {
+    let cross_tenant = !tenant::permits(sender, target, msg_id);
+    let broken = !contracts::permits(sender, target, msg_id);
+    if !cross_tenant && !broken && verdict(sender, target, msg_id) == Verdict::Allow {
+        return true;
+    }
+    let why = match (cross_tenant, broken) {
+        (true, _) => " (other tenant)",
+        (false, true) => " (breaks contract)",
+        _ => "",
+    };
+    deny(sender, target, msg_id, why);
+    false
+}
+
+/// Check only the tenants of a Rust -> Rust message (see tenant); a
+/// denial is counted, logged and audited like any other
+pub(crate) fn allow_tenant(sender: &str, target: &str, msg_id: i32) -> bool {
+    if tenant::permits(sender, target, msg_id) {
+        return true;
+    }
+    deny(sender, target, msg_id, " (other tenant)");
+    false
+}
+
+fn deny(sender: &str, target: &str, msg_id: i32, why: &str) {
+    DENIED.fetch_add(1, Ordering::Relaxed);
+    eprintln!("[Policy] denied message {} from '{}' to '{}'{}", msg_id, sender, target, why);
+    let cb = *AUDIT.lock().unwrap();
+    if let (Some(cb), Ok(s), Ok(t)) = (cb, CString::new(sender), CString::new(target)) {
+        cb(s.as_ptr(), t.as_ptr(), msg_id);
+    }
+}
+
+fn c_name(s: *const c_char) -> Option<Option<String>> {
+    if s.is_null() {
+        return Some(None);
+    }
+    c_str(s).map(|s| Some(s.to_string()))
+}
+
+fn add_c_rule(sender: *const c_char, target: *const c_char, msg_id: c_int, verdict: Verdict) -> c_int {
+    let (sender, target) = match (c_name(sender), c_name(target)) {
+        (Some(s), Some(t)) => (s, t),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let msg_id = if msg_id == ANY_MSG { None } else { Some(msg_id) };
+    sealed_unless(add_rule(Rule { sender, target, msg_id, verdict }))
+}
+
+/// Ok, or PolicyDenied if a sealed policy refused the change
+pub(crate) fn sealed_unless(changed: bool) -> c_int {
+    let rc = if changed { InteropErrorCode::Ok } else { InteropErrorCode::PolicyDenied };
+    rc as c_int
+}
+
+/// Allow `msg_id` (INTEROP_POLICY_ANY_MSG for all) from `sender` to
+/// `target`; NULL names match any actor. Returns 0, PolicyDenied if
+/// sealed, or InvalidArgument on a non-UTF-8 name.
+#[export_name = c_symbol!("interop_policy_allow")]
+pub extern "C" fn interop_policy_allow(sender: *const c_char, target: *const c_char, msg_id: c_int) -> c_int {
+    add_c_rule(sender, target, msg_id, Verdict::Allow)
+}
+
+/// Deny counterpart of interop_policy_allow()
+#[export_name = c_symbol!("interop_policy_deny")]
+pub extern "C" fn interop_policy_deny(sender: *const c_char, target: *const c_char, msg_id: c_int) -> c_int {
+    add_c_rule(sender, target, msg_id, Verdict::Deny)
+}
+
+/// Verdict when no rule matches (INTEROP_POLICY_ALLOW / _DENY).
+/// Returns 0, PolicyDenied if sealed, or InvalidArgument if `verdict` is
+/// unknown.
+#[export_name = c_symbol!("interop_policy_set_default")]
+pub extern "C" fn interop_policy_set_default(verdict: c_int) -> c_int {
+    let verdict = match verdict {
+        0 => Verdict::Deny,
+        1 => Verdict::Allow,
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    sealed_unless(set_default(verdict))
+}
+
+#[export_name = c_symbol!("interop_policy_seal")]
+pub extern "C" fn interop_policy_seal() {
+    seal();
+}
+
+/// Set the callback for denied messages, or clear it with null
+#[export_name = c_symbol!("interop_set_policy_audit_callback")]
+pub extern "C" fn interop_set_policy_audit_callback(cb: Option<AuditCallback>) {
+    *AUDIT.lock().unwrap() = cb;
+}
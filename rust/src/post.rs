@@ -0,0 +1,169 @@
+//! Posting messages to Rust actors from arbitrary C++ threads
+//!
+//! `rust_actor_send()` is meant for C++ actor threads. `rust_actor_post()`
+//! is the entry point for everything else (network threads, timers,
+//! callbacks from third-party libraries):
+//!
+//! - Safe to call from any thread, concurrently. The Manager is only
+//!   consulted under a lock on the first post to a name; after that the
+//!   `ActorRef` is cached and posting is a channel send.
+//! - The C struct is converted before returning, so `data` can be reused
+//!   as soon as the call returns.
+//! - Admitted and queued as `rust_actor_send()` does (see inbound), with
+//!   no sender: the bridge policy, sender auth and size limit apply, and
+//!   taps, the journal, spill and subscriber queues see the message.
+//! - Never blocks. With a limit set by `rust_actor_set_post_limit()`, a
+//!   post to an actor whose queue depth is at the limit is refused with
+//!   `POST_QUEUE_FULL` so the caller can drop, retry or slow down.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::Mutex;
+
+use actors::ActorRef;
+
+use crate::boundary::{self, Direction};
+use crate::dead_letters::{self, Stage};
+use crate::dedup;
+use crate::fallback;
+use crate::health;
+use crate::idempotency;
+use crate::inbound;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_log;
+use crate::interop_messages::{
+    c_struct_size, give_back_message_bytes, message_from_c, normalize_message, validate_message,
+};
+use crate::names::c_str;
+use crate::policy;
+use crate::queue_depth;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::sender_auth::Entry;
+use crate::shutdown;
+use crate::size_limit;
+use crate::validation;
+
+/// Queued
+pub const POST_OK: c_int = InteropErrorCode::Ok as c_int;
+/// No Rust actor with that name, or no Manager yet
+pub const POST_NOT_FOUND: c_int = InteropErrorCode::NotFound as c_int;
+/// Unknown message ID
+pub const POST_UNKNOWN_MESSAGE: c_int = InteropErrorCode::UnknownMessage as c_int;
+/// Queue depth at the configured limit; nothing was queued
+pub const POST_QUEUE_FULL: c_int = InteropErrorCode::QueueFull as c_int;
+/// Null name or data
+pub const POST_INVALID: c_int = InteropErrorCode::InvalidArgument as c_int;
+/// Refused by the bridge policy (see policy.rs) or sender auth (see
+/// sender_auth.rs)
+pub const POST_DENIED: c_int = policy::POLICY_DENIED;
+/// Over the bridge size limit (see size_limit.rs)
+pub const POST_TOO_LARGE: c_int = size_limit::MESSAGE_TOO_LARGE;
+/// Shutdown has been requested (see shutdown.rs)
+pub const POST_SHUTTING_DOWN: c_int = shutdown::SHUTTING_DOWN;
+/// Breaks a field rule of its type (see validation.rs); dropped
+pub const POST_INVALID_MESSAGE: c_int = validation::INVALID_MESSAGE;
+
+struct PostTarget {
+    actor: ActorRef,
+    // 0 = unlimited
+    limit: usize,
+}
+
+lazy_static::lazy_static! {
+    static ref TARGETS: Mutex<HashMap<String, PostTarget>> = Mutex::new(HashMap::new());
+    static ref LIMITS: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+}
+
+fn resolve(name: &str) -> Option<(ActorRef, usize)> {
+    let mut targets = TARGETS.lock().unwrap();
+    if let Some(t) = targets.get(name) {
+        return Some((t.actor.clone(), t.limit));
+    }
+    let actor = get_actor_ref(name, "")?;
+    if let ActorRef::Cpp(_) = actor {
+        return None;  // only Rust actors take posts
+    }
+    let limit = LIMITS.lock().unwrap().get(name).copied().unwrap_or(0);
+    targets.insert(name.to_string(), PostTarget { actor: actor.clone(), limit });
+    Some((actor, limit))
+}
+
+/// Post a message to a Rust actor from any thread. Returns POST_OK,
+/// POST_NOT_FOUND, POST_UNKNOWN_MESSAGE, POST_QUEUE_FULL, POST_INVALID,
+/// POST_DENIED, POST_TOO_LARGE, POST_SHUTTING_DOWN or POST_INVALID_MESSAGE.
+#[export_name = c_symbol!("rust_actor_post")]
+pub extern "C" fn rust_actor_post(actor_name: *const c_char, msg_type: c_int, msg_data: *const c_void) -> c_int {
+    let rc = dead_letters::from_cpp(|| post_to_actor(actor_name, msg_type, msg_data));
+    interop_log::send_c(actor_name, std::ptr::null(), msg_type, rc);
+    rc
+}
+
+fn post_to_actor(actor_name: *const c_char, msg_type: c_int, msg_data: *const c_void) -> c_int {
+    let name = match c_str(actor_name) {
+        Some(s) if !msg_data.is_null() => s,
+        _ => return POST_INVALID,
+    };
+    if !shutdown::accepting() {
+        return POST_SHUTTING_DOWN;
+    }
+    if c_struct_size(msg_type).is_none() {
+        health::record_unknown_message("", name, msg_type);
+        // Still seen by a catch-all handler (see fallback)
+        if unsafe { inbound::admit(name, "", msg_type, Entry::Post, msg_data) }.is_ok() {
+            if let Some((actor, _)) = resolve(name) {
+                fallback::deliver_unknown(&actor, name, "", msg_type, None);
+            }
+        }
+        return POST_UNKNOWN_MESSAGE;
+    }
+    if let Err(rc) = unsafe { inbound::admit(name, "", msg_type, Entry::Post, msg_data) } {
+        return rc;
+    }
+    let (actor, limit) = match resolve(name) {
+        Some(r) => r,
+        None => {
+            health::record_dead_letter("", name, msg_type);
+            return POST_NOT_FOUND;
+        }
+    };
+    if limit > 0 && queue_depth::pending(name) >= limit {
+        dead_letters::record(Stage::MailboxFull, "", name, msg_type, POST_QUEUE_FULL);
+        return POST_QUEUE_FULL;
+    }
+    let mut msg = match unsafe { message_from_c(msg_type, msg_data) } {
+        Some(m) => m,
+        None => return POST_UNKNOWN_MESSAGE,
+    };
+    boundary::record(Direction::ToRust, msg_type);
+    if let Err(e) = validate_message(msg.as_ref()).and_then(|()| normalize_message(msg.as_mut())) {
+        // A take-mode buffer stays the poster's to free
+        unsafe { give_back_message_bytes(msg.as_mut(), msg_data) };
+        validation::reject(name, "", &e);
+        return POST_INVALID_MESSAGE;
+    }
+    if dedup::is_duplicate(name, msg.as_ref()) || idempotency::is_replay(name, "", msg.as_ref()) {
+        return POST_OK;
+    }
+    inbound::deliver_boxed(&actor, name, "", None, msg);
+    POST_OK
+}
+
+/// Refuse posts to `actor_name` while its queue depth is at least
+/// `max_depth` (0 = unlimited). Returns 0, or POST_INVALID on a bad name.
+#[export_name = c_symbol!("rust_actor_set_post_limit")]
+pub extern "C" fn rust_actor_set_post_limit(actor_name: *const c_char, max_depth: usize) -> c_int {
+    let name = match c_str(actor_name) {
+        Some(s) => s,
+        None => return POST_INVALID,
+    };
+    LIMITS.lock().unwrap().insert(name.to_string(), max_depth);
+    if let Some(t) = TARGETS.lock().unwrap().get_mut(name) {
+        t.limit = max_depth;
+    }
+    POST_OK
+}
+
+/// Drop cached targets, e.g. after the Manager is recreated
+pub fn clear_cache() {
+    TARGETS.lock().unwrap().clear();
+}
@@ -0,0 +1,396 @@
+//! Latency probes: round trips to an echo actor, measured continuously
+//!
+//! A slow bridge otherwise shows up as fills arriving late. A probe sends
+//! a timestamped LatencyProbe to an echo actor at a fixed interval and
+//! keeps the round-trip times of the echoes, so the percentiles can be
+//! watched like any other metric:
+//!
+//! ```ignore
+//! let probe = Probe::start("cpp_echo", Duration::from_secs(1), Duration::from_millis(500)).unwrap();
+//! let stats = probe.stats();
+//! println!("p99 {} us, {} lost", stats.p99_ns / 1000, stats.lost);
+//! ```
+//!
+//! The echo actor answers every LatencyProbe with a LatencyEcho of the
+//! same fields. In C++ it is `interop::EchoActor` (`interop/echo_actor.hpp`),
+//! managed like any C++ actor; in Rust `register_echo()` starts one, so C++
+//! can probe the other way with `interop_probe_start()` (`interop/probe.h`).
+//!
+//! - The prober's reply mailbox is `$probe-<id>`. The echo is matched to
+//!   its probe by `seq`, and the round trip is timed on the monotonic
+//!   system clock, even under a simulated one (see clock).
+//! - Percentiles cover the last `WINDOW` echoes. A probe not echoed within
+//!   the timeout is lost; its echo arriving later is counted as late.
+//! - Probes run as a task (see tasks): not while the Manager is not
+//!   running, and not after rust_manager_end().
+//! - `rust_interop_health()` lists every probe under `probes` (see health).
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actors::{Actor, ActorContext, ActorRef, CppActorRef, Message, ThreadConfig};
+
+use crate::codec::JsonValue;
+use crate::health;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{LatencyEcho, LatencyProbe};
+use crate::names::c_str;
+use crate::rust_manager_ffi::{get_actor_ref, register_with};
+use crate::tasks::{self, TaskId};
+use crate::timestamp::Timestamp;
+
+/// Default name of the Rust echo actor
+pub const ECHO_ACTOR: &str = "rust_echo";
+
+/// Reply mailboxes of probes are this followed by the probe id
+pub const PROBE_PREFIX: &str = "$probe-";
+
+/// Echoes the percentiles are taken over
+pub const WINDOW: usize = 1024;
+
+/// Answers every LatencyProbe with a LatencyEcho of the same fields
+pub struct EchoActor;
+
+impl Actor for EchoActor {
+    fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+        if let Some(probe) = msg.as_any().downcast_ref::<LatencyProbe>() {
+            ctx.reply(Box::new(LatencyEcho { probe_id: probe.probe_id, seq: probe.seq, sent_at: probe.sent_at }));
+        }
+    }
+}
+
+/// Register an EchoActor as `name` with the Rust Manager. False if there
+/// is no Manager or the name is taken (see names).
+pub fn register_echo(name: &str) -> bool {
+    register_with(name, |_| Box::new(EchoActor), ThreadConfig::default())
+}
+
+/// Counts and round-trip percentiles of one probe; mirrors
+/// InteropProbeStats in interop/probe.h
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProbeStats {
+    /// Probes sent
+    pub sent: u64,
+    /// Echoes received in time
+    pub echoed: u64,
+    /// Probes not echoed within the timeout
+    pub lost: u64,
+    /// Echoes of probes already counted as lost
+    pub late: u64,
+    /// Ticks the target could not be looked up
+    pub unreachable: u64,
+    /// Round trips over the last WINDOW echoes, in nanoseconds; 0 before
+    /// the first echo
+    pub p50_ns: u64,
+    pub p90_ns: u64,
+    pub p99_ns: u64,
+    pub max_ns: u64,
+    /// The latest round trip
+    pub last_ns: u64,
+}
+
+#[derive(Default)]
+struct State {
+    seq: i64,
+    // Sent and not yet echoed nor lost, by seq
+    outstanding: HashMap<i64, Instant>,
+    // Round trips in ns, oldest first
+    window: VecDeque<u64>,
+    stats: ProbeStats,
+}
+
+struct Inner {
+    id: i64,
+    target: String,
+    mailbox: String,
+    timeout: Duration,
+    state: Mutex<State>,
+    task: Mutex<Option<TaskId>>,
+}
+
+/// A running probe of one echo actor. Clones share it.
+#[derive(Clone)]
+pub struct Probe {
+    inner: Arc<Inner>,
+}
+
+impl Probe {
+    /// Probe echo actor `target` (C++ or Rust) every `interval`, counting a
+    /// probe lost after `timeout`. None if the task cannot be started.
+    pub fn start(target: &str, interval: Duration, timeout: Duration) -> Option<Probe> {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let probe = Probe {
+            inner: Arc::new(Inner {
+                id,
+                target: target.to_string(),
+                mailbox: format!("{}{}", PROBE_PREFIX, id),
+                timeout,
+                state: Mutex::new(State::default()),
+                task: Mutex::new(None),
+            }),
+        };
+        PROBES.lock().unwrap().insert(id, probe.clone());
+        let ticking = probe.clone();
+        match tasks::spawn(interval, move || {
+            ticking.tick();
+            true
+        }) {
+            Some(task) => {
+                *probe.inner.task.lock().unwrap() = Some(task);
+                Some(probe)
+            }
+            None => {
+                PROBES.lock().unwrap().remove(&id);
+                None
+            }
+        }
+    }
+
+    pub fn id(&self) -> i64 {
+        self.inner.id
+    }
+
+    pub fn target(&self) -> &str {
+        &self.inner.target
+    }
+
+    /// Counts so far and percentiles over the last WINDOW echoes
+    pub fn stats(&self) -> ProbeStats {
+        let state = self.inner.state.lock().unwrap();
+        let mut stats = state.stats;
+        let mut sorted: Vec<u64> = state.window.iter().copied().collect();
+        sorted.sort_unstable();
+        stats.p50_ns = percentile(&sorted, 50);
+        stats.p90_ns = percentile(&sorted, 90);
+        stats.p99_ns = percentile(&sorted, 99);
+        stats.max_ns = sorted.last().copied().unwrap_or(0);
+        stats
+    }
+
+    /// Stop probing; echoes still on their way are then dead letters.
+    /// False if already stopped.
+    pub fn stop(&self) -> bool {
+        if PROBES.lock().unwrap().remove(&self.inner.id).is_none() {
+            return false;
+        }
+        if let Some(task) = self.inner.task.lock().unwrap().take() {
+            tasks::cancel(task);
+        }
+        true
+    }
+
+    /// Count the probes past the timeout as lost, then send the next one
+    pub fn tick(&self) {
+        let now = Instant::now();
+        let seq = {
+            let mut state = self.inner.state.lock().unwrap();
+            let timeout = self.inner.timeout;
+            let before = state.outstanding.len();
+            state.outstanding.retain(|_, sent| now.duration_since(*sent) < timeout);
+            state.stats.lost += (before - state.outstanding.len()) as u64;
+            state.seq += 1;
+            state.seq
+        };
+        let target = match get_actor_ref(&self.inner.target, "") {
+            Some(target) => target,
+            None => {
+                self.inner.state.lock().unwrap().stats.unreachable += 1;
+                return;
+            }
+        };
+        // As for an ask: the C++ side replies to the sender name the ref carries
+        let (target, reply_to) = match target {
+            ActorRef::Cpp(cpp) => {
+                (ActorRef::Cpp(CppActorRef::new(&cpp.target, &self.inner.mailbox, cpp.send_fn)), None)
+            }
+            other => (other, Some(ActorRef::Cpp(CppActorRef::new(&self.inner.mailbox, "", echo_send_fn)))),
+        };
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            state.outstanding.insert(seq, Instant::now());
+            state.stats.sent += 1;
+        }
+        target.send(Box::new(LatencyProbe { probe_id: self.inner.id, seq, sent_at: Timestamp::now() }), reply_to);
+    }
+
+    fn on_echo(&self, echo: &LatencyEcho) {
+        let mut state = self.inner.state.lock().unwrap();
+        match state.outstanding.remove(&echo.seq) {
+            Some(sent) => {
+                let rtt = sent.elapsed().as_nanos().min(u64::MAX as u128) as u64;
+                if state.window.len() == WINDOW {
+                    state.window.pop_front();
+                }
+                state.window.push_back(rtt);
+                state.stats.echoed += 1;
+                state.stats.last_ns = rtt;
+            }
+            None => state.stats.late += 1,
+        }
+    }
+}
+
+// Nearest rank of `sorted`
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+static NEXT_ID: AtomicI64 = AtomicI64::new(1);
+
+lazy_static::lazy_static! {
+    static ref PROBES: Mutex<HashMap<i64, Probe>> = Mutex::new(HashMap::new());
+}
+
+fn by_id(probe: i64) -> Option<Probe> {
+    PROBES.lock().unwrap().get(&probe).cloned()
+}
+
+fn echo_send_fn(target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    let probe = target.strip_prefix(PROBE_PREFIX).and_then(|id| id.parse().ok()).and_then(by_id);
+    match (probe, msg.as_any().downcast_ref::<LatencyEcho>()) {
+        (Some(probe), Some(echo)) => {
+            probe.on_echo(echo);
+            InteropErrorCode::Ok as i32
+        }
+        (Some(_), None) => InteropErrorCode::WrongType as i32,
+        (None, _) => {
+            health::record_dead_letter(sender, target, msg.message_id());
+            InteropErrorCode::NotFound as i32
+        }
+    }
+}
+
+/// The reply mailbox of a running probe. Called by get_actor_ref() and
+/// the bridge.
+pub fn probe_ref(name: &str) -> Option<ActorRef> {
+    let id: i64 = name.strip_prefix(PROBE_PREFIX)?.parse().ok()?;
+    PROBES.lock().unwrap().contains_key(&id).then(|| ActorRef::Cpp(CppActorRef::new(name, "", echo_send_fn)))
+}
+
+/// Every running probe, by id
+pub fn probes() -> Vec<Probe> {
+    let mut probes: Vec<Probe> = PROBES.lock().unwrap().values().cloned().collect();
+    probes.sort_by_key(|p| p.id());
+    probes
+}
+
+/// Stop every probe
+pub fn clear() {
+    for probe in probes() {
+        probe.stop();
+    }
+}
+
+/// Running probes as a JSON array, round trips in microseconds:
+/// `[{"id":1,"target":"cpp_echo","sent":60,"echoed":59,"lost":1,"late":0,
+/// "unreachable":0,"p50_us":18.2,"p90_us":25.0,"p99_us":61.7,"max_us":61.7}]`
+pub fn probes_json() -> String {
+    let mut out = String::from("[");
+    for (i, probe) in probes().iter().enumerate() {
+        let s = probe.stats();
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "{{\"id\":{},\"target\":", probe.id());
+        probe.target().write_json(&mut out);
+        let _ = write!(
+            out,
+            ",\"sent\":{},\"echoed\":{},\"lost\":{},\"late\":{},\"unreachable\":{},\
+             \"p50_us\":{:.1},\"p90_us\":{:.1},\"p99_us\":{:.1},\"max_us\":{:.1}}}",
+            s.sent,
+            s.echoed,
+            s.lost,
+            s.late,
+            s.unreachable,
+            s.p50_ns as f64 / 1000.0,
+            s.p90_ns as f64 / 1000.0,
+            s.p99_ns as f64 / 1000.0,
+            s.max_ns as f64 / 1000.0
+        );
+    }
+    out.push(']');
+    out
+}
+
+/// Register a Rust echo actor as `name` (NULL: "rust_echo"). Returns 0,
+/// InvalidArgument on a bad name, or NameTaken if there is no Manager or
+/// the name is taken.
+#[export_name = c_symbol!("interop_register_echo_actor")]
+pub extern "C" fn interop_register_echo_actor(name: *const c_char) -> c_int {
+    let name = if name.is_null() { Some(ECHO_ACTOR) } else { c_str(name) };
+    let rc = match name {
+        Some(name) if register_echo(name) => InteropErrorCode::Ok,
+        Some(_) => InteropErrorCode::NameTaken,
+        None => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Probe echo actor `target` every `interval_ms`, counting a probe lost
+/// after `timeout_ms`. Returns the probe id (> 0), InvalidArgument on a
+/// bad name or a zero interval, or QueueFull if the task cannot start.
+#[export_name = c_symbol!("interop_probe_start")]
+pub extern "C" fn interop_probe_start(target: *const c_char, interval_ms: u32, timeout_ms: u32) -> i64 {
+    match c_str(target) {
+        Some(target) if interval_ms > 0 => {
+            let interval = Duration::from_millis(interval_ms as u64);
+            match Probe::start(target, interval, Duration::from_millis(timeout_ms as u64)) {
+                Some(probe) => {
+                    eprintln!("[Probe] {} probing {} every {} ms", probe.id(), target, interval_ms);
+                    probe.id()
+                }
+                None => InteropErrorCode::QueueFull as i64,
+            }
+        }
+        _ => InteropErrorCode::InvalidArgument as i64,
+    }
+}
+
+/// Fill `out` with the counts and percentiles of `probe`. Returns 0,
+/// InvalidArgument on a null `out`, or NotFound on an unknown probe.
+#[export_name = c_symbol!("interop_probe_stats")]
+pub extern "C" fn interop_probe_stats(probe: i64, out: *mut ProbeStats) -> c_int {
+    if out.is_null() {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    match by_id(probe) {
+        Some(p) => {
+            unsafe { *out = p.stats() };
+            0
+        }
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Stop `probe`. Returns 0, or NotFound on an unknown probe.
+#[export_name = c_symbol!("interop_probe_stop")]
+pub extern "C" fn interop_probe_stop(probe: i64) -> c_int {
+    match by_id(probe) {
+        Some(p) if p.stop() => 0,
+        _ => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Write `probes_json()` into `out_json` (NUL-terminated, truncated to
+/// fit `len`). Returns the full JSON length like snprintf.
+#[export_name = c_symbol!("interop_probes")]
+pub extern "C" fn interop_probes(out_json: *mut c_char, len: usize) -> c_int {
+    let json = probes_json();
+    if !out_json.is_null() && len > 0 {
+        let n = json.len().min(len - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(json.as_ptr(), out_json as *mut u8, n);
+            *out_json.add(n) = 0;
+        }
+    }
+    json.len().min(c_int::MAX as usize) as c_int
+}
@@ -0,0 +1,463 @@
+//! Protocol Buffers wire format for interop messages
+//!
+//! Every generated message has `encode_protobuf()` / `decode_protobuf()`,
+//! and `codec::ProtobufCodec` carries that body on remote links. Messages
+//! defined in a `.proto` file use its field numbers and integer types, so
+//! peers generating from the same file with protoc read them directly;
+//! messages from `interop_messages.h` number their fields 1..n in order.
+//!
+//! Proto3 rules apply: zero scalars are omitted, unknown fields are
+//! skipped, fixed-size arrays are packed repeated fields and maps are
+//! repeated `{1: key, 2: value}` entries.
+
+use std::collections::HashMap;
+
+use crate::decimal::Decimal;
+use crate::interop_string::InteropString;
+use crate::timestamp::Timestamp;
+use crate::uuid::Uuid;
+
+/// Protobuf wire types used by interop fields
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireType {
+    Varint,
+    Fixed64,
+    Len,
+    Fixed32,
+}
+
+impl WireType {
+    fn bits(self) -> u64 {
+        match self {
+            WireType::Varint => 0,
+            WireType::Fixed64 => 1,
+            WireType::Len => 2,
+            WireType::Fixed32 => 5,
+        }
+    }
+
+    fn from_bits(bits: u64) -> Option<Self> {
+        match bits {
+            0 => Some(WireType::Varint),
+            1 => Some(WireType::Fixed64),
+            2 => Some(WireType::Len),
+            5 => Some(WireType::Fixed32),
+            _ => None,
+        }
+    }
+}
+
+/// Integer encoding from the field's .proto type: `int32`/`uint64` are
+/// `Varint`, `sint32` is `ZigZag`, `fixed32`/`sfixed64` are `Fixed`.
+/// Floats are always fixed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntEncoding {
+    Varint,
+    ZigZag,
+    Fixed,
+}
+
+pub fn put_varint(mut v: u64, out: &mut Vec<u8>) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+pub fn get_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut v = 0u64;
+    for shift in (0..70).step_by(7) {
+        let (&b, rest) = input.split_first()?;
+        *input = rest;
+        v |= ((b & 0x7F) as u64) << shift;
+        if b < 0x80 {
+            return Some(v);
+        }
+    }
+    None  // more than 10 bytes
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if input.len() < n {
+        return None;
+    }
+    let (head, rest) = input.split_at(n);
+    *input = rest;
+    Some(head)
+}
+
+pub fn put_key(tag: u32, wire: WireType, out: &mut Vec<u8>) {
+    put_varint(((tag as u64) << 3) | wire.bits(), out);
+}
+
+/// Field number and wire type of the next field
+pub fn read_key(input: &mut &[u8]) -> Option<(u32, WireType)> {
+    let key = get_varint(input)?;
+    let tag = u32::try_from(key >> 3).ok().filter(|&t| t != 0)?;
+    Some((tag, WireType::from_bits(key & 7)?))
+}
+
+fn put_len(bytes: &[u8], out: &mut Vec<u8>) {
+    put_varint(bytes.len() as u64, out);
+    out.extend_from_slice(bytes);
+}
+
+fn get_len<'a>(input: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = usize::try_from(get_varint(input)?).ok()?;
+    take(input, len)
+}
+
+/// Skip a field this message does not have
+pub fn skip(wire: WireType, input: &mut &[u8]) -> Option<()> {
+    match wire {
+        WireType::Varint => get_varint(input).map(|_| ()),
+        WireType::Fixed64 => take(input, 8).map(|_| ()),
+        WireType::Len => get_len(input).map(|_| ()),
+        WireType::Fixed32 => take(input, 4).map(|_| ()),
+    }
+}
+
+/// Numeric field value, the element type of arrays and map values
+pub trait ProtoScalar: Copy + Default {
+    fn wire(enc: IntEncoding) -> WireType;
+    fn is_zero(self) -> bool;
+    fn put_value(self, enc: IntEncoding, out: &mut Vec<u8>);
+    fn get_value(enc: IntEncoding, input: &mut &[u8]) -> Option<Self>;
+}
+
+macro_rules! proto_int {
+    ($t:ty, $u:ty, $fixed:ident) => {
+        impl ProtoScalar for $t {
+            fn wire(enc: IntEncoding) -> WireType {
+                if enc == IntEncoding::Fixed { WireType::$fixed } else { WireType::Varint }
+            }
+            fn is_zero(self) -> bool {
+                self == 0
+            }
+            fn put_value(self, enc: IntEncoding, out: &mut Vec<u8>) {
+                match enc {
+                    // Negative int32 is sign-extended to ten bytes, as protoc does
+                    IntEncoding::Varint => put_varint(self as i64 as u64, out),
+                    IntEncoding::ZigZag => put_varint(((self << 1) ^ (self >> (<$t>::BITS - 1))) as $u as u64, out),
+                    IntEncoding::Fixed => out.extend_from_slice(&self.to_le_bytes()),
+                }
+            }
+            fn get_value(enc: IntEncoding, input: &mut &[u8]) -> Option<Self> {
+                match enc {
+                    IntEncoding::Varint => Some(get_varint(input)? as $t),
+                    IntEncoding::ZigZag => {
+                        let v = get_varint(input)? as $u;
+                        Some(((v >> 1) as $t) ^ -((v & 1) as $t))
+                    }
+                    IntEncoding::Fixed => Some(<$t>::from_le_bytes(take(input, std::mem::size_of::<$t>())?.try_into().ok()?)),
+                }
+            }
+        }
+    };
+}
+
+proto_int!(i32, u32, Fixed32);
+proto_int!(i64, u64, Fixed64);
+
+macro_rules! proto_uint {
+    ($t:ty, $fixed:ident) => {
+        impl ProtoScalar for $t {
+            fn wire(enc: IntEncoding) -> WireType {
+                if enc == IntEncoding::Fixed { WireType::$fixed } else { WireType::Varint }
+            }
+            fn is_zero(self) -> bool {
+                self == 0
+            }
+            fn put_value(self, enc: IntEncoding, out: &mut Vec<u8>) {
+                match enc {
+                    IntEncoding::Fixed => out.extend_from_slice(&self.to_le_bytes()),
+                    _ => put_varint(self as u64, out),
+                }
+            }
+            fn get_value(enc: IntEncoding, input: &mut &[u8]) -> Option<Self> {
+                match enc {
+                    IntEncoding::Fixed => Some(<$t>::from_le_bytes(take(input, std::mem::size_of::<$t>())?.try_into().ok()?)),
+                    _ => Some(get_varint(input)? as $t),
+                }
+            }
+        }
+    };
+}
+
+proto_uint!(u32, Fixed32);
+proto_uint!(u64, Fixed64);
+
+macro_rules! proto_float {
+    ($t:ty, $fixed:ident) => {
+        impl ProtoScalar for $t {
+            fn wire(_: IntEncoding) -> WireType {
+                WireType::$fixed
+            }
+            fn is_zero(self) -> bool {
+                self.to_bits() == 0  // -0.0 is sent
+            }
+            fn put_value(self, _: IntEncoding, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+            fn get_value(_: IntEncoding, input: &mut &[u8]) -> Option<Self> {
+                Some(<$t>::from_le_bytes(take(input, std::mem::size_of::<$t>())?.try_into().ok()?))
+            }
+        }
+    };
+}
+
+proto_float!(f32, Fixed32);
+proto_float!(f64, Fixed64);
+
+impl ProtoScalar for bool {
+    fn wire(_: IntEncoding) -> WireType {
+        WireType::Varint
+    }
+    fn is_zero(self) -> bool {
+        !self
+    }
+    fn put_value(self, _: IntEncoding, out: &mut Vec<u8>) {
+        out.push(self as u8);
+    }
+    fn get_value(_: IntEncoding, input: &mut &[u8]) -> Option<Self> {
+        Some(get_varint(input)? != 0)
+    }
+}
+
+/// A message field, encoded with its field number
+pub trait ProtoField {
+    fn put_proto(&self, tag: u32, enc: IntEncoding, out: &mut Vec<u8>);
+    /// Read one occurrence of the field; repeated occurrences merge
+    fn merge_proto(&mut self, wire: WireType, enc: IntEncoding, input: &mut &[u8]) -> Option<()>;
+}
+
+macro_rules! proto_scalar_field {
+    ($($t:ty),*) => {$(
+        impl ProtoField for $t {
+            fn put_proto(&self, tag: u32, enc: IntEncoding, out: &mut Vec<u8>) {
+                if !self.is_zero() {
+                    put_key(tag, <$t>::wire(enc), out);
+                    self.put_value(enc, out);
+                }
+            }
+            fn merge_proto(&mut self, wire: WireType, enc: IntEncoding, input: &mut &[u8]) -> Option<()> {
+                if wire != <$t>::wire(enc) {
+                    return None;
+                }
+                *self = <$t>::get_value(enc, input)?;
+                Some(())
+            }
+        }
+    )*};
+}
+
+proto_scalar_field!(i32, i64, u32, u64, f32, f64, bool);
+
+fn put_str(tag: u32, s: &str, out: &mut Vec<u8>) {
+    if !s.is_empty() {
+        put_key(tag, WireType::Len, out);
+        put_len(s.as_bytes(), out);
+    }
+}
+
+impl ProtoField for String {
+    fn put_proto(&self, tag: u32, _: IntEncoding, out: &mut Vec<u8>) {
+        put_str(tag, self, out);
+    }
+    fn merge_proto(&mut self, wire: WireType, _: IntEncoding, input: &mut &[u8]) -> Option<()> {
+        if wire != WireType::Len {
+            return None;
+        }
+        *self = String::from_utf8(get_len(input)?.to_vec()).ok()?;
+        Some(())
+    }
+}
+
+impl<const N: usize> ProtoField for InteropString<N> {
+    fn put_proto(&self, tag: u32, _: IntEncoding, out: &mut Vec<u8>) {
+        put_str(tag, self.as_str(), out);
+    }
+    fn merge_proto(&mut self, wire: WireType, enc: IntEncoding, input: &mut &[u8]) -> Option<()> {
+        let mut s = String::new();
+        s.merge_proto(wire, enc, input)?;
+        *self = InteropString::from(s.as_str());
+        Some(())
+    }
+}
+
+impl ProtoField for Vec<u8> {
+    fn put_proto(&self, tag: u32, _: IntEncoding, out: &mut Vec<u8>) {
+        if !self.is_empty() {
+            put_key(tag, WireType::Len, out);
+            put_len(self, out);
+        }
+    }
+    fn merge_proto(&mut self, wire: WireType, _: IntEncoding, input: &mut &[u8]) -> Option<()> {
+        if wire != WireType::Len {
+            return None;
+        }
+        *self = get_len(input)?.to_vec();
+        Some(())
+    }
+}
+
+/// `interop.Decimal { sint64 units = 1; int32 scale = 2; }`
+impl ProtoField for Decimal {
+    fn put_proto(&self, tag: u32, _: IntEncoding, out: &mut Vec<u8>) {
+        if *self == Decimal::default() {
+            return;
+        }
+        let mut body = Vec::new();
+        self.units.put_proto(1, IntEncoding::ZigZag, &mut body);
+        self.scale.put_proto(2, IntEncoding::Varint, &mut body);
+        put_key(tag, WireType::Len, out);
+        put_len(&body, out);
+    }
+    fn merge_proto(&mut self, wire: WireType, _: IntEncoding, input: &mut &[u8]) -> Option<()> {
+        if wire != WireType::Len {
+            return None;
+        }
+        let mut body = get_len(input)?;
+        while !body.is_empty() {
+            match read_key(&mut body)? {
+                (1, w) => self.units.merge_proto(w, IntEncoding::ZigZag, &mut body)?,
+                (2, w) => self.scale.merge_proto(w, IntEncoding::Varint, &mut body)?,
+                (_, w) => skip(w, &mut body)?,
+            }
+        }
+        Some(())
+    }
+}
+
+/// `interop.Uuid { bytes value = 1; }`
+impl ProtoField for Uuid {
+    fn put_proto(&self, tag: u32, _: IntEncoding, out: &mut Vec<u8>) {
+        if self.is_nil() {
+            return;
+        }
+        let mut body = Vec::new();
+        put_key(1, WireType::Len, &mut body);
+        put_len(&self.bytes, &mut body);
+        put_key(tag, WireType::Len, out);
+        put_len(&body, out);
+    }
+    fn merge_proto(&mut self, wire: WireType, _: IntEncoding, input: &mut &[u8]) -> Option<()> {
+        if wire != WireType::Len {
+            return None;
+        }
+        let mut body = get_len(input)?;
+        while !body.is_empty() {
+            match read_key(&mut body)? {
+                (1, WireType::Len) => self.bytes = get_len(&mut body)?.try_into().ok()?,
+                (_, w) => skip(w, &mut body)?,
+            }
+        }
+        Some(())
+    }
+}
+
+/// `google.protobuf.Timestamp { int64 seconds = 1; int32 nanos = 2; }`
+impl ProtoField for Timestamp {
+    fn put_proto(&self, tag: u32, _: IntEncoding, out: &mut Vec<u8>) {
+        if *self == Timestamp::UNIX_EPOCH {
+            return;
+        }
+        let mut body = Vec::new();
+        self.as_secs().put_proto(1, IntEncoding::Varint, &mut body);
+        (self.nanos.rem_euclid(1_000_000_000) as i32).put_proto(2, IntEncoding::Varint, &mut body);
+        put_key(tag, WireType::Len, out);
+        put_len(&body, out);
+    }
+    fn merge_proto(&mut self, wire: WireType, _: IntEncoding, input: &mut &[u8]) -> Option<()> {
+        if wire != WireType::Len {
+            return None;
+        }
+        let mut body = get_len(input)?;
+        let (mut secs, mut nanos) = (self.as_secs(), self.nanos.rem_euclid(1_000_000_000) as i32);
+        while !body.is_empty() {
+            match read_key(&mut body)? {
+                (1, w) => secs.merge_proto(w, IntEncoding::Varint, &mut body)?,
+                (2, w) => nanos.merge_proto(w, IntEncoding::Varint, &mut body)?,
+                (_, w) => skip(w, &mut body)?,
+            }
+        }
+        let total = secs.checked_mul(1_000_000_000)?.checked_add(nanos as i64)?;
+        *self = Timestamp::from_nanos(total);
+        Some(())
+    }
+}
+
+impl<V: ProtoScalar> ProtoField for HashMap<String, V> {
+    fn put_proto(&self, tag: u32, enc: IntEncoding, out: &mut Vec<u8>) {
+        // Sorted so equal maps encode identically
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        let mut entry = Vec::new();
+        for (key, value) in entries {
+            entry.clear();
+            put_key(1, WireType::Len, &mut entry);
+            put_len(key.as_bytes(), &mut entry);
+            put_key(2, V::wire(enc), &mut entry);
+            value.put_value(enc, &mut entry);
+            put_key(tag, WireType::Len, out);
+            put_len(&entry, out);
+        }
+    }
+    fn merge_proto(&mut self, wire: WireType, enc: IntEncoding, input: &mut &[u8]) -> Option<()> {
+        if wire != WireType::Len {
+            return None;
+        }
+        let mut entry = get_len(input)?;
+        let (mut key, mut value) = (String::new(), V::default());
+        while !entry.is_empty() {
+            match read_key(&mut entry)? {
+                (1, w) => key.merge_proto(w, enc, &mut entry)?,
+                (2, w) if w == V::wire(enc) => value = V::get_value(enc, &mut entry)?,
+                (_, w) => skip(w, &mut entry)?,
+            }
+        }
+        self.insert(key, value);
+        Some(())
+    }
+}
+
+/// Write a fixed-size array as a packed repeated field
+pub fn put_packed<T: ProtoScalar>(tag: u32, enc: IntEncoding, values: &[T], out: &mut Vec<u8>) {
+    if values.is_empty() {
+        return;
+    }
+    let mut body = Vec::new();
+    for v in values {
+        v.put_value(enc, &mut body);
+    }
+    put_key(tag, WireType::Len, out);
+    put_len(&body, out);
+}
+
+/// Read a repeated field, packed or not, appending to `values`
+pub fn merge_repeated<T: ProtoScalar>(values: &mut Vec<T>, wire: WireType, enc: IntEncoding, input: &mut &[u8]) -> Option<()> {
+    if wire == WireType::Len {
+        let mut body = get_len(input)?;
+        while !body.is_empty() {
+            values.push(T::get_value(enc, &mut body)?);
+        }
+        Some(())
+    } else if wire == T::wire(enc) {
+        values.push(T::get_value(enc, input)?);
+        Some(())
+    } else {
+        None
+    }
+}
+
+/// Array from decoded elements; missing elements are zero, extra ones are
+/// an error
+pub fn to_array<T: ProtoScalar, const N: usize>(values: Vec<T>) -> Option<[T; N]> {
+    if values.len() > N {
+        return None;
+    }
+    let mut arr = [T::default(); N];
+    arr[..values.len()].copy_from_slice(&values);
+    Some(arr)
+}
@@ -0,0 +1,194 @@
+//! Per-topic delivery QoS
+//!
+//! `Subscribe.qos` selects how a publisher delivers a topic:
+//! - Best-effort: each update is sent once; a failed send is not repeated.
+//!   Under pressure updates may be conflated or dropped, by the
+//!   subscriber's overflow policy (below) or a ThrottledPublisher (see
+//!   throttle). Suited to market data where only the latest value matters.
+//! - Reliable: every update is kept in a bounded retransmit buffer until the
+//!   subscriber acknowledges it, and sent again if it did not get through.
+//!   When the buffer is full further updates wait in a backlog of the same
+//!   size; past that they are refused and the publisher is told; see
+//!   reliable.
+//!
+//! Independently of the mode, a Rust subscriber can add an overflow policy
+//! (`SUB_DROP_OLDEST`, `SUB_DROP_NEWEST`, `SUB_CONFLATE`, `SUB_BLOCK`) and
+//! a capacity (`sub_capacity(n)`) to the QoS with a bitwise or, to have its
+//! messages held in a bounded queue of its own; see sub_queue.
+
+use std::collections::VecDeque;
+
+/// Mirrors INTEROP_QOS_BEST_EFFORT in interop_messages.h
+pub const QOS_BEST_EFFORT: i32 = 0;
+/// Mirrors INTEROP_QOS_RELIABLE in interop_messages.h
+pub const QOS_RELIABLE: i32 = 1;
+/// Mirrors INTEROP_SUB_COMPACT in interop_messages.h; or'd into the QoS
+pub const SUB_COMPACT: i32 = 0x100;
+
+/// Mirror INTEROP_SUB_DROP_OLDEST etc. in interop_messages.h; at most one
+/// is or'd into the QoS
+pub const SUB_DROP_OLDEST: i32 = 0x1000;
+pub const SUB_DROP_NEWEST: i32 = 0x2000;
+pub const SUB_CONFLATE: i32 = 0x3000;
+pub const SUB_BLOCK: i32 = 0x4000;
+/// Bits of the QoS holding the overflow policy
+pub const SUB_OVERFLOW_MASK: i32 = 0xF000;
+/// Queue capacity when the QoS gives none
+pub const SUB_DEFAULT_CAPACITY: usize = 1024;
+
+// Bits of the QoS holding the delivery mode
+const QOS_MODE_MASK: i32 = 0xFF;
+const SUB_CAPACITY_SHIFT: u32 = 16;
+const SUB_CAPACITY_MAX: i32 = 0x7FFF;
+
+/// Queue capacity to or into the QoS, at most 32767; mirrors
+/// INTEROP_SUB_CAPACITY(n)
+pub const fn sub_capacity(n: u16) -> i32 {
+    ((n as i32) & SUB_CAPACITY_MAX) << SUB_CAPACITY_SHIFT
+}
+
+/// Queue capacity or'd into a QoS, `SUB_DEFAULT_CAPACITY` if none
+pub fn capacity_of(flag: i32) -> usize {
+    match (flag >> SUB_CAPACITY_SHIFT) & SUB_CAPACITY_MAX {
+        0 => SUB_DEFAULT_CAPACITY,
+        n => n as usize,
+    }
+}
+
+/// Delivery mode requested by a subscriber
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Qos {
+    #[default]
+    BestEffort,
+    Reliable,
+}
+
+impl Qos {
+    /// Decode the `qos` field of a Subscribe; unknown values are best-effort
+    pub fn from_flag(flag: i32) -> Self {
+        if flag & QOS_MODE_MASK == QOS_RELIABLE { Qos::Reliable } else { Qos::BestEffort }
+    }
+
+    pub fn to_flag(self) -> i32 {
+        match self {
+            Qos::BestEffort => QOS_BEST_EFFORT,
+            Qos::Reliable => QOS_RELIABLE,
+        }
+    }
+}
+
+/// What a subscriber's queue does when it is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued message to make room
+    DropOldest,
+    /// Drop the new message
+    DropNewest,
+    /// Replace the queued message with the same type and key, else drop
+    /// the oldest
+    Conflate,
+    /// Make the publisher wait for room
+    Block,
+}
+
+impl OverflowPolicy {
+    /// Decode the overflow policy of a Subscribe's `qos`; None if it has
+    /// none (or an unknown one) and messages go straight to the mailbox
+    pub fn from_flag(flag: i32) -> Option<Self> {
+        match flag & SUB_OVERFLOW_MASK {
+            SUB_DROP_OLDEST => Some(OverflowPolicy::DropOldest),
+            SUB_DROP_NEWEST => Some(OverflowPolicy::DropNewest),
+            SUB_CONFLATE => Some(OverflowPolicy::Conflate),
+            SUB_BLOCK => Some(OverflowPolicy::Block),
+            _ => None,
+        }
+    }
+
+    pub fn to_flag(self) -> i32 {
+        match self {
+            OverflowPolicy::DropOldest => SUB_DROP_OLDEST,
+            OverflowPolicy::DropNewest => SUB_DROP_NEWEST,
+            OverflowPolicy::Conflate => SUB_CONFLATE,
+            OverflowPolicy::Block => SUB_BLOCK,
+        }
+    }
+}
+
+/// Reliable delivery: bounded buffer of unacknowledged updates.
+///
+/// Sequence numbers start at 1, so a `TopicAck.seq` equal to the number of
+/// updates received acknowledges everything up to and including it.
+pub struct RetransmitBuffer<V> {
+    next_seq: i64,
+    pending: VecDeque<(i64, V)>,
+    capacity: usize,
+}
+
+impl<V> RetransmitBuffer<V> {
+    pub fn new(capacity: usize) -> Self {
+        RetransmitBuffer {
+            next_seq: 1,
+            pending: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record an update before sending it.
+    /// Returns the assigned sequence number, or gives the value back if the
+    /// buffer is full and the subscriber must catch up first.
+    pub fn push(&mut self, value: V) -> Result<i64, V> {
+        if self.pending.len() >= self.capacity {
+            return Err(value);
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.pending.push_back((seq, value));
+        Ok(seq)
+    }
+
+    /// Drop everything up to and including `seq`
+    pub fn ack(&mut self, seq: i64) {
+        while matches!(self.pending.front(), Some((s, _)) if *s <= seq) {
+            self.pending.pop_front();
+        }
+    }
+
+    /// Drop `seq` only, for a subscriber that acknowledges updates one by
+    /// one and may leave earlier ones to be sent again
+    pub fn ack_one(&mut self, seq: i64) {
+        if let Some(i) = self.pending.iter().position(|(s, _)| *s == seq) {
+            self.pending.remove(i);
+        }
+    }
+
+    /// Updates still awaiting acknowledgement, oldest first
+    pub fn unacked(&self) -> impl Iterator<Item = &(i64, V)> {
+        self.pending.iter()
+    }
+
+    /// Take every update still awaiting acknowledgement, oldest first
+    pub fn drain(&mut self) -> impl Iterator<Item = V> + '_ {
+        self.pending.drain(..).map(|(_, v)| v)
+    }
+
+    /// Sequence number of the last update pushed, 0 before the first
+    pub fn last_seq(&self) -> i64 {
+        self.next_seq - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.pending.len() >= self.capacity
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
@@ -0,0 +1,115 @@
+//! Quarantine for poison messages
+//!
+//! When a handler panics, the supervisor (`lifecycle::Supervised`) restarts
+//! the actor and delivers the same message again, up to
+//! `MAX_DELIVERY_ATTEMPTS` times. A message that panics on every attempt is
+//! moved here with the panic message, the error callback is called, and the
+//! actor goes on with the rest of its mailbox instead of crash-looping.
+//! It is also recorded as a dead letter at stage `panic` (see
+//! dead_letters). An actor's state dump (see panic_context) is kept with
+//! the message, added to the callback's text and to the dead letter.
+//!
+//! Up to `QUARANTINE_CAPACITY` messages are kept; older ones are dropped.
+//! `take()` hands them back, e.g. to replay them after a fix.
+
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use actors::Message;
+
+use crate::dead_letters;
+use crate::envelope;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::clone_message;
+use crate::names::c_str;
+
+/// Deliveries of one message, including the first, before it is quarantined
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+
+/// Quarantined messages kept across all actors
+pub const QUARANTINE_CAPACITY: usize = 1024;
+
+pub type ErrorCallback = extern "C" fn(actor_name: *const c_char, msg_type: c_int, error: *const c_char);
+
+pub struct QuarantinedMessage {
+    pub actor: String,
+    pub msg_id: i32,
+    /// Panic message of the last attempt
+    pub panic: String,
+    /// The actor's state dump after the last attempt, if it gives one
+    pub context: Option<String>,
+    /// Copy of the message; None if it is not an interop message
+    pub message: Option<Box<dyn Message>>,
+}
+
+static QUARANTINE: Mutex<VecDeque<QuarantinedMessage>> = Mutex::new(VecDeque::new());
+static ERROR_CALLBACK: Mutex<Option<ErrorCallback>> = Mutex::new(None);
+
+/// Set the callback for quarantined messages, or clear it with null
+#[export_name = c_symbol!("rust_register_error_callback")]
+pub extern "C" fn rust_register_error_callback(cb: Option<ErrorCallback>) {
+    *ERROR_CALLBACK.lock().unwrap() = cb;
+}
+
+/// Quarantine `msg` for `actor` and report it, with the actor's state
+/// dump `context` if any
+pub fn add(actor: &str, msg: &dyn Message, panic: &str, context: Option<&str>) {
+    eprintln!(
+        "[Quarantine] {}: message {} panicked {} times, quarantined: {}",
+        actor,
+        msg.message_id(),
+        MAX_DELIVERY_ATTEMPTS,
+        panic
+    );
+    {
+        let mut q = QUARANTINE.lock().unwrap();
+        if q.len() == QUARANTINE_CAPACITY {
+            q.pop_front();
+        }
+        q.push_back(QuarantinedMessage {
+            actor: actor.to_string(),
+            msg_id: msg.message_id(),
+            panic: panic.to_string(),
+            context: context.map(str::to_string),
+            message: clone_message(msg),
+        });
+    }
+    let sender = envelope::current().and_then(|e| e.sender).unwrap_or_default();
+    dead_letters::record_panic(&sender, actor, msg.message_id(), context);
+
+    let cb = *ERROR_CALLBACK.lock().unwrap();
+    if let Some(cb) = cb {
+        let name = CString::new(actor).unwrap_or_default();
+        let error = match context {
+            Some(context) => format!("{}; state: {}", panic, context),
+            None => panic.to_string(),
+        };
+        let error = CString::new(error.replace('\0', " ")).unwrap_or_default();
+        cb(name.as_ptr(), msg.message_id(), error.as_ptr());
+    }
+}
+
+/// Number of quarantined messages for `actor`
+pub fn count(actor: &str) -> usize {
+    QUARANTINE.lock().unwrap().iter().filter(|m| m.actor == actor).count()
+}
+
+/// Remove and return the quarantined messages for `actor`, oldest first
+pub fn take(actor: &str) -> Vec<QuarantinedMessage> {
+    let mut q = QUARANTINE.lock().unwrap();
+    let (taken, kept): (VecDeque<_>, VecDeque<_>) = q.drain(..).partition(|m| m.actor == actor);
+    *q = kept;
+    taken.into()
+}
+
+/// Number of quarantined messages for `actor_name`, or InvalidArgument on
+/// a bad name
+#[export_name = c_symbol!("rust_quarantine_count")]
+pub extern "C" fn rust_quarantine_count(actor_name: *const c_char) -> c_int {
+    match c_str(actor_name) {
+        Some(name) => count(name).min(c_int::MAX as usize) as c_int,
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
@@ -0,0 +1,142 @@
+//! Inbound queue depth for Rust actors
+//!
+//! Messages entering a Rust actor through the bridge are wrapped in a
+//! `Tracked` envelope. The envelope is transparent to handlers (`as_any()`
+//! returns the inner message) and decrements the actor's counter when it
+//! is dropped after handling. The counter is therefore an estimate of the
+//! mailbox depth for bridge traffic, cheap enough to poll on every publish.
+//! It also carries the message's deadline when the actor has a TTL (see ttl)
+//! and its metadata when the actor takes envelopes (see envelope), and its
+//! bytes are charged to the actor while it exists (see alloc_stats).
+//!
+//! C++ reads it with `rust_actor_queue_depth(name)` to throttle publishers.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use actors::Message;
+
+use crate::alloc_stats::{self, Charge};
+use crate::envelope;
+use crate::sys_topics;
+use crate::ttl;
+
+lazy_static::lazy_static! {
+    static ref DEPTHS: Mutex<HashMap<String, Arc<AtomicUsize>>> = Mutex::new(HashMap::new());
+}
+
+/// Live counter of `name`'s queue depth
+pub(crate) fn counter(name: &str) -> Arc<AtomicUsize> {
+    let mut depths = DEPTHS.lock().unwrap();
+    // Look up by &str first so the hot path does not allocate a key
+    if let Some(c) = depths.get(name) {
+        return c.clone();
+    }
+    let c = Arc::new(AtomicUsize::new(0));
+    depths.insert(name.to_string(), c.clone());
+    c
+}
+
+/// Message envelope that counts itself in the target's queue depth
+pub struct Tracked<M: Message> {
+    inner: M,
+    counter: Arc<AtomicUsize>,
+    deadline: Option<Instant>,
+    enveloped: bool,
+    charge: Charge,
+}
+
+impl<M: Message> Message for Tracked<M> {
+    fn as_any(&self) -> &dyn std::any::Any { self.inner.as_any() }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self.inner.as_any_mut() }
+    fn message_id(&self) -> i32 { self.inner.message_id() }
+}
+
+impl<M: Message> Drop for Tracked<M> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::Relaxed);
+        if self.deadline.is_some() {
+            ttl::unstamp(self.inner.as_any());
+        }
+        if self.enveloped {
+            envelope::unstamp(self.inner.as_any());
+        }
+    }
+}
+
+/// Wrap a message bound for `target` from `sender` ("" for none),
+/// counting it as pending
+pub fn track<M: Message>(target: &str, sender: &str, msg: M) -> Box<dyn Message> {
+    let counter = counter(target);
+    let depth = counter.fetch_add(1, Ordering::Relaxed) + 1;
+    sys_topics::queue_depth_rose(target, depth);
+    let deadline = ttl::deadline(target, msg.message_id());
+    let charge = alloc_stats::charge(target, std::mem::size_of::<Tracked<M>>() + alloc_stats::heap_bytes(&msg));
+    let mut tracked = Box::new(Tracked { inner: msg, counter, deadline: None, enveloped: false, charge });
+    // Stamped once boxed: deadline and envelope are kept by the message's address
+    tracked.deadline = deadline.filter(|d| ttl::stamp(tracked.inner.as_any(), *d));
+    tracked.enveloped = envelope::stamp(target, sender, &tracked.inner);
+    if tracked.enveloped {
+        tracked.charge.add(envelope::stamped_size(sender));
+    }
+    tracked
+}
+
+/// Message envelope for messages that are already boxed
+pub struct TrackedBox {
+    inner: Box<dyn Message>,
+    counter: Arc<AtomicUsize>,
+    deadline: Option<Instant>,
+    enveloped: bool,
+    // Given back to the actor's account on drop
+    _charge: Charge,
+}
+
+impl Message for TrackedBox {
+    fn as_any(&self) -> &dyn std::any::Any { self.inner.as_any() }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self.inner.as_any_mut() }
+    fn message_id(&self) -> i32 { self.inner.message_id() }
+}
+
+impl Drop for TrackedBox {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::Relaxed);
+        if self.deadline.is_some() {
+            ttl::unstamp(self.inner.as_any());
+        }
+        if self.enveloped {
+            envelope::unstamp(self.inner.as_any());
+        }
+    }
+}
+
+/// `track()` for a boxed message
+pub fn track_boxed(target: &str, sender: &str, msg: Box<dyn Message>) -> Box<dyn Message> {
+    let counter = counter(target);
+    let depth = counter.fetch_add(1, Ordering::Relaxed) + 1;
+    sys_topics::queue_depth_rose(target, depth);
+    let deadline = ttl::deadline(target, msg.message_id()).filter(|d| ttl::stamp(msg.as_any(), *d));
+    let enveloped = envelope::stamp(target, sender, msg.as_ref());
+    let mut bytes =
+        std::mem::size_of::<TrackedBox>() + std::mem::size_of_val(msg.as_any()) + alloc_stats::heap_bytes(msg.as_ref());
+    if enveloped {
+        bytes += envelope::stamped_size(sender);
+    }
+    Box::new(TrackedBox { inner: msg, counter, deadline, enveloped, _charge: alloc_stats::charge(target, bytes) })
+}
+
+/// Messages queued for every Rust actor but not yet handled
+pub fn total_pending() -> usize {
+    DEPTHS.lock().unwrap().values().map(|c| c.load(Ordering::Relaxed)).sum()
+}
+
+/// Estimated number of messages queued for `name` but not yet handled
+pub fn pending(name: &str) -> usize {
+    DEPTHS
+        .lock()
+        .unwrap()
+        .get(name)
+        .map_or(0, |c| c.load(Ordering::Relaxed))
+}
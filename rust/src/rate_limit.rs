@@ -0,0 +1,251 @@
+//! Token-bucket limits on sends to C++ actors
+//!
+//! A limit caps how fast one Rust actor may send across the bridge, so a
+//! misbehaving strategy cannot flood the C++ order gateway:
+//!
+//! - `set_actor_rate_limit("strategy", limit)`: all of the actor's sends
+//! - `set_ref_rate_limit(&gateway_ref, limit)`: sends through one
+//!   `ActorRef::Cpp` (its sender -> target pair)
+//! - `CppActorIF::with_rate_limit(limit)`: sends through one interface
+//!
+//! When several limits apply, a send needs a token from each. What happens
+//! without one is the limit's `OverLimit` policy:
+//!
+//! | Policy | Effect |
+//! |--------|--------|
+//! | `Drop` | Message discarded, send returns 0 |
+//! | `Delay` | Sending thread sleeps until a token is free, then sends |
+//! | `Error` | Message discarded, send returns `RATE_LIMITED` |
+//!
+//! `Delay` blocks the sending actor like a retry backoff does. C++ sets
+//! limits with `interop_set_rate_limit()` (`interop/rate_limit.h`).
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actors::ActorRef;
+
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+
+/// Send return code for a message rejected by an `Error` limit
+pub const RATE_LIMITED: i32 = InteropErrorCode::RateLimited as i32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum OverLimit {
+    Drop = 0,
+    Delay = 1,
+    Error = 2,
+}
+
+impl OverLimit {
+    pub fn from_i32(v: i32) -> Option<Self> {
+        match v {
+            0 => Some(OverLimit::Drop),
+            1 => Some(OverLimit::Delay),
+            2 => Some(OverLimit::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    /// Sustained messages per second
+    pub per_second: f64,
+    /// Messages that may be sent back to back after a quiet period
+    pub burst: u32,
+    pub policy: OverLimit,
+}
+
+impl RateLimit {
+    pub fn new(per_second: f64, burst: u32, policy: OverLimit) -> Self {
+        RateLimit { per_second, burst: burst.max(1), policy }
+    }
+
+    /// A rate must be positive and finite
+    pub fn is_valid(&self) -> bool {
+        self.per_second.is_finite() && self.per_second > 0.0
+    }
+}
+
+/// Tokens for one limit; starts full
+#[derive(Debug)]
+pub struct TokenBucket {
+    limit: RateLimit,
+    // Below zero while Delay senders are queued for future tokens
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(limit: RateLimit) -> Self {
+        TokenBucket { limit, tokens: limit.burst as f64, refilled: clock::now() }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.limit.per_second).min(self.limit.burst as f64);
+        self.refilled = now;
+    }
+
+    // Time until the next token is free
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / self.limit.per_second)
+        }
+    }
+}
+
+/// Outcome of `admit()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Admission {
+    Send,
+    Drop,
+    Reject,
+}
+
+// (sender, Some(target)) for one ActorRef, (sender, None) for the actor
+type Key = (String, Option<String>);
+
+lazy_static::lazy_static! {
+    static ref BUCKETS: Mutex<HashMap<Key, TokenBucket>> = Mutex::new(HashMap::new());
+}
+
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static REJECTED: AtomicU64 = AtomicU64::new(0);
+
+fn set(key: Key, limit: RateLimit) -> bool {
+    if !limit.is_valid() {
+        return false;
+    }
+    BUCKETS.lock().unwrap().insert(key, TokenBucket::new(limit));
+    true
+}
+
+/// Limit every send to C++ made by actor `sender`.
+/// Returns false for an invalid limit.
+pub fn set_actor_rate_limit(sender: &str, limit: RateLimit) -> bool {
+    set((sender.to_string(), None), limit)
+}
+
+/// Limit sends through `actor`, an `ActorRef::Cpp`. Returns false for an
+/// invalid limit or a local ref, whose sends never cross the bridge.
+pub fn set_ref_rate_limit(actor: &ActorRef, limit: RateLimit) -> bool {
+    match actor {
+        ActorRef::Cpp(cpp) => set((cpp.sender.clone(), Some(cpp.target.clone())), limit),
+        _ => false,
+    }
+}
+
+/// Remove the limit of `sender` (target None) or of one sender -> target pair
+pub fn clear_rate_limit(sender: &str, target: Option<&str>) {
+    BUCKETS.lock().unwrap().remove(&(sender.to_string(), target.map(str::to_string)));
+}
+
+/// Messages discarded by `Drop` limits
+pub fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Messages refused by `Error` limits
+pub fn rejected() -> u64 {
+    REJECTED.load(Ordering::Relaxed)
+}
+
+/// Take a token for a send from `sender` to `target` from every limit that
+/// applies, including `own` (a CppActorIF's). Sleeps first under `Delay`.
+pub fn admit(sender: &str, target: &str, own: Option<&Mutex<TokenBucket>>) -> Admission {
+    let mut own = own.map(|b| b.lock().unwrap());
+    let mut buckets = BUCKETS.lock().unwrap();
+    if own.is_none() && buckets.is_empty() {
+        return Admission::Send;
+    }
+
+    let now = clock::now();
+    let mut applicable: Vec<&mut TokenBucket> = buckets
+        .iter_mut()
+        .filter(|((s, t), _)| s == sender && t.as_deref().is_none_or(|t| t == target))
+        .map(|(_, bucket)| bucket)
+        .collect();
+    if let Some(b) = own.as_deref_mut() {
+        applicable.push(b);
+    }
+
+    // Check every limit before taking from any
+    let mut wait = Duration::ZERO;
+    for bucket in applicable.iter_mut() {
+        bucket.refill(now);
+        let w = bucket.wait();
+        if w.is_zero() {
+            continue;
+        }
+        match bucket.limit.policy {
+            OverLimit::Drop => {
+                DROPPED.fetch_add(1, Ordering::Relaxed);
+                return Admission::Drop;
+            }
+            OverLimit::Error => {
+                REJECTED.fetch_add(1, Ordering::Relaxed);
+                return Admission::Reject;
+            }
+            OverLimit::Delay => wait = wait.max(w),
+        }
+    }
+    for bucket in applicable {
+        bucket.tokens -= 1.0;
+    }
+    drop(buckets);
+    drop(own);
+
+    if !wait.is_zero() {
+        clock::sleep(wait);
+    }
+    Admission::Send
+}
+
+/// Limit sends to C++ from Rust actor `sender`, to `target` only or to
+/// every target if `target` is null. `policy` is INTEROP_RATE_*.
+/// Returns 0, or InvalidArgument on a bad name, rate or policy.
+#[export_name = c_symbol!("interop_set_rate_limit")]
+pub extern "C" fn interop_set_rate_limit(
+    sender: *const c_char,
+    target: *const c_char,
+    per_second: f64,
+    burst: u32,
+    policy: c_int,
+) -> c_int {
+    let (sender, policy) = match (c_str(sender), OverLimit::from_i32(policy)) {
+        (Some(s), Some(p)) => (s, p),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    if !target.is_null() && c_str(target).is_none() {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    let key = (sender.to_string(), c_str(target).map(str::to_string));
+    let rc = if set(key, RateLimit::new(per_second, burst, policy)) {
+        InteropErrorCode::Ok
+    } else {
+        InteropErrorCode::InvalidArgument
+    };
+    rc as c_int
+}
+
+/// Remove a limit set with interop_set_rate_limit() (same sender/target)
+#[export_name = c_symbol!("interop_clear_rate_limit")]
+pub extern "C" fn interop_clear_rate_limit(sender: *const c_char, target: *const c_char) {
+    if let Some(sender) = c_str(sender) {
+        clear_rate_limit(sender, c_str(target));
+    }
+}
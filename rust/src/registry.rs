@@ -0,0 +1,60 @@
+//! Actor types that C++ can create by type name
+//!
+//! Annotate an actor with `#[interop_actor("default_name")]` and it is
+//! collected at link time; C++ then registers instances with
+//! `register_actor_by_type_name("RustPongActor", "rust_pong")` instead of a
+//! hand-written `register_*` function in rust_manager_ffi. The actor must
+//! have `fn new(ManagerHandle) -> Self`:
+//!
+//! ```ignore
+//! #[interop_actor("rust_pong")]
+//! pub struct RustPongActor { ... }
+//! ```
+//!
+//! `#[interop_actor("rust_price_monitor", snapshot)]` registers an actor
+//! that also implements `snapshot::Snapshot`, so its state survives
+//! restarts.
+//!
+//! `#[interop_actor("rust_ping", requires = ["cpp_pong"])]` holds the
+//! actor's Start until `cpp_pong` is registered in either runtime, so a
+//! Start handler can send to it (see `dependencies::require_for_start`).
+//!
+//! `#[interop_actor("rust_strategy", deadline_us(MarketUpdate = 500))]`
+//! reports each instance handling a MarketUpdate in more than 500µs (see
+//! deadlines). Options may be given in any order.
+//!
+//! Actors that need arguments (e.g. `CppHostedActor`, the MQTT bridge) keep
+//! their own register functions.
+
+use actors::ManagerHandle;
+
+use crate::snapshot::Instance;
+
+pub use actors_interop_macros::interop_actor;
+// Used by the code #[interop_actor] expands to
+pub use inventory;
+
+/// One actor type, submitted by #[interop_actor]
+pub struct ActorType {
+    /// Rust type name, e.g. "RustPongActor"
+    pub type_name: &'static str,
+    /// Instance name used when C++ does not give one
+    pub default_name: &'static str,
+    pub build: fn(ManagerHandle) -> Instance,
+    /// Actors each instance's Start waits for
+    pub requires: &'static [&'static str],
+    /// (message ID, µs) handler deadlines of each instance
+    pub deadlines_us: &'static [(i32, u64)],
+}
+
+inventory::collect!(ActorType);
+
+/// Look up an actor type by its Rust type name
+pub fn find(type_name: &str) -> Option<&'static ActorType> {
+    inventory::iter::<ActorType>.into_iter().find(|t| t.type_name == type_name)
+}
+
+/// Names of every registrable actor type
+pub fn type_names() -> Vec<&'static str> {
+    inventory::iter::<ActorType>.into_iter().map(|t| t.type_name).collect()
+}
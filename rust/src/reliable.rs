@@ -0,0 +1,509 @@
+//! Acknowledged delivery for reliable subscriptions
+//!
+//! A subscriber whose Subscribe asks for `QOS_RELIABLE` (see qos) is sent
+//! to through a stream per (publisher, topic, subscriber). Each update
+//! published to it gets the next sequence number, from 1, and is kept in a
+//! `RetransmitBuffer` until the subscriber acknowledges it:
+//!
+//! - A Rust subscriber acknowledges an update by handling it. The broker
+//!   sends it in an envelope that is transparent to handlers; the
+//!   supervisor (see lifecycle) marks it handled once the handler has
+//!   returned normally, and it acks when dropped. An update dropped without
+//!   that, e.g. discarded with the mailbox when the Manager ends or expired
+//!   (see ttl), is sent again, possibly after later ones. One kept by the
+//!   subscriber's runtime instead of handled, frozen (see freeze) or
+//!   quarantined after panicking, stays unacknowledged and is sent again
+//!   when the subscriber subscribes again. An actor outside supervision
+//!   acknowledges by dropping the update. Backlog an ack makes room for
+//!   goes out with the next publish, or with the retry task below.
+//! - A C++ subscriber sends `TopicAck { topic, seq }` to the publisher,
+//!   `seq` being the number of updates it has received on the topic since
+//!   it subscribed. The bridge hands it to the stream on the way (see
+//!   `broker::observe`); the publisher gets it as well.
+//!
+//! ```cpp
+//! void on_update(const msg::MarketUpdate* m) noexcept {
+//!     ...
+//!     auto* ack = new msg::TopicAck();
+//!     std::copy(sym, sym + 4, ack->topic.begin());   // "AAPL"
+//!     ack->seq = ++received_;
+//!     publisher_ref_.send(ack, this);
+//! }
+//! ```
+//!
+//! Updates are sent in sequence order. A send that fails stops the stream
+//! there, and that update and the ones after it are sent again on the next
+//! publish or ack, or by a task every `RETRY_INTERVAL` while the Manager
+//! runs. A subscriber that subscribes again (e.g. after a restart) starts
+//! over: what it had not acknowledged is sent again, numbered from 1.
+//!
+//! The buffer holds `capacity_of(qos)` updates (see qos). With it full
+//! further updates wait in the stream's backlog until acks make room, and
+//! are not counted by `broker::publish` as sent. The backlog holds as many
+//! updates as the buffer; with both full an update is refused: it is not
+//! kept, `refused()` counts it, and the publisher is sent an
+//! `InteropError` with `ERR_BACKLOG_FULL` (once until the backlog has room
+//! again, see interop_error), so it can e.g. send a snapshot later. The
+//! subscriber's overflow policy (see sub_queue) does not apply, and
+//! reliable subscribers are served on the publisher thread with fan-out
+//! workers or fairness too.
+//!
+//! Only interop messages are kept, since only those can be copied for a
+//! resend (`clone_message`); anything else is sent once, as to a
+//! best-effort subscriber.
+
+use std::cell::Cell;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actors::{ActorRef, Message};
+
+use crate::interop_error::{self, ERR_BACKLOG_FULL};
+use crate::interop_messages::{clone_message, message_name};
+use crate::qos::{self, RetransmitBuffer};
+use crate::rust_manager_ffi::{self, get_actor_ref};
+use crate::send_error::{SendError, TrySend};
+use crate::tasks;
+
+/// How often streams stopped by a failed send are sent again
+pub const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+// (publisher, topic, subscriber)
+type Key = (String, String, String);
+
+struct Stream {
+    key: Key,
+    buffer: RetransmitBuffer<Box<dyn Message>>,
+    // Of the buffer and of the backlog
+    capacity: usize,
+    // Everything up to this sequence number has been sent
+    sent: i64,
+    // Sent, then dropped by the subscriber unhandled: to send again
+    nacked: BTreeSet<i64>,
+    // Updates waiting for room in the buffer
+    backlog: VecDeque<Box<dyn Message>>,
+    // Updates refused with the backlog full
+    refused: u64,
+    // The publisher was told the backlog is full; reset once it has room
+    overflowing: bool,
+    // Bumped when the subscriber starts over, so acks for the old
+    // numbering are ignored
+    epoch: u64,
+    // A thread is sending from the stream
+    flushing: bool,
+    // The last send failed (logged once until one succeeds)
+    stalled: bool,
+}
+
+impl Stream {
+    fn new(key: Key, capacity: usize) -> Self {
+        Stream {
+            key,
+            buffer: RetransmitBuffer::new(capacity),
+            capacity,
+            sent: 0,
+            nacked: BTreeSet::new(),
+            backlog: VecDeque::new(),
+            refused: 0,
+            overflowing: false,
+            epoch: 0,
+            flushing: false,
+            stalled: false,
+        }
+    }
+
+    /// Move backlog into the buffer while it has room
+    fn refill(&mut self) {
+        while !self.buffer.is_full() {
+            match self.backlog.pop_front() {
+                Some(msg) => {
+                    let _ = self.buffer.push(msg);
+                }
+                None => break,
+            }
+        }
+        if self.backlog.len() < self.capacity {
+            self.overflowing = false;
+        }
+    }
+
+    /// Whether an update could be sent and has not been
+    fn has_unsent(&self) -> bool {
+        !self.nacked.is_empty()
+            || self.buffer.last_seq() > self.sent
+            || (!self.backlog.is_empty() && !self.buffer.is_full())
+    }
+
+    /// Number again from 1 and resend everything unacknowledged
+    fn restart(&mut self, capacity: usize) {
+        let mut unacked: VecDeque<Box<dyn Message>> = self.buffer.drain().collect();
+        unacked.append(&mut self.backlog);
+        self.buffer = RetransmitBuffer::new(capacity);
+        self.capacity = self.buffer.capacity();
+        self.backlog = unacked;
+        self.sent = 0;
+        self.nacked.clear();
+        self.epoch += 1;
+    }
+}
+
+/// What became of an update sent to a Rust subscriber
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Outcome {
+    /// Queued, or being handled
+    Pending,
+    /// The handler returned normally
+    Handled,
+    /// Kept by the subscriber's runtime instead of handled
+    Held,
+}
+
+lazy_static::lazy_static! {
+    static ref STREAMS: Mutex<HashMap<Key, Arc<Mutex<Stream>>>> = Mutex::new(HashMap::new());
+    // Outcome of each update in an envelope, by the address of the update
+    // (what handlers see), like a TTL deadline (see ttl)
+    static ref OUTCOMES: Mutex<HashMap<usize, Outcome>> = Mutex::new(HashMap::new());
+}
+
+// Entries in OUTCOMES, so handlers of other messages skip the lock
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+// A retry task is running
+static RETRYING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Set while this thread hands an update to a Rust mailbox: an envelope
+    // dropped meanwhile was refused, not handled
+    static SENDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Update for a Rust subscriber that acknowledges itself once dropped
+/// after being handled
+struct Acked {
+    inner: Box<dyn Message>,
+    stream: Arc<Mutex<Stream>>,
+    epoch: u64,
+    seq: i64,
+    // Key in OUTCOMES; None for a zero-sized update, acked when dropped
+    outcome: Option<usize>,
+}
+
+impl Acked {
+    fn new(inner: Box<dyn Message>, stream: &Arc<Mutex<Stream>>, epoch: u64, seq: i64) -> Self {
+        let outcome = address(inner.as_ref());
+        if let Some(addr) = outcome {
+            OUTCOMES.lock().unwrap().insert(addr, Outcome::Pending);
+            IN_FLIGHT.fetch_add(1, Ordering::Release);
+        }
+        Acked { inner, stream: stream.clone(), epoch, seq, outcome }
+    }
+
+    fn take_outcome(&self) -> Outcome {
+        let addr = match self.outcome {
+            Some(addr) => addr,
+            None => return Outcome::Handled,
+        };
+        let outcome = OUTCOMES.lock().unwrap().remove(&addr);
+        IN_FLIGHT.fetch_sub(1, Ordering::Release);
+        outcome.unwrap_or(Outcome::Handled)
+    }
+}
+
+impl Message for Acked {
+    fn as_any(&self) -> &dyn std::any::Any { self.inner.as_any() }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self.inner.as_any_mut() }
+    fn message_id(&self) -> i32 { self.inner.message_id() }
+}
+
+// Dropped on the subscriber's thread, possibly while the Manager tears it
+// down, so what the ack made room for, or the nack, is left to the retry
+// task
+impl Drop for Acked {
+    fn drop(&mut self) {
+        let mut outcome = self.take_outcome();
+        if SENDING.with(|s| s.get()) {
+            return;  // refused: flush() sends it again
+        }
+        if outcome == Outcome::Pending {
+            let subscriber = self.stream.lock().unwrap().key.2.clone();
+            if !rust_manager_ffi::is_supervised(&subscriber) {
+                outcome = Outcome::Handled;  // no one marks it handled
+            }
+        }
+        let mut stream = self.stream.lock().unwrap();
+        if stream.epoch != self.epoch {
+            return;
+        }
+        match outcome {
+            Outcome::Handled => stream.buffer.ack_one(self.seq),
+            Outcome::Pending => {
+                stream.nacked.insert(self.seq);
+            }
+            Outcome::Held => return,
+        }
+        if stream.has_unsent() {
+            drop(stream);
+            retry_later();
+        }
+    }
+}
+
+// Zero-sized messages share one address and can't be told apart
+fn address(msg: &dyn Message) -> Option<usize> {
+    let any = msg.as_any();
+    (std::mem::size_of_val(any) != 0).then_some(any as *const dyn std::any::Any as *const () as usize)
+}
+
+fn set_outcome(msg: &dyn Message, outcome: Outcome) {
+    if IN_FLIGHT.load(Ordering::Acquire) == 0 {
+        return;
+    }
+    if let Some(addr) = address(msg) {
+        if let Some(o) = OUTCOMES.lock().unwrap().get_mut(&addr) {
+            *o = outcome;
+        }
+    }
+}
+
+/// The handler of a supervised actor returned normally for `msg`: if it
+/// is a reliable update, it is acknowledged once dropped (see lifecycle)
+pub(crate) fn handled(msg: &dyn Message) {
+    set_outcome(msg, Outcome::Handled);
+}
+
+/// A supervised actor kept `msg` instead of handling it (frozen or
+/// quarantined): if it is a reliable update, it stays unacknowledged
+/// without being sent again until the subscriber subscribes again
+pub(crate) fn held(msg: &dyn Message) {
+    set_outcome(msg, Outcome::Held);
+}
+
+fn key(publisher: &str, topic: &str, subscriber: &str) -> Key {
+    (publisher.to_string(), topic.to_string(), subscriber.to_string())
+}
+
+fn stream(publisher: &str, topic: &str, subscriber: &str) -> Option<Arc<Mutex<Stream>>> {
+    STREAMS.lock().unwrap().get(&key(publisher, topic, subscriber)).cloned()
+}
+
+/// Send `msg` published on `topic` to reliable subscriber `subscriber` with
+/// `qos`. Returns false if it is waiting for room in the buffer, was
+/// refused with the backlog full, or was not an interop message and could
+/// not be sent.
+pub(crate) fn offer(publisher: &str, topic: &str, subscriber: &str, qos: i32, msg: Box<dyn Message>) -> bool {
+    if message_name(msg.message_id()).is_none() {
+        return send_once(publisher, topic, subscriber, msg);
+    }
+    let stream = STREAMS
+        .lock()
+        .unwrap()
+        .entry(key(publisher, topic, subscriber))
+        .or_insert_with(|| Arc::new(Mutex::new(Stream::new(key(publisher, topic, subscriber), qos::capacity_of(qos)))))
+        .clone();
+    let msg_id = msg.message_id();
+    let (buffered, tell) = {
+        let mut s = stream.lock().unwrap();
+        s.refill();
+        if s.backlog.len() >= s.capacity {
+            s.refused += 1;
+            (false, !std::mem::replace(&mut s.overflowing, true))
+        } else {
+            s.backlog.push_back(msg);
+            s.refill();
+            (s.backlog.is_empty(), false)
+        }
+    };
+    if tell {
+        let text = format!("reliable backlog of {} full on {}", subscriber, topic);
+        eprintln!("[Reliable] {} -> {}: backlog full, refusing updates on {}", publisher, subscriber, topic);
+        interop_error::notify(publisher, subscriber, ERR_BACKLOG_FULL, msg_id, &text);
+    }
+    flush(&stream);
+    buffered
+}
+
+fn send_once(publisher: &str, topic: &str, subscriber: &str, msg: Box<dyn Message>) -> bool {
+    let result =
+        get_actor_ref(subscriber, publisher).ok_or(SendError::ActorNotFound).and_then(|r| r.try_send(msg, None));
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("[Reliable] {} -> {} on {}: {}", publisher, subscriber, topic, e);
+            false
+        }
+    }
+}
+
+/// Send what the stream has not sent yet, in order, until a send fails
+fn flush(stream: &Arc<Mutex<Stream>>) {
+    let key = {
+        let mut s = stream.lock().unwrap();
+        if s.flushing {
+            return;  // the thread sending will see what was added
+        }
+        s.flushing = true;
+        s.key.clone()
+    };
+    let (publisher, topic, subscriber) = &key;
+    loop {
+        let (seq, epoch, copy, resend) = {
+            let mut s = stream.lock().unwrap();
+            s.refill();
+            // Nacked updates first, then the next not sent yet
+            let resend = s.nacked.pop_first();
+            let sent = s.sent;
+            let next = s
+                .buffer
+                .unacked()
+                .find(|(seq, _)| resend.map_or(*seq > sent, |r| *seq == r))
+                .map(|(seq, msg)| (*seq, clone_message(msg.as_ref())));
+            match next {
+                Some((seq, copy)) => (seq, s.epoch, copy, resend.is_some()),
+                None if resend.is_some() => continue,  // acknowledged meanwhile
+                None => {
+                    s.flushing = false;
+                    s.stalled = false;
+                    return;
+                }
+            }
+        };
+        let result = match copy {
+            Some(copy) => send(publisher, subscriber, stream, epoch, seq, copy),
+            None => Ok(()),  // not the type its ID says; skipped
+        };
+        let mut s = stream.lock().unwrap();
+        match result {
+            Ok(()) => {
+                if s.epoch == epoch && !resend {
+                    s.sent = s.sent.max(seq);
+                }
+                s.stalled = false;
+            }
+            Err(e) => {
+                if s.epoch == epoch && resend {
+                    s.nacked.insert(seq);
+                }
+                if !s.stalled {
+                    eprintln!("[Reliable] {} -> {} on {}: {}, will resend", publisher, subscriber, topic, e);
+                }
+                s.stalled = true;
+                s.flushing = false;
+                drop(s);
+                retry_later();
+                return;
+            }
+        }
+    }
+}
+
+fn send(
+    publisher: &str,
+    subscriber: &str,
+    stream: &Arc<Mutex<Stream>>,
+    epoch: u64,
+    seq: i64,
+    msg: Box<dyn Message>,
+) -> Result<(), SendError> {
+    let sub_ref = get_actor_ref(subscriber, publisher).ok_or(SendError::ActorNotFound)?;
+    if let ActorRef::Cpp(_) = sub_ref {
+        return sub_ref.try_send(msg, None);
+    }
+    let acked = Box::new(Acked::new(msg, stream, epoch, seq));
+    SENDING.with(|s| s.set(true));
+    let result = sub_ref.try_send(acked, None);
+    SENDING.with(|s| s.set(false));
+    result
+}
+
+// Clears RETRYING when the retry task ends, however it ends
+struct RetryGuard;
+
+impl Drop for RetryGuard {
+    fn drop(&mut self) {
+        RETRYING.store(false, Ordering::Release);
+    }
+}
+
+fn retry_later() {
+    if RETRYING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let guard = RetryGuard;
+    // If the task cannot start, the closure and guard are dropped with it
+    tasks::spawn(RETRY_INTERVAL, move || {
+        let _running = &guard;
+        retransmit()
+    });
+}
+
+/// Send what every stream can send and has not, e.g. after a failed send.
+/// Returns whether a stream is left with something unsent.
+pub fn retransmit() -> bool {
+    let streams: Vec<Arc<Mutex<Stream>>> = STREAMS.lock().unwrap().values().cloned().collect();
+    let mut unsent = false;
+    for stream in streams {
+        if stream.lock().unwrap().has_unsent() {
+            flush(&stream);
+            unsent |= stream.lock().unwrap().has_unsent();
+        }
+    }
+    unsent
+}
+
+/// Acknowledge updates up to and including `seq` of `subscriber`'s stream,
+/// from a TopicAck it sent (see broker::observe)
+pub(crate) fn ack(publisher: &str, topic: &str, subscriber: &str, seq: i64) {
+    if let Some(stream) = stream(publisher, topic, subscriber) {
+        stream.lock().unwrap().buffer.ack(seq);
+        flush(&stream);
+    }
+}
+
+/// `subscriber` subscribed (again) to `topic` of `publisher` with `qos`
+/// (see broker::add): a reliable stream it already has starts over, and
+/// is dropped if it is no longer reliable
+pub(crate) fn subscribed(publisher: &str, topic: &str, subscriber: &str, qos: i32) {
+    let key = key(publisher, topic, subscriber);
+    let stream = {
+        let mut streams = STREAMS.lock().unwrap();
+        if qos::Qos::from_flag(qos) != qos::Qos::Reliable {
+            streams.remove(&key);
+            return;
+        }
+        match streams.get(&key) {
+            Some(s) => s.clone(),
+            None => return,  // made on the first publish
+        }
+    };
+    stream.lock().unwrap().restart(qos::capacity_of(qos));
+    flush(&stream);
+}
+
+/// Drop `subscriber`'s stream for `topic` of `publisher`, unacknowledged
+/// updates and backlog included (see broker::remove)
+pub(crate) fn forget(publisher: &str, topic: &str, subscriber: &str) {
+    STREAMS.lock().unwrap().remove(&key(publisher, topic, subscriber));
+}
+
+/// Drop every stream (see broker::clear)
+pub(crate) fn clear() {
+    STREAMS.lock().unwrap().clear();
+}
+
+/// Updates sent to `subscriber` on `topic` of `publisher` and not yet
+/// acknowledged, or not yet sent again after a failure
+pub fn unacked(publisher: &str, topic: &str, subscriber: &str) -> usize {
+    stream(publisher, topic, subscriber).map_or(0, |s| s.lock().unwrap().buffer.len())
+}
+
+/// Updates waiting for room in the stream's buffer
+pub fn backlog(publisher: &str, topic: &str, subscriber: &str) -> usize {
+    stream(publisher, topic, subscriber).map_or(0, |s| s.lock().unwrap().backlog.len())
+}
+
+/// Updates refused because the stream's buffer and backlog were full
+pub fn refused(publisher: &str, topic: &str, subscriber: &str) -> u64 {
+    stream(publisher, topic, subscriber).map_or(0, |s| s.lock().unwrap().refused)
+}
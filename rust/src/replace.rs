@@ -0,0 +1,210 @@
+//! Replace a running Rust actor without stopping its traffic (blue/green)
+//!
+//! Upgrading a Rust strategy used to mean `rust_manager_restart()`, which
+//! stops every Rust actor while the C++ feed keeps sending. `replace_actor`
+//! swaps one actor's instance under its name instead:
+//!
+//! ```ignore
+//! replace::replace_actor("rust_strategy", |handle| Instance::stateful(StrategyV2::new(handle)))?;
+//! ```
+//!
+//! ```c
+//! interop_replace_actor("rust_strategy", "StrategyV2");   // an #[interop_actor] type
+//! ```
+//!
+//! 1. The replacement is built on the caller's thread while the old
+//!    instance keeps handling messages.
+//! 2. A swap is queued in the actor's mailbox behind what is already
+//!    there, so the old instance drains everything sent before it.
+//! 3. On the actor's thread, the old instance is snapshotted and the state
+//!    restored into the replacement (see snapshot), which then handles
+//!    Start and takes the old one's place. Messages after the swap go to
+//!    the replacement; none are lost or handled twice.
+//! 4. The name's lookup caches are dropped and Restarted is reported (see
+//!    lifecycle), so subscribers of a publisher subscribe again. Later
+//!    supervision restarts and `rust_manager_restart()` build the new
+//!    version.
+//!
+//! The old instance is dropped without End: its name, subscriptions and
+//! mailbox now belong to the replacement. If the replacement panics while
+//! being built, restored or started, or the swap is not reached within
+//! the timeout (default `SWAP_TIMEOUT`), the old instance stays. Only
+//! actors registered through rust_manager_ffi can be replaced.
+
+use std::fmt;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actors::messages::Start;
+use actors::{Actor, ActorContext, ManagerHandle, Message};
+
+use crate::aliases;
+use crate::interop_errors::InteropErrorCode;
+use crate::lifecycle::{self, ActorFactory, LifecycleEvent};
+use crate::names::c_str;
+use crate::registry;
+use crate::rust_manager_ffi::{self, get_actor_ref, ActorBuilder};
+use crate::shared_thread;
+use crate::snapshot::Instance;
+
+/// How long `replace_actor` waits for the old instance to reach the swap
+pub const SWAP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplaceError {
+    /// Not registered through rust_manager_ffi, or no Manager
+    NotRegistered(String),
+    /// The factory panicked
+    Build(String),
+    /// `restore` panicked on the old instance's state
+    Restore(String),
+    /// The replacement panicked in Start
+    Start(String),
+    /// The old instance did not reach the swap within the timeout
+    Timeout(String),
+    /// The actor stopped before the swap
+    Stopped(String),
+}
+
+impl fmt::Display for ReplaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplaceError::NotRegistered(name) => write!(f, "{} is not a registered Rust actor", name),
+            ReplaceError::Build(name) => write!(f, "building the replacement of {} panicked", name),
+            ReplaceError::Restore(name) => write!(f, "restoring the state of {} into its replacement panicked", name),
+            ReplaceError::Start(name) => write!(f, "the replacement of {} panicked in Start", name),
+            ReplaceError::Timeout(name) => write!(f, "{} did not reach the swap in time", name),
+            ReplaceError::Stopped(name) => write!(f, "{} stopped before the swap", name),
+        }
+    }
+}
+
+impl std::error::Error for ReplaceError {}
+
+/// A built replacement waiting for the swap
+struct Green {
+    instance: Instance,
+    factory: ActorFactory,
+}
+
+/// Queued in the actor's mailbox; handled by its supervisor
+pub(crate) struct Swap {
+    // Taken by the supervisor, or back by a caller that gave up waiting
+    green: Arc<Mutex<Option<Green>>>,
+    done: Sender<Result<(), ReplaceError>>,
+}
+
+// Only the supervisor looks inside
+impl Message for Swap {
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+    fn message_id(&self) -> i32 { 0 }
+}
+
+/// Replace Rust actor `name` with what `new_factory` builds, carrying its
+/// state over. Waits up to SWAP_TIMEOUT.
+pub fn replace_actor<F>(name: &str, new_factory: F) -> Result<(), ReplaceError>
+where
+    F: FnMut(ManagerHandle) -> Instance + Send + 'static,
+{
+    replace_actor_within(name, new_factory, SWAP_TIMEOUT)
+}
+
+/// replace_actor() waiting up to `timeout` for the old instance to drain
+pub fn replace_actor_within<F>(name: &str, new_factory: F, timeout: Duration) -> Result<(), ReplaceError>
+where
+    F: FnMut(ManagerHandle) -> Instance + Send + 'static,
+{
+    let name = aliases::resolve(name).into_owned();
+    let handle = rust_manager_ffi::registered_handle(&name).ok_or_else(|| ReplaceError::NotRegistered(name.clone()))?;
+    let build: ActorBuilder = Arc::new(Mutex::new(new_factory));
+    let instance = panic::catch_unwind(AssertUnwindSafe(|| (build.lock().unwrap())(handle.clone())))
+        .map_err(|_| ReplaceError::Build(name.clone()))?;
+    let factory: ActorFactory = {
+        let build = build.clone();
+        Box::new(move || (build.lock().unwrap())(handle.clone()))
+    };
+
+    let green = Arc::new(Mutex::new(Some(Green { instance, factory })));
+    let (done, result) = mpsc::channel();
+    let swap = Box::new(Swap { green: green.clone(), done });
+    if shared_thread::group_of(&name).is_some() {
+        // Member sends copy the message, which a Swap cannot be
+        shared_thread::send_to_member(&name, swap);
+    } else {
+        get_actor_ref(&name, "").ok_or_else(|| ReplaceError::NotRegistered(name.clone()))?.send(swap, None);
+    }
+
+    let outcome = match result.recv_timeout(timeout) {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            // Taking it back cancels the swap, unless the actor already has it
+            let cancelled = green.lock().unwrap().take().is_some();
+            match e {
+                _ if !cancelled => result.recv().unwrap_or_else(|_| Err(ReplaceError::Stopped(name.clone()))),
+                RecvTimeoutError::Timeout => Err(ReplaceError::Timeout(name.clone())),
+                RecvTimeoutError::Disconnected => Err(ReplaceError::Stopped(name.clone())),
+            }
+        }
+    };
+    match &outcome {
+        Ok(()) => {
+            rust_manager_ffi::rebuild_with(&name, build);
+            eprintln!("[Replace] {}: replaced", name);
+        }
+        Err(e) => eprintln!("[Replace] {}", e),
+    }
+    outcome
+}
+
+/// Swap `actor` for the replacement `swap` carries. Called by the
+/// supervisor of `name` on its thread.
+pub(crate) fn apply(swap: &Swap, name: &str, actor: &mut Instance, factory: &mut ActorFactory, ctx: &mut ActorContext) {
+    let Green { instance: mut next, factory: next_factory } = match swap.green.lock().unwrap().take() {
+        Some(green) => green,
+        None => return,  // the caller gave up
+    };
+    if let (Instance::Stateful(_), Some(state)) = (&next, actor.snapshot()) {
+        if !next.restore(&state) {
+            let _ = swap.done.send(Err(ReplaceError::Restore(name.to_string())));
+            return;
+        }
+    }
+    if panic::catch_unwind(AssertUnwindSafe(|| next.process_message(&Start, ctx))).is_err() {
+        let _ = swap.done.send(Err(ReplaceError::Start(name.to_string())));
+        return;
+    }
+    drop(std::mem::replace(actor, next));
+    *factory = next_factory;
+    lifecycle::notify(name, LifecycleEvent::Restarted);
+    let _ = swap.done.send(Ok(()));
+}
+
+/// Replace Rust actor `actor_name` with an instance of #[interop_actor]
+/// type `type_name` (see registry), carrying its state over. Returns 0,
+/// InvalidArgument on a null or non-UTF-8 name, UnknownType, NotFound if
+/// the actor is not registered or stopped, Timeout, or ReplaceFailed if the
+/// replacement panicked; on any error the old instance stays.
+#[export_name = c_symbol!("interop_replace_actor")]
+pub extern "C" fn interop_replace_actor(actor_name: *const c_char, type_name: *const c_char) -> c_int {
+    let (name, type_name) = match (c_str(actor_name), c_str(type_name)) {
+        (Some(n), Some(t)) => (n, t),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let actor_type = match registry::find(type_name) {
+        Some(t) => t,
+        None => return InteropErrorCode::UnknownType as c_int,
+    };
+    let rc = match replace_actor(name, actor_type.build) {
+        Ok(()) => InteropErrorCode::Ok,
+        Err(ReplaceError::NotRegistered(_) | ReplaceError::Stopped(_)) => InteropErrorCode::NotFound,
+        Err(ReplaceError::Timeout(_)) => InteropErrorCode::Timeout,
+        Err(ReplaceError::Build(_) | ReplaceError::Restore(_) | ReplaceError::Start(_)) => {
+            InteropErrorCode::ReplaceFailed
+        }
+    };
+    rc as c_int
+}
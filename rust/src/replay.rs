@@ -0,0 +1,372 @@
+//! Backtests from recorded market data
+//!
+//! `ReplaySource` reads recorded MarketUpdate / MarketDepth messages and
+//! publishes each through the broker under its symbol, as a live publisher
+//! would. Strategies in either language subscribe to it by name as usual
+//! (Subscribe from C++, `broker::subscribe` from Rust), so a backtest sees
+//! the same interop message flow as production.
+//!
+//! Two input formats:
+//! - Recording: records `[time_ns: i64 LE][wire frame]` back to back, the
+//!   frame as in `wire`. `Recorder` writes them, e.g. from a tap.
+//! - CSV: `time,symbol,price,volume` per line, one MarketUpdate each;
+//!   time is nanoseconds since the epoch or RFC 3339. Blank lines, `#`
+//!   comments and a header line are skipped.
+//!
+//! `speed` paces the replay: 1.0 keeps the recorded gaps, 10.0 is ten times
+//! faster, 0 publishes as fast as the subscribers take it. With a simulated
+//! clock installed (see clock), virtual time is set to each record's time
+//! before it is published, so leases, breakers and `Timestamp::now()` see
+//! market time.
+//!
+//! Replay starts on Start, or once `min_subscribers` Subscribes have
+//! arrived so early messages are not published to nobody. It runs once on
+//! a thread of its own; other messages in a recording are skipped.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use actors::{handle_messages, ActorContext, Message, ThreadConfig};
+use actors::messages::Start;
+
+use crate::broker;
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{c_struct_size, MarketDepth, MarketUpdate, Subscribe, Unsubscribe};
+use crate::names::c_str;
+use crate::rust_manager_ffi;
+use crate::timestamp::Timestamp;
+use crate::wire::{self, WireError, HEADER_LEN};
+
+/// Default name of the source, which subscribers send Subscribe to
+pub const REPLAY_SOURCE_NAME: &str = "replay_source";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayFormat {
+    Recording,
+    Csv,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReplayConfig {
+    /// Publisher name subscriptions are recorded under
+    pub name: String,
+    pub path: PathBuf,
+    pub format: ReplayFormat,
+    /// Multiple of the recorded pace, 0 for as fast as possible
+    pub speed: f64,
+    /// Subscribes to wait for before replaying, 0 to replay on Start
+    pub min_subscribers: usize,
+}
+
+impl ReplayConfig {
+    pub fn new(path: impl Into<PathBuf>, format: ReplayFormat) -> Self {
+        ReplayConfig {
+            name: REPLAY_SOURCE_NAME.to_string(),
+            path: path.into(),
+            format,
+            speed: 1.0,
+            min_subscribers: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    /// A recording frame that does not decode
+    Frame(WireError),
+    /// A CSV line that does not parse (1-based)
+    Csv { line: usize, reason: &'static str },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "{}", e),
+            ReplayError::Frame(e) => write!(f, "bad frame: {}", e),
+            ReplayError::Csv { line, reason } => write!(f, "line {}: {}", line, reason),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+/// A recorded message and the time it was recorded at
+pub type Tick = (Timestamp, Box<dyn Message>);
+
+/// Reads a recording, one Tick per record
+pub struct RecordingReader<R: Read> {
+    input: R,
+}
+
+impl<R: Read> RecordingReader<R> {
+    pub fn new(input: R) -> Self {
+        RecordingReader { input }
+    }
+
+    fn read_tick(&mut self) -> Result<Option<Tick>, ReplayError> {
+        let mut head = [0u8; 8 + HEADER_LEN];
+        match self.input.read_exact(&mut head) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        let time = Timestamp::from_nanos(i64::from_le_bytes(head[..8].try_into().unwrap()));
+        let msg_id = i32::from_le_bytes(head[8..12].try_into().unwrap());
+        let len = u32::from_le_bytes(head[12..16].try_into().unwrap()) as usize;
+        // Check before reading the body so a corrupt length is not allocated
+        match c_struct_size(msg_id) {
+            Some(expected) if expected == len => {}
+            Some(expected) => return Err(ReplayError::Frame(WireError::SizeMismatch { msg_id, expected, actual: len })),
+            None => return Err(ReplayError::Frame(WireError::UnknownMessage(msg_id))),
+        }
+        let mut frame = head[8..].to_vec();
+        frame.resize(HEADER_LEN + len, 0);
+        self.input.read_exact(&mut frame[HEADER_LEN..])?;
+        let msg = wire::decode_message(&frame).map_err(ReplayError::Frame)?;
+        Ok(Some((time, msg)))
+    }
+}
+
+impl<R: Read> Iterator for RecordingReader<R> {
+    type Item = Result<Tick, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_tick().transpose()
+    }
+}
+
+/// Reads `time,symbol,price,volume` lines as MarketUpdates
+pub struct CsvReader<R: BufRead> {
+    lines: io::Lines<R>,
+    line: usize,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(input: R) -> Self {
+        CsvReader { lines: input.lines(), line: 0 }
+    }
+}
+
+fn parse_time(s: &str) -> Option<Timestamp> {
+    s.parse::<i64>().ok().map(Timestamp::from_nanos).or_else(|| s.parse().ok())
+}
+
+fn parse_csv_line(text: &str) -> Result<Tick, &'static str> {
+    let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+    if fields.len() != 4 {
+        return Err("expected time,symbol,price,volume");
+    }
+    let time = parse_time(fields[0]).ok_or("bad time")?;
+    let price = fields[2].parse::<f64>().map_err(|_| "bad price")?;
+    let volume = fields[3].parse::<i32>().map_err(|_| "bad volume")?;
+    let update = MarketUpdate { symbol: fields[1].into(), price, timestamp: time, volume, seq: 0 };
+    Ok((time, Box::new(update)))
+}
+
+impl<R: BufRead> Iterator for CsvReader<R> {
+    type Item = Result<Tick, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let text = match self.lines.next()? {
+                Ok(t) => t,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line += 1;
+            let text = text.trim();
+            if text.is_empty() || text.starts_with('#') {
+                continue;
+            }
+            match parse_csv_line(text) {
+                Ok(tick) => return Some(Ok(tick)),
+                // A header names the columns instead of holding a time
+                Err(_) if self.line == 1 && parse_time(text.split(',').next().unwrap_or("")).is_none() => continue,
+                Err(reason) => return Some(Err(ReplayError::Csv { line: self.line, reason })),
+            }
+        }
+    }
+}
+
+/// Open `path` as `format`
+pub fn open(path: &Path, format: ReplayFormat) -> Result<Box<dyn Iterator<Item = Result<Tick, ReplayError>> + Send>, ReplayError> {
+    let input = BufReader::new(File::open(path)?);
+    Ok(match format {
+        ReplayFormat::Recording => Box::new(RecordingReader::new(input)),
+        ReplayFormat::Csv => Box::new(CsvReader::new(input)),
+    })
+}
+
+/// Writes a recording for ReplayFormat::Recording
+pub struct Recorder<W: Write> {
+    output: W,
+}
+
+impl Recorder<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Recorder::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    pub fn new(output: W) -> Self {
+        Recorder { output }
+    }
+
+    /// Append `msg` recorded at `time`. Ok(false) if it is not a portable
+    /// interop message (see wire) and was not written.
+    pub fn record(&mut self, time: Timestamp, msg: &dyn Message) -> io::Result<bool> {
+        let frame = match wire::encode_dyn(msg) {
+            Some(f) => f,
+            None => return Ok(false),
+        };
+        self.output.write_all(&time.as_nanos().to_le_bytes())?;
+        self.output.write_all(&frame)?;
+        Ok(true)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// Publish `msg` under its symbol. False if it is not a market data message.
+fn publish(publisher: &str, msg: &dyn Message) -> bool {
+    let any = msg.as_any();
+    let result = if let Some(update) = any.downcast_ref::<MarketUpdate>() {
+        broker::publish(publisher, update.symbol.as_str(), update)
+    } else if let Some(depth) = any.downcast_ref::<MarketDepth>() {
+        broker::publish(publisher, depth.symbol.as_str(), depth)
+    } else {
+        return false;
+    };
+    if let Err(e) = result {
+        eprintln!("[Replay] {}: {}", publisher, e);
+    }
+    true
+}
+
+/// Replay `config` on the calling thread. Returns how many messages were
+/// published.
+pub fn run(config: &ReplayConfig) -> Result<usize, ReplayError> {
+    let mut published = 0;
+    let mut previous: Option<Timestamp> = None;
+    for tick in open(&config.path, config.format)? {
+        let (time, msg) = tick?;
+        if config.speed > 0.0 {
+            if let Some(gap) = previous.and_then(|p| time.duration_since(p)) {
+                thread::sleep(gap.div_f64(config.speed));
+            }
+        }
+        previous = Some(time);
+        if let Some(sim) = clock::simulated() {
+            sim.set(time);
+        }
+        if publish(&config.name, msg.as_ref()) {
+            published += 1;
+        }
+    }
+    Ok(published)
+}
+
+pub struct ReplaySource {
+    config: ReplayConfig,
+    subscribers: usize,
+    started: bool,
+}
+
+impl ReplaySource {
+    pub fn new(config: ReplayConfig) -> Self {
+        ReplaySource { config, subscribers: 0, started: false }
+    }
+
+    fn begin(&mut self) {
+        if self.started {
+            return;
+        }
+        self.started = true;
+        let config = self.config.clone();
+        thread::spawn(move || match run(&config) {
+            Ok(n) => println!("[Replay] {} published {} messages from {}", config.name, n, config.path.display()),
+            Err(e) => eprintln!("[Replay] {} stopped on {}: {}", config.name, config.path.display(), e),
+        });
+    }
+
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        if self.config.min_subscribers == 0 {
+            self.begin();
+        }
+    }
+
+    // The bridge and broker::subscribe record subscriptions; only count them here
+    fn on_subscribe(&mut self, _msg: &Subscribe, _ctx: &mut ActorContext) {
+        self.subscribers += 1;
+        if self.subscribers >= self.config.min_subscribers {
+            self.begin();
+        }
+    }
+
+    fn on_unsubscribe(&mut self, _msg: &Unsubscribe, _ctx: &mut ActorContext) {}
+}
+
+// Register message handlers
+handle_messages!(ReplaySource,
+    Start => on_start,
+    Subscribe => on_subscribe,
+    Unsubscribe => on_unsubscribe
+);
+
+/// Register a ReplaySource for `config` with the Rust Manager. False if
+/// there is no Manager or the name is taken (see names).
+pub fn register(config: ReplayConfig) -> bool {
+    let name = config.name.clone();
+    rust_manager_ffi::register_with(&name, move |_| Box::new(ReplaySource::new(config.clone())), ThreadConfig::default())
+}
+
+/// Register a replay source from C++; `format` 0 is a recording, 1 CSV.
+/// `name` may be null for "replay_source".
+/// Returns 0, InvalidArgument on bad arguments or a missing file, or
+/// NameTaken if there is no Manager or the name is taken
+#[export_name = c_symbol!("interop_replay_source")]
+pub extern "C" fn interop_replay_source(
+    name: *const c_char,
+    path: *const c_char,
+    format: i32,
+    speed: f64,
+    min_subscribers: u32,
+) -> i32 {
+    let path = match c_str(path) {
+        Some(p) if Path::new(p).is_file() => p,
+        _ => return InteropErrorCode::InvalidArgument as i32,
+    };
+    let format = match format {
+        0 => ReplayFormat::Recording,
+        1 => ReplayFormat::Csv,
+        _ => return InteropErrorCode::InvalidArgument as i32,
+    };
+    if !speed.is_finite() || speed < 0.0 {
+        return InteropErrorCode::InvalidArgument as i32;
+    }
+    let mut config = ReplayConfig::new(path, format);
+    if !name.is_null() {
+        match c_str(name) {
+            Some(n) if !n.is_empty() => config.name = n.to_string(),
+            _ => return InteropErrorCode::InvalidArgument as i32,
+        }
+    }
+    config.speed = speed;
+    config.min_subscribers = min_subscribers as usize;
+    let rc = if register(config) { InteropErrorCode::Ok } else { InteropErrorCode::NameTaken };
+    rc as i32
+}
@@ -0,0 +1,137 @@
+//! Replaceable lookup behind `get_actor_ref()` for names Rust doesn't own
+//!
+//! The Rust Manager answers for its own actors first. Every other name goes
+//! to the installed `Resolver`; by default that is `CppRegistry`, the C++
+//! runtimes reached through `cpp_actor_exists()`. Tests and transports
+//! install their own, usually as a `Chain` asked in order:
+//!
+//! ```ignore
+//! resolver::set_actor_resolver(Box::new(
+//!     Chain::new()
+//!         .then(CppRegistry)
+//!         .then(remote_resolver)                  // a transport's lookup
+//!         .then(|name: &str, sender: &str| mocks.get(name, sender)),
+//! ));
+//! ...
+//! resolver::reset_actor_resolver();              // back to CppRegistry
+//! ```
+//!
+//! A resolver runs on the looking-up actor's thread and may itself call
+//! `get_actor_ref()`. Install it before or after `init_cpp_actor_lookup()`;
+//! the latter keeps an installed resolver.
+
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use actors::{register_cpp_lookup, ActorRef};
+
+use crate::aliases;
+use crate::rust_manager_ffi;
+
+/// Answers lookups for names the Rust Manager does not own
+pub trait Resolver: Send + Sync {
+    /// An `ActorRef` for `name` whose replies go to `sender`, or None to
+    /// leave the name unresolved
+    fn resolve(&self, name: &str, sender: &str) -> Option<ActorRef>;
+}
+
+impl<F> Resolver for F
+where
+    F: Fn(&str, &str) -> Option<ActorRef> + Send + Sync,
+{
+    fn resolve(&self, name: &str, sender: &str) -> Option<ActorRef> {
+        self(name, sender)
+    }
+}
+
+/// The default: actors of the linked and attached C++ runtimes (see
+/// cpp_runtime), with answers cached by lookup_cache
+pub struct CppRegistry;
+
+impl Resolver for CppRegistry {
+    fn resolve(&self, name: &str, sender: &str) -> Option<ActorRef> {
+        rust_manager_ffi::cpp_actor_lookup(name, sender)
+    }
+}
+
+/// Resolvers asked in order; the first answer wins
+#[derive(Default)]
+pub struct Chain {
+    resolvers: Vec<Box<dyn Resolver>>,
+}
+
+impl Chain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask `resolver` after the ones already in the chain
+    pub fn then(mut self, resolver: impl Resolver + 'static) -> Self {
+        self.resolvers.push(Box::new(resolver));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.resolvers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+}
+
+impl Resolver for Chain {
+    fn resolve(&self, name: &str, sender: &str) -> Option<ActorRef> {
+        self.resolvers.iter().find_map(|r| r.resolve(name, sender))
+    }
+}
+
+// None: CppRegistry. Cloned out of the lock so a resolver that looks up
+// other names does not hold it.
+static RESOLVER: RwLock<Option<Arc<dyn Resolver>>> = RwLock::new(None);
+// Set once the Manager asks resolve() for names it does not own
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Replace the lookup for names the Rust Manager does not own. Without
+/// `CppRegistry` in it, Rust no longer finds C++ actors.
+pub fn set_actor_resolver(resolver: Box<dyn Resolver>) {
+    *RESOLVER.write().unwrap() = Some(Arc::from(resolver));
+    install();
+}
+
+/// Hand resolve() to the Manager
+pub(crate) fn install() {
+    register_cpp_lookup(resolve);
+    INSTALLED.store(true, Ordering::Release);
+}
+
+/// True once `get_actor_ref()` reaches resolve() for names Rust does not
+/// own, i.e. C++ actors can be found
+pub(crate) fn is_installed() -> bool {
+    INSTALLED.load(Ordering::Acquire)
+}
+
+/// Go back to `CppRegistry` alone
+pub fn reset_actor_resolver() {
+    *RESOLVER.write().unwrap() = None;
+}
+
+/// True while a resolver other than the default is installed
+pub fn is_overridden() -> bool {
+    RESOLVER.read().unwrap().is_some()
+}
+
+/// Lookup handed to the Manager: the installed resolver, else CppRegistry.
+/// An alias (see aliases) is looked up again under its target, which may
+/// be a Rust actor.
+pub(crate) fn resolve(name: &str, sender: &str) -> Option<ActorRef> {
+    if let Cow::Owned(target) = aliases::resolve(name) {
+        return rust_manager_ffi::get_actor_ref(&target, sender);
+    }
+    let installed = RESOLVER.read().unwrap().clone();
+    match installed {
+        Some(resolver) => resolver.resolve(name, sender),
+        None => CppRegistry.resolve(name, sender),
+    }
+}
@@ -0,0 +1,141 @@
+//! Retry with backoff for sends to C++ actors
+//!
+//! A send to C++ can fail transiently while the C++ side is starting or
+//! reloading (NotFound: actor not registered yet) or while its queue is
+//! full (QueueFull). `RetryPolicy` retries those with exponential backoff;
+//! errors that cannot succeed on retry (UnknownMessage, WrongType,
+//! InvalidMessage, ...) fail immediately.
+//!
+//! Retries block: the backoff is a `clock::sleep` on the sending thread,
+//! and a send from an actor handler holds up that actor's whole mailbox
+//! until the last attempt. Keep `max_attempts` and `max_backoff` small for
+//! sends made from actor threads; the worst case is the sum of the
+//! backoffs. Under a simulated clock the sleep waits for virtual time (see
+//! clock). When the last attempt fails, the failure callback is called so
+//! the message is not lost silently.
+//!
+//! - `CppActorIF::with_retry(policy)` / `on_failure(cb)` per interface
+//! - `set_cpp_retry_policy()` / `set_cpp_failure_callback()` for every
+//!   `ActorRef::Cpp` send made through the Manager
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+
+/// Called with (target, msg_id, last return code) after the final attempt fails
+pub type FailureCallback = Arc<dyn Fn(&str, i32, i32) + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first; 1 disables retry
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// No retry, matching the behaviour without a policy
+    fn default() -> Self {
+        RetryPolicy::none()
+    }
+}
+
+impl RetryPolicy {
+    pub const fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    /// Retry up to `max_attempts` times, doubling the backoff each time
+    /// up to one second
+    pub fn new(max_attempts: u32, initial_backoff: Duration) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Delay before attempt `attempt` (1-based; attempt 1 has none)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        if attempt <= 1 {
+            return Duration::ZERO;
+        }
+        let factor = 1u32.checked_shl(attempt - 2).unwrap_or(u32::MAX);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+/// True for bridge return codes that may succeed on a later attempt
+pub fn is_transient(rc: i32) -> bool {
+    rc == InteropErrorCode::NotFound as i32 || rc == InteropErrorCode::QueueFull as i32
+}
+
+/// Run `send` under `policy`. Returns the last return code.
+pub fn run<F: FnMut() -> i32>(policy: &RetryPolicy, mut send: F) -> i32 {
+    let mut rc = send();
+    let mut attempt = 1;
+    while rc != 0 && is_transient(rc) && attempt < policy.max_attempts {
+        attempt += 1;
+        clock::sleep(policy.backoff(attempt));
+        rc = send();
+    }
+    rc
+}
+
+/// Run `send` under `policy` and report a final failure to `on_failure`
+pub fn send_with_retry<F: FnMut() -> i32>(
+    policy: &RetryPolicy,
+    on_failure: Option<&FailureCallback>,
+    target: &str,
+    msg_id: i32,
+    send: F,
+) -> i32 {
+    let rc = run(policy, send);
+    if rc != 0 {
+        if let Some(cb) = on_failure {
+            cb(target, msg_id, rc);
+        }
+    }
+    rc
+}
+
+lazy_static::lazy_static! {
+    static ref CPP_POLICY: Mutex<RetryPolicy> = Mutex::new(RetryPolicy::none());
+    static ref CPP_ON_FAILURE: Mutex<Option<FailureCallback>> = Mutex::new(None);
+}
+
+/// Policy for `ActorRef::Cpp` sends made through the Manager
+pub fn set_cpp_retry_policy(policy: RetryPolicy) {
+    *CPP_POLICY.lock().unwrap() = policy;
+}
+
+pub fn cpp_retry_policy() -> RetryPolicy {
+    *CPP_POLICY.lock().unwrap()
+}
+
+/// Failure callback for `ActorRef::Cpp` sends made through the Manager
+pub fn set_cpp_failure_callback<F>(cb: F)
+where
+    F: Fn(&str, i32, i32) + Send + Sync + 'static,
+{
+    *CPP_ON_FAILURE.lock().unwrap() = Some(Arc::new(cb));
+}
+
+pub fn clear_cpp_failure_callback() {
+    *CPP_ON_FAILURE.lock().unwrap() = None;
+}
+
+pub fn cpp_failure_callback() -> Option<FailureCallback> {
+    CPP_ON_FAILURE.lock().unwrap().clone()
+}
@@ -0,0 +1,247 @@
+//! Routers that pick one of several targets for each message
+//!
+//! `Router` is used like an `ActorRef`, over members that can be Rust or
+//! C++ actors, with the policy fixed when it is built:
+//!
+//! - `RoundRobin`: members in turn.
+//! - `Weighted`: in proportion to each member's weight, e.g. 80/20 between
+//!   a legacy C++ pricer and a new Rust pricer while the new one is proven.
+//! - `Failover`: the first member, in the order added, whose circuit (see
+//!   circuit) is not open. Traffic moves to the next member when the
+//!   breaker opens on the primary and back once a probe closes it again.
+//!
+//! ```ignore
+//! let mut pricer = Router::new(RoutingPolicy::Weighted);
+//! pricer.add_weighted_member("cpp_pricer", get_actor_ref("cpp_pricer", "rust_quoter").unwrap(), 80);
+//! pricer.add_weighted_member("rust_pricer", get_actor_ref("rust_pricer", "rust_quoter").unwrap(), 20);
+//! pricer.send(Box::new(request), None)?;
+//! ```
+//!
+//! Failover needs a breaker policy (`circuit::set_policy`); without one,
+//! or for Rust members, every member is always available and the primary
+//! takes everything. For per-key ordering use `hash_router::HashRouter`.
+//!
+//! A sticky router keeps a conversation on one member: the first message
+//! with a given key is routed by the policy, and later ones with the same
+//! key go to the same member, so an order, its amends and its cancel sent
+//! from C++ reach the worker holding that order's state:
+//!
+//! ```ignore
+//! let mut orders = Router::new(RoutingPolicy::RoundRobin);
+//! orders.set_sticky(StickyKey::Field("order_id".into()), Duration::from_secs(300));
+//! ```
+//!
+//! A session ends once its key has not been seen for the idle time, or
+//! with `end_session`. If its member is removed, or its circuit opens, the
+//! next message starts a new session elsewhere. Messages without the key
+//! are routed by the policy alone.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actors::{ActorRef, Message};
+
+use crate::circuit::{self, CIRCUIT_OPEN};
+use crate::dead_letters::{self, Route};
+use crate::hash_router::KeyFn;
+use crate::interop_messages::{idempotency_key, message_field_key};
+use crate::send_error::{SendError, TrySend};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoutingPolicy {
+    RoundRobin,
+    /// By member weight; members with weight 0 get nothing
+    Weighted,
+    /// Members in priority order, skipping those with an open circuit
+    Failover,
+}
+
+/// What a sticky router keys a conversation by
+#[derive(Clone)]
+pub enum StickyKey {
+    /// The message's idempotency key, e.g. NewOrder.order_id
+    Correlation,
+    /// A field of any message that has it (see message_field_key)
+    Field(String),
+    Custom(KeyFn),
+}
+
+impl StickyKey {
+    fn key(&self, msg: &dyn Message) -> Option<String> {
+        match self {
+            StickyKey::Correlation => idempotency_key(msg).map(|k| k.to_string()),
+            StickyKey::Field(field) => message_field_key(msg, field),
+            StickyKey::Custom(key_fn) => key_fn(msg),
+        }
+    }
+}
+
+struct Sticky {
+    key: StickyKey,
+    idle: Duration,
+    // key -> (member name, last message)
+    sessions: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+// Expired sessions are swept when the table reaches this size, then
+// each time it doubles
+const STICKY_SWEEP_AT: usize = 1024;
+
+struct Member {
+    name: String,
+    actor: ActorRef,
+    weight: u32,
+}
+
+impl Member {
+    fn available(&self) -> bool {
+        match &self.actor {
+            ActorRef::Cpp(cpp) => circuit::available(&cpp.sender, &cpp.target),
+            _ => true,
+        }
+    }
+}
+
+pub struct Router {
+    policy: RoutingPolicy,
+    members: Vec<Member>,
+    total_weight: u64,
+    next: AtomicUsize,
+    sticky: Option<Sticky>,
+}
+
+impl Router {
+    pub fn new(policy: RoutingPolicy) -> Self {
+        Router {
+            policy,
+            members: Vec::new(),
+            total_weight: 0,
+            next: AtomicUsize::new(0),
+            sticky: None,
+        }
+    }
+
+    /// Route every message with the same `key` to the same member until
+    /// the key has been idle for `idle`; replaces the sessions so far
+    pub fn set_sticky(&mut self, key: StickyKey, idle: Duration) {
+        self.sticky = Some(Sticky { key, idle, sessions: Mutex::new(HashMap::new()) });
+    }
+
+    /// Back to routing by the policy alone
+    pub fn clear_sticky(&mut self) {
+        self.sticky = None;
+    }
+
+    /// Forget the session for `key`; false if there was none
+    pub fn end_session(&self, key: &str) -> bool {
+        self.sticky.as_ref().is_some_and(|s| s.sessions.lock().unwrap().remove(key).is_some())
+    }
+
+    /// Sessions open, expired ones not yet swept included
+    pub fn session_count(&self) -> usize {
+        self.sticky.as_ref().map_or(0, |s| s.sessions.lock().unwrap().len())
+    }
+
+    pub fn policy(&self) -> RoutingPolicy {
+        self.policy
+    }
+
+    /// Add a member with weight 1; replaces an existing member with the
+    /// same name. For failover, members added first are preferred.
+    pub fn add_member(&mut self, name: &str, actor: ActorRef) {
+        self.add_weighted_member(name, actor, 1);
+    }
+
+    /// Add a member taking `weight` shares of a weighted router's messages
+    pub fn add_weighted_member(&mut self, name: &str, actor: ActorRef, weight: u32) {
+        match self.members.iter_mut().find(|m| m.name == name) {
+            Some(m) => {
+                m.actor = actor;
+                m.weight = weight;
+            }
+            None => self.members.push(Member { name: name.to_string(), actor, weight }),
+        }
+        self.total_weight = self.members.iter().map(|m| m.weight as u64).sum();
+    }
+
+    pub fn remove_member(&mut self, name: &str) -> bool {
+        let before = self.members.len();
+        self.members.retain(|m| m.name != name);
+        self.total_weight = self.members.iter().map(|m| m.weight as u64).sum();
+        self.members.len() != before
+    }
+
+    /// Member names, in the order added
+    pub fn names(&self) -> Vec<&str> {
+        self.members.iter().map(|m| m.name.as_str()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    fn pick(&self) -> Option<usize> {
+        if self.members.is_empty() {
+            return None;
+        }
+        match self.policy {
+            RoutingPolicy::RoundRobin => Some(self.next.fetch_add(1, Ordering::Relaxed) % self.members.len()),
+            RoutingPolicy::Weighted => {
+                if self.total_weight == 0 {
+                    return None;
+                }
+                let mut slot = self.next.fetch_add(1, Ordering::Relaxed) as u64 % self.total_weight;
+                self.members.iter().position(|m| {
+                    let hit = slot < m.weight as u64;
+                    slot = slot.saturating_sub(m.weight as u64);
+                    hit
+                })
+            }
+            RoutingPolicy::Failover => self.members.iter().position(Member::available),
+        }
+    }
+
+    // The session's member if it is still usable, else a new pick that
+    // becomes the session's member
+    fn pick_sticky(&self, sticky: &Sticky, key: String) -> Option<usize> {
+        let now = Instant::now();
+        let mut sessions = sticky.sessions.lock().unwrap();
+        if let Some((name, last)) = sessions.get_mut(&key) {
+            if now.duration_since(*last) < sticky.idle {
+                if let Some(i) = self.members.iter().position(|m| m.name == *name && m.available()) {
+                    *last = now;
+                    return Some(i);
+                }
+            }
+        }
+        let i = self.pick()?;
+        if sessions.len() >= STICKY_SWEEP_AT && sessions.len().is_power_of_two() {
+            sessions.retain(|_, (_, last)| now.duration_since(*last) < sticky.idle);
+        }
+        sessions.insert(key, (self.members[i].name.clone(), now));
+        Some(i)
+    }
+
+    /// Route a message to one member. Fails with `ActorNotFound` if there
+    /// is no member to take it, or `TransportError(CIRCUIT_OPEN)` if every
+    /// failover member's circuit is open.
+    pub fn send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) -> Result<(), SendError> {
+        let picked = match self.sticky.as_ref().and_then(|s| s.key.key(msg.as_ref()).map(|k| (s, k))) {
+            Some((sticky, key)) => self.pick_sticky(sticky, key),
+            None => self.pick(),
+        };
+        match picked {
+            Some(i) => dead_letters::via(Route::Router, || self.members[i].actor.try_send(msg, sender)),
+            None if self.policy == RoutingPolicy::Failover && !self.members.is_empty() => {
+                Err(SendError::TransportError(CIRCUIT_OPEN))
+            }
+            None => Err(SendError::ActorNotFound),
+        }
+    }
+}
@@ -5,15 +5,49 @@
 //! - Register actors with the Manager
 //! - Initialize and run the Manager
 //! - Shutdown
+//! - Restart every Rust actor from the recorded registrations
 //! - Register C++ actor lookup for cross-language transparency
 
-use std::ffi::CString;
 use std::sync::Mutex;
-use actors::{register_cpp_lookup, ActorRef, CppActorRef, Manager, ThreadConfig};
-use crate::ping_pong::RustPongActor;
-use crate::rust_ping::RustPingActor;
-use crate::pubsub::RustPublisher;
-use crate::rust_subscriber::RustSubscriber;
+use std::sync::Arc;
+use std::time::Duration;
+use actors::{Actor, ActorRef, CppActorRef, Manager, ManagerHandle, ThreadConfig};
+use crate::aliases;
+use crate::boundary::{self, Direction};
+use crate::broker;
+use crate::busy_poll::BusyPoll;
+use crate::capabilities;
+use crate::circuit;
+use crate::conversion_cache;
+use crate::cpp_runtime;
+use crate::cpp_hosted_actor::{CppActorVTable, CppHostedActor};
+use crate::deadlines;
+use crate::dependencies;
+use crate::group::{self, ActorGroup};
+use crate::health::{self, ManagerState};
+use crate::interop_error;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_log;
+use crate::lease;
+use crate::lifecycle::Supervised;
+use crate::locality;
+use crate::names::{self, c_str, ActorName};
+use crate::orphans;
+use crate::outbound;
+use crate::registry;
+use crate::resolver;
+use crate::startup_barrier::{self, Side};
+use crate::policy;
+use crate::rate_limit::{self, Admission};
+use crate::retry;
+use crate::sequencing;
+use crate::shared_thread;
+use crate::shutdown;
+use crate::size_limit;
+use crate::snapshot::{self, Instance};
+use crate::stats;
+use crate::sys_topics;
+use crate::tap;
 
 // Wrapper to make Manager pointer safe for static storage
 struct ManagerPtr(*mut Manager);
@@ -24,51 +58,382 @@ unsafe impl Sync for ManagerPtr {}
 // Use Mutex with a pointer wrapper since Manager doesn't impl Sync
 static RUST_MANAGER: Mutex<ManagerPtr> = Mutex::new(ManagerPtr(std::ptr::null_mut()));
 
+/// Builds an actor for a Manager; kept so rust_manager_restart() can build
+/// it again
+pub(crate) type ActorBuilder = Arc<Mutex<dyn FnMut(ManagerHandle) -> Instance + Send>>;
+
+/// One actor registered through this module
+struct Registration {
+    name: String,
+    build: ActorBuilder,
+    thread_config: ThreadConfig,
+    // Shared thread group (see shared_thread), None for a thread of its own
+    group: Option<String>,
+}
+
+// Every registration since create_rust_manager(), in order
+static MANIFEST: Mutex<Vec<Registration>> = Mutex::new(Vec::new());
+
+fn builder<F>(mut build: F) -> ActorBuilder
+where
+    F: FnMut(ManagerHandle) -> Box<dyn Actor> + Send + 'static,
+{
+    instance_builder(move |handle| Instance::from(build(handle)))
+}
+
+/// builder() for factories that may return actors with Snapshot hooks
+fn instance_builder<F>(build: F) -> ActorBuilder
+where
+    F: FnMut(ManagerHandle) -> Instance + Send + 'static,
+{
+    Arc::new(Mutex::new(build))
+}
+
+/// Register `name` with `mgr` under supervision (see lifecycle), spinning
+/// for its busy-poll budget (see busy_poll), or add it to shared thread
+/// `group` (see shared_thread)
+fn manage_supervised(
+    mgr: &mut Manager,
+    name: &str,
+    build: &ActorBuilder,
+    thread_config: ThreadConfig,
+    group: Option<&str>,
+) {
+    let handle = mgr.get_handle();
+    let build = build.clone();
+    let actor = Supervised::new(name, move || (build.lock().unwrap())(handle.clone()));
+    match group {
+        Some(group) => {
+            if let Some(host) = shared_thread::join(group, name, Box::new(actor)) {
+                mgr.manage(&host.name(), Box::new(host), thread_config);
+            }
+        }
+        None => mgr.manage(name, Box::new(BusyPoll::new(name, Box::new(actor))), thread_config),
+    }
+    health::register_actor(name);
+}
+
+/// manage_supervised() and record the registration for restarts
+fn manage_recorded(mgr: &mut Manager, name: &str, build: ActorBuilder, thread_config: ThreadConfig) {
+    manage_recorded_in(mgr, name, build, thread_config, None);
+}
+
+fn manage_recorded_in(
+    mgr: &mut Manager,
+    name: &str,
+    build: ActorBuilder,
+    thread_config: ThreadConfig,
+    group: Option<&str>,
+) {
+    manage_supervised(mgr, name, &build, thread_config.clone(), group);
+    let group = group.map(str::to_string);
+    MANIFEST.lock().unwrap().push(Registration { name: name.to_string(), build, thread_config, group });
+}
+
 /// Create the Rust Manager
 /// Call this once at startup before registering actors
-#[no_mangle]
+#[export_name = c_symbol!("create_rust_manager")]
 pub extern "C" fn create_rust_manager() {
-    let mgr = Box::new(Manager::new());
-    let ptr = Box::into_raw(mgr);
+    install_manager(Manager::new());
+}
+
+/// create_rust_manager() with a Manager built in Rust. Actors it already
+/// manages run as they are; only actors registered through this module
+/// are supervised and rebuilt by rust_manager_restart().
+pub fn install_manager(mgr: Manager) {
+    let ptr = Box::into_raw(Box::new(mgr));
     let mut guard = RUST_MANAGER.lock().unwrap();
     guard.0 = ptr;
+    MANIFEST.lock().unwrap().clear();
+    shared_thread::clear();
+    snapshot::clear();
+    dependencies::clear();
+    shutdown::reset();
+    health::set_manager_state(ManagerState::Created);
 }
 
 /// Register the RustPingActor with the Rust Manager
 /// Returns the Manager pointer for rust_actor_init()
-#[no_mangle]
+#[export_name = c_symbol!("register_rust_ping_actor")]
 pub extern "C" fn register_rust_ping_actor() -> *const Manager {
-    let mut guard = RUST_MANAGER.lock().unwrap();
-    if !guard.0.is_null() {
-        let mgr = unsafe { &mut *guard.0 };
-        let handle = mgr.get_handle();
-        let actor = RustPingActor::new(handle);
-        mgr.manage("rust_ping", Box::new(actor), ThreadConfig::default());
-        guard.0 as *const Manager
-    } else {
-        std::ptr::null()
-    }
+    register_by_type_name("RustPingActor", None)
 }
 
 /// Register the RustPongActor with the Rust Manager
 /// Returns the Manager pointer for rust_actor_init()
-#[no_mangle]
+#[export_name = c_symbol!("register_rust_pong_actor")]
 pub extern "C" fn register_rust_pong_actor() -> *const Manager {
+    register_by_type_name("RustPongActor", None)
+}
+
+/// Register an instance of an #[interop_actor] type (see registry).
+/// `instance_name` None uses the type's default name.
+/// Returns the Manager pointer, or null if the type is unknown or the name
+/// is taken (see names)
+pub fn register_by_type_name(type_name: &str, instance_name: Option<&str>) -> *const Manager {
+    register_in(type_name, instance_name, None)
+}
+
+/// register_by_type_name() onto the shared thread of `group` (see
+/// shared_thread) instead of a thread of its own
+pub fn register_shared(type_name: &str, instance_name: Option<&str>, group: &str) -> *const Manager {
+    if group.is_empty() {
+        return std::ptr::null();
+    }
+    register_in(type_name, instance_name, Some(group))
+}
+
+fn register_in(type_name: &str, instance_name: Option<&str>, group: Option<&str>) -> *const Manager {
+    match try_register(type_name, instance_name, group) {
+        REGISTER_OK => get_rust_manager(),
+        _ => std::ptr::null(),
+    }
+}
+
+/// Registered (status codes of rust_register_actors())
+pub const REGISTER_OK: i32 = InteropErrorCode::Ok as i32;
+/// Null factory name or empty group name
+pub const REGISTER_BAD_DESCRIPTOR: i32 = InteropErrorCode::InvalidArgument as i32;
+/// No #[interop_actor] type of that name (see registry)
+pub const REGISTER_UNKNOWN_TYPE: i32 = InteropErrorCode::UnknownType as i32;
+/// Instance name already taken (see names)
+pub const REGISTER_NAME_TAKEN: i32 = InteropErrorCode::NameTaken as i32;
+/// create_rust_manager() has not been called
+pub const REGISTER_NO_MANAGER: i32 = InteropErrorCode::NoManager as i32;
+/// Instance name empty or containing a NUL byte (see names)
+pub const REGISTER_INVALID_NAME: i32 = InteropErrorCode::InvalidName as i32;
+
+/// register_in() returning one of the REGISTER_* codes
+fn try_register(type_name: &str, instance_name: Option<&str>, group: Option<&str>) -> i32 {
+    let actor_type = match registry::find(type_name) {
+        Some(t) => t,
+        None => {
+            eprintln!("[Rust Manager] unknown actor type {}; known: {:?}", type_name, registry::type_names());
+            return REGISTER_UNKNOWN_TYPE;
+        }
+    };
+    let name = instance_name.unwrap_or(actor_type.default_name);
+    let rc = claim_name(name);
+    if rc != REGISTER_OK {
+        return rc;
+    }
+    let guard = RUST_MANAGER.lock().unwrap();
+    if guard.0.is_null() {
+        names::release(name);
+        return REGISTER_NO_MANAGER;
+    }
+    let mgr = unsafe { &mut *guard.0 };
+    manage_recorded_in(mgr, name, instance_builder(actor_type.build), ThreadConfig::default(), group);
+    apply_type_options(name, actor_type);
+    REGISTER_OK
+}
+
+/// Validate and claim `name` for a registration, as every entry point
+/// does before building anything: REGISTER_OK, REGISTER_INVALID_NAME or
+/// REGISTER_NAME_TAKEN
+fn claim_name(name: &str) -> i32 {
+    if let Err(e) = names::validate(name) {
+        eprintln!("[Rust Manager] cannot register {:?}: {}", name, e);
+        return REGISTER_INVALID_NAME;
+    }
+    if !names::claim(name) {
+        return REGISTER_NAME_TAKEN;
+    }
+    REGISTER_OK
+}
+
+/// Dependencies and deadlines declared with #[interop_actor] for instance
+/// `name`
+fn apply_type_options(name: &str, actor_type: &registry::ActorType) {
+    for dependency in actor_type.requires {
+        dependencies::require_for_start(name, dependency);
+    }
+    for &(msg_id, micros) in actor_type.deadlines_us {
+        deadlines::set(name, Some(msg_id), Duration::from_micros(micros));
+    }
+}
+
+/// Register `name`, built by `factory` now and again on every restart,
+/// with the Rust Manager. False if there is no Manager or the name is
+/// invalid or taken (see names).
+pub fn register_with<F>(name: &str, factory: F, thread_config: ThreadConfig) -> bool
+where
+    F: FnMut(ManagerHandle) -> Box<dyn Actor> + Send + 'static,
+{
+    if claim_name(name) != REGISTER_OK {
+        return false;
+    }
     let mut guard = RUST_MANAGER.lock().unwrap();
-    if !guard.0.is_null() {
-        let mgr = unsafe { &mut *guard.0 };
-        let handle = mgr.get_handle();
-        let actor = RustPongActor::new(handle);
-        mgr.manage("rust_pong", Box::new(actor), ThreadConfig::default());
-        guard.0 as *const Manager
-    } else {
-        std::ptr::null()
+    if guard.0.is_null() {
+        names::release(name);
+        return false;
+    }
+    let mgr = unsafe { &mut *guard.0 };
+    manage_recorded(mgr, name, builder(factory), thread_config);
+    true
+}
+
+/// Register an instance of an #[interop_actor] type by its Rust type name.
+/// `instance_name` may be null to use the type's default name.
+/// Returns the Manager pointer for rust_actor_init(), or null if the type
+/// is unknown or the name is taken
+#[export_name = c_symbol!("register_actor_by_type_name")]
+pub extern "C" fn register_actor_by_type_name(
+    type_name: *const std::os::raw::c_char,
+    instance_name: *const std::os::raw::c_char,
+) -> *const Manager {
+    match c_str(type_name) {
+        Some(t) => register_by_type_name(t, c_str(instance_name)),
+        None => std::ptr::null(),
+    }
+}
+
+/// Register an instance of an #[interop_actor] type on the shared thread
+/// of `group_name`, which is created with its first member.
+/// `instance_name` may be null to use the type's default name.
+/// Returns the Manager pointer for rust_actor_init(), or null if the type
+/// is unknown, the name is taken or the group name is empty
+#[export_name = c_symbol!("register_actor_shared")]
+pub extern "C" fn register_actor_shared(
+    type_name: *const std::os::raw::c_char,
+    instance_name: *const std::os::raw::c_char,
+    group_name: *const std::os::raw::c_char,
+) -> *const Manager {
+    match (c_str(type_name), c_str(group_name)) {
+        (Some(t), Some(g)) => register_shared(t, c_str(instance_name), g),
+        _ => std::ptr::null(),
+    }
+}
+
+/// One entry of rust_register_actors()
+#[repr(C)]
+pub struct ActorDescriptor {
+    /// #[interop_actor] type name
+    pub factory_name: *const std::os::raw::c_char,
+    /// Null for the type's default name
+    pub instance_name: *const std::os::raw::c_char,
+    /// Shared thread group (see shared_thread), null for a thread of its own
+    pub thread_group: *const std::os::raw::c_char,
+}
+
+/// Register `count` actors in one call, as register_actor_by_type_name()
+/// or register_actor_shared() would one by one. Writes a REGISTER_* code
+/// per entry to `status` (may be null) and returns how many registered;
+/// a failed entry does not stop the rest.
+#[export_name = c_symbol!("rust_register_actors")]
+pub extern "C" fn rust_register_actors(
+    descriptors: *const ActorDescriptor,
+    count: usize,
+    status: *mut i32,
+) -> i32 {
+    if descriptors.is_null() || count == 0 {
+        return 0;
+    }
+    let descriptors = unsafe { std::slice::from_raw_parts(descriptors, count) };
+    let mut registered = 0;
+    for (i, desc) in descriptors.iter().enumerate() {
+        let group = c_str(desc.thread_group);
+        let rc = match c_str(desc.factory_name) {
+            Some(t) if desc.thread_group.is_null() || group.is_some_and(|g| !g.is_empty()) => {
+                try_register(t, c_str(desc.instance_name), group)
+            }
+            _ => REGISTER_BAD_DESCRIPTOR,
+        };
+        if rc == REGISTER_OK {
+            registered += 1;
+        }
+        if !status.is_null() {
+            unsafe { *status.add(i) = rc };
+        }
+    }
+    registered
+}
+
+/// Register `n` instances built by `factory(index, handle)`, named
+/// `base-0` .. `base-(n-1)`, each on its own thread with the default
+/// ThreadConfig. Returns the group, or None if there is no Manager or a
+/// name is invalid or taken (then nothing is registered).
+pub fn spawn_n<F>(base: &str, factory: F, n: usize) -> Option<ActorGroup>
+where
+    F: Fn(usize, ManagerHandle) -> Box<dyn Actor> + Send + Sync + 'static,
+{
+    spawn_n_with(base, factory, n, |_| ThreadConfig::default())
+}
+
+/// spawn_n() with `thread_config(index)` for each instance, e.g. to pin
+/// workers to different cores
+pub fn spawn_n_with<F, C>(base: &str, factory: F, n: usize, thread_config: C) -> Option<ActorGroup>
+where
+    F: Fn(usize, ManagerHandle) -> Box<dyn Actor> + Send + Sync + 'static,
+    C: Fn(usize) -> ThreadConfig,
+{
+    spawn_instances(base, move |i, handle| Instance::from(factory(i, handle)), n, thread_config)
+}
+
+/// spawn_n_with() for factories that may return actors with Snapshot hooks
+fn spawn_instances<F, C>(base: &str, factory: F, n: usize, thread_config: C) -> Option<ActorGroup>
+where
+    F: Fn(usize, ManagerHandle) -> Instance + Send + Sync + 'static,
+    C: Fn(usize) -> ThreadConfig,
+{
+    let group = ActorGroup::new(base, n);
+    for (i, name) in group.names().iter().enumerate() {
+        if claim_name(name) != REGISTER_OK {
+            group.names()[..i].iter().for_each(|claimed| names::release(claimed));
+            return None;
+        }
+    }
+
+    let mut guard = RUST_MANAGER.lock().unwrap();
+    if guard.0.is_null() {
+        group.names().iter().for_each(|claimed| names::release(claimed));
+        return None;
+    }
+    let mgr = unsafe { &mut *guard.0 };
+    let factory = Arc::new(factory);
+    for (i, name) in group.names().iter().enumerate() {
+        let factory = factory.clone();
+        manage_recorded(mgr, name, instance_builder(move |handle| factory(i, handle)), thread_config(i));
+    }
+    group::add(&group);
+    Some(group)
+}
+
+/// Register `n` instances of an #[interop_actor] type named
+/// `base_name-0` .. `base_name-(n-1)`.
+/// Returns the Manager pointer for rust_actor_init(), or null if the type
+/// is unknown, n is not positive or a name is taken
+#[export_name = c_symbol!("spawn_actor_group")]
+pub extern "C" fn spawn_actor_group(
+    type_name: *const std::os::raw::c_char,
+    base_name: *const std::os::raw::c_char,
+    n: std::os::raw::c_int,
+) -> *const Manager {
+    let (type_name, base) = match (c_str(type_name), c_str(base_name)) {
+        (Some(t), Some(b)) if n > 0 => (t, b),
+        _ => return std::ptr::null(),
+    };
+    let actor_type = match registry::find(type_name) {
+        Some(t) => t,
+        None => {
+            eprintln!("[Rust Manager] unknown actor type {}; known: {:?}", type_name, registry::type_names());
+            return std::ptr::null();
+        }
+    };
+    match spawn_instances(base, move |_, handle| (actor_type.build)(handle), n as usize, |_| ThreadConfig::default()) {
+        Some(group) => {
+            for name in group.names() {
+                apply_type_options(name, actor_type);
+            }
+            get_rust_manager()
+        }
+        None => std::ptr::null(),
     }
 }
 
 /// Get pointer to the Rust Manager
 /// For passing to rust_actor_init()
-#[no_mangle]
+#[export_name = c_symbol!("get_rust_manager")]
 pub extern "C" fn get_rust_manager() -> *const Manager {
     let guard = RUST_MANAGER.lock().unwrap();
     guard.0 as *const Manager
@@ -86,6 +451,38 @@ pub extern "C" fn get_rust_manager() -> *const Manager {
 /// # Returns
 /// Some(ActorRef) if found, None otherwise
 pub fn get_actor_ref(name: &str, sender: &str) -> Option<ActorRef> {
+    let resolved = aliases::resolve(name);
+    let name = &*resolved;
+    if let Some(sys) = sys_topics::sys_ref(name, sender) {
+        return Some(sys);
+    }
+    if let Some(config) = crate::config::config_ref(name, sender) {
+        return Some(config);
+    }
+    if let Some(route) = crate::content_routes::route_ref(name, sender) {
+        return Some(route);
+    }
+    if let Some(router) = crate::hash_router::router_ref(name, sender) {
+        return Some(router);
+    }
+    if let Some(probe) = crate::probe::probe_ref(name) {
+        return Some(probe);
+    }
+    if let Some(member) = shared_thread::member_ref(name, sender) {
+        return Some(member);
+    }
+    if let Some(tap) = crate::control::mailbox_ref(name) {
+        return Some(tap);
+    }
+    #[cfg(feature = "sim")]
+    if let Some(sim) = crate::sim::rust_ref(name, sender) {
+        return Some(sim);
+    }
+    manager_ref(name, sender).map(|actor| tenant::guard(name, sender, actor))
+}
+
+/// `name` as the Rust Manager knows it, without the hooks above
+pub(crate) fn manager_ref(name: &str, sender: &str) -> Option<ActorRef> {
     let guard = RUST_MANAGER.lock().unwrap();
     if !guard.0.is_null() {
         let mgr = unsafe { &*guard.0 };
@@ -96,138 +493,609 @@ pub fn get_actor_ref(name: &str, sender: &str) -> Option<ActorRef> {
 }
 
 /// Initialize and start all Rust actors
-/// This sends Start message to all actors
-#[no_mangle]
+/// This sends Start message to all actors. With the startup barrier
+/// enabled, Start is held until interop_cpp_ready() has also been called;
+/// with dependencies declared, until they are registered (see dependencies).
+#[export_name = c_symbol!("rust_manager_init")]
 pub extern "C" fn rust_manager_init() {
+    let released = startup_barrier::signal(Side::Rust);
+    if startup_barrier::is_enabled() && !released {
+        return;  // Start sent from interop_cpp_ready()
+    }
+    start_rust_manager();
+}
+
+/// Signal that the C++ runtime is ready: its actors are registered and
+/// cpp_actor_init() has run. Installs the C++ lookup and, with the startup
+/// barrier enabled, releases Rust Start messages if Rust is ready too.
+/// Returns 0, or Incompatible if the C++ side lacks capabilities Rust
+/// needs (see capabilities); C++ is then not marked ready.
+#[export_name = c_symbol!("interop_cpp_ready")]
+pub extern "C" fn interop_cpp_ready() -> c_int {
+    let rc = init_cpp_actor_lookup();
+    if rc != 0 {
+        return rc;
+    }
+    if startup_barrier::signal(Side::Cpp) && startup_barrier::is_enabled() {
+        start_rust_manager();
+    }
+    0
+}
+
+fn start_rust_manager() {
+    if dependencies::is_empty() {
+        start_now();
+        return;
+    }
+    // Off the caller's thread: C++ actors it registers next must be found
+    std::thread::spawn(|| match dependencies::check(|name| get_actor_ref(name, "").is_some()) {
+        Ok(()) => start_now(),
+        Err(e) => eprintln!("[Rust Manager] not starting Rust actors: {}", e),
+    });
+}
+
+fn start_now() {
     let mut guard = RUST_MANAGER.lock().unwrap();
     if !guard.0.is_null() {
         let mgr = unsafe { &mut *guard.0 };
         mgr.init();
+        stats::start();
+        health::set_manager_state(ManagerState::Running);
     }
 }
 
-/// Shutdown all Rust actors and wait for threads to finish
-#[no_mangle]
+/// Shutdown all Rust actors and wait for threads to finish. Background
+/// tasks are cancelled first.
+#[export_name = c_symbol!("rust_manager_end")]
 pub extern "C" fn rust_manager_end() {
+    crate::tasks::cancel_all();
+    end_manager();
+}
+
+fn end_manager() {
+    crate::post::clear_cache();
+    crate::lookup_cache::invalidate_all();
     let mut guard = RUST_MANAGER.lock().unwrap();
     if !guard.0.is_null() {
         let mgr = unsafe { &mut *guard.0 };
+        crate::passivation::set_ending(true);
         mgr.end();
+        crate::passivation::set_ending(false);
+        health::set_manager_state(ManagerState::Stopped);
     }
 }
 
+// Serializes rust_manager_restart() calls
+static RESTARTING: Mutex<()> = Mutex::new(());
+
+/// Tear down every Rust actor and start them again, for when the Rust side
+/// is wedged but the C++ engine must keep running.
+///
+/// Ends the Manager as rust_manager_end() does (actors get End and their
+/// threads are joined) but keeps background tasks (see tasks), replaces
+/// the Manager with a fresh one holding a new instance of every actor
+/// registered since create_rust_manager() (same names, factories, thread
+/// configs and shared thread groups, in registration order), re-points the bridge at it as
+/// rust_actor_init() does and starts it as rust_manager_init() does. Sends
+/// to Rust fail with NotFound while the restart is in progress. Rust actor
+/// state is lost unless the actor has Snapshot hooks (see snapshot).
+/// Returns the number of actors restarted, or NoManager if there is no
+/// Manager.
+#[export_name = c_symbol!("rust_manager_restart")]
+pub extern "C" fn rust_manager_restart() -> c_int {
+    let _restarting = RESTARTING.lock().unwrap();
+    if get_rust_manager().is_null() {
+        return InteropErrorCode::NoManager as c_int;
+    }
+
+    crate::rust_actor_bridge::rust_actor_shutdown();
+    end_manager();
+
+    let mut mgr = Box::new(Manager::new());
+    stats::clear_actors();
+    shared_thread::clear();
+    let manifest = MANIFEST.lock().unwrap();
+    for reg in manifest.iter() {
+        manage_supervised(&mut mgr, &reg.name, &reg.build, reg.thread_config.clone(), reg.group.as_deref());
+    }
+    let restarted = manifest.len();
+    drop(manifest);
+
+    let old = {
+        let mut guard = RUST_MANAGER.lock().unwrap();
+        std::mem::replace(&mut guard.0, Box::into_raw(mgr))
+    };
+    drop(unsafe { Box::from_raw(old) });
+    health::set_manager_state(ManagerState::Created);
+
+    crate::rust_actor_bridge::rust_actor_init(get_rust_manager());
+    init_cpp_actor_lookup();
+    start_rust_manager();
+    eprintln!("[Rust Manager] restarted {} actors", restarted);
+    restarted.min(c_int::MAX as usize) as c_int
+}
+
 /// Register the RustPublisher with the Rust Manager
 /// Returns the Manager pointer for rust_actor_init()
-#[no_mangle]
+#[export_name = c_symbol!("register_rust_publisher")]
 pub extern "C" fn register_rust_publisher() -> *const Manager {
+    register_by_type_name("RustPublisher", None)
+}
+
+/// Register the RustSubscriber with the Rust Manager
+/// Returns the Manager pointer for rust_actor_init()
+#[export_name = c_symbol!("register_rust_subscriber")]
+pub extern "C" fn register_rust_subscriber() -> *const Manager {
+    register_by_type_name("RustSubscriber", None)
+}
+
+/// Register a C++-implemented actor with the Rust Manager
+/// The actor runs on its own Rust thread and is driven through `vtable`.
+/// Returns the Manager pointer for rust_actor_init(), or null on bad input
+/// or a name that is invalid or already taken
+#[export_name = c_symbol!("register_cpp_hosted_actor")]
+pub extern "C" fn register_cpp_hosted_actor(
+    name: *const std::os::raw::c_char,
+    vtable: CppActorVTable,
+) -> *const Manager {
+    let name_str = match c_str(name) {
+        Some(s) => s,
+        None => return std::ptr::null(),
+    };
+    if claim_name(name_str) != REGISTER_OK {
+        return std::ptr::null();
+    }
     let mut guard = RUST_MANAGER.lock().unwrap();
     if !guard.0.is_null() {
         let mgr = unsafe { &mut *guard.0 };
-        let handle = mgr.get_handle();
-        let actor = RustPublisher::new(handle);
-        mgr.manage("rust_publisher", Box::new(actor), ThreadConfig::default());
+        let mut build = CppHostedActor::factory(vtable);
+        manage_recorded(mgr, name_str, builder(move |_| Box::new(build())), ThreadConfig::default());
         guard.0 as *const Manager
     } else {
+        names::release(name_str);
         std::ptr::null()
     }
 }
 
-/// Register the RustSubscriber with the Rust Manager
-/// Returns the Manager pointer for rust_actor_init()
-#[no_mangle]
-pub extern "C" fn register_rust_subscriber() -> *const Manager {
+/// Register the MQTT bridge with the Rust Manager
+/// `topics` is a comma-separated list of interop topics to mirror from
+/// `publisher`. `inbound_target` may be null to ignore inbound MQTT traffic.
+/// Returns the Manager pointer for rust_actor_init(), or null on bad input
+/// or a name that is already taken
+#[cfg(feature = "mqtt")]
+#[export_name = c_symbol!("register_mqtt_bridge")]
+pub extern "C" fn register_mqtt_bridge(
+    host: *const std::os::raw::c_char,
+    port: u16,
+    prefix: *const std::os::raw::c_char,
+    publisher: *const std::os::raw::c_char,
+    topics: *const std::os::raw::c_char,
+    inbound_target: *const std::os::raw::c_char,
+) -> *const Manager {
+    use crate::mqtt_bridge::{MqttBridge, MqttBridgeConfig, MQTT_BRIDGE_NAME};
+
+    let (host, prefix, publisher, topics) = match (c_str(host), c_str(prefix), c_str(publisher), c_str(topics)) {
+        (Some(h), Some(p), Some(pb), Some(t)) => (h, p, pb, t),
+        _ => return std::ptr::null(),
+    };
+    let config = MqttBridgeConfig {
+        host: host.to_string(),
+        port,
+        prefix: prefix.to_string(),
+        publisher: publisher.to_string(),
+        topics: split_list(topics),
+        inbound_target: c_str(inbound_target).map(|s| s.to_string()),
+    };
+
+    if claim_name(MQTT_BRIDGE_NAME) != REGISTER_OK {
+        return std::ptr::null();
+    }
     let mut guard = RUST_MANAGER.lock().unwrap();
     if !guard.0.is_null() {
         let mgr = unsafe { &mut *guard.0 };
-        let handle = mgr.get_handle();
-        let actor = RustSubscriber::new(handle);
-        mgr.manage("rust_price_monitor", Box::new(actor), ThreadConfig::default());
+        let build = builder(move |_| Box::new(MqttBridge::new(config.clone())));
+        manage_recorded(mgr, MQTT_BRIDGE_NAME, build, ThreadConfig::default());
         guard.0 as *const Manager
     } else {
+        names::release(MQTT_BRIDGE_NAME);
         std::ptr::null()
     }
 }
 
-// ============================================================================
-// C++ Actor Lookup Integration
-// ============================================================================
-
-use std::os::raw::{c_char, c_int, c_void};
+/// Register a Kafka source with the Rust Manager
+/// `brokers` and `subscribers` are comma-separated lists; every record on
+/// `topic` is decoded as a wire frame and sent to each subscriber.
+/// Returns the Manager pointer for rust_actor_init(), or null on bad input
+/// or a name that is already taken
+#[cfg(feature = "kafka")]
+#[export_name = c_symbol!("register_kafka_source")]
+pub extern "C" fn register_kafka_source(
+    brokers: *const std::os::raw::c_char,
+    topic: *const std::os::raw::c_char,
+    group: *const std::os::raw::c_char,
+    subscribers: *const std::os::raw::c_char,
+) -> *const Manager {
+    use crate::kafka_connector::{KafkaSource, KafkaSourceConfig, KAFKA_SOURCE_NAME};
 
-// FFI functions to send to C++ actors
-extern "C" {
-    fn cpp_actor_send(
-        actor_name: *const c_char,
-        sender_name: *const c_char,
-        msg_type: c_int,
-        msg_data: *const c_void,
-    ) -> c_int;
+    let (brokers, topic, group, subscribers) = match (c_str(brokers), c_str(topic), c_str(group), c_str(subscribers)) {
+        (Some(b), Some(t), Some(g), Some(s)) => (b, t, g, s),
+        _ => return std::ptr::null(),
+    };
+    let config = KafkaSourceConfig {
+        brokers: split_list(brokers),
+        topic: topic.to_string(),
+        group: group.to_string(),
+        subscribers: split_list(subscribers),
+    };
 
-    fn cpp_actor_exists(name: *const c_char) -> c_int;
+    if claim_name(KAFKA_SOURCE_NAME) != REGISTER_OK {
+        return std::ptr::null();
+    }
+    let mut guard = RUST_MANAGER.lock().unwrap();
+    if !guard.0.is_null() {
+        let mgr = unsafe { &mut *guard.0 };
+        let build = builder(move |_| Box::new(KafkaSource::new(config.clone())));
+        manage_recorded(mgr, KAFKA_SOURCE_NAME, build, ThreadConfig::default());
+        guard.0 as *const Manager
+    } else {
+        names::release(KAFKA_SOURCE_NAME);
+        std::ptr::null()
+    }
 }
 
-/// The send function that will be passed to CppActorRef.
-/// This dispatches by message_id, downcasts to concrete type, converts to C struct,
-/// and calls the FFI function. Actors just call send() - they don't know about FFI.
-fn cpp_send_fn(target: &str, sender: &str, msg: &dyn actors::Message) -> i32 {
-    use crate::interop_messages::*;
+/// Register a Kafka sink with the Rust Manager
+/// Subscribes to `topics` (comma-separated) on `publisher` and writes the
+/// messages to the Kafka `topic`. `msg_ids` may be null to forward every
+/// message type the sink handles.
+/// Returns the Manager pointer for rust_actor_init(), or null on bad input
+/// or a name that is already taken
+#[cfg(feature = "kafka")]
+#[export_name = c_symbol!("register_kafka_sink")]
+pub extern "C" fn register_kafka_sink(
+    brokers: *const std::os::raw::c_char,
+    topic: *const std::os::raw::c_char,
+    publisher: *const std::os::raw::c_char,
+    topics: *const std::os::raw::c_char,
+    msg_ids: *const i32,
+    msg_id_count: usize,
+) -> *const Manager {
+    use crate::kafka_connector::{KafkaSink, KafkaSinkConfig, KAFKA_SINK_NAME};
 
-    let target_cstr = CString::new(target).unwrap();
-    let sender_cstr = if sender.is_empty() {
-        None
+    let (brokers, topic, publisher, topics) = match (c_str(brokers), c_str(topic), c_str(publisher), c_str(topics)) {
+        (Some(b), Some(t), Some(p), Some(ts)) => (b, t, p, ts),
+        _ => return std::ptr::null(),
+    };
+    let msg_ids = if msg_ids.is_null() {
+        Vec::new()
     } else {
-        Some(CString::new(sender).unwrap())
+        unsafe { std::slice::from_raw_parts(msg_ids, msg_id_count).to_vec() }
+    };
+    let config = KafkaSinkConfig {
+        brokers: split_list(brokers),
+        topic: topic.to_string(),
+        publisher: publisher.to_string(),
+        topics: split_list(topics),
+        msg_ids,
     };
-    let sender_ptr = sender_cstr.as_ref().map_or(std::ptr::null(), |s| s.as_ptr());
 
-    let msg_id = msg.message_id();
+    if claim_name(KAFKA_SINK_NAME) != REGISTER_OK {
+        return std::ptr::null();
+    }
+    let mut guard = RUST_MANAGER.lock().unwrap();
+    if !guard.0.is_null() {
+        let mgr = unsafe { &mut *guard.0 };
+        let build = builder(move |_| Box::new(KafkaSink::new(config.clone())));
+        manage_recorded(mgr, KAFKA_SINK_NAME, build, ThreadConfig::default());
+        guard.0 as *const Manager
+    } else {
+        names::release(KAFKA_SINK_NAME);
+        std::ptr::null()
+    }
+}
+
+/// Register a WASM module as an actor with the Rust Manager
+/// The module at `wasm_path` is compiled once; the actor exchanges portable
+/// frames with it (see wasm_actor for the guest ABI).
+/// Returns the Manager pointer for rust_actor_init(), or null on bad input,
+/// an invalid or taken name or a module that does not compile
+#[cfg(feature = "wasm")]
+#[export_name = c_symbol!("register_wasm_actor")]
+pub extern "C" fn register_wasm_actor(
+    name: *const std::os::raw::c_char,
+    wasm_path: *const std::os::raw::c_char,
+) -> *const Manager {
+    use crate::wasm_actor::{WasmActor, WasmModule};
 
-    // Dispatch by message ID, downcast, convert to C struct, call FFI
-    match msg_id {
-        MSG_PING => {
-            if let Some(m) = msg.as_any().downcast_ref::<Ping>() {
-                let c_msg = m.to_c_struct();
-                unsafe { cpp_actor_send(target_cstr.as_ptr(), sender_ptr, msg_id, &c_msg as *const _ as *const c_void) }
-            } else { -3 }
+    let (name_str, path) = match (c_str(name), c_str(wasm_path)) {
+        (Some(n), Some(p)) => (n, p),
+        _ => return std::ptr::null(),
+    };
+    let module = match WasmModule::load(std::path::Path::new(path)) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("[WASM] {}: cannot load {}: {}", name_str, path, e);
+            return std::ptr::null();
         }
-        MSG_PONG => {
-            if let Some(m) = msg.as_any().downcast_ref::<Pong>() {
-                let c_msg = m.to_c_struct();
-                unsafe { cpp_actor_send(target_cstr.as_ptr(), sender_ptr, msg_id, &c_msg as *const _ as *const c_void) }
-            } else { -3 }
+    };
+    if claim_name(name_str) != REGISTER_OK {
+        return std::ptr::null();
+    }
+
+    let mut guard = RUST_MANAGER.lock().unwrap();
+    if !guard.0.is_null() {
+        let mgr = unsafe { &mut *guard.0 };
+        let actor_name = name_str.to_string();
+        let build = builder(move |_| Box::new(WasmActor::new(&actor_name, module.clone())));
+        manage_recorded(mgr, name_str, build, ThreadConfig::default());
+        guard.0 as *const Manager
+    } else {
+        names::release(name_str);
+        std::ptr::null()
+    }
+}
+
+/// Split a comma-separated argument, dropping empty entries
+#[cfg(any(feature = "mqtt", feature = "kafka"))]
+fn split_list(s: &str) -> Vec<String> {
+    s.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect()
+}
+
+// ============================================================================
+// C++ Actor Lookup Integration
+// ============================================================================
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+
+/// The send function that will be passed to CppActorRef.
+/// Retries transient failures under the policy from retry::set_cpp_retry_policy().
+/// Returns shutdown::SHUTTING_DOWN once shutdown has been requested (see
+/// shutdown).
+/// Returns circuit::CIRCUIT_OPEN without calling C++ while the sender ->
+/// target circuit is open (see circuit).
+/// With an outbound queue for the target's runtime the FFI call is made by
+/// its flusher thread instead (see outbound).
+/// A RawPayload over the bridge size limit goes as PayloadChunks if
+/// chunking is on, otherwise the send returns size_limit::MESSAGE_TOO_LARGE.
+/// Failures the sender should react to also come back to it as an
+/// InteropError (see interop_error).
+/// A target that has since become a Rust actor is sent to in process once
+/// the send is admitted, without conversion or FFI (see locality).
+/// A target or sender name that cannot cross to C returns INVALID_NAME
+/// and is recorded as a dead letter (see names).
+fn cpp_send_fn(target: &str, sender: &str, msg: &dyn actors::Message) -> i32 {
+    cpp_send_named(target, sender, msg, None)
+}
+
+/// C names of a send kept by the caller (see cached_send); None builds
+/// them per send
+#[derive(Clone, Copy)]
+pub(crate) struct CNames<'a> {
+    pub target: &'a CStr,
+    pub sender: *const c_char,
+}
+
+/// cpp_send_fn() with the C names already built
+pub(crate) fn cpp_send_named(target: &str, sender: &str, msg: &dyn actors::Message, names: Option<CNames>) -> i32 {
+    let rc = cpp_send_checked(target, sender, msg, names);
+    interop_error::report(sender, target, msg.message_id(), rc);
+    interop_log::send(sender, target, msg.message_id(), rc);
+    rc
+}
+
+fn cpp_send_checked(target: &str, sender: &str, msg: &dyn actors::Message, c_names: Option<CNames>) -> i32 {
+    // Kept names were checked when the caller built them
+    let checked = match c_names {
+        Some(_) => Ok(()),
+        None => names::send_names(target, sender).map(drop),
+    };
+    if let Err(e) = checked {
+        eprintln!("[Rust Manager] {:?} -> {:?} refused: {}", sender, target, e);
+        let rc = InteropErrorCode::InvalidName as i32;
+        health::record_send_result(sender, target, msg.message_id(), rc);
+        return rc;
+    }
+    if !shutdown::admits(sender) {
+        return shutdown::SHUTTING_DOWN;
+    }
+    // A Rust actor has the name now: admitted like any send, then
+    // delivered in process
+    let local = locality::local_ref(target);
+    // Its runtime is gone: neither a retry nor the circuit would help
+    if local.is_none() && cpp_runtime::detached_owner(target).is_some() {
+        let rc = cpp_runtime::RUNTIME_DETACHED;
+        health::record_send_result(sender, target, msg.message_id(), rc);
+        return rc;
+    }
+    orphans::check_send(sender, target);
+    if !policy::allow(sender, target, msg.message_id()) {
+        return policy::POLICY_DENIED;
+    }
+    let chunks = match size_limit::split_for_bridge(msg) {
+        Ok(chunks) => chunks,
+        Err(e) => {
+            eprintln!("[Rust Manager] {} -> {} refused: {}", sender, target, e);
+            return size_limit::MESSAGE_TOO_LARGE;
         }
-        MSG_SUBSCRIBE => {
-            if let Some(m) = msg.as_any().downcast_ref::<Subscribe>() {
-                let c_msg = m.to_c_struct();
-                unsafe { cpp_actor_send(target_cstr.as_ptr(), sender_ptr, msg_id, &c_msg as *const _ as *const c_void) }
-            } else { -3 }
+    };
+    match rate_limit::admit(sender, target, None) {
+        Admission::Send => {}
+        Admission::Drop => return 0,
+        Admission::Reject => return rate_limit::RATE_LIMITED,
+    }
+    tap::observe(sender, target, msg);
+    lease::observe(sender, target, msg);
+    broker::observe(sender, target, msg);
+    if let Some(rc) = local.and_then(|actor| locality::send(&actor, target, sender, msg)) {
+        return rc;
+    }
+    match chunks {
+        Some(chunks) => {
+            for chunk in &chunks {
+                let rc = outbound::offer(target, sender, chunk)
+                    .unwrap_or_else(|| cpp_send_retrying_named(target, sender, chunk, c_names));
+                if rc != 0 {
+                    return rc;
+                }
+            }
+            0
         }
-        MSG_MARKETUPDATE => {
-            if let Some(m) = msg.as_any().downcast_ref::<MarketUpdate>() {
-                let c_msg = m.to_c_struct();
-                unsafe { cpp_actor_send(target_cstr.as_ptr(), sender_ptr, msg_id, &c_msg as *const _ as *const c_void) }
-            } else { -3 }
+        None => outbound::offer(target, sender, msg)
+            .unwrap_or_else(|| cpp_send_retrying_named(target, sender, msg, c_names)),
+    }
+}
+
+pub(crate) fn cpp_send_retrying(target: &str, sender: &str, msg: &dyn actors::Message) -> i32 {
+    cpp_send_retrying_named(target, sender, msg, None)
+}
+
+fn cpp_send_retrying_named(target: &str, sender: &str, msg: &dyn actors::Message, c_names: Option<CNames>) -> i32 {
+    send_retrying_with(target, sender, msg, || cpp_send_once(target, sender, msg, c_names))
+}
+
+/// cpp_send_retrying() with `attempt` in place of the FFI call, for
+/// stand-ins for the C++ side (see sim)
+pub(crate) fn send_retrying_with<F>(target: &str, sender: &str, msg: &dyn actors::Message, attempt: F) -> i32
+where
+    F: FnMut() -> i32,
+{
+    let policy = retry::cpp_retry_policy();
+    let on_failure = retry::cpp_failure_callback();
+    if !circuit::admit(sender, target) {
+        if let Some(cb) = &on_failure {
+            cb(target, msg.message_id(), circuit::CIRCUIT_OPEN);
         }
-        _ => -2  // Unknown message type
+        return circuit::CIRCUIT_OPEN;
     }
+    let rc = retry::send_with_retry(&policy, on_failure.as_ref(), target, msg.message_id(), attempt);
+    circuit::record(sender, target, rc);
+    health::record_send_result(sender, target, msg.message_id(), rc);
+    rc
 }
 
-/// Lookup function for C++ actors
-/// Returns Some(ActorRef::Cpp) if the actor exists in C++
-fn cpp_actor_lookup(name: &str, sender: &str) -> Option<ActorRef> {
-    let name_cstr = CString::new(name).unwrap();
-    unsafe {
-        if cpp_actor_exists(name_cstr.as_ptr()) != 0 {
-            Some(ActorRef::Cpp(CppActorRef::new(name, sender, cpp_send_fn)))
-        } else {
-            None
+/// One attempt for cpp_send_fn.
+/// Converts any interop message to its C struct (stamping sequenced fields)
+/// and calls the FFI function. Actors just call send() - they don't know about FFI.
+fn cpp_send_once(target: &str, sender: &str, msg: &dyn actors::Message, c_names: Option<CNames>) -> i32 {
+    use crate::interop_messages::{c_struct_size, with_c_struct};
+
+    let built;
+    let (target_c, sender_ptr) = match c_names {
+        Some(kept) => (kept.target, kept.sender),
+        None => {
+            built = match names::send_names(target, sender) {
+                Ok(names) => names,
+                Err(_) => return InteropErrorCode::InvalidName as i32,
+            };
+            (built.0.as_c_str(), built.1.as_ref().map_or(std::ptr::null(), ActorName::as_ptr))
         }
+    };
+
+    let msg_id = msg.message_id();
+    let send = |data: *const std::os::raw::c_void| {
+        boundary::record(Direction::ToCpp, msg_id);
+        interop_log::conversion(Direction::ToCpp, sender, target, msg);
+        unsafe { cpp_runtime::send(target, target_c, sender_ptr, msg_id, data) }
+    };
+    if let Some(rc) = conversion_cache::with_cached(msg, &send) {
+        return rc;
+    }
+
+    let sent = with_c_struct(msg, |topic, seq| sequencing::stamp(sender, target, topic, seq), send);
+    match sent {
+        Some(rc) => rc,
+        None if c_struct_size(msg_id).is_some() => InteropErrorCode::WrongType as i32,
+        None => InteropErrorCode::UnknownMessage as i32,
+    }
+}
+
+/// Lookup function for C++ actors (resolver::CppRegistry)
+/// Returns Some(ActorRef::Cpp) if the actor exists in any C++ runtime
+/// (see cpp_runtime), None for a name that cannot cross to C (see names).
+/// Answers are cached, see lookup_cache.
+pub(crate) fn cpp_actor_lookup(name: &str, sender: &str) -> Option<ActorRef> {
+    if names::validate(name).is_err() {
+        return None;
+    }
+    let exists = crate::lookup_cache::cpp_exists(name, || cpp_runtime::exists(name));
+    interop_log::lookup(sender, name, exists);
+    if exists {
+        orphans::acquired(sender, name, orphans::Side::Cpp);
+        Some(ActorRef::Cpp(CppActorRef::new(name, sender, cpp_send_fn)))
+    } else {
+        None
     }
 }
 
 /// Initialize C++ actor lookup for cross-language transparency.
 /// Call this after cpp_actor_init() and before using Manager::get_ref().
-#[no_mangle]
-pub extern "C" fn init_cpp_actor_lookup() {
-    register_cpp_lookup(cpp_actor_lookup);
+/// A resolver installed with resolver::set_actor_resolver() is kept.
+/// Returns 0, or Incompatible without installing the lookup if the C++
+/// side lacks functions or messages Rust needs; interop_capability_report()
+/// says what.
+#[export_name = c_symbol!("init_cpp_actor_lookup")]
+pub extern "C" fn init_cpp_actor_lookup() -> c_int {
+    if let Err(e) = capabilities::verify() {
+        eprintln!("[Rust Manager] not connecting to C++: {}", e);
+        return InteropErrorCode::Incompatible as c_int;
+    }
+    install_cpp_lookup();
+    0
+}
+
+/// End the Manager unless it has been ended, free it and forget every
+/// registration (see teardown). False if there was no Manager.
+pub(crate) fn free_manager() -> bool {
+    crate::rust_actor_bridge::rust_actor_shutdown();
+    if health::manager_state() != ManagerState::Stopped {
+        end_manager();
+    }
+    let old = std::mem::replace(&mut RUST_MANAGER.lock().unwrap().0, std::ptr::null_mut());
+    MANIFEST.lock().unwrap().clear();
+    if old.is_null() {
+        return false;
+    }
+    drop(unsafe { Box::from_raw(old) });
+    health::set_manager_state(ManagerState::None);
+    true
+}
+
+/// Handle of the Manager, if `name` was registered through this module
+pub(crate) fn registered_handle(name: &str) -> Option<ManagerHandle> {
+    if !is_supervised(name) {
+        return None;
+    }
+    let guard = RUST_MANAGER.lock().unwrap();
+    if guard.0.is_null() {
+        return None;
+    }
+    Some(unsafe { &*guard.0 }.get_handle())
+}
+
+/// True if `name` was registered through this module, so runs under
+/// supervision (see lifecycle)
+pub(crate) fn is_supervised(name: &str) -> bool {
+    MANIFEST.lock().unwrap().iter().any(|reg| reg.name == name)
+}
+
+/// Build `name` with `build` from now on, in rust_manager_restart() (see
+/// replace)
+pub(crate) fn rebuild_with(name: &str, build: ActorBuilder) {
+    if let Some(reg) = MANIFEST.lock().unwrap().iter_mut().find(|reg| reg.name == name) {
+        reg.build = build;
+    }
+}
+
+/// True if `name` is a claimed Rust actor or, once the C++ lookup is
+/// installed, resolves; does not take the Manager lock, so actor threads
+/// may poll it while the Manager is starting
+pub(crate) fn is_registered(name: &str) -> bool {
+    names::is_rust(name) || (resolver::is_installed() && resolver::resolve(name, "").is_some())
+}
+
+/// Route lookups of names Rust does not own to the resolver (see
+/// resolver) without checking capabilities, for C++ runtimes that were
+/// checked when attached
+pub(crate) fn install_cpp_lookup() {
+    resolver::install();
 }
@@ -0,0 +1,215 @@
+//! Message schemas at run time
+//!
+//! Codegen describes every message of the IDL in `MESSAGE_SCHEMAS`: its
+//! name, ID, C struct size and fields with their types, C offsets and
+//! rules. Generic tools (wire-tap printers, gateways, bindings for other
+//! languages) look messages up here instead of being compiled against the
+//! generated types:
+//!
+//! ```ignore
+//! let schema = schema::by_name("MarketUpdate").unwrap();
+//! for field in schema.fields {
+//!     println!("{} {:?} at {}", field.name, field.kind, field.offset);
+//! }
+//! ```
+//!
+//! C and other languages get the same as JSON with
+//! `interop_message_schema(name)` (`interop/schema.h`):
+//!
+//! ```json
+//! {"id":1000,"name":"Ping","c_size":4,
+//!  "fields":[{"name":"count","c_type":"int32_t","kind":"i32","array_len":0,
+//!             "map_capacity":0,"offset":0,"sequenced":false,
+//!             "idempotency_key":false,"rules":[]}]}
+//! ```
+//!
+//! `offset` is the field's offset in the C struct (of its `_count` for a
+//! map), so a tool can read fields straight from the struct the bridge
+//! passes. `kind` of a map is the kind of its values; keys are strings.
+
+use std::ffi::CString;
+use std::fmt::Write;
+use std::os::raw::{c_char, c_int};
+
+use crate::codec::JsonValue;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::MESSAGE_SCHEMAS;
+use crate::names::c_str;
+
+/// Type of a field as Rust sees it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    I32,
+    I64,
+    U32,
+    U64,
+    F32,
+    F64,
+    /// int32_t marked bool in the IDL
+    Bool,
+    /// char[N]: InteropString<N>
+    Text,
+    /// interop_string: String
+    String,
+    /// interop_bytes: Vec<u8>
+    Bytes,
+    Decimal,
+    Uuid,
+    Timestamp,
+}
+
+impl FieldKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FieldKind::I32 => "i32",
+            FieldKind::I64 => "i64",
+            FieldKind::U32 => "u32",
+            FieldKind::U64 => "u64",
+            FieldKind::F32 => "f32",
+            FieldKind::F64 => "f64",
+            FieldKind::Bool => "bool",
+            FieldKind::Text => "text",
+            FieldKind::String => "string",
+            FieldKind::Bytes => "bytes",
+            FieldKind::Decimal => "decimal",
+            FieldKind::Uuid => "uuid",
+            FieldKind::Timestamp => "timestamp",
+        }
+    }
+}
+
+/// One field of a message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    /// Type in the C header, e.g. "int64_t" or "interop_decimal"
+    pub c_type: &'static str,
+    pub kind: FieldKind,
+    /// Elements of a fixed array, capacity of a char[N]; 0 otherwise
+    pub array_len: usize,
+    /// Capacity of an INTEROP_MAP; 0 if the field is not a map
+    pub map_capacity: usize,
+    /// Offset in the C struct
+    pub offset: usize,
+    /// Stamped by the bridge (see sequencing)
+    pub sequenced: bool,
+    /// Replays detected by the receiver (see idempotency)
+    pub idempotency_key: bool,
+    /// "valid:" rules (see validation)
+    pub rules: &'static [&'static str],
+}
+
+impl FieldSchema {
+    pub fn is_map(&self) -> bool {
+        self.map_capacity > 0
+    }
+}
+
+/// One message type
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageSchema {
+    pub id: i32,
+    pub name: &'static str,
+    /// Size of the C struct
+    pub c_size: usize,
+    pub fields: &'static [FieldSchema],
+}
+
+impl MessageSchema {
+    pub fn field(&self, name: &str) -> Option<&'static FieldSchema> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// The schema as one JSON object (see the module docs)
+    pub fn to_json(&self) -> String {
+        let mut out = String::with_capacity(64 + 160 * self.fields.len());
+        let _ = write!(out, "{{\"id\":{},\"name\":", self.id);
+        self.name.write_json(&mut out);
+        let _ = write!(out, ",\"c_size\":{},\"fields\":[", self.c_size);
+        for (i, f) in self.fields.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            f.name.write_json(&mut out);
+            out.push_str(",\"c_type\":");
+            f.c_type.write_json(&mut out);
+            let _ = write!(
+                out,
+                ",\"kind\":\"{}\",\"array_len\":{},\"map_capacity\":{},\"offset\":{},\"sequenced\":{},\"idempotency_key\":{},\"rules\":[",
+                f.kind.as_str(),
+                f.array_len,
+                f.map_capacity,
+                f.offset,
+                f.sequenced,
+                f.idempotency_key
+            );
+            for (j, rule) in f.rules.iter().enumerate() {
+                if j > 0 {
+                    out.push(',');
+                }
+                rule.write_json(&mut out);
+            }
+            out.push_str("]}");
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+/// Every message, in header order
+pub fn all() -> &'static [MessageSchema] {
+    MESSAGE_SCHEMAS
+}
+
+pub fn by_name(name: &str) -> Option<&'static MessageSchema> {
+    MESSAGE_SCHEMAS.iter().find(|s| s.name == name)
+}
+
+pub fn by_id(id: i32) -> Option<&'static MessageSchema> {
+    MESSAGE_SCHEMAS.iter().find(|s| s.id == id)
+}
+
+/// ID of message `name`
+pub fn id_of(name: &str) -> Option<i32> {
+    by_name(name).map(|s| s.id)
+}
+
+/// Every schema as a JSON array
+pub fn all_to_json() -> String {
+    let parts: Vec<String> = MESSAGE_SCHEMAS.iter().map(MessageSchema::to_json).collect();
+    format!("[{}]", parts.join(","))
+}
+
+fn into_c(json: String) -> *mut c_char {
+    CString::new(json).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+/// ID of message `name`, or UnknownMessage if there is none
+#[export_name = c_symbol!("interop_message_id")]
+pub extern "C" fn interop_message_id(name: *const c_char) -> c_int {
+    c_str(name).and_then(id_of).unwrap_or(InteropErrorCode::UnknownMessage as c_int)
+}
+
+/// Schema of message `name` as JSON; every schema as a JSON array if
+/// `name` is null. Returns null for an unknown name; free the string with
+/// interop_free_json().
+#[export_name = c_symbol!("interop_message_schema")]
+pub extern "C" fn interop_message_schema(name: *const c_char) -> *mut c_char {
+    if name.is_null() {
+        return into_c(all_to_json());
+    }
+    match c_str(name).and_then(by_name) {
+        Some(s) => into_c(s.to_json()),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// `interop_message_schema()` by message ID
+#[export_name = c_symbol!("interop_message_schema_by_id")]
+pub extern "C" fn interop_message_schema_by_id(msg_id: c_int) -> *mut c_char {
+    match by_id(msg_id) {
+        Some(s) => into_c(s.to_json()),
+        None => std::ptr::null_mut(),
+    }
+}
@@ -0,0 +1,109 @@
+//! Send results as `Result<(), SendError>`
+//!
+//! The bridge reports the outcome of a send as an i32 `InteropErrorCode`
+//! (see interop_errors), which callers are free to ignore and mostly did. The Rust send surface returns `Result<(), SendError>` instead, so
+//! an unchecked failure is a compiler warning:
+//!
+//! ```ignore
+//! gateway_if.send(&order)?;                            // CppActorIF
+//! cached.send_pooled("cpp_subscriber", &update)?;      // CachedSender
+//! gateway_ref.try_send(Box::new(order), None)?;        // ActorRef, via TrySend
+//! ```
+//!
+//! | Code | Variant |
+//! |------|---------|
+//! | `NotFound` | `ActorNotFound` |
+//! | `InvalidName` | `InvalidName` |
+//! | `UnknownMessage`, `WrongType` | `ConversionFailed` |
+//! | `RateLimited`, `OutboundFull`, `QueueFull` | `Backpressure` |
+//! | anything else (policy, size limit, open circuit, shutdown, C++ errors) | `TransportError` |
+//!
+//! `code()` gives the i32 back for C callers and logs. `ActorRef::send()`
+//! to a Rust actor only queues the message and cannot fail, so `try_send`
+//! reports Ok for Rust targets.
+
+use std::fmt;
+
+use actors::{ActorRef, Message};
+
+use crate::interop_errors::InteropErrorCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendError {
+    /// No actor with the target name (`NotFound`)
+    ActorNotFound,
+    /// Target or sender name empty or containing a NUL byte, so it cannot
+    /// cross to C (`InvalidName`, see names)
+    InvalidName,
+    /// Refused to protect the receiver: a rate limit or full queue
+    Backpressure(i32),
+    /// Not an interop message, or not the type its ID says
+    /// (`UnknownMessage`, `WrongType`)
+    ConversionFailed(i32),
+    /// Refused by the bridge (policy, size limit, validation, open circuit,
+    /// shutdown) or failed in C++
+    TransportError(i32),
+}
+
+impl SendError {
+    /// Ok for 0, else the error for a bridge send code
+    pub fn check(code: i32) -> Result<(), SendError> {
+        match InteropErrorCode::from_code(code) {
+            Some(InteropErrorCode::Ok) => Ok(()),
+            Some(InteropErrorCode::NotFound) => Err(SendError::ActorNotFound),
+            Some(InteropErrorCode::InvalidName) => Err(SendError::InvalidName),
+            Some(InteropErrorCode::UnknownMessage | InteropErrorCode::WrongType) => {
+                Err(SendError::ConversionFailed(code))
+            }
+            Some(InteropErrorCode::RateLimited | InteropErrorCode::OutboundFull | InteropErrorCode::QueueFull) => {
+                Err(SendError::Backpressure(code))
+            }
+            _ => Err(SendError::TransportError(code)),
+        }
+    }
+
+    /// The bridge send code
+    pub fn code(&self) -> i32 {
+        match *self {
+            SendError::ActorNotFound => InteropErrorCode::NotFound as i32,
+            SendError::InvalidName => InteropErrorCode::InvalidName as i32,
+            SendError::Backpressure(code) | SendError::ConversionFailed(code) | SendError::TransportError(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SendError::ActorNotFound => write!(f, "actor not found"),
+            SendError::InvalidName => f.write_str(InteropErrorCode::InvalidName.description()),
+            SendError::Backpressure(code) | SendError::ConversionFailed(code) | SendError::TransportError(code) => {
+                match InteropErrorCode::from_code(code) {
+                    Some(known) => f.write_str(known.description()),
+                    None => write!(f, "transport error ({})", code),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for SendError {}
+
+/// `ActorRef::send` with the outcome
+pub trait TrySend {
+    /// Send `msg`; to a C++ actor the bridge's result is returned, to a
+    /// Rust actor the message is queued and the result is Ok
+    fn try_send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) -> Result<(), SendError>;
+}
+
+impl TrySend for ActorRef {
+    fn try_send(&self, msg: Box<dyn Message>, sender: Option<ActorRef>) -> Result<(), SendError> {
+        match self {
+            ActorRef::Cpp(cpp) => SendError::check((cpp.send_fn)(&cpp.target, &cpp.sender, msg.as_ref())),
+            other => {
+                other.send(msg, sender);
+                Ok(())
+            }
+        }
+    }
+}
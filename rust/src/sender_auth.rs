@@ -0,0 +1,205 @@
+//! Pluggable authentication of senders of inbound bridge messages
+//!
+//! The policy (see policy) is a fixed rule table. Deciding who may send
+//! what can also take the host's own state: the exec gateway's session,
+//! an entitlement list reloaded at run time. A hook set here is asked
+//! about every message entering Rust through the bridge, with the sender's
+//! name and the entry point it came in by:
+//!
+//! ```ignore
+//! sender_auth::set_hook(|m: &Inbound| {
+//!     // Only the exec gateway may send order control to the risk actor
+//!     !(m.target == "rust_risk" && ORDER_CONTROL.contains(&m.msg_id)) || m.sender == "cpp_exec_gateway"
+//! });
+//! ```
+//!
+//! The hook runs on the sending thread after the policy, for
+//! `rust_actor_send` (and so `rust_actor_send_id` and `wire::deliver`),
+//! `rust_actor_fast_send`, `rust_actor_borrow_send`, `rust_actor_post` and
+//! `rust_ask`; the last two have sender "". Sends through C++ refs that a
+//! Rust actor now answers (see locality) are asked about as `Send`, as
+//! they would be on the way back in. Targets are resolved names
+//! (see aliases). A rejected message is not delivered: the send returns
+//! `POLICY_DENIED`, the reject is counted per sender, logged, kept in a
+//! ring of the last `AUDIT_CAPACITY` and passed to the audit callback. A
+//! hook that panics rejects. C++ sets a hook with
+//! `interop_set_sender_auth()`.
+
+use std::collections::{HashMap, VecDeque};
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::UNIX_EPOCH;
+
+use crate::clock;
+use crate::names::c_str;
+
+/// Rejects kept for `recent()`
+pub const AUDIT_CAPACITY: usize = 256;
+
+/// Bridge function a message came in by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum Entry {
+    Send = 0,
+    FastSend = 1,
+    BorrowSend = 2,
+    Post = 3,
+    Ask = 4,
+}
+
+impl Entry {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Entry::Send => "send",
+            Entry::FastSend => "fast_send",
+            Entry::BorrowSend => "borrow_send",
+            Entry::Post => "post",
+            Entry::Ask => "ask",
+        }
+    }
+}
+
+/// One inbound message, as the hook sees it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Inbound<'a> {
+    /// "" if the sender gave no name
+    pub sender: &'a str,
+    pub target: &'a str,
+    pub msg_id: i32,
+    pub entry: Entry,
+}
+
+/// One rejected message
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reject {
+    /// Wall clock, ms since the epoch (see clock)
+    pub time_ms: u64,
+    pub sender: String,
+    pub target: String,
+    pub msg_id: i32,
+    pub entry: Entry,
+}
+
+/// True to deliver the message
+pub type Hook = Arc<dyn Fn(&Inbound) -> bool + Send + Sync>;
+
+/// C hook: nonzero delivers the message
+pub type CHook = extern "C" fn(sender: *const c_char, target: *const c_char, msg_id: c_int, entry: c_int) -> c_int;
+
+/// Called for every rejected message with (sender, target, msg_id, entry)
+pub type AuditCallback = extern "C" fn(sender: *const c_char, target: *const c_char, msg_id: c_int, entry: c_int);
+
+// Fast path: no lock while no hook is set
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+static REJECTED: AtomicU64 = AtomicU64::new(0);
+static AUDIT: Mutex<Option<AuditCallback>> = Mutex::new(None);
+static RECENT: Mutex<VecDeque<Reject>> = Mutex::new(VecDeque::new());
+
+lazy_static::lazy_static! {
+    static ref BY_SENDER: Mutex<HashMap<String, u64>> = Mutex::new(HashMap::new());
+}
+
+/// Ask `hook` about every inbound message, replacing an earlier hook
+pub fn set_hook<F>(hook: F)
+where
+    F: Fn(&Inbound) -> bool + Send + Sync + 'static,
+{
+    *HOOK.write().unwrap() = Some(Arc::new(hook));
+    ACTIVE.store(true, Ordering::Release);
+}
+
+/// Deliver inbound messages without asking a hook
+pub fn clear_hook() {
+    *HOOK.write().unwrap() = None;
+    ACTIVE.store(false, Ordering::Release);
+}
+
+pub fn has_hook() -> bool {
+    ACTIVE.load(Ordering::Acquire)
+}
+
+/// Messages rejected so far
+pub fn rejected() -> u64 {
+    REJECTED.load(Ordering::Relaxed)
+}
+
+/// Messages from `sender` rejected so far
+pub fn rejected_from(sender: &str) -> u64 {
+    BY_SENDER.lock().unwrap().get(sender).copied().unwrap_or(0)
+}
+
+/// The last `n` rejects, oldest first
+pub fn recent(n: usize) -> Vec<Reject> {
+    let ring = RECENT.lock().unwrap();
+    ring.iter().skip(ring.len().saturating_sub(n)).cloned().collect()
+}
+
+/// Check a message entering Rust by `entry`. Returns true if it may be
+/// delivered; a reject is counted, logged and audited.
+pub(crate) fn admit(sender: &str, target: &str, msg_id: i32, entry: Entry) -> bool {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return true;
+    }
+    let hook = match HOOK.read().unwrap().clone() {
+        Some(h) => h,
+        None => return true,
+    };
+    let inbound = Inbound { sender, target, msg_id, entry };
+    if panic::catch_unwind(AssertUnwindSafe(|| hook(&inbound))).unwrap_or(false) {
+        return true;
+    }
+    REJECTED.fetch_add(1, Ordering::Relaxed);
+    *BY_SENDER.lock().unwrap().entry(sender.to_string()).or_insert(0) += 1;
+    eprintln!("[SenderAuth] rejected message {} from '{}' to '{}' ({})", msg_id, sender, target, entry.as_str());
+    {
+        let mut ring = RECENT.lock().unwrap();
+        if ring.len() >= AUDIT_CAPACITY {
+            ring.pop_front();
+        }
+        ring.push_back(Reject {
+            time_ms: clock::wall().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+            sender: sender.to_string(),
+            target: target.to_string(),
+            msg_id,
+            entry,
+        });
+    }
+    let cb = *AUDIT.lock().unwrap();
+    if let (Some(cb), Ok(s), Ok(t)) = (cb, CString::new(sender), CString::new(target)) {
+        cb(s.as_ptr(), t.as_ptr(), msg_id, entry as c_int);
+    }
+    false
+}
+
+/// Ask `hook` about every inbound message (entry is an
+/// INTEROP_ENTRY_* value); null clears it
+#[export_name = c_symbol!("interop_set_sender_auth")]
+pub extern "C" fn interop_set_sender_auth(hook: Option<CHook>) {
+    let hook = match hook {
+        Some(h) => h,
+        None => return clear_hook(),
+    };
+    set_hook(move |m: &Inbound| match (CString::new(m.sender), CString::new(m.target)) {
+        (Ok(s), Ok(t)) => hook(s.as_ptr(), t.as_ptr(), m.msg_id, m.entry as c_int) != 0,
+        _ => false,
+    });
+}
+
+/// Set the callback for rejected messages, or clear it with null
+#[export_name = c_symbol!("interop_set_sender_auth_audit_callback")]
+pub extern "C" fn interop_set_sender_auth_audit_callback(cb: Option<AuditCallback>) {
+    *AUDIT.lock().unwrap() = cb;
+}
+
+/// Messages from `sender_name` rejected so far; every sender's if null
+#[export_name = c_symbol!("interop_sender_auth_rejected")]
+pub extern "C" fn interop_sender_auth_rejected(sender_name: *const c_char) -> u64 {
+    if sender_name.is_null() {
+        return rejected();
+    }
+    c_str(sender_name).map_or(0, rejected_from)
+}
@@ -0,0 +1,92 @@
+//! Sequence numbers on subscriptions and gap detection
+//!
+//! With `interop_sequencing_enable(1)`, the bridge stamps every message
+//! with a `sequenced` field (MarketUpdate.seq) as it crosses between C++
+//! and Rust. Numbers start at 1 and count per (publisher, topic,
+//! subscriber), so fan-out to several subscribers does not look like a
+//! gap to any of them. A message that already carries a non-zero seq is
+//! left alone, so a publisher can number its own stream instead.
+//!
+//! Subscribers feed what they receive to a `GapDetector`, which returns a
+//! `GapDetected` message when numbers are skipped, e.g. because a mailbox
+//! or transport dropped updates. The subscriber can then ask the publisher
+//! for a snapshot.
+
+use std::collections::HashMap;
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::interop_messages::GapDetected;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    // (publisher, topic, subscriber) -> last stamped seq
+    static ref STREAMS: Mutex<HashMap<(String, String, String), i64>> = Mutex::new(HashMap::new());
+}
+
+/// Turn bridge sequencing on (1) or off (0)
+#[export_name = c_symbol!("interop_sequencing_enable")]
+pub extern "C" fn interop_sequencing_enable(on: c_int) {
+    ENABLED.store(on != 0, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Stamp `seq` with the next number for the stream if sequencing is on
+/// and the message is not already numbered
+pub fn stamp(publisher: &str, subscriber: &str, topic: &str, seq: &mut i64) {
+    if *seq != 0 || !is_enabled() {
+        return;
+    }
+    let mut streams = STREAMS.lock().unwrap();
+    let key = (publisher.to_string(), topic.to_string(), subscriber.to_string());
+    let next = streams.entry(key).or_insert(0);
+    *next += 1;
+    *seq = *next;
+}
+
+/// Per-subscriber gap detection over sequenced streams
+#[derive(Default)]
+pub struct GapDetector {
+    last: HashMap<(String, String), i64>,
+}
+
+impl GapDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `seq` for (publisher, topic). Returns a GapDetected when
+    /// updates between the last seen number and `seq` are missing.
+    /// Unsequenced messages (seq 0) and duplicates are ignored; seq 1
+    /// after a higher number is treated as a publisher restart.
+    pub fn check(&mut self, publisher: &str, topic: &str, seq: i64) -> Option<GapDetected> {
+        if seq <= 0 {
+            return None;
+        }
+        let last = self.last.entry((publisher.to_string(), topic.to_string())).or_insert(0);
+        let expected = *last + 1;
+        if seq == 1 || seq >= expected {
+            *last = seq;
+        }
+        if seq > expected {
+            Some(GapDetected {
+                publisher: publisher.into(),
+                topic: topic.into(),
+                expected_seq: expected,
+                received_seq: seq,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Forget a stream, e.g. after a snapshot resets it
+    pub fn reset(&mut self, publisher: &str, topic: &str) {
+        self.last.remove(&(publisher.to_string(), topic.to_string()));
+    }
+}
@@ -0,0 +1,174 @@
+//! Low-rate Rust actors sharing one OS thread
+//!
+//! Every actor registered with the Manager gets a thread of its own. A C++
+//! host registering dozens of small monitoring actors can put them in a
+//! shared group instead, scheduled cooperatively on one thread:
+//!
+//! ```c
+//! register_actor_shared("PriceMonitor", "monitor_aapl", "monitors");
+//! register_actor_shared("PriceMonitor", "monitor_msft", "monitors");
+//! register_actor_shared("LagMonitor", NULL, "monitors");
+//! ```
+//!
+//! `ThreadConfig` belongs to actors-rust, so the group is named at
+//! registration rather than in the config (from Rust,
+//! `rust_manager_ffi::register_shared()`). The group is one Manager actor,
+//! `$shared/<group>`, on the thread config of its first member. Members keep their own names: sends to them from
+//! either language are forwarded to the group's mailbox and handed to the
+//! member there, one message at a time in arrival order, so a slow
+//! handler delays the whole group. Start and End reach every member.
+//!
+//! Forwarding copies each message (`clone_message`), so only interop
+//! messages reach members and a group suits low-rate traffic. Members are
+//! supervised as usual (see lifecycle) but do not busy-poll.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use actors::{Actor, ActorContext, ActorRef, CppActorRef, Message};
+
+use crate::health;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::clone_message;
+use crate::rust_manager_ffi::get_actor_ref;
+
+/// Manager name of a group's host actor is this plus the group name
+pub const HOST_PREFIX: &str = "$shared/";
+
+struct Member {
+    name: String,
+    actor: Box<dyn Actor>,
+}
+
+type Members = Arc<Mutex<Vec<Member>>>;
+
+lazy_static::lazy_static! {
+    // group -> members, in registration order
+    static ref GROUPS: Mutex<HashMap<String, Members>> = Mutex::new(HashMap::new());
+    // member -> group
+    static ref MEMBER_OF: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+}
+
+pub fn host_name(group: &str) -> String {
+    format!("{}{}", HOST_PREFIX, group)
+}
+
+/// Group `name` runs in, None if it has a thread of its own
+pub fn group_of(name: &str) -> Option<String> {
+    MEMBER_OF.lock().unwrap().get(name).cloned()
+}
+
+/// Members of `group`, in registration order
+pub fn members(group: &str) -> Vec<String> {
+    match GROUPS.lock().unwrap().get(group) {
+        Some(members) => members.lock().unwrap().iter().map(|m| m.name.clone()).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Add `actor` to `group` as `name`. Returns the group's host for the
+/// caller to register with the Manager if this is its first member.
+pub(crate) fn join(group: &str, name: &str, actor: Box<dyn Actor>) -> Option<SharedThread> {
+    let mut groups = GROUPS.lock().unwrap();
+    MEMBER_OF.lock().unwrap().insert(name.to_string(), group.to_string());
+    let member = Member { name: name.to_string(), actor };
+    match groups.get(group) {
+        Some(members) => {
+            members.lock().unwrap().push(member);
+            None
+        }
+        None => {
+            let members: Members = Arc::new(Mutex::new(vec![member]));
+            groups.insert(group.to_string(), members.clone());
+            Some(SharedThread { group: group.to_string(), members })
+        }
+    }
+}
+
+/// Forget every group, for a new Manager
+pub(crate) fn clear() {
+    GROUPS.lock().unwrap().clear();
+    MEMBER_OF.lock().unwrap().clear();
+}
+
+/// A message for one member, queued on its group's host
+struct Addressed {
+    to: String,
+    msg: Box<dyn Message>,
+}
+
+// Only the host looks inside; as_any gives the envelope, not the message
+impl Message for Addressed {
+    fn as_any(&self) -> &dyn std::any::Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self }
+    fn message_id(&self) -> i32 { self.msg.message_id() }
+}
+
+/// Host actor of a group: runs its members' handlers on its thread
+pub struct SharedThread {
+    group: String,
+    members: Members,
+}
+
+impl SharedThread {
+    pub fn name(&self) -> String {
+        host_name(&self.group)
+    }
+}
+
+impl Actor for SharedThread {
+    fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+        let mut members = self.members.lock().unwrap();
+        match msg.as_any().downcast_ref::<Addressed>() {
+            Some(addressed) => match members.iter_mut().find(|m| m.name == addressed.to) {
+                Some(member) => member.actor.process_message(addressed.msg.as_ref(), ctx),
+                None => health::record_dead_letter("", &addressed.to, addressed.msg.message_id()),
+            },
+            // Start, End and anything sent to the host itself
+            None => {
+                for member in members.iter_mut() {
+                    member.actor.process_message(msg, ctx);
+                }
+            }
+        }
+    }
+}
+
+/// Forward a send to a member to its group's host
+fn member_send_fn(target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    let group = match group_of(target) {
+        Some(g) => g,
+        None => return InteropErrorCode::NotFound as i32,
+    };
+    let host = match get_actor_ref(&host_name(&group), sender) {
+        Some(h) => h,
+        None => return InteropErrorCode::NotFound as i32,
+    };
+    let copy = match clone_message(msg) {
+        Some(c) => c,
+        None => return InteropErrorCode::UnknownMessage as i32,
+    };
+    let sender_ref = if sender.is_empty() { None } else { get_actor_ref(sender, target) };
+    host.send(Box::new(Addressed { to: target.to_string(), msg: copy }), sender_ref);
+    0
+}
+
+/// Queue `msg` for member `name` without copying it, for messages that
+/// are not interop messages (see replace). False if `name` is not a member.
+pub(crate) fn send_to_member(name: &str, msg: Box<dyn Message>) -> bool {
+    let host = match group_of(name).and_then(|group| get_actor_ref(&host_name(&group), "")) {
+        Some(h) => h,
+        None => return false,
+    };
+    host.send(Box::new(Addressed { to: name.to_string(), msg }), None);
+    true
+}
+
+/// ActorRef for an actor on a shared thread, None for any other name.
+/// Lookups from Rust (`get_actor_ref`) and sends from C++ use this.
+pub fn member_ref(name: &str, sender: &str) -> Option<ActorRef> {
+    if !MEMBER_OF.lock().unwrap().contains_key(name) {
+        return None;
+    }
+    Some(ActorRef::Cpp(CppActorRef::new(name, sender, member_send_fn)))
+}
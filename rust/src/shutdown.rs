@@ -0,0 +1,220 @@
+//! Coordinated shutdown of the Rust and C++ managers
+//!
+//! Ending one manager while the other still sends into it loses messages
+//! and races the thread joins. Either side starts the handshake instead,
+//! and both managers are ended only once both have drained:
+//!
+//! 1. ShutdownRequested: `request()` (Rust) or `interop_shutdown_request()`
+//!    (C++). From then on new interop sends are refused in both directions
+//!    with `SHUTTING_DOWN`; sends within a runtime are unaffected, and so
+//!    is `$sys` topic delivery so subscribers see the handshake. The C++
+//!    callback from `interop_on_shutdown_requested()` is called and the
+//!    event is published on `$sys/shutdown`.
+//! 2. Drain: Rust is ready once every bridge queue (see queue_depth) and
+//!    outbound queue (see outbound) is empty, or after the drain timeout.
+//!    C++ drains its side and calls `interop_shutdown_cpp_ready()`.
+//! 3. ShutdownReady: each side's readiness is published on `$sys/shutdown`;
+//!    once both are ready, `wait()` / `interop_shutdown_wait()` return and
+//!    the owner calls `rust_manager_end()` and the C++ Manager's `end()`.
+//!
+//! ```ignore
+//! // In a Rust actor, instead of manager_handle.terminate()
+//! shutdown::request("rust_ping");
+//! ```
+//!
+//! Replies that handlers send while draining are refused too, so a side
+//! that needs an answer must get it before requesting shutdown.
+//! `create_rust_manager()` starts over with a running state.
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::outbound;
+use crate::queue_depth;
+use crate::sys_topics;
+
+/// Send return code once shutdown has been requested
+pub const SHUTTING_DOWN: i32 = InteropErrorCode::ShuttingDown as i32;
+
+/// How long Rust waits for its queues to empty before confirming anyway
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Mirrors INTEROP_SHUTDOWN_* in interop/shutdown.h
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum ShutdownState {
+    Running = 0,
+    /// Requested; at least one side is still draining
+    Draining = 1,
+    /// Both sides confirmed; the managers may be ended
+    Ready = 2,
+}
+
+pub type ShutdownCallback = extern "C" fn(initiator: *const c_char);
+
+struct Handshake {
+    requested: bool,
+    rust_ready: bool,
+    cpp_ready: bool,
+    drain_timeout: Duration,
+}
+
+impl Handshake {
+    fn state(&self) -> ShutdownState {
+        match (self.requested, self.rust_ready && self.cpp_ready) {
+            (false, _) => ShutdownState::Running,
+            (true, false) => ShutdownState::Draining,
+            (true, true) => ShutdownState::Ready,
+        }
+    }
+}
+
+// Fast path for the send gates
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+static HANDSHAKE: Mutex<Handshake> = Mutex::new(Handshake {
+    requested: false,
+    rust_ready: false,
+    cpp_ready: false,
+    drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+});
+static CHANGED: Condvar = Condvar::new();
+static CALLBACK: Mutex<Option<ShutdownCallback>> = Mutex::new(None);
+
+/// Whether interop sends are still accepted
+pub fn accepting() -> bool {
+    !REQUESTED.load(Ordering::Acquire)
+}
+
+/// Whether an interop send from `sender` passes the shutdown gate.
+/// `$sys` events (the handshake itself among them) are always delivered.
+pub fn admits(sender: &str) -> bool {
+    accepting() || sender == sys_topics::SYS_PUBLISHER
+}
+
+pub fn state() -> ShutdownState {
+    HANDSHAKE.lock().unwrap().state()
+}
+
+pub fn set_drain_timeout(timeout: Duration) {
+    HANDSHAKE.lock().unwrap().drain_timeout = timeout;
+}
+
+/// Start the handshake on behalf of `initiator` (an actor or runtime
+/// name). Returns false if shutdown was already requested.
+pub fn request(initiator: &str) -> bool {
+    let drain_timeout = {
+        let mut handshake = HANDSHAKE.lock().unwrap();
+        if handshake.requested {
+            return false;
+        }
+        handshake.requested = true;
+        REQUESTED.store(true, Ordering::Release);
+        handshake.drain_timeout
+    };
+    eprintln!("[Shutdown] requested by {}", initiator);
+    sys_topics::shutdown(initiator, false);
+    // Copied out so the callback may call interop_on_shutdown_requested()
+    let cb = *CALLBACK.lock().unwrap();
+    if let (Some(cb), Ok(c_initiator)) = (cb, CString::new(initiator)) {
+        cb(c_initiator.as_ptr());
+    }
+    thread::spawn(move || drain(drain_timeout));
+    true
+}
+
+/// Wait for the Rust side's queues to empty, then confirm it
+fn drain(timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let left = queue_depth::total_pending() + outbound::total_pending();
+        if left == 0 {
+            break;
+        }
+        if Instant::now() >= deadline {
+            eprintln!("[Shutdown] Rust side ready with {} messages still queued", left);
+            break;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    confirm(true);
+}
+
+fn confirm(rust: bool) {
+    let mut handshake = HANDSHAKE.lock().unwrap();
+    if !handshake.requested {
+        return;
+    }
+    let ready = if rust { &mut handshake.rust_ready } else { &mut handshake.cpp_ready };
+    if *ready {
+        return;
+    }
+    *ready = true;
+    CHANGED.notify_all();
+    drop(handshake);
+    sys_topics::shutdown(if rust { "rust" } else { "cpp" }, true);
+}
+
+/// Block until both sides have confirmed. Returns false on timeout.
+pub fn wait(timeout: Duration) -> bool {
+    let handshake = HANDSHAKE.lock().unwrap();
+    let (handshake, _) = CHANGED
+        .wait_timeout_while(handshake, timeout, |h| h.state() != ShutdownState::Ready)
+        .unwrap();
+    handshake.state() == ShutdownState::Ready
+}
+
+/// Back to running, for a new Manager. Called by create_rust_manager().
+pub fn reset() {
+    let mut handshake = HANDSHAKE.lock().unwrap();
+    handshake.requested = false;
+    handshake.rust_ready = false;
+    handshake.cpp_ready = false;
+    REQUESTED.store(false, Ordering::Release);
+}
+
+/// Request shutdown from C++. `initiator` may be null.
+/// Returns 0, or 1 if shutdown was already requested.
+#[export_name = c_symbol!("interop_shutdown_request")]
+pub extern "C" fn interop_shutdown_request(initiator: *const c_char) -> c_int {
+    if request(c_str(initiator).unwrap_or("cpp")) { 0 } else { 1 }
+}
+
+/// Called once when shutdown is requested by either side, on the
+/// requesting thread; the C++ side drains and calls
+/// interop_shutdown_cpp_ready(). Null clears it.
+#[export_name = c_symbol!("interop_on_shutdown_requested")]
+pub extern "C" fn interop_on_shutdown_requested(cb: Option<ShutdownCallback>) {
+    *CALLBACK.lock().unwrap() = cb;
+}
+
+/// The C++ side has drained. Ignored if shutdown was not requested.
+#[export_name = c_symbol!("interop_shutdown_cpp_ready")]
+pub extern "C" fn interop_shutdown_cpp_ready() {
+    confirm(false);
+}
+
+/// INTEROP_SHUTDOWN_* state of the handshake
+#[export_name = c_symbol!("interop_shutdown_state")]
+pub extern "C" fn interop_shutdown_state() -> c_int {
+    state() as c_int
+}
+
+/// Block until both sides are ready, up to `timeout_ms`.
+/// Returns 1 when the managers may be ended, 0 on timeout.
+#[export_name = c_symbol!("interop_shutdown_wait")]
+pub extern "C" fn interop_shutdown_wait(timeout_ms: u32) -> c_int {
+    if wait(Duration::from_millis(timeout_ms as u64)) { 1 } else { 0 }
+}
+
+/// How long the Rust side waits for its queues to empty, in ms
+#[export_name = c_symbol!("interop_set_shutdown_drain_timeout")]
+pub extern "C" fn interop_set_shutdown_drain_timeout(timeout_ms: u32) {
+    set_drain_timeout(Duration::from_millis(timeout_ms as u64));
+}
@@ -0,0 +1,430 @@
+//! Deterministic simulation of cross-language traffic (feature `sim`)
+//!
+//! For tests only. Replaces the C++ side with mock actors written in Rust
+//! and drives every cross-language delivery from one virtual clock:
+//!
+//! - `start(seed, max_jitter_ms)` installs a resolver that routes
+//!   `ActorRef::Cpp` sends into the simulation instead of FFI, and makes
+//!   `get_actor_ref()` return simulated refs for Rust actors too.
+//! - Each delivery is scheduled at `now + delay + jitter`. Jitter and
+//!   tie-breaking are derived from the seed, sender, target and the
+//!   number of earlier sends between the two, not from the order actor
+//!   threads happen to send in, so a seed reproduces an ordering.
+//! - `step()` delivers exactly one message. Rust targets get it through
+//!   their mailbox, and the step waits until the mailbox is empty again,
+//!   so the handler has finished before the next step.
+//!
+//! Start comes from `rust_manager_init()` as usual, but what Start
+//! handlers send is scheduled on the virtual clock; wait for those sends
+//! with `pending()` before stepping. Races between actors starting up,
+//! e.g. a subscription and the first publish, then play out per seed.
+//! Refs taken before `start()` (or from the Manager directly) bypass the
+//! simulation.
+//!
+//! ```ignore
+//! sim::start(42, 5);
+//! sim::add_cpp_actor("cpp_price_feed", |msg, out| {
+//!     if msg.message_id() == MSG_SUBSCRIBE {
+//!         out.send("rust_price_monitor", Box::new(update.clone()), 1);
+//!     }
+//! });
+//! rust_manager_init();                  // rust_price_monitor subscribes in Start
+//! while sim::pending() == 0 {}
+//! sim::run_until_idle(1000);
+//! ```
+//!
+//! Rust sends to mock actors go through the same retry, circuit breaker
+//! and dead letter handling as sends to C++ (see retry, circuit,
+//! health), so those can be tested against programmed failures:
+//!
+//! ```ignore
+//! sim::fail("cpp_gateway", Failure::nth(3, sim::MAILBOX_FULL));           // the 3rd send only
+//! sim::fail("cpp_gateway", Failure::from(1, -1).for_message(MSG_ORDER).times(2));
+//! sim::vanish_after("cpp_risk", 10);   // gone once it has taken 10 messages
+//! ```
+//!
+//! Each attempt, retries included, is one send. A failed send is not
+//! delivered and returns the failure's code to the sender. A vanished
+//! actor is no longer found by lookups, and sends through refs taken
+//! before return NotFound; messages it accepted are still delivered.
+//! Sends to Rust actors, between mock actors and `inject()` never fail.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actors::{ActorRef, CppActorRef, Message};
+
+use crate::broker;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::clone_message;
+use crate::names;
+use crate::pause;
+use crate::queue_depth;
+use crate::resolver;
+use crate::rust_manager_ffi::{self, get_actor_ref};
+
+/// Code a mock returns for a full mailbox. Any nonzero code can be
+/// programmed; C++ runtimes with bounded mailboxes return their own.
+pub const MAILBOX_FULL: i32 = InteropErrorCode::QueueFull as i32;
+
+/// Longest a step waits for a Rust handler, e.g. of a paused actor
+const STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Handler for a mock C++ actor
+pub type MockHandler = Box<dyn FnMut(&dyn Message, &mut Outbox) + Send>;
+
+/// Messages sent by a mock C++ actor while handling a delivery
+#[derive(Default)]
+pub struct Outbox {
+    sends: Vec<(String, Box<dyn Message>, u64)>,
+}
+
+impl Outbox {
+    /// Send to any actor (Rust or mock C++) after `delay_ms` of virtual time
+    pub fn send(&mut self, target: &str, msg: Box<dyn Message>, delay_ms: u64) {
+        self.sends.push((target.to_string(), msg, delay_ms));
+    }
+}
+
+/// Sends to a mock actor that fail, for `fail()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// Only sends of this message ID count and fail; None for all
+    pub msg_id: Option<i32>,
+    /// First failing send, counting from 1
+    pub from: u64,
+    /// Failing sends from there on; None for every later one
+    pub count: Option<u64>,
+    /// Returned to the sender instead of delivering
+    pub code: i32,
+}
+
+impl Failure {
+    /// Fail the `n`th send with `code`
+    pub fn nth(n: u64, code: i32) -> Self {
+        Failure { msg_id: None, from: n, count: Some(1), code }
+    }
+
+    /// Fail every send from the `n`th on with `code`
+    pub fn from(n: u64, code: i32) -> Self {
+        Failure { msg_id: None, from: n, count: None, code }
+    }
+
+    /// Count and fail only sends of `msg_id`
+    pub fn for_message(mut self, msg_id: i32) -> Self {
+        self.msg_id = Some(msg_id);
+        self
+    }
+
+    /// Fail `count` sends, then deliver again
+    pub fn times(mut self, count: u64) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    fn covers(&self, send: u64) -> bool {
+        send >= self.from && self.count.map_or(true, |c| send - self.from < c)
+    }
+}
+
+struct FaultRule {
+    target: String,
+    failure: Failure,
+    // Matching sends so far
+    seen: u64,
+}
+
+struct Delivery {
+    target: String,
+    sender: String,
+    msg: Box<dyn Message>,
+}
+
+struct SimState {
+    now_ms: u64,
+    max_jitter_ms: u64,
+    seed: u64,
+    // Sends so far per (sender, target), for jitter and tie-breaks
+    pair_sends: HashMap<(String, String), u64>,
+    next_seq: u64,
+    // (due time, seeded tie-break, seq) - seq keys `pending`
+    queue: BinaryHeap<Reverse<(u64, u64, u64)>>,
+    pending: HashMap<u64, Delivery>,
+    mock_names: HashSet<String>,
+    faults: Vec<FaultRule>,
+    // Sends from Rust to each mock, attempts included
+    sends: HashMap<String, u64>,
+    // Messages each mock takes before it vanishes
+    vanish_after: HashMap<String, u64>,
+}
+
+impl SimState {
+    /// Count a send to `target`; the code to fail it with, if any
+    fn fault(&mut self, target: &str, msg_id: i32) -> Option<i32> {
+        *self.sends.entry(target.to_string()).or_insert(0) += 1;
+        if !self.mock_names.contains(target) {
+            return Some(InteropErrorCode::NotFound as i32);
+        }
+        let mut code = None;
+        for rule in self.faults.iter_mut() {
+            if rule.target != target || rule.failure.msg_id.is_some_and(|id| id != msg_id) {
+                continue;
+            }
+            rule.seen += 1;
+            if code.is_none() && rule.failure.covers(rule.seen) {
+                code = Some(rule.failure.code);
+            }
+        }
+        code
+    }
+
+    /// Count a message `target` took; it vanishes at its limit
+    fn accepted(&mut self, target: &str) {
+        if let Some(left) = self.vanish_after.get_mut(target) {
+            *left = left.saturating_sub(1);
+            if *left == 0 {
+                self.vanish_after.remove(target);
+                self.mock_names.remove(target);
+            }
+        }
+    }
+
+    fn schedule(&mut self, target: String, sender: String, msg: Box<dyn Message>, delay_ms: u64) {
+        let count = self.pair_sends.entry((sender.clone(), target.clone())).or_insert(0);
+        let n = *count;
+        *count += 1;
+        let draw = |salt: u8| {
+            let mut h = DefaultHasher::new();
+            (self.seed, &sender, &target, n, salt).hash(&mut h);
+            h.finish()
+        };
+        let jitter = if self.max_jitter_ms > 0 { draw(0) % (self.max_jitter_ms + 1) } else { 0 };
+        let due = self.now_ms + delay_ms + jitter;
+        let tie_break = draw(1);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.queue.push(Reverse((due, tie_break, seq)));
+        self.pending.insert(seq, Delivery { target, sender, msg });
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SIM: Mutex<Option<SimState>> = Mutex::new(None);
+    // Kept apart from SIM so handlers can run without holding its lock
+    static ref MOCKS: Mutex<HashMap<String, Arc<Mutex<MockHandler>>>> = Mutex::new(HashMap::new());
+}
+
+/// Start a simulation, replacing the real C++ lookup (see resolver)
+pub fn start(seed: u64, max_jitter_ms: u64) {
+    *SIM.lock().unwrap() = Some(SimState {
+        now_ms: 0,
+        max_jitter_ms,
+        seed,
+        pair_sends: HashMap::new(),
+        next_seq: 0,
+        queue: BinaryHeap::new(),
+        pending: HashMap::new(),
+        mock_names: HashSet::new(),
+        faults: Vec::new(),
+        sends: HashMap::new(),
+        vanish_after: HashMap::new(),
+    });
+    MOCKS.lock().unwrap().clear();
+    resolver::set_actor_resolver(Box::new(sim_lookup));
+}
+
+/// Add a mock C++ actor visible to Rust through get_actor_ref()
+pub fn add_cpp_actor<F>(name: &str, handler: F)
+where
+    F: FnMut(&dyn Message, &mut Outbox) + Send + 'static,
+{
+    if let Some(state) = SIM.lock().unwrap().as_mut() {
+        state.mock_names.insert(name.to_string());
+    }
+    MOCKS.lock().unwrap().insert(name.to_string(), Arc::new(Mutex::new(Box::new(handler))));
+}
+
+/// Fail sends from Rust to mock actor `target` as `failure` says. Rules
+/// add up; where several cover a send, the first added gives the code.
+pub fn fail(target: &str, failure: Failure) {
+    if let Some(state) = SIM.lock().unwrap().as_mut() {
+        state.faults.push(FaultRule { target: target.to_string(), failure, seen: 0 });
+    }
+}
+
+/// Make mock actor `target` disappear once it has taken `n` more messages
+/// from Rust; 0 at once
+pub fn vanish_after(target: &str, n: u64) {
+    if let Some(state) = SIM.lock().unwrap().as_mut() {
+        if n == 0 {
+            state.mock_names.remove(target);
+        } else {
+            state.vanish_after.insert(target.to_string(), n);
+        }
+    }
+}
+
+/// Drop every programmed failure; vanished actors stay gone
+pub fn clear_failures() {
+    if let Some(state) = SIM.lock().unwrap().as_mut() {
+        state.faults.clear();
+        state.vanish_after.clear();
+    }
+}
+
+/// Sends from Rust to mock actor `target` so far, failed ones and retries
+/// included
+pub fn sends_to(target: &str) -> u64 {
+    SIM.lock().unwrap().as_ref().and_then(|s| s.sends.get(target).copied()).unwrap_or(0)
+}
+
+/// Schedule a message to any actor after `delay_ms` of virtual time
+pub fn inject(target: &str, msg: Box<dyn Message>, delay_ms: u64) {
+    if let Some(state) = SIM.lock().unwrap().as_mut() {
+        state.schedule(target.to_string(), String::new(), msg, delay_ms);
+    }
+}
+
+/// Current virtual time in milliseconds
+pub fn now_ms() -> u64 {
+    SIM.lock().unwrap().as_ref().map_or(0, |s| s.now_ms)
+}
+
+/// Deliveries scheduled and not yet made
+pub fn pending() -> usize {
+    SIM.lock().unwrap().as_ref().map_or(0, |s| s.pending.len())
+}
+
+/// Deliver the next message. Returns false when nothing is pending.
+pub fn step() -> bool {
+    let delivery = {
+        let mut guard = SIM.lock().unwrap();
+        let state = match guard.as_mut() {
+            Some(s) => s,
+            None => return false,
+        };
+        let Reverse((due, _, seq)) = match state.queue.pop() {
+            Some(next) => next,
+            None => return false,
+        };
+        state.now_ms = state.now_ms.max(due);
+        match state.pending.remove(&seq) {
+            Some(d) => d,
+            None => return true,
+        }
+    };
+
+    let mock = MOCKS.lock().unwrap().get(&delivery.target).cloned();
+    match mock {
+        Some(handler) => {
+            let mut outbox = Outbox::default();
+            (handler.lock().unwrap())(delivery.msg.as_ref(), &mut outbox);
+            if let Some(state) = SIM.lock().unwrap().as_mut() {
+                for (target, msg, delay) in outbox.sends {
+                    state.schedule(target, delivery.target.clone(), msg, delay);
+                }
+            }
+        }
+        None => deliver_to_rust(delivery),
+    }
+    true
+}
+
+/// Through the target's mailbox, as outside the simulation, then wait for
+/// the mailbox to drain so the handler completes inside this step
+fn deliver_to_rust(delivery: Delivery) {
+    let Delivery { target, sender, msg } = delivery;
+    let actor = match rust_manager_ffi::manager_ref(&target, &sender) {
+        Some(a) => a,
+        None => return,
+    };
+    let sender_ref = if sender.is_empty() { None } else { get_actor_ref(&sender, &target) };
+    // Subscriptions and acks from mocks are recorded as the bridge would
+    broker::observe(&sender, &target, msg.as_ref());
+    pause::send(&actor, &target, queue_depth::track_boxed(&target, &sender, msg), sender_ref);
+    let begin = Instant::now();
+    while queue_depth::pending(&target) > 0 && begin.elapsed() < STEP_TIMEOUT {
+        std::thread::sleep(Duration::from_micros(50));
+    }
+}
+
+/// Step until no deliveries remain or `max_steps` is reached.
+/// Returns the number of deliveries made.
+pub fn run_until_idle(max_steps: usize) -> usize {
+    let mut steps = 0;
+    while steps < max_steps && step() {
+        steps += 1;
+    }
+    steps
+}
+
+/// Stop the simulation, drop anything still pending and go back to the
+/// default resolver
+pub fn stop() {
+    *SIM.lock().unwrap() = None;
+    MOCKS.lock().unwrap().clear();
+    resolver::reset_actor_resolver();
+}
+
+/// Rust -> mock C++: enqueue instead of calling FFI, with retries, the
+/// circuit breaker and dead letters as for C++
+fn sim_send_fn(target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    rust_manager_ffi::send_retrying_with(target, sender, msg, || sim_send_once(target, sender, msg))
+}
+
+/// One attempt: a programmed failure, or the message enqueued
+fn sim_send_once(target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    let mut guard = SIM.lock().unwrap();
+    let state = match guard.as_mut() {
+        Some(s) => s,
+        None => return InteropErrorCode::NotFound as i32,
+    };
+    if let Some(code) = state.fault(target, msg.message_id()) {
+        return code;
+    }
+    let copy = match clone_message(msg) {
+        Some(m) => m,
+        None => return InteropErrorCode::UnknownMessage as i32,
+    };
+    state.schedule(target.to_string(), sender.to_string(), copy, 0);
+    state.accepted(target);
+    0
+}
+
+/// Simulated ref for Rust actor `name` while a simulation runs; used by
+/// `get_actor_ref()` so Rust sends are scheduled like any other
+pub(crate) fn rust_ref(name: &str, sender: &str) -> Option<ActorRef> {
+    if SIM.lock().unwrap().is_none() || !names::is_rust(name) {
+        return None;
+    }
+    Some(ActorRef::Cpp(CppActorRef::new(name, sender, rust_send_fn)))
+}
+
+/// Anything -> Rust: enqueue a copy for `step()` to deliver
+fn rust_send_fn(target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    let mut guard = SIM.lock().unwrap();
+    let state = match guard.as_mut() {
+        Some(s) => s,
+        None => return InteropErrorCode::NotFound as i32,
+    };
+    match clone_message(msg) {
+        Some(copy) => {
+            state.schedule(target.to_string(), sender.to_string(), copy, 0);
+            0
+        }
+        None => InteropErrorCode::UnknownMessage as i32,
+    }
+}
+
+fn sim_lookup(name: &str, sender: &str) -> Option<ActorRef> {
+    let known = SIM.lock().unwrap().as_ref().is_some_and(|s| s.mock_names.contains(name));
+    if known {
+        Some(ActorRef::Cpp(CppActorRef::new(name, sender, sim_send_fn)))
+    } else {
+        None
+    }
+}
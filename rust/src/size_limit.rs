@@ -0,0 +1,287 @@
+//! Per-transport message size limits
+//!
+//! A `RawPayload` (or any message with `interop_bytes`) can be arbitrarily
+//! large, which a fixed-size transport buffer cannot take. Each link can
+//! be given a maximum message size; `BRIDGE` is the in-process C++ <-> Rust
+//! bridge, other names are transport links as in `codec::set_link_codec()`:
+//!
+//! ```c
+//! interop_set_max_message_size(INTEROP_BRIDGE_LINK, 1 << 20, 1);  // 1 MiB, chunk payloads
+//! interop_set_max_message_size("kafka_sink", 900 * 1024, 0);
+//! ```
+//!
+//! - On the bridge the size is the C struct plus its `interop_bytes`
+//!   payloads (`message_size`); on a link it is the encoded frame.
+//! - A message over the limit is not sent: `rust_actor_send()`,
+//!   `rust_actor_post()` and Rust -> C++ sends return `MESSAGE_TOO_LARGE`,
+//!   transports log and drop it.
+//! - With chunking on, a `RawPayload` over the limit is sent instead as
+//!   `PayloadChunk`s that each fit. Receivers put them back together with
+//!   `PayloadAssembler` (C++: interop/payload_chunks.hpp).
+//!
+//! Links without a limit are unchecked.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use actors::Message;
+
+use crate::codec::{Codec, CodecError};
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{c_message_size, message_size, PayloadChunk, RawPayload};
+use crate::interop_string::InteropString;
+use crate::names::c_str;
+
+/// Send status for a message over the size limit
+pub const MESSAGE_TOO_LARGE: i32 = InteropErrorCode::TooLarge as i32;
+
+/// Link name of the in-process bridge
+pub const BRIDGE: &str = "bridge";
+
+/// Room kept in each chunk for length prefixes that grow with the data
+const CHUNK_SLACK: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeLimit {
+    pub max_bytes: usize,
+    /// Split oversized RawPayloads into PayloadChunks instead of refusing
+    pub chunking: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SizeError {
+    TooLarge { msg_id: i32, size: usize, max: usize },
+    /// Chunking is on but the limit leaves no room for chunk data
+    LimitTooSmall { max: usize },
+    Encode(CodecError),
+}
+
+impl fmt::Display for SizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeError::TooLarge { msg_id, size, max } => {
+                write!(f, "message {} is {} bytes, limit is {}", msg_id, size, max)
+            }
+            SizeError::LimitTooSmall { max } => write!(f, "limit of {} bytes is too small for a chunk", max),
+            SizeError::Encode(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SizeError {}
+
+impl From<CodecError> for SizeError {
+    fn from(e: CodecError) -> Self {
+        SizeError::Encode(e)
+    }
+}
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref LIMITS: Mutex<HashMap<String, SizeLimit>> = Mutex::new(HashMap::new());
+}
+
+/// Limit messages on `link` to `max_bytes`; 0 removes the limit
+pub fn set_limit(link: &str, max_bytes: usize, chunking: bool) {
+    let mut limits = LIMITS.lock().unwrap();
+    if max_bytes == 0 {
+        limits.remove(link);
+    } else {
+        limits.insert(link.to_string(), SizeLimit { max_bytes, chunking });
+    }
+    ACTIVE.store(!limits.is_empty(), Ordering::Release);
+}
+
+pub fn limit(link: &str) -> Option<SizeLimit> {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return None;
+    }
+    LIMITS.lock().unwrap().get(link).copied()
+}
+
+/// False if a C struct sent over the bridge is over its limit. Chunking
+/// does not apply here: the C++ side splits before sending.
+///
+/// # Safety
+/// `data` must point to a valid C struct for `msg_id`
+pub unsafe fn admit_c(msg_id: i32, data: *const c_void) -> bool {
+    let max = match limit(BRIDGE) {
+        Some(l) => l.max_bytes,
+        None => return true,
+    };
+    match c_message_size(msg_id, data) {
+        Some(size) if size > max => {
+            eprintln!("[Size Limit] refused: {}", SizeError::TooLarge { msg_id, size, max });
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Check a Rust -> C++ send. Ok(None) if `msg` fits the bridge limit,
+/// Ok(Some(chunks)) if it is a RawPayload to be sent as those chunks.
+pub fn split_for_bridge(msg: &dyn Message) -> Result<Option<Vec<PayloadChunk>>, SizeError> {
+    let limit = match limit(BRIDGE) {
+        Some(l) => l,
+        None => return Ok(None),
+    };
+    let size = message_size(msg).unwrap_or(0);
+    if size <= limit.max_bytes {
+        return Ok(None);
+    }
+    let overhead = message_size(&empty_chunk()).unwrap_or(0);
+    split(limit, msg, size, overhead).map(Some)
+}
+
+/// Encode `msg` for `link`: one frame, or one per PayloadChunk when it is
+/// an oversized RawPayload and chunking is on
+pub fn encode_frames(link: &str, codec: &dyn Codec, msg: &dyn Message) -> Result<Vec<Vec<u8>>, SizeError> {
+    let frame = codec.encode(msg)?;
+    let limit = match limit(link) {
+        Some(l) if frame.len() > l.max_bytes => l,
+        _ => return Ok(vec![frame]),
+    };
+    let overhead = codec.encode(&empty_chunk())?.len();
+    split(limit, msg, frame.len(), overhead)?
+        .iter()
+        .map(|chunk| codec.encode(chunk).map_err(SizeError::from))
+        .collect()
+}
+
+fn empty_chunk() -> PayloadChunk {
+    PayloadChunk {
+        transfer_id: i64::MAX,
+        index: i32::MAX,
+        count: i32::MAX,
+        content_type: InteropString::default(),
+        data: Vec::new(),
+    }
+}
+
+fn split(limit: SizeLimit, msg: &dyn Message, size: usize, overhead: usize) -> Result<Vec<PayloadChunk>, SizeError> {
+    let too_large = SizeError::TooLarge { msg_id: msg.message_id(), size, max: limit.max_bytes };
+    let payload = match msg.as_any().downcast_ref::<RawPayload>() {
+        Some(p) if limit.chunking => p,
+        _ => return Err(too_large),
+    };
+    let room = limit.max_bytes.saturating_sub(overhead + CHUNK_SLACK);
+    if room == 0 {
+        return Err(SizeError::LimitTooSmall { max: limit.max_bytes });
+    }
+    Ok(chunk_payload(payload, room))
+}
+
+/// Split `payload` into chunks of at most `room` data bytes
+pub fn chunk_payload(payload: &RawPayload, room: usize) -> Vec<PayloadChunk> {
+    let room = room.max(1);
+    let mut transfer_id = 0;
+    while transfer_id == 0 {
+        transfer_id = rand::random::<i64>();
+    }
+    let mut parts: Vec<&[u8]> = payload.bytes.chunks(room).collect();
+    if parts.is_empty() {
+        parts.push(&[]);
+    }
+    let count = parts.len() as i32;
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(i, data)| PayloadChunk {
+            transfer_id,
+            index: i as i32,
+            count,
+            content_type: payload.content_type,
+            data: data.to_vec(),
+        })
+        .collect()
+}
+
+struct Partial {
+    content_type: InteropString<32>,
+    parts: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+/// Reassembles PayloadChunks into the RawPayload they were cut from
+#[derive(Default)]
+pub struct PayloadAssembler {
+    partial: HashMap<i64, Partial>,
+}
+
+impl PayloadAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a chunk; returns the payload once every chunk has arrived. A
+    /// chunk that does not match its transfer drops the transfer.
+    pub fn add(&mut self, chunk: &PayloadChunk) -> Option<RawPayload> {
+        let count = usize::try_from(chunk.count).ok().filter(|&c| c > 0)?;
+        let index = usize::try_from(chunk.index).ok().filter(|&i| i < count);
+        let entry = self.partial.entry(chunk.transfer_id).or_insert_with(|| Partial {
+            content_type: chunk.content_type,
+            parts: vec![None; count],
+            received: 0,
+        });
+        let index = match index {
+            Some(i) if entry.parts.len() == count => i,
+            _ => {
+                self.partial.remove(&chunk.transfer_id);
+                return None;
+            }
+        };
+        if entry.parts[index].is_none() {
+            entry.parts[index] = Some(chunk.data.clone());
+            entry.received += 1;
+        }
+        if entry.received < count {
+            return None;
+        }
+        let done = self.partial.remove(&chunk.transfer_id)?;
+        Some(RawPayload {
+            content_type: done.content_type,
+            bytes: done.parts.into_iter().flatten().flatten().collect(),
+        })
+    }
+
+    /// Transfers still missing chunks
+    pub fn pending(&self) -> usize {
+        self.partial.len()
+    }
+
+    /// Forget a transfer, e.g. when its sender went away
+    pub fn discard(&mut self, transfer_id: i64) {
+        self.partial.remove(&transfer_id);
+    }
+}
+
+/// Limit messages on `link` (INTEROP_BRIDGE_LINK or a transport name) to
+/// `max_bytes`, 0 to remove the limit. With `chunking` nonzero, oversized
+/// RawPayloads are sent as PayloadChunks. Returns 0, or InvalidArgument on a
+/// bad name.
+#[export_name = c_symbol!("interop_set_max_message_size")]
+pub extern "C" fn interop_set_max_message_size(link: *const c_char, max_bytes: u64, chunking: c_int) -> c_int {
+    let rc = match c_str(link) {
+        Some(link) => {
+            set_limit(link, usize::try_from(max_bytes).unwrap_or(usize::MAX), chunking != 0);
+            InteropErrorCode::Ok
+        }
+        None => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Limit on `link`, 0 if none. `chunking` (may be null) is set to 1 if
+/// oversized RawPayloads are chunked.
+#[export_name = c_symbol!("interop_max_message_size")]
+pub extern "C" fn interop_max_message_size(link: *const c_char, chunking: *mut c_int) -> u64 {
+    let limit = c_str(link).and_then(limit);
+    if !chunking.is_null() {
+        unsafe { *chunking = limit.map_or(0, |l| l.chunking as c_int) };
+    }
+    limit.map_or(0, |l| l.max_bytes as u64)
+}
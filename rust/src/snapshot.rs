@@ -0,0 +1,117 @@
+//! State carried across restarts
+//!
+//! A restarted actor normally comes back empty: a fresh instance from its
+//! factory. An actor that implements `Snapshot` keeps its state instead:
+//!
+//! ```ignore
+//! #[interop_actor("rust_price_monitor", snapshot)]
+//! pub struct RustSubscriber { ... }
+//!
+//! impl Snapshot for RustSubscriber {
+//!     fn snapshot(&self) -> Vec<u8> { ... }
+//!     fn restore(&mut self, state: &[u8]) { ... }
+//! }
+//! ```
+//!
+//! - Supervision restart (see lifecycle): the instance that panicked is
+//!   snapshotted, as the panic left it, and the state restored into the
+//!   fresh instance before it handles Start.
+//! - `rust_manager_restart()`: each actor is snapshotted when the old
+//!   Manager drops it and restored before its first Start on the new one.
+//!
+//! `restore` runs before Start, so Start sees the restored state (e.g.
+//! resubscribes to the restored topics). A panic in either hook is caught;
+//! the actor then starts empty. Actors registered with a plain
+//! `Box<dyn Actor>` have no hooks.
+
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+use actors::{Actor, ActorContext, Message};
+
+/// Save and restore hooks of an actor
+pub trait Snapshot {
+    /// Encode the state to keep; any format the actor can read back
+    fn snapshot(&self) -> Vec<u8>;
+    /// Load state from `snapshot()` into a fresh instance
+    fn restore(&mut self, state: &[u8]);
+}
+
+/// An actor with Snapshot hooks; implemented for every such actor
+pub trait StatefulActor: Actor + Snapshot {
+    fn as_actor(&mut self) -> &mut dyn Actor;
+}
+
+impl<A: Actor + Snapshot + 'static> StatefulActor for A {
+    fn as_actor(&mut self) -> &mut dyn Actor {
+        self
+    }
+}
+
+/// An actor as built by a factory, with its hooks if it has them
+pub enum Instance {
+    Plain(Box<dyn Actor>),
+    Stateful(Box<dyn StatefulActor>),
+}
+
+impl Instance {
+    pub fn plain<A: Actor + 'static>(actor: A) -> Self {
+        Instance::Plain(Box::new(actor))
+    }
+
+    pub fn stateful<A: Actor + Snapshot + 'static>(actor: A) -> Self {
+        Instance::Stateful(Box::new(actor))
+    }
+
+    /// State to carry over, None without hooks or if `snapshot` panicked
+    pub fn snapshot(&self) -> Option<Vec<u8>> {
+        match self {
+            Instance::Plain(_) => None,
+            Instance::Stateful(actor) => panic::catch_unwind(AssertUnwindSafe(|| actor.snapshot())).ok(),
+        }
+    }
+
+    /// Load `state`; false without hooks or if `restore` panicked
+    pub fn restore(&mut self, state: &[u8]) -> bool {
+        match self {
+            Instance::Plain(_) => false,
+            Instance::Stateful(actor) => panic::catch_unwind(AssertUnwindSafe(|| actor.restore(state))).is_ok(),
+        }
+    }
+}
+
+impl From<Box<dyn Actor>> for Instance {
+    fn from(actor: Box<dyn Actor>) -> Self {
+        Instance::Plain(actor)
+    }
+}
+
+impl Actor for Instance {
+    fn process_message(&mut self, msg: &dyn Message, ctx: &mut ActorContext) {
+        match self {
+            Instance::Plain(actor) => actor.process_message(msg, ctx),
+            Instance::Stateful(actor) => actor.as_actor().process_message(msg, ctx),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    // Actor name -> state saved when its Manager dropped it
+    static ref SAVED: Mutex<HashMap<String, Vec<u8>>> = Mutex::new(HashMap::new());
+}
+
+/// Keep `state` for the next instance named `name`
+pub fn save(name: &str, state: Vec<u8>) {
+    SAVED.lock().unwrap().insert(name.to_string(), state);
+}
+
+/// Remove and return the state kept for `name`
+pub fn take(name: &str) -> Option<Vec<u8>> {
+    SAVED.lock().unwrap().remove(name)
+}
+
+/// Forget all kept state, e.g. for a new Manager
+pub fn clear() {
+    SAVED.lock().unwrap().clear();
+}
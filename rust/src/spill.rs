@@ -0,0 +1,196 @@
+//! Spill-to-disk mailbox for bursts from the C++ side
+//!
+//! Off by default. After `rust_actor_enable_spill(name, threshold, dir)`,
+//! a message arriving through the bridge (`rust_actor_send` /
+//! `rust_actor_post`) for an actor whose queue depth is at `threshold` is
+//! appended to `{dir}/{name}.spill` instead of the in-memory mailbox. Once
+//! anything is on disk, later messages go to disk too, so order is kept.
+//!
+//! A drain thread re-reads the file in order whenever the queue depth falls
+//! below half the threshold, and truncates it once it is empty. The
+//! threshold counts messages, as tracked by `queue_depth`, standing in for
+//! memory.
+//!
+//! Records are `[u32 sender_len][sender][u32 frame_len][portable frame]`
+//! (see `codec::PortableCodec`). `fast_send` is synchronous and never
+//! spills. Spilled messages are lost if the process exits before they are
+//! drained; the file is not replayed on restart.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::os::raw::{c_char, c_int};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use actors::Message;
+
+use crate::codec::{codec, CodecKind};
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::pause;
+use crate::queue_depth;
+use crate::rust_manager_ffi::get_actor_ref;
+
+/// How often the drain thread checks the queue depth
+const DRAIN_POLL: Duration = Duration::from_millis(1);
+
+// Fast path: no hash lookup while no actor has spill enabled
+static ANY_ENABLED: AtomicBool = AtomicBool::new(false);
+
+struct SpillFile {
+    writer: BufWriter<File>,
+    reader: BufReader<File>,
+    // Records written but not yet re-read
+    pending: usize,
+    draining: bool,
+}
+
+struct SpillQueue {
+    name: String,
+    threshold: usize,
+    file: Mutex<SpillFile>,
+}
+
+lazy_static::lazy_static! {
+    static ref QUEUES: Mutex<HashMap<String, Arc<SpillQueue>>> = Mutex::new(HashMap::new());
+}
+
+/// Enable spilling for `name` past `threshold` queued messages
+pub fn enable(name: &str, threshold: usize, dir: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path: PathBuf = dir.join(format!("{}.spill", name));
+    let write_handle = OpenOptions::new().create(true).read(true).append(true).open(&path)?;
+    write_handle.set_len(0)?;
+    let read_handle = File::open(&path)?;
+    let queue = SpillQueue {
+        name: name.to_string(),
+        threshold: threshold.max(1),
+        file: Mutex::new(SpillFile {
+            writer: BufWriter::new(write_handle),
+            reader: BufReader::new(read_handle),
+            pending: 0,
+            draining: false,
+        }),
+    };
+    QUEUES.lock().unwrap().insert(name.to_string(), Arc::new(queue));
+    ANY_ENABLED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Number of messages for `name` currently on disk
+pub fn spilled(name: &str) -> usize {
+    QUEUES.lock().unwrap().get(name).map_or(0, |q| q.file.lock().unwrap().pending)
+}
+
+/// Spill `msg` if `name` is over its threshold or already has messages on
+/// disk. Returns true if the message was written and must not be sent.
+pub fn offer(name: &str, sender: &str, msg: &dyn Message) -> bool {
+    if !ANY_ENABLED.load(Ordering::Relaxed) {
+        return false;
+    }
+    let queue = match QUEUES.lock().unwrap().get(name) {
+        Some(q) => q.clone(),
+        None => return false,
+    };
+    let mut file = queue.file.lock().unwrap();
+    if file.pending == 0 && queue_depth::pending(name) < queue.threshold {
+        return false;
+    }
+    let frame = match codec(CodecKind::Portable).encode(msg) {
+        Ok(f) => f,
+        Err(_) => return false,  // not an interop message; deliver in memory
+    };
+    if let Err(e) = write_record(&mut file.writer, sender.as_bytes(), &frame) {
+        eprintln!("[Spill] {}: write failed, delivering in memory: {}", name, e);
+        return false;
+    }
+    file.pending += 1;
+    if !file.draining {
+        file.draining = true;
+        let queue = queue.clone();
+        thread::spawn(move || drain(queue));
+    }
+    true
+}
+
+fn write_record(w: &mut BufWriter<File>, sender: &[u8], frame: &[u8]) -> io::Result<()> {
+    w.write_all(&(sender.len() as u32).to_le_bytes())?;
+    w.write_all(sender)?;
+    w.write_all(&(frame.len() as u32).to_le_bytes())?;
+    w.write_all(frame)
+}
+
+fn read_chunk(r: &mut BufReader<File>) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len)?;
+    let mut buf = vec![0u8; u32::from_le_bytes(len) as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn drain(queue: Arc<SpillQueue>) {
+    let low_water = queue.threshold / 2;
+    loop {
+        thread::sleep(DRAIN_POLL);
+        let mut file = queue.file.lock().unwrap();
+        if file.pending == 0 {
+            // Empty: start the file over
+            let _ = file.writer.flush();
+            let _ = file.writer.get_ref().set_len(0);
+            let _ = file.reader.seek(SeekFrom::Start(0));
+            file.draining = false;
+            return;
+        }
+        if let Err(e) = file.writer.flush() {
+            eprintln!("[Spill] {}: flush failed: {}", queue.name, e);
+            continue;
+        }
+        while file.pending > 0 && queue_depth::pending(&queue.name) <= low_water {
+            let record = read_chunk(&mut file.reader).and_then(|s| Ok((s, read_chunk(&mut file.reader)?)));
+            let (sender, frame) = match record {
+                Ok(r) => r,
+                Err(e) => {
+                    eprintln!("[Spill] {}: dropping {} unreadable messages: {}", queue.name, file.pending, e);
+                    file.pending = 0;
+                    break;
+                }
+            };
+            file.pending -= 1;
+            let msg = match codec(CodecKind::Portable).decode(&frame) {
+                Ok(m) => m,
+                Err(e) => {
+                    eprintln!("[Spill] {}: dropping message: {}", queue.name, e);
+                    continue;
+                }
+            };
+            let sender = String::from_utf8_lossy(&sender);
+            let sender_ref = if sender.is_empty() { None } else { get_actor_ref(&sender, &queue.name) };
+            match get_actor_ref(&queue.name, "") {
+                Some(actor) => pause::send(&actor, &queue.name, queue_depth::track_boxed(&queue.name, &sender, msg), sender_ref),
+                None => eprintln!("[Spill] {}: actor gone, dropping message", queue.name),
+            }
+        }
+    }
+}
+
+/// Spill messages for `actor_name` to `dir` once `threshold` are queued.
+/// Returns 0, or InvalidArgument on a bad name or directory, including one
+/// the spill file cannot be opened in (see stderr).
+#[export_name = c_symbol!("rust_actor_enable_spill")]
+pub extern "C" fn rust_actor_enable_spill(actor_name: *const c_char, threshold: usize, dir: *const c_char) -> c_int {
+    let (name, dir) = match (c_str(actor_name), c_str(dir)) {
+        (Some(n), Some(d)) => (n, d),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    match enable(name, threshold, Path::new(dir)) {
+        Ok(()) => InteropErrorCode::Ok as c_int,
+        Err(e) => {
+            eprintln!("[Spill] {}: cannot open spill file in {}: {}", name, dir, e);
+            InteropErrorCode::InvalidArgument as c_int
+        }
+    }
+}
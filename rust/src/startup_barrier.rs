@@ -0,0 +1,81 @@
+//! Two-phase startup barrier between the Rust and C++ runtimes
+//!
+//! Without it, the order of `rust_manager_init()`, `cpp_actor_init()` and
+//! `init_cpp_actor_lookup()` decides whether Start-time sends arrive: a
+//! Rust actor that sends from its Start handler before the C++ target is
+//! registered loses the message.
+//!
+//! With `interop_barrier_enable()`:
+//! 1. `rust_manager_init()` marks the Rust side ready but holds Start.
+//! 2. `interop_cpp_ready()` marks the C++ side ready (actors registered,
+//!    bridge initialized) and installs the C++ lookup.
+//! 3. Once both sides are ready, Rust Start messages are released, from
+//!    whichever call completed the barrier. `interop_barrier_wait()` lets
+//!    other threads block until then.
+
+use std::os::raw::c_int;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Rust,
+    Cpp,
+}
+
+#[derive(Default)]
+struct BarrierState {
+    enabled: bool,
+    rust_ready: bool,
+    cpp_ready: bool,
+}
+
+impl BarrierState {
+    fn released(&self) -> bool {
+        self.rust_ready && self.cpp_ready
+    }
+}
+
+static STATE: Mutex<BarrierState> = Mutex::new(BarrierState { enabled: false, rust_ready: false, cpp_ready: false });
+static RELEASED: Condvar = Condvar::new();
+
+/// Hold Rust Start messages until both sides have signalled.
+/// Call before rust_manager_init().
+#[export_name = c_symbol!("interop_barrier_enable")]
+pub extern "C" fn interop_barrier_enable() {
+    STATE.lock().unwrap().enabled = true;
+}
+
+pub fn is_enabled() -> bool {
+    STATE.lock().unwrap().enabled
+}
+
+/// Mark one side ready. Returns true for the call that completes the
+/// barrier, exactly once.
+pub fn signal(side: Side) -> bool {
+    let mut state = STATE.lock().unwrap();
+    let was_released = state.released();
+    match side {
+        Side::Rust => state.rust_ready = true,
+        Side::Cpp => state.cpp_ready = true,
+    }
+    let completed = !was_released && state.released();
+    if completed {
+        RELEASED.notify_all();
+    }
+    completed
+}
+
+/// Block until both sides are ready. Returns false on timeout.
+pub fn wait(timeout: Duration) -> bool {
+    let state = STATE.lock().unwrap();
+    let (state, _) = RELEASED.wait_timeout_while(state, timeout, |s| !s.released()).unwrap();
+    state.released()
+}
+
+/// Block until both sides are ready, up to `timeout_ms`.
+/// Returns 1 when released, 0 on timeout.
+#[export_name = c_symbol!("interop_barrier_wait")]
+pub extern "C" fn interop_barrier_wait(timeout_ms: u32) -> c_int {
+    if wait(Duration::from_millis(timeout_ms as u64)) { 1 } else { 0 }
+}
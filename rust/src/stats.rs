@@ -0,0 +1,154 @@
+//! Manager statistics for the C++ host's monitoring thread
+//!
+//! `rust_manager_stats(&out)` fills a plain struct: no strings and no
+//! allocation, so it can be sampled often. Counts come from the supervisor
+//! (`lifecycle::Supervised`), which times every handler call.
+//!
+//! Rates and utilization cover the interval since the previous call (since
+//! the Manager started, for the first call), so one sampler should own the
+//! call; use `rust_interop_health()` for ad-hoc inspection. Every actor has
+//! its own thread, so utilization is handler time / wall time per thread.
+//! The CPU time those threads use, against the C++ threads, is in
+//! thread_usage. The bytes and conversions across the language boundary
+//! are counted in boundary.
+
+use std::os::raw::c_int;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::alloc_stats;
+use crate::boundary::{self, Direction};
+use crate::clock;
+use crate::health;
+use crate::interop_errors::InteropErrorCode;
+
+/// Mirrors RustManagerStats in interop/health.h
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RustManagerStats {
+    /// Since rust_manager_init() started the actors; 0 before
+    pub uptime_ms: u64,
+    /// Messages handled by Rust actors since start
+    pub messages_dispatched: u64,
+    /// Messages per second since the previous call
+    pub dispatch_rate: f64,
+    /// Mean fraction (0..1) of time actor threads spent in handlers since
+    /// the previous call
+    pub mean_thread_utilization: f64,
+    /// Highest such fraction of any single actor thread
+    pub max_thread_utilization: f64,
+    /// Actors registered with the Manager
+    pub actor_count: u32,
+    /// health::ManagerState: 0 none, 1 created, 2 running, 3 stopped
+    pub manager_state: u32,
+    /// Bytes the bridge holds for messages queued for Rust actors (see
+    /// alloc_stats)
+    pub live_envelope_bytes: u64,
+    /// C struct bytes converted from C++ to Rust since start (see boundary)
+    pub bytes_to_rust: u64,
+    /// C struct bytes converted from Rust to C++ since start
+    pub bytes_to_cpp: u64,
+    /// Conversions either way per second since the previous call
+    pub conversion_rate: f64,
+}
+
+struct ActorLoad {
+    busy_ns: Arc<AtomicU64>,
+    // busy_ns at the previous sample
+    sampled_ns: u64,
+}
+
+struct Sample {
+    at: Option<Instant>,
+    dispatched: u64,
+    conversions: u64,
+}
+
+static DISPATCHED: AtomicU64 = AtomicU64::new(0);
+static STARTED: Mutex<Option<Instant>> = Mutex::new(None);
+static LOADS: Mutex<Vec<ActorLoad>> = Mutex::new(Vec::new());
+static LAST: Mutex<Sample> = Mutex::new(Sample { at: None, dispatched: 0, conversions: 0 });
+
+/// Mark the Manager started; uptime counts from here
+pub fn start() {
+    let now = clock::now();
+    *STARTED.lock().unwrap() = Some(now);
+    *LAST.lock().unwrap() =
+        Sample { at: Some(now), dispatched: DISPATCHED.load(Ordering::Relaxed), conversions: boundary::conversions() };
+}
+
+/// Busy-time counter for one actor's thread
+pub fn actor_counter() -> Arc<AtomicU64> {
+    let busy_ns = Arc::new(AtomicU64::new(0));
+    LOADS.lock().unwrap().push(ActorLoad { busy_ns: busy_ns.clone(), sampled_ns: 0 });
+    busy_ns
+}
+
+/// Forget the busy-time counters of actors that are gone (restart)
+pub fn clear_actors() {
+    LOADS.lock().unwrap().clear();
+}
+
+/// Count one handled message that took `elapsed`
+pub fn record_dispatch(busy_ns: &AtomicU64, elapsed: Duration) {
+    DISPATCHED.fetch_add(1, Ordering::Relaxed);
+    busy_ns.fetch_add(elapsed.as_nanos().min(u64::MAX as u128) as u64, Ordering::Relaxed);
+}
+
+/// Take a sample, starting the next interval
+pub fn sample() -> RustManagerStats {
+    let now = clock::now();
+    let dispatched = DISPATCHED.load(Ordering::Relaxed);
+    let conversions = boundary::conversions();
+    let mut stats = RustManagerStats {
+        messages_dispatched: dispatched,
+        actor_count: health::actor_count().min(u32::MAX as usize) as u32,
+        manager_state: health::manager_state() as u32,
+        live_envelope_bytes: alloc_stats::total_live_bytes(),
+        bytes_to_rust: boundary::totals(Direction::ToRust).bytes,
+        bytes_to_cpp: boundary::totals(Direction::ToCpp).bytes,
+        ..Default::default()
+    };
+    if let Some(started) = *STARTED.lock().unwrap() {
+        stats.uptime_ms = now.duration_since(started).as_millis().min(u64::MAX as u128) as u64;
+    }
+
+    let mut last = LAST.lock().unwrap();
+    let interval = match last.at {
+        Some(at) => now.duration_since(at),
+        None => return stats,  // not started
+    };
+    let interval_ns = interval.as_nanos() as f64;
+    if interval_ns > 0.0 {
+        stats.dispatch_rate = (dispatched - last.dispatched) as f64 / interval.as_secs_f64();
+        stats.conversion_rate = (conversions - last.conversions) as f64 / interval.as_secs_f64();
+
+        let mut loads = LOADS.lock().unwrap();
+        let mut total = 0.0;
+        for load in loads.iter_mut() {
+            let busy = load.busy_ns.load(Ordering::Relaxed);
+            let utilization = ((busy - load.sampled_ns) as f64 / interval_ns).min(1.0);
+            load.sampled_ns = busy;
+            total += utilization;
+            stats.max_thread_utilization = stats.max_thread_utilization.max(utilization);
+        }
+        if !loads.is_empty() {
+            stats.mean_thread_utilization = total / loads.len() as f64;
+        }
+    }
+    *last = Sample { at: Some(now), dispatched, conversions };
+    stats
+}
+
+/// Fill `out` with the current statistics. Returns 0, or InvalidArgument
+/// if `out` is null.
+#[export_name = c_symbol!("rust_manager_stats")]
+pub extern "C" fn rust_manager_stats(out: *mut RustManagerStats) -> c_int {
+    if out.is_null() {
+        return InteropErrorCode::InvalidArgument as c_int;
+    }
+    let stats = sample();
+    unsafe { *out = stats };
+    0
+}
@@ -0,0 +1,268 @@
+//! Streams: ordered chunk transfers between two actors
+//!
+//! For transfers too large for one message (end-of-day position files), a
+//! stream carries ordered `StreamChunk`s in both directions with
+//! credit-based flow control, so a fast sender cannot flood a slow
+//! receiver's mailbox:
+//!
+//! ```ignore
+//! // Opener (Rust actor "rust_positions")
+//! let mut s = open_stream("rust_positions", "cpp_risk", 8)?;
+//! for part in file.chunks(64 * 1024) {
+//!     s.send(part.to_vec())?;   // queued until the peer grants credit
+//! }
+//! s.finish();
+//!
+//! // In the handlers for StreamOpen / StreamChunk / StreamCredit / StreamEnd
+//! let s = StreamHandle::accept(msg, "rust_risk", 8)?;     // on StreamOpen
+//! match s.handle(msg) {                                   // on the others
+//!     Some(StreamEvent::Chunk(bytes)) => ..,
+//!     Some(StreamEvent::Finished) => ..,                  // peer sent everything
+//!     Some(StreamEvent::Cancelled) | Some(StreamEvent::Failed(_)) => ..,
+//!     None => {}                                          // credit, nothing to do
+//! }
+//! ```
+//!
+//! - Each side may send `credit` chunks, then waits for `StreamCredit`.
+//!   The receiver grants more as it handles chunks (half its window at a
+//!   time). The opener's window is sent in `StreamOpen`, the acceptor's in
+//!   its first `StreamCredit`.
+//! - Chunks carry an index per direction; a gap fails the stream.
+//! - `finish()` ends one direction once its queue has drained
+//!   (`StreamEnd` with `INTEROP_STREAM_COMPLETE` and the chunk count).
+//!   `cancel()` abandons both directions.
+//!
+//! An actor with several streams routes by `stream_id()`. The C++ side is
+//! `interop::Stream` in `interop/stream.hpp`, with the same rules.
+
+use std::collections::VecDeque;
+use std::fmt;
+
+use actors::{ActorRef, Message};
+
+use crate::interop_messages::{StreamChunk, StreamCredit, StreamEnd, StreamOpen};
+use crate::interop_string::{InteropString, TruncationPolicy};
+use crate::rust_manager_ffi::get_actor_ref;
+
+/// StreamEnd.status: this direction is done
+pub const STREAM_COMPLETE: i32 = 0;
+/// StreamEnd.status: both directions abandoned
+pub const STREAM_CANCEL: i32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamError {
+    /// No actor with this name in either runtime
+    NotFound(String),
+    /// send() after finish() or cancel()
+    Closed,
+    /// A chunk index was skipped
+    OutOfOrder { expected: i64, got: i64 },
+    /// StreamEnd reported more chunks than arrived
+    Incomplete { expected: i64, got: i64 },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::NotFound(name) => write!(f, "actor {} not found", name),
+            StreamError::Closed => write!(f, "stream is closed for sending"),
+            StreamError::OutOfOrder { expected, got } => write!(f, "expected chunk {}, got {}", expected, got),
+            StreamError::Incomplete { expected, got } => write!(f, "stream ended after {} of {} chunks", got, expected),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+/// What a stream message meant for the receiving side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    Chunk(Vec<u8>),
+    /// The peer finished sending; every chunk arrived
+    Finished,
+    /// The peer cancelled the stream
+    Cancelled,
+    /// The stream broke (lost chunks); it has been cancelled
+    Failed(StreamError),
+}
+
+/// Stream ID carried by any stream message, None for other messages
+pub fn stream_id(msg: &dyn Message) -> Option<i64> {
+    let any = msg.as_any();
+    any.downcast_ref::<StreamChunk>()
+        .map(|m| m.stream_id)
+        .or_else(|| any.downcast_ref::<StreamCredit>().map(|m| m.stream_id))
+        .or_else(|| any.downcast_ref::<StreamEnd>().map(|m| m.stream_id))
+        .or_else(|| any.downcast_ref::<StreamOpen>().map(|m| m.stream_id))
+}
+
+/// One side of a stream
+pub struct StreamHandle {
+    id: i64,
+    peer: ActorRef,
+    // Sending
+    credit: u32,
+    queue: VecDeque<Vec<u8>>,
+    sent: i64,
+    finishing: bool,
+    sent_end: bool,
+    // Receiving
+    window: u32,
+    received: i64,
+    ungranted: u32,
+    peer_done: bool,
+}
+
+/// Open a stream from actor `me` to `target`, letting the peer send up to
+/// `window` chunks ahead of this side's handler
+pub fn open_stream(me: &str, target: &str, window: u32) -> Result<StreamHandle, StreamError> {
+    let peer = get_actor_ref(target, me).ok_or_else(|| StreamError::NotFound(target.to_string()))?;
+    let origin = InteropString::with_policy(me, TruncationPolicy::Reject)
+        .map_err(|_| StreamError::NotFound(me.to_string()))?;
+    let window = window.max(1);
+    let id = loop {
+        let id = rand::random::<i64>();
+        if id != 0 {
+            break id;
+        }
+    };
+    peer.send(Box::new(StreamOpen { stream_id: id, origin, credit: window as i32 }), None);
+    Ok(StreamHandle::new(id, peer, 0, window))
+}
+
+impl StreamHandle {
+    fn new(id: i64, peer: ActorRef, credit: u32, window: u32) -> Self {
+        StreamHandle {
+            id,
+            peer,
+            credit,
+            queue: VecDeque::new(),
+            sent: 0,
+            finishing: false,
+            sent_end: false,
+            window,
+            received: 0,
+            ungranted: 0,
+            peer_done: false,
+        }
+    }
+
+    /// Accept a stream opened to actor `me`, with a receive window of
+    /// `window` chunks
+    pub fn accept(open: &StreamOpen, me: &str, window: u32) -> Result<Self, StreamError> {
+        let origin = open.origin.as_str();
+        let peer = get_actor_ref(origin, me).ok_or_else(|| StreamError::NotFound(origin.to_string()))?;
+        let window = window.max(1);
+        peer.send(Box::new(StreamCredit { stream_id: open.stream_id, credit: window as i32 }), None);
+        Ok(StreamHandle::new(open.stream_id, peer, open.credit.max(0) as u32, window))
+    }
+
+    pub fn stream_id(&self) -> i64 {
+        self.id
+    }
+
+    /// Queue a chunk; it goes out as soon as the peer has granted credit
+    pub fn send(&mut self, chunk: Vec<u8>) -> Result<(), StreamError> {
+        if self.finishing || self.sent_end {
+            return Err(StreamError::Closed);
+        }
+        self.queue.push_back(chunk);
+        self.flush();
+        Ok(())
+    }
+
+    /// Chunks waiting for credit
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// End this direction after the queued chunks
+    pub fn finish(&mut self) {
+        self.finishing = true;
+        self.flush();
+    }
+
+    /// Abandon the stream in both directions; queued chunks are dropped
+    pub fn cancel(&mut self) {
+        if !self.sent_end {
+            self.queue.clear();
+            self.end(STREAM_CANCEL);
+        }
+        self.peer_done = true;
+    }
+
+    /// Both directions are over
+    pub fn is_closed(&self) -> bool {
+        self.sent_end && self.peer_done
+    }
+
+    fn end(&mut self, status: i32) {
+        self.sent_end = true;
+        let end = StreamEnd { stream_id: self.id, status, chunks: self.sent };
+        self.peer.send(Box::new(end), None);
+    }
+
+    fn flush(&mut self) {
+        while self.credit > 0 && !self.sent_end {
+            let data = match self.queue.pop_front() {
+                Some(d) => d,
+                None => break,
+            };
+            let chunk = StreamChunk { stream_id: self.id, index: self.sent, data };
+            self.peer.send(Box::new(chunk), None);
+            self.sent += 1;
+            self.credit -= 1;
+        }
+        if self.finishing && self.queue.is_empty() && !self.sent_end {
+            self.end(STREAM_COMPLETE);
+        }
+    }
+
+    fn fail(&mut self, err: StreamError) -> StreamEvent {
+        self.cancel();
+        StreamEvent::Failed(err)
+    }
+
+    /// Handle a StreamChunk / StreamCredit / StreamEnd for this stream.
+    /// Returns None for credit and for messages of other streams.
+    pub fn handle(&mut self, msg: &dyn Message) -> Option<StreamEvent> {
+        if stream_id(msg) != Some(self.id) || self.peer_done {
+            return None;
+        }
+        let any = msg.as_any();
+        if let Some(c) = any.downcast_ref::<StreamCredit>() {
+            self.credit = self.credit.saturating_add(c.credit.max(0) as u32);
+            self.flush();
+            return None;
+        }
+        if let Some(chunk) = any.downcast_ref::<StreamChunk>() {
+            if chunk.index != self.received {
+                let err = StreamError::OutOfOrder { expected: self.received, got: chunk.index };
+                return Some(self.fail(err));
+            }
+            self.received += 1;
+            self.ungranted += 1;
+            if self.ungranted >= self.window.div_ceil(2) {
+                let grant = StreamCredit { stream_id: self.id, credit: self.ungranted as i32 };
+                self.peer.send(Box::new(grant), None);
+                self.ungranted = 0;
+            }
+            return Some(StreamEvent::Chunk(chunk.data.clone()));
+        }
+        if let Some(end) = any.downcast_ref::<StreamEnd>() {
+            if end.status == STREAM_CANCEL {
+                self.peer_done = true;
+                self.sent_end = true;  // nothing more may go out
+                self.queue.clear();
+                return Some(StreamEvent::Cancelled);
+            }
+            if end.chunks != self.received {
+                let err = StreamError::Incomplete { expected: end.chunks, got: self.received };
+                return Some(self.fail(err));
+            }
+            self.peer_done = true;
+            return Some(StreamEvent::Finished);
+        }
+        None
+    }
+}
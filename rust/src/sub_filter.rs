@@ -0,0 +1,424 @@
+//! Filters and transforms per subscription, applied by the publisher
+//!
+//! A dashboard that only shows large trades still receives every update
+//! of its topic and throws most of them away after they have crossed the
+//! bridge. A subscriber can instead give its subscription a filter, which
+//! the publisher side applies before sending, so what it does not want
+//! never crosses:
+//!
+//! ```ignore
+//! sub_filter::subscribe("rust_dashboard", "rust_feed", "trades", QOS_BEST_EFFORT, "volume > 1000")?;
+//!
+//! // At most one update per symbol every 100 ms
+//! sub_filter::set("rust_chart", "rust_feed", "quotes", "every 100ms by symbol")?;
+//! ```
+//!
+//! ```cpp
+//! interop_set_subscription_filter("cpp_monitor", "rust_feed", "trades", "symbol == AAPL && price >= 150.5");
+//! ```
+//!
+//! A filter is one or more stages separated by `|`, each handed what the
+//! one before let through:
+//!
+//! - `field op value` conditions joined by `&&`, op one of `==`, `!=`,
+//!   `<`, `<=`, `>` and `>=`. A number is compared with numeric and bool
+//!   fields, anything else (quoted or not) with the text of string and
+//!   integer fields. A message without the field fails the condition.
+//! - `every <n>ms` (or `<n>s`) `[by <field>]`: at most one message per
+//!   interval, per value of the field if given. The rest are dropped, not
+//!   delayed. Intervals follow the clock (see clock).
+//! - `@name`: a transform registered with `register_transform`, which
+//!   drops the message or sends it or another in its place. Each
+//!   subscription has an instance of its own, so it can keep state.
+//!
+//! Values cannot hold `|` or `&&`. A filter can be set before or after
+//! subscribing and goes away with the subscription; setting another
+//! replaces it and its state. The broker's publishes apply the filter of
+//! each subscription, on the topic as subscribed (a partition topic is a
+//! topic of its own). C++ publishers that send to their subscribers
+//! themselves ask `interop_subscription_filter_check()`, which decides
+//! whether to send; a transform's replacement message is not applied
+//! there.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use actors::Message;
+
+use crate::broker;
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_field_key, message_field_number, message_from_c_borrowed};
+use crate::names::c_str;
+
+/// One subscription's instance of a registered transform: the message to
+/// send (the one passed or another), or None to drop it
+pub type Transform = Box<dyn FnMut(Box<dyn Message>) -> Option<Box<dyn Message>> + Send>;
+
+type TransformFactory = Arc<dyn Fn() -> Transform + Send + Sync>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FilterError {
+    /// A stage that is not a condition list, `every` or `@name`
+    Parse { stage: String, reason: &'static str },
+    /// `@name` of no registered transform
+    UnknownTransform(String),
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::Parse { stage, reason } => write!(f, "'{}': {}", stage, reason),
+            FilterError::UnknownTransform(name) => write!(f, "no transform named '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+fn bad(stage: &str, reason: &'static str) -> FilterError {
+    FilterError::Parse { stage: stage.to_string(), reason }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn holds(self, ord: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::*;
+        match self {
+            Op::Eq => ord == Equal,
+            Op::Ne => ord != Equal,
+            Op::Lt => ord == Less,
+            Op::Le => ord != Greater,
+            Op::Gt => ord == Greater,
+            Op::Ge => ord != Less,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Cond {
+    field: String,
+    op: Op,
+    value: Value,
+}
+
+impl Cond {
+    fn holds(&self, msg: &dyn Message) -> bool {
+        let ord = match &self.value {
+            Value::Number(n) => message_field_number(msg, &self.field).and_then(|v| v.partial_cmp(n)),
+            Value::Text(t) => message_field_key(msg, &self.field).map(|v| v.as_str().cmp(t)),
+        };
+        ord.map_or(false, |o| self.op.holds(o))
+    }
+}
+
+/// `field op value` conditions joined by `&&`, all of which must hold;
+/// also the rules of content_routes
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Conditions(Vec<Cond>);
+
+impl Conditions {
+    pub(crate) fn parse(text: &str) -> Result<Self, FilterError> {
+        text.split("&&").map(|c| parse_cond(c.trim())).collect::<Result<_, _>>().map(Conditions)
+    }
+
+    pub(crate) fn hold(&self, msg: &dyn Message) -> bool {
+        self.0.iter().all(|c| c.holds(msg))
+    }
+}
+
+enum Stage {
+    Where(Conditions),
+    Every { interval: Duration, by: Option<String>, last: HashMap<String, Instant> },
+    Transform(String, Transform),
+}
+
+/// A parsed filter with its state
+struct Filter {
+    spec: String,
+    stages: Vec<Stage>,
+    dropped: u64,
+}
+
+impl Filter {
+    fn apply(&mut self, mut msg: Box<dyn Message>) -> Option<Box<dyn Message>> {
+        for stage in &mut self.stages {
+            msg = match stage {
+                Stage::Where(conds) => conds.hold(msg.as_ref()).then_some(msg)?,
+                Stage::Every { interval, by, last } => {
+                    let key = by.as_deref().and_then(|f| message_field_key(msg.as_ref(), f)).unwrap_or_default();
+                    let now = clock::now();
+                    if last.get(&key).map_or(false, |&t| now.saturating_duration_since(t) < *interval) {
+                        return None;
+                    }
+                    last.insert(key, now);
+                    msg
+                }
+                Stage::Transform(name, transform) => match panic::catch_unwind(AssertUnwindSafe(|| transform(msg))) {
+                    Ok(next) => next?,
+                    Err(_) => {
+                        eprintln!("[SubFilter] transform '{}' panicked, message dropped", name);
+                        return None;
+                    }
+                },
+            };
+        }
+        Some(msg)
+    }
+}
+
+// Fast path: no lookups while no filter is set
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    // (publisher, topic, subscriber) -> filter
+    static ref FILTERS: RwLock<HashMap<(String, String, String), Arc<Mutex<Filter>>>> = RwLock::new(HashMap::new());
+    static ref TRANSFORMS: RwLock<HashMap<String, TransformFactory>> = RwLock::new(HashMap::new());
+}
+
+/// Make `@name` available to filters; `factory` is called once per
+/// subscription that uses it. Filters already set keep the old transform.
+pub fn register_transform<F>(name: &str, factory: F)
+where
+    F: Fn() -> Transform + Send + Sync + 'static,
+{
+    TRANSFORMS.write().unwrap().insert(name.to_string(), Arc::new(factory));
+}
+
+fn unquote(value: &str) -> Option<&str> {
+    let first = value.chars().next()?;
+    (value.len() >= 2 && (first == '"' || first == '\'') && value.ends_with(first)).then(|| &value[1..value.len() - 1])
+}
+
+fn is_field(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_cond(text: &str) -> Result<Cond, FilterError> {
+    const OPS: [(&str, Op); 6] =
+        [("==", Op::Eq), ("!=", Op::Ne), ("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)];
+    // The leftmost operator, two characters before one at the same place
+    let (at, sym, op) = OPS
+        .iter()
+        .filter_map(|&(sym, op)| text.find(sym).map(|at| (at, sym, op)))
+        .min_by_key(|&(at, sym, _)| (at, std::cmp::Reverse(sym.len())))
+        .ok_or_else(|| bad(text, "expected field op value"))?;
+    let field = text[..at].trim();
+    let value = text[at + sym.len()..].trim();
+    if !is_field(field) {
+        return Err(bad(text, "expected a field name"));
+    }
+    if value.is_empty() {
+        return Err(bad(text, "expected a value"));
+    }
+    let value = match unquote(value) {
+        Some(quoted) => Value::Text(quoted.to_string()),
+        None => value.parse().map_or_else(|_| Value::Text(value.to_string()), Value::Number),
+    };
+    Ok(Cond { field: field.to_string(), op, value })
+}
+
+fn parse_interval(text: &str) -> Option<Duration> {
+    let interval = match text.strip_suffix("ms") {
+        Some(ms) => Duration::from_millis(ms.parse().ok()?),
+        None => Duration::from_secs(text.strip_suffix('s')?.parse().ok()?),
+    };
+    (!interval.is_zero()).then_some(interval)
+}
+
+fn parse_stage(text: &str) -> Result<Stage, FilterError> {
+    if let Some(name) = text.strip_prefix('@') {
+        let factory = TRANSFORMS.read().unwrap().get(name).cloned();
+        return match factory {
+            Some(factory) => Ok(Stage::Transform(name.to_string(), factory())),
+            None => Err(FilterError::UnknownTransform(name.to_string())),
+        };
+    }
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.first() == Some(&"every") {
+        let interval = words.get(1).and_then(|w| parse_interval(w)).ok_or_else(|| bad(text, "expected an interval"))?;
+        let by = match &words[2..] {
+            [] => None,
+            ["by", field] if is_field(field) => Some(field.to_string()),
+            _ => return Err(bad(text, "expected every <interval> [by <field>]")),
+        };
+        return Ok(Stage::Every { interval, by, last: HashMap::new() });
+    }
+    Conditions::parse(text).map(Stage::Where)
+}
+
+fn parse(spec: &str) -> Result<Vec<Stage>, FilterError> {
+    spec.split('|')
+        .map(str::trim)
+        .map(|stage| if stage.is_empty() { Err(bad(stage, "empty stage")) } else { parse_stage(stage) })
+        .collect()
+}
+
+fn key(subscriber: &str, publisher: &str, topic: &str) -> (String, String, String) {
+    (publisher.to_string(), topic.to_string(), subscriber.to_string())
+}
+
+/// Filter what `publisher` sends `subscriber` on `topic` by `spec`,
+/// replacing an earlier filter; a blank `spec` clears it
+pub fn set(subscriber: &str, publisher: &str, topic: &str, spec: &str) -> Result<(), FilterError> {
+    if spec.trim().is_empty() {
+        clear(subscriber, publisher, topic);
+        return Ok(());
+    }
+    let filter = Filter { spec: spec.trim().to_string(), stages: parse(spec)?, dropped: 0 };
+    let mut filters = FILTERS.write().unwrap();
+    filters.insert(key(subscriber, publisher, topic), Arc::new(Mutex::new(filter)));
+    ACTIVE.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// Remove the filter of a subscription. False if it had none.
+pub fn clear(subscriber: &str, publisher: &str, topic: &str) -> bool {
+    let mut filters = FILTERS.write().unwrap();
+    let removed = filters.remove(&key(subscriber, publisher, topic)).is_some();
+    ACTIVE.store(!filters.is_empty(), Ordering::Release);
+    removed
+}
+
+/// The filter of a subscription as set
+pub fn filter_of(subscriber: &str, publisher: &str, topic: &str) -> Option<String> {
+    let filter = FILTERS.read().unwrap().get(&key(subscriber, publisher, topic)).cloned()?;
+    let spec = filter.lock().unwrap().spec.clone();
+    Some(spec)
+}
+
+/// Messages the filter of a subscription has kept from its subscriber
+pub fn dropped(subscriber: &str, publisher: &str, topic: &str) -> u64 {
+    FILTERS.read().unwrap().get(&key(subscriber, publisher, topic)).map_or(0, |f| f.lock().unwrap().dropped)
+}
+
+/// Set the filter, then subscribe Rust actor `me` to `topic` of
+/// `publisher` (see broker). Ok(false) if the publisher is not found or
+/// the topic is too long; the filter is not kept.
+pub fn subscribe(me: &str, publisher: &str, topic: &str, qos: i32, spec: &str) -> Result<bool, FilterError> {
+    set(me, publisher, topic, spec)?;
+    if broker::subscribe(me, publisher, topic, qos) {
+        return Ok(true);
+    }
+    clear(me, publisher, topic);
+    Ok(false)
+}
+
+/// What to send `subscriber` in place of `msg`, None for nothing. Called
+/// by the broker for each subscription.
+pub(crate) fn apply(publisher: &str, topic: &str, subscriber: &str, msg: Box<dyn Message>) -> Option<Box<dyn Message>> {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return Some(msg);
+    }
+    let filter = match FILTERS.read().unwrap().get(&key(subscriber, publisher, topic)) {
+        Some(f) => f.clone(),
+        None => return Some(msg),
+    };
+    let mut filter = filter.lock().unwrap();
+    let sent = filter.apply(msg);
+    if sent.is_none() {
+        filter.dropped += 1;
+    }
+    sent
+}
+
+/// Called by the broker when a subscription is removed
+pub(crate) fn forget(publisher: &str, topic: &str, subscriber: &str) {
+    if ACTIVE.load(Ordering::Acquire) {
+        clear(subscriber, publisher, topic);
+    }
+}
+
+/// Filter what `publisher` sends `subscriber` on `topic` by `spec`; null
+/// or blank clears it. Returns 0, InvalidName on a bad name,
+/// InvalidArgument if `spec` does not parse, or NotFound if it names an
+/// unknown transform (see stderr).
+#[export_name = c_symbol!("interop_set_subscription_filter")]
+pub extern "C" fn interop_set_subscription_filter(
+    subscriber: *const c_char,
+    publisher: *const c_char,
+    topic: *const c_char,
+    spec: *const c_char,
+) -> c_int {
+    let (s, p, t) = match (c_str(subscriber), c_str(publisher), c_str(topic)) {
+        (Some(s), Some(p), Some(t)) => (s, p, t),
+        _ => return InteropErrorCode::InvalidName as c_int,
+    };
+    let spec = if spec.is_null() {
+        ""
+    } else {
+        match c_str(spec) {
+            Some(spec) => spec,
+            None => return InteropErrorCode::InvalidArgument as c_int,
+        }
+    };
+    let rc = match set(s, p, t, spec) {
+        Ok(()) => InteropErrorCode::Ok,
+        Err(e) => {
+            eprintln!("[SubFilter] {} <- {} on {}: {}", s, p, t, e);
+            match e {
+                FilterError::Parse { .. } => InteropErrorCode::InvalidArgument,
+                FilterError::UnknownTransform(_) => InteropErrorCode::NotFound,
+            }
+        }
+    };
+    rc as c_int
+}
+
+/// Whether to send C struct `msg_data` (message `msg_id`) to `subscriber`
+/// of `topic` of `publisher`, advancing the filter's state as a send would.
+/// Returns 1 to send, 0 not to, InvalidArgument on a bad name, or
+/// UnknownMessage.
+#[export_name = c_symbol!("interop_subscription_filter_check")]
+pub extern "C" fn interop_subscription_filter_check(
+    subscriber: *const c_char,
+    publisher: *const c_char,
+    topic: *const c_char,
+    msg_id: c_int,
+    msg_data: *const c_void,
+) -> c_int {
+    let (s, p, t) = match (c_str(subscriber), c_str(publisher), c_str(topic)) {
+        (Some(s), Some(p), Some(t)) if !msg_data.is_null() => (s, p, t),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    if !ACTIVE.load(Ordering::Acquire) {
+        return 1;
+    }
+    match unsafe { message_from_c_borrowed(msg_id, msg_data) } {
+        Some(msg) => apply(p, t, s, msg).is_some() as c_int,
+        None => InteropErrorCode::UnknownMessage as c_int,
+    }
+}
+
+/// Messages the filter of a subscription has kept from its subscriber
+#[export_name = c_symbol!("interop_subscription_filter_dropped")]
+pub extern "C" fn interop_subscription_filter_dropped(
+    subscriber: *const c_char,
+    publisher: *const c_char,
+    topic: *const c_char,
+) -> u64 {
+    match (c_str(subscriber), c_str(publisher), c_str(topic)) {
+        (Some(s), Some(p), Some(t)) => dropped(s, p, t),
+        _ => 0,
+    }
+}
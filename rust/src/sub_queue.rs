@@ -0,0 +1,302 @@
+//! Per-subscriber queues with overflow policies
+//!
+//! A mailbox is unbounded: a subscriber that falls behind a busy topic
+//! reads ever older updates. A Rust subscriber that or's an overflow policy
+//! into `Subscribe.qos` gets a bounded queue of its own in front of its
+//! mailbox instead, so consumers of one topic choose independently:
+//!
+//! ```ignore
+//! // Latest update per symbol, at most 64 symbols behind
+//! broker::subscribe("rust_monitor", "cpp_feed", "quotes", QOS_BEST_EFFORT | SUB_CONFLATE | sub_capacity(64));
+//! // Every update; the publisher waits while 10000 are queued
+//! broker::subscribe("rust_recorder", "cpp_feed", "quotes", QOS_BEST_EFFORT | SUB_BLOCK | sub_capacity(10_000));
+//! ```
+//!
+//! | Policy | When the queue is full |
+//! |--------|------------------------|
+//! | `SUB_DROP_OLDEST` | the oldest queued message is dropped |
+//! | `SUB_DROP_NEWEST` | the new message is dropped |
+//! | `SUB_CONFLATE` | a queued message of the same type and `symbol` is replaced at once, full or not; else the oldest is dropped |
+//! | `SUB_BLOCK` | the publisher waits for room |
+//!
+//! - The queue hands the subscriber one message at a time: the next goes
+//!   to the mailbox once the previous one has been handled, so what waits
+//!   is in the queue, where the policy applies.
+//! - There is one queue per (publisher, subscriber), set up when the bridge
+//!   or broker records the Subscribe; the latest Subscribe's policy wins.
+//!   It covers `broker::publish` and bridge sends from C++ publishers
+//!   (`rust_actor_send`, not `rust_actor_fast_send`). The queue is removed,
+//!   and what it holds delivered, on the last Unsubscribe.
+//! - C++ subscribers are sent to directly: Rust cannot tell when a C++
+//!   actor has handled a message.
+//! - `SUB_BLOCK` waits on the publisher's thread, so a publisher must not
+//!   use it for a subscriber on its own thread (see shared_thread).
+//!
+//! Dropped and replaced messages are counted; C++ reads the count with
+//! `rust_subscriber_dropped()`.
+
+use std::collections::{HashMap, VecDeque};
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use actors::{ActorRef, Message};
+
+use crate::interop_messages::message_field_key;
+use crate::names::{self, c_str};
+use crate::pause;
+use crate::qos::{self, OverflowPolicy};
+use crate::rust_manager_ffi::get_actor_ref;
+
+/// Field a conflating queue keys messages by, with their type
+pub const CONFLATION_FIELD: &str = "symbol";
+
+struct Queued {
+    // (type, symbol) under SUB_CONFLATE, if the message has a symbol
+    key: Option<(i32, String)>,
+    msg: Box<dyn Message>,
+    sender: Option<ActorRef>,
+}
+
+struct State {
+    policy: OverflowPolicy,
+    capacity: usize,
+    items: VecDeque<Queued>,
+    // A message is in the subscriber's mailbox, not yet handled
+    in_flight: bool,
+    // Set when the queue is removed; blocked publishers stop waiting
+    closed: bool,
+}
+
+struct Queue {
+    publisher: String,
+    subscriber: String,
+    state: Mutex<State>,
+    // Signalled when a message leaves the queue, for blocked publishers
+    room: Condvar,
+    dropped: AtomicU64,
+}
+
+// Fast path: no subscriber has a queue
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref QUEUES: Mutex<HashMap<(String, String), Arc<Queue>>> = Mutex::new(HashMap::new());
+}
+
+/// Apply the overflow policy of a Subscribe with `qos` from `subscriber`
+/// to `publisher`: set up or update its queue, or remove it if the QoS has
+/// no policy. Called by the broker as it records the Subscribe.
+pub fn configure(publisher: &str, subscriber: &str, qos: i32) {
+    let policy = match OverflowPolicy::from_flag(qos) {
+        Some(p) if names::is_rust(subscriber) => p,
+        _ => return remove(publisher, subscriber),
+    };
+    let capacity = qos::capacity_of(qos);
+    let mut queues = QUEUES.lock().unwrap();
+    let key = (publisher.to_string(), subscriber.to_string());
+    if let Some(queue) = queues.get(&key) {
+        let mut state = queue.state.lock().unwrap();
+        state.policy = policy;
+        state.capacity = capacity;
+        drop(state);
+        queue.room.notify_all();
+        return;
+    }
+    queues.insert(
+        key,
+        Arc::new(Queue {
+            publisher: publisher.to_string(),
+            subscriber: subscriber.to_string(),
+            state: Mutex::new(State {
+                policy,
+                capacity,
+                items: VecDeque::new(),
+                in_flight: false,
+                closed: false,
+            }),
+            room: Condvar::new(),
+            dropped: AtomicU64::new(0),
+        }),
+    );
+    ACTIVE.store(true, Ordering::Release);
+}
+
+/// Remove the queue of `subscriber` for `publisher`, delivering what it
+/// holds
+pub fn remove(publisher: &str, subscriber: &str) {
+    let mut queues = QUEUES.lock().unwrap();
+    let old = queues.remove(&(publisher.to_string(), subscriber.to_string()));
+    ACTIVE.store(!queues.is_empty(), Ordering::Release);
+    drop(queues);
+    let queue = match old {
+        Some(q) => q,
+        None => return,
+    };
+    let held = {
+        let mut state = queue.state.lock().unwrap();
+        state.closed = true;
+        std::mem::take(&mut state.items)
+    };
+    queue.room.notify_all();
+    if let Some(target) = get_actor_ref(&queue.subscriber, &queue.publisher) {
+        for q in held {
+            pause::send(&target, &queue.subscriber, q.msg, q.sender);
+        }
+    }
+}
+
+/// Overflow policy of `subscriber`'s queue for `publisher`, None without one
+pub fn policy(publisher: &str, subscriber: &str) -> Option<OverflowPolicy> {
+    find(publisher, subscriber).map(|q| q.state.lock().unwrap().policy)
+}
+
+/// Messages waiting in `subscriber`'s queue for `publisher`
+pub fn pending(publisher: &str, subscriber: &str) -> usize {
+    find(publisher, subscriber).map_or(0, |q| q.state.lock().unwrap().items.len())
+}
+
+/// Messages from `publisher` dropped or replaced in `subscriber`'s queue
+pub fn dropped(publisher: &str, subscriber: &str) -> u64 {
+    find(publisher, subscriber).map_or(0, |q| q.dropped.load(Ordering::Relaxed))
+}
+
+fn find(publisher: &str, subscriber: &str) -> Option<Arc<Queue>> {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return None;
+    }
+    QUEUES.lock().unwrap().get(&(publisher.to_string(), subscriber.to_string())).cloned()
+}
+
+/// Queue `msg` from `publisher` if `subscriber` has a queue for it. Gives
+/// the message back if not, and the caller sends it directly; None if it
+/// was queued, handed to the mailbox or dropped by the policy.
+pub fn offer(
+    publisher: &str,
+    subscriber: &str,
+    msg: Box<dyn Message>,
+    sender: Option<ActorRef>,
+) -> Option<Box<dyn Message>> {
+    match queue_message(publisher, subscriber, msg, sender, true) {
+        Ok(unqueued) => unqueued,
+        Err(msg) => Some(msg),  // not reached: offer waits
+    }
+}
+
+/// `offer` that does not wait: Err gives the message back if a `SUB_BLOCK`
+/// queue is full (see fairness)
+pub fn try_offer(
+    publisher: &str,
+    subscriber: &str,
+    msg: Box<dyn Message>,
+    sender: Option<ActorRef>,
+) -> Result<Option<Box<dyn Message>>, Box<dyn Message>> {
+    queue_message(publisher, subscriber, msg, sender, false)
+}
+
+fn queue_message(
+    publisher: &str,
+    subscriber: &str,
+    msg: Box<dyn Message>,
+    sender: Option<ActorRef>,
+    wait: bool,
+) -> Result<Option<Box<dyn Message>>, Box<dyn Message>> {
+    let queue = match find(publisher, subscriber) {
+        Some(q) => q,
+        None => return Ok(Some(msg)),
+    };
+    let mut state = queue.state.lock().unwrap();
+    if !state.in_flight {
+        state.in_flight = true;
+        drop(state);
+        dispatch(&queue, msg, sender);
+        return Ok(None);
+    }
+    let key = match state.policy {
+        OverflowPolicy::Conflate => message_field_key(msg.as_ref(), CONFLATION_FIELD).map(|k| (msg.message_id(), k)),
+        _ => None,
+    };
+    if let Some(k) = &key {
+        if let Some(q) = state.items.iter_mut().find(|q| q.key.as_ref() == Some(k)) {
+            q.msg = msg;
+            q.sender = sender;
+            queue.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        }
+    }
+    while state.items.len() >= state.capacity {
+        match state.policy {
+            OverflowPolicy::DropNewest => {
+                queue.dropped.fetch_add(1, Ordering::Relaxed);
+                return Ok(None);
+            }
+            OverflowPolicy::DropOldest | OverflowPolicy::Conflate => {
+                state.items.pop_front();
+                queue.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            OverflowPolicy::Block if state.closed => break,
+            OverflowPolicy::Block if !wait => return Err(msg),
+            OverflowPolicy::Block => state = queue.room.wait(state).unwrap(),
+        }
+    }
+    if state.closed {
+        // Removed while we waited: deliver directly
+        return Ok(Some(msg));
+    }
+    state.items.push_back(Queued { key, msg, sender });
+    Ok(None)
+}
+
+/// Message in the subscriber's mailbox; handing it over releases the next
+struct InFlight {
+    inner: Box<dyn Message>,
+    queue: Arc<Queue>,
+}
+
+impl Message for InFlight {
+    fn as_any(&self) -> &dyn std::any::Any { self.inner.as_any() }
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any { self.inner.as_any_mut() }
+    fn message_id(&self) -> i32 { self.inner.message_id() }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let next = {
+            let mut state = self.queue.state.lock().unwrap();
+            let next = state.items.pop_front();
+            state.in_flight = next.is_some();
+            next
+        };
+        if let Some(next) = next {
+            self.queue.room.notify_all();
+            dispatch(&self.queue, next.msg, next.sender);
+        }
+    }
+}
+
+fn dispatch(queue: &Arc<Queue>, msg: Box<dyn Message>, sender: Option<ActorRef>) {
+    match get_actor_ref(&queue.subscriber, &queue.publisher) {
+        Some(target) => {
+            let msg = Box::new(InFlight { inner: msg, queue: queue.clone() });
+            pause::send(&target, &queue.subscriber, msg, sender);
+        }
+        None => {
+            // Subscriber gone: nothing will release the queue
+            let mut state = queue.state.lock().unwrap();
+            state.items.clear();
+            state.in_flight = false;
+            drop(state);
+            queue.room.notify_all();
+        }
+    }
+}
+
+/// Messages from `publisher_name` dropped or replaced by the overflow
+/// policy of `subscriber_name`'s queue (0 without one or on a bad name)
+#[export_name = c_symbol!("rust_subscriber_dropped")]
+pub extern "C" fn rust_subscriber_dropped(publisher_name: *const c_char, subscriber_name: *const c_char) -> u64 {
+    match (c_str(publisher_name), c_str(subscriber_name)) {
+        (Some(p), Some(s)) => dropped(p, s),
+        _ => 0,
+    }
+}
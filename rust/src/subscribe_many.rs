@@ -0,0 +1,72 @@
+//! Many topics in one Subscribe
+//!
+//! Subscribe carries one topic, so subscribing to 500 symbols is 500
+//! messages across the bridge. `SubscribeMany` / `UnsubscribeMany` carry
+//! a list of topics in one bytes field, each name followed by a NUL, and
+//! the bridge records each topic as if it had come in a Subscribe of its
+//! own (see broker, lease):
+//!
+//! ```ignore
+//! // Subscriber
+//! broker::subscribe_many("rust_monitor", "cpp_feed", &symbols, QOS_BEST_EFFORT);
+//!
+//! // Publisher handler
+//! fn on_subscribe_many(&mut self, msg: &SubscribeMany, _ctx: &mut ActorContext) {
+//!     for topic in msg.topics() {
+//!         self.start(topic);
+//!     }
+//! }
+//! ```
+//!
+//! Names follow the rules of `Subscribe.topic`: building a list refuses a
+//! name over 31 bytes, and reading one skips names that are too long or
+//! not UTF-8. A publisher that only handles Subscribe still has the
+//! subscriptions recorded by the broker. C++ builds lists with
+//! `interop::topic_list()` (`interop/subscribe_many.hpp`).
+
+use crate::interop_messages::{SubscribeMany, UnsubscribeMany};
+use crate::interop_string::{InteropString, TruncationPolicy};
+use crate::topic::TopicError;
+
+/// Longest topic name, as in `Subscribe.topic`
+pub const TOPIC_MAX: usize = 31;
+
+/// `topics` as a topic list. Fails on a name that would not fit in
+/// `Subscribe.topic`.
+pub fn encode<S: AsRef<str>>(topics: &[S]) -> Result<Vec<u8>, TopicError> {
+    let mut list = Vec::with_capacity(topics.len() * 8);
+    for topic in topics {
+        let name: InteropString<32> =
+            InteropString::with_policy(topic.as_ref(), TruncationPolicy::Reject).map_err(TopicError::NameTooLong)?;
+        list.extend_from_slice(name.as_str().as_bytes());
+        list.push(0);
+    }
+    Ok(list)
+}
+
+/// The names in topic list `list`
+pub fn decode(list: &[u8]) -> impl Iterator<Item = &str> {
+    list.split(|&b| b == 0)
+        .filter(|name| !name.is_empty() && name.len() <= TOPIC_MAX)
+        .filter_map(|name| std::str::from_utf8(name).ok())
+}
+
+impl SubscribeMany {
+    pub fn new<S: AsRef<str>>(topics: &[S], qos: i32) -> Result<Self, TopicError> {
+        Ok(SubscribeMany { topics: encode(topics)?, qos })
+    }
+
+    pub fn topics(&self) -> impl Iterator<Item = &str> {
+        decode(&self.topics)
+    }
+}
+
+impl UnsubscribeMany {
+    pub fn new<S: AsRef<str>>(topics: &[S]) -> Result<Self, TopicError> {
+        Ok(UnsubscribeMany { topics: encode(topics)? })
+    }
+
+    pub fn topics(&self) -> impl Iterator<Item = &str> {
+        decode(&self.topics)
+    }
+}
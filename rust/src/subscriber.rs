@@ -0,0 +1,268 @@
+//! Subscriptions of Rust subscribers changed at run time by the C++ host
+//!
+//! A C++ GUI changes what a Rust monitor watches by sending it
+//! `SubscribeRequest` / `UnsubscribeRequest`, or with the FFI helpers:
+//!
+//! ```c
+//! rust_subscriber_subscribe("rust_price_monitor", "MSFT");     // interop/subscriber.h
+//! rust_subscriber_unsubscribe("rust_price_monitor", "AAPL");
+//! ```
+//!
+//! The actor implements `DynamicSubscriber`, which handles both requests
+//! through the broker, and lists them in `handle_messages!`:
+//!
+//! ```ignore
+//! impl DynamicSubscriber for RustMonitor {
+//!     fn subscriber_name(&self) -> &str { "rust_price_monitor" }
+//!     fn default_publisher(&self) -> &str { "cpp_price_feed" }
+//!     fn subscriptions(&mut self) -> &mut Subscriptions { &mut self.subs }
+//! }
+//!
+//! handle_messages!(RustMonitor,
+//!     MarketUpdate => on_market_update,
+//!     SubscribeRequest => on_subscribe_request,
+//!     UnsubscribeRequest => on_unsubscribe_request,
+//! );
+//! ```
+//!
+//! `Subscriptions` remembers what the actor holds, so it can subscribe
+//! again after a restart (`resubscribe`).
+//!
+//! A publisher that restarts, in either language, forgets its subscribers.
+//! With `watch_restarts` the subscriber is told through `$sys` events and
+//! subscribes again by itself:
+//!
+//! ```ignore
+//! fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+//!     self.subs.subscribe("rust_price_monitor", "cpp_price_feed", "AAPL", QOS_BEST_EFFORT);
+//!     self.subs.watch_restarts("rust_price_monitor");
+//! }
+//!
+//! handle_messages!(RustMonitor,
+//!     ..
+//!     SystemEvent => on_system_event,   // DynamicSubscriber, or
+//! );                                    // self.subs.on_system_event(me, ev)
+//! ```
+//!
+//! A Rust publisher's restart is its Restarted event on `$sys/lifecycle`;
+//! a C++ actor restarted by the C++ side is reported the same way with
+//! `interop_report_cpp_restart()`. When the whole C++ side went away and
+//! came back (HeartbeatResumed on `$sys/heartbeat`) every C++ publisher is
+//! subscribed to again. Publishers should treat a repeated Subscribe as a
+//! no-op, as the broker does.
+
+use std::os::raw::{c_char, c_int, c_void};
+
+use actors::ActorContext;
+
+use crate::broker;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{
+    CSubscribeRequest, CUnsubscribeRequest, SubscribeRequest, SystemEvent, UnsubscribeRequest, MSG_SUBSCRIBEREQUEST,
+    MSG_UNSUBSCRIBEREQUEST,
+};
+use crate::interop_string::{InteropString, TruncationPolicy};
+use crate::names;
+use crate::qos::QOS_BEST_EFFORT;
+use crate::rust_actor_bridge::rust_actor_send;
+use crate::sys_topics::{SysEventKind, SYS_PUBLISHER, TOPIC_HEARTBEAT, TOPIC_LIFECYCLE};
+
+/// One subscription held by a subscriber
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subscribed {
+    pub publisher: String,
+    pub topic: String,
+    pub qos: i32,
+}
+
+/// Subscriptions held by one subscriber, in the order taken
+#[derive(Clone, Debug, Default)]
+pub struct Subscriptions {
+    held: Vec<Subscribed>,
+}
+
+impl Subscriptions {
+    pub fn new() -> Self {
+        Subscriptions::default()
+    }
+
+    /// Subscribe `me` to `topic` of `publisher`; a repeat updates the qos.
+    /// Returns false if the publisher is not found or the topic is too long.
+    pub fn subscribe(&mut self, me: &str, publisher: &str, topic: &str, qos: i32) -> bool {
+        if !broker::subscribe(me, publisher, topic, qos) {
+            return false;
+        }
+        match self.held.iter_mut().find(|h| h.publisher == publisher && h.topic == topic) {
+            Some(h) => h.qos = qos,
+            None => self.held.push(Subscribed { publisher: publisher.to_string(), topic: topic.to_string(), qos }),
+        }
+        true
+    }
+
+    /// Returns false if `me` did not hold the subscription
+    pub fn unsubscribe(&mut self, me: &str, publisher: &str, topic: &str) -> bool {
+        let before = self.held.len();
+        self.held.retain(|h| h.publisher != publisher || h.topic != topic);
+        if self.held.len() == before {
+            return false;
+        }
+        broker::unsubscribe(me, publisher, topic);
+        true
+    }
+
+    /// Subscribe `me` to every one of `topics` of `publisher` in one
+    /// message; repeats update the qos. Returns false, subscribing to none,
+    /// if the publisher is not found or a topic is too long.
+    pub fn subscribe_many<S: AsRef<str>>(&mut self, me: &str, publisher: &str, topics: &[S], qos: i32) -> bool {
+        if !broker::subscribe_many(me, publisher, topics, qos) {
+            return false;
+        }
+        for topic in topics {
+            let topic = topic.as_ref();
+            match self.held.iter_mut().find(|h| h.publisher == publisher && h.topic == topic) {
+                Some(h) => h.qos = qos,
+                None => self.held.push(Subscribed { publisher: publisher.to_string(), topic: topic.to_string(), qos }),
+            }
+        }
+        true
+    }
+
+    /// Unsubscribe `me` from those of `topics` it holds, in one message.
+    /// Returns how many it held.
+    pub fn unsubscribe_many<S: AsRef<str>>(&mut self, me: &str, publisher: &str, topics: &[S]) -> usize {
+        let held: Vec<&str> = topics.iter().map(AsRef::as_ref).filter(|t| self.contains(publisher, t)).collect();
+        if held.is_empty() {
+            return 0;
+        }
+        self.held.retain(|h| h.publisher != publisher || !held.contains(&h.topic.as_str()));
+        broker::unsubscribe_many(me, publisher, &held);
+        held.len()
+    }
+
+    /// Subscribe `me` again to everything held, e.g. after a restart
+    pub fn resubscribe(&self, me: &str) {
+        for h in &self.held {
+            broker::subscribe(me, &h.publisher, &h.topic, h.qos);
+        }
+    }
+
+    /// Have `me` receive the `$sys` events that tell of publisher restarts,
+    /// for `on_system_event`. Call once, e.g. on Start.
+    pub fn watch_restarts(&self, me: &str) {
+        broker::subscribe(me, SYS_PUBLISHER, TOPIC_LIFECYCLE, QOS_BEST_EFFORT);
+        broker::subscribe(me, SYS_PUBLISHER, TOPIC_HEARTBEAT, QOS_BEST_EFFORT);
+    }
+
+    /// Subscribe `me` again to what it holds of publishers that `event`
+    /// says restarted: the subject of Restarted, every C++ publisher on
+    /// HeartbeatResumed. Returns the number of subscriptions renewed.
+    pub fn on_system_event(&self, me: &str, event: &SystemEvent) -> usize {
+        let subject = event.subject.as_str();
+        let restarted = |h: &&Subscribed| match event.kind {
+            k if k == SysEventKind::Restarted as i32 => h.publisher == subject,
+            k if k == SysEventKind::HeartbeatResumed as i32 => !names::is_rust(&h.publisher),
+            _ => false,
+        };
+        let renewed = self
+            .held
+            .iter()
+            .filter(restarted)
+            .filter(|h| broker::subscribe(me, &h.publisher, &h.topic, h.qos))
+            .count();
+        if renewed > 0 {
+            eprintln!("[Subscriber] {}: subscribed again to {} topics after {} restarted", me, renewed, subject);
+        }
+        renewed
+    }
+
+    pub fn contains(&self, publisher: &str, topic: &str) -> bool {
+        self.held.iter().any(|h| h.publisher == publisher && h.topic == topic)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Subscribed> {
+        self.held.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+}
+
+/// Mixin for Rust subscribers whose subscriptions the host changes
+pub trait DynamicSubscriber {
+    /// Registered name of this actor
+    fn subscriber_name(&self) -> &str;
+
+    /// Publisher for requests that do not name one
+    fn default_publisher(&self) -> &str;
+
+    fn subscriptions(&mut self) -> &mut Subscriptions;
+
+    /// Called after a request changed the subscriptions
+    fn on_subscriptions_changed(&mut self, _publisher: &str, _topic: &str, _subscribed: bool) {}
+
+    fn on_subscribe_request(&mut self, msg: &SubscribeRequest, _ctx: &mut ActorContext) {
+        let me = self.subscriber_name().to_string();
+        let publisher = publisher_or(msg.publisher.as_str(), self.default_publisher());
+        let topic = msg.topic.as_str();
+        if self.subscriptions().subscribe(&me, &publisher, topic, msg.qos) {
+            self.on_subscriptions_changed(&publisher, topic, true);
+        } else {
+            eprintln!("[Subscriber] {}: cannot subscribe to {} of {}", me, topic, publisher);
+        }
+    }
+
+    /// Subscribe again after a publisher restart (see `watch_restarts`)
+    fn on_system_event(&mut self, msg: &SystemEvent, _ctx: &mut ActorContext) {
+        let me = self.subscriber_name().to_string();
+        self.subscriptions().on_system_event(&me, msg);
+    }
+
+    fn on_unsubscribe_request(&mut self, msg: &UnsubscribeRequest, _ctx: &mut ActorContext) {
+        let me = self.subscriber_name().to_string();
+        let publisher = publisher_or(msg.publisher.as_str(), self.default_publisher());
+        let topic = msg.topic.as_str();
+        if self.subscriptions().unsubscribe(&me, &publisher, topic) {
+            self.on_subscriptions_changed(&publisher, topic, false);
+        }
+    }
+}
+
+fn publisher_or(publisher: &str, default: &str) -> String {
+    if publisher.is_empty() { default } else { publisher }.to_string()
+}
+
+fn c_topic(topic: *const c_char) -> Option<InteropString<32>> {
+    let topic = names::c_str(topic)?;
+    InteropString::with_policy(topic, TruncationPolicy::Reject).ok()
+}
+
+/// Ask Rust subscriber `actor_name` to subscribe to `topic` of its default
+/// publisher. Returns rust_actor_send()'s result, or InvalidArgument on a
+/// bad topic.
+#[export_name = c_symbol!("rust_subscriber_subscribe")]
+pub extern "C" fn rust_subscriber_subscribe(actor_name: *const c_char, topic: *const c_char) -> c_int {
+    let request = match c_topic(topic) {
+        Some(topic) => SubscribeRequest { publisher: "".into(), topic, qos: QOS_BEST_EFFORT },
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let c_msg: CSubscribeRequest = request.to_c_struct();
+    rust_actor_send(actor_name, std::ptr::null(), MSG_SUBSCRIBEREQUEST, &c_msg as *const CSubscribeRequest as *const c_void)
+}
+
+/// Ask Rust subscriber `actor_name` to drop its subscription to `topic` of
+/// its default publisher. Returns rust_actor_send()'s result, or
+/// InvalidArgument on a bad topic.
+#[export_name = c_symbol!("rust_subscriber_unsubscribe")]
+pub extern "C" fn rust_subscriber_unsubscribe(actor_name: *const c_char, topic: *const c_char) -> c_int {
+    let request = match c_topic(topic) {
+        Some(topic) => UnsubscribeRequest { publisher: "".into(), topic },
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let c_msg: CUnsubscribeRequest = request.to_c_struct();
+    rust_actor_send(actor_name, std::ptr::null(), MSG_UNSUBSCRIBEREQUEST, &c_msg as *const CUnsubscribeRequest as *const c_void)
+}
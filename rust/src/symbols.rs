@@ -0,0 +1,95 @@
+//! Symbol-interned market data for hot paths
+//!
+//! A subscriber that or's `SUB_COMPACT` into `Subscribe.qos` asks for
+//! `CompactMarketUpdate`, which carries a u32 `symbol_id` instead of the
+//! symbol string. The publisher replies to the Subscribe with a
+//! `SymbolDirectory` entry mapping the topic's symbol to its id, then sends
+//! compact updates. Ids are assigned by each publisher and only mean
+//! something between that publisher and its subscribers.
+//!
+//! - Publisher side: `SymbolInterner` assigns ids and builds the directory
+//!   entries and compact updates.
+//! - Subscriber side: `SymbolTable` learns directory entries and expands
+//!   compact updates back into `MarketUpdate` for code that wants strings.
+//!
+//! Publishers that do not know the flag ignore it and keep sending
+//! `MarketUpdate`, so subscribers should handle both.
+
+use std::collections::HashMap;
+
+use crate::interop_messages::{CompactMarketUpdate, MarketUpdate, SymbolDirectory};
+use crate::qos::SUB_COMPACT;
+
+/// True if a Subscribe `qos` field asks for compact updates
+pub fn wants_compact(qos: i32) -> bool {
+    qos & SUB_COMPACT != 0
+}
+
+/// Publisher side: assigns ids to symbols, starting at 1
+#[derive(Default)]
+pub struct SymbolInterner {
+    ids: HashMap<String, u32>,
+}
+
+impl SymbolInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Id for `symbol`, assigning the next one on first use
+    pub fn intern(&mut self, symbol: &str) -> u32 {
+        let next = self.ids.len() as u32 + 1;
+        *self.ids.entry(symbol.to_string()).or_insert(next)
+    }
+
+    /// Directory entry to send a compact subscriber for `symbol`
+    pub fn directory_entry(&mut self, symbol: &str) -> SymbolDirectory {
+        SymbolDirectory {
+            symbol: symbol.into(),
+            symbol_id: self.intern(symbol),
+        }
+    }
+
+    /// Compact form of `update`
+    pub fn compact(&mut self, update: &MarketUpdate) -> CompactMarketUpdate {
+        CompactMarketUpdate {
+            price: update.price,
+            timestamp: update.timestamp,
+            seq: update.seq,
+            symbol_id: self.intern(update.symbol.as_str()),
+            volume: update.volume,
+        }
+    }
+}
+
+/// Subscriber side: ids learned from one publisher's SymbolDirectory entries
+#[derive(Default)]
+pub struct SymbolTable {
+    symbols: HashMap<u32, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a directory entry; a repeated id replaces the old symbol
+    pub fn learn(&mut self, entry: &SymbolDirectory) {
+        self.symbols.insert(entry.symbol_id, entry.symbol.as_str().to_string());
+    }
+
+    pub fn symbol(&self, symbol_id: u32) -> Option<&str> {
+        self.symbols.get(&symbol_id).map(String::as_str)
+    }
+
+    /// MarketUpdate for `update`, or None if its id has not been announced
+    pub fn expand(&self, update: &CompactMarketUpdate) -> Option<MarketUpdate> {
+        Some(MarketUpdate {
+            symbol: self.symbol(update.symbol_id)?.into(),
+            price: update.price,
+            timestamp: update.timestamp,
+            volume: update.volume,
+            seq: update.seq,
+        })
+    }
+}
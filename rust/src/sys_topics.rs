@@ -0,0 +1,246 @@
+//! Runtime events on well-known `$sys/` topics
+//!
+//! Monitoring actors in either language subscribe to runtime events the
+//! way they subscribe to anything else: a Subscribe sent to the reserved
+//! publisher `$sys`. Events arrive as `SystemEvent` messages:
+//!
+//! ```ignore
+//! // Rust
+//! broker::subscribe("rust_monitor", SYS_PUBLISHER, TOPIC_LIFECYCLE, QOS_BEST_EFFORT);
+//! ```
+//!
+//! ```cpp
+//! // C++: "$sys" resolves like a Rust actor
+//! auto* sub = new msg::Subscribe();
+//! std::strncpy(sub->topic.data(), "$sys/lifecycle", sub->topic.size() - 1);
+//! manager->get_ref("$sys").send(sub, this);
+//! ```
+//!
+//! | Topic | Kinds | `subject` | `value` |
+//! |-------|-------|-----------|---------|
+//! | `$sys/lifecycle` | Started, Stopped, Panicked, Restarted | Rust actor (Restarted: or C++ actor) | 0 |
+//! | `$sys/dead_letters` | DeadLetter, InvalidMessage | target | 0, or the invalid message's ID |
+//! | `$sys/heartbeat` | HeartbeatMissed, HeartbeatResumed | `"cpp"` | ms since the last heartbeat, 0 |
+//! | `$sys/queue_depth` | QueueHigh | Rust actor | its queue depth |
+//! | `$sys/circuit` | CircuitOpened, CircuitClosed | C++ target | failed sends that opened it |
+//! | `$sys/shutdown` | ShutdownRequested, ShutdownReady | initiator / `"rust"`, `"cpp"` | 0 |
+//! | `$sys/deadlines` | DeadlineMissed | Rust actor | handler time in ns |
+//! | `$sys/cpp_runtime` | CppRuntimeDetached, CppRuntimeAttached | C++ runtime (see cpp_runtime) | 0 |
+//!
+//! - HeartbeatMissed is published once when the C++ heartbeat has been
+//!   silent for `health::PEER_TIMEOUT_MS`, and again only after heartbeats
+//!   resume and stop again; HeartbeatResumed when they resume, e.g. after
+//!   the C++ side restarted. It is watched only while `$sys/heartbeat` has
+//!   subscribers.
+//! - C++ reports a restarted C++ actor with `interop_report_cpp_restart()`.
+//! - QueueHigh is published when an actor's bridge queue depth (see
+//!   queue_depth) rises to `queue_high_watermark()`.
+//! - DeadlineMissed is published for every handler that overran its
+//!   deadline (see deadlines).
+//! - CppRuntimeDetached is published when a C++ runtime reports it is
+//!   going away, CppRuntimeAttached when a runtime reported gone is back.
+//!   Actors holding refs to its actors fail over on the first.
+//! - Events caused by publishing an event (e.g. a dead letter for a gone
+//!   subscriber) are not published again.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use actors::{ActorRef, CppActorRef, Message};
+
+use crate::broker;
+use crate::clock;
+use crate::health;
+use crate::interop_messages::{Subscribe, SubscribeMany, SystemEvent, Timestamp};
+use crate::lifecycle::LifecycleEvent;
+
+/// Publisher name to send Subscribe / Unsubscribe to
+pub const SYS_PUBLISHER: &str = "$sys";
+
+pub const TOPIC_LIFECYCLE: &str = "$sys/lifecycle";
+pub const TOPIC_DEAD_LETTERS: &str = "$sys/dead_letters";
+pub const TOPIC_HEARTBEAT: &str = "$sys/heartbeat";
+pub const TOPIC_QUEUE_DEPTH: &str = "$sys/queue_depth";
+pub const TOPIC_CIRCUIT: &str = "$sys/circuit";
+pub const TOPIC_SHUTDOWN: &str = "$sys/shutdown";
+pub const TOPIC_DEADLINES: &str = "$sys/deadlines";
+pub const TOPIC_CPP_RUNTIME: &str = "$sys/cpp_runtime";
+
+/// Default queue depth that publishes QueueHigh
+pub const DEFAULT_QUEUE_HIGH_WATERMARK: usize = 1000;
+
+/// `SystemEvent.kind`; mirrors INTEROP_SYS_* in interop/sys_topics.h
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum SysEventKind {
+    Started = 0,
+    Stopped = 1,
+    Panicked = 2,
+    Restarted = 3,
+    DeadLetter = 10,
+    InvalidMessage = 11,
+    HeartbeatMissed = 20,
+    HeartbeatResumed = 21,
+    QueueHigh = 30,
+    CircuitOpened = 40,
+    CircuitClosed = 41,
+    ShutdownRequested = 50,
+    ShutdownReady = 51,
+    DeadlineMissed = 60,
+    CppRuntimeDetached = 70,
+    CppRuntimeAttached = 71,
+}
+
+impl From<LifecycleEvent> for SysEventKind {
+    fn from(event: LifecycleEvent) -> Self {
+        match event {
+            LifecycleEvent::Started => SysEventKind::Started,
+            LifecycleEvent::Stopped => SysEventKind::Stopped,
+            LifecycleEvent::Panicked => SysEventKind::Panicked,
+            LifecycleEvent::Restarted => SysEventKind::Restarted,
+        }
+    }
+}
+
+static QUEUE_HIGH_WATERMARK: AtomicUsize = AtomicUsize::new(DEFAULT_QUEUE_HIGH_WATERMARK);
+static WATCHING_HEARTBEAT: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Set while this thread publishes an event
+    static PUBLISHING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn publish(topic: &str, kind: SysEventKind, subject: &str, value: i64) {
+    if PUBLISHING.with(|p| p.replace(true)) {
+        return;
+    }
+    let event = SystemEvent { kind: kind as i32, subject: subject.into(), value, timestamp: Timestamp::now() };
+    broker::publish_with(SYS_PUBLISHER, topic, |_| Some(Box::new(event.clone())));
+    PUBLISHING.with(|p| p.set(false));
+}
+
+/// Publish a lifecycle event of Rust actor `name`. Called by lifecycle.
+pub fn lifecycle(name: &str, event: LifecycleEvent) {
+    publish(TOPIC_LIFECYCLE, event.into(), name, 0);
+}
+
+/// Publish a send to missing actor `target`. Called by health.
+pub fn dead_letter(target: &str) {
+    publish(TOPIC_DEAD_LETTERS, SysEventKind::DeadLetter, target, 0);
+}
+
+/// Publish a message to `target` dropped by validation. Called by health.
+pub fn invalid_message(target: &str, msg_id: i32) {
+    publish(TOPIC_DEAD_LETTERS, SysEventKind::InvalidMessage, target, msg_id as i64);
+}
+
+pub fn queue_high_watermark() -> usize {
+    QUEUE_HIGH_WATERMARK.load(Ordering::Relaxed)
+}
+
+/// Set the queue depth that publishes QueueHigh; 0 turns it off
+pub fn set_queue_high_watermark(depth: usize) {
+    QUEUE_HIGH_WATERMARK.store(depth, Ordering::Relaxed);
+}
+
+/// `name`'s queue depth rose to `depth`. Called by queue_depth.
+pub fn queue_depth_rose(name: &str, depth: usize) {
+    let mark = queue_high_watermark();
+    if mark > 0 && depth == mark {
+        publish(TOPIC_QUEUE_DEPTH, SysEventKind::QueueHigh, name, depth as i64);
+    }
+}
+
+/// Publish a circuit to C++ actor `target` opening after `failures`
+/// failed sends, or closing. Called by circuit.
+pub fn circuit(target: &str, opened: bool, failures: i64) {
+    let kind = if opened { SysEventKind::CircuitOpened } else { SysEventKind::CircuitClosed };
+    publish(TOPIC_CIRCUIT, kind, target, failures);
+}
+
+/// Publish a shutdown request by `subject`, or a side (`"rust"` or
+/// `"cpp"`) being ready. Called by shutdown.
+pub fn shutdown(subject: &str, ready: bool) {
+    let kind = if ready { SysEventKind::ShutdownReady } else { SysEventKind::ShutdownRequested };
+    publish(TOPIC_SHUTDOWN, kind, subject, 0);
+}
+
+/// Publish a handler of `name` that overran its deadline, having taken
+/// `elapsed`. Called by deadlines.
+pub fn deadline_missed(name: &str, elapsed: Duration) {
+    let ns = elapsed.as_nanos().min(i64::MAX as u128) as i64;
+    publish(TOPIC_DEADLINES, SysEventKind::DeadlineMissed, name, ns);
+}
+
+/// Publish C++ runtime `name` going away, or coming back. Called by
+/// cpp_runtime.
+pub fn cpp_runtime(name: &str, attached: bool) {
+    let kind = if attached { SysEventKind::CppRuntimeAttached } else { SysEventKind::CppRuntimeDetached };
+    publish(TOPIC_CPP_RUNTIME, kind, name, 0);
+}
+
+/// Start the heartbeat watcher once `$sys/heartbeat` has a subscriber
+fn watch_heartbeat() {
+    if WATCHING_HEARTBEAT.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    thread::spawn(|| {
+        let watched = || !broker::subscribers(SYS_PUBLISHER, TOPIC_HEARTBEAT).is_empty();
+        let mut reported = false;
+        loop {
+            while watched() {
+                match health::heartbeat_age_ms() {
+                    Some(ago) if ago > health::PEER_TIMEOUT_MS => {
+                        if !reported {
+                            publish(TOPIC_HEARTBEAT, SysEventKind::HeartbeatMissed, "cpp", ago as i64);
+                            reported = true;
+                        }
+                    }
+                    _ => {
+                        if reported {
+                            publish(TOPIC_HEARTBEAT, SysEventKind::HeartbeatResumed, "cpp", 0);
+                            reported = false;
+                        }
+                    }
+                }
+                clock::sleep(Duration::from_millis(health::PEER_TIMEOUT_MS / 5));
+            }
+            WATCHING_HEARTBEAT.store(false, Ordering::Release);
+            // A subscriber that came in meanwhile found the flag still set
+            if !watched() || WATCHING_HEARTBEAT.swap(true, Ordering::AcqRel) {
+                return;
+            }
+        }
+    });
+}
+
+/// Subscribe / Unsubscribe sent to `$sys`; the subscription itself is
+/// recorded by the broker, where the bridge or `broker::subscribe` sees it
+fn sys_send_fn(_target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    broker::observe(sender, SYS_PUBLISHER, msg);
+    if let Some(sub) = msg.as_any().downcast_ref::<Subscribe>() {
+        if sub.topic.as_str() == TOPIC_HEARTBEAT {
+            watch_heartbeat();
+        }
+    } else if let Some(many) = msg.as_any().downcast_ref::<SubscribeMany>() {
+        if many.topics().any(|t| t == TOPIC_HEARTBEAT) {
+            watch_heartbeat();
+        }
+    }
+    0
+}
+
+/// ActorRef for `$sys`, None for any other name. Lookups from Rust
+/// (`get_actor_ref`) and sends from C++ (`rust_actor_send`) use this.
+pub fn sys_ref(name: &str, sender: &str) -> Option<ActorRef> {
+    (name == SYS_PUBLISHER).then(|| ActorRef::Cpp(CppActorRef::new(SYS_PUBLISHER, sender, sys_send_fn)))
+}
+
+/// Set the queue depth that publishes QueueHigh on `$sys/queue_depth`; 0
+/// turns it off
+#[export_name = c_symbol!("interop_set_queue_high_watermark")]
+pub extern "C" fn interop_set_queue_high_watermark(depth: u32) {
+    set_queue_high_watermark(depth as usize);
+}
@@ -0,0 +1,284 @@
+//! Setting up the interop system from Rust
+//!
+//! The FFI functions assume a C++ host drives setup. A Rust binary that
+//! loads the C++ side itself does the same from Rust:
+//!
+//! ```ignore
+//! let system = InteropSystem::builder()
+//!     .with_manager(Manager::new())
+//!     .with_actor("rust_strategy", |handle| Box::new(Strategy::new(handle)))
+//!     .with_actor_type("RustPongActor", None)
+//!     .with_cpp_transport(CppTransport::library("pricing", "./libpricing.so", "pricing."))
+//!     .build()?;
+//! system.start();
+//! ...
+//! system.shutdown();   // also on drop
+//! ```
+//!
+//! `build()` does what a C++ host does before `rust_manager_init()`: it
+//! installs the Manager (`create_rust_manager`), registers the actors,
+//! points the bridge at the Manager (`rust_actor_init`) and connects each
+//! C++ transport. `CppTransport::Linked` is checked as
+//! `init_cpp_actor_lookup()` checks it; attached runtimes are taken as
+//! they are, loaded libraries are checked by hot_reload. With
+//! `with_control_socket()` it then opens the socket interop-ctl connects to
+//! (see control). When `build()`
+//! fails, what it had set up is shut down again.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use actors::{Actor, Manager, ManagerHandle, ThreadConfig};
+
+use crate::capabilities::{self, CapabilityError};
+use crate::control;
+use crate::cpp_runtime::{self, AttachError, CppRuntimeVTable};
+#[cfg(feature = "hot_reload")]
+use crate::hot_reload::{self, ReloadError};
+use crate::registry;
+use crate::rust_actor_bridge;
+use crate::rust_manager_ffi;
+
+/// How Rust reaches C++ actors
+pub enum CppTransport {
+    /// The C++ bridge linked into this binary (`cpp_actor_send` and
+    /// friends); its `cpp_actor_init()` must have run
+    Linked,
+    /// A C++ runtime's bridge functions, e.g. from dlsym (see cpp_runtime)
+    Runtime { name: String, prefix: String, vtable: CppRuntimeVTable },
+    /// A C++ actor library loaded and started by hot_reload
+    #[cfg(feature = "hot_reload")]
+    Library { name: String, path: PathBuf, prefix: String },
+}
+
+impl CppTransport {
+    pub fn runtime(name: &str, prefix: &str, vtable: CppRuntimeVTable) -> Self {
+        CppTransport::Runtime { name: name.to_string(), prefix: prefix.to_string(), vtable }
+    }
+
+    #[cfg(feature = "hot_reload")]
+    pub fn library(name: &str, path: impl Into<PathBuf>, prefix: &str) -> Self {
+        CppTransport::Library { name: name.to_string(), path: path.into(), prefix: prefix.to_string() }
+    }
+}
+
+#[derive(Debug)]
+pub enum SystemError {
+    UnknownActorType(String),
+    /// Another Rust or C++ actor has the name (see names)
+    NameTaken(String),
+    Capabilities(CapabilityError),
+    Attach(AttachError),
+    #[cfg(feature = "hot_reload")]
+    Load(ReloadError),
+    /// The control socket could not be opened
+    Control(io::Error),
+}
+
+impl fmt::Display for SystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SystemError::UnknownActorType(t) => {
+                write!(f, "unknown actor type {}; known: {:?}", t, registry::type_names())
+            }
+            SystemError::NameTaken(name) => write!(f, "actor name {} is taken", name),
+            SystemError::Capabilities(e) => write!(f, "linked C++ side: {}", e),
+            SystemError::Attach(e) => write!(f, "{}", e),
+            #[cfg(feature = "hot_reload")]
+            SystemError::Load(e) => write!(f, "{}", e),
+            SystemError::Control(e) => write!(f, "control socket: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SystemError {}
+
+type Factory = Box<dyn FnMut(ManagerHandle) -> Box<dyn Actor> + Send>;
+
+enum ActorSpec {
+    Factory { name: String, factory: Factory, thread_config: ThreadConfig },
+    Type { type_name: String, instance: Option<String>, group: Option<String> },
+}
+
+/// Collects the parts of an `InteropSystem`; see the module docs
+#[derive(Default)]
+pub struct InteropSystemBuilder {
+    manager: Option<Manager>,
+    actors: Vec<ActorSpec>,
+    transports: Vec<CppTransport>,
+    control_socket: Option<PathBuf>,
+}
+
+impl InteropSystemBuilder {
+    /// Use `mgr` instead of a new Manager, e.g. with actors already
+    /// managed. Those are not rebuilt by `rust_manager_restart()`.
+    pub fn with_manager(mut self, mgr: Manager) -> Self {
+        self.manager = Some(mgr);
+        self
+    }
+
+    /// Register `name`, built by `factory` (again on every restart), on a
+    /// thread of its own
+    pub fn with_actor<F>(self, name: &str, factory: F) -> Self
+    where
+        F: FnMut(ManagerHandle) -> Box<dyn Actor> + Send + 'static,
+    {
+        self.with_actor_config(name, factory, ThreadConfig::default())
+    }
+
+    /// with_actor() with a ThreadConfig, e.g. to pin it to a core
+    pub fn with_actor_config<F>(mut self, name: &str, factory: F, thread_config: ThreadConfig) -> Self
+    where
+        F: FnMut(ManagerHandle) -> Box<dyn Actor> + Send + 'static,
+    {
+        self.actors.push(ActorSpec::Factory { name: name.to_string(), factory: Box::new(factory), thread_config });
+        self
+    }
+
+    /// Register an #[interop_actor] type, as `register_actor_by_type_name`
+    /// does. `instance` None uses the type's default name.
+    pub fn with_actor_type(mut self, type_name: &str, instance: Option<&str>) -> Self {
+        self.actors.push(ActorSpec::Type {
+            type_name: type_name.to_string(),
+            instance: instance.map(str::to_string),
+            group: None,
+        });
+        self
+    }
+
+    /// with_actor_type() on the shared thread of `group` (see shared_thread)
+    pub fn with_shared_actor_type(mut self, type_name: &str, instance: Option<&str>, group: &str) -> Self {
+        self.actors.push(ActorSpec::Type {
+            type_name: type_name.to_string(),
+            instance: instance.map(str::to_string),
+            group: Some(group.to_string()),
+        });
+        self
+    }
+
+    /// Reach C++ actors through `transport`. Several may be given; lookups
+    /// follow cpp_runtime's routing.
+    pub fn with_cpp_transport(mut self, transport: CppTransport) -> Self {
+        self.transports.push(transport);
+        self
+    }
+
+    /// Open the control socket for interop-ctl at `path` (unix only)
+    pub fn with_control_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.control_socket = Some(path.into());
+        self
+    }
+
+    /// Set up the Manager, actors, bridge and transports. The actors are
+    /// not started until `InteropSystem::start()`.
+    pub fn build(self) -> Result<InteropSystem, SystemError> {
+        rust_manager_ffi::install_manager(self.manager.unwrap_or_default());
+        let mut system = InteropSystem { runtimes: Vec::new(), libraries: Vec::new(), control: false, running: true };
+
+        for spec in self.actors {
+            match spec {
+                ActorSpec::Factory { name, factory, thread_config } => {
+                    if !rust_manager_ffi::register_with(&name, factory, thread_config) {
+                        return Err(SystemError::NameTaken(name));
+                    }
+                }
+                ActorSpec::Type { type_name, instance, group } => {
+                    let actor_type = match registry::find(&type_name) {
+                        Some(t) => t,
+                        None => return Err(SystemError::UnknownActorType(type_name)),
+                    };
+                    let name = instance.as_deref().unwrap_or(actor_type.default_name).to_string();
+                    let registered = match group {
+                        Some(group) => rust_manager_ffi::register_shared(&type_name, Some(&name), &group),
+                        None => rust_manager_ffi::register_by_type_name(&type_name, Some(&name)),
+                    };
+                    if registered.is_null() {
+                        return Err(SystemError::NameTaken(name));
+                    }
+                }
+            }
+        }
+        rust_actor_bridge::rust_actor_init(rust_manager_ffi::get_rust_manager());
+
+        let mut checked = true;
+        for transport in self.transports {
+            match transport {
+                CppTransport::Linked => checked = false,
+                CppTransport::Runtime { name, prefix, vtable } => {
+                    cpp_runtime::attach(&name, &prefix, vtable).map_err(SystemError::Attach)?;
+                    system.runtimes.push(name);
+                }
+                #[cfg(feature = "hot_reload")]
+                CppTransport::Library { name, path, prefix } => {
+                    hot_reload::load(&name, &path, &prefix).map_err(SystemError::Load)?;
+                    system.libraries.push(name);
+                }
+            }
+        }
+        if !checked {
+            capabilities::verify().map_err(SystemError::Capabilities)?;
+        }
+        if !checked || !system.runtimes.is_empty() || !system.libraries.is_empty() {
+            rust_manager_ffi::install_cpp_lookup();
+        }
+        if let Some(path) = self.control_socket {
+            control::listen(path).map_err(SystemError::Control)?;
+            system.control = true;
+        }
+        Ok(system)
+    }
+}
+
+/// The interop system set up by `InteropSystemBuilder`; shut down on drop
+pub struct InteropSystem {
+    runtimes: Vec<String>,
+    // Only filled with the hot_reload feature
+    libraries: Vec<String>,
+    // The control socket is open
+    control: bool,
+    running: bool,
+}
+
+impl InteropSystem {
+    pub fn builder() -> InteropSystemBuilder {
+        InteropSystemBuilder::default()
+    }
+
+    /// Start the Rust actors, as `rust_manager_init()` does
+    pub fn start(&self) {
+        rust_manager_ffi::rust_manager_init();
+    }
+
+    /// End the Rust actors, disconnect the bridge and detach or unload the
+    /// C++ runtimes this system attached
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if !std::mem::take(&mut self.running) {
+            return;
+        }
+        if std::mem::take(&mut self.control) {
+            control::close();
+        }
+        rust_manager_ffi::rust_manager_end();
+        rust_actor_bridge::rust_actor_shutdown();
+        for name in self.runtimes.drain(..) {
+            cpp_runtime::detach(&name);
+        }
+        #[cfg(feature = "hot_reload")]
+        for name in self.libraries.drain(..) {
+            if let Err(e) = hot_reload::unload(&name) {
+                eprintln!("[Interop System] {}", e);
+            }
+        }
+    }
+}
+
+impl Drop for InteropSystem {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
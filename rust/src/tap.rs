@@ -0,0 +1,195 @@
+//! Wire taps: observe interop messages between actors
+//!
+//! `tap(sender_pattern, target_pattern, f)` calls `f` with a view of every
+//! message that crosses the bridge from a matching sender to a matching
+//! target. Patterns are actor names where `*` matches any run of
+//! characters, so `tap("*", "rust_publisher", ..)` shows everything sent
+//! to the publisher. Neither actor can tell it is being observed.
+//!
+//! C code registers with `interop_tap()`; the callback receives the C
+//! struct, valid for the duration of the call (NULL for messages with
+//! `interop_bytes` fields).
+//!
+//! Covered: C++ -> Rust (`rust_actor_send`, `rust_actor_fast_send`,
+//! `rust_actor_post`) and Rust -> C++ (`ActorRef::Cpp`, `CppActorIF`).
+//! Rust -> Rust sends do not cross the bridge and are not seen. Observers
+//! run on the sending thread, so keep them short. Sampled capture (see capture) sees the same messages.
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use actors::Message;
+
+use crate::capture;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_to_c_bytes, message_to_debug_json};
+use crate::names::c_str;
+use crate::wire::aligned_copy;
+
+/// Rust observer: (sender, target, message)
+pub type TapFn = Arc<dyn Fn(&str, &str, &dyn Message) + Send + Sync>;
+
+/// C observer: (sender, target, msg_type, C struct or NULL, user_data)
+pub type CTapCallback = extern "C" fn(
+    sender_name: *const c_char,
+    target_name: *const c_char,
+    msg_type: c_int,
+    msg_data: *const c_void,
+    user_data: *mut c_void,
+);
+
+#[derive(Clone)]
+enum Observer {
+    Rust(TapFn),
+    // user_data as usize so the table is Send
+    C(CTapCallback, usize),
+}
+
+struct Tap {
+    id: u64,
+    sender: String,
+    target: String,
+    observer: Observer,
+}
+
+// Fast path: no lock while nothing is tapped
+static ANY_TAPS: AtomicBool = AtomicBool::new(false);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static TAPS: Mutex<Vec<Tap>> = Mutex::new(Vec::new());
+
+fn add(sender_pattern: &str, target_pattern: &str, observer: Observer) -> u64 {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let mut taps = TAPS.lock().unwrap();
+    taps.push(Tap {
+        id,
+        sender: sender_pattern.to_string(),
+        target: target_pattern.to_string(),
+        observer,
+    });
+    ANY_TAPS.store(true, Ordering::Relaxed);
+    id
+}
+
+/// Observe messages from `sender_pattern` to `target_pattern`.
+/// Returns an id for `untap()`.
+pub fn tap<F>(sender_pattern: &str, target_pattern: &str, f: F) -> u64
+where
+    F: Fn(&str, &str, &dyn Message) + Send + Sync + 'static,
+{
+    add(sender_pattern, target_pattern, Observer::Rust(Arc::new(f)))
+}
+
+/// Remove a tap. Returns false if `id` was not registered.
+pub fn untap(id: u64) -> bool {
+    let mut taps = TAPS.lock().unwrap();
+    let before = taps.len();
+    taps.retain(|t| t.id != id);
+    ANY_TAPS.store(!taps.is_empty(), Ordering::Relaxed);
+    taps.len() != before
+}
+
+/// Print matching messages to stderr as JSON (`to_json()`).
+/// Returns an id for `untap()`.
+pub fn print_tap(sender_pattern: &str, target_pattern: &str) -> u64 {
+    tap(sender_pattern, target_pattern, |sender, target, msg| {
+        match message_to_debug_json(msg) {
+            Some(json) => eprintln!("[Tap] {} -> {}: {}", sender, target, json),
+            None => eprintln!("[Tap] {} -> {}: message {}", sender, target, msg.message_id()),
+        }
+    })
+}
+
+/// Glob match where `*` matches any run of characters
+fn matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+    let mut rest = match name.strip_prefix(first) {
+        Some(r) => r,
+        None => return false,
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(l) => l,
+        None => return rest.is_empty(),  // no '*'
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Show `msg` to every tap matching (sender, target). Called by the bridge.
+pub fn observe(sender: &str, target: &str, msg: &dyn Message) {
+    capture::sample(sender, target, msg);
+    if !ANY_TAPS.load(Ordering::Relaxed) {
+        return;
+    }
+    // Call observers without the lock so they may tap/untap
+    let observers: Vec<Observer> = TAPS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|t| matches(&t.sender, sender) && matches(&t.target, target))
+        .map(|t| t.observer.clone())
+        .collect();
+    if observers.is_empty() {
+        return;
+    }
+
+    let mut c_args: Option<(CString, CString, Option<Vec<u64>>)> = None;
+    for observer in observers {
+        match observer {
+            Observer::Rust(f) => f(sender, target, msg),
+            Observer::C(cb, user_data) => {
+                let (sender_c, target_c, data) = c_args.get_or_insert_with(|| {
+                    (
+                        CString::new(sender).unwrap_or_default(),
+                        CString::new(target).unwrap_or_default(),
+                        message_to_c_bytes(msg).map(|b| aligned_copy(&b)),
+                    )
+                });
+                let data_ptr = data.as_ref().map_or(std::ptr::null(), |d| d.as_ptr() as *const c_void);
+                cb(sender_c.as_ptr(), target_c.as_ptr(), msg.message_id(), data_ptr, user_data as *mut c_void);
+            }
+        }
+    }
+}
+
+/// Observe messages from `sender_pattern` to `target_pattern` in C.
+/// Returns a tap id for interop_untap(), or InvalidArgument on bad
+/// arguments.
+#[export_name = c_symbol!("interop_tap")]
+pub extern "C" fn interop_tap(
+    sender_pattern: *const c_char,
+    target_pattern: *const c_char,
+    cb: Option<CTapCallback>,
+    user_data: *mut c_void,
+) -> i64 {
+    match (cb, c_str(sender_pattern), c_str(target_pattern)) {
+        (Some(cb), Some(s), Some(t)) => add(s, t, Observer::C(cb, user_data as usize)) as i64,
+        _ => InteropErrorCode::InvalidArgument as i64,
+    }
+}
+
+/// Print messages from `sender_pattern` to `target_pattern` to stderr as
+/// JSON. Returns a tap id for interop_untap(), or InvalidArgument on bad
+/// arguments.
+#[export_name = c_symbol!("interop_tap_print")]
+pub extern "C" fn interop_tap_print(sender_pattern: *const c_char, target_pattern: *const c_char) -> i64 {
+    match (c_str(sender_pattern), c_str(target_pattern)) {
+        (Some(s), Some(t)) => print_tap(s, t) as i64,
+        _ => InteropErrorCode::InvalidArgument as i64,
+    }
+}
+
+/// Remove a tap. Returns 0, or NotFound if the id was not registered.
+#[export_name = c_symbol!("interop_untap")]
+pub extern "C" fn interop_untap(id: i64) -> c_int {
+    let rc = if id > 0 && untap(id as u64) { InteropErrorCode::Ok } else { InteropErrorCode::NotFound };
+    rc as c_int
+}
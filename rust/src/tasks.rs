@@ -0,0 +1,182 @@
+//! Periodic background jobs owned by the Rust Manager
+//!
+//! C++ code that refreshes a cache or flushes metrics every few seconds
+//! would otherwise start its own thread and call into the FFI from it,
+//! racing rust_manager_end() and restarts. A task runs on a thread the
+//! Rust side owns instead:
+//!
+//! ```c
+//! int flush_metrics(void* user_data) {
+//!     metrics_flush((Metrics*)user_data);
+//!     return 0;                        // nonzero: stop the task
+//! }
+//!
+//! int64_t id = rust_manager_spawn_task(flush_metrics, &metrics, 1000);
+//! ...
+//! rust_manager_cancel_task(id);        // no run is in progress after this
+//! ```
+//!
+//! A task first runs one interval after it is spawned, then every
+//! interval, a run that overran pushing the next one back rather than
+//! queueing catch-up runs. Runs are skipped while the Manager is not
+//! running (before rust_manager_init(), during rust_manager_restart()),
+//! and rust_manager_end() cancels every task. Intervals are measured on
+//! the installed clock (see clock), so tasks follow virtual time in a
+//! backtest. Rust code can use `spawn` with a closure.
+
+use std::collections::HashMap;
+use std::os::raw::{c_int, c_void};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::clock;
+use crate::health::{self, ManagerState};
+use crate::interop_errors::InteropErrorCode;
+
+/// Longest real-time wait between looks at a clock other than the system's
+const CLOCK_POLL: Duration = Duration::from_millis(1);
+
+/// Returns 0 to keep running, nonzero to stop
+pub type TaskFn = extern "C" fn(user_data: *mut c_void) -> c_int;
+
+pub type TaskId = i64;
+
+struct Task {
+    cancelled: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl Task {
+    /// Wait for `deadline` on the installed clock. False if cancelled first.
+    fn wait_until(&self, deadline: Instant) -> bool {
+        let mut cancelled = self.cancelled.lock().unwrap();
+        loop {
+            if *cancelled {
+                return false;
+            }
+            let left = deadline.saturating_duration_since(clock::now());
+            if left.is_zero() {
+                return true;
+            }
+            let wait = if clock::is_system_clock() { left } else { left.min(CLOCK_POLL) };
+            cancelled = self.wake.wait_timeout(cancelled, wait).unwrap().0;
+        }
+    }
+
+    fn cancel(&self) {
+        *self.cancelled.lock().unwrap() = true;
+        self.wake.notify_all();
+    }
+}
+
+struct Running {
+    task: Arc<Task>,
+    thread: JoinHandle<()>,
+}
+
+static NEXT_ID: AtomicI64 = AtomicI64::new(1);
+
+lazy_static::lazy_static! {
+    static ref TASKS: Mutex<HashMap<TaskId, Running>> = Mutex::new(HashMap::new());
+}
+
+// C++ owns user_data and keeps it valid until the task is cancelled
+struct UserData(*mut c_void);
+unsafe impl Send for UserData {}
+
+/// Call `job` every `interval` on a thread of its own until it returns
+/// false or the task is cancelled. None if the thread could not be started.
+pub fn spawn<F>(interval: Duration, mut job: F) -> Option<TaskId>
+where
+    F: FnMut() -> bool + Send + 'static,
+{
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let task = Arc::new(Task { cancelled: Mutex::new(false), wake: Condvar::new() });
+    let mut tasks = TASKS.lock().unwrap();
+    let runner = task.clone();
+    let thread = thread::Builder::new().name(format!("interop-task-{}", id)).spawn(move || {
+        let mut next = clock::now() + interval;
+        while runner.wait_until(next) {
+            next = (next + interval).max(clock::now());
+            if health::manager_state() != ManagerState::Running {
+                continue;
+            }
+            if !job() {
+                break;
+            }
+        }
+        TASKS.lock().unwrap().remove(&id);
+    });
+    match thread {
+        Ok(thread) => {
+            tasks.insert(id, Running { task, thread });
+            Some(id)
+        }
+        Err(e) => {
+            eprintln!("[Tasks] could not start task thread: {}", e);
+            None
+        }
+    }
+}
+
+/// Stop task `id` and wait for a run in progress to finish, unless called
+/// from that run. Returns false for an unknown or finished task.
+pub fn cancel(id: TaskId) -> bool {
+    let running = TASKS.lock().unwrap().remove(&id);
+    match running {
+        Some(running) => {
+            stop(running);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Cancel every task. Called by rust_manager_end().
+pub fn cancel_all() {
+    let all: Vec<Running> = TASKS.lock().unwrap().drain().map(|(_, r)| r).collect();
+    for running in all {
+        stop(running);
+    }
+}
+
+fn stop(running: Running) {
+    running.task.cancel();
+    if running.thread.thread().id() != thread::current().id() {
+        let _ = running.thread.join();
+    }
+}
+
+/// Number of tasks not yet finished or cancelled
+pub fn count() -> usize {
+    TASKS.lock().unwrap().len()
+}
+
+/// Run `task(user_data)` every `interval_ms` on a Rust-owned thread until
+/// it returns nonzero or is cancelled. `user_data` must stay valid until
+/// then. Returns the task id (> 0), InvalidArgument for a null task or an
+/// interval of 0, or QueueFull if no thread could be started.
+#[export_name = c_symbol!("rust_manager_spawn_task")]
+pub extern "C" fn rust_manager_spawn_task(task: Option<TaskFn>, user_data: *mut c_void, interval_ms: u32) -> i64 {
+    let task = match task {
+        Some(f) if interval_ms > 0 => f,
+        _ => return InteropErrorCode::InvalidArgument as i64,
+    };
+    let data = UserData(user_data);
+    let job = move || {
+        let data = &data;
+        task(data.0) == 0
+    };
+    spawn(Duration::from_millis(interval_ms as u64), job).unwrap_or(InteropErrorCode::QueueFull as i64)
+}
+
+/// Cancel a task. Once this returns no run of it is in progress (unless
+/// called from the task itself) and its user_data may be freed.
+/// Returns 0, or NotFound for an unknown or already finished task.
+#[export_name = c_symbol!("rust_manager_cancel_task")]
+pub extern "C" fn rust_manager_cancel_task(task_id: i64) -> c_int {
+    let rc = if cancel(task_id) { InteropErrorCode::Ok } else { InteropErrorCode::NotFound };
+    rc as c_int
+}
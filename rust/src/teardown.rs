@@ -0,0 +1,105 @@
+//! Freeing everything the interop layer holds before the process exits
+//!
+//! A host normally exits with the Rust Manager still allocated: it is a
+//! `Box::into_raw` that nothing frees, and the lookup caches keep
+//! ActorRefs to its actors. Under LeakSanitizer or Miri these report as
+//! leaks and hide real ones. `shutdown_all()` takes the layer apart once
+//! the managers are done with:
+//!
+//! ```cpp
+//! interop_shutdown_wait(5000);   // see shutdown
+//! rust_manager_end();
+//! cpp_manager.end();
+//! interop_shutdown_all();
+//! ```
+//!
+//! 1. Background tasks are cancelled and fan-out workers joined (see
+//!    tasks, broker).
+//! 2. The Rust Manager is ended if it has not been, the bridge stops
+//!    delivering to it and it is freed with every actor factory kept for
+//!    restarts.
+//! 3. Cached ActorRefs and names are dropped: post targets, lookup
+//!    answers, subscriptions, shared thread members, aliases, routers,
+//!    dead letters, actor statuses, latency probes, unregistered
+//!    subscribers built from C++ and a custom resolver.
+//! 4. Callbacks and hooks are unregistered and C++ runtimes detached, so
+//!    nothing calls into C++ code that is about to be unloaded.
+//!
+//! Afterwards the layer is as it was before `create_rust_manager()`,
+//! which starts over; a second call does nothing more. Do not call it
+//! from a Rust actor's thread, whose join it would wait for. `make lsan`
+//! and `make miri` run the tests under the leak checkers.
+
+use std::os::raw::c_int;
+
+use crate::aliases;
+use crate::broker;
+use crate::content_routes;
+use crate::cpp_runtime;
+use crate::dead_letters;
+use crate::dependencies;
+use crate::hash_router;
+use crate::health;
+use crate::interop_subscriber;
+use crate::lifecycle;
+use crate::lookup_cache;
+use crate::policy;
+use crate::post;
+use crate::probe;
+use crate::quarantine;
+use crate::resolver;
+use crate::retry;
+use crate::rust_manager_ffi;
+use crate::sender_auth;
+use crate::shared_thread;
+use crate::shutdown;
+use crate::snapshot;
+use crate::stats;
+use crate::tasks;
+
+/// Stop and free the Rust side of the bridge. Returns false if there was
+/// no Manager to free (everything else is still cleared).
+pub fn shutdown_all() -> bool {
+    tasks::cancel_all();
+    broker::set_fanout_workers(0);
+
+    let freed = rust_manager_ffi::free_manager();
+
+    post::clear_cache();
+    lookup_cache::invalidate_all();
+    broker::clear();
+    shared_thread::clear();
+    snapshot::clear();
+    dependencies::clear();
+    aliases::clear();
+    content_routes::clear();
+    hash_router::clear();
+    interop_subscriber::clear();
+    dead_letters::clear();
+    probe::clear();
+    stats::clear_actors();
+    health::clear_lifecycle();
+    resolver::reset_actor_resolver();
+
+    shutdown::interop_on_shutdown_requested(None);
+    lifecycle::rust_register_lifecycle_callback(None);
+    quarantine::rust_register_error_callback(None);
+    policy::interop_set_policy_audit_callback(None);
+    sender_auth::clear_hook();
+    sender_auth::interop_set_sender_auth_audit_callback(None);
+    retry::clear_cpp_failure_callback();
+    cpp_runtime::detach_all();
+    shutdown::reset();
+    freed
+}
+
+/// Stop and free the Rust side of the bridge, after rust_manager_end() and
+/// the C++ Manager's end(). Returns 0, or 1 if there was no Rust Manager.
+#[export_name = c_symbol!("interop_shutdown_all")]
+pub extern "C" fn interop_shutdown_all() -> c_int {
+    if shutdown_all() {
+        0
+    } else {
+        1
+    }
+}
@@ -0,0 +1,287 @@
+//! Tenant labels: several desks in one host, kept apart at the bridge
+//!
+//! One C++ host may embed the Rust strategy actors of several desks. Label
+//! each actor, Rust or C++, with its desk's tenant, and the bridge refuses
+//! a message from one tenant to another unless a rule allows it:
+//!
+//! ```c
+//! interop_set_tenant("rust_rates_strategy", "rates");
+//! interop_set_tenant("cpp_rates_gateway", "rates");
+//! interop_set_tenant("rust_fx_strategy", "fx");
+//! interop_tenant_allow("rates", "fx", 1012);   // rates quotes to fx
+//! interop_policy_seal();                       // seals the labels too
+//! ```
+//!
+//! - Checked with the policy (see policy), on the same sends: a refused
+//!   message returns `POLICY_DENIED` and is counted, logged and audited.
+//!   Both checks must pass.
+//! - Rust -> Rust sends are checked too, without the rest of the policy.
+//!   `get_actor_ref(target, sender)` of a Rust actor of another tenant
+//!   returns a ref that checks each send and delivers it in process; it
+//!   carries interop messages only (others return UnknownMessage). Refs
+//!   looked up before the labels were set, and lookups without a sender
+//!   name (host code, not an actor), are not checked.
+//! - Unlabelled actors, and C++ code that is not an actor (sender ""), are
+//!   shared by default: they may send to and receive from any tenant.
+//!   `set_unlabelled(Unlabelled::Isolated)` makes them a tenant of their
+//!   own, named "" in rules, so their traffic with labelled actors is
+//!   refused unless a rule allows it.
+//! - Rules are one way; a NULL tenant or `ANY_MSG` is a wildcard.
+//!
+//! Introspection can be narrowed to one tenant. Within `view()` (C++:
+//! `interop_tenant_view()` on the calling thread; interop-ctl:
+//! `--tenant`), the health summary, subscriptions, dead letters, captured
+//! samples and thread list show only what involves that tenant's actors.
+//! Process-wide counters are not split.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+use actors::{ActorRef, CppActorRef, Message};
+
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::clone_message;
+use crate::locality;
+use crate::names::c_str;
+use crate::policy::{self, ANY_MSG};
+use crate::rust_manager_ffi::get_actor_ref;
+
+/// What unlabelled actors and senders are while any actor is labelled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum Unlabelled {
+    /// Talk to every tenant (the default)
+    Shared = 0,
+    /// A tenant of their own, named "" in rules
+    Isolated = 1,
+}
+
+impl Unlabelled {
+    pub fn from_i32(v: i32) -> Option<Self> {
+        match v {
+            0 => Some(Unlabelled::Shared),
+            1 => Some(Unlabelled::Isolated),
+            _ => None,
+        }
+    }
+}
+
+/// One cross-tenant allowance; None matches anything
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TenantRule {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub msg_id: Option<i32>,
+}
+
+impl TenantRule {
+    // Unlabelled sides are tenant ""
+    fn matches(&self, from: Option<&str>, to: Option<&str>, msg_id: i32) -> bool {
+        self.from.as_deref().is_none_or(|t| t == from.unwrap_or(""))
+            && self.to.as_deref().is_none_or(|t| t == to.unwrap_or(""))
+            && self.msg_id.is_none_or(|id| id == msg_id)
+    }
+}
+
+#[derive(Default)]
+struct Tenants {
+    labels: HashMap<String, String>,
+    rules: Vec<TenantRule>,
+}
+
+// Skips the lock while no actor is labelled
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static ISOLATED: AtomicBool = AtomicBool::new(false);
+
+lazy_static::lazy_static! {
+    static ref TENANTS: RwLock<Tenants> = RwLock::new(Tenants::default());
+}
+
+thread_local! {
+    static VIEW: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Label `actor` with `tenant`, or remove its label with None. Returns
+/// false once the policy is sealed.
+pub fn set(actor: &str, tenant: Option<&str>) -> bool {
+    if policy::is_sealed() {
+        return false;
+    }
+    let mut tenants = TENANTS.write().unwrap();
+    match tenant {
+        Some(tenant) => tenants.labels.insert(actor.to_string(), tenant.to_string()),
+        None => tenants.labels.remove(actor),
+    };
+    ACTIVE.store(!tenants.labels.is_empty(), Ordering::Release);
+    true
+}
+
+pub fn tenant_of(actor: &str) -> Option<String> {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return None;
+    }
+    TENANTS.read().unwrap().labels.get(actor).cloned()
+}
+
+/// Allow messages from one tenant to another. Returns false once the
+/// policy is sealed.
+pub fn allow(rule: TenantRule) -> bool {
+    if policy::is_sealed() {
+        return false;
+    }
+    TENANTS.write().unwrap().rules.push(rule);
+    true
+}
+
+/// Make unlabelled actors and senders shared or a tenant of their own.
+/// Returns false once the policy is sealed.
+pub fn set_unlabelled(unlabelled: Unlabelled) -> bool {
+    if policy::is_sealed() {
+        return false;
+    }
+    ISOLATED.store(unlabelled == Unlabelled::Isolated, Ordering::Release);
+    true
+}
+
+pub fn unlabelled() -> Unlabelled {
+    if ISOLATED.load(Ordering::Acquire) { Unlabelled::Isolated } else { Unlabelled::Shared }
+}
+
+// Whether tenants `from` and `to` (None: unlabelled) are kept apart
+fn apart(from: Option<&String>, to: Option<&String>) -> bool {
+    match (from, to) {
+        (Some(from), Some(to)) => from != to,
+        (None, None) => false,
+        _ => ISOLATED.load(Ordering::Acquire),
+    }
+}
+
+/// True if messages from `sender` to `target` need a tenant rule
+pub fn crosses(sender: &str, target: &str) -> bool {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return false;
+    }
+    let tenants = TENANTS.read().unwrap();
+    apart(tenants.labels.get(sender), tenants.labels.get(target))
+}
+
+/// True unless `sender` and `target` are kept apart and no rule allows
+/// `msg_id` between them
+pub fn permits(sender: &str, target: &str, msg_id: i32) -> bool {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return true;
+    }
+    let tenants = TENANTS.read().unwrap();
+    let (from, to) = (tenants.labels.get(sender), tenants.labels.get(target));
+    !apart(from, to)
+        || tenants.rules.iter().any(|r| r.matches(from.map(String::as_str), to.map(String::as_str), msg_id))
+}
+
+/// Rust actor `target` as `actor` for `sender`: as it is, or a ref that
+/// checks tenants on every send if the two are kept apart
+pub(crate) fn guard(target: &str, sender: &str, actor: ActorRef) -> ActorRef {
+    if sender.is_empty() || matches!(actor, ActorRef::Cpp(_)) || !crosses(sender, target) {
+        return actor;
+    }
+    ActorRef::Cpp(CppActorRef::new(target, sender, checked_send_fn))
+}
+
+/// Rust -> Rust across tenants: checked, then queued like a plain send
+fn checked_send_fn(target: &str, sender: &str, msg: &dyn Message) -> i32 {
+    if !policy::allow_tenant(sender, target, msg.message_id()) {
+        return policy::POLICY_DENIED;
+    }
+    let actor = match locality::local_ref(target) {
+        Some(actor) => actor,
+        None => return InteropErrorCode::NotFound as i32,
+    };
+    let msg = match clone_message(msg) {
+        Some(msg) => msg,
+        None => return InteropErrorCode::UnknownMessage as i32,
+    };
+    actor.send(msg, get_actor_ref(sender, target));
+    InteropErrorCode::Ok as i32
+}
+
+/// Run `f` with introspection narrowed to `tenant` on this thread (None:
+/// everything)
+pub fn view<R>(tenant: Option<&str>, f: impl FnOnce() -> R) -> R {
+    let previous = VIEW.with(|v| v.replace(tenant.map(str::to_string)));
+    let result = f();
+    VIEW.with(|v| *v.borrow_mut() = previous);
+    result
+}
+
+/// The tenant introspection on this thread is narrowed to
+pub fn in_view() -> Option<String> {
+    VIEW.with(|v| v.borrow().clone())
+}
+
+/// Whether introspection on this thread shows `actor`
+pub fn visible(actor: &str) -> bool {
+    match in_view() {
+        Some(tenant) => tenant_of(actor).as_deref() == Some(tenant.as_str()),
+        None => true,
+    }
+}
+
+// Some(None) for null, None if not UTF-8
+fn c_name(s: *const c_char) -> Option<Option<String>> {
+    if s.is_null() {
+        return Some(None);
+    }
+    c_str(s).map(|s| Some(s.to_string()))
+}
+
+/// Label `actor` with `tenant`; NULL `tenant` removes the label. Returns
+/// 0, InvalidArgument if `actor` is null or either is not UTF-8, or
+/// PolicyDenied once sealed.
+#[export_name = c_symbol!("interop_set_tenant")]
+pub extern "C" fn interop_set_tenant(actor: *const c_char, tenant: *const c_char) -> c_int {
+    match (c_name(actor), c_name(tenant)) {
+        (Some(Some(actor)), Some(tenant)) => policy::sealed_unless(set(&actor, tenant.as_deref())),
+        _ => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Allow `msg_id` (INTEROP_POLICY_ANY_MSG for all) from tenant `from` to
+/// tenant `to`; NULL matches any tenant. Returns 0, InvalidArgument if
+/// either is not UTF-8, or PolicyDenied once sealed.
+#[export_name = c_symbol!("interop_tenant_allow")]
+pub extern "C" fn interop_tenant_allow(from: *const c_char, to: *const c_char, msg_id: c_int) -> c_int {
+    let (from, to) = match (c_name(from), c_name(to)) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let msg_id = if msg_id == ANY_MSG { None } else { Some(msg_id) };
+    policy::sealed_unless(allow(TenantRule { from, to, msg_id }))
+}
+
+/// Make unlabelled actors and non-actor senders shared (0) or a tenant of
+/// their own (1). Returns 0, InvalidArgument for another value or
+/// PolicyDenied once sealed.
+#[export_name = c_symbol!("interop_tenant_set_unlabelled")]
+pub extern "C" fn interop_tenant_set_unlabelled(unlabelled: c_int) -> c_int {
+    match Unlabelled::from_i32(unlabelled) {
+        Some(u) if set_unlabelled(u) => InteropErrorCode::Ok as c_int,
+        Some(_) => InteropErrorCode::PolicyDenied as c_int,
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
+
+/// Narrow introspection on the calling thread to `tenant`; NULL shows
+/// everything again. Returns 0, or InvalidArgument if `tenant` is not
+/// UTF-8.
+#[export_name = c_symbol!("interop_tenant_view")]
+pub extern "C" fn interop_tenant_view(tenant: *const c_char) -> c_int {
+    match c_name(tenant) {
+        Some(tenant) => {
+            VIEW.with(|v| *v.borrow_mut() = tenant);
+            InteropErrorCode::Ok as c_int
+        }
+        None => InteropErrorCode::InvalidArgument as c_int,
+    }
+}
@@ -0,0 +1,304 @@
+//! CPU time and context switches of each Rust actor's thread
+//!
+//! The C++ host and the Rust actors share one process, so `top` shows one
+//! figure for both. Each supervised actor records the thread it runs on
+//! when it is sent Start, and the kernel's counters for that thread are
+//! read from `/proc/self/task/<tid>` on demand:
+//!
+//! ```ignore
+//! let u = thread_usage::of("rust_strategy").unwrap();   // user, system, switches
+//! let rust = thread_usage::actors_total();
+//! let cpp = thread_usage::process().unwrap().cpu() - rust.cpu();   // the rest of the process
+//! ```
+//!
+//! Actors sharing a thread (see shared_thread) each report that thread,
+//! and `actors_total` counts it once. The process total includes threads
+//! that have exited; its context switches are those of live threads.
+//! CPU times are in kernel ticks (USER_HZ, 100 per second on mainstream
+//! architectures), so 10 ms resolution. Linux only: elsewhere every read
+//! is None. C++ uses `rust_actor_thread_usage()` and
+//! `rust_thread_usage_totals()`; `interop-ctl threads` lists every thread
+//! of the process with the actors on it.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::codec::JsonValue;
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+use crate::tenant;
+
+/// Kernel ticks per second of /proc CPU times
+const USER_HZ: u64 = 100;
+
+/// Counters of one thread, or summed over several
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThreadUsage {
+    pub user: Duration,
+    pub system: Duration,
+    /// Switches because the thread blocked (mailbox empty, lock, I/O)
+    pub voluntary_switches: u64,
+    /// Switches because the scheduler preempted it
+    pub involuntary_switches: u64,
+}
+
+impl ThreadUsage {
+    pub fn cpu(&self) -> Duration {
+        self.user + self.system
+    }
+
+    fn add(&mut self, other: &ThreadUsage) {
+        self.user += other.user;
+        self.system += other.system;
+        self.voluntary_switches += other.voluntary_switches;
+        self.involuntary_switches += other.involuntary_switches;
+    }
+}
+
+/// One live thread of the process
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ThreadInfo {
+    pub tid: u32,
+    /// Kernel thread name (15 bytes at most)
+    pub name: String,
+    /// Rust actors running on it; empty for C++ and other threads
+    pub actors: Vec<String>,
+    pub usage: ThreadUsage,
+}
+
+lazy_static::lazy_static! {
+    // Actor -> thread it was last started on
+    static ref THREADS: Mutex<HashMap<String, u32>> = Mutex::new(HashMap::new());
+}
+
+/// Kernel id of the calling thread
+#[cfg(target_os = "linux")]
+fn current_tid() -> Option<u32> {
+    // "<pid>/task/<tid>"
+    let link = std::fs::read_link("/proc/thread-self").ok()?;
+    link.file_name()?.to_str()?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_tid() -> Option<u32> {
+    None
+}
+
+fn ticks(field: Option<&str>) -> Option<Duration> {
+    let ticks: u64 = field?.parse().ok()?;
+    Some(Duration::from_millis(ticks * 1000 / USER_HZ))
+}
+
+/// Counters in /proc/self/`dir` (the process, or `task/<tid>`)
+fn read_usage(dir: &str) -> Option<ThreadUsage> {
+    if cfg!(not(target_os = "linux")) {
+        return None;
+    }
+    let stat = std::fs::read_to_string(format!("/proc/self/{}/stat", dir)).ok()?;
+    // The name is in parentheses and may hold spaces; fields after it
+    // start at state (3), so utime (14) and stime (15) are 11 and 12
+    let mut fields = stat[stat.rfind(')')? + 1..].split_whitespace().skip(11);
+    let mut usage = ThreadUsage { user: ticks(fields.next())?, system: ticks(fields.next())?, ..Default::default() };
+    let status = std::fs::read_to_string(format!("/proc/self/{}/status", dir)).ok()?;
+    for line in status.lines() {
+        if let Some(n) = line.strip_prefix("voluntary_ctxt_switches:") {
+            usage.voluntary_switches = n.trim().parse().unwrap_or(0);
+        } else if let Some(n) = line.strip_prefix("nonvoluntary_ctxt_switches:") {
+            usage.involuntary_switches = n.trim().parse().unwrap_or(0);
+        }
+    }
+    Some(usage)
+}
+
+fn task_usage(tid: u32) -> Option<ThreadUsage> {
+    read_usage(&format!("task/{}", tid))
+}
+
+/// Ids of the live threads of the process
+fn live_tids() -> Vec<u32> {
+    let mut tids: Vec<u32> = match std::fs::read_dir("/proc/self/task") {
+        Ok(dir) => dir.filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok()).collect(),
+        Err(_) => Vec::new(),
+    };
+    tids.sort_unstable();
+    tids
+}
+
+/// Record the calling thread as `actor`'s. Called by the supervisor when
+/// the actor is sent Start.
+pub(crate) fn register(actor: &str) {
+    if let Some(tid) = current_tid() {
+        THREADS.lock().unwrap().insert(actor.to_string(), tid);
+    }
+}
+
+/// Called by the supervisor when `actor` stops
+pub(crate) fn forget(actor: &str) {
+    THREADS.lock().unwrap().remove(actor);
+}
+
+/// Kernel thread id `actor` runs on, once it has started
+pub fn thread_of(actor: &str) -> Option<u32> {
+    THREADS.lock().unwrap().get(actor).copied()
+}
+
+/// Counters of the thread `actor` runs on
+pub fn of(actor: &str) -> Option<ThreadUsage> {
+    task_usage(thread_of(actor)?)
+}
+
+/// Counters of every started actor with its thread id, by actor name
+pub fn all() -> Vec<(String, u32, ThreadUsage)> {
+    let mut threads: Vec<(String, u32)> = THREADS.lock().unwrap().iter().map(|(a, &t)| (a.clone(), t)).collect();
+    threads.sort();
+    threads.into_iter().filter_map(|(actor, tid)| Some((actor, tid, task_usage(tid)?))).collect()
+}
+
+/// Counters summed over the threads of Rust actors, each thread once
+pub fn actors_total() -> ThreadUsage {
+    let mut tids: Vec<u32> = THREADS.lock().unwrap().values().copied().collect();
+    tids.sort_unstable();
+    tids.dedup();
+    let mut total = ThreadUsage::default();
+    for usage in tids.into_iter().filter_map(task_usage) {
+        total.add(&usage);
+    }
+    total
+}
+
+/// Counters of the whole process: CPU of every thread it has run,
+/// switches of its live threads
+pub fn process() -> Option<ThreadUsage> {
+    let mut usage = read_usage(".")?;
+    let (mut voluntary, mut involuntary) = (0, 0);
+    for u in live_tids().into_iter().filter_map(task_usage) {
+        voluntary += u.voluntary_switches;
+        involuntary += u.involuntary_switches;
+    }
+    usage.voluntary_switches = voluntary;
+    usage.involuntary_switches = involuntary;
+    Some(usage)
+}
+
+/// Every live thread of the process with the actors on it, by thread id
+pub fn threads() -> Vec<ThreadInfo> {
+    let mut by_tid: HashMap<u32, Vec<String>> = HashMap::new();
+    for (actor, &tid) in THREADS.lock().unwrap().iter() {
+        by_tid.entry(tid).or_default().push(actor.clone());
+    }
+    live_tids()
+        .into_iter()
+        .filter_map(|tid| {
+            let usage = task_usage(tid)?;
+            let name = std::fs::read_to_string(format!("/proc/self/task/{}/comm", tid)).unwrap_or_default();
+            let mut actors = by_tid.remove(&tid).unwrap_or_default();
+            actors.sort();
+            Some(ThreadInfo { tid, name: name.trim_end().to_string(), actors, usage })
+        })
+        .collect()
+}
+
+/// threads() as a JSON array. Within a tenant view, only the threads of
+/// its actors (see tenant).
+pub fn threads_json() -> String {
+    let mut shown = threads();
+    if tenant::in_view().is_some() {
+        for t in shown.iter_mut() {
+            t.actors.retain(|actor| tenant::visible(actor));
+        }
+        shown.retain(|t| !t.actors.is_empty());
+    }
+    let mut out = String::from("[");
+    for (i, t) in shown.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let _ = write!(out, "{{\"tid\":{},\"name\":", t.tid);
+        t.name.write_json(&mut out);
+        out.push_str(",\"actors\":[");
+        for (j, actor) in t.actors.iter().enumerate() {
+            if j > 0 {
+                out.push(',');
+            }
+            actor.write_json(&mut out);
+        }
+        let _ = write!(
+            out,
+            "],\"user_ms\":{},\"system_ms\":{},\"voluntary_switches\":{},\"involuntary_switches\":{}}}",
+            t.usage.user.as_millis(),
+            t.usage.system.as_millis(),
+            t.usage.voluntary_switches,
+            t.usage.involuntary_switches
+        );
+    }
+    out.push(']');
+    out
+}
+
+/// Mirrors RustThreadUsage in interop/thread_usage.h
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RustThreadUsage {
+    pub user_ns: u64,
+    pub system_ns: u64,
+    pub voluntary_switches: u64,
+    pub involuntary_switches: u64,
+    /// Kernel thread id; 0 for totals
+    pub tid: u32,
+}
+
+impl RustThreadUsage {
+    fn new(tid: u32, u: &ThreadUsage) -> Self {
+        RustThreadUsage {
+            user_ns: u.user.as_nanos().min(u64::MAX as u128) as u64,
+            system_ns: u.system.as_nanos().min(u64::MAX as u128) as u64,
+            voluntary_switches: u.voluntary_switches,
+            involuntary_switches: u.involuntary_switches,
+            tid,
+        }
+    }
+}
+
+/// Fill `out` with the counters of the thread `actor_name` runs on.
+/// Returns 0, InvalidArgument on a bad name or null `out`, or NotFound if
+/// the actor has not started or the counters cannot be read.
+#[export_name = c_symbol!("rust_actor_thread_usage")]
+pub extern "C" fn rust_actor_thread_usage(actor_name: *const c_char, out: *mut RustThreadUsage) -> c_int {
+    let actor = match c_str(actor_name) {
+        Some(a) if !out.is_null() => a,
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let tid = match thread_of(actor) {
+        Some(t) => t,
+        None => return InteropErrorCode::NotFound as c_int,
+    };
+    let rc = match task_usage(tid) {
+        Some(u) => {
+            unsafe { *out = RustThreadUsage::new(tid, &u) };
+            InteropErrorCode::Ok
+        }
+        None => InteropErrorCode::NotFound,
+    };
+    rc as c_int
+}
+
+/// Fill `rust_actors` (if non-null) with the counters summed over Rust
+/// actor threads and `process` (if non-null) with those of the whole
+/// process. Returns 0, or NotFound if the counters cannot be read.
+#[export_name = c_symbol!("rust_thread_usage_totals")]
+pub extern "C" fn rust_thread_usage_totals(rust_actors: *mut RustThreadUsage, process: *mut RustThreadUsage) -> c_int {
+    let whole = match self::process() {
+        Some(p) => p,
+        None => return InteropErrorCode::NotFound as c_int,
+    };
+    if !rust_actors.is_null() {
+        unsafe { *rust_actors = RustThreadUsage::new(0, &actors_total()) };
+    }
+    if !process.is_null() {
+        unsafe { *process = RustThreadUsage::new(0, &whole) };
+    }
+    InteropErrorCode::Ok as c_int
+}
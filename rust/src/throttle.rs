@@ -0,0 +1,315 @@
+//! Downsampled publishing: at most so many updates per topic per second
+//!
+//! A feed handler may hand a publisher thousands of quotes a second while
+//! a GUI subscriber wants a few. A `ThrottledPublisher` takes updates at
+//! any rate and publishes each topic at most `max_per_sec` times a second
+//! through the broker, keeping only the latest update of a topic between
+//! two publishes:
+//!
+//! ```ignore
+//! let quotes = ThrottledPublisher::new("rust_publisher", 10);
+//! quotes.update("AAPL", Box::new(update))?;   // on every tick
+//! ```
+//!
+//! - An update to a topic not published for an interval goes out at once;
+//!   later ones within the interval replace each other and the last goes
+//!   out when the interval ends, from a flusher thread (see tasks). No
+//!   topic sees more than one publish per interval, and the last update
+//!   always reaches subscribers.
+//! - `with_build` shapes the message per subscriber, like
+//!   `broker::publish_with`; the default sends each a copy.
+//! - Updates carrying a partition key reach the partition's subscribers
+//!   too (see partitions); a topic declared for another message type is
+//!   refused (see topic).
+//! - `max_per_sec` of 0 publishes every update.
+//!
+//! C++ feeds one with `interop_throttle_new()` / `interop_throttle_update()`
+//! (`interop/throttle.h`). Dropping the last handle stops the flusher;
+//! updates still held are dropped unless `flush()` is called first.
+
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use actors::Message;
+
+use crate::broker::{self, Subscription};
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{clone_message, give_back_message_bytes, message_from_c};
+use crate::names::c_str;
+use crate::partitions;
+use crate::tasks::{self, TaskId};
+use crate::topic::{self, TopicError};
+
+/// Builds the message for one subscriber from the topic's latest update
+pub type Build = Box<dyn FnMut(&Subscription, &dyn Message) -> Option<Box<dyn Message>> + Send>;
+
+#[derive(Default)]
+struct Topic {
+    // Held until the interval since the last publish ends
+    latest: Option<Box<dyn Message>>,
+    last: Option<Instant>,
+}
+
+struct Inner {
+    publisher: String,
+    interval: Duration,
+    topics: Mutex<HashMap<String, Topic>>,
+    build: Mutex<Build>,
+    // Started on the first held update
+    flusher: Mutex<Option<TaskId>>,
+    published: AtomicU64,
+    superseded: AtomicU64,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(id) = self.flusher.get_mut().unwrap().take() {
+            tasks::cancel(id);
+        }
+    }
+}
+
+/// Publishes each topic of one publisher at a capped rate. Clones share
+/// the same topics.
+#[derive(Clone)]
+pub struct ThrottledPublisher {
+    inner: Arc<Inner>,
+}
+
+impl ThrottledPublisher {
+    /// Publish as `publisher`, each topic at most `max_per_sec` times a
+    /// second
+    pub fn new(publisher: &str, max_per_sec: u32) -> Self {
+        Self::with_build(publisher, max_per_sec, |_, msg| clone_message(msg))
+    }
+
+    /// `new` that sends `build(subscription, latest)` to each subscriber;
+    /// None skips that subscriber
+    pub fn with_build<F>(publisher: &str, max_per_sec: u32, build: F) -> Self
+    where
+        F: FnMut(&Subscription, &dyn Message) -> Option<Box<dyn Message>> + Send + 'static,
+    {
+        let interval = if max_per_sec == 0 { Duration::ZERO } else { Duration::from_secs(1) / max_per_sec };
+        ThrottledPublisher {
+            inner: Arc::new(Inner {
+                publisher: publisher.to_string(),
+                interval,
+                topics: Mutex::new(HashMap::new()),
+                build: Mutex::new(Box::new(build)),
+                flusher: Mutex::new(None),
+                published: AtomicU64::new(0),
+                superseded: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn publisher(&self) -> &str {
+        &self.inner.publisher
+    }
+
+    /// Shortest time between two publishes of a topic
+    pub fn interval(&self) -> Duration {
+        self.inner.interval
+    }
+
+    /// Make `msg` the latest update of `topic`: published now if the
+    /// topic's interval has passed, else when it does
+    pub fn update(&self, topic: &str, msg: Box<dyn Message>) -> Result<(), TopicError> {
+        topic::check(topic, msg.as_ref())?;
+        let now = clock::now();
+        {
+            let mut topics = self.inner.topics.lock().unwrap();
+            let entry = topics.entry(topic.to_string()).or_default();
+            let due = entry.last.is_none_or(|t| now.saturating_duration_since(t) >= self.inner.interval);
+            if !due || entry.latest.is_some() {
+                if entry.latest.replace(msg).is_some() {
+                    self.inner.superseded.fetch_add(1, Ordering::Relaxed);
+                }
+                drop(topics);
+                self.start_flusher();
+                return Ok(());
+            }
+            entry.last = Some(now);
+        }
+        // Published outside the lock, so a slow fan-out holds up no other topic
+        self.publish(topic, msg.as_ref());
+        Ok(())
+    }
+
+    /// Publish every held update now
+    pub fn flush(&self) {
+        self.flush_where(|_| true);
+    }
+
+    /// Updates held for a later publish
+    pub fn pending(&self) -> usize {
+        self.inner.topics.lock().unwrap().values().filter(|t| t.latest.is_some()).count()
+    }
+
+    /// Publishes since creation, one per topic update sent
+    pub fn published(&self) -> u64 {
+        self.inner.published.load(Ordering::Relaxed)
+    }
+
+    /// Updates replaced by a later one before they were published
+    pub fn superseded(&self) -> u64 {
+        self.inner.superseded.load(Ordering::Relaxed)
+    }
+
+    fn publish(&self, topic: &str, msg: &dyn Message) {
+        let partition = partitions::partition_of(topic, msg);
+        let mut build = self.inner.build.lock().unwrap();
+        broker::publish_partition_with(&self.inner.publisher, topic, partition, |sub| (*build)(sub, msg));
+        self.inner.published.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn flush_where(&self, due: impl Fn(&Topic) -> bool) {
+        let now = clock::now();
+        let held: Vec<(String, Box<dyn Message>)> = {
+            let mut topics = self.inner.topics.lock().unwrap();
+            topics
+                .iter_mut()
+                .filter(|(_, t)| t.latest.is_some() && due(t))
+                .map(|(name, t)| {
+                    t.last = Some(now);
+                    (name.clone(), t.latest.take().unwrap())
+                })
+                .collect()
+        };
+        for (topic, msg) in held {
+            self.publish(&topic, msg.as_ref());
+        }
+    }
+
+    fn start_flusher(&self) {
+        let mut flusher = self.inner.flusher.lock().unwrap();
+        if flusher.is_some() {
+            return;
+        }
+        // A quarter of the interval, so a held update waits little past it
+        let tick = (self.inner.interval / 4).max(Duration::from_millis(1));
+        let weak: Weak<Inner> = Arc::downgrade(&self.inner);
+        *flusher = tasks::spawn(tick, move || match weak.upgrade() {
+            Some(inner) => {
+                let throttle = ThrottledPublisher { inner };
+                let interval = throttle.inner.interval;
+                let now = clock::now();
+                throttle.flush_where(|t| t.last.is_none_or(|l| now.saturating_duration_since(l) >= interval));
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+static NEXT_ID: AtomicI64 = AtomicI64::new(1);
+
+lazy_static::lazy_static! {
+    static ref THROTTLES: Mutex<HashMap<i64, ThrottledPublisher>> = Mutex::new(HashMap::new());
+}
+
+fn by_id(throttle: i64) -> Option<ThrottledPublisher> {
+    THROTTLES.lock().unwrap().get(&throttle).cloned()
+}
+
+/// Start a throttled publisher publishing as `publisher`, each topic at
+/// most `max_per_sec` times a second (0: every update). Returns its id
+/// (> 0), or InvalidArgument on a bad name.
+#[export_name = c_symbol!("interop_throttle_new")]
+pub extern "C" fn interop_throttle_new(publisher: *const c_char, max_per_sec: u32) -> i64 {
+    match c_str(publisher) {
+        Some(name) => {
+            let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+            THROTTLES.lock().unwrap().insert(id, ThrottledPublisher::new(name, max_per_sec));
+            id
+        }
+        None => InteropErrorCode::InvalidArgument as i64,
+    }
+}
+
+/// Make C struct `msg_data` (message `msg_id`) the latest update of
+/// `topic`. Returns 0, NotFound on an unknown throttle, InvalidArgument on
+/// a bad topic or null `msg_data`, UnknownMessage on an unknown message,
+/// or WrongType if the topic carries another message type.
+#[export_name = c_symbol!("interop_throttle_update")]
+pub extern "C" fn interop_throttle_update(
+    throttle: i64,
+    topic: *const c_char,
+    msg_id: c_int,
+    msg_data: *const c_void,
+) -> c_int {
+    let throttle = match by_id(throttle) {
+        Some(t) => t,
+        None => return InteropErrorCode::NotFound as c_int,
+    };
+    let topic = match c_str(topic) {
+        Some(topic) if !msg_data.is_null() => topic,
+        _ => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let mut msg = match unsafe { message_from_c(msg_id, msg_data) } {
+        Some(msg) => msg,
+        None => return InteropErrorCode::UnknownMessage as c_int,
+    };
+    let refused = |e: TopicError| {
+        eprintln!("[Throttle] {}", e);
+        match e {
+            TopicError::Mismatch { .. } => InteropErrorCode::WrongType as c_int,
+            TopicError::NameTooLong(_) => InteropErrorCode::InvalidArgument as c_int,
+        }
+    };
+    if let Err(e) = topic::check(topic, msg.as_ref()) {
+        // A take-mode buffer stays the caller's to free
+        unsafe { give_back_message_bytes(msg.as_mut(), msg_data) };
+        return refused(e);
+    }
+    match throttle.update(topic, msg) {
+        Ok(()) => 0,
+        Err(e) => refused(e),
+    }
+}
+
+/// Publish the held updates of `throttle` now. Returns 0, or NotFound on
+/// an unknown throttle.
+#[export_name = c_symbol!("interop_throttle_flush")]
+pub extern "C" fn interop_throttle_flush(throttle: i64) -> c_int {
+    match by_id(throttle) {
+        Some(t) => {
+            t.flush();
+            0
+        }
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
+
+/// Write the publish and superseded counts of `throttle`; either pointer
+/// may be null. Returns 0, or NotFound on an unknown throttle.
+#[export_name = c_symbol!("interop_throttle_stats")]
+pub extern "C" fn interop_throttle_stats(throttle: i64, published_out: *mut u64, superseded_out: *mut u64) -> c_int {
+    let t = match by_id(throttle) {
+        Some(t) => t,
+        None => return InteropErrorCode::NotFound as c_int,
+    };
+    if !published_out.is_null() {
+        unsafe { *published_out = t.published() };
+    }
+    if !superseded_out.is_null() {
+        unsafe { *superseded_out = t.superseded() };
+    }
+    0
+}
+
+/// Stop `throttle`; updates it still holds are dropped. Returns 0, or
+/// NotFound on an unknown throttle.
+#[export_name = c_symbol!("interop_throttle_free")]
+pub extern "C" fn interop_throttle_free(throttle: i64) -> c_int {
+    // Dropped outside the lock: stopping the flusher waits for its run
+    let removed = THROTTLES.lock().unwrap().remove(&throttle);
+    match removed {
+        Some(_) => 0,
+        None => InteropErrorCode::NotFound as c_int,
+    }
+}
@@ -0,0 +1,245 @@
+//! Point-in-time fields (`interop_timestamp`)
+//!
+//! Timestamps used to be `int64_t` fields filled by hand, in milliseconds
+//! on one side (`as_millis() as i64`) and whatever the author chose on the
+//! other. A field declared as `interop_timestamp` is nanoseconds since the
+//! Unix epoch, UTC, in both languages:
+//!
+//! ```ignore
+//! let update = MarketUpdate { timestamp: Timestamp::now(), ..Default::default() };
+//! let sent: SystemTime = update.timestamp.into();
+//! let age = update.timestamp.elapsed();
+//! ```
+//!
+//! The struct is `#[repr(C)]` and matches the C typedef. C++ gets
+//! `interop::Timestamp` in InteropMessages.hpp with `std::chrono`
+//! conversions; with feature `chrono`, `DateTime<Utc>` converts both ways.
+//! An `i64` of nanoseconds covers 1677-09-21 to 2262-04-11; conversions
+//! from times outside that saturate. JSON carries RFC 3339 text
+//! ("2026-10-16T09:30:00.000000001Z").
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::clock;
+
+const NANOS_PER_SEC: i64 = 1_000_000_000;
+const SECS_PER_DAY: i64 = 86_400;
+
+/// Nanoseconds since the Unix epoch, UTC; matches the C `interop_timestamp`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timestamp {
+    pub nanos: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseTimestampError;
+
+impl fmt::Display for ParseTimestampError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected an RFC 3339 UTC time like 2026-10-16T09:30:00Z")
+    }
+}
+
+impl std::error::Error for ParseTimestampError {}
+
+// Days since 1970-01-01 of a proleptic Gregorian date (H. Hinnant)
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+impl Timestamp {
+    pub const UNIX_EPOCH: Timestamp = Timestamp { nanos: 0 };
+
+    /// Wall-clock time of the installed clock (see `clock`)
+    pub fn now() -> Self {
+        clock::wall().into()
+    }
+
+    pub const fn from_nanos(nanos: i64) -> Self {
+        Timestamp { nanos }
+    }
+
+    pub const fn from_micros(micros: i64) -> Self {
+        Timestamp { nanos: micros.saturating_mul(1_000) }
+    }
+
+    pub const fn from_millis(millis: i64) -> Self {
+        Timestamp { nanos: millis.saturating_mul(1_000_000) }
+    }
+
+    pub const fn from_secs(secs: i64) -> Self {
+        Timestamp { nanos: secs.saturating_mul(NANOS_PER_SEC) }
+    }
+
+    pub const fn as_nanos(&self) -> i64 {
+        self.nanos
+    }
+
+    /// Whole microseconds, rounded toward the past
+    pub const fn as_micros(&self) -> i64 {
+        self.nanos.div_euclid(1_000)
+    }
+
+    /// Whole milliseconds, rounded toward the past
+    pub const fn as_millis(&self) -> i64 {
+        self.nanos.div_euclid(1_000_000)
+    }
+
+    /// Whole seconds, rounded toward the past
+    pub const fn as_secs(&self) -> i64 {
+        self.nanos.div_euclid(NANOS_PER_SEC)
+    }
+
+    /// Time from `earlier` to this one, None if `earlier` is later
+    pub fn duration_since(&self, earlier: Timestamp) -> Option<Duration> {
+        let d = (self.nanos as i128) - (earlier.nanos as i128);
+        if d < 0 {
+            return None;
+        }
+        Some(Duration::new((d / NANOS_PER_SEC as i128) as u64, (d % NANOS_PER_SEC as i128) as u32))
+    }
+
+    /// Time since this timestamp, zero if it is in the future
+    pub fn elapsed(&self) -> Duration {
+        Timestamp::now().duration_since(*self).unwrap_or_default()
+    }
+
+    pub fn checked_add(&self, d: Duration) -> Option<Timestamp> {
+        let nanos = i64::try_from(d.as_nanos()).ok()?;
+        self.nanos.checked_add(nanos).map(Timestamp::from_nanos)
+    }
+
+    pub fn checked_sub(&self, d: Duration) -> Option<Timestamp> {
+        let nanos = i64::try_from(d.as_nanos()).ok()?;
+        self.nanos.checked_sub(nanos).map(Timestamp::from_nanos)
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(t: SystemTime) -> Self {
+        let nanos = match t.duration_since(UNIX_EPOCH) {
+            Ok(after) => i64::try_from(after.as_nanos()).unwrap_or(i64::MAX),
+            Err(e) => i64::try_from(e.duration().as_nanos()).map_or(i64::MIN, |n| -n),
+        };
+        Timestamp { nanos }
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(t: Timestamp) -> Self {
+        let magnitude = Duration::from_nanos(t.nanos.unsigned_abs());
+        if t.nanos >= 0 {
+            UNIX_EPOCH + magnitude
+        } else {
+            UNIX_EPOCH - magnitude
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<chrono::DateTime<chrono::Utc>> for Timestamp {
+    fn from(t: chrono::DateTime<chrono::Utc>) -> Self {
+        let nanos = t.timestamp_nanos_opt().unwrap_or(if t.timestamp() < 0 { i64::MIN } else { i64::MAX });
+        Timestamp { nanos }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl From<Timestamp> for chrono::DateTime<chrono::Utc> {
+    fn from(t: Timestamp) -> Self {
+        chrono::DateTime::from_timestamp_nanos(t.nanos)
+    }
+}
+
+/// RFC 3339 in UTC with nanoseconds: 2026-10-16T09:30:00.000000001Z
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.nanos.div_euclid(NANOS_PER_SEC);
+        let sub = self.nanos.rem_euclid(NANOS_PER_SEC);
+        let (y, mo, d) = civil_from_days(secs.div_euclid(SECS_PER_DAY));
+        let tod = secs.rem_euclid(SECS_PER_DAY);
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+            y,
+            mo,
+            d,
+            tod / 3600,
+            tod % 3600 / 60,
+            tod % 60,
+            sub
+        )
+    }
+}
+
+/// RFC 3339: `YYYY-MM-DDTHH:MM:SS[.fraction](Z|+HH:MM|-HH:MM)`
+impl FromStr for Timestamp {
+    type Err = ParseTimestampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let b = s.as_bytes();
+        let num = |range: std::ops::Range<usize>| -> Result<i64, ParseTimestampError> {
+            let part = b.get(range).ok_or(ParseTimestampError)?;
+            if !part.iter().all(u8::is_ascii_digit) {
+                return Err(ParseTimestampError);
+            }
+            Ok(part.iter().fold(0, |n, c| n * 10 + (c - b'0') as i64))
+        };
+        if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !matches!(b[10], b'T' | b't' | b' ') || b[13] != b':' || b[16] != b':' {
+            return Err(ParseTimestampError);
+        }
+        let (y, mo, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+        let (h, mi, sec) = (num(11..13)?, num(14..16)?, num(17..19)?);
+        if !(1..=12).contains(&mo) || !(1..=31).contains(&d) || h > 23 || mi > 59 || sec > 60 {
+            return Err(ParseTimestampError);
+        }
+        let mut i = 19;
+        let mut sub = 0i64;
+        if b[i] == b'.' {
+            i += 1;
+            let start = i;
+            while i < b.len() && b[i].is_ascii_digit() {
+                if i - start < 9 {
+                    sub = sub * 10 + (b[i] - b'0') as i64;
+                }
+                i += 1;
+            }
+            if i == start {
+                return Err(ParseTimestampError);
+            }
+            sub *= 10i64.pow(9u32.saturating_sub((i - start) as u32));
+        }
+        let offset = match b.get(i) {
+            Some(b'Z' | b'z') if i + 1 == b.len() => 0,
+            Some(sign @ (b'+' | b'-')) if i + 6 == b.len() && b[i + 3] == b':' => {
+                let off = num(i + 1..i + 3)? * 3600 + num(i + 4..i + 6)? * 60;
+                if *sign == b'+' { off } else { -off }
+            }
+            _ => return Err(ParseTimestampError),
+        };
+        let secs = (days_from_civil(y, mo, d) * SECS_PER_DAY + h * 3600 + mi * 60 + sec - offset) as i128;
+        let nanos = secs * NANOS_PER_SEC as i128 + sub as i128;
+        i64::try_from(nanos).map(Timestamp::from_nanos).map_err(|_| ParseTimestampError)
+    }
+}
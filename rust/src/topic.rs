@@ -0,0 +1,185 @@
+//! Typed topics: the message type a topic carries
+//!
+//! `Subscribe` names a topic with a plain string, so nothing stops a
+//! publisher from sending `MarketDepth` to a subscriber expecting
+//! `MarketUpdate`. A `Topic<M>` declares the type once per process:
+//!
+//! ```ignore
+//! use actors_interop::topic::Topic;
+//!
+//! // Subscriber
+//! let aapl = Topic::<MarketUpdate>::new("AAPL")?;
+//! aapl.subscribe(&publisher, QOS_BEST_EFFORT);
+//!
+//! // Publisher, on Subscribe
+//! let topic = Topic::<MarketUpdate>::from_subscribe(msg)?;  // Err if "AAPL" carries another type
+//! topic.publish(&subscriber, update);                      // only a MarketUpdate compiles
+//! ```
+//!
+//! Within Rust the type is checked at compile time by `publish`; across
+//! declarations it is checked at run time: declaring a topic again with
+//! another type fails with `TopicError::Mismatch`. Publishers that only
+//! see `&dyn Message` use `check()`. C++ declares and checks topics by
+//! message ID with `interop_declare_topic()` / `interop_check_topic()`
+//! (`interop/topic.h`), sharing the same declarations.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::os::raw::{c_char, c_int};
+use std::sync::Mutex;
+
+use actors::{ActorRef, Message};
+
+use crate::cpp_actor_if::InteropMessage;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{message_name, Subscribe, Unsubscribe};
+use crate::interop_string::{InteropString, TruncationPolicy, TooLong};
+use crate::names::c_str;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopicError {
+    /// The topic is declared with another message type
+    Mismatch { topic: String, declared: i32, got: i32 },
+    /// The name does not fit in `Subscribe.topic`
+    NameTooLong(TooLong),
+}
+
+fn type_name(msg_id: i32) -> String {
+    message_name(msg_id).map(str::to_string).unwrap_or_else(|| format!("message {}", msg_id))
+}
+
+impl fmt::Display for TopicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TopicError::Mismatch { topic, declared, got } => write!(
+                f,
+                "topic '{}' carries {}, not {}",
+                topic,
+                type_name(*declared),
+                type_name(*got)
+            ),
+            TopicError::NameTooLong(e) => write!(f, "topic name: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TopicError {}
+
+lazy_static::lazy_static! {
+    // Topic name -> message ID it carries
+    static ref DECLARED: Mutex<HashMap<String, i32>> = Mutex::new(HashMap::new());
+}
+
+/// Record that `topic` carries `msg_id`; a no-op if it already does
+pub fn declare(topic: &str, msg_id: i32) -> Result<(), TopicError> {
+    let mut declared = DECLARED.lock().unwrap();
+    match declared.get(topic) {
+        Some(&id) if id != msg_id => {
+            Err(TopicError::Mismatch { topic: topic.to_string(), declared: id, got: msg_id })
+        }
+        Some(_) => Ok(()),
+        None => {
+            declared.insert(topic.to_string(), msg_id);
+            Ok(())
+        }
+    }
+}
+
+/// Message ID `topic` is declared with, None if undeclared
+pub fn declared(topic: &str) -> Option<i32> {
+    DECLARED.lock().unwrap().get(topic).copied()
+}
+
+/// Whether `msg` may be published on `topic`; undeclared topics accept anything
+pub fn check(topic: &str, msg: &dyn Message) -> Result<(), TopicError> {
+    match declared(topic) {
+        Some(id) if id != msg.message_id() => Err(TopicError::Mismatch {
+            topic: topic.to_string(),
+            declared: id,
+            got: msg.message_id(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// A topic carrying messages of type `M`
+pub struct Topic<M> {
+    name: InteropString<32>,
+    _msg: PhantomData<fn() -> M>,
+}
+
+impl<M> Clone for Topic<M> {
+    fn clone(&self) -> Self {
+        Topic { name: self.name, _msg: PhantomData }
+    }
+}
+
+impl<M> fmt::Debug for Topic<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Topic").field(&self.name.as_str()).finish()
+    }
+}
+
+impl<M: Message + InteropMessage> Topic<M> {
+    /// Declare `name` as carrying `M`
+    pub fn new(name: &str) -> Result<Self, TopicError> {
+        let name = InteropString::with_policy(name, TruncationPolicy::Reject).map_err(TopicError::NameTooLong)?;
+        declare(name.as_str(), M::MSG_ID)?;
+        Ok(Topic { name, _msg: PhantomData })
+    }
+
+    /// The topic a subscriber asked for, if it may carry `M`
+    pub fn from_subscribe(msg: &Subscribe) -> Result<Self, TopicError> {
+        Self::new(msg.topic.as_str())
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Send Subscribe for this topic to `publisher`
+    pub fn subscribe(&self, publisher: &ActorRef, qos: i32) {
+        publisher.send(Box::new(Subscribe { topic: self.name, qos }), None);
+    }
+
+    /// Send Unsubscribe for this topic to `publisher`
+    pub fn unsubscribe(&self, publisher: &ActorRef) {
+        publisher.send(Box::new(Unsubscribe { topic: self.name }), None);
+    }
+
+    /// Send one message of the topic to a subscriber
+    pub fn publish(&self, subscriber: &ActorRef, msg: M) {
+        subscriber.send(Box::new(msg), None);
+    }
+}
+
+/// Declare that `topic` carries message `msg_id`.
+/// Returns 0, WrongType if it is declared with another type, or
+/// InvalidArgument if `topic` is bad.
+#[export_name = c_symbol!("interop_declare_topic")]
+pub extern "C" fn interop_declare_topic(topic: *const c_char, msg_id: c_int) -> c_int {
+    let rc = match c_str(topic).map(|t| declare(t, msg_id)) {
+        Some(Ok(())) => InteropErrorCode::Ok,
+        Some(Err(e)) => {
+            eprintln!("[Topic] {}", e);
+            InteropErrorCode::WrongType
+        }
+        None => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
+
+/// Returns 0 if message `msg_id` may be published on `topic` (declared with
+/// it, or undeclared), WrongType if not, or InvalidArgument if `topic` is
+/// bad
+#[export_name = c_symbol!("interop_check_topic")]
+pub extern "C" fn interop_check_topic(topic: *const c_char, msg_id: c_int) -> c_int {
+    let rc = match c_str(topic).map(declared) {
+        Some(None) => InteropErrorCode::Ok,
+        Some(Some(id)) if id == msg_id => InteropErrorCode::Ok,
+        Some(Some(_)) => InteropErrorCode::WrongType,
+        None => InteropErrorCode::InvalidArgument,
+    };
+    rc as c_int
+}
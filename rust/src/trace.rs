@@ -0,0 +1,124 @@
+//! Tracing spans across the FFI boundary (feature `tracing`)
+//!
+//! With the feature on, the bridge reports spans through the `tracing`
+//! crate so a profiler sees C++ work, the FFI send and the Rust handler in
+//! one flame:
+//!
+//! - `cpp`: opened by C++ with `rust_trace_begin(name)`, closed with
+//!   `rust_trace_end(span_id)` on the same thread. Field `name`.
+//! - `ffi_send`: each `rust_actor_send` / `rust_actor_fast_send`, a child
+//!   of the C++ span open on the calling thread. Fields `target`, `msg_id`.
+//! - `handle`: each handler invocation of a Rust actor, on the actor's
+//!   thread. Fields `actor`, `msg_id`.
+//!
+//! ```cpp
+//! uint64_t span = rust_trace_begin("on_quote");
+//! rust_pricer.send(new msg::MarketUpdate(...));   // ffi_send inside on_quote
+//! rust_trace_end(span);
+//! ```
+//!
+//! Install a subscriber (e.g. tracing-subscriber, tracing-chrome) from Rust
+//! before rust_manager_init(). Without the feature the C functions are
+//! no-ops returning 0, so C++ can call them unconditionally.
+
+use std::os::raw::{c_char, c_int};
+
+#[cfg(feature = "tracing")]
+use crate::interop_errors::InteropErrorCode;
+#[cfg(feature = "tracing")]
+use crate::names::c_str;
+
+/// Entered span, exited on drop
+pub struct SpanGuard {
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Span around one handler invocation of `actor`
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn handler(actor: &str, msg_id: i32) -> SpanGuard {
+    SpanGuard {
+        #[cfg(feature = "tracing")]
+        _span: tracing::info_span!("handle", actor, msg_id).entered(),
+    }
+}
+
+/// Span around one C++ -> Rust send
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub fn ffi_send(target: &str, msg_id: i32) -> SpanGuard {
+    SpanGuard {
+        #[cfg(feature = "tracing")]
+        _span: tracing::info_span!("ffi_send", target, msg_id).entered(),
+    }
+}
+
+/// Id of the span the calling thread is in, e.g. a C++ span around a send;
+/// None outside any span or without the `tracing` feature
+pub fn current_id() -> Option<u64> {
+    #[cfg(feature = "tracing")]
+    {
+        tracing::Span::current().id().map(|id| id.into_u64())
+    }
+    #[cfg(not(feature = "tracing"))]
+    None
+}
+
+#[cfg(feature = "tracing")]
+mod cpp_spans {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use tracing::span::EnteredSpan;
+
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+    thread_local! {
+        // Entered spans are tied to their thread
+        static OPEN: RefCell<HashMap<u64, EnteredSpan>> = RefCell::new(HashMap::new());
+    }
+
+    pub fn begin(name: &str) -> u64 {
+        let span = tracing::info_span!("cpp", name).entered();
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        OPEN.with(|open| open.borrow_mut().insert(id, span));
+        id
+    }
+
+    pub fn end(id: u64) -> bool {
+        OPEN.with(|open| open.borrow_mut().remove(&id)).is_some()
+    }
+}
+
+/// Open a span named `name` on the calling thread and enter it. Returns
+/// its id for rust_trace_end(), or 0 without the `tracing` feature or on a
+/// bad name.
+#[export_name = c_symbol!("rust_trace_begin")]
+pub extern "C" fn rust_trace_begin(name: *const c_char) -> u64 {
+    #[cfg(feature = "tracing")]
+    {
+        c_str(name).map_or(0, cpp_spans::begin)
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = name;
+        0
+    }
+}
+
+/// Close a span from rust_trace_begin(). Must be called on the thread that
+/// opened it. Returns 0, or NotFound if `span_id` is not open on this
+/// thread.
+#[export_name = c_symbol!("rust_trace_end")]
+pub extern "C" fn rust_trace_end(span_id: u64) -> c_int {
+    #[cfg(feature = "tracing")]
+    {
+        let rc = if cpp_spans::end(span_id) { InteropErrorCode::Ok } else { InteropErrorCode::NotFound };
+        rc as c_int
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = span_id;
+        0
+    }
+}
@@ -0,0 +1,176 @@
+//! All-or-nothing sends from a handler
+//!
+//! A handler that sends an order to the C++ gateway and then a position
+//! update to the C++ risk engine, and panics in between, leaves the two
+//! out of step. Sends made through a transaction are buffered instead and
+//! made only once the handler has returned:
+//!
+//! ```ignore
+//! use actors_interop::transaction::Transact;
+//!
+//! ctx.transaction(|tx| {
+//!     tx.send("cpp_gateway", Box::new(order));
+//!     tx.send("cpp_risk", Box::new(position));
+//!     if !limits_ok { tx.abort(); }        // nothing is sent
+//! });
+//! ```
+//!
+//! Under supervision (see lifecycle) the buffered sends are made after the
+//! handler returns; if it panics, in the transaction or after it, they are
+//! discarded. Without supervision they are made when the closure returns.
+//! Targets named by string are looked up when the sends are made, from the
+//! handling actor. Atomicity covers the handler failing, not delivery: a
+//! send the bridge refuses at that point (e.g. target gone, open circuit)
+//! is a dead letter as usual and the rest are still made.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use actors::{ActorContext, ActorRef, Message};
+
+use crate::health;
+use crate::rust_manager_ffi::get_actor_ref;
+use crate::send_error::TrySend;
+
+enum Target {
+    Name(String),
+    Ref(ActorRef),
+}
+
+struct Pending {
+    target: Target,
+    msg: Box<dyn Message>,
+}
+
+/// Handler's buffered sends
+struct Outbox {
+    actor: String,
+    sends: Vec<Pending>,
+}
+
+thread_local! {
+    // Set by the supervisor for the duration of a handler
+    static OUTBOX: RefCell<Option<Outbox>> = const { RefCell::new(None) };
+}
+
+static DISCARDED: AtomicU64 = AtomicU64::new(0);
+
+/// Sends buffered by `Transact::transaction`
+pub struct Transaction {
+    sends: Vec<Pending>,
+    aborted: bool,
+}
+
+impl Transaction {
+    /// Send `msg` to the actor named `target`, Rust or C++, on commit
+    pub fn send(&mut self, target: &str, msg: Box<dyn Message>) {
+        self.sends.push(Pending { target: Target::Name(target.to_string()), msg });
+    }
+
+    /// Send `msg` to `target` on commit
+    pub fn send_ref(&mut self, target: ActorRef, msg: Box<dyn Message>) {
+        self.sends.push(Pending { target: Target::Ref(target), msg });
+    }
+
+    /// Discard every send of this transaction
+    pub fn abort(&mut self) {
+        self.aborted = true;
+    }
+
+    pub fn len(&self) -> usize {
+        self.sends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sends.is_empty()
+    }
+}
+
+/// `ActorContext::transaction()`
+pub trait Transact {
+    /// Run `f`, then make the sends it buffered unless it aborted or the
+    /// handler panics (see the module docs)
+    fn transaction<R>(&mut self, f: impl FnOnce(&mut Transaction) -> R) -> R;
+}
+
+impl Transact for ActorContext {
+    fn transaction<R>(&mut self, f: impl FnOnce(&mut Transaction) -> R) -> R {
+        let mut tx = Transaction { sends: Vec::new(), aborted: false };
+        let result = f(&mut tx);
+        if tx.aborted {
+            DISCARDED.fetch_add(tx.sends.len() as u64, Ordering::Relaxed);
+            return result;
+        }
+        let unsent = OUTBOX.with(|o| match o.borrow_mut().as_mut() {
+            Some(outbox) => {
+                outbox.sends.append(&mut tx.sends);
+                None
+            }
+            None => Some(tx.sends),
+        });
+        if let Some(sends) = unsent {
+            flush("", sends);
+        }
+        result
+    }
+}
+
+/// Sends discarded by aborts and failed handlers
+pub fn discarded() -> u64 {
+    DISCARDED.load(Ordering::Relaxed)
+}
+
+fn flush(actor: &str, sends: Vec<Pending>) {
+    for send in sends {
+        let (name, target) = match send.target {
+            Target::Name(name) => {
+                let target = get_actor_ref(&name, actor);
+                (name, target)
+            }
+            Target::Ref(target) => {
+                let name = match &target {
+                    ActorRef::Cpp(cpp) => cpp.target.clone(),
+                    _ => String::new(),
+                };
+                (name, Some(target))
+            }
+        };
+        let target = match target {
+            Some(t) => t,
+            None => {
+                health::record_dead_letter(actor, &name, send.msg.message_id());
+                continue;
+            }
+        };
+        let sender = if actor.is_empty() { None } else { get_actor_ref(actor, &name) };
+        if let Err(e) = target.try_send(send.msg, sender) {
+            eprintln!("[Transaction] {} -> {}: {}", actor, name, e);
+        }
+    }
+}
+
+/// Name of the actor whose handler runs on this thread, under supervision
+pub(crate) fn current_actor() -> Option<String> {
+    OUTBOX.with(|o| o.borrow().as_ref().map(|outbox| outbox.actor.clone()))
+}
+
+/// Buffer transactions of `actor`'s handler on this thread. Called by the
+/// supervisor before the handler runs.
+pub(crate) fn begin(actor: &str) {
+    OUTBOX.with(|o| *o.borrow_mut() = Some(Outbox { actor: actor.to_string(), sends: Vec::new() }));
+}
+
+/// Make the buffered sends if the handler `succeeded`, else discard them.
+/// Called by the supervisor after the handler.
+pub(crate) fn end(succeeded: bool) {
+    let outbox = match OUTBOX.with(|o| o.borrow_mut().take()) {
+        Some(o) => o,
+        None => return,
+    };
+    if succeeded {
+        flush(&outbox.actor, outbox.sends);
+    } else if !outbox.sends.is_empty() {
+        eprintln!("[Transaction] {}: handler failed, discarding {} sends", outbox.actor, outbox.sends.len());
+        DISCARDED.fetch_add(outbox.sends.len() as u64, Ordering::Relaxed);
+    }
+}
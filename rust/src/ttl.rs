@@ -0,0 +1,165 @@
+//! Time-to-live for messages queued to Rust actors
+//!
+//! A MarketUpdate that sat behind a backlog is worse than none: the
+//! strategy would act on a price that has moved since. With a TTL set for
+//! an actor, optionally per message type, the bridge stamps a deadline on
+//! each message as it enters the mailbox. A message the actor dequeues
+//! after its deadline is dropped before the handler runs and counted:
+//!
+//! ```ignore
+//! ttl::set("rust_strategy", Some(MSG_MARKETUPDATE), Duration::from_millis(50));
+//! ttl::set("rust_strategy", None, Duration::from_secs(5));   // every other type
+//! ttl::expired_count("rust_strategy");
+//! ```
+//!
+//! The deadline travels in the queue_depth envelope, so it covers bridge
+//! traffic (`rust_actor_send`, `rust_actor_post`, spilled messages) but not
+//! Rust-to-Rust sends. Handlers can't see the envelope, so deadlines are
+//! kept here by message address until the message is handled or dropped.
+//! Time is the interop clock. C++ uses `rust_actor_set_ttl()`.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::os::raw::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use actors::Message;
+
+use crate::clock;
+use crate::interop_errors::InteropErrorCode;
+use crate::names::c_str;
+
+#[derive(Default)]
+struct ActorTtl {
+    default: Option<Duration>,
+    by_type: HashMap<i32, Duration>,
+}
+
+// Fast path: no TTL anywhere
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static TOTAL_EXPIRED: AtomicU64 = AtomicU64::new(0);
+
+lazy_static::lazy_static! {
+    static ref TTLS: RwLock<HashMap<String, ActorTtl>> = RwLock::new(HashMap::new());
+    // Address of a queued message -> its deadline
+    static ref DEADLINES: Mutex<HashMap<usize, Instant>> = Mutex::new(HashMap::new());
+    static ref EXPIRED: Mutex<HashMap<String, u64>> = Mutex::new(HashMap::new());
+}
+
+/// Drop messages for `actor` dequeued more than `ttl` after they were
+/// queued: of type `msg_id`, or of every type without a TTL of its own
+/// for None. A zero `ttl` removes that TTL.
+pub fn set(actor: &str, msg_id: Option<i32>, ttl: Duration) {
+    let mut ttls = TTLS.write().unwrap();
+    let entry = ttls.entry(actor.to_string()).or_default();
+    let ttl = (!ttl.is_zero()).then_some(ttl);
+    match (msg_id, ttl) {
+        (Some(id), Some(ttl)) => {
+            entry.by_type.insert(id, ttl);
+        }
+        (Some(id), None) => {
+            entry.by_type.remove(&id);
+        }
+        (None, ttl) => entry.default = ttl,
+    }
+    if entry.default.is_none() && entry.by_type.is_empty() {
+        ttls.remove(actor);
+    }
+    ACTIVE.store(!ttls.is_empty(), Ordering::Release);
+}
+
+/// Remove every TTL of `actor`
+pub fn clear(actor: &str) {
+    let mut ttls = TTLS.write().unwrap();
+    ttls.remove(actor);
+    ACTIVE.store(!ttls.is_empty(), Ordering::Release);
+}
+
+/// TTL applied to messages of type `msg_id` queued for `actor`
+pub fn ttl_of(actor: &str, msg_id: i32) -> Option<Duration> {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return None;
+    }
+    let ttls = TTLS.read().unwrap();
+    let entry = ttls.get(actor)?;
+    entry.by_type.get(&msg_id).copied().or(entry.default)
+}
+
+/// Messages dropped unhandled for `actor` because they expired
+pub fn expired_count(actor: &str) -> u64 {
+    EXPIRED.lock().unwrap().get(actor).copied().unwrap_or(0)
+}
+
+/// Messages dropped unhandled for any actor because they expired
+pub fn total_expired() -> u64 {
+    TOTAL_EXPIRED.load(Ordering::Relaxed)
+}
+
+// Zero-sized messages share one address and can't be told apart
+fn key(msg: &dyn Any) -> Option<usize> {
+    (std::mem::size_of_val(msg) != 0).then_some(msg as *const dyn Any as *const () as usize)
+}
+
+/// Deadline for a message of type `msg_id` queued for `target` now
+pub(crate) fn deadline(target: &str, msg_id: i32) -> Option<Instant> {
+    ttl_of(target, msg_id).map(|ttl| clock::now() + ttl)
+}
+
+/// Remember the deadline of a queued message (the envelope's inner
+/// message, which is what the actor sees). False if it can't be tracked.
+pub(crate) fn stamp(msg: &dyn Any, deadline: Instant) -> bool {
+    match key(msg) {
+        Some(k) => {
+            DEADLINES.lock().unwrap().insert(k, deadline);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Forget the deadline of a message that is being dropped
+pub(crate) fn unstamp(msg: &dyn Any) {
+    if let Some(k) = key(msg) {
+        DEADLINES.lock().unwrap().remove(&k);
+    }
+}
+
+/// Called as `actor` dequeues `msg`: true, and counted, if it has expired
+pub(crate) fn expired(actor: &str, msg: &dyn Message) -> bool {
+    if !ACTIVE.load(Ordering::Acquire) {
+        return false;
+    }
+    let deadline = match key(msg.as_any()).and_then(|k| DEADLINES.lock().unwrap().remove(&k)) {
+        Some(d) => d,
+        None => return false,
+    };
+    if clock::now() <= deadline {
+        return false;
+    }
+    *EXPIRED.lock().unwrap().entry(actor.to_string()).or_insert(0) += 1;
+    TOTAL_EXPIRED.fetch_add(1, Ordering::Relaxed);
+    true
+}
+
+/// Set the TTL of messages of type `msg_type` (0: every type without its
+/// own) queued for `actor_name`. `ttl_ms` 0 removes it. Returns 0, or
+/// InvalidArgument on a bad name.
+#[export_name = c_symbol!("rust_actor_set_ttl")]
+pub extern "C" fn rust_actor_set_ttl(actor_name: *const c_char, msg_type: i32, ttl_ms: u32) -> c_int {
+    let name = match c_str(actor_name) {
+        Some(n) => n,
+        None => return InteropErrorCode::InvalidArgument as c_int,
+    };
+    let msg_id = if msg_type == 0 { None } else { Some(msg_type) };
+    set(name, msg_id, Duration::from_millis(ttl_ms as u64));
+    InteropErrorCode::Ok as c_int
+}
+
+/// Messages dropped unhandled for `actor_name` because they expired (0 on
+/// a bad name)
+#[export_name = c_symbol!("rust_actor_expired_count")]
+pub extern "C" fn rust_actor_expired_count(actor_name: *const c_char) -> u64 {
+    c_str(actor_name).map_or(0, expired_count)
+}
@@ -0,0 +1,98 @@
+//! 16-byte identifier fields (`interop_uuid`)
+//!
+//! Order, trace and session ids that must be unique without a central
+//! counter. A field declared as `interop_uuid` is the 16 bytes of an
+//! RFC 4122 UUID in network byte order, in both languages:
+//!
+//! ```ignore
+//! let order = NewOrder { client_order_id: Uuid::new_v4(), ..Default::default() };
+//! let id: Uuid = "6f1c2a9e-3b1d-4e5f-9a7b-0c2d4e6f8a1b".parse()?;
+//! println!("{}", order.client_order_id);   // hyphenated lowercase hex
+//! ```
+//!
+//! The struct is `#[repr(C)]` and matches the C typedef. C++ gets
+//! `interop::Uuid` in InteropMessages.hpp with `generate()`, `parse()` and
+//! `to_string()`. JSON carries the hyphenated text.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// RFC 4122 UUID bytes; matches the C `interop_uuid`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Uuid {
+    pub bytes: [u8; 16],
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseUuidError;
+
+impl fmt::Display for ParseUuidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected 32 hex digits, optionally hyphenated 8-4-4-4-12")
+    }
+}
+
+impl std::error::Error for ParseUuidError {}
+
+impl Uuid {
+    pub const NIL: Uuid = Uuid { bytes: [0; 16] };
+
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Uuid { bytes }
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; 16] {
+        &self.bytes
+    }
+
+    /// Random (version 4) UUID
+    pub fn new_v4() -> Self {
+        let mut bytes: [u8; 16] = rand::random();
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Uuid { bytes }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.bytes == [0; 16]
+    }
+}
+
+/// Hyphenated lowercase: 6f1c2a9e-3b1d-4e5f-9a7b-0c2d4e6f8a1b
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, b) in self.bytes.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                f.write_str("-")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hyphenated or plain hex, either case
+impl FromStr for Uuid {
+    type Err = ParseUuidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex: Vec<u8> = match s.len() {
+            32 => s.bytes().collect(),
+            36 if [8, 13, 18, 23].iter().all(|&i| s.as_bytes()[i] == b'-') => {
+                s.bytes().filter(|&c| c != b'-').collect()
+            }
+            _ => return Err(ParseUuidError),
+        };
+        if hex.len() != 32 {
+            return Err(ParseUuidError);
+        }
+        let digit = |c: u8| (c as char).to_digit(16).map(|d| d as u8).ok_or(ParseUuidError);
+        let mut bytes = [0u8; 16];
+        for (i, pair) in hex.chunks(2).enumerate() {
+            bytes[i] = digit(pair[0])? << 4 | digit(pair[1])?;
+        }
+        Ok(Uuid { bytes })
+    }
+}
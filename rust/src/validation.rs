@@ -0,0 +1,76 @@
+//! Rules on message fields, checked by the bridge on receipt
+//!
+//! A buggy C++ producer can send a MarketDepth whose `num_levels` is past
+//! the end of its arrays, and the handler indexes out of bounds. Fields
+//! can declare rules in the IDL instead:
+//!
+//! ```c
+//! int32_t num_levels;  /* valid: >= 0, <= len(bid_prices) */
+//! double price;        /* valid: > 0 */
+//! char topic[32];      /* valid: utf8, nonempty */
+//! ```
+//!
+//! ```proto
+//! int32 num_levels = 2 [(interop.valid) = ">= 0, <= len(bid_prices)"];
+//! ```
+//!
+//! - `< <= > >= == != N`: numeric fields; N is a number or `len(array)`,
+//!   the size of an array field of the same message.
+//! - `utf8`: char array fields hold valid UTF-8 up to the first NUL.
+//! - `nonempty`: strings and byte fields are not empty.
+//!
+//! The generator emits `validate()` on each message and
+//! `validate_message()` for `&dyn Message`. Messages from C++
+//! (`rust_actor_send`, `rust_actor_fast_send`, `rust_actor_post`,
+//! `rust_actor_ask`) that break a rule never reach the handler: the send
+//! fails with `INVALID_MESSAGE`, and the rejection is logged, counted in
+//! the health summary, published on `$sys/dead_letters` as
+//! InvalidMessage and sent back to the sender as an `InteropError`.
+//! Messages built in Rust are not checked.
+
+use std::fmt;
+
+use crate::health;
+use crate::interop_error::{self, ERR_INVALID_MESSAGE};
+use crate::interop_errors::InteropErrorCode;
+
+/// Send return code for a message that breaks a rule of its type
+pub const INVALID_MESSAGE: i32 = InteropErrorCode::InvalidMessage as i32;
+
+/// The first rule a message broke
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    pub message_id: i32,
+    pub message: &'static str,
+    pub field: &'static str,
+    /// As declared, e.g. "<= len(bid_prices)"
+    pub rule: &'static str,
+}
+
+impl ValidationError {
+    /// Ok if `ok`, else the error for `message.field` breaking `rule`.
+    /// Called by the generated `validate()`.
+    pub fn check(ok: bool, message_id: i32, message: &'static str, field: &'static str, rule: &'static str) -> Result<(), ValidationError> {
+        if ok {
+            Ok(())
+        } else {
+            Err(ValidationError { message_id, message, field, rule })
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{} breaks \"{}\"", self.message, self.field, self.rule)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Drop a message from `sender` to `target` that failed validation, and
+/// tell the sender with an InteropError
+pub fn reject(target: &str, sender: &str, err: &ValidationError) {
+    eprintln!("[Validation] {} -> {}: {}", if sender.is_empty() { "?" } else { sender }, target, err);
+    health::record_invalid_message(sender, target, err.message_id);
+    interop_error::notify(sender, target, ERR_INVALID_MESSAGE, err.message_id, &err.to_string());
+}
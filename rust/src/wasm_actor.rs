@@ -0,0 +1,195 @@
+//! WASM modules as actors (feature `wasm`)
+//!
+//! A module compiled to wasm32 runs in wasmtime on its own Rust Manager
+//! thread and exchanges interop messages with C++ and Rust actors as
+//! portable frames (see `codec::PortableCodec`), so the guest never depends
+//! on host struct layout.
+//!
+//! Guest exports:
+//! - `memory`
+//! - `interop_alloc(len: i32) -> i32`: buffer for an incoming frame
+//! - `interop_on_message(ptr: i32, len: i32)`: handle one frame
+//! - `interop_on_start()`: optional, called once the actor is started
+//!
+//! Host imports (module `interop`):
+//! - `send(target_ptr, target_len, frame_ptr, frame_len) -> i32`: 0 sent,
+//!   NotFound (-1) for an unknown target, InvalidArgument (-4) for a bad
+//!   frame or name (see `InteropErrorCode`)
+//!
+//! The sandbox has no WASI, so no files, clock or network. Linear memory is
+//! capped at `WASM_MAX_MEMORY` and every call into the guest gets
+//! `WASM_FUEL_PER_CALL` fuel, so a runaway handler traps instead of taking
+//! the thread. A trap panics the actor; its supervisor (`lifecycle`) then
+//! starts a fresh instance.
+
+use std::path::Path;
+
+use actors::{ActorContext, Message};
+use actors::messages::Start;
+use wasmtime::{Caller, Config, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+use crate::codec::{codec, CodecKind};
+use crate::fallback::InteropEnvelope;
+use crate::handle_messages;
+use crate::interop_errors::InteropErrorCode;
+use crate::rust_manager_ffi::get_actor_ref;
+
+/// Linear memory limit per instance
+pub const WASM_MAX_MEMORY: usize = 64 * 1024 * 1024;
+
+/// Fuel (roughly, wasm instructions) per call into the guest
+pub const WASM_FUEL_PER_CALL: u64 = 10_000_000;
+
+/// A compiled module, shared by every instance built from it
+#[derive(Clone)]
+pub struct WasmModule {
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmModule {
+    pub fn load(path: &Path) -> wasmtime::Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::from_file(&engine, path)?;
+        Ok(WasmModule { engine, module })
+    }
+}
+
+struct HostState {
+    name: String,
+    limits: StoreLimits,
+}
+
+// Running instance of the guest
+struct Guest {
+    store: Store<HostState>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    on_message: TypedFunc<(i32, i32), ()>,
+}
+
+impl Guest {
+    fn instantiate(name: &str, module: &WasmModule) -> wasmtime::Result<Self> {
+        let mut linker = Linker::new(&module.engine);
+        linker.func_wrap("interop", "send", host_send)?;
+
+        let limits = StoreLimitsBuilder::new().memory_size(WASM_MAX_MEMORY).build();
+        let mut store = Store::new(&module.engine, HostState { name: name.to_string(), limits });
+        store.limiter(|s| &mut s.limits);
+        store.set_fuel(WASM_FUEL_PER_CALL)?;
+
+        let instance = linker.instantiate(&mut store, &module.module)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| wasmtime::Error::msg("module does not export memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "interop_alloc")?;
+        let on_message = instance.get_typed_func::<(i32, i32), ()>(&mut store, "interop_on_message")?;
+        if let Ok(on_start) = instance.get_typed_func::<(), ()>(&mut store, "interop_on_start") {
+            store.set_fuel(WASM_FUEL_PER_CALL)?;
+            on_start.call(&mut store, ())?;
+        }
+        Ok(Guest { store, memory, alloc, on_message })
+    }
+
+    fn deliver(&mut self, frame: &[u8]) -> wasmtime::Result<()> {
+        let len = i32::try_from(frame.len()).map_err(wasmtime::Error::msg)?;
+        self.store.set_fuel(WASM_FUEL_PER_CALL)?;
+        let ptr = self.alloc.call(&mut self.store, len)?;
+        let offset = usize::try_from(ptr).map_err(wasmtime::Error::msg)?;
+        self.memory.write(&mut self.store, offset, frame)?;
+        self.on_message.call(&mut self.store, (ptr, len))
+    }
+}
+
+// Bounds-checked view of guest memory
+fn guest_slice(data: &[u8], ptr: i32, len: i32) -> Option<&[u8]> {
+    let start = usize::try_from(ptr).ok()?;
+    let end = start.checked_add(usize::try_from(len).ok()?)?;
+    data.get(start..end)
+}
+
+// interop.send imported by the guest
+fn host_send(mut caller: Caller<'_, HostState>, target_ptr: i32, target_len: i32, frame_ptr: i32, frame_len: i32) -> i32 {
+    const BAD: i32 = InteropErrorCode::InvalidArgument as i32;
+    let memory = match caller.get_export("memory").and_then(|e| e.into_memory()) {
+        Some(m) => m,
+        None => return BAD,
+    };
+    let data = memory.data(&caller);
+    let (target, frame) = match (guest_slice(data, target_ptr, target_len), guest_slice(data, frame_ptr, frame_len)) {
+        (Some(t), Some(f)) => (t, f),
+        _ => return BAD,
+    };
+    let target = match std::str::from_utf8(target) {
+        Ok(t) => t,
+        Err(_) => return BAD,
+    };
+    let msg = match codec(CodecKind::Portable).decode(frame) {
+        Ok(m) => m,
+        Err(_) => return BAD,
+    };
+    let name = &caller.data().name;
+    match get_actor_ref(target, name) {
+        Some(actor) => {
+            // Sender ref so the target can reply to the guest
+            actor.send(msg, get_actor_ref(name, target));
+            InteropErrorCode::Ok as i32
+        }
+        None => InteropErrorCode::NotFound as i32,
+    }
+}
+
+/// Rust actor that runs a WASM guest
+pub struct WasmActor {
+    name: String,
+    module: WasmModule,
+    guest: Option<Guest>,
+}
+
+impl WasmActor {
+    pub fn new(name: &str, module: WasmModule) -> Self {
+        WasmActor { name: name.to_string(), module, guest: None }
+    }
+
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        match Guest::instantiate(&self.name, &self.module) {
+            Ok(guest) => self.guest = Some(guest),
+            Err(e) => eprintln!("[WASM] {}: cannot instantiate module, dropping its messages: {}", self.name, e),
+        }
+    }
+
+    /// Encode and hand to the guest. An UnknownMessage (see fallback) has
+    /// no frame and is dropped.
+    fn on_message(&mut self, env: &InteropEnvelope, _ctx: &mut ActorContext) {
+        if env.is_unknown() {
+            return;
+        }
+        self.forward(env.message);
+    }
+
+    fn forward(&mut self, msg: &dyn Message) {
+        let guest = match self.guest.as_mut() {
+            Some(g) => g,
+            None => return,
+        };
+        let frame = match codec(CodecKind::Portable).encode(msg) {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("[WASM] {}: cannot encode message {}: {}", self.name, msg.message_id(), e);
+                return;
+            }
+        };
+        if let Err(e) = guest.deliver(&frame) {
+            // Instance state is unknown after a trap; let the supervisor rebuild it
+            panic!("[WASM] {}: guest trapped on message {}: {}", self.name, msg.message_id(), e);
+        }
+    }
+}
+
+// Register message handlers; every other message goes to the guest
+handle_messages!(WasmActor,
+    Start => on_start,
+    _ => on_message
+);
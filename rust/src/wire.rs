@@ -0,0 +1,190 @@
+//! Byte frames for interop messages leaving the process
+//!
+//! Frame layout: `[msg_id: i32 LE][len: u32 LE][C struct bytes]`.
+//! The body is the `#[repr(C)]` struct from interop_messages.h, so both
+//! ends must share the same ABI (all supported targets are little-endian
+//! x86_64/aarch64). C++ can produce the same frame from `to_c_struct()`.
+//! Messages with `interop_bytes` fields hold pointers and are rejected.
+//! The body is written field by field (see `CBytes`), so padding between
+//! fields goes out as zeros rather than whatever was on the stack.
+
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::c_void;
+
+use actors::Message;
+
+use crate::cpp_actor_if::InteropMessage;
+use crate::decimal::Decimal;
+use crate::interop_errors::InteropErrorCode;
+use crate::interop_messages::{c_struct_is_portable, c_struct_size, message_from_c, message_to_c_bytes};
+use crate::rust_actor_bridge::rust_actor_send;
+use crate::timestamp::Timestamp;
+use crate::uuid::Uuid;
+
+/// Bytes before the struct body
+pub const HEADER_LEN: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WireError {
+    /// Fewer bytes than the header or the declared body length
+    Truncated,
+    /// Message ID not in interop_messages.h
+    UnknownMessage(i32),
+    /// Body length does not match the C struct for this ID
+    SizeMismatch { msg_id: i32, expected: usize, actual: usize },
+    /// C struct holds pointers and cannot be framed
+    NotPortable(i32),
+    /// rust_actor_send() returned an error code
+    NotDelivered(i32),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::Truncated => write!(f, "truncated frame"),
+            WireError::UnknownMessage(id) => write!(f, "unknown message id {}", id),
+            WireError::SizeMismatch { msg_id, expected, actual } => write!(
+                f, "message {} body is {} bytes, expected {}", msg_id, actual, expected
+            ),
+            WireError::NotPortable(id) => write!(f, "message {} holds pointers", id),
+            WireError::NotDelivered(rc) => write!(f, "delivery failed ({})", rc),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+/// A `#[repr(C)]` value that can write its bytes without its padding.
+/// Implemented for the field types and, by the generator, for the C
+/// struct of every portable message.
+pub trait CBytes {
+    /// Write the value's fields at their offsets into `out`, which is at
+    /// least `size_of::<Self>()` bytes; bytes between fields are left as
+    /// they are
+    fn write_c_bytes(&self, out: &mut [u8]);
+}
+
+macro_rules! c_bytes_scalar {
+    ($($t:ty),*) => {$(
+        impl CBytes for $t {
+            fn write_c_bytes(&self, out: &mut [u8]) {
+                out[..std::mem::size_of::<$t>()].copy_from_slice(&self.to_ne_bytes());
+            }
+        }
+    )*};
+}
+
+c_bytes_scalar!(u8, i32, u32, i64, u64, f32, f64);
+
+impl<T: CBytes, const N: usize> CBytes for [T; N] {
+    fn write_c_bytes(&self, out: &mut [u8]) {
+        let size = std::mem::size_of::<T>();
+        for (i, item) in self.iter().enumerate() {
+            item.write_c_bytes(&mut out[i * size..]);
+        }
+    }
+}
+
+impl CBytes for Decimal {
+    fn write_c_bytes(&self, out: &mut [u8]) {
+        self.units.write_c_bytes(&mut out[std::mem::offset_of!(Decimal, units)..]);
+        self.scale.write_c_bytes(&mut out[std::mem::offset_of!(Decimal, scale)..]);
+    }
+}
+
+impl CBytes for Uuid {
+    fn write_c_bytes(&self, out: &mut [u8]) {
+        self.bytes.write_c_bytes(out);
+    }
+}
+
+impl CBytes for Timestamp {
+    fn write_c_bytes(&self, out: &mut [u8]) {
+        self.nanos.write_c_bytes(out);
+    }
+}
+
+/// Bytes of a C struct with every padding byte zero
+pub fn c_bytes<T: CBytes>(c: &T) -> Vec<u8> {
+    let mut out = vec![0u8; std::mem::size_of::<T>()];
+    c.write_c_bytes(&mut out);
+    out
+}
+
+/// Encode a message as a frame. Only portable messages (see
+/// `c_struct_is_portable`) have a `CBytes` C struct.
+pub fn encode<M: InteropMessage>(msg: &M) -> Vec<u8>
+where
+    M::CStruct: CBytes,
+{
+    let body = c_bytes(&msg.to_c_struct());
+    let mut frame = Vec::with_capacity(HEADER_LEN + body.len());
+    frame.extend_from_slice(&M::MSG_ID.to_le_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&body);
+    frame
+}
+
+/// Encode a message known only as `&dyn Message`; None if it is not a
+/// portable interop message
+pub fn encode_dyn(msg: &dyn Message) -> Option<Vec<u8>> {
+    let body = message_to_c_bytes(msg)?;
+    let mut frame = Vec::with_capacity(HEADER_LEN + body.len());
+    frame.extend_from_slice(&msg.message_id().to_le_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&body);
+    Some(frame)
+}
+
+/// Split a frame into message ID and C struct body, validating sizes
+pub fn decode(frame: &[u8]) -> Result<(i32, &[u8]), WireError> {
+    if frame.len() < HEADER_LEN {
+        return Err(WireError::Truncated);
+    }
+    let msg_id = i32::from_le_bytes([frame[0], frame[1], frame[2], frame[3]]);
+    let len = u32::from_le_bytes([frame[4], frame[5], frame[6], frame[7]]) as usize;
+    let body = frame.get(HEADER_LEN..HEADER_LEN + len).ok_or(WireError::Truncated)?;
+
+    let expected = c_struct_size(msg_id).ok_or(WireError::UnknownMessage(msg_id))?;
+    if !c_struct_is_portable(msg_id) {
+        return Err(WireError::NotPortable(msg_id));
+    }
+    if len != expected {
+        return Err(WireError::SizeMismatch { msg_id, expected, actual: len });
+    }
+    Ok((msg_id, body))
+}
+
+// Copy into 8-byte aligned storage before handing it out as a struct
+pub(crate) fn aligned_copy(body: &[u8]) -> Vec<u64> {
+    let mut aligned = vec![0u64; body.len().div_ceil(8)];
+    unsafe {
+        std::ptr::copy_nonoverlapping(body.as_ptr(), aligned.as_mut_ptr() as *mut u8, body.len());
+    }
+    aligned
+}
+
+/// Decode a frame into a boxed message that can go to any ActorRef
+pub fn decode_message(frame: &[u8]) -> Result<Box<dyn Message>, WireError> {
+    let (msg_id, body) = decode(frame)?;
+    let aligned = aligned_copy(body);
+    unsafe { message_from_c(msg_id, aligned.as_ptr() as *const c_void) }.ok_or(WireError::UnknownMessage(msg_id))
+}
+
+/// Decode a frame and deliver it to a Rust actor through the bridge
+pub fn deliver(target: &str, sender: Option<&str>, frame: &[u8]) -> Result<(), WireError> {
+    let (msg_id, body) = decode(frame)?;
+    let aligned = aligned_copy(body);
+
+    let bad_name = |_| WireError::NotDelivered(InteropErrorCode::InvalidName as i32);
+    let target_cstr = CString::new(target).map_err(bad_name)?;
+    let sender_cstr = match sender {
+        Some(s) => Some(CString::new(s).map_err(bad_name)?),
+        None => None,
+    };
+    let sender_ptr = sender_cstr.as_ref().map_or(std::ptr::null(), |s| s.as_ptr());
+
+    let rc = rust_actor_send(target_cstr.as_ptr(), sender_ptr, msg_id, aligned.as_ptr() as *const c_void);
+    if rc == 0 { Ok(()) } else { Err(WireError::NotDelivered(rc)) }
+}
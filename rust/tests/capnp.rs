@@ -0,0 +1,75 @@
+//! The Cap'n Proto codec writes what capnp itself writes and reads what
+//! other implementations and schema versions write
+//!
+//! Needs `--features capnp`.
+
+#![cfg(feature = "capnp")]
+
+use actors_interop::codec::{codec, CodecKind};
+use actors_interop::interop_messages::*;
+use actors_interop::interop_string::InteropString;
+
+fn words(words: &[u64]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+#[test]
+fn encodes_as_capnp_does() {
+    // Segment table, root struct pointer (1 data word), data
+    let expected = words(&[0x0000_0002_0000_0000, 0x0000_0001_0000_0000, 7]);
+    assert_eq!(Ping { count: 7 }.encode_capnp(), expected);
+
+    // found @2 :Bool takes the free upper half of requestId's word
+    let response = DataResponse { request_id: 5, value: 2.5, found: true };
+    let expected = words(&[0x0000_0003_0000_0000, 0x0000_0002_0000_0000, 0x0000_0001_0000_0005, 2.5f64.to_bits()]);
+    assert_eq!(response.encode_capnp(), expected);
+}
+
+#[test]
+fn fields_may_be_added_at_the_end() {
+    // DataRequest is Ping's count with a field appended: each reads the other
+    let request = DataRequest { request_id: 9, symbol: "AAPL".into() };
+    assert_eq!(Ping::decode_capnp(&request.encode_capnp()), Some(Ping { count: 9 }));
+    let older = Ping { count: 9 }.encode_capnp();
+    assert_eq!(DataRequest::decode_capnp(&older), Some(DataRequest { request_id: 9, symbol: "".into() }));
+}
+
+#[test]
+fn reads_far_pointers() {
+    // Root in segment 0 is a far pointer to a landing pad in segment 1
+    let two_segments = words(&[
+        0x0000_0001_0000_0001, // 2 segments: 1 word, 2 words
+        0x0000_0000_0000_0002,
+        0x0000_0001_0000_0002, // far pointer to segment 1, word 0
+        0x0000_0001_0000_0000, // landing pad: struct, 1 data word
+        42,
+    ]);
+    assert_eq!(Ping::decode_capnp(&two_segments), Some(Ping { count: 42 }));
+}
+
+#[test]
+fn views_read_in_place() {
+    let depth = MarketDepth {
+        symbol: InteropString::from("MSFT"),
+        num_levels: 2,
+        bid_prices: [101.5, 101.25, 0.0, 0.0, 0.0],
+        ..Default::default()
+    };
+    let body = depth.encode_capnp();
+    let view = MarketDepthCapnpView::new(&body).unwrap();
+    assert_eq!(view.symbol(), Some("MSFT"));
+    assert_eq!(view.num_levels(), 2);
+    assert_eq!(view.bid_prices().and_then(|p| p.get(1)), Some(101.25));
+}
+
+#[test]
+fn codec_frames_and_rejects_garbage() {
+    let c = codec(CodecKind::CapnProto);
+    let frame = c.encode(&Pong { count: 3 }).unwrap();
+    let msg = c.decode(&frame).unwrap();
+    assert_eq!(msg.as_any().downcast_ref::<Pong>(), Some(&Pong { count: 3 }));
+
+    let mut bad = frame.clone();
+    bad[12] = 0xff; // segment size past the end
+    assert!(c.decode(&bad).is_err());
+}
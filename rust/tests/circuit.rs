@@ -0,0 +1,87 @@
+//! A sender -> C++ target pair whose sends keep failing is cut off, then
+//! probed until it works again
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::time::Duration;
+
+use actors_interop::circuit::{self, BreakerPolicy, CircuitState};
+use actors_interop::clock;
+use actors_interop::interop_errors::InteropErrorCode;
+use actors_interop::timestamp::Timestamp;
+
+const NOT_FOUND: i32 = InteropErrorCode::NotFound as i32;
+const OPEN_FOR: Duration = Duration::from_millis(40);
+
+fn fail(times: usize) {
+    for _ in 0..times {
+        assert!(circuit::admit("cb_strategy", "cpp_gateway"));
+        circuit::record("cb_strategy", "cpp_gateway", NOT_FOUND);
+    }
+}
+
+fn state() -> CircuitState {
+    circuit::state("cb_strategy", "cpp_gateway")
+}
+
+#[test]
+fn opens_on_consecutive_failures_and_closes_after_a_probe() {
+    // Stands still, so an open circuit only times out when advanced
+    let sim = clock::simulate(Timestamp::from_secs(1_704_187_800), 0.0);
+    circuit::set_policy(Some(BreakerPolicy::new(3, OPEN_FOR)));
+
+    // A bad message is the sender's fault, not the target's
+    let senders = [
+        InteropErrorCode::UnknownMessage,
+        InteropErrorCode::WrongType,
+        InteropErrorCode::InvalidMessage,
+        InteropErrorCode::TooLarge,
+        InteropErrorCode::InvalidArgument,
+    ];
+    for code in senders {
+        circuit::record("cb_strategy", "cpp_gateway", code as i32);
+    }
+    assert_eq!(state(), CircuitState::Closed);
+
+    // Only consecutive failures count
+    fail(2);
+    circuit::record("cb_strategy", "cpp_gateway", 0);
+    fail(2);
+    assert_eq!(state(), CircuitState::Closed);
+    fail(1);
+    assert_eq!(state(), CircuitState::Open);
+
+    // Open: fail fast, for this pair only
+    assert!(!circuit::admit("cb_strategy", "cpp_gateway"));
+    assert!(!circuit::available("cb_strategy", "cpp_gateway"));
+    assert!(circuit::admit("cb_other", "cpp_gateway"));
+    assert_eq!(circuit::state("cb_other", "cpp_gateway"), CircuitState::Closed);
+
+    // One probe once the time is up; the rest still fail fast
+    sim.advance(OPEN_FOR - Duration::from_millis(1));
+    assert!(!circuit::available("cb_strategy", "cpp_gateway"));
+    sim.advance(Duration::from_millis(1));
+    assert!(circuit::available("cb_strategy", "cpp_gateway"));
+    assert!(circuit::admit("cb_strategy", "cpp_gateway"));
+    assert_eq!(state(), CircuitState::HalfOpen);
+    assert!(!circuit::admit("cb_strategy", "cpp_gateway"));
+
+    // A failed probe opens it again, a good one closes it
+    circuit::record("cb_strategy", "cpp_gateway", NOT_FOUND);
+    assert_eq!(state(), CircuitState::Open);
+    sim.advance(OPEN_FOR);
+    assert!(circuit::admit("cb_strategy", "cpp_gateway"));
+    circuit::record("cb_strategy", "cpp_gateway", 0);
+    assert_eq!(state(), CircuitState::Closed);
+    assert!(circuit::admit("cb_strategy", "cpp_gateway"));
+
+    // Without a policy nothing is cut off
+    fail(3);
+    circuit::set_policy(None);
+    assert_eq!(state(), CircuitState::Closed);
+    fail(10);
+    assert_eq!(state(), CircuitState::Closed);
+    clock::use_system_clock();
+}
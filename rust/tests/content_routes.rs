@@ -0,0 +1,115 @@
+//! Sends to a content route reach the target of the first rule the
+//! message matches, fall through to a `*` rule, and are dead letters when
+//! no rule matches
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, ThreadConfig};
+use actors_interop::content_routes::{self, RouteError};
+use actors_interop::interop_messages::MarketUpdate;
+use actors_interop::rust_manager_ffi::{
+    create_rust_manager, get_actor_ref, register_with, rust_manager_end, rust_manager_init,
+};
+use actors_interop::send_error::{SendError, TrySend};
+use actors_interop::sub_filter::FilterError;
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+struct Recorder {
+    seen: mpsc::Sender<i32>,
+}
+
+impl Recorder {
+    fn on_update(&mut self, msg: &MarketUpdate, _ctx: &mut ActorContext) {
+        self.seen.send(msg.volume).unwrap();
+    }
+}
+
+handle_messages!(Recorder, MarketUpdate => on_update);
+
+fn update(symbol: &str, volume: i32) -> Box<MarketUpdate> {
+    Box::new(MarketUpdate { symbol: symbol.into(), price: 1.0, timestamp: Timestamp::default(), volume, seq: 0 })
+}
+
+fn recorder(name: &str) -> mpsc::Receiver<i32> {
+    let (tx, rx) = mpsc::channel();
+    assert!(register_with(name, move |_| Box::new(Recorder { seen: tx.clone() }), ThreadConfig::default()));
+    rx
+}
+
+fn take(rx: &mpsc::Receiver<i32>, n: usize) -> Vec<i32> {
+    (0..n).map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap()).collect()
+}
+
+fn hits(route: &str) -> Vec<(String, u64)> {
+    content_routes::rules().into_iter().filter(|r| r.route == route).map(|r| (r.target, r.hits)).collect()
+}
+
+#[test]
+fn messages_go_to_the_first_matching_rule_or_fall_through() {
+    create_rust_manager();
+    let legacy = recorder("cr_legacy");
+    let engine = recorder("cr_engine");
+    rust_manager_init();
+
+    let conf = "# migrated symbols\n\
+                cr_orders: symbol < N -> cr_legacy\n\
+                cr_orders: symbol >= N -> cr_engine\n\
+                \n\
+                cr_large: volume > 1000 && symbol == AAPL -> cr_engine\n\
+                cr_large: volume > 1000 -> cr_legacy\n";
+    assert_eq!(content_routes::load_str(conf), Ok(4));
+    assert!(content_routes::is_route("cr_orders"));
+
+    // Match: by symbol, the first rule that holds wins
+    let orders = get_actor_ref("cr_orders", "cr_gateway").unwrap();
+    assert_eq!(orders.try_send(update("AAPL", 1), None), Ok(()));
+    assert_eq!(orders.try_send(update("TSLA", 2), None), Ok(()));
+    assert_eq!(orders.try_send(update("MSFT", 3), None), Ok(()));
+    let large = get_actor_ref("cr_large", "cr_gateway").unwrap();
+    assert_eq!(large.try_send(update("AAPL", 5000), None), Ok(()));
+    assert_eq!(large.try_send(update("TSLA", 6000), None), Ok(()));
+
+    // No match: a dead letter, and the send says so
+    let unmatched = content_routes::unmatched();
+    assert_eq!(large.try_send(update("AAPL", 10), None), Err(SendError::ActorNotFound));
+    assert_eq!(content_routes::unmatched(), unmatched + 1);
+
+    // Fallback: a `*` rule added last takes what the others do not
+    content_routes::add_rule("cr_large", "*", "cr_engine").unwrap();
+    assert_eq!(large.try_send(update("AAPL", 20), None), Ok(()));
+    assert_eq!(content_routes::unmatched(), unmatched + 1);
+
+    assert_eq!(take(&legacy, 3), vec![1, 3, 6000]);
+    assert_eq!(take(&engine, 3), vec![2, 5000, 20]);
+    assert_eq!(hits("cr_orders"), vec![("cr_legacy".into(), 2), ("cr_engine".into(), 1)]);
+    assert_eq!(hits("cr_large"), vec![("cr_engine".into(), 1), ("cr_legacy".into(), 1), ("cr_engine".into(), 1)]);
+
+    // A rule to an actor that does not exist is not found either
+    content_routes::set_rules("cr_orders", &[("*", "cr_missing")]).unwrap();
+    assert_eq!(orders.try_send(update("AAPL", 30), None), Err(SendError::ActorNotFound));
+
+    assert!(content_routes::remove("cr_orders"));
+    assert!(content_routes::remove("cr_large"));
+    assert!(!content_routes::is_route("cr_orders"));
+    rust_manager_end();
+    assert!(shutdown_all());
+}
+
+#[test]
+fn bad_rules_are_refused_and_set_nothing() {
+    assert_eq!(content_routes::load_str("cr_bad: symbol == AAPL"), Err(RouteError::Parse(1)));
+    assert_eq!(content_routes::load_str("cr_bad: * -> cr_engine\ncr_bad * -> x"), Err(RouteError::Parse(2)));
+    assert_eq!(content_routes::load_str("cr_bad: * -> cr_bad"), Err(RouteError::BadName));
+    assert_eq!(content_routes::load_str("cr_bad: * -> "), Err(RouteError::BadName));
+    assert!(matches!(
+        content_routes::load_str("cr_bad: volume > -> cr_engine"),
+        Err(RouteError::Filter(FilterError::Parse { .. }))
+    ));
+    assert!(!content_routes::is_route("cr_bad"));
+}
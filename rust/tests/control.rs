@@ -0,0 +1,39 @@
+//! The control socket is private from the start and refuses runaway lines
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(all(unix, feature = "standalone"))]
+
+use std::io::{Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixStream;
+
+use actors_interop::control::{self, MAX_LINE};
+
+fn command(path: &std::path::Path, line: &[u8]) -> String {
+    let mut stream = UnixStream::connect(path).unwrap();
+    stream.write_all(line).unwrap();
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply).unwrap();
+    reply
+}
+
+#[test]
+fn socket_is_owner_only_and_lines_are_capped() {
+    let dir = std::env::temp_dir().join(format!("interop-control-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("ctl.sock");
+
+    control::listen(&path).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    // Only the socket is left in the directory
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+    assert_eq!(command(&path, b"actors\n"), "");
+    let long = vec![b'x'; MAX_LINE + 1];
+    assert!(command(&path, &long).starts_with("error: command longer than"));
+
+    control::close();
+    assert!(!path.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,151 @@
+//! Decimal arithmetic aligns scales, refuses what does not fit, rounds
+//! negatives half away from zero, and crosses the bridge exactly
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::cmp::Ordering;
+use std::ffi::{c_void, CString};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, ThreadConfig};
+use actors_interop::decimal::{Decimal, ParseDecimalError};
+use actors_interop::interop_messages::{BondQuote, MSG_BONDQUOTE};
+use actors_interop::rust_actor_bridge::{rust_actor_init, rust_actor_send};
+use actors_interop::rust_manager_ffi::{
+    create_rust_manager, get_rust_manager, register_with, rust_manager_end, rust_manager_init,
+};
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+fn d(text: &str) -> Decimal {
+    text.parse().unwrap()
+}
+
+#[test]
+fn mixed_scales_are_aligned() {
+    // 1.5 + 0.25 at the larger scale
+    assert_eq!(d("1.5").checked_add(d("0.25")), Some(Decimal::new(175, 2)));
+    assert_eq!(d("0.25").checked_sub(d("1.5")), Some(Decimal::new(-125, 2)));
+    assert_eq!(d("1.5").checked_mul(d("0.25")), Some(Decimal::new(375, 3)));
+
+    // Equality is by mantissa and scale, cmp_value by amount
+    assert_ne!(d("1.50"), d("1.5"));
+    assert_eq!(d("1.50").cmp_value(&d("1.5")), Some(Ordering::Equal));
+    assert_eq!(d("1.49").cmp_value(&d("1.5")), Some(Ordering::Less));
+    assert_eq!(d("1.5").rescale(4), Some(Decimal::new(15000, 4)));
+
+    // A scale outside 0..=MAX_SCALE is refused, not guessed at
+    let bad = Decimal::new(15, Decimal::MAX_SCALE + 1);
+    assert!(!bad.is_valid());
+    assert_eq!(bad.checked_add(d("1")), None);
+    assert_eq!(d("1").checked_mul(Decimal::new(1, -1)), None);
+    assert_eq!(bad.cmp_value(&d("1")), None);
+    assert_eq!(d("1.5").rescale(Decimal::MAX_SCALE + 1), None);
+    assert_eq!(Decimal::from_f64(1.5, -1), None);
+}
+
+#[test]
+fn results_that_do_not_fit_are_none() {
+    let max = Decimal::from_int(i64::MAX);
+    let min = Decimal::from_int(i64::MIN);
+    assert_eq!(max.checked_add(d("1")), None);
+    assert_eq!(min.checked_sub(d("1")), None);
+    assert_eq!(max.checked_mul(d("2")), None);
+    assert_eq!(max.mul_rounded(d("2"), 0), None);
+    assert_eq!(min.checked_mul(d("-1")), None);
+
+    // A larger scale leaves less room: 10 has no i64 mantissa at scale 18
+    assert_eq!(d("10").checked_add(Decimal::new(1, Decimal::MAX_SCALE)), None);
+    assert_eq!(d("10").rescale(Decimal::MAX_SCALE), None);
+    assert_eq!(d("0.9").rescale(Decimal::MAX_SCALE), Some(Decimal::new(900_000_000_000_000_000, 18)));
+
+    // The scale of a product is the sum of both, and must fit too
+    assert_eq!(Decimal::new(1, 10).checked_mul(Decimal::new(1, 9)), None);
+    assert_eq!(Decimal::new(1, 10).mul_rounded(Decimal::new(1, 9), 18), Some(Decimal::new(0, 18)));
+
+    assert_eq!(Decimal::from_f64(1e19, 0), None);
+    assert_eq!(Decimal::from_f64(f64::NAN, 2), None);
+    assert_eq!(Decimal::from_f64(f64::INFINITY, 2), None);
+    assert_eq!("9223372036854775808".parse::<Decimal>(), Err(ParseDecimalError::OutOfRange));
+    assert_eq!("-9223372036854775808".parse::<Decimal>(), Ok(min));
+    assert_eq!("0.0000000000000000001".parse::<Decimal>(), Err(ParseDecimalError::OutOfRange));
+    assert_eq!("".parse::<Decimal>(), Err(ParseDecimalError::Empty));
+    assert_eq!("1.2.3".parse::<Decimal>(), Err(ParseDecimalError::InvalidDigit));
+}
+
+#[test]
+fn negatives_round_half_away_from_zero() {
+    assert_eq!(d("-1.25").rescale(1), Some(d("-1.3")));
+    assert_eq!(d("-1.24").rescale(1), Some(d("-1.2")));
+    assert_eq!(d("-1.26").rescale(1), Some(d("-1.3")));
+    assert_eq!(d("-0.05").rescale(1), Some(d("-0.1")));
+    assert_eq!(d("-0.04").rescale(1), Some(d("0.0")));
+    assert_eq!(d("1.25").rescale(1), Some(d("1.3")));
+
+    // -1.5 * 1.5 = -2.25
+    assert_eq!(d("-1.5").mul_rounded(d("1.5"), 1), Some(d("-2.3")));
+    assert_eq!(d("-1.5").mul_rounded(d("-1.5"), 1), Some(d("2.3")));
+    assert_eq!(Decimal::from_f64(-2.5, 0), Some(d("-3")));
+    assert_eq!(Decimal::from_f64(-0.125, 2), Some(d("-0.13")));
+
+    assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+    assert_eq!(d("-12.340").to_string(), "-12.340");
+    assert_eq!(d("-0.005"), Decimal::new(-5, 3));
+}
+
+struct Recorder {
+    seen: mpsc::Sender<BondQuote>,
+}
+
+impl Recorder {
+    fn on_quote(&mut self, msg: &BondQuote, _ctx: &mut ActorContext) {
+        self.seen.send(msg.clone()).unwrap();
+    }
+}
+
+handle_messages!(Recorder, BondQuote => on_quote);
+
+#[test]
+fn decimals_cross_the_bridge_exactly() {
+    create_rust_manager();
+    let (tx, rx) = mpsc::channel();
+    assert!(register_with("dec_book", move |_| Box::new(Recorder { seen: tx.clone() }), ThreadConfig::default()));
+    rust_actor_init(get_rust_manager());
+    rust_manager_init();
+
+    let quotes = [
+        BondQuote {
+            isin: "US912828U816".into(),
+            bid: d("99.875"),
+            ask: d("99.90"),
+            yield_rate: d("-0.0125"),
+            timestamp: Timestamp::from_millis(1_704_187_800_000),
+        },
+        BondQuote {
+            isin: "XS0000000009".into(),
+            bid: Decimal::new(i64::MIN, Decimal::MAX_SCALE),
+            ask: Decimal::new(i64::MAX, 0),
+            yield_rate: Decimal::new(-1, Decimal::MAX_SCALE),
+            timestamp: Timestamp::default(),
+        },
+    ];
+
+    let target = CString::new("dec_book").unwrap();
+    let sender = CString::new("cpp_pricer").unwrap();
+    for quote in &quotes {
+        let c_msg = quote.to_c_struct();
+        assert_eq!(BondQuote::from_c_struct(&c_msg), *quote);
+        let rc = rust_actor_send(target.as_ptr(), sender.as_ptr(), MSG_BONDQUOTE, &c_msg as *const _ as *const c_void);
+        assert_eq!(rc, 0);
+    }
+    for quote in &quotes {
+        // Mantissa and scale both survive, so 99.90 is not 99.9
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), *quote);
+    }
+
+    rust_manager_end();
+    assert!(shutdown_all());
+}
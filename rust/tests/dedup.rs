@@ -0,0 +1,91 @@
+//! Repeats of a message bridged to an actor with a dedup window are
+//! dropped before its mailbox
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::ffi::{c_void, CString};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, Message, ThreadConfig};
+use actors_interop::clock;
+use actors_interop::dedup::{self, DedupConfig};
+use actors_interop::interop_messages::{Ping, MSG_PING};
+use actors_interop::rust_actor_bridge::{rust_actor_init, rust_actor_send};
+use actors_interop::rust_manager_ffi::{
+    create_rust_manager, get_rust_manager, register_with, rust_manager_end, rust_manager_init,
+};
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+// Ends each batch of sends, so everything before it has been handled
+const DONE: i32 = -1;
+
+struct Recorder {
+    seen: mpsc::Sender<i32>,
+}
+
+impl Recorder {
+    fn on_ping(&mut self, msg: &Ping, _ctx: &mut ActorContext) {
+        self.seen.send(msg.count).unwrap();
+    }
+}
+
+handle_messages!(Recorder, Ping => on_ping);
+
+/// Key for by_key windows: Pings in the same tens repeat each other
+fn tens(msg: &dyn Message) -> Option<u64> {
+    msg.as_any().downcast_ref::<Ping>().filter(|p| p.count != DONE).map(|p| p.count as u64 / 10)
+}
+
+/// Send Pings with `counts` as C++ would, then take what was handled
+fn bridge(counts: &[i32], rx: &mpsc::Receiver<i32>) -> Vec<i32> {
+    let target = CString::new("dd_oms").unwrap();
+    let sender = CString::new("cpp_feed").unwrap();
+    for count in counts.iter().chain([DONE].iter()) {
+        let c_msg = Ping { count: *count }.to_c_struct();
+        let rc = rust_actor_send(target.as_ptr(), sender.as_ptr(), MSG_PING, &c_msg as *const _ as *const c_void);
+        assert_eq!(rc, 0, "a dropped duplicate still reports success");
+    }
+    let mut handled = Vec::new();
+    loop {
+        match rx.recv_timeout(Duration::from_secs(5)).unwrap() {
+            DONE => return handled,
+            count => handled.push(count),
+        }
+    }
+}
+
+#[test]
+fn repeats_inside_the_window_are_dropped() {
+    create_rust_manager();
+    let (tx, rx) = mpsc::channel();
+    assert!(register_with("dd_oms", move |_| Box::new(Recorder { seen: tx.clone() }), ThreadConfig::default()));
+    rust_actor_init(get_rust_manager());
+    rust_manager_init();
+    // Stands still, so windows only run out when advanced
+    let sim = clock::simulate(Timestamp::from_secs(1_704_187_800), 0.0);
+
+    // Equal messages repeat until the window is over
+    dedup::enable("dd_oms", DedupConfig::fingerprint(Duration::from_millis(200), 100));
+    let dropped = dedup::duplicates();
+    assert_eq!(bridge(&[1, 1, 2, 1], &rx), vec![1, 2]);
+    assert_eq!(dedup::duplicates() - dropped, 2);
+    sim.advance(Duration::from_millis(199));
+    assert_eq!(bridge(&[1], &rx), Vec::<i32>::new());
+    sim.advance(Duration::from_millis(1));
+    assert_eq!(bridge(&[1], &rx), vec![1]);
+
+    // By key, remembering only the 2 newest keys
+    dedup::enable("dd_oms", DedupConfig::by_key(Duration::from_secs(60), 2, tens));
+    assert_eq!(bridge(&[11, 12, 21, 31, 13], &rx), vec![11, 21, 31, 13]);
+
+    dedup::disable("dd_oms");
+    assert_eq!(bridge(&[13, 13], &rx), vec![13, 13]);
+
+    clock::use_system_clock();
+    rust_manager_end();
+    assert!(shutdown_all());
+}
@@ -0,0 +1,95 @@
+//! Failures programmed on mock C++ actors go through retry, the circuit
+//! breaker and dead letters as real ones would
+//!
+//! Needs `--features sim,standalone` since no C++ side is linked.
+
+#![cfg(all(feature = "sim", feature = "standalone"))]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actors_interop::circuit::{self, BreakerPolicy, CircuitState};
+use actors_interop::clock;
+use actors_interop::dead_letters::{self, Stage};
+use actors_interop::interop_errors::InteropErrorCode;
+use actors_interop::interop_messages::{Ping, MSG_PING};
+use actors_interop::retry::{self, RetryPolicy};
+use actors_interop::rust_manager_ffi::{create_rust_manager, get_actor_ref, rust_manager_end, rust_manager_init};
+use actors_interop::send_error::{SendError, TrySend};
+use actors_interop::sim::{self, Failure};
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+const NOT_FOUND: i32 = InteropErrorCode::NotFound as i32;
+
+fn ping(count: i32) -> Box<Ping> {
+    Box::new(Ping { count })
+}
+
+#[test]
+fn programmed_failures_reach_retry_circuit_and_dead_letters() {
+    create_rust_manager();
+    sim::start(1, 0);
+    sim::add_cpp_actor("cpp_gateway", |_, _| {});
+    sim::add_cpp_actor("cpp_risk", |_, _| {});
+    rust_manager_init();
+    let gateway = get_actor_ref("cpp_gateway", "fi_strategy").unwrap();
+    let risk = get_actor_ref("cpp_risk", "fi_strategy").unwrap();
+
+    // A transient failure is retried and the second attempt gets through
+    retry::set_cpp_retry_policy(RetryPolicy::new(3, Duration::from_millis(1)));
+    sim::fail("cpp_gateway", Failure::nth(1, NOT_FOUND));
+    assert_eq!(gateway.try_send(ping(1), None), Ok(()));
+    assert_eq!(sim::sends_to("cpp_gateway"), 2);
+
+    // A full mailbox is retried too; when it stays full, the failure
+    // callback and the dead letters both see the last attempt
+    let failed = Arc::new(Mutex::new(Vec::new()));
+    let seen = failed.clone();
+    retry::set_cpp_failure_callback(move |target, msg_id, rc| {
+        seen.lock().unwrap().push((target.to_string(), msg_id, rc));
+    });
+    sim::clear_failures();
+    sim::fail("cpp_gateway", Failure::from(1, sim::MAILBOX_FULL));
+    assert_eq!(gateway.try_send(ping(2), None), Err(SendError::Backpressure(sim::MAILBOX_FULL)));
+    assert_eq!(sim::sends_to("cpp_gateway"), 5);
+    assert_eq!(*failed.lock().unwrap(), vec![("cpp_gateway".to_string(), MSG_PING, sim::MAILBOX_FULL)]);
+    let letter = dead_letters::recent(1).pop().unwrap();
+    assert_eq!(letter.stage, Stage::Transport);
+    assert_eq!((letter.sender.as_str(), letter.target.as_str()), ("fi_strategy", "cpp_gateway"));
+    assert_eq!((letter.msg_id, letter.code), (MSG_PING, sim::MAILBOX_FULL));
+    sim::clear_failures();
+
+    // An actor that vanished opens the circuit after two failed sends,
+    // then fails fast without a send until the probe. Without retries
+    // nothing sleeps, so the clock can stand still until advanced
+    retry::set_cpp_retry_policy(RetryPolicy::none());
+    let sim_clock = clock::simulate(Timestamp::from_secs(1_704_187_800), 0.0);
+    circuit::set_policy(Some(BreakerPolicy::new(2, Duration::from_millis(50))));
+    sim::vanish_after("cpp_risk", 1);
+    assert_eq!(risk.try_send(ping(3), None), Ok(()));
+    for count in 4..6 {
+        assert_eq!(risk.try_send(ping(count), None), Err(SendError::ActorNotFound));
+    }
+    assert_eq!(circuit::state("fi_strategy", "cpp_risk"), CircuitState::Open);
+    let sends = sim::sends_to("cpp_risk");
+    assert_eq!(risk.try_send(ping(6), None), Err(SendError::TransportError(circuit::CIRCUIT_OPEN)));
+    assert_eq!(sim::sends_to("cpp_risk"), sends);
+    assert_eq!(failed.lock().unwrap().last().unwrap().2, circuit::CIRCUIT_OPEN);
+    // Other pairs are not cut off
+    assert_eq!(gateway.try_send(ping(7), None), Ok(()));
+
+    // The probe is sent, fails and opens the circuit again
+    sim_clock.advance(Duration::from_millis(50));
+    assert_eq!(risk.try_send(ping(8), None), Err(SendError::ActorNotFound));
+    assert_eq!(sim::sends_to("cpp_risk"), sends + 1);
+    assert_eq!(circuit::state("fi_strategy", "cpp_risk"), CircuitState::Open);
+    assert_eq!(dead_letters::recent(1)[0].stage, Stage::Lookup);
+
+    circuit::set_policy(None);
+    retry::clear_cpp_failure_callback();
+    clock::use_system_clock();
+    rust_manager_end();
+    sim::stop();
+    assert!(shutdown_all());
+}
@@ -0,0 +1,99 @@
+//! A registered HashRouter is a send target that keeps each key on one worker
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::collections::HashMap;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, ThreadConfig};
+use actors_interop::hash_router::{self, symbol_key, HashRouter};
+use actors_interop::interop_messages::MarketUpdate;
+use actors_interop::rust_manager_ffi::{
+    create_rust_manager, get_actor_ref, register_with, rust_manager_end, rust_manager_init,
+};
+use actors_interop::send_error::{SendError, TrySend};
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+const SYMBOLS: [&str; 6] = ["AAPL", "MSFT", "GOOG", "AMZN", "TSLA", "NVDA"];
+
+// Reports (worker, symbol) for each update it handles
+struct Worker {
+    name: &'static str,
+    seen: mpsc::Sender<(&'static str, String)>,
+}
+
+impl Worker {
+    fn on_update(&mut self, msg: &MarketUpdate, _ctx: &mut ActorContext) {
+        self.seen.send((self.name, msg.symbol.as_str().to_string())).unwrap();
+    }
+}
+
+handle_messages!(Worker, MarketUpdate => on_update);
+
+fn update(symbol: &str, seq: i64) -> Box<MarketUpdate> {
+    Box::new(MarketUpdate { symbol: symbol.into(), price: 100.0, timestamp: Timestamp::default(), volume: 1, seq })
+}
+
+#[test]
+fn routes_by_key_through_its_name() {
+    create_rust_manager();
+    let (tx, rx) = mpsc::channel();
+    for name in ["hr_worker_0", "hr_worker_1", "hr_worker_2"] {
+        let seen = tx.clone();
+        let factory = move |_| Box::new(Worker { name, seen: seen.clone() }) as Box<dyn actors::Actor>;
+        assert!(register_with(name, factory, ThreadConfig::default()));
+    }
+    rust_manager_init();
+
+    let mut router = HashRouter::new(symbol_key);
+    for name in ["hr_worker_0", "hr_worker_1", "hr_worker_2"] {
+        router.add_member(name, get_actor_ref(name, "").unwrap());
+    }
+    let owners: HashMap<&str, String> =
+        SYMBOLS.iter().map(|s| (*s, router.member_for(s).unwrap().to_string())).collect();
+    assert!(router.register("hr_quotes"));
+
+    // Through the name, as any other actor would send
+    let quotes = get_actor_ref("hr_quotes", "").unwrap();
+    for seq in 0..5 {
+        for symbol in SYMBOLS {
+            quotes.try_send(update(symbol, seq), None).unwrap();
+        }
+    }
+    for _ in 0..5 * SYMBOLS.len() {
+        let (worker, symbol) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(worker, owners[symbol.as_str()], "{} went to {}", symbol, worker);
+    }
+
+    // Without members nothing can take the message
+    assert!(HashRouter::new(symbol_key).register("hr_quotes"));
+    assert_eq!(quotes.try_send(update("AAPL", 5), None), Err(SendError::ActorNotFound));
+
+    assert!(hash_router::unregister("hr_quotes"));
+    assert!(get_actor_ref("hr_quotes", "").is_none());
+    rust_manager_end();
+    assert!(shutdown_all());
+}
+
+#[test]
+fn removing_a_member_moves_only_its_keys() {
+    let mut router = HashRouter::new(symbol_key);
+    for name in ["a", "b", "c", "d"] {
+        router.add_member(name, actors::ActorRef::Cpp(actors::CppActorRef::new(name, "", |_, _, _| 0)));
+    }
+    let keys: Vec<String> = (0..400).map(|i| format!("S{}", i)).collect();
+    let before: Vec<String> = keys.iter().map(|k| router.member_for(k).unwrap().to_string()).collect();
+    assert!(router.remove_member("c"));
+    for (key, owner) in keys.iter().zip(&before) {
+        let now = router.member_for(key).unwrap();
+        if owner != "c" {
+            assert_eq!(now, owner, "{} moved off a remaining member", key);
+        } else {
+            assert_ne!(now, "c");
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! Each actor's health status follows its own lifecycle
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::time::{Duration, Instant};
+
+use actors::messages::Start;
+use actors::{handle_messages, ActorContext, ThreadConfig};
+use actors_interop::health::actor_status;
+use actors_interop::rust_manager_ffi::{create_rust_manager, register_with, rust_manager_end, rust_manager_init};
+use actors_interop::teardown::shutdown_all;
+
+struct Steady;
+
+impl Steady {
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {}
+}
+
+handle_messages!(Steady, Start => on_start);
+
+// Panics in Start, after the restart too
+struct Broken;
+
+impl Broken {
+    fn on_start(&mut self, _msg: &Start, _ctx: &mut ActorContext) {
+        panic!("no config");
+    }
+}
+
+handle_messages!(Broken, Start => on_start);
+
+fn wait_for(name: &str, status: &str) {
+    let begin = Instant::now();
+    while actor_status(name) != status {
+        assert!(begin.elapsed() < Duration::from_secs(5), "{} is {}, not {}", name, actor_status(name), status);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn status_is_per_actor() {
+    create_rust_manager();
+    assert!(register_with("health_steady", |_| Box::new(Steady), ThreadConfig::default()));
+    assert!(register_with("health_broken", |_| Box::new(Broken), ThreadConfig::default()));
+    assert_eq!(actor_status("health_steady"), "registered");
+
+    rust_manager_init();
+    wait_for("health_steady", "running");
+    wait_for("health_broken", "panicked");
+    assert_eq!(actor_status("health_steady"), "running");
+
+    rust_manager_end();
+    wait_for("health_steady", "stopped");
+    wait_for("health_broken", "stopped");
+    assert!(shutdown_all());
+}
@@ -0,0 +1,31 @@
+//! Strings from C compare and hash by their text, not the bytes after it
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+use actors_interop::interop_string::InteropString;
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut h = DefaultHasher::new();
+    value.hash(&mut h);
+    h.finish()
+}
+
+#[test]
+fn bytes_after_the_nul_are_ignored() {
+    // What C leaves behind after strcpy into a reused buffer
+    let reused = InteropString::<8>::from_bytes(*b"AAPL\0SF\0");
+    let fresh = InteropString::<8>::from("AAPL");
+    assert_eq!(reused, fresh);
+    assert_eq!(hash_of(&reused), hash_of(&fresh));
+    assert_eq!(HashSet::from([reused, fresh]).len(), 1);
+
+    assert_ne!(reused, InteropString::<8>::from("AAP"));
+    // No NUL at all: the whole field is the text
+    assert_eq!(InteropString::<4>::from_bytes(*b"MSFT"), InteropString::<4>::from_bytes(*b"MSFT"));
+}
@@ -0,0 +1,52 @@
+//! InteropSubscriber hands each message type to its handler
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors_interop::interop_errors::InteropErrorCode;
+use actors_interop::interop_messages::{Ping, Pong};
+use actors_interop::interop_subscriber::{
+    interop_subscriber_new, interop_subscriber_on, interop_subscriber_register, InteropSubscriber, SubscriberCallback,
+};
+use actors_interop::rust_manager_ffi::{create_rust_manager, get_actor_ref, rust_manager_end, rust_manager_init};
+use actors_interop::teardown::shutdown_all;
+
+fn c(s: &str) -> CString {
+    CString::new(s).unwrap()
+}
+
+extern "C" fn ignore(_: *mut c_void, _: i32, _: *const c_void) {}
+
+#[test]
+fn handlers_get_their_messages() {
+    create_rust_manager();
+    let (tx, rx) = mpsc::channel();
+    let sub = InteropSubscriber::new("test_publisher").on::<Pong>(move |m| tx.send(m.count).unwrap());
+    assert!(sub.register("test_subscriber"));
+
+    // Built from C++: unknown IDs and names are refused
+    assert_eq!(interop_subscriber_new(c("test_from_c").as_ptr(), c("test_publisher").as_ptr()), 0);
+    let on = |name: &str, id: i32, cb: Option<SubscriberCallback>| {
+        interop_subscriber_on(c(name).as_ptr(), id, cb, std::ptr::null_mut())
+    };
+    assert_eq!(on("test_from_c", -1, Some(ignore)), InteropErrorCode::UnknownMessage as i32);
+    assert_eq!(on("test_from_c", 1001, None), InteropErrorCode::InvalidArgument as i32);
+    assert_eq!(on("nobody", 1001, Some(ignore)), InteropErrorCode::NotFound as i32);
+    assert!(interop_subscriber_register(c("nobody").as_ptr()).is_null());
+    assert!(!interop_subscriber_register(c("test_from_c").as_ptr()).is_null());
+
+    rust_manager_init();
+    let me = get_actor_ref("test_subscriber", "").unwrap();
+    me.send(Box::new(Ping { count: 1 }), None);  // no handler: ignored
+    me.send(Box::new(Pong { count: 2 }), None);
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(2));
+
+    rust_manager_end();
+    shutdown_all();
+}
@@ -0,0 +1,70 @@
+//! A journal left by a crashed run is handled again after Start
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors_interop::codec::{codec, CodecKind};
+use actors_interop::interop_messages::Ping;
+use actors_interop::interop_subscriber::InteropSubscriber;
+use actors_interop::journal::{self, FsyncPolicy};
+use actors_interop::rust_manager_ffi::{create_rust_manager, rust_manager_end, rust_manager_init};
+use actors_interop::teardown::shutdown_all;
+
+// CRC-32 (IEEE), bit by bit
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut c = !0u32;
+    for &b in bytes {
+        c ^= b as u32;
+        for _ in 0..8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+        }
+    }
+    !c
+}
+
+fn record(kind: u8, seq: u64, frame: &[u8]) -> Vec<u8> {
+    let mut body = vec![kind];
+    body.extend_from_slice(&seq.to_le_bytes());
+    body.extend_from_slice(frame);
+    let mut out = (body.len() as u32).to_le_bytes().to_vec();
+    out.extend_from_slice(&crc32(&body).to_le_bytes());
+    out.extend_from_slice(&body);
+    out
+}
+
+#[test]
+fn unhandled_entries_are_handled_after_start() {
+    let dir = std::env::temp_dir().join(format!("interop-journal-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("journaled.journal");
+
+    // Ping 1 was handled, Ping 2 was not, and the crash tore a third record
+    let ping = |count| codec(CodecKind::Portable).encode(&Ping { count }).unwrap();
+    let mut file = record(1, 0, &ping(1));
+    file.extend(record(1, 1, &ping(2)));
+    file.extend(record(2, 0, &[]));
+    let good_len = file.len() as u64;
+    file.extend(&record(1, 2, &ping(3))[..10]);
+    std::fs::write(&path, file).unwrap();
+
+    assert_eq!(journal::enable("journaled", &dir, FsyncPolicy::Always).unwrap(), 1);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), good_len);
+    assert_eq!(journal::pending("journaled"), Some(1));
+
+    create_rust_manager();
+    let (tx, rx) = mpsc::channel();
+    assert!(InteropSubscriber::new("nobody").on::<Ping>(move |m| tx.send(m.count).unwrap()).register("journaled"));
+    rust_manager_init();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(2));
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+    rust_manager_end();
+    assert_eq!(journal::pending("journaled"), Some(0));
+    journal::disable("journaled");
+    shutdown_all();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
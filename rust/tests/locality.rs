@@ -0,0 +1,75 @@
+//! Sends through C++ refs to a name a Rust actor has taken are admitted
+//! like any bridge send before they are delivered in process
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, ThreadConfig};
+use actors_interop::cached_send::CachedSender;
+use actors_interop::clock;
+use actors_interop::interop_messages::{Ping, MSG_PING};
+use actors_interop::locality;
+use actors_interop::policy::{self, Rule, Verdict};
+use actors_interop::rate_limit::{self, OverLimit, RateLimit, RATE_LIMITED};
+use actors_interop::rust_manager_ffi::{create_rust_manager, register_with, rust_manager_end, rust_manager_init};
+use actors_interop::send_error::SendError;
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+struct Recorder {
+    seen: mpsc::Sender<i32>,
+}
+
+impl Recorder {
+    fn on_ping(&mut self, msg: &Ping, _ctx: &mut ActorContext) {
+        self.seen.send(msg.count).unwrap();
+    }
+}
+
+handle_messages!(Recorder, Ping => on_ping);
+
+fn ping(count: i32) -> Ping {
+    Ping { count }
+}
+
+#[test]
+fn refused_sends_to_a_local_target_are_not_delivered() {
+    create_rust_manager();
+    let (tx, rx) = mpsc::channel();
+    assert!(register_with("loc_oms", move |_| Box::new(Recorder { seen: tx.clone() }), ThreadConfig::default()));
+    rust_manager_init();
+    // Stands still, so no rate limit token comes back unless advanced
+    let sim = clock::simulate(Timestamp::from_secs(1_704_187_800), 0.0);
+
+    // CachedSender goes through cpp_send_fn, as an ActorRef::Cpp does
+    let mut strategy = CachedSender::new("loc_strategy");
+    let mut rogue = CachedSender::new("loc_rogue");
+    let rule = Rule {
+        sender: Some("loc_rogue".into()),
+        target: Some("loc_oms".into()),
+        msg_id: Some(MSG_PING),
+        verdict: Verdict::Deny,
+    };
+    assert!(policy::add_rule(rule));
+    assert!(rate_limit::set_actor_rate_limit("loc_strategy", RateLimit::new(1.0, 2, OverLimit::Error)));
+
+    assert_eq!(rogue.send_pooled("loc_oms", &ping(99)), Err(SendError::TransportError(policy::POLICY_DENIED)));
+    assert_eq!(strategy.send_pooled("loc_oms", &ping(1)), Ok(()));
+    assert_eq!(strategy.send_pooled("loc_oms", &ping(2)), Ok(()));
+    assert_eq!(strategy.send_pooled("loc_oms", &ping(3)), Err(SendError::Backpressure(RATE_LIMITED)));
+    sim.advance(Duration::from_secs(1));
+    assert_eq!(strategy.send_pooled("loc_oms", &ping(4)), Ok(()));
+
+    let seen: Vec<i32> = (0..3).map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap()).collect();
+    assert_eq!(seen, vec![1, 2, 4]);
+    assert_eq!(locality::local_sends(), 3);
+
+    rate_limit::clear_rate_limit("loc_strategy", None);
+    clock::use_system_clock();
+    rust_manager_end();
+    assert!(shutdown_all());
+}
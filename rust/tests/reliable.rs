@@ -0,0 +1,71 @@
+//! A reliable Rust subscriber gets every update, in order, through a full
+//! buffer
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use actors::{handle_messages, ActorContext, ThreadConfig};
+use actors_interop::broker;
+use actors_interop::interop_messages::{MarketUpdate, Subscribe};
+use actors_interop::qos::{sub_capacity, QOS_RELIABLE};
+use actors_interop::reliable;
+use actors_interop::rust_manager_ffi::{create_rust_manager, register_with, rust_manager_end, rust_manager_init};
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+// Takes subscriptions; the broker does the rest
+struct Publisher;
+
+impl Publisher {
+    fn on_subscribe(&mut self, _msg: &Subscribe, _ctx: &mut ActorContext) {}
+}
+
+handle_messages!(Publisher, Subscribe => on_subscribe);
+
+// Reports the seq of each update it handles
+struct Subscriber {
+    seen: mpsc::Sender<i64>,
+}
+
+impl Subscriber {
+    fn on_update(&mut self, msg: &MarketUpdate, _ctx: &mut ActorContext) {
+        self.seen.send(msg.seq).unwrap();
+    }
+}
+
+handle_messages!(Subscriber, MarketUpdate => on_update);
+
+fn update(seq: i64) -> MarketUpdate {
+    MarketUpdate { symbol: "AAPL".into(), price: 100.0, timestamp: Timestamp::default(), volume: 1, seq }
+}
+
+#[test]
+fn rust_subscriber_acks_by_handling() {
+    create_rust_manager();
+    let (tx, rx) = mpsc::channel();
+    assert!(register_with("rel_pub", |_| Box::new(Publisher), ThreadConfig::default()));
+    assert!(register_with("rel_sub", move |_| Box::new(Subscriber { seen: tx.clone() }), ThreadConfig::default()));
+    rust_manager_init();
+    assert!(broker::subscribe("rel_sub", "rel_pub", "AAPL", QOS_RELIABLE | sub_capacity(4)));
+
+    // Far more than the buffer holds: the rest waits instead of dropping
+    for seq in 1..=50 {
+        broker::publish("rel_pub", "AAPL", &update(seq)).unwrap();
+    }
+    let seen: Vec<i64> = (0..50).map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap()).collect();
+    assert_eq!(seen, (1..=50).collect::<Vec<i64>>());
+
+    let begin = Instant::now();
+    while reliable::unacked("rel_pub", "AAPL", "rel_sub") > 0 {
+        assert!(begin.elapsed() < Duration::from_secs(5), "updates left unacknowledged");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(reliable::backlog("rel_pub", "AAPL", "rel_sub"), 0);
+
+    rust_manager_end();
+    assert!(shutdown_all());
+}
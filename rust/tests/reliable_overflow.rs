@@ -0,0 +1,62 @@
+//! A reliable subscriber that stops acknowledging fills its buffer and
+//! backlog, after which updates are refused and the publisher is told
+//!
+//! Needs `--features sim,standalone` since no C++ side is linked.
+
+#![cfg(all(feature = "sim", feature = "standalone"))]
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, ThreadConfig};
+use actors_interop::broker;
+use actors_interop::interop_error::ERR_BACKLOG_FULL;
+use actors_interop::interop_messages::{InteropError, MarketUpdate, MSG_MARKETUPDATE};
+use actors_interop::qos::{sub_capacity, QOS_RELIABLE};
+use actors_interop::reliable;
+use actors_interop::rust_manager_ffi::{create_rust_manager, register_with, rust_manager_end, rust_manager_init};
+use actors_interop::sim;
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+// Reports the InteropErrors it is sent
+struct Publisher {
+    told: mpsc::Sender<(i32, i32)>,
+}
+
+impl Publisher {
+    fn on_error(&mut self, err: &InteropError, _ctx: &mut ActorContext) {
+        self.told.send((err.code, err.in_reply_to)).unwrap();
+    }
+}
+
+handle_messages!(Publisher, InteropError => on_error);
+
+fn update(seq: i64) -> MarketUpdate {
+    MarketUpdate { symbol: "AAPL".into(), price: 100.0, timestamp: Timestamp::default(), volume: 1, seq }
+}
+
+#[test]
+fn full_backlog_refuses_updates_and_tells_the_publisher() {
+    create_rust_manager();
+    sim::start(3, 0);
+    // Takes updates and never acknowledges them
+    sim::add_cpp_actor("cpp_slow_sub", |_, _| {});
+    let (tx, rx) = mpsc::channel();
+    assert!(register_with("ovf_pub", move |_| Box::new(Publisher { told: tx.clone() }), ThreadConfig::default()));
+    rust_manager_init();
+    broker::add("ovf_pub", "AAPL", "cpp_slow_sub", QOS_RELIABLE | sub_capacity(2));
+
+    // 2 sent and kept, 2 waiting, then refused
+    let sent: Vec<usize> = (1..=6).map(|seq| broker::publish("ovf_pub", "AAPL", &update(seq)).unwrap()).collect();
+    assert_eq!(sent, vec![1, 1, 0, 0, 0, 0]);
+    assert_eq!(reliable::unacked("ovf_pub", "AAPL", "cpp_slow_sub"), 2);
+    assert_eq!(reliable::backlog("ovf_pub", "AAPL", "cpp_slow_sub"), 2);
+    assert_eq!(reliable::refused("ovf_pub", "AAPL", "cpp_slow_sub"), 2);
+
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), (ERR_BACKLOG_FULL, MSG_MARKETUPDATE));
+
+    rust_manager_end();
+    sim::stop();
+    assert!(shutdown_all());
+}
@@ -0,0 +1,74 @@
+//! A send to a reliable C++ subscriber that failed is made again once it
+//! can succeed, and nothing is delivered twice
+//!
+//! Needs `--features sim,standalone` since no C++ side is linked.
+
+#![cfg(all(feature = "sim", feature = "standalone"))]
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actors::{handle_messages, ActorContext, ThreadConfig};
+use actors_interop::broker;
+use actors_interop::interop_messages::{MarketUpdate, TopicAck};
+use actors_interop::qos::{sub_capacity, QOS_RELIABLE};
+use actors_interop::reliable;
+use actors_interop::rust_manager_ffi::{create_rust_manager, register_with, rust_manager_end, rust_manager_init};
+use actors_interop::sim::{self, Failure};
+use actors_interop::teardown::shutdown_all;
+use actors_interop::timestamp::Timestamp;
+
+// Receives the acks the broker has already taken
+struct Publisher;
+
+impl Publisher {
+    fn on_ack(&mut self, _msg: &TopicAck, _ctx: &mut ActorContext) {}
+}
+
+handle_messages!(Publisher, TopicAck => on_ack);
+
+fn update(seq: i64) -> MarketUpdate {
+    MarketUpdate { symbol: "AAPL".into(), price: 100.0, timestamp: Timestamp::default(), volume: 1, seq }
+}
+
+#[test]
+fn failed_send_to_cpp_subscriber_is_resent() {
+    create_rust_manager();
+    sim::start(7, 0);
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    // Acks each update as a C++ subscriber would: with the count received
+    sim::add_cpp_actor("cpp_rel_sub", move |msg, out| {
+        if let Some(update) = msg.as_any().downcast_ref::<MarketUpdate>() {
+            let mut log = log.lock().unwrap();
+            log.push(update.seq);
+            let ack = TopicAck { topic: "AAPL".into(), seq: log.len() as i64 };
+            out.send("rel_cpp_pub", Box::new(ack), 1);
+        }
+    });
+    sim::fail("cpp_rel_sub", Failure::nth(2, sim::MAILBOX_FULL));
+    assert!(register_with("rel_cpp_pub", |_| Box::new(Publisher), ThreadConfig::default()));
+    rust_manager_init();
+    // As the bridge records a Subscribe from C++
+    broker::add("rel_cpp_pub", "AAPL", "cpp_rel_sub", QOS_RELIABLE | sub_capacity(1));
+
+    for seq in 1..=3 {
+        broker::publish("rel_cpp_pub", "AAPL", &update(seq)).unwrap();
+    }
+    let begin = Instant::now();
+    while received.lock().unwrap().len() < 3 {
+        assert!(begin.elapsed() < Duration::from_secs(5), "got only {:?}", received.lock().unwrap());
+        sim::run_until_idle(100);
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    sim::run_until_idle(100);
+
+    // The second send failed and was made again, nothing twice
+    assert_eq!(*received.lock().unwrap(), vec![1, 2, 3]);
+    assert_eq!(sim::sends_to("cpp_rel_sub"), 4);
+    assert_eq!(reliable::unacked("rel_cpp_pub", "AAPL", "cpp_rel_sub"), 0);
+
+    rust_manager_end();
+    sim::stop();
+    assert!(shutdown_all());
+}
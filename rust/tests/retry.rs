@@ -0,0 +1,80 @@
+//! Transient send failures are retried with growing backoff, others are not
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actors_interop::interop_errors::InteropErrorCode;
+use actors_interop::retry::{self, FailureCallback, RetryPolicy};
+
+const NOT_FOUND: i32 = InteropErrorCode::NotFound as i32;
+const QUEUE_FULL: i32 = InteropErrorCode::QueueFull as i32;
+const UNKNOWN_MESSAGE: i32 = InteropErrorCode::UnknownMessage as i32;
+
+/// A send returning `codes` in turn, then 0; counts its attempts
+fn sends(codes: &[i32]) -> (impl FnMut() -> i32 + '_, Arc<Mutex<u32>>) {
+    let attempts = Arc::new(Mutex::new(0));
+    let counted = attempts.clone();
+    let send = move || {
+        let mut n = counted.lock().unwrap();
+        *n += 1;
+        codes.get(*n as usize - 1).copied().unwrap_or(0)
+    };
+    (send, attempts)
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let policy = RetryPolicy::new(6, Duration::from_millis(10)).with_max_backoff(Duration::from_millis(35));
+    let backoffs: Vec<u64> = (1..=5).map(|a| policy.backoff(a).as_millis() as u64).collect();
+    assert_eq!(backoffs, vec![0, 10, 20, 35, 35]);
+    assert_eq!(RetryPolicy::new(0, Duration::ZERO).max_attempts, 1);
+}
+
+#[test]
+fn transient_failures_are_retried_until_success_or_the_last_attempt() {
+    let policy = RetryPolicy::new(3, Duration::from_millis(1));
+
+    let (send, attempts) = sends(&[NOT_FOUND, NOT_FOUND]);
+    assert_eq!(retry::run(&policy, send), 0);
+    assert_eq!(*attempts.lock().unwrap(), 3);
+
+    // A full C++ queue drains
+    let (send, attempts) = sends(&[QUEUE_FULL, NOT_FOUND]);
+    assert_eq!(retry::run(&policy, send), 0);
+    assert_eq!(*attempts.lock().unwrap(), 3);
+
+    let (send, attempts) = sends(&[NOT_FOUND; 5]);
+    assert_eq!(retry::run(&policy, send), NOT_FOUND);
+    assert_eq!(*attempts.lock().unwrap(), 3);
+
+    // The message is wrong: another attempt cannot help
+    let (send, attempts) = sends(&[UNKNOWN_MESSAGE]);
+    assert_eq!(retry::run(&policy, send), UNKNOWN_MESSAGE);
+    assert_eq!(*attempts.lock().unwrap(), 1);
+
+    let (send, attempts) = sends(&[NOT_FOUND]);
+    assert_eq!(retry::run(&RetryPolicy::none(), send), NOT_FOUND);
+    assert_eq!(*attempts.lock().unwrap(), 1);
+}
+
+#[test]
+fn failure_callback_hears_only_of_final_failures() {
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let seen = failures.clone();
+    let cb: FailureCallback = Arc::new(move |target: &str, msg_id, rc| {
+        seen.lock().unwrap().push((target.to_string(), msg_id, rc));
+    });
+    let policy = RetryPolicy::new(2, Duration::from_millis(1));
+
+    let (send, _) = sends(&[NOT_FOUND]);
+    assert_eq!(retry::send_with_retry(&policy, Some(&cb), "cpp_gateway", 1000, send), 0);
+    assert!(failures.lock().unwrap().is_empty());
+
+    let (send, _) = sends(&[NOT_FOUND, NOT_FOUND]);
+    assert_eq!(retry::send_with_retry(&policy, Some(&cb), "cpp_gateway", 1000, send), NOT_FOUND);
+    assert_eq!(*failures.lock().unwrap(), vec![("cpp_gateway".to_string(), 1000, NOT_FOUND)]);
+}
@@ -0,0 +1,7 @@
+//! Round-trip tests for every interop message
+//!
+//! The tests are generated by codegen/generate.py; run `make generate`
+//! (or `make test`) after changing messages/interop_messages.h.
+
+#[path = "../../generated/rust/roundtrip_tests.rs"]
+mod roundtrip_tests;
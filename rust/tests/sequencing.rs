@@ -0,0 +1,68 @@
+//! Bridge sequence numbers count per subscriber, and skipped numbers are
+//! reported as gaps
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use actors_interop::interop_messages::GapDetected;
+use actors_interop::sequencing::{self, interop_sequencing_enable, GapDetector};
+
+fn gap(expected_seq: i64, received_seq: i64) -> Option<GapDetected> {
+    Some(GapDetected { publisher: "cpp_feed".into(), topic: "AAPL".into(), expected_seq, received_seq })
+}
+
+/// Stamp an unnumbered message for `subscriber`
+fn next(subscriber: &str, topic: &str) -> i64 {
+    let mut seq = 0;
+    sequencing::stamp("cpp_feed", subscriber, topic, &mut seq);
+    seq
+}
+
+#[test]
+fn stamps_count_per_stream_and_only_when_enabled() {
+    assert_eq!(next("sq_a", "AAPL"), 0);
+
+    interop_sequencing_enable(1);
+    assert_eq!((1..=3).map(|_| next("sq_a", "AAPL")).collect::<Vec<_>>(), vec![1, 2, 3]);
+    // Fan-out and other topics number from 1 on their own
+    assert_eq!(next("sq_b", "AAPL"), 1);
+    assert_eq!(next("sq_a", "MSFT"), 1);
+    assert_eq!(next("sq_a", "AAPL"), 4);
+
+    // A publisher numbering its own stream is left alone
+    let mut seq = 42;
+    sequencing::stamp("cpp_feed", "sq_a", "AAPL", &mut seq);
+    assert_eq!(seq, 42);
+    assert_eq!(next("sq_a", "AAPL"), 5);
+
+    interop_sequencing_enable(0);
+    assert_eq!(next("sq_a", "AAPL"), 0);
+}
+
+#[test]
+fn skipped_numbers_are_reported_once() {
+    let mut detector = GapDetector::new();
+    assert_eq!(detector.check("cpp_feed", "AAPL", 1), None);
+    assert_eq!(detector.check("cpp_feed", "AAPL", 2), None);
+    assert_eq!(detector.check("cpp_feed", "AAPL", 5), gap(3, 5));
+    assert_eq!(detector.check("cpp_feed", "AAPL", 6), None);
+
+    // Late, duplicate and unsequenced messages are not gaps
+    assert_eq!(detector.check("cpp_feed", "AAPL", 4), None);
+    assert_eq!(detector.check("cpp_feed", "AAPL", 6), None);
+    assert_eq!(detector.check("cpp_feed", "AAPL", 0), None);
+    assert_eq!(detector.check("cpp_feed", "AAPL", 7), None);
+
+    // Each stream is tracked on its own
+    assert_eq!(detector.check("cpp_feed", "MSFT", 1), None);
+    assert_eq!(detector.check("cpp_feed", "AAPL", 9), gap(8, 9));
+
+    // A restarted publisher numbers from 1 again
+    assert_eq!(detector.check("cpp_feed", "AAPL", 1), None);
+    assert_eq!(detector.check("cpp_feed", "AAPL", 2), None);
+
+    // After a reset (e.g. a snapshot) the stream is expected from 1 again
+    detector.reset("cpp_feed", "AAPL");
+    assert_eq!(detector.check("cpp_feed", "AAPL", 3), gap(1, 3));
+}
@@ -0,0 +1,144 @@
+//! Subscription filters parse operators leftmost and longest first,
+//! compare numbers and text by the value given, drop messages without the
+//! field and refuse specs that do not parse
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::ffi::{c_void, CString};
+
+use actors_interop::interop_errors::InteropErrorCode;
+use actors_interop::interop_messages::{MarketUpdate, MSG_MARKETUPDATE};
+use actors_interop::sub_filter::{
+    self, interop_set_subscription_filter, interop_subscription_filter_check, FilterError,
+};
+use actors_interop::timestamp::Timestamp;
+
+const SUBSCRIBER: &str = "sf_dashboard";
+const PUBLISHER: &str = "sf_feed";
+
+fn update(symbol: &str, price: f64, volume: i32) -> MarketUpdate {
+    MarketUpdate { symbol: symbol.into(), price, timestamp: Timestamp::default(), volume, seq: 0 }
+}
+
+fn c(s: &str) -> CString {
+    CString::new(s).unwrap()
+}
+
+/// Whether `msg` would be sent to the subscriber of `topic`, as a C++
+/// publisher asks
+fn passes(topic: &str, msg: &MarketUpdate) -> bool {
+    let (s, p, t) = (c(SUBSCRIBER), c(PUBLISHER), c(topic));
+    let c_msg = msg.to_c_struct();
+    let data = &c_msg as *const _ as *const c_void;
+    match interop_subscription_filter_check(s.as_ptr(), p.as_ptr(), t.as_ptr(), MSG_MARKETUPDATE, data) {
+        1 => true,
+        0 => false,
+        rc => panic!("filter check failed: {}", rc),
+    }
+}
+
+/// Messages of `msgs` the filter `spec` lets through, by volume
+fn through(topic: &str, spec: &str, msgs: &[MarketUpdate]) -> Vec<i32> {
+    sub_filter::set(SUBSCRIBER, PUBLISHER, topic, spec).unwrap();
+    msgs.iter().filter(|m| passes(topic, m)).map(|m| m.volume).collect()
+}
+
+fn parse_error(spec: &str) -> Option<&'static str> {
+    match sub_filter::set(SUBSCRIBER, PUBLISHER, "sf_bad", spec) {
+        Err(FilterError::Parse { reason, .. }) => Some(reason),
+        _ => None,
+    }
+}
+
+#[test]
+fn operators_bind_leftmost_and_longest_first() {
+    let prices = [update("AAPL", 149.0, 1), update("AAPL", 150.0, 2), update("AAPL", 151.0, 3)];
+
+    // `>=` is one operator, not `>` against "=150"
+    assert_eq!(through("sf_ops", "price>=150", &prices), vec![2, 3]);
+    assert_eq!(through("sf_ops", "price > 150", &prices), vec![3]);
+    assert_eq!(through("sf_ops", "price<=150", &prices), vec![1, 2]);
+    assert_eq!(through("sf_ops", "price < 150", &prices), vec![1]);
+    assert_eq!(through("sf_ops", "price != 150", &prices), vec![1, 3]);
+
+    // The leftmost operator splits, so the value may hold another
+    let odd = [update("A<B", 1.0, 1), update("AAPL", 1.0, 2)];
+    assert_eq!(through("sf_ops", "symbol == A<B", &odd), vec![1]);
+
+    // `&&` needs every condition, each `|` stage sees what the last let through
+    let mixed = [update("AAPL", 151.0, 10), update("AAPL", 149.0, 20), update("MSFT", 151.0, 30)];
+    assert_eq!(through("sf_ops", "symbol == AAPL && price > 150", &mixed), vec![10]);
+    assert_eq!(through("sf_ops", "symbol == AAPL | price > 150", &mixed), vec![10]);
+    assert_eq!(through("sf_ops", "price > 150 | volume >= 30", &mixed), vec![30]);
+
+    assert!(sub_filter::clear(SUBSCRIBER, PUBLISHER, "sf_ops"));
+}
+
+#[test]
+fn numbers_and_text_compare_by_the_value_given() {
+    let volumes = [update("AAPL", 1.0, 9), update("AAPL", 1.0, 100)];
+
+    // A number compares numerically, quoted text by the field's text
+    assert_eq!(through("sf_cmp", "volume < 50", &volumes), vec![9]);
+    assert_eq!(through("sf_cmp", "volume < \"50\"", &volumes), vec![100]);
+    assert_eq!(through("sf_cmp", "volume == '100'", &volumes), vec![100]);
+    assert_eq!(through("sf_cmp", "volume == 100.0", &volumes), vec![100]);
+
+    // Unquoted text compares as text, and case matters
+    let symbols = [update("AAPL", 1.0, 1), update("MSFT", 1.0, 2), update("aapl", 1.0, 3)];
+    assert_eq!(through("sf_cmp", "symbol == AAPL", &symbols), vec![1]);
+    assert_eq!(through("sf_cmp", "symbol < MSFT", &symbols), vec![1]);
+    assert_eq!(through("sf_cmp", "symbol == \"aapl\"", &symbols), vec![3]);
+
+    // price has no text form, so text never matches it
+    assert_eq!(through("sf_cmp", "price == \"1\"", &symbols), Vec::<i32>::new());
+
+    assert!(sub_filter::clear(SUBSCRIBER, PUBLISHER, "sf_cmp"));
+}
+
+#[test]
+fn messages_without_the_field_are_dropped_and_counted() {
+    let msgs = [update("AAPL", 1.0, 1), update("AAPL", 1.0, 2)];
+
+    assert_eq!(through("sf_unknown", "bid > 0", &msgs), Vec::<i32>::new());
+    assert_eq!(through("sf_unknown", "bid != 0", &msgs), Vec::<i32>::new());
+    assert_eq!(through("sf_unknown", "symbol == AAPL && bid != 0", &msgs), Vec::<i32>::new());
+    // Setting a filter again starts its count over
+    assert_eq!(sub_filter::dropped(SUBSCRIBER, PUBLISHER, "sf_unknown"), 2);
+
+    assert!(sub_filter::clear(SUBSCRIBER, PUBLISHER, "sf_unknown"));
+    assert_eq!(sub_filter::dropped(SUBSCRIBER, PUBLISHER, "sf_unknown"), 0);
+}
+
+#[test]
+fn malformed_specs_are_refused_and_keep_the_filter_set() {
+    sub_filter::set(SUBSCRIBER, PUBLISHER, "sf_bad", "volume > 10").unwrap();
+
+    assert_eq!(parse_error("volume"), Some("expected field op value"));
+    assert_eq!(parse_error("volume || price"), Some("expected field op value"));
+    assert_eq!(parse_error("> 10"), Some("expected a field name"));
+    assert_eq!(parse_error("vol ume > 10"), Some("expected a field name"));
+    assert_eq!(parse_error("volume >"), Some("expected a value"));
+    assert_eq!(parse_error("volume > 10 &&"), Some("expected field op value"));
+    assert_eq!(parse_error("volume > 10 |"), Some("empty stage"));
+    assert_eq!(parse_error("| volume > 10"), Some("empty stage"));
+    assert_eq!(parse_error("every 0ms"), Some("expected an interval"));
+    assert_eq!(parse_error("every soon"), Some("expected an interval"));
+    assert_eq!(parse_error("every 5ms per symbol"), Some("expected every <interval> [by <field>]"));
+    assert_eq!(
+        sub_filter::set(SUBSCRIBER, PUBLISHER, "sf_bad", "volume > 10 | @sf_missing"),
+        Err(FilterError::UnknownTransform("sf_missing".into()))
+    );
+    assert_eq!(sub_filter::filter_of(SUBSCRIBER, PUBLISHER, "sf_bad").as_deref(), Some("volume > 10"));
+
+    // From C++: InvalidArgument for a spec that does not parse, NotFound
+    // for an unknown transform, and a blank one clears
+    let (s, p, t) = (c(SUBSCRIBER), c(PUBLISHER), c("sf_bad"));
+    let from_cpp = |spec: &str| interop_set_subscription_filter(s.as_ptr(), p.as_ptr(), t.as_ptr(), c(spec).as_ptr());
+    assert_eq!(from_cpp("volume >"), InteropErrorCode::InvalidArgument as i32);
+    assert_eq!(from_cpp("@sf_missing"), InteropErrorCode::NotFound as i32);
+    assert_eq!(from_cpp("  "), 0);
+    assert_eq!(sub_filter::filter_of(SUBSCRIBER, PUBLISHER, "sf_bad"), None);
+}
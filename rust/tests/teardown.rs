@@ -0,0 +1,28 @@
+//! interop_shutdown_all() leaves nothing allocated and can start over
+//!
+//! Run under the leak checkers with `make lsan` or `make miri`; needs
+//! `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use actors_interop::rust_manager_ffi::{
+    create_rust_manager, get_rust_manager, register_rust_pong_actor, rust_manager_end, rust_manager_init,
+};
+use actors_interop::teardown::{interop_shutdown_all, shutdown_all};
+
+#[test]
+fn shutdown_all_frees_the_manager_and_starts_over() {
+    create_rust_manager();
+    assert!(!register_rust_pong_actor().is_null());
+    rust_manager_init();
+    rust_manager_end();
+    assert!(shutdown_all());
+    assert!(get_rust_manager().is_null());
+
+    // Nothing left to free
+    assert_eq!(interop_shutdown_all(), 1);
+
+    create_rust_manager();
+    assert!(!register_rust_pong_actor().is_null());
+    assert_eq!(interop_shutdown_all(), 0);
+}
@@ -0,0 +1,82 @@
+//! Tenants are kept apart on Rust -> Rust sends too, and unlabelled
+//! senders are shared or isolated as configured
+//!
+//! Needs `--features standalone` since no C++ side is linked.
+
+#![cfg(feature = "standalone")]
+
+use std::sync::mpsc;
+use std::time::Duration;
+
+use actors::{handle_messages, ActorContext, ActorRef, ThreadConfig};
+use actors_interop::interop_messages::{Ping, MSG_PING, MSG_PONG};
+use actors_interop::policy::POLICY_DENIED;
+use actors_interop::rust_manager_ffi::{
+    create_rust_manager, get_actor_ref, register_with, rust_manager_end, rust_manager_init,
+};
+use actors_interop::send_error::{SendError, TrySend};
+use actors_interop::teardown::shutdown_all;
+use actors_interop::tenant::{self, TenantRule, Unlabelled};
+
+struct Recorder {
+    seen: mpsc::Sender<i32>,
+}
+
+impl Recorder {
+    fn on_ping(&mut self, msg: &Ping, _ctx: &mut ActorContext) {
+        self.seen.send(msg.count).unwrap();
+    }
+}
+
+handle_messages!(Recorder, Ping => on_ping);
+
+fn rule(from: Option<&str>, to: Option<&str>, msg_id: Option<i32>) -> TenantRule {
+    TenantRule { from: from.map(str::to_string), to: to.map(str::to_string), msg_id }
+}
+
+fn ping(actor: &ActorRef, count: i32) -> Result<(), SendError> {
+    actor.try_send(Box::new(Ping { count }), None)
+}
+
+#[test]
+fn tenants_are_kept_apart_between_rust_actors() {
+    create_rust_manager();
+    let (tx, rx) = mpsc::channel();
+    assert!(register_with("tn_fx", move |_| Box::new(Recorder { seen: tx.clone() }), ThreadConfig::default()));
+    rust_manager_init();
+    assert!(tenant::set("tn_rates", Some("rates")));
+    assert!(tenant::set("tn_fx", Some("fx")));
+
+    // Shared by default: unlabelled and anonymous senders reach any tenant
+    assert_eq!(tenant::unlabelled(), Unlabelled::Shared);
+    assert!(tenant::permits("tn_host", "tn_fx", MSG_PING));
+    assert!(tenant::permits("", "tn_fx", MSG_PING));
+    assert!(!matches!(get_actor_ref("tn_fx", "tn_host").unwrap(), ActorRef::Cpp(_)));
+
+    // Another tenant needs a rule, per message
+    let rates_to_fx = get_actor_ref("tn_fx", "tn_rates").unwrap();
+    assert_eq!(ping(&rates_to_fx, 1), Err(SendError::TransportError(POLICY_DENIED)));
+    assert!(tenant::allow(rule(Some("rates"), Some("fx"), Some(MSG_PING))));
+    assert_eq!(ping(&rates_to_fx, 2), Ok(()));
+    assert!(!tenant::permits("tn_rates", "tn_fx", MSG_PONG));
+
+    // Isolated: unlabelled is tenant "", in both directions
+    assert!(tenant::set_unlabelled(Unlabelled::Isolated));
+    assert!(!tenant::permits("", "tn_fx", MSG_PING));
+    assert!(!tenant::permits("tn_fx", "tn_host", MSG_PING));
+    assert!(tenant::permits("tn_host", "tn_other_host", MSG_PING));
+    let host_to_fx = get_actor_ref("tn_fx", "tn_host").unwrap();
+    assert_eq!(ping(&host_to_fx, 3), Err(SendError::TransportError(POLICY_DENIED)));
+    assert!(tenant::allow(rule(Some(""), Some("fx"), None)));
+    assert_eq!(ping(&host_to_fx, 4), Ok(()));
+    assert!(tenant::permits("", "tn_fx", MSG_PONG));
+    // The rule names the unlabelled only
+    assert!(!tenant::permits("tn_rates", "tn_fx", MSG_PONG));
+
+    let seen: Vec<i32> = (0..2).map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap()).collect();
+    assert_eq!(seen, vec![2, 4]);
+
+    assert!(tenant::set_unlabelled(Unlabelled::Shared));
+    rust_manager_end();
+    assert!(shutdown_all());
+}
@@ -0,0 +1,22 @@
+@echo off
+rem Windows smoke test: tests\windows\smoke.cpp against actors_interop.dll
+rem
+rem Run from the repository root in an "x64 Native Tools Command Prompt"
+rem (MSVC cl and link on PATH), with Python 3 and the Rust MSVC toolchain.
+
+python codegen\generate.py messages\interop_messages.h generated || exit /b 1
+
+pushd rust
+cargo build --release || (popd & exit /b 1)
+popd
+
+if not exist lib mkdir lib
+cl /nologo /std:c++17 /EHsc /W4 /DINTEROP_DLL ^
+    /Icpp\include\interop /Imessages ^
+    tests\windows\smoke.cpp ^
+    /Fo:lib\ /Fe:lib\windows_smoke.exe ^
+    /link rust\target\release\actors_interop.dll.lib || exit /b 1
+
+rem The DLL must sit next to the executable (or on PATH)
+copy /y rust\target\release\actors_interop.dll lib\ >nul || exit /b 1
+lib\windows_smoke.exe
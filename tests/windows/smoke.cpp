@@ -0,0 +1,99 @@
+/*
+ * Windows smoke test - a C++ host driving the Rust library as a DLL
+ *
+ * No actors-cpp here: the host implements the cpp_actor_* bridge itself
+ * with one fake C++ actor, "cpp_ping", and hands it to the DLL the way
+ * the generated cpp_actor_init() does. It then checks both directions:
+ *
+ *   C++ -> Rust   rust_actor_send("rust_pong", "cpp_ping", Ping)
+ *   Rust -> C++   rust_pong replies with a Pong to cpp_ping
+ *
+ * Build and run with tests\windows\build.bat. The same file also builds
+ * on Linux against libactors_interop.so (link with -rdynamic).
+ */
+
+#include <atomic>
+#include <chrono>
+#include <cstring>
+#include <iostream>
+#include <thread>
+
+#include "interop_messages.h"
+#include "cpp_runtime.h"
+
+// Rust Manager FFI functions, imported from actors_interop.dll
+extern "C" {
+    INTEROP_API void create_rust_manager();
+    INTEROP_API void* register_rust_pong_actor();
+    INTEROP_API void rust_manager_init();
+    INTEROP_API void rust_manager_end();
+    INTEROP_API void rust_actor_init(const void* mgr);
+    INTEROP_API void rust_actor_shutdown();
+    INTEROP_API int32_t rust_actor_exists(const char* name);
+    INTEROP_API int32_t rust_actor_send(const char* actor_name, const char* sender_name,
+                                        int32_t msg_type, const void* msg_data);
+}
+
+static std::atomic<int32_t> g_pong_count{-1};
+
+// The fake C++ side: a single actor, cpp_ping, that records Pongs
+extern "C" {
+
+INTEROP_EXPORT int32_t cpp_actor_exists(const char* name) {
+    return name && std::strcmp(name, "cpp_ping") == 0 ? 1 : 0;
+}
+
+INTEROP_EXPORT int32_t cpp_actor_send(const char* actor_name, const char* sender_name,
+                                      int32_t msg_type, const void* msg_data) {
+    (void)sender_name;
+    if (!cpp_actor_exists(actor_name) || !msg_data) return -1;
+    if (msg_type != 1001) return -2;  // only Pong
+    g_pong_count = static_cast<const Pong*>(msg_data)->count;
+    return 0;
+}
+
+INTEROP_EXPORT int32_t cpp_actor_fast_send(const char* actor_name, const char* sender_name,
+                                           int32_t msg_type, const void* msg_data) {
+    return cpp_actor_send(actor_name, sender_name, msg_type, msg_data);
+}
+
+}  // extern "C"
+
+static int fail(const char* what) {
+    std::cout << "FAILED: " << what << std::endl;
+    return 1;
+}
+
+int main() {
+    std::cout << "=== actors-interop Windows smoke test ===" << std::endl;
+
+    // What the generated cpp_actor_init() does on Windows
+    InteropCppRuntime linked = {cpp_actor_send, cpp_actor_fast_send, cpp_actor_exists};
+    if (interop_set_linked_cpp_runtime(linked) != 0) return fail("interop_set_linked_cpp_runtime");
+
+    create_rust_manager();
+    void* rust_mgr = register_rust_pong_actor();
+    if (!rust_mgr) return fail("register_rust_pong_actor");
+    rust_actor_init(rust_mgr);
+    rust_manager_init();
+
+    if (rust_actor_exists("rust_pong") != 1) return fail("rust_actor_exists(rust_pong)");
+
+    Ping ping;
+    std::memset(&ping, 0, sizeof(ping));
+    ping.count = 7;
+    if (rust_actor_send("rust_pong", "cpp_ping", 1000, &ping) != 0) return fail("rust_actor_send(Ping)");
+
+    auto deadline = std::chrono::steady_clock::now() + std::chrono::seconds(5);
+    while (g_pong_count != 7 && std::chrono::steady_clock::now() < deadline) {
+        std::this_thread::sleep_for(std::chrono::milliseconds(10));
+    }
+
+    rust_manager_end();
+    rust_actor_shutdown();
+
+    if (g_pong_count != 7) return fail("no Pong from rust_pong");
+    std::cout << "Ping -> rust_pong -> Pong -> cpp_ping: ok" << std::endl;
+    std::cout << "=== Smoke test passed ===" << std::endl;
+    return 0;
+}